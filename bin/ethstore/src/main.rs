@@ -34,6 +34,7 @@ use docopt::Docopt;
 use ethstore::{
     accounts_dir::{KeyDirectory, RootDiskDirectory},
     ethkey::{Address, Password},
+    hd::{self, HdPath, Mnemonic},
     import_accounts, EthStore, PresaleWallet, SecretStore, SecretVaultRef, SimpleSecretStore,
     StoreAccountRef,
 };
@@ -59,6 +60,8 @@ Usage:
     ethstore change-vault-pwd <vault> <old-pwd> <new-pwd> [--dir DIR]
     ethstore move-to-vault <address> <vault> <password> [--dir DIR] [--vault VAULT] [--vault-pwd VAULTPWD]
     ethstore move-from-vault <address> <vault> <password> [--dir DIR]
+    ethstore derive <mnemonic> [--path PATH]
+    ethstore hd-import <mnemonic> <vault> <password> [--count COUNT] [--dir DIR]
     ethstore [-h | --help]
 
 Options:
@@ -73,6 +76,8 @@ Options:
     --src DIR                Specify import source. It may be either
                              parity, parity-(chain), geth, geth-test
                              or a path [default: geth].
+    --path PATH              Specify BIP-32 derivation path [default: m/44'/60'/0'/0/0].
+    --count COUNT            Specify number of BIP-44 accounts to derive [default: 1].
 
 Commands:
     insert             Save account with password.
@@ -89,6 +94,8 @@ Commands:
     change-vault-pwd   Change vault password.
     move-to-vault      Move account to vault from another vault/root directory.
     move-from-vault    Move account to root directory from given vault.
+    derive             Displays address derived from a BIP-39 mnemonic.
+    hd-import          Store BIP-39 mnemonic in a vault and derive accounts from it.
 "#;
 
 #[derive(Debug, Deserialize)]
@@ -107,6 +114,8 @@ struct Args {
     cmd_change_vault_pwd: bool,
     cmd_move_to_vault: bool,
     cmd_move_from_vault: bool,
+    cmd_derive: bool,
+    cmd_hd_import: bool,
     arg_secret: String,
     arg_password: String,
     arg_old_pwd: String,
@@ -115,10 +124,13 @@ struct Args {
     arg_message: String,
     arg_path: String,
     arg_vault: String,
+    arg_mnemonic: String,
     flag_src: String,
     flag_dir: String,
     flag_vault: String,
     flag_vault_pwd: String,
+    flag_path: String,
+    flag_count: u32,
 }
 
 enum Error {
@@ -226,6 +238,13 @@ fn load_password(path: &str) -> Result<Password, Error> {
     Ok(password.into())
 }
 
+fn load_mnemonic(path: &str) -> Result<Mnemonic, Error> {
+    let phrase = fs::read_to_string(path).map_err(|e| {
+        ethstore::Error::Custom(format!("Error reading mnemonic file '{}': {}", path, e))
+    })?;
+    Ok(Mnemonic::from_phrase(&phrase)?)
+}
+
 fn execute<S, I>(command: I) -> Result<String, Error>
 where
     I: IntoIterator<Item = S>,
@@ -357,6 +376,28 @@ where
             StoreAccountRef::vault(&args.arg_vault, address),
         )?;
         Ok("OK".to_owned())
+    } else if args.cmd_derive {
+        let mnemonic = load_mnemonic(&args.arg_mnemonic)?;
+        let path: HdPath = args.flag_path.parse()?;
+        let address = hd::derive_address(&mnemonic.to_seed(""), &path)?;
+        Ok(format!("0x{:x}", address))
+    } else if args.cmd_hd_import {
+        let mnemonic = load_mnemonic(&args.arg_mnemonic)?;
+        let password = load_password(&args.arg_password)?;
+        if store.list_vaults()?.contains(&args.arg_vault) {
+            store.open_vault(&args.arg_vault, &password)?;
+        } else {
+            store.create_vault(&args.arg_vault, &password)?;
+        }
+        store.set_vault_mnemonic(&args.arg_vault, &mnemonic)?;
+        let accounts = (0..args.flag_count)
+            .map(|index| {
+                store
+                    .insert_hd_account(&args.arg_vault, &HdPath::ethereum(index), &password)
+                    .map(|account_ref| account_ref.address)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(format_accounts(&accounts))
     } else {
         Ok(format!("{}", USAGE))
     };
@@ -0,0 +1,279 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Comparison of standardized JSON traces against a reference trace
+//! (i.e. go-ethereum `evm --json` or any other EIP-3155 trace).
+
+use display::std_json;
+use ethereum_types::U256;
+use rustc_hex::{FromHex, ToHex};
+use serde_json::{self, Value};
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+
+/// In-memory sink collecting the trace of a run.
+#[derive(Debug, Clone, Default)]
+pub struct TraceBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl TraceBuffer {
+    /// Returns the collected trace.
+    pub fn contents(&self) -> String {
+        String::from_utf8_lossy(&self.0.lock().expect("trace buffer lock poisoned")).into_owned()
+    }
+}
+
+impl std_json::Writer for TraceBuffer {
+    fn clone(&self) -> Self {
+        Clone::clone(self)
+    }
+
+    fn default() -> Self {
+        Default::default()
+    }
+}
+
+impl io::Write for TraceBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .lock()
+            .expect("trace buffer lock poisoned")
+            .extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// First difference between two traces.
+#[derive(Debug, PartialEq)]
+pub struct Divergence {
+    /// Index of the diverging step (starting at 0).
+    pub step: usize,
+    /// Name of the diverging field.
+    pub field: String,
+    /// Our value, `None` if our trace has ended.
+    pub ours: Option<String>,
+    /// Reference value, `None` if the reference trace has ended.
+    pub reference: Option<String>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let display = |v: &Option<String>| v.clone().unwrap_or_else(|| "<end of trace>".into());
+        write!(
+            f,
+            "Traces diverge at step {} ({}):\n  ours:      {}\n  reference: {}",
+            self.step,
+            self.field,
+            display(&self.ours),
+            display(&self.reference)
+        )
+    }
+}
+
+/// Compares two traces in JSON lines format, step by step.
+///
+/// Lines which are not execution steps (i.e. summaries or test markers) are skipped.
+/// Program counter, opcode, depth, gas and stack are always compared,
+/// memory only when both traces include it.
+pub fn compare(ours: &str, reference: &str) -> Result<Option<Divergence>, String> {
+    let ours = steps(ours).map_err(|e| format!("Invalid trace: {}", e))?;
+    let reference = steps(reference).map_err(|e| format!("Invalid reference trace: {}", e))?;
+
+    for (step, (a, b)) in ours.iter().zip(reference.iter()).enumerate() {
+        if let Some((field, ours, reference)) = compare_step(a, b)? {
+            return Ok(Some(Divergence {
+                step,
+                field,
+                ours: Some(ours),
+                reference: Some(reference),
+            }));
+        }
+    }
+
+    if ours.len() == reference.len() {
+        return Ok(None);
+    }
+    let step = ::std::cmp::min(ours.len(), reference.len());
+    let describe = |s: Option<&Value>| s.map(|s| s.to_string());
+    Ok(Some(Divergence {
+        step,
+        field: "length".into(),
+        ours: describe(ours.get(step)),
+        reference: describe(reference.get(step)),
+    }))
+}
+
+fn steps(trace: &str) -> Result<Vec<Value>, String> {
+    let mut steps = Vec::new();
+    for line in trace.lines().map(str::trim).filter(|l| !l.is_empty()) {
+        let value: Value = serde_json::from_str(line).map_err(|e| format!("{}: {}", line, e))?;
+        if value.get("pc").is_some() && value.get("op").is_some() {
+            steps.push(value);
+        }
+    }
+    Ok(steps)
+}
+
+fn compare_step(a: &Value, b: &Value) -> Result<Option<(String, String, String)>, String> {
+    for field in &["pc", "op", "depth", "gas"] {
+        let (x, y) = (number(&a[*field])?, number(&b[*field])?);
+        if x != y {
+            return Ok(Some((
+                field.to_string(),
+                format!("{:#x}", x),
+                format!("{:#x}", y),
+            )));
+        }
+    }
+
+    let (x, y) = (stack(&a["stack"])?, stack(&b["stack"])?);
+    if x != y {
+        let display = |s: &[U256]| {
+            let items: Vec<_> = s.iter().map(|v| format!("{:#x}", v)).collect();
+            format!("[{}]", items.join(","))
+        };
+        return Ok(Some(("stack".into(), display(&x), display(&y))));
+    }
+
+    if let (Some(x), Some(y)) = (memory(&a["memory"])?, memory(&b["memory"])?) {
+        let len = ::std::cmp::max(x.len(), y.len());
+        let pad = |mut m: Vec<u8>| {
+            m.resize(len, 0);
+            m
+        };
+        let (x, y) = (pad(x), pad(y));
+        if x != y {
+            let display = |m: &[u8]| format!("0x{}", m.to_hex());
+            return Ok(Some(("memory".into(), display(&x), display(&y))));
+        }
+    }
+
+    Ok(None)
+}
+
+fn number(value: &Value) -> Result<U256, String> {
+    match *value {
+        Value::Number(ref n) => n
+            .as_u64()
+            .map(Into::into)
+            .ok_or_else(|| format!("Invalid number: {}", n)),
+        Value::String(ref s) if s.starts_with("0x") => {
+            let digits = &s[2..];
+            if digits.is_empty() {
+                return Ok(U256::zero());
+            }
+            digits
+                .parse()
+                .map_err(|e| format!("Invalid number {}: {:?}", s, e))
+        }
+        Value::String(ref s) => {
+            U256::from_dec_str(s).map_err(|e| format!("Invalid number {}: {:?}", s, e))
+        }
+        ref other => Err(format!("Invalid number: {}", other)),
+    }
+}
+
+fn stack(value: &Value) -> Result<Vec<U256>, String> {
+    match *value {
+        Value::Array(ref items) => items.iter().map(number).collect(),
+        Value::Null => Ok(Vec::new()),
+        ref other => Err(format!("Invalid stack: {}", other)),
+    }
+}
+
+fn memory(value: &Value) -> Result<Option<Vec<u8>>, String> {
+    match *value {
+        Value::Null => Ok(None),
+        Value::String(ref s) => s
+            .trim_start_matches("0x")
+            .from_hex()
+            .map(Some)
+            .map_err(|e| format!("Invalid memory {}: {}", s, e)),
+        // Some tracers emit the memory as a list of 32 bytes words.
+        Value::Array(ref words) => {
+            let mut memory = Vec::new();
+            for word in words {
+                let word = word
+                    .as_str()
+                    .ok_or_else(|| format!("Invalid memory word: {}", word))?;
+                memory.extend(
+                    word.trim_start_matches("0x")
+                        .from_hex()
+                        .map_err(|e| format!("Invalid memory word {}: {}", word, e))?,
+                );
+            }
+            Ok(Some(memory))
+        }
+        ref other => Err(format!("Invalid memory: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OURS: &str = r#"{"depth":1,"gas":"0xffff","memory":"0x","op":96,"opName":"PUSH1","pc":0,"stack":[],"storage":{}}
+{"depth":1,"gas":"0xfffc","memory":"0x","op":96,"opName":"PUSH1","pc":2,"stack":["0x1"],"storage":{}}
+{"depth":1,"gas":"0xfff9","memory":"0x","op":82,"opName":"MSTORE","pc":4,"stack":["0x1","0x0"],"storage":{}}
+{"depth":1,"gas":"0xfff3","memory":"0x0000000000000000000000000000000000000000000000000000000000000001","op":89,"opName":"MSIZE","pc":5,"stack":[],"storage":{}}
+"#;
+
+    #[test]
+    fn should_accept_equivalent_reference() {
+        let reference = r#"{"pc":0,"op":96,"gas":"0xffff","gasCost":"0x3","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}
+{"pc":2,"op":96,"gas":"0xfffc","gasCost":"0x3","memSize":0,"stack":["0x1"],"depth":1,"refund":0,"opName":"PUSH1"}
+{"pc":4,"op":82,"gas":"0xfff9","gasCost":"0x6","memory":"0x","memSize":0,"stack":["0x1","0x0"],"depth":1,"refund":0,"opName":"MSTORE"}
+{"pc":5,"op":89,"gas":65523,"gasCost":"0x2","memory":"0x00000000000000000000000000000000000000000000000000000000000000010000","memSize":32,"stack":[],"depth":1,"refund":0,"opName":"MSIZE"}
+{"output":"","gasUsed":"0xe","time":1000}
+"#;
+        assert_eq!(compare(OURS, reference), Ok(None));
+    }
+
+    #[test]
+    fn should_report_first_divergence() {
+        let reference = OURS.replace(r#""gas":"0xfff3""#, r#""gas":"0xfff4""#);
+        assert_eq!(
+            compare(OURS, &reference),
+            Ok(Some(Divergence {
+                step: 3,
+                field: "gas".into(),
+                ours: Some("0xfff3".into()),
+                reference: Some("0xfff4".into()),
+            }))
+        );
+
+        let reference = OURS.replace(r#"["0x1","0x0"]"#, r#"["0x2","0x0"]"#);
+        assert_eq!(compare(OURS, &reference).unwrap().unwrap().field, "stack");
+
+        let reference = OURS.replace("0001\"", "0002\"");
+        assert_eq!(compare(OURS, &reference).unwrap().unwrap().field, "memory");
+    }
+
+    #[test]
+    fn should_report_trace_ending_early() {
+        let reference: String = OURS.lines().take(2).map(|l| format!("{}\n", l)).collect();
+        let divergence = compare(OURS, &reference).unwrap().unwrap();
+        assert_eq!(divergence.step, 2);
+        assert_eq!(divergence.field, "length");
+        assert!(divergence.ours.is_some());
+        assert_eq!(divergence.reference, None);
+    }
+}
@@ -20,6 +20,8 @@
 pub struct Config {
     omit_storage_output: bool,
     omit_memory_output: bool,
+    std_json_memory_output: bool,
+    eip3155_memory_output: bool,
 }
 
 impl Config {
@@ -27,9 +29,23 @@ impl Config {
         Config {
             omit_storage_output,
             omit_memory_output,
+            std_json_memory_output: false,
+            eip3155_memory_output: false,
         }
     }
 
+    /// Includes the memory in standardized JSON traces.
+    pub fn with_std_json_memory_output(mut self) -> Config {
+        self.std_json_memory_output = true;
+        self
+    }
+
+    /// Includes the memory in EIP-3155 traces.
+    pub fn with_eip3155_memory_output(mut self) -> Config {
+        self.eip3155_memory_output = true;
+        self
+    }
+
     pub fn omit_storage_output(&self) -> bool {
         self.omit_storage_output
     }
@@ -37,4 +53,12 @@ impl Config {
     pub fn omit_memory_output(&self) -> bool {
         self.omit_memory_output
     }
+
+    pub fn std_json_memory_output(&self) -> bool {
+        self.std_json_memory_output && !self.omit_memory_output
+    }
+
+    pub fn eip3155_memory_output(&self) -> bool {
+        self.eip3155_memory_output && !self.omit_memory_output
+    }
 }
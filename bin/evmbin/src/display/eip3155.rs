@@ -0,0 +1,193 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-3155 JSON VM output.
+
+use std::io;
+
+use super::{config::Config, std_json::Writer};
+use bytes::ToPretty;
+use display;
+use ethcore::trace::{self, Eip3155Sink, Eip3155Step, Eip3155Tracer};
+use ethereum_types::{Address, U256};
+use info as vm;
+use serde_json;
+
+/// EIP-3155 formatting informant.
+///
+/// Prints one line per execution step as it is traced, followed by a summary line,
+/// all to a single sink (standard error by default, like go-ethereum).
+pub struct Informant<Out> {
+    tracer: Eip3155Tracer<StepWriter<Out>>,
+    sink: Out,
+}
+
+/// Writes each step as a JSON line.
+pub struct StepWriter<Out>(Out);
+
+impl<Out: Writer> Eip3155Sink for StepWriter<Out> {
+    fn step(&mut self, step: Eip3155Step) {
+        let line = serde_json::to_string(&step).expect("serialization cannot fail; qed");
+        writeln!(self.0, "{}", line).expect("The sink must be writeable.");
+    }
+}
+
+impl Default for Informant<io::Stderr> {
+    fn default() -> Self {
+        Self::new(io::stderr(), Config::default())
+    }
+}
+
+impl<Out: Writer> Informant<Out> {
+    pub fn new(sink: Out, config: Config) -> Self {
+        let tracer = Eip3155Tracer::new(StepWriter(sink.clone()));
+        let tracer = if config.eip3155_memory_output() {
+            tracer.with_memory()
+        } else {
+            tracer
+        };
+        Informant { tracer, sink }
+    }
+}
+
+impl<Out: Writer> vm::Informant for Informant<Out> {
+    type Sink = Out;
+
+    fn before_test(&mut self, _name: &str, _action: &str) {}
+
+    fn clone_sink(&self) -> Self::Sink {
+        self.sink.clone()
+    }
+
+    fn finish(result: vm::RunResult<Self::Output>, sink: &mut Self::Sink) {
+        let summary = match result {
+            Ok(success) => json!({
+                    "stateRoot": success.state_root,
+                    "output": format!("0x{}", success.output.to_hex()),
+                    "gasUsed": format!("{:#x}", success.gas_used),
+                    "pass": true,
+                "time": display::as_micros(&success.time),
+            }),
+            Err(failure) => json!({
+                    "stateRoot": failure.state_root,
+                    "output": "0x",
+                    "gasUsed": format!("{:#x}", failure.gas_used),
+                    "pass": false,
+                    "error": failure.error.to_string(),
+                "time": display::as_micros(&failure.time),
+            }),
+        };
+
+        writeln!(sink, "{}", summary).expect("The sink must be writeable.");
+    }
+}
+
+impl<Out: Writer> trace::VMTracer for Informant<Out> {
+    type Output = ();
+
+    fn trace_next_instruction(&mut self, pc: usize, instruction: u8, current_gas: U256) -> bool {
+        self.tracer
+            .trace_next_instruction(pc, instruction, current_gas)
+    }
+
+    fn trace_prepare_execute(
+        &mut self,
+        pc: usize,
+        instruction: u8,
+        gas_cost: U256,
+        mem_written: Option<(usize, usize)>,
+        store_written: Option<(U256, U256)>,
+    ) {
+        self.tracer
+            .trace_prepare_execute(pc, instruction, gas_cost, mem_written, store_written)
+    }
+
+    fn trace_failed(&mut self) {
+        self.tracer.trace_failed()
+    }
+
+    fn trace_executed(&mut self, gas_used: U256, stack_push: &[U256], mem: &[u8]) {
+        self.tracer.trace_executed(gas_used, stack_push, mem)
+    }
+
+    fn trace_refund(&mut self, refund: i128) {
+        self.tracer.trace_refund(refund)
+    }
+
+    fn trace_suicide_refund(&mut self, address: Address, refund: i128) {
+        self.tracer.trace_suicide_refund(address, refund)
+    }
+
+    fn prepare_subtrace(&mut self, code: &[u8]) {
+        self.tracer.prepare_subtrace(code)
+    }
+
+    fn done_subtrace(&mut self) {
+        self.tracer.done_subtrace()
+    }
+
+    fn drain(self) -> Option<Self::Output> {
+        self.tracer.drain();
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use display::std_json::tests::TestWriter;
+    use info::tests::run_test;
+
+    fn informant(config: Config) -> (Informant<TestWriter>, TestWriter) {
+        let writer: TestWriter = Default::default();
+        (Informant::new(Clone::clone(&writer), config), writer)
+    }
+
+    fn compare_steps(writer: TestWriter) -> impl FnOnce(Option<()>, &str) {
+        move |_, expected| {
+            let bytes = writer.0.lock().unwrap();
+            assert_eq!(String::from_utf8_lossy(&**bytes).trim_end(), expected);
+        }
+    }
+
+    #[test]
+    fn should_trace_steps() {
+        let (inf, writer) = informant(Config::default().with_eip3155_memory_output());
+        run_test(
+            inf,
+            compare_steps(writer),
+            "600160005259",
+            0xffff,
+            r#"{"pc":0,"op":96,"gas":"0xffff","gasCost":"0x3","memory":"0x","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"PUSH1"}
+{"pc":2,"op":96,"gas":"0xfffc","gasCost":"0x3","memory":"0x","memSize":0,"stack":["0x1"],"depth":1,"refund":0,"opName":"PUSH1"}
+{"pc":4,"op":82,"gas":"0xfff9","gasCost":"0x6","memory":"0x","memSize":0,"stack":["0x1","0x0"],"depth":1,"refund":0,"opName":"MSTORE"}
+{"pc":5,"op":89,"gas":"0xfff3","gasCost":"0x2","memory":"0x0000000000000000000000000000000000000000000000000000000000000001","memSize":32,"stack":[],"depth":1,"refund":0,"opName":"MSIZE"}"#,
+        );
+    }
+
+    #[test]
+    fn should_omit_memory_by_default() {
+        let (inf, writer) = informant(Config::default());
+        run_test(
+            inf,
+            compare_steps(writer),
+            "5A51",
+            0xfffff,
+            r#"{"pc":0,"op":90,"gas":"0xfffff","gasCost":"0x2","memSize":0,"stack":[],"depth":1,"refund":0,"opName":"GAS"}
+{"pc":1,"op":81,"gas":"0xffffd","gasCost":"0x0","memSize":0,"stack":["0xffffd"],"depth":1,"refund":0,"opName":"MLOAD"}"#,
+        );
+    }
+}
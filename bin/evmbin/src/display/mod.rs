@@ -19,6 +19,7 @@
 use std::time::Duration;
 
 pub mod config;
+pub mod eip3155;
 pub mod json;
 pub mod simple;
 pub mod std_json;
@@ -56,6 +56,8 @@ pub struct Informant<Trace, Out> {
     instruction: u8,
     depth: usize,
     stack: Vec<U256>,
+    memory: Vec<u8>,
+    mem_written: Option<(usize, usize)>,
     storage: HashMap<H256, H256>,
     subinfos: Vec<Informant<Trace, Out>>,
     subdepth: usize,
@@ -99,6 +101,8 @@ impl<Trace: Writer, Out: Writer> Informant<Trace, Out> {
             instruction: Default::default(),
             depth: Default::default(),
             stack: Default::default(),
+            memory: Default::default(),
+            mem_written: None,
             storage: Default::default(),
             subinfos: Default::default(),
             subdepth: 0,
@@ -210,7 +214,7 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
             };
             let info = ::evm::Instruction::from_u8(instruction).map(|i| i.info());
             informant.instruction = instruction;
-            let trace_data = json!({
+            let mut trace_data = json!({
                 "pc": pc,
                 "op": instruction,
                 "opName": info.map(|i| i.name).unwrap_or(""),
@@ -219,6 +223,9 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
                 "storage": storage,
                 "depth": informant.depth,
             });
+            if informant.config.std_json_memory_output() {
+                trace_data["memory"] = json!(format!("0x{}", informant.memory.to_hex()));
+            }
 
             writeln!(&mut informant.trace_sink, "{}", trace_data)
                 .expect("The sink must be writeable.");
@@ -231,11 +238,12 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
         _pc: usize,
         _instruction: u8,
         _gas_cost: U256,
-        _mem_written: Option<(usize, usize)>,
+        mem_written: Option<(usize, usize)>,
         store_written: Option<(U256, U256)>,
     ) {
         let subdepth = self.subdepth;
         Self::with_informant_in_depth(self, subdepth, |informant: &mut Informant<Trace, Out>| {
+            informant.mem_written = mem_written;
             if let Some((pos, val)) = store_written {
                 informant.storage.insert(
                     BigEndianHash::from_uint(&pos),
@@ -245,7 +253,7 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
         });
     }
 
-    fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], _mem: &[u8]) {
+    fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], mem: &[u8]) {
         let subdepth = self.subdepth;
         Self::with_informant_in_depth(self, subdepth, |informant: &mut Informant<Trace, Out>| {
             let info = ::evm::Instruction::from_u8(informant.instruction).map(|i| i.info());
@@ -256,6 +264,13 @@ impl<Trace: Writer, Out: Writer> trace::VMTracer for Informant<Trace, Out> {
                 .stack
                 .truncate(if len > info_args { len - info_args } else { 0 });
             informant.stack.extend_from_slice(stack_push);
+
+            if let Some((pos, size)) = informant.mem_written {
+                if informant.memory.len() < (pos + size) {
+                    informant.memory.resize(pos + size, 0);
+                }
+                informant.memory[pos..(pos + size)].copy_from_slice(&mem[pos..(pos + size)]);
+            }
         });
     }
 
@@ -391,6 +406,25 @@ pub mod tests {
         )
     }
 
+    #[test]
+    fn should_trace_memory_when_requested() {
+        let (informant, res) = informant(Config::default().with_std_json_memory_output());
+        run_test(
+            informant,
+            move |_, expected| {
+                let bytes = res.lock().unwrap();
+                assert_eq!(expected, &String::from_utf8_lossy(&**bytes))
+            },
+            "600160005259",
+            0xffff,
+            r#"{"depth":1,"gas":"0xffff","memory":"0x","op":96,"opName":"PUSH1","pc":0,"stack":[],"storage":{}}
+{"depth":1,"gas":"0xfffc","memory":"0x","op":96,"opName":"PUSH1","pc":2,"stack":["0x1"],"storage":{}}
+{"depth":1,"gas":"0xfff9","memory":"0x","op":82,"opName":"MSTORE","pc":4,"stack":["0x1","0x0"],"storage":{}}
+{"depth":1,"gas":"0xfff3","memory":"0x0000000000000000000000000000000000000000000000000000000000000001","op":89,"opName":"MSIZE","pc":5,"stack":[],"storage":{}}
+"#,
+        )
+    }
+
     #[test]
     fn should_omit_storage_and_memory_flag() {
         // should omit storage
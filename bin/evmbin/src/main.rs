@@ -33,6 +33,8 @@ extern crate ethereum_types;
 extern crate evm;
 extern crate panic_hook;
 extern crate parity_bytes as bytes;
+extern crate rlp;
+extern crate triehash_ethereum as triehash;
 extern crate vm;
 
 #[cfg(test)]
@@ -48,12 +50,18 @@ use ethcore::{json_tests, spec, TrieSpec};
 use ethereum_types::{Address, U256};
 use ethjson::spec::ForkSpec;
 use evm::EnvInfo;
-use rustc_hex::FromHex;
-use std::{fmt, fs, path::PathBuf, sync::Arc};
+use rustc_hex::{FromHex, ToHex};
+use std::{
+    env, fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 use vm::{ActionParams, CallType};
 
+mod diff;
 mod display;
 mod info;
+mod t8ntool;
 
 use info::Informant;
 
@@ -62,17 +70,26 @@ EVM implementation for Parity.
   Copyright 2015-2020 Parity Technologies (UK) Ltd.
 
 Usage:
-    openethereum-evm state-test <file> [--json --std-json --std-dump-json --only NAME --chain CHAIN --std-out-only --std-err-only --omit-storage-output --omit-memory-output]
+    openethereum-evm state-test <file> [--json --std-json --std-dump-json --only NAME --chain CHAIN --std-out-only --std-err-only --omit-storage-output --omit-memory-output --diff-trace FILE --eip3155 --eip3155-memory]
+    openethereum-evm blockchain-test <file> [--json --only NAME --chain CHAIN]
     openethereum-evm stats [options]
     openethereum-evm stats-jsontests-vm <file>
+    openethereum-evm t8n [--input-alloc FILE --input-env FILE --input-txs FILE --output-basedir DIR --output-alloc FILE --output-result FILE --output-body FILE --state-fork FORK --state-reward WEI --state-chainid ID]
+    openethereum-evm b11r [--input-header FILE --input-ommers FILE --input-txs FILE --output-basedir DIR --output-block FILE]
     openethereum-evm [options]
     openethereum-evm [-h | --help]
 
 Commands:
     state-test         Run a state test from a json file.
+    blockchain-test    Run a blockchain test from a json file and report the
+                       result of each block.
     stats              Execute EVM runtime code and return the statistics.
     stats-jsontests-vm Execute standard json-tests format VMTests and return
                        timing statistics in tsv format.
+    t8n                Apply transactions on top of a pre-state within a single block
+                       and output the post-state, receipts and roots.
+    b11r               Assemble an RLP encoded block from a header, transactions
+                       and ommers.
 
 Transaction options:
     --code CODE        Contract code as hex (without 0x).
@@ -89,6 +106,27 @@ State test options:
                        HomesteadToDaoAt5, HomesteadToEIP150At5, Berlin, Yolo3).
     --only NAME        Runs only a single test matching the name.
 
+State transition and block builder options:
+    --input-alloc FILE     Pre-state accounts (default: alloc.json).
+    --input-env FILE       Block environment (default: env.json).
+    --input-txs FILE       Transactions to apply as JSON for t8n (default: txs.json),
+                           RLP encoded transactions list for b11r (default: txs.rlp).
+    --input-header FILE    Block header (default: header.json).
+    --input-ommers FILE    RLP encoded ommer headers (default: ommers.json).
+    --output-basedir DIR   Directory of the output files (default: current directory).
+    --output-alloc FILE    Post-state accounts (default: alloc.json).
+    --output-result FILE   Roots, receipts and rejected transactions (default: result.json).
+    --output-body FILE     RLP encoded list of the included transactions.
+    --output-block FILE    RLP encoded block and its hash (default: block.json).
+    --state-fork FORK      Fork rules to apply, by name or chain spec file path (default: London).
+    --state-reward WEI     Block reward in wei, -1 to skip rewards (default: -1).
+    --state-chainid ID     Chain id to sign and verify transactions with (default: chain
+                           id of the fork).
+
+    Inputs named stdin are read from a single JSON object on standard input and
+    outputs named stdout are written as a single JSON object to standard output.
+    Option names may also be separated with dots, e.g. --input.alloc.
+
 General options:
     --json                    Display verbose results in JSON.
     --std-json                Display results in standardized JSON format.
@@ -98,6 +136,12 @@ General options:
     --omit-memory-output      With --std-json omit memory output.
     --std-dump-json           Display results in standardized JSON format
                               with additional state dump.
+    --eip3155                 Display results in EIP-3155 JSON format on the error
+                              output, or on the standard output with --std-out-only.
+    --eip3155-memory          With --eip3155 include the memory in each step.
+    --diff-trace FILE         Compare the standardized JSON trace, including memory,
+                              with a reference trace (i.e. go-ethereum `evm --json`)
+                              and report the first divergence.
 
 Display result state dump in standardized JSON format.
     --chain CHAIN      Chain spec file path.
@@ -109,15 +153,38 @@ fn main() {
     env_logger::init();
 
     let args: Args = Docopt::new(USAGE)
-        .and_then(|d| d.deserialize())
+        .and_then(|d| d.argv(env::args().map(normalize_option)).deserialize())
         .unwrap_or_else(|e| e.exit());
 
     let config = args.config();
 
     if args.cmd_state_test {
         run_state_test(args)
+    } else if args.cmd_blockchain_test {
+        run_blockchain_test(args)
     } else if args.cmd_stats_jsontests_vm {
         run_stats_jsontests_vm(args)
+    } else if args.cmd_t8n {
+        run_t8n(args)
+    } else if args.cmd_b11r {
+        run_b11r(args)
+    } else if let Some(reference) = args.flag_diff_trace.clone() {
+        let trace = diff::TraceBuffer::default();
+        run_call(
+            args,
+            display::std_json::Informant::new(
+                trace.clone(),
+                io::stdout(),
+                config.with_std_json_memory_output(),
+            ),
+        );
+        compare_trace(&trace, &reference);
+    } else if args.flag_eip3155 {
+        if args.flag_std_out_only {
+            run_call(args, display::eip3155::Informant::new(io::stdout(), config))
+        } else {
+            run_call(args, display::eip3155::Informant::new(io::stderr(), config))
+        }
     } else if args.flag_json {
         run_call(args, display::json::Informant::new(config))
     } else if args.flag_std_dump_json || args.flag_std_json {
@@ -184,6 +251,10 @@ fn run_state_test(args: Args) {
     };
     let only_test = args.flag_only.map(|s| s.to_lowercase());
     let only_chain = args.flag_chain.map(|s| s.to_lowercase());
+    let diff_trace = args
+        .flag_diff_trace
+        .as_ref()
+        .map(|_| diff::TraceBuffer::default());
 
     for (name, test) in state_test {
         if let Some(false) = only_test
@@ -221,7 +292,47 @@ fn run_state_test(args: Args) {
                 } else {
                     TrieSpec::Secure
                 };
-                if args.flag_json {
+                if let Some(ref trace) = diff_trace {
+                    info::run_transaction(
+                        &name,
+                        idx,
+                        &spec,
+                        &pre,
+                        post_root,
+                        &test_env,
+                        transaction,
+                        display::std_json::Informant::new(
+                            trace.clone(),
+                            io::stdout(),
+                            config.with_std_json_memory_output(),
+                        ),
+                        trie_spec,
+                    )
+                } else if args.flag_eip3155 && args.flag_std_out_only {
+                    info::run_transaction(
+                        &name,
+                        idx,
+                        &spec,
+                        &pre,
+                        post_root,
+                        &test_env,
+                        transaction,
+                        display::eip3155::Informant::new(io::stdout(), config),
+                        trie_spec,
+                    )
+                } else if args.flag_eip3155 {
+                    info::run_transaction(
+                        &name,
+                        idx,
+                        &spec,
+                        &pre,
+                        post_root,
+                        &test_env,
+                        transaction,
+                        display::eip3155::Informant::new(io::stderr(), config),
+                        trie_spec,
+                    )
+                } else if args.flag_json {
                     info::run_transaction(
                         &name,
                         idx,
@@ -287,6 +398,213 @@ fn run_state_test(args: Args) {
             }
         }
     }
+
+    if let (Some(trace), Some(reference)) = (diff_trace, args.flag_diff_trace) {
+        compare_trace(&trace, &reference);
+    }
+}
+
+fn run_blockchain_test(args: Args) {
+    use ethjson::blockchain::Test;
+    let file = args.arg_file.expect("FILE is required");
+    let mut file = match fs::File::open(&file) {
+        Err(err) => die(format!("Unable to open: {:?}: {}", file, err)),
+        Ok(file) => file,
+    };
+    let chain_test = match Test::load(&mut file) {
+        Err(err) => die(format!("Unable to load the test file: {}", err)),
+        Ok(test) => test,
+    };
+    let only_test = args.flag_only.map(|s| s.to_lowercase());
+    let only_chain = args.flag_chain.map(|s| s.to_lowercase());
+    let mut failed = false;
+
+    for (name, blockchain) in chain_test {
+        if let Some(false) = only_test
+            .as_ref()
+            .map(|only_test| &name.to_lowercase() == only_test)
+        {
+            continue;
+        }
+
+        let network = format!("{:?}", blockchain.network);
+        if let Some(false) = only_chain
+            .as_ref()
+            .map(|only_chain| &network.to_lowercase() == only_chain)
+        {
+            continue;
+        }
+        let spec = match ethcore::client::EvmTestClient::spec_from_json(&blockchain.network) {
+            Some(spec) => spec,
+            None => {
+                println!("{}: skipped, {} is not supported", name, network);
+                continue;
+            }
+        };
+
+        let result = json_tests::run_chain_test(&name, spec, &blockchain, false);
+        failed |= !result.is_success();
+
+        for (idx, block) in result.blocks.iter().enumerate() {
+            if args.flag_json {
+                println!(
+                    "{}",
+                    json!({
+                        "test": name,
+                        "block": idx,
+                        "number": block.number,
+                        "hash": block.hash,
+                        "expected": block.expected,
+                        "imported": block.imported,
+                        "error": block.error,
+                    })
+                );
+            } else {
+                println!(
+                    "{} block {} ({}): {}, imported: {}, expected: {}{}",
+                    name,
+                    idx,
+                    block
+                        .number
+                        .map_or_else(|| "undecodable".into(), |n| format!("#{}", n)),
+                    if block.is_success() { "OK" } else { "FAILED" },
+                    block.imported,
+                    block.expected,
+                    block
+                        .error
+                        .as_ref()
+                        .map_or_else(String::new, |e| format!(" ({})", e)),
+                );
+            }
+        }
+
+        if args.flag_json {
+            println!(
+                "{}",
+                json!({
+                    "test": name,
+                    "pass": result.is_success(),
+                    "bestBlock": result.best_block,
+                    "expectedBestBlock": result.expected_best_block,
+                    "postState": result.post_state,
+                })
+            );
+        } else {
+            println!(
+                "{}: {}, best block: {:?}, expected: {:?}, post-state: {}",
+                name,
+                if result.is_success() { "OK" } else { "FAILED" },
+                result.best_block,
+                result.expected_best_block,
+                if result.post_state { "OK" } else { "mismatch" },
+            );
+        }
+    }
+
+    if failed {
+        ::std::process::exit(-1)
+    }
+}
+
+fn compare_trace(trace: &diff::TraceBuffer, reference: &str) {
+    let reference = fs::read_to_string(reference)
+        .unwrap_or_else(|e| die(format!("Unable to read {}: {}", reference, e)));
+    match diff::compare(&trace.contents(), &reference) {
+        Ok(None) => println!("Traces match."),
+        Ok(Some(divergence)) => die(divergence),
+        Err(err) => die(err),
+    }
+}
+
+fn run_t8n(args: Args) {
+    use t8ntool::transition::{self, Alloc, Env, Transaction};
+
+    let spec = arg(args.fork(), "--state-fork");
+    let reward = arg(args.reward(), "--state-reward");
+    let chain_id = arg(args.chain_id(&spec), "--state-chainid");
+
+    let mut inputs = t8ntool::Inputs::default();
+    let alloc: Alloc = arg(
+        inputs.read("alloc", flag_or(&args.flag_input_alloc, "alloc.json")),
+        "--input-alloc",
+    );
+    let env: Env = arg(
+        inputs.read("env", flag_or(&args.flag_input_env, "env.json")),
+        "--input-env",
+    );
+    let txs: Vec<Transaction> = arg(
+        inputs.read("txs", flag_or(&args.flag_input_txs, "txs.json")),
+        "--input-txs",
+    );
+
+    let transition = transition::transition(&spec, alloc, env, txs, reward, chain_id)
+        .unwrap_or_else(|e| die(format!("State transition failed: {}", e)));
+
+    let mut outputs = t8ntool::Outputs::new(args.output_basedir());
+    arg(
+        outputs.write(
+            "alloc",
+            flag_or(&args.flag_output_alloc, "alloc.json"),
+            serde_json::to_value(&transition.alloc).expect("pod state is serializable; qed"),
+        ),
+        "--output-alloc",
+    );
+    arg(
+        outputs.write(
+            "result",
+            flag_or(&args.flag_output_result, "result.json"),
+            transition.result,
+        ),
+        "--output-result",
+    );
+    if let Some(ref body) = args.flag_output_body {
+        arg(
+            outputs.write(
+                "body",
+                body,
+                json!(format!("0x{}", transition.body.to_hex())),
+            ),
+            "--output-body",
+        );
+    }
+    outputs.finish();
+}
+
+fn run_b11r(args: Args) {
+    use t8ntool::block::{self, Header};
+
+    let mut inputs = t8ntool::Inputs::default();
+    let header: Header = arg(
+        inputs.read("header", flag_or(&args.flag_input_header, "header.json")),
+        "--input-header",
+    );
+    let ommers: Vec<ethjson::bytes::Bytes> = arg(
+        inputs.read("ommers", flag_or(&args.flag_input_ommers, "ommers.json")),
+        "--input-ommers",
+    );
+    let txs: ethjson::bytes::Bytes = arg(
+        inputs.read("txs", flag_or(&args.flag_input_txs, "txs.rlp")),
+        "--input-txs",
+    );
+
+    let ommers: Vec<Bytes> = ommers.into_iter().map(Into::into).collect();
+    let txs: Bytes = txs.into();
+    let block = block::build(header, &txs, &ommers)
+        .unwrap_or_else(|e| die(format!("Unable to build the block: {}", e)));
+
+    let mut outputs = t8ntool::Outputs::new(args.output_basedir());
+    arg(
+        outputs.write(
+            "block",
+            flag_or(&args.flag_output_block, "block.json"),
+            json!({
+                "rlp": format!("0x{}", block.rlp.to_hex()),
+                "hash": block.hash,
+            }),
+        ),
+        "--output-block",
+    );
+    outputs.finish();
 }
 
 fn run_call<T: Informant>(args: Args, informant: T) {
@@ -339,6 +657,9 @@ struct Args {
     cmd_stats: bool,
     cmd_state_test: bool,
     cmd_stats_jsontests_vm: bool,
+    cmd_t8n: bool,
+    cmd_b11r: bool,
+    cmd_blockchain_test: bool,
     arg_file: Option<PathBuf>,
     flag_only: Option<String>,
     flag_from: Option<String>,
@@ -355,6 +676,22 @@ struct Args {
     flag_std_out_only: bool,
     flag_omit_storage_output: bool,
     flag_omit_memory_output: bool,
+    flag_diff_trace: Option<String>,
+    flag_eip3155: bool,
+    flag_eip3155_memory: bool,
+    flag_input_alloc: Option<String>,
+    flag_input_env: Option<String>,
+    flag_input_txs: Option<String>,
+    flag_input_header: Option<String>,
+    flag_input_ommers: Option<String>,
+    flag_output_basedir: Option<PathBuf>,
+    flag_output_alloc: Option<String>,
+    flag_output_result: Option<String>,
+    flag_output_body: Option<String>,
+    flag_output_block: Option<String>,
+    flag_state_fork: Option<String>,
+    flag_state_reward: Option<String>,
+    flag_state_chainid: Option<String>,
 }
 
 impl Args {
@@ -401,24 +738,72 @@ impl Args {
     }
 
     pub fn spec(&self) -> Result<spec::Spec, String> {
-        Ok(match self.flag_chain {
-            Some(ref spec_name) => {
-                let fork_spec: Result<ethjson::spec::ForkSpec, _> =
-                    serde_json::from_str(&format!("{:?}", spec_name));
-                if let Ok(fork_spec) = fork_spec {
-                    ethcore::client::EvmTestClient::spec_from_json(&fork_spec)
-                        .expect("this forkspec is not defined")
-                } else {
-                    let file = fs::File::open(spec_name).map_err(|e| format!("{}", e))?;
-                    spec::Spec::load(&::std::env::temp_dir(), file)?
-                }
-            }
-            None => ethcore::ethereum::new_foundation(&::std::env::temp_dir()),
-        })
+        match self.flag_chain {
+            Some(ref spec_name) => load_spec(spec_name),
+            None => Ok(ethcore::ethereum::new_foundation(&::std::env::temp_dir())),
+        }
+    }
+
+    pub fn fork(&self) -> Result<spec::Spec, String> {
+        load_spec(flag_or(&self.flag_state_fork, "London"))
+    }
+
+    pub fn reward(&self) -> Result<Option<U256>, String> {
+        match self.flag_state_reward {
+            Some(ref reward) if reward != "-1" => U256::from_dec_str(reward)
+                .map(Some)
+                .map_err(|e| format!("{:?}", e)),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn chain_id(&self, spec: &spec::Spec) -> Result<u64, String> {
+        match self.flag_state_chainid {
+            Some(ref chain_id) => chain_id.parse().map_err(to_string),
+            None => Ok(spec.chain_id()),
+        }
+    }
+
+    pub fn output_basedir(&self) -> PathBuf {
+        self.flag_output_basedir.clone().unwrap_or_default()
     }
 
     pub fn config(&self) -> display::config::Config {
-        display::config::Config::new(self.flag_omit_storage_output, self.flag_omit_memory_output)
+        let config = display::config::Config::new(
+            self.flag_omit_storage_output,
+            self.flag_omit_memory_output,
+        );
+        if self.flag_eip3155_memory {
+            config.with_eip3155_memory_output()
+        } else {
+            config
+        }
+    }
+}
+
+fn load_spec(spec_name: &str) -> Result<spec::Spec, String> {
+    let fork_spec: Result<ethjson::spec::ForkSpec, _> =
+        serde_json::from_str(&format!("{:?}", spec_name));
+    if let Ok(fork_spec) = fork_spec {
+        Ok(ethcore::client::EvmTestClient::spec_from_json(&fork_spec)
+            .expect("this forkspec is not defined"))
+    } else {
+        spec::Spec::load_file(&::std::env::temp_dir(), Path::new(spec_name))
+    }
+}
+
+fn flag_or<'a>(flag: &'a Option<String>, default: &'a str) -> &'a str {
+    flag.as_ref().map_or(default, String::as_str)
+}
+
+/// Accepts go-ethereum style option names, i.e. `--input.alloc` for `--input-alloc`.
+fn normalize_option(arg: String) -> String {
+    if !arg.starts_with("--") {
+        return arg;
+    }
+    match arg.find('=') {
+        Some(eq) => format!("{}{}", arg[..eq].replace('.', "-"), &arg[eq..]),
+        None => arg.replace('.', "-"),
     }
 }
 
@@ -437,13 +822,20 @@ fn die<T: fmt::Display>(msg: T) -> ! {
 
 #[cfg(test)]
 mod tests {
-    use super::{Args, USAGE};
+    use super::{normalize_option, Args, USAGE};
     use docopt::Docopt;
-    use ethereum_types::Address;
+    use ethereum_types::{Address, U256};
+    use std::path::PathBuf;
 
     fn run<T: AsRef<str>>(args: &[T]) -> Args {
         Docopt::new(USAGE)
-            .and_then(|d| d.argv(args.into_iter()).deserialize())
+            .and_then(|d| {
+                d.argv(
+                    args.iter()
+                        .map(|arg| normalize_option(arg.as_ref().to_owned())),
+                )
+                .deserialize()
+            })
             .unwrap()
     }
 
@@ -508,4 +900,124 @@ mod tests {
         assert_eq!(args.flag_chain, Some("homestead".to_owned()));
         assert_eq!(args.flag_only, Some("add11".to_owned()));
     }
+
+    #[test]
+    fn should_parse_state_test_with_diff_trace() {
+        let args = run(&[
+            "openethereum-evm",
+            "state-test",
+            "./file.json",
+            "--only",
+            "add11",
+            "--diff-trace",
+            "./geth.jsonl",
+        ]);
+
+        assert_eq!(args.cmd_state_test, true);
+        assert_eq!(args.flag_diff_trace, Some("./geth.jsonl".to_owned()));
+        assert!(args
+            .config()
+            .with_std_json_memory_output()
+            .std_json_memory_output());
+    }
+
+    #[test]
+    fn should_parse_eip3155_option() {
+        let args = run(&[
+            "openethereum-evm",
+            "state-test",
+            "./file.json",
+            "--eip3155",
+            "--std-out-only",
+        ]);
+
+        assert_eq!(args.cmd_state_test, true);
+        assert_eq!(args.flag_eip3155, true);
+        assert_eq!(args.flag_std_out_only, true);
+        assert!(!args.config().eip3155_memory_output());
+
+        let args = run(&[
+            "openethereum-evm",
+            "state-test",
+            "./file.json",
+            "--eip3155",
+            "--eip3155-memory",
+        ]);
+        assert!(args.config().eip3155_memory_output());
+    }
+
+    #[test]
+    fn should_parse_blockchain_test_command() {
+        let args = run(&[
+            "openethereum-evm",
+            "blockchain-test",
+            "./file.json",
+            "--chain",
+            "London",
+            "--only=blockhash",
+            "--json",
+        ]);
+
+        assert_eq!(args.cmd_blockchain_test, true);
+        assert!(args.arg_file.is_some());
+        assert_eq!(args.flag_json, true);
+        assert_eq!(args.flag_chain, Some("London".to_owned()));
+        assert_eq!(args.flag_only, Some("blockhash".to_owned()));
+    }
+
+    #[test]
+    fn should_parse_t8n_command() {
+        let args = run(&[
+            "openethereum-evm",
+            "t8n",
+            "--input.alloc=stdin",
+            "--input.env",
+            "stdin",
+            "--input-txs",
+            "./txs.json",
+            "--output.result=stdout",
+            "--output.alloc",
+            "stdout",
+            "--output.basedir",
+            "./out",
+            "--state.fork",
+            "Berlin",
+            "--state.reward",
+            "2000000000000000000",
+            "--state.chainid=5",
+        ]);
+
+        assert_eq!(args.cmd_t8n, true);
+        assert_eq!(args.flag_input_alloc, Some("stdin".to_owned()));
+        assert_eq!(args.flag_input_env, Some("stdin".to_owned()));
+        assert_eq!(args.flag_input_txs, Some("./txs.json".to_owned()));
+        assert_eq!(args.flag_output_result, Some("stdout".to_owned()));
+        assert_eq!(args.flag_output_alloc, Some("stdout".to_owned()));
+        assert_eq!(args.flag_output_body, None);
+        assert_eq!(args.output_basedir(), PathBuf::from("./out"));
+        assert_eq!(args.flag_state_fork, Some("Berlin".to_owned()));
+        assert_eq!(
+            args.reward(),
+            Ok(Some(U256::from(2_000_000_000_000_000_000u64)))
+        );
+        assert_eq!(args.flag_state_chainid, Some("5".to_owned()));
+    }
+
+    #[test]
+    fn should_parse_b11r_command() {
+        let args = run(&[
+            "openethereum-evm",
+            "b11r",
+            "--input.header",
+            "./header.json",
+            "--input.txs=./txs.rlp",
+            "--output.block=stdout",
+        ]);
+
+        assert_eq!(args.cmd_b11r, true);
+        assert_eq!(args.flag_input_header, Some("./header.json".to_owned()));
+        assert_eq!(args.flag_input_ommers, None);
+        assert_eq!(args.flag_input_txs, Some("./txs.rlp".to_owned()));
+        assert_eq!(args.flag_output_block, Some("stdout".to_owned()));
+    }
 }
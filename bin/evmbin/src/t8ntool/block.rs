@@ -0,0 +1,184 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block assembly from a header, transactions and ommers.
+
+use bytes::Bytes;
+use ethereum_types::{H256, H64};
+use ethjson::{self, uint::Uint};
+use rlp::{self, Rlp, RlpStream};
+use triehash::ordered_trie_root;
+use types::{hash::keccak, header, transaction::TypedTransaction};
+
+/// Block header, with the field names of the JSON-RPC API.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Header {
+    parent_hash: ethjson::hash::H256,
+    #[serde(alias = "sha3Uncles", alias = "uncleHash")]
+    ommers_hash: Option<ethjson::hash::H256>,
+    #[serde(alias = "coinbase", alias = "author")]
+    miner: ethjson::hash::Address,
+    state_root: ethjson::hash::H256,
+    #[serde(alias = "transactionsTrie", alias = "txRoot")]
+    transactions_root: Option<ethjson::hash::H256>,
+    #[serde(alias = "receiptTrie")]
+    receipts_root: ethjson::hash::H256,
+    #[serde(alias = "bloom")]
+    logs_bloom: ethjson::hash::Bloom,
+    difficulty: Uint,
+    number: Uint,
+    gas_limit: Uint,
+    gas_used: Uint,
+    timestamp: Uint,
+    extra_data: Option<ethjson::bytes::Bytes>,
+    mix_hash: Option<ethjson::hash::H256>,
+    nonce: Option<ethjson::hash::H64>,
+    base_fee_per_gas: Option<Uint>,
+}
+
+/// Assembled block.
+pub struct Block {
+    /// RLP encoded block.
+    pub rlp: Bytes,
+    /// Hash of the block header.
+    pub hash: H256,
+}
+
+/// Assembles a block from `header`, the RLP encoded list of transactions `txs`
+/// and the RLP encoded `ommers` headers.
+///
+/// Transactions and ommers roots missing from the header are computed.
+pub fn build(header: Header, txs: &[u8], ommers: &[Bytes]) -> Result<Block, String> {
+    let transactions = TypedTransaction::decode_rlp_list(&Rlp::new(txs))
+        .map_err(|e| format!("Invalid transactions: {}", e))?;
+    let mut ommers_rlp = RlpStream::new_list(ommers.len());
+    for ommer in ommers {
+        if !Rlp::new(ommer).is_list() {
+            return Err("Invalid ommer: header is not an RLP list".into());
+        }
+        ommers_rlp.append_raw(ommer, 1);
+    }
+    let ommers_rlp = ommers_rlp.out();
+
+    let mut block_header = header::Header::new();
+    block_header.set_parent_hash(header.parent_hash.into());
+    block_header.set_uncles_hash(
+        header
+            .ommers_hash
+            .map_or_else(|| keccak(&ommers_rlp), Into::into),
+    );
+    block_header.set_author(header.miner.into());
+    block_header.set_state_root(header.state_root.into());
+    block_header.set_transactions_root(header.transactions_root.map_or_else(
+        || ordered_trie_root(transactions.iter().map(|tx| tx.encode())),
+        Into::into,
+    ));
+    block_header.set_receipts_root(header.receipts_root.into());
+    block_header.set_log_bloom(header.logs_bloom.into());
+    block_header.set_difficulty(header.difficulty.into());
+    block_header.set_number(header.number.into());
+    block_header.set_gas_limit(header.gas_limit.into());
+    block_header.set_gas_used(header.gas_used.into());
+    block_header.set_timestamp(header.timestamp.into());
+    block_header.set_extra_data(header.extra_data.map_or_else(Vec::new, Into::into));
+    block_header.set_seal(vec![
+        rlp::encode(&header.mix_hash.map_or_else(H256::zero, Into::into)),
+        rlp::encode(&header.nonce.map_or_else(H64::zero, Into::into)),
+    ]);
+    block_header.set_base_fee(header.base_fee_per_gas.map(Into::into));
+
+    let mut block = RlpStream::new_list(3);
+    block.append(&block_header);
+    block.append_raw(txs, 1);
+    block.append_raw(&ommers_rlp, 1);
+
+    Ok(Block {
+        rlp: block.out(),
+        hash: block_header.hash(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+    use types::{
+        block,
+        hash::{KECCAK_EMPTY_LIST_RLP, KECCAK_NULL_RLP},
+    };
+
+    fn header() -> Header {
+        serde_json::from_value(json!({
+            "parentHash": "0xe268cf0e4a1c1ff21d6b2d3cf3bc55fa1c5e7cb4fa4a4c6b0d0f1f2f3f4f5f6f",
+            "miner": "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba",
+            "stateRoot": "0x5fb2b4bfdef7b314451cb138a534d225c922fc0e5fbe25e451142732c3e25c25",
+            "receiptsRoot": "0x56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421",
+            "logsBloom": format!("0x{}", "00".repeat(256)),
+            "difficulty": "0x20000",
+            "number": "0x1",
+            "gasLimit": "0x1000000",
+            "gasUsed": "0x0",
+            "timestamp": "0x3e8",
+            "extraData": "0x",
+            "baseFeePerGas": "0xa",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_build_block_with_computed_roots() {
+        // given
+        let txs = rlp::EMPTY_LIST_RLP;
+
+        // when
+        let built = build(header(), &txs, &[]).unwrap();
+
+        // then
+        let block = block::Block::decode_rlp(&Rlp::new(&built.rlp), 0).unwrap();
+        assert_eq!(block.header.hash(), built.hash);
+        assert_eq!(block.header.base_fee(), Some(10.into()));
+        assert_eq!(*block.header.uncles_hash(), KECCAK_EMPTY_LIST_RLP);
+        assert_eq!(*block.header.transactions_root(), KECCAK_NULL_RLP);
+        assert!(block.transactions.is_empty());
+        assert!(block.uncles.is_empty());
+    }
+
+    #[test]
+    fn should_include_ommers() {
+        // given
+        let mut ommer = header::Header::new();
+        ommer.set_number(0);
+        ommer.set_base_fee(Some(10.into()));
+        ommer.set_seal(vec![rlp::encode(&H256::zero()), rlp::encode(&H64::zero())]);
+        let ommers = vec![rlp::encode(&ommer)];
+
+        // when
+        let built = build(header(), &rlp::EMPTY_LIST_RLP, &ommers).unwrap();
+
+        // then
+        let ommers_rlp = Rlp::new(&built.rlp).at(2).unwrap();
+        assert_eq!(ommers_rlp.item_count().unwrap(), 1);
+        assert_eq!(ommers_rlp.at(0).unwrap().as_raw(), &ommers[0][..]);
+        let block = block::Block::decode_rlp(&Rlp::new(&built.rlp), 0).unwrap();
+        assert_eq!(*block.header.uncles_hash(), keccak(ommers_rlp.as_raw()));
+    }
+
+    #[test]
+    fn should_reject_invalid_ommers() {
+        assert!(build(header(), &rlp::EMPTY_LIST_RLP, &[vec![0x80]]).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State transition (`t8n`) and block builder (`b11r`) tools.
+//!
+//! Both follow the interface of the go-ethereum `evm t8n` and `evm b11r` commands,
+//! so they can be driven by retesteth and the execution-spec-tests fillers.
+
+pub mod block;
+pub mod transition;
+
+use serde::de::DeserializeOwned;
+use serde_json::{self, Map, Value};
+use std::{fs, io, path::PathBuf};
+
+/// Name of the input which is read from standard input.
+const STDIN: &str = "stdin";
+/// Name of the output which is written to standard output.
+const STDOUT: &str = "stdout";
+
+/// Reads tool inputs from files, or from a single JSON object on standard input.
+#[derive(Default)]
+pub struct Inputs {
+    stdin: Option<Map<String, Value>>,
+}
+
+impl Inputs {
+    /// Reads the input `key` from `path`, or from the `key` field of the object
+    /// on standard input if `path` is `stdin`.
+    pub fn read<T: DeserializeOwned>(&mut self, key: &str, path: &str) -> Result<T, String> {
+        if path != STDIN {
+            let file =
+                fs::File::open(path).map_err(|e| format!("Unable to open {}: {}", path, e))?;
+            return serde_json::from_reader(file)
+                .map_err(|e| format!("Unable to parse {}: {}", path, e));
+        }
+
+        if self.stdin.is_none() {
+            let stdin = serde_json::from_reader(io::stdin())
+                .map_err(|e| format!("Unable to parse standard input: {}", e))?;
+            self.stdin = Some(stdin);
+        }
+        let value = self
+            .stdin
+            .as_mut()
+            .expect("standard input is read above; qed")
+            .remove(key)
+            .ok_or_else(|| format!("Missing {} in standard input", key))?;
+        serde_json::from_value(value).map_err(|e| format!("Unable to parse {}: {}", key, e))
+    }
+}
+
+/// Writes tool outputs to files, or collects them into a single JSON object
+/// printed on standard output.
+pub struct Outputs {
+    basedir: PathBuf,
+    stdout: Map<String, Value>,
+}
+
+impl Outputs {
+    /// Creates outputs with file paths relative to `basedir`.
+    pub fn new(basedir: PathBuf) -> Self {
+        Outputs {
+            basedir,
+            stdout: Map::new(),
+        }
+    }
+
+    /// Writes the output `key` to `path`, or to the standard output object
+    /// if `path` is `stdout`.
+    pub fn write(&mut self, key: &str, path: &str, value: Value) -> Result<(), String> {
+        if path == STDOUT {
+            self.stdout.insert(key.to_owned(), value);
+            return Ok(());
+        }
+
+        let path = self.basedir.join(path);
+        let file = fs::File::create(&path)
+            .map_err(|e| format!("Unable to create {}: {}", path.display(), e))?;
+        serde_json::to_writer_pretty(file, &value)
+            .map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+    }
+
+    /// Prints the outputs collected for standard output.
+    pub fn finish(self) {
+        if !self.stdout.is_empty() {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&Value::Object(self.stdout))
+                    .expect("serialization of a JSON value cannot fail; qed")
+            );
+        }
+    }
+}
@@ -0,0 +1,614 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State transition of a single block.
+
+use bytes::Bytes;
+use ethcore::{
+    client::{EnvInfo, EvmTestClient},
+    pod_account::PodAccount,
+    pod_state::PodState,
+    spec, trace, TrieSpec,
+};
+use ethereum_types::{Address, BigEndianHash, Bloom, H256, U256};
+use ethjson::{self, blockchain::transaction::AccessList, maybe::MaybeEmpty, uint::Uint};
+use rlp::{self, RlpStream};
+use rustc_hex::ToHex;
+use serde_json::Value;
+use std::{cmp, collections::BTreeMap, sync::Arc};
+use triehash::ordered_trie_root;
+use types::{
+    crypto::publickey::Secret,
+    hash::{keccak, KECCAK_EMPTY_LIST_RLP},
+    header::Header,
+    log_entry::LogEntry,
+    receipt::{LegacyReceipt, TransactionOutcome, TypedReceipt},
+    transaction::{
+        self, signature, AccessListTx, EIP1559TransactionTx, SignatureComponents,
+        SignedTransaction, TypedTransaction, TypedTxId, UnverifiedTransaction,
+    },
+};
+
+/// Pre-state accounts by address.
+pub type Alloc = BTreeMap<ethjson::hash::Address, Account>;
+
+/// Pre-state account.
+#[derive(Debug, Deserialize)]
+pub struct Account {
+    balance: Option<Uint>,
+    nonce: Option<Uint>,
+    code: Option<ethjson::bytes::Bytes>,
+    storage: Option<BTreeMap<Uint, Uint>>,
+}
+
+impl From<Account> for PodAccount {
+    fn from(account: Account) -> Self {
+        PodAccount {
+            balance: account.balance.map_or_else(U256::zero, Into::into),
+            nonce: account.nonce.map_or_else(U256::zero, Into::into),
+            code: Some(account.code.map_or_else(Vec::new, Into::into)),
+            storage: account
+                .storage
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(key, value)| {
+                    (
+                        BigEndianHash::from_uint(&key.into()),
+                        BigEndianHash::from_uint(&value.into()),
+                    )
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Block environment.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Env {
+    current_coinbase: ethjson::hash::Address,
+    current_difficulty: Option<Uint>,
+    current_gas_limit: Uint,
+    current_number: Uint,
+    current_timestamp: Uint,
+    current_base_fee: Option<Uint>,
+    parent_difficulty: Option<Uint>,
+    parent_timestamp: Option<Uint>,
+    parent_uncle_hash: Option<ethjson::hash::H256>,
+    parent_base_fee: Option<Uint>,
+    parent_gas_used: Option<Uint>,
+    parent_gas_limit: Option<Uint>,
+    #[serde(default)]
+    block_hashes: BTreeMap<Uint, ethjson::hash::H256>,
+    #[serde(default)]
+    ommers: Vec<Ommer>,
+}
+
+/// Ommer of the block, `delta` blocks behind it.
+#[derive(Debug, Deserialize)]
+pub struct Ommer {
+    delta: u64,
+    address: ethjson::hash::Address,
+}
+
+impl Env {
+    fn env_info(&self, spec: &spec::Spec) -> Result<EnvInfo, String> {
+        let number: u64 = self.current_number.into();
+        let timestamp: u64 = self.current_timestamp.into();
+
+        let difficulty = match (
+            self.current_difficulty,
+            self.parent_difficulty,
+            self.parent_timestamp,
+        ) {
+            (Some(difficulty), _, _) => difficulty.into(),
+            (None, Some(parent_difficulty), Some(parent_timestamp)) if number > 0 => {
+                let mut parent = Header::new();
+                parent.set_number(number - 1);
+                parent.set_difficulty(parent_difficulty.into());
+                parent.set_timestamp(parent_timestamp.into());
+                parent.set_uncles_hash(
+                    self.parent_uncle_hash
+                        .clone()
+                        .map_or(KECCAK_EMPTY_LIST_RLP, Into::into),
+                );
+                let mut header = Header::new();
+                header.set_number(number);
+                header.set_timestamp(timestamp);
+                spec.engine.populate_from_parent(&mut header, &parent);
+                *header.difficulty()
+            }
+            _ => {
+                return Err(
+                    "currentDifficulty, or parentDifficulty and parentTimestamp are required"
+                        .into(),
+                )
+            }
+        };
+
+        let base_fee = match (
+            self.current_base_fee,
+            self.parent_gas_used,
+            self.parent_gas_limit,
+        ) {
+            (Some(base_fee), _, _) => Some(base_fee.into()),
+            _ if number < spec.engine.params().eip1559_transition => None,
+            (None, Some(parent_gas_used), Some(parent_gas_limit)) if number > 0 => {
+                let mut parent = Header::new();
+                parent.set_number(number - 1);
+                parent.set_gas_used(parent_gas_used.into());
+                parent.set_gas_limit(parent_gas_limit.into());
+                parent.set_base_fee(self.parent_base_fee.map(Into::into));
+                spec.engine.calculate_base_fee(&parent)
+            }
+            _ => {
+                return Err(
+                    "currentBaseFee, or parentGasUsed and parentGasLimit are required".into(),
+                )
+            }
+        };
+
+        // `last_hashes[0]` is the hash of the parent block.
+        let last_hashes = (1..=cmp::min(number, 256))
+            .map(|distance| {
+                self.block_hashes
+                    .get(&Uint((number - distance).into()))
+                    .cloned()
+                    .map_or_else(H256::zero, Into::into)
+            })
+            .collect();
+
+        Ok(EnvInfo {
+            number,
+            author: self.current_coinbase.clone().into(),
+            timestamp,
+            difficulty,
+            gas_limit: self.current_gas_limit.into(),
+            last_hashes: Arc::new(last_hashes),
+            gas_used: U256::zero(),
+            base_fee,
+        })
+    }
+}
+
+/// Transaction, either signed (`v`, `r`, `s`) or with the `secretKey` to sign it with.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    #[serde(rename = "type")]
+    transaction_type: Option<Uint>,
+    chain_id: Option<Uint>,
+    nonce: Uint,
+    gas_price: Option<Uint>,
+    max_fee_per_gas: Option<Uint>,
+    max_priority_fee_per_gas: Option<Uint>,
+    #[serde(alias = "gasLimit")]
+    gas: Uint,
+    to: Option<MaybeEmpty<ethjson::hash::Address>>,
+    value: Uint,
+    #[serde(alias = "data")]
+    input: ethjson::bytes::Bytes,
+    access_list: Option<AccessList>,
+    v: Option<Uint>,
+    r: Option<Uint>,
+    s: Option<Uint>,
+    secret_key: Option<ethjson::hash::H256>,
+    protected: Option<bool>,
+}
+
+impl Transaction {
+    /// Signs the transaction with its secret key, or recovers the sender from its signature.
+    fn sign(self, chain_id: u64) -> Result<SignedTransaction, String> {
+        let tx_type = match self.transaction_type.map(Into::<U256>::into) {
+            None => Some(TypedTxId::Legacy),
+            Some(id) if id <= U256::from(u8::max_value()) => {
+                TypedTxId::from_u8_id(id.low_u64() as u8)
+            }
+            Some(_) => None,
+        }
+        .ok_or_else(|| "unsupported transaction type".to_owned())?;
+
+        let to: Option<ethjson::hash::Address> = self.to.and_then(Into::into);
+        let tx = transaction::Transaction {
+            nonce: self.nonce.into(),
+            gas_price: self
+                .gas_price
+                .or(self.max_fee_per_gas)
+                .map_or_else(U256::zero, Into::into),
+            gas: self.gas.into(),
+            action: match to {
+                Some(to) => transaction::Action::Call(to.into()),
+                None => transaction::Action::Create,
+            },
+            value: self.value.into(),
+            data: self.input.into(),
+        };
+        let access_list = self
+            .access_list
+            .unwrap_or_default()
+            .into_iter()
+            .map(|item| (item.address, item.storage_keys))
+            .collect();
+        let unsigned = match tx_type {
+            TypedTxId::Legacy => TypedTransaction::Legacy(tx),
+            TypedTxId::AccessList => {
+                TypedTransaction::AccessList(AccessListTx::new(tx, access_list))
+            }
+            TypedTxId::EIP1559Transaction => {
+                TypedTransaction::EIP1559Transaction(EIP1559TransactionTx {
+                    transaction: AccessListTx::new(tx, access_list),
+                    max_priority_fee_per_gas: self
+                        .max_priority_fee_per_gas
+                        .map_or_else(U256::zero, Into::into),
+                })
+            }
+        };
+
+        if let Some(secret) = self.secret_key {
+            let secret = Secret::import_key((secret.0).as_bytes()).map_err(|e| format!("{}", e))?;
+            let chain_id = match tx_type {
+                TypedTxId::Legacy if !self.protected.unwrap_or(true) => None,
+                _ => Some(self.chain_id.map_or(chain_id, Into::into)),
+            };
+            return Ok(unsigned.sign(&secret, chain_id));
+        }
+
+        let (v, r, s): (u64, U256, U256) = match (self.v, self.r, self.s) {
+            (Some(v), Some(r), Some(s)) => (v.into(), r.into(), s.into()),
+            _ => return Err("either v, r and s or secretKey is required".into()),
+        };
+        let (standard_v, chain_id) = match tx_type {
+            TypedTxId::Legacy => (
+                signature::extract_standard_v(v),
+                signature::extract_chain_id_from_legacy_v(v),
+            ),
+            _ => (
+                if v > 1 { 4 } else { v as u8 },
+                Some(self.chain_id.map_or(chain_id, Into::into)),
+            ),
+        };
+        let transaction = UnverifiedTransaction {
+            unsigned,
+            signature: SignatureComponents { standard_v, r, s },
+            chain_id,
+            hash: H256::zero(),
+        }
+        .compute_hash();
+        SignedTransaction::new(transaction).map_err(|e| format!("{}", e))
+    }
+}
+
+/// Outcome of a block state transition.
+pub struct Transition {
+    /// Post-state of all accounts.
+    pub alloc: PodState,
+    /// Roots, receipts and rejected transactions, in the `evm t8n` result format.
+    pub result: Value,
+    /// RLP encoded list of the included transactions.
+    pub body: Bytes,
+}
+
+/// Applies `txs` on top of `alloc` within the block described by `env`.
+///
+/// Transactions are signed and verified for `chain_id`. Transactions which are
+/// invalid in the block are reported as rejected and leave the state untouched.
+/// If `reward` is set, the block and ommer rewards are paid once all transactions
+/// are applied.
+pub fn transition(
+    spec: &spec::Spec,
+    alloc: Alloc,
+    env: Env,
+    txs: Vec<Transaction>,
+    reward: Option<U256>,
+    chain_id: u64,
+) -> Result<Transition, String> {
+    let mut env_info = env.env_info(spec)?;
+    let pre_state = PodState::from(
+        alloc
+            .into_iter()
+            .map(|(address, account)| (address.into(), account.into()))
+            .collect(),
+    );
+    let mut client = EvmTestClient::from_pod_state_with_trie(spec, pre_state, TrieSpec::Fat)
+        .map_err(|e| format!("{}", e))?;
+
+    let mut included = Vec::new();
+    let mut receipts = Vec::new();
+    let mut receipts_json = Vec::new();
+    let mut rejected = Vec::new();
+    let mut logs = Vec::new();
+
+    for (index, tx) in txs.into_iter().enumerate() {
+        let tx = match tx.sign(chain_id) {
+            Ok(tx) => tx,
+            Err(error) => {
+                rejected.push(json!({ "index": index, "error": error }));
+                continue;
+            }
+        };
+        let gas = tx.tx().gas;
+        let result = match client.transact_with_chain_id(
+            &env_info,
+            tx.clone(),
+            Some(chain_id),
+            trace::NoopTracer,
+            trace::NoopVMTracer,
+        ) {
+            Ok(result) => result,
+            Err(err) => {
+                rejected.push(json!({ "index": index, "error": format!("{}", err.error) }));
+                continue;
+            }
+        };
+
+        let gas_used = gas - result.gas_left;
+        env_info.gas_used = env_info.gas_used + gas_used;
+        let receipt = TypedReceipt::new(
+            tx.tx_type(),
+            LegacyReceipt::new(result.outcome, env_info.gas_used, result.logs),
+        );
+        receipts_json.push(receipt_json(
+            &tx,
+            &receipt,
+            result.contract_address.unwrap_or_default(),
+            gas_used,
+            included.len(),
+            logs.len(),
+        ));
+        logs.extend(receipt.logs.iter().cloned());
+        receipts.push(receipt);
+        included.push(tx);
+    }
+
+    if let Some(reward) = reward {
+        for ommer in &env.ommers {
+            let amount = reward * U256::from(8u64.saturating_sub(ommer.delta)) / 8;
+            client
+                .reward(&env_info, &ommer.address.clone().into(), &amount)
+                .map_err(|e| format!("{}", e))?;
+        }
+        let amount = reward + reward / 32 * U256::from(env.ommers.len());
+        client
+            .reward(&env_info, &env_info.author, &amount)
+            .map_err(|e| format!("{}", e))?;
+    }
+
+    let logs_bloom = receipts.iter().fold(Bloom::zero(), |mut bloom, receipt| {
+        bloom.accrue_bloom(&receipt.log_bloom);
+        bloom
+    });
+    let mut result = json!({
+        "stateRoot": client.state().root(),
+        "txRoot": ordered_trie_root(included.iter().map(|tx| tx.encode())),
+        "receiptsRoot": ordered_trie_root(receipts.iter().map(|receipt| receipt.encode())),
+        "logsHash": keccak(rlp::encode_list::<LogEntry, _>(&logs)),
+        "logsBloom": logs_bloom,
+        "receipts": receipts_json,
+        "rejected": rejected,
+        "currentDifficulty": env_info.difficulty,
+        "gasUsed": env_info.gas_used,
+    });
+    if let Some(base_fee) = env_info.base_fee {
+        result["currentBaseFee"] = json!(base_fee);
+    }
+
+    let mut body = RlpStream::new();
+    SignedTransaction::rlp_append_list(&mut body, &included);
+
+    Ok(Transition {
+        alloc: client.state().to_pod_full().map_err(|e| format!("{}", e))?,
+        result,
+        body: body.out(),
+    })
+}
+
+fn receipt_json(
+    tx: &SignedTransaction,
+    receipt: &TypedReceipt,
+    contract_address: Address,
+    gas_used: U256,
+    transaction_index: usize,
+    first_log_index: usize,
+) -> Value {
+    let (root, status) = match receipt.outcome {
+        TransactionOutcome::StateRoot(root) => (format!("{:?}", root), None),
+        TransactionOutcome::StatusCode(status) => ("0x".to_owned(), Some(status)),
+        TransactionOutcome::Unknown => ("0x".to_owned(), None),
+    };
+    let logs: Vec<Value> = receipt
+        .logs
+        .iter()
+        .enumerate()
+        .map(|(index, log)| {
+            json!({
+                "address": log.address,
+                "topics": log.topics,
+                "data": format!("0x{}", log.data.to_hex()),
+                "transactionHash": tx.hash(),
+                "transactionIndex": format!("{:#x}", transaction_index),
+                "logIndex": format!("{:#x}", first_log_index + index),
+            })
+        })
+        .collect();
+
+    let mut json = json!({
+        "type": format!("{:#x}", tx.tx_type() as u8),
+        "root": root,
+        "cumulativeGasUsed": receipt.gas_used,
+        "logsBloom": receipt.log_bloom,
+        "logs": logs,
+        "transactionHash": tx.hash(),
+        "contractAddress": contract_address,
+        "gasUsed": gas_used,
+        "blockHash": H256::zero(),
+        "transactionIndex": format!("{:#x}", transaction_index),
+    });
+    if let Some(status) = status {
+        json["status"] = json!(format!("{:#x}", status));
+    }
+    json
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethcore::ethereum;
+    use serde_json;
+
+    const SECRET: &str = "0x45a915e4d060149eb4365960e6a7a45f334393093061116b197e3240065ff2d8";
+    const SENDER: &str = "0xa94f5374fce5edbccd0e9f4f9a1fa12d95c4a4d9";
+    const RECIPIENT: &str = "0x1000000000000000000000000000000000000000";
+    const COINBASE: &str = "0x2adc25665018aa1fe0e6bc666dac8fc2697ff9ba";
+
+    fn alloc() -> Alloc {
+        serde_json::from_value(json!({
+            SENDER: { "balance": "0x5ffd4878be161d74", "nonce": "0x0" },
+            RECIPIENT: { "balance": "0x0", "code": "0x", "storage": {} },
+        }))
+        .unwrap()
+    }
+
+    fn env() -> Env {
+        serde_json::from_value(json!({
+            "currentCoinbase": COINBASE,
+            "currentDifficulty": "0x20000",
+            "currentGasLimit": "0x750a163df65e8a",
+            "currentNumber": "0x1",
+            "currentTimestamp": "0x3e8",
+            "currentBaseFee": "0xa",
+            "blockHashes": {
+                "0x0": "0xe268cf0e4a1c1ff21d6b2d3cf3bc55fa1c5e7cb4fa4a4c6b0d0f1f2f3f4f5f6f"
+            },
+        }))
+        .unwrap()
+    }
+
+    fn transfer(nonce: u64) -> Transaction {
+        serde_json::from_value(json!({
+            "gas": "0x5208",
+            "gasPrice": "0xa",
+            "nonce": format!("{:#x}", nonce),
+            "to": RECIPIENT,
+            "value": "0x1",
+            "input": "0x",
+            "secretKey": SECRET,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn should_apply_transactions_and_reject_invalid_ones() {
+        // given
+        let spec = ethereum::new_london_test();
+        let txs = vec![transfer(0), transfer(0), transfer(1)];
+
+        // when
+        let transition = transition(&spec, alloc(), env(), txs, None, 1).unwrap();
+
+        // then
+        let accounts = transition.alloc.get();
+        let recipient: Address = RECIPIENT.parse().unwrap();
+        let sender: Address = SENDER.parse().unwrap();
+        assert_eq!(accounts[&recipient].balance, U256::from(2));
+        assert_eq!(accounts[&sender].nonce, U256::from(2));
+
+        let result = &transition.result;
+        assert_eq!(result["gasUsed"], json!("0xa410"));
+        assert_eq!(result["currentBaseFee"], json!("0xa"));
+        assert_eq!(result["receipts"].as_array().unwrap().len(), 2);
+        assert_eq!(result["receipts"][1]["status"], json!("0x1"));
+        assert_eq!(result["receipts"][1]["cumulativeGasUsed"], json!("0xa410"));
+        assert_eq!(result["rejected"].as_array().unwrap().len(), 1);
+        assert_eq!(result["rejected"][0]["index"], json!(1));
+        assert_eq!(
+            result["stateRoot"],
+            json!(format!("{:?}", transition.alloc.root()))
+        );
+    }
+
+    #[test]
+    fn should_sign_and_verify_transactions_for_chain_id() {
+        // given
+        let spec = ethereum::new_london_test();
+        let other_chain = serde_json::from_value(json!({
+            "type": "0x1",
+            "chainId": "0x1",
+            "gas": "0x5208",
+            "gasPrice": "0xa",
+            "nonce": "0x1",
+            "to": RECIPIENT,
+            "value": "0x1",
+            "input": "0x",
+            "secretKey": SECRET,
+        }))
+        .unwrap();
+        let txs = vec![transfer(0), other_chain];
+
+        // when
+        let transition = transition(&spec, alloc(), env(), txs, None, 5).unwrap();
+
+        // then
+        let result = &transition.result;
+        assert_eq!(result["receipts"].as_array().unwrap().len(), 1);
+        assert_eq!(result["rejected"].as_array().unwrap().len(), 1);
+        assert_eq!(result["rejected"][0]["index"], json!(1));
+    }
+
+    #[test]
+    fn should_pay_block_and_ommer_rewards() {
+        // given
+        let spec = ethereum::new_london_test();
+        let mut env = env();
+        env.ommers = vec![Ommer {
+            delta: 1,
+            address: RECIPIENT.parse::<Address>().unwrap().into(),
+        }];
+
+        // when
+        let transition = transition(&spec, alloc(), env, vec![], Some(U256::from(32)), 1).unwrap();
+
+        // then
+        let accounts = transition.alloc.get();
+        let recipient: Address = RECIPIENT.parse().unwrap();
+        let coinbase: Address = COINBASE.parse().unwrap();
+        assert_eq!(accounts[&recipient].balance, U256::from(28));
+        assert_eq!(accounts[&coinbase].balance, U256::from(33));
+    }
+
+    #[test]
+    fn should_calculate_base_fee_from_parent() {
+        // given
+        let spec = ethereum::new_london_test();
+        let env: Env = serde_json::from_value(json!({
+            "currentCoinbase": COINBASE,
+            "currentDifficulty": "0x20000",
+            "currentGasLimit": "0x1000000",
+            "currentNumber": "0x2",
+            "currentTimestamp": "0x3e8",
+            "parentBaseFee": "0x64",
+            "parentGasUsed": "0x1000000",
+            "parentGasLimit": "0x1000000",
+        }))
+        .unwrap();
+
+        // when
+        let env_info = env.env_info(&spec).unwrap();
+
+        // then
+        assert_eq!(env_info.base_fee, Some(U256::from(112)));
+        assert_eq!(env_info.last_hashes.len(), 2);
+    }
+}
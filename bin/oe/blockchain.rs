@@ -172,7 +172,7 @@ fn execute_import(cmd: ImportBlockchain) -> Result<(), String> {
     let snapshot_path = db_dirs.snapshot_path();
 
     // execute upgrades
-    execute_upgrades(&cmd.dirs.base, &db_dirs, algorithm, &cmd.compaction)?;
+    execute_upgrades(&cmd.dirs.base, &db_dirs, algorithm, &cmd.compaction, false)?;
 
     // create dirs used by parity
     cmd.dirs.create_dirs(false, false)?;
@@ -310,7 +310,7 @@ fn start_client(
     let snapshot_path = db_dirs.snapshot_path();
 
     // execute upgrades
-    execute_upgrades(&dirs.base, &db_dirs, algorithm, &compaction)?;
+    execute_upgrades(&dirs.base, &db_dirs, algorithm, &compaction, false)?;
 
     // create dirs used by OpenEthereum.
     dirs.create_dirs(false, false)?;
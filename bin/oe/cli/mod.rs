@@ -211,6 +211,11 @@ usage! {
                 "<FILE>",
                 "File",
             }
+
+            CMD cmd_tools_openrpc
+            {
+                "Print the OpenRPC document describing the JSON-RPC API",
+            }
         }
 
         CMD cmd_db
@@ -433,6 +438,38 @@ usage! {
             "--jsonrpc-max-payload=[MB]",
             "Specify maximum size for HTTP JSON-RPC requests in megabytes.",
 
+            ARG arg_jsonrpc_jwt_secret: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.jwt_secret.clone(),
+            "--jsonrpc-jwt-secret=[FILE]",
+            "Authenticate HTTP JSON-RPC clients with JSON Web Tokens (HS256) signed with the hex-encoded secret read from FILE. Clients pass the token in the 'Authorization: Bearer' header and can only use the APIs listed in its 'apis' claim (same format as --jsonrpc-apis). Clients without a token can use the APIs from --jsonrpc-apis, clients with an invalid token are rejected.",
+
+            ARG arg_jsonrpc_jwt_public_key: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.jwt_public_key.clone(),
+            "--jsonrpc-jwt-public-key=[FILE]",
+            "Same as --jsonrpc-jwt-secret, but for JSON Web Tokens (ES256) signed with the private counterpart of the PEM-encoded P-256 public key read from FILE.",
+
+            ARG arg_jsonrpc_rate_limit: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.rate_limit,
+            "--jsonrpc-rate-limit=[UNITS]",
+            "Limit each HTTP and WebSockets JSON-RPC client to UNITS request units per second. Most calls cost one unit, expensive ones more (see --jsonrpc-method-costs). Clients are identified by their JSON Web Token subject or the address reported by trusted reverse proxies (see --jsonrpc-trusted-proxies); remaining clients share a single quota.",
+
+            ARG arg_jsonrpc_rate_limit_burst: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.rate_limit_burst,
+            "--jsonrpc-rate-limit-burst=[UNITS]",
+            "Maximal number of request units a JSON-RPC client can accumulate while idle. Defaults to ten times --jsonrpc-rate-limit.",
+
+            ARG arg_jsonrpc_method_costs: (Option<String>) = None, or |c: &Config| c.rpc.as_ref()?.method_costs.as_ref().map(|vec| vec.join(",")),
+            "--jsonrpc-method-costs=[COSTS]",
+            "Override request units charged for JSON-RPC methods, e.g. \"eth_call=5,trace_filter=50\". Costs of eth_getLogs and trace_filter are charged for every 1000 blocks of the queried range.",
+
+            ARG arg_jsonrpc_max_block_range: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.max_block_range,
+            "--jsonrpc-max-block-range=[BLOCKS]",
+            "Reject eth_getLogs and trace_filter calls of rate limited clients querying more than BLOCKS blocks.",
+
+            ARG arg_jsonrpc_trusted_proxies: (usize) = 0usize, or |c: &Config| c.rpc.as_ref()?.trusted_proxies,
+            "--jsonrpc-trusted-proxies=[NUM]",
+            "Number of reverse proxies in front of the HTTP JSON-RPC server. HTTP clients are identified by the address the outermost of them added to the X-Forwarded-For header, which is ignored by default.",
+
+            ARG arg_jsonrpc_slow_query_threshold: (Option<u64>) = None, or |c: &Config| c.rpc.as_ref()?.slow_query_threshold,
+            "--jsonrpc-slow-query-threshold=[MS]",
+            "Log RPC calls taking longer than MS milliseconds, with their parameters and client, to the rpc_slow logging target.",
+
             ARG arg_poll_lifetime: (u32) = 60u32, or |c: &Config| c.rpc.as_ref()?.poll_lifetime.clone(),
             "--poll-lifetime=[S]",
             "Set the RPC filter lifetime to S seconds. The filter has to be polled at least every S seconds , otherwise it is removed.",
@@ -470,6 +507,43 @@ usage! {
             "--ws-max-payload=[MB]",
             "Specify maximum size for WS JSON-RPC requests in megabytes.",
 
+            ARG arg_ws_jwt_secret: (Option<String>) = None, or |c: &Config| c.websockets.as_ref()?.jwt_secret.clone(),
+            "--ws-jwt-secret=[FILE]",
+            "Authenticate WebSockets JSON-RPC clients with JSON Web Tokens (HS256) signed with the hex-encoded secret read from FILE. Clients pass the token as the WebSocket protocol and can only use the APIs listed in its 'apis' claim (same format as --ws-apis). Clients without a token can use the APIs from --ws-apis, clients with an invalid token are rejected.",
+
+            ARG arg_ws_jwt_public_key: (Option<String>) = None, or |c: &Config| c.websockets.as_ref()?.jwt_public_key.clone(),
+            "--ws-jwt-public-key=[FILE]",
+            "Same as --ws-jwt-secret, but for JSON Web Tokens (ES256) signed with the private counterpart of the PEM-encoded P-256 public key read from FILE.",
+
+        ["API and Console Options – GraphQL"]
+            FLAG flag_graphql: (bool) = false, or |c: &Config| c.graphql.as_ref()?.enable.clone(),
+            "--graphql",
+            "Enable the GraphQL server (EIP-1767).",
+
+            ARG arg_graphql_port: (u16) = 8547u16, or |c: &Config| c.graphql.as_ref()?.port.clone(),
+            "--graphql-port=[PORT]",
+            "Specify the port portion of the GraphQL server.",
+
+            ARG arg_graphql_interface: (String) = "local", or |c: &Config| c.graphql.as_ref()?.interface.clone(),
+            "--graphql-interface=[IP]",
+            "Specify the hostname portion of the GraphQL server, IP should be an interface's IP address, or all (all interfaces) or local.",
+
+            ARG arg_graphql_apis: (String) = "eth", or |c: &Config| c.graphql.as_ref()?.apis.as_ref().map(|vec| vec.join(",")),
+            "--graphql-apis=[APIS]",
+            "Specify the APIs available through the GraphQL interface using a comma-delimited list of API names, same as --jsonrpc-apis. The GraphQL schema is served only if eth is enabled, sending transactions additionally requires graphql_mutation.",
+
+            ARG arg_graphql_cors: (String) = "none", or |c: &Config| c.graphql.as_ref()?.cors.as_ref().map(|vec| vec.join(",")),
+            "--graphql-cors=[URL]",
+            "Specify CORS header for GraphQL responses. Special options: \"all\", \"none\".",
+
+            ARG arg_graphql_hosts: (String) = "none", or |c: &Config| c.graphql.as_ref()?.hosts.as_ref().map(|vec| vec.join(",")),
+            "--graphql-hosts=[HOSTS]",
+            "List of allowed Host header values. This option will validate the Host header sent by the browser, it is additional security against some attack vectors. Special options: \"all\", \"none\".",
+
+            ARG arg_graphql_max_payload: (usize) = 5usize, or |c: &Config| c.graphql.as_ref()?.max_payload,
+            "--graphql-max-payload=[MB]",
+            "Specify maximum size for GraphQL requests in megabytes.",
+
         ["Metrics"]
             FLAG flag_metrics: (bool) = false, or |c: &Config| c.metrics.as_ref()?.enable.clone(),
             "--metrics",
@@ -487,6 +561,22 @@ usage! {
             "--metrics-interface=[IP]",
             "Specify the hostname portion of the metrics server, IP should be an interface's IP address, or all (all interfaces) or local.",
 
+            ARG arg_health_min_peers: (usize) = 1usize, or |c: &Config| c.metrics.as_ref()?.health_min_peers.clone(),
+            "--health-min-peers=[NUM]",
+            "Minimum number of connected peers for the /ready endpoint of the metrics server to report the node as ready.",
+
+            ARG arg_health_max_head_age: (u64) = 300u64, or |c: &Config| c.metrics.as_ref()?.health_max_head_age.clone(),
+            "--health-max-head-age=[SECS]",
+            "Maximum age in seconds of the best block for the /ready endpoint of the metrics server to report the node as ready.",
+
+            ARG arg_otlp_endpoint: (Option<String>) = None, or |c: &Config| c.metrics.as_ref()?.otlp_endpoint.clone(),
+            "--otlp-endpoint=[URL]",
+            "Export spans of block import and RPC handling to the OpenTelemetry collector at URL using OTLP/HTTP, e.g. http://127.0.0.1:4318.",
+
+            ARG arg_otlp_service_name: (String) = "openethereum", or |c: &Config| c.metrics.as_ref()?.otlp_service_name.clone(),
+            "--otlp-service-name=[NAME]",
+            "Service name reported with exported spans.",
+
         ["API and Console Options – IPC"]
             FLAG flag_no_ipc: (bool) = false, or |c: &Config| c.ipc.as_ref()?.disable.clone(),
             "--no-ipc",
@@ -694,6 +784,18 @@ usage! {
             "--engine-signer=[ADDRESS]",
             "Specify the address which should be used to sign consensus messages and issue blocks. Relevant only to non-PoW chains.",
 
+            ARG arg_engine_signer_remote: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.engine_signer_remote.clone(),
+            "--engine-signer-remote=[URL]",
+            "Sign consensus messages with the --engine-signer key held by an external Web3Signer-style service instead of the local keystore. URL is either http://HOST:PORT or unix:PATH.",
+
+            ARG arg_engine_signer_remote_timeout: (u64) = 1000u64, or |c: &Config| c.mining.as_ref()?.engine_signer_remote_timeout.clone(),
+            "--engine-signer-remote-timeout=[MS]",
+            "Timeout in milliseconds for a single request to the remote engine signer.",
+
+            ARG arg_engine_signer_remote_retries: (usize) = 2usize, or |c: &Config| c.mining.as_ref()?.engine_signer_remote_retries.clone(),
+            "--engine-signer-remote-retries=[NUM]",
+            "Number of times a failed request to the remote engine signer is retried.",
+
             ARG arg_tx_gas_limit: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_gas_limit.clone(),
             "--tx-gas-limit=[GAS]",
             "Apply a limit of GAS as the maximum amount of gas a single transaction may have for it to be mined.",
@@ -714,6 +816,14 @@ usage! {
             "--stratum-secret=[STRING]",
             "Secret for authorizing Stratum server for peers.",
 
+            ARG arg_stratum_min_difficulty: (u64) = 1u64, or |c: &Config| c.stratum.as_ref()?.min_difficulty.clone(),
+            "--stratum-min-difficulty=[DIFF]",
+            "Lowest share difficulty, in units of 2^32 hashes, assigned to EthereumStratum/1.0.0 workers.",
+
+            ARG arg_stratum_share_interval: (u64) = 15u64, or |c: &Config| c.stratum.as_ref()?.share_interval.clone(),
+            "--stratum-share-interval=[SECS]",
+            "Desired average time between two shares of an EthereumStratum/1.0.0 worker. Share difficulty of each worker is adjusted towards it.",
+
             ARG arg_max_round_blocks_to_import: (usize) = 1usize, or |c: &Config| c.mining.as_ref()?.max_round_blocks_to_import.clone(),
             "--max-round-blocks-to-import=[S]",
             "Maximal number of blocks to import for each import round.",
@@ -769,6 +879,22 @@ usage! {
             "--pruning-memory=[MB]",
             "The ideal amount of memory in megabytes to use to store recent states. As many states as possible will be kept within this limit, and at least --pruning-history states will always be kept.",
 
+            FLAG flag_state_regeneration: (bool) = false, or |c: &Config| c.footprint.as_ref()?.state_regeneration.clone(),
+            "--state-regeneration",
+            "Retain checkpoint states when pruning and regenerate pruned historical state on demand by re-executing blocks from the nearest checkpoint.",
+
+            ARG arg_state_checkpoint_interval: (u64) = 8192u64, or |c: &Config| c.footprint.as_ref()?.state_checkpoint_interval.clone(),
+            "--state-checkpoint-interval=[BLOCKS]",
+            "Number of blocks between retained checkpoint states when --state-regeneration is enabled.",
+
+            ARG arg_state_regeneration_max_blocks: (u64) = 8192u64, or |c: &Config| c.footprint.as_ref()?.state_regeneration_max_blocks.clone(),
+            "--state-regeneration-max-blocks=[BLOCKS]",
+            "Maximum number of blocks re-executed to regenerate a single historical state.",
+
+            ARG arg_state_regeneration_max_memory: (usize) = 512usize, or |c: &Config| c.footprint.as_ref()?.state_regeneration_max_memory.clone(),
+            "--state-regeneration-max-memory=[MB]",
+            "Maximum amount of memory in megabytes used by the changes of re-executed blocks when regenerating a single historical state. At most two states are regenerated at once.",
+
             ARG arg_cache_size_db: (u32) = 128u32, or |c: &Config| c.footprint.as_ref()?.cache_size_db.clone(),
             "--cache-size-db=[MB]",
             "Override database cache size.",
@@ -801,6 +927,22 @@ usage! {
             "--num-verifiers=[INT]",
             "Amount of verifier threads to use or to begin with, if verifier auto-scaling is enabled.",
 
+            FLAG flag_parallel_transactions: (bool) = false, or |c: &Config| c.footprint.as_ref()?.parallel_transactions.clone(),
+            "--parallel-transactions",
+            "Execute transactions of imported blocks optimistically in parallel, re-executing conflicting ones in order.",
+
+            FLAG flag_ethash_precompute: (bool) = false, or |c: &Config| c.footprint.as_ref()?.ethash_precompute.clone(),
+            "--ethash-precompute",
+            "Generate the ethash light cache of the next epoch in background, so that block verification does not stall at epoch boundaries.",
+
+            FLAG flag_ethash_precompute_dag: (bool) = false, or |c: &Config| c.footprint.as_ref()?.ethash_precompute_dag.clone(),
+            "--ethash-precompute-dag",
+            "Also generate the full ethash dataset (DAG) of the next epoch in background. Implies --ethash-precompute.",
+
+            ARG arg_ethash_cache_epochs: (u64) = 2u64, or |c: &Config| c.footprint.as_ref()?.ethash_cache_epochs.clone(),
+            "--ethash-cache-epochs=[NUM]",
+            "Number of most recent epochs whose ethash light caches and datasets are kept on disk.",
+
         ["Import/export Options"]
             FLAG flag_no_seal_check: (bool) = false, or |_| None,
             "--no-seal-check",
@@ -826,6 +968,7 @@ struct Config {
     network: Option<Network>,
     rpc: Option<Rpc>,
     websockets: Option<Ws>,
+    graphql: Option<GraphQL>,
     ipc: Option<Ipc>,
     secretstore: Option<SecretStore>,
     mining: Option<Mining>,
@@ -903,6 +1046,14 @@ struct Rpc {
     experimental_rpcs: Option<bool>,
     poll_lifetime: Option<u32>,
     allow_missing_blocks: Option<bool>,
+    jwt_secret: Option<String>,
+    jwt_public_key: Option<String>,
+    rate_limit: Option<u64>,
+    rate_limit_burst: Option<u64>,
+    method_costs: Option<Vec<String>>,
+    max_block_range: Option<u64>,
+    trusted_proxies: Option<usize>,
+    slow_query_threshold: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -916,6 +1067,20 @@ struct Ws {
     hosts: Option<Vec<String>>,
     max_connections: Option<usize>,
     max_payload: Option<usize>,
+    jwt_secret: Option<String>,
+    jwt_public_key: Option<String>,
+}
+
+#[derive(Default, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GraphQL {
+    enable: Option<bool>,
+    port: Option<u16>,
+    interface: Option<String>,
+    apis: Option<Vec<String>>,
+    cors: Option<Vec<String>>,
+    hosts: Option<Vec<String>>,
+    max_payload: Option<usize>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -933,6 +1098,10 @@ struct Metrics {
     prefix: Option<String>,
     port: Option<u16>,
     interface: Option<String>,
+    health_min_peers: Option<usize>,
+    health_max_head_age: Option<u64>,
+    otlp_endpoint: Option<String>,
+    otlp_service_name: Option<String>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -963,6 +1132,9 @@ struct SecretStore {
 struct Mining {
     author: Option<String>,
     engine_signer: Option<String>,
+    engine_signer_remote: Option<String>,
+    engine_signer_remote_timeout: Option<u64>,
+    engine_signer_remote_retries: Option<usize>,
     force_sealing: Option<bool>,
     reseal_on_uncle: Option<bool>,
     reseal_on_txs: Option<String>,
@@ -1003,6 +1175,8 @@ struct Stratum {
     interface: Option<String>,
     port: Option<u16>,
     secret: Option<String>,
+    min_difficulty: Option<u64>,
+    share_interval: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -1012,6 +1186,10 @@ struct Footprint {
     pruning: Option<String>,
     pruning_history: Option<u64>,
     pruning_memory: Option<usize>,
+    state_regeneration: Option<bool>,
+    state_checkpoint_interval: Option<u64>,
+    state_regeneration_max_blocks: Option<u64>,
+    state_regeneration_max_memory: Option<usize>,
     fast_and_loose: Option<bool>,
     cache_size: Option<u32>,
     cache_size_db: Option<u32>,
@@ -1022,6 +1200,10 @@ struct Footprint {
     fat_db: Option<String>,
     scale_verifiers: Option<bool>,
     num_verifiers: Option<usize>,
+    parallel_transactions: Option<bool>,
+    ethash_precompute: Option<bool>,
+    ethash_precompute_dag: Option<bool>,
+    ethash_cache_epochs: Option<u64>,
 }
 
 #[derive(Default, Debug, PartialEq, Deserialize)]
@@ -1248,6 +1430,7 @@ mod tests {
                 cmd_restore: false,
                 cmd_tools: false,
                 cmd_tools_hash: false,
+                cmd_tools_openrpc: false,
                 cmd_db: false,
                 cmd_db_kill: false,
                 cmd_db_reset: false,
@@ -1327,6 +1510,14 @@ mod tests {
                 arg_jsonrpc_server_threads: None,
                 arg_jsonrpc_threads: 4,
                 arg_jsonrpc_max_payload: None,
+                arg_jsonrpc_jwt_secret: None,
+                arg_jsonrpc_jwt_public_key: None,
+                arg_jsonrpc_rate_limit: None,
+                arg_jsonrpc_rate_limit_burst: None,
+                arg_jsonrpc_method_costs: None,
+                arg_jsonrpc_max_block_range: None,
+                arg_jsonrpc_trusted_proxies: 0usize,
+                arg_jsonrpc_slow_query_threshold: None,
                 arg_poll_lifetime: 60u32,
                 flag_jsonrpc_allow_missing_blocks: false,
 
@@ -1339,6 +1530,17 @@ mod tests {
                 arg_ws_hosts: "none".into(),
                 arg_ws_max_connections: 100,
                 arg_ws_max_payload: 5,
+                arg_ws_jwt_secret: None,
+                arg_ws_jwt_public_key: None,
+
+                // GraphQL
+                flag_graphql: false,
+                arg_graphql_port: 8547u16,
+                arg_graphql_interface: "local".into(),
+                arg_graphql_apis: "eth".into(),
+                arg_graphql_cors: "none".into(),
+                arg_graphql_hosts: "none".into(),
+                arg_graphql_max_payload: 5,
 
                 // IPC
                 flag_no_ipc: false,
@@ -1351,6 +1553,10 @@ mod tests {
                 arg_metrics_prefix: "".into(),
                 arg_metrics_port: 3000u16,
                 arg_metrics_interface: "local".into(),
+                arg_health_min_peers: 1usize,
+                arg_health_max_head_age: 300u64,
+                arg_otlp_endpoint: None,
+                arg_otlp_service_name: "openethereum".into(),
 
                 // SECRETSTORE
                 flag_no_secretstore: false,
@@ -1375,6 +1581,9 @@ mod tests {
                 // -- Sealing/Mining Options
                 arg_author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
                 arg_engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+                arg_engine_signer_remote: Some("http://127.0.0.1:9000".into()),
+                arg_engine_signer_remote_timeout: 1000u64,
+                arg_engine_signer_remote_retries: 2usize,
                 flag_force_sealing: true,
                 arg_reseal_on_txs: "all".into(),
                 arg_reseal_min_period: 4000u64,
@@ -1410,12 +1619,18 @@ mod tests {
                 arg_stratum_interface: "local".to_owned(),
                 arg_stratum_port: 8008u16,
                 arg_stratum_secret: None,
+                arg_stratum_min_difficulty: 1u64,
+                arg_stratum_share_interval: 15u64,
 
                 // -- Footprint Options
                 arg_tracing: "auto".into(),
                 arg_pruning: "auto".into(),
                 arg_pruning_history: 64u64,
                 arg_pruning_memory: 500usize,
+                flag_state_regeneration: false,
+                arg_state_checkpoint_interval: 8192u64,
+                arg_state_regeneration_max_blocks: 8192u64,
+                arg_state_regeneration_max_memory: 512usize,
                 arg_cache_size_db: 64u32,
                 arg_cache_size_blocks: 8u32,
                 arg_cache_size_queue: 50u32,
@@ -1425,6 +1640,10 @@ mod tests {
                 arg_fat_db: "auto".into(),
                 flag_scale_verifiers: true,
                 arg_num_verifiers: Some(6),
+                flag_parallel_transactions: false,
+                flag_ethash_precompute: false,
+                flag_ethash_precompute_dag: false,
+                arg_ethash_cache_epochs: 2u64,
 
                 // -- Import/Export Options
                 arg_export_blocks_from: "1".into(),
@@ -1531,6 +1750,8 @@ mod tests {
                     hosts: None,
                     max_connections: None,
                     max_payload: None,
+                    jwt_secret: None,
+                    jwt_public_key: None,
                 }),
                 rpc: Some(Rpc {
                     disable: Some(true),
@@ -1545,8 +1766,17 @@ mod tests {
                     keep_alive: None,
                     experimental_rpcs: None,
                     poll_lifetime: None,
-                    allow_missing_blocks: None
+                    allow_missing_blocks: None,
+                    jwt_secret: None,
+                    jwt_public_key: None,
+                    rate_limit: None,
+                    rate_limit_burst: None,
+                    method_costs: None,
+                    max_block_range: None,
+                    trusted_proxies: None,
+                    slow_query_threshold: None,
                 }),
+                graphql: None,
                 ipc: Some(Ipc {
                     disable: None,
                     path: None,
@@ -1557,6 +1787,10 @@ mod tests {
                     prefix: Some("oe".to_string()),
                     interface: Some("local".to_string()),
                     port: Some(4000),
+                    health_min_peers: Some(3),
+                    health_max_head_age: Some(60),
+                    otlp_endpoint: Some("http://127.0.0.1:4318".into()),
+                    otlp_service_name: None,
                 }),
                 secretstore: Some(SecretStore {
                     disable: None,
@@ -1581,6 +1815,9 @@ mod tests {
                 mining: Some(Mining {
                     author: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
                     engine_signer: Some("0xdeadbeefcafe0000000000000000000000000001".into()),
+                    engine_signer_remote: None,
+                    engine_signer_remote_timeout: None,
+                    engine_signer_remote_retries: None,
                     force_sealing: Some(true),
                     reseal_on_txs: Some("all".into()),
                     reseal_on_uncle: None,
@@ -1619,6 +1856,10 @@ mod tests {
                     pruning: Some("fast".into()),
                     pruning_history: Some(64),
                     pruning_memory: None,
+                    state_regeneration: None,
+                    state_checkpoint_interval: None,
+                    state_regeneration_max_blocks: None,
+                    state_regeneration_max_memory: None,
                     fast_and_loose: None,
                     cache_size: None,
                     cache_size_db: Some(256),
@@ -1629,6 +1870,10 @@ mod tests {
                     fat_db: Some("off".into()),
                     scale_verifiers: Some(false),
                     num_verifiers: None,
+                    parallel_transactions: None,
+                    ethash_precompute: None,
+                    ethash_precompute_dag: None,
+                    ethash_cache_epochs: None,
                 }),
                 snapshots: Some(Snapshots {
                     enable: Some(false),
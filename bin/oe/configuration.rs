@@ -18,7 +18,7 @@ use crate::{
     bytes::Bytes,
     cli::{Args, ArgsError},
     hash::keccak,
-    metrics::MetricsConfiguration,
+    metrics::{HealthConfiguration, MetricsConfiguration},
     miner::pool,
     sync::{self, validate_node_url, NetworkConfiguration},
 };
@@ -26,9 +26,10 @@ use ansi_term::Colour;
 
 use crypto::publickey::{Public, Secret};
 use ethcore::{
-    client::VMType,
+    client::{StateRegenerationConfig, VMType},
     miner::{stratum, MinerOptions},
     snapshot::SnapshotConfiguration,
+    spec::EthashCacheConfig,
     verification::queue::VerifierSettings,
 };
 use ethereum_types::{Address, H256, U256};
@@ -58,9 +59,11 @@ use crate::{
         to_pending_set, to_price, to_queue_penalization, to_queue_strategy, to_u256,
     },
     network::IpFilter,
-    params::{AccountsConfig, GasPricerConfig, MinerExtras, ResealPolicy, SpecType},
+    params::{
+        AccountsConfig, GasPricerConfig, MinerExtras, RemoteEngineSigner, ResealPolicy, SpecType,
+    },
     presale::ImportWallet,
-    rpc::{HttpConfiguration, IpcConfiguration, WsConfiguration},
+    rpc::{GraphQLConfiguration, HttpConfiguration, IpcConfiguration, JwtKey, WsConfiguration},
     run::RunCmd,
     secretstore::{
         Configuration as SecretStoreConfiguration, ContractAddress as SecretStoreContractAddress,
@@ -75,7 +78,7 @@ use dir::{
     Directories,
 };
 use ethcore_logger::Config as LogConfig;
-use parity_rpc::NetworkSettings;
+use parity_rpc::{rate_limit::RateLimitConfig, NetworkSettings};
 
 const DEFAULT_MAX_PEERS: u16 = 50;
 const DEFAULT_MIN_PEERS: u16 = 25;
@@ -107,6 +110,7 @@ pub enum Cmd {
     },
     Snapshot(SnapshotCommand),
     Hash(Option<String>),
+    OpenRpc,
 }
 
 pub struct Execute {
@@ -155,6 +159,7 @@ impl Configuration {
         let ws_conf = self.ws_config()?;
         let snapshot_conf = self.snapshot_config()?;
         let http_conf = self.http_config()?;
+        let graphql_conf = self.graphql_config()?;
         let ipc_conf = self.ipc_config()?;
         let net_conf = self.net_config()?;
         let network_id = self.network_id();
@@ -167,6 +172,8 @@ impl Configuration {
         let secretstore_conf = self.secretstore_config()?;
         let format = self.format()?;
         let metrics_conf = self.metrics_config()?;
+        let telemetry_conf = self.telemetry_config();
+        let rate_limit = self.rate_limit_config()?;
         let keys_iterations = NonZeroU32::new(self.args.arg_keys_iterations)
             .ok_or_else(|| "--keys-iterations must be non-zero")?;
 
@@ -205,6 +212,8 @@ impl Configuration {
             }
         } else if self.args.cmd_tools && self.args.cmd_tools_hash {
             Cmd::Hash(self.args.arg_tools_hash_file)
+        } else if self.args.cmd_tools && self.args.cmd_tools_openrpc {
+            Cmd::OpenRpc
         } else if self.args.cmd_db && self.args.cmd_db_reset {
             Cmd::Blockchain(BlockchainCmd::Reset(ResetBlockchain {
                 dirs,
@@ -393,11 +402,13 @@ impl Configuration {
 
             let run_cmd = RunCmd {
                 cache_config: cache_config,
+                ethash_cache: self.ethash_cache_config()?,
                 dirs: dirs,
                 spec: spec,
                 pruning: pruning,
                 pruning_history: pruning_history,
                 pruning_memory: self.args.arg_pruning_memory,
+                state_regeneration: self.state_regeneration_config(),
                 daemon: daemon,
                 logger_config: logger_config.clone(),
                 miner_options: self.miner_options()?,
@@ -406,6 +417,7 @@ impl Configuration {
                 ws_conf: ws_conf,
                 snapshot_conf: snapshot_conf,
                 http_conf: http_conf,
+                graphql_conf: graphql_conf,
                 ipc_conf: ipc_conf,
                 net_conf: net_conf,
                 network_id: network_id,
@@ -430,9 +442,16 @@ impl Configuration {
                 download_old_blocks: !self.args.flag_no_ancient_blocks,
                 new_transactions_stats_period: self.args.arg_new_transactions_stats_period,
                 verifier_settings: verifier_settings,
+                parallel_transactions: self.args.flag_parallel_transactions,
                 no_persistent_txqueue: self.args.flag_no_persistent_txqueue,
                 max_round_blocks_to_import: self.args.arg_max_round_blocks_to_import,
                 metrics_conf,
+                telemetry_conf,
+                rate_limit,
+                rpc_slow_query_threshold: self
+                    .args
+                    .arg_jsonrpc_slow_query_threshold
+                    .map(Duration::from_millis),
             };
             Cmd::Run(run_cmd)
         };
@@ -455,6 +474,7 @@ impl Configuration {
             extra_data: self.extra_data()?,
             gas_range_target: (floor, ceil),
             engine_signer: self.engine_signer()?,
+            engine_signer_remote: self.engine_signer_remote()?,
             work_notify: self.work_notify(),
             local_accounts: HashSet::from_iter(
                 to_addresses(&self.args.arg_tx_queue_locals)?.into_iter(),
@@ -472,6 +492,23 @@ impl Configuration {
         to_address(self.args.arg_engine_signer.clone())
     }
 
+    fn engine_signer_remote(&self) -> Result<Option<RemoteEngineSigner>, String> {
+        let endpoint = match self.args.arg_engine_signer_remote {
+            Some(ref endpoint) => endpoint.parse()?,
+            None => return Ok(None),
+        };
+
+        if self.args.arg_engine_signer.is_none() {
+            return Err("--engine-signer-remote requires --engine-signer to be set.".into());
+        }
+
+        Ok(Some(RemoteEngineSigner {
+            endpoint,
+            timeout: Duration::from_millis(self.args.arg_engine_signer_remote_timeout),
+            retries: self.args.arg_engine_signer_remote_retries,
+        }))
+    }
+
     fn format(&self) -> Result<Option<DataFormat>, String> {
         match self
             .args
@@ -485,6 +522,18 @@ impl Configuration {
         }
     }
 
+    fn ethash_cache_config(&self) -> Result<EthashCacheConfig, String> {
+        if self.args.arg_ethash_cache_epochs == 0 {
+            return Err("--ethash-cache-epochs must be greater than 0".into());
+        }
+
+        Ok(EthashCacheConfig {
+            precompute: self.args.flag_ethash_precompute || self.args.flag_ethash_precompute_dag,
+            precompute_dataset: self.args.flag_ethash_precompute_dag,
+            keep_epochs: self.args.arg_ethash_cache_epochs,
+        })
+    }
+
     fn cache_config(&self) -> CacheConfig {
         match self.args.arg_cache_size {
             Some(size) => CacheConfig::new_with_total_cache_size(size),
@@ -574,6 +623,12 @@ impl Configuration {
 
     fn stratum_options(&self) -> Result<Option<stratum::Options>, String> {
         if self.args.flag_stratum {
+            if self.args.arg_stratum_min_difficulty == 0 {
+                return Err("--stratum-min-difficulty must be greater than zero".into());
+            }
+            let min_difficulty = U256::from(self.args.arg_stratum_min_difficulty) << 32;
+            let vardiff = stratum::Vardiff::default();
+
             Ok(Some(stratum::Options {
                 io_path: self.directories().db,
                 listen_addr: self.stratum_interface(),
@@ -583,6 +638,12 @@ impl Configuration {
                     .arg_stratum_secret
                     .as_ref()
                     .map(|s| s.parse::<H256>().unwrap_or_else(|_| keccak(s))),
+                vardiff: stratum::Vardiff {
+                    initial: cmp::max(vardiff.initial, min_difficulty),
+                    min: min_difficulty,
+                    target_interval: Duration::from_secs(self.args.arg_stratum_share_interval),
+                    ..vardiff
+                },
             }))
         } else {
             Ok(None)
@@ -903,6 +964,10 @@ impl Configuration {
         self.hosts(&self.args.arg_ws_hosts, &self.ws_interface())
     }
 
+    fn graphql_hosts(&self) -> Option<Vec<String>> {
+        self.hosts(&self.args.arg_graphql_hosts, &self.graphql_interface())
+    }
+
     fn ws_origins(&self) -> Option<Vec<String>> {
         if self.args.flag_unsafe_expose {
             return None;
@@ -939,6 +1004,12 @@ impl Configuration {
                 _ => 5usize,
             },
             keep_alive: !self.args.flag_jsonrpc_no_keep_alive,
+            jwt: self.jwt_key(
+                "jsonrpc",
+                &self.args.arg_jsonrpc_jwt_secret,
+                &self.args.arg_jsonrpc_jwt_public_key,
+            )?,
+            trusted_proxies: self.args.arg_jsonrpc_trusted_proxies,
         };
 
         Ok(conf)
@@ -960,21 +1031,112 @@ impl Configuration {
             support_token_api,
             max_connections: self.args.arg_ws_max_connections,
             max_payload: self.args.arg_ws_max_payload,
+            jwt: self.jwt_key(
+                "ws",
+                &self.args.arg_ws_jwt_secret,
+                &self.args.arg_ws_jwt_public_key,
+            )?,
         };
 
         Ok(conf)
     }
 
+    fn graphql_config(&self) -> Result<GraphQLConfiguration, String> {
+        let conf = GraphQLConfiguration {
+            enabled: self.args.flag_graphql,
+            interface: self.graphql_interface(),
+            port: self.args.arg_ports_shift + self.args.arg_graphql_port,
+            apis: self.args.arg_graphql_apis.parse()?,
+            cors: Self::cors(&self.args.arg_graphql_cors),
+            hosts: self.graphql_hosts(),
+            server_threads: 1,
+            // queries are always executed off the server thread
+            processing_threads: cmp::max(self.args.arg_jsonrpc_threads, 1),
+            max_payload: match self.args.arg_graphql_max_payload {
+                0 => 5,
+                max => max,
+            },
+        };
+
+        Ok(conf)
+    }
+
+    fn jwt_key(
+        &self,
+        server: &str,
+        secret: &Option<String>,
+        public_key: &Option<String>,
+    ) -> Result<Option<JwtKey>, String> {
+        let base = &self.directories().base;
+        match (secret, public_key) {
+            (Some(_), Some(_)) => Err(format!(
+                "Only one of --{0}-jwt-secret and --{0}-jwt-public-key can be used",
+                server
+            )),
+            (Some(path), None) => Ok(Some(JwtKey::Secret(replace_home(base, path).into()))),
+            (None, Some(path)) => Ok(Some(JwtKey::PublicKey(replace_home(base, path).into()))),
+            (None, None) => Ok(None),
+        }
+    }
+
+    fn rate_limit_config(&self) -> Result<Option<RateLimitConfig>, String> {
+        let rate = match self.args.arg_jsonrpc_rate_limit {
+            Some(0) => return Err("--jsonrpc-rate-limit must be non-zero".into()),
+            Some(rate) => rate,
+            None => return Ok(None),
+        };
+        let mut conf = RateLimitConfig {
+            rate,
+            burst: self
+                .args
+                .arg_jsonrpc_rate_limit_burst
+                .unwrap_or_else(|| rate.saturating_mul(10)),
+            max_block_range: self.args.arg_jsonrpc_max_block_range,
+            ..Default::default()
+        };
+        if conf.burst < rate {
+            return Err(
+                "--jsonrpc-rate-limit-burst must not be lower than --jsonrpc-rate-limit".into(),
+            );
+        }
+        if let Some(ref costs) = self.args.arg_jsonrpc_method_costs {
+            for cost in costs.split(',').filter(|cost| !cost.is_empty()) {
+                let mut parts = cost.splitn(2, '=');
+                match (parts.next(), parts.next().map(str::parse)) {
+                    (Some(method), Some(Ok(cost))) if !method.is_empty() => {
+                        conf.costs.insert(method.trim().to_owned(), cost);
+                    }
+                    _ => return Err(format!("Invalid JSON-RPC method cost: {}", cost)),
+                }
+            }
+        }
+        Ok(Some(conf))
+    }
+
     fn metrics_config(&self) -> Result<MetricsConfiguration, String> {
         let conf = MetricsConfiguration {
             enabled: self.metrics_enabled(),
             prefix: self.metrics_prefix(),
             interface: self.metrics_interface(),
             port: self.args.arg_ports_shift + self.args.arg_metrics_port,
+            health: HealthConfiguration {
+                min_peers: self.args.arg_health_min_peers,
+                max_head_age: self.args.arg_health_max_head_age,
+            },
         };
         Ok(conf)
     }
 
+    fn telemetry_config(&self) -> Option<telemetry::Config> {
+        self.args
+            .arg_otlp_endpoint
+            .clone()
+            .map(|endpoint| telemetry::Config {
+                endpoint,
+                service_name: self.args.arg_otlp_service_name.clone(),
+            })
+    }
+
     fn snapshot_config(&self) -> Result<SnapshotConfiguration, String> {
         let conf = SnapshotConfiguration {
             enable: self.args.flag_enable_snapshotting,
@@ -987,6 +1149,15 @@ impl Configuration {
         Ok(conf)
     }
 
+    fn state_regeneration_config(&self) -> StateRegenerationConfig {
+        StateRegenerationConfig {
+            enabled: self.args.flag_state_regeneration,
+            checkpoint_interval: self.args.arg_state_checkpoint_interval,
+            max_blocks: self.args.arg_state_regeneration_max_blocks,
+            max_memory: self.args.arg_state_regeneration_max_memory * 1024 * 1024,
+        }
+    }
+
     fn network_settings(&self) -> Result<NetworkSettings, String> {
         let http_conf = self.http_config()?;
         let net_addresses = self.net_addresses()?;
@@ -1070,6 +1241,10 @@ impl Configuration {
         self.interface(&self.args.arg_ws_interface)
     }
 
+    fn graphql_interface(&self) -> String {
+        self.interface(&self.args.arg_graphql_interface)
+    }
+
     fn metrics_interface(&self) -> String {
         self.interface(&self.args.arg_metrics_interface)
     }
@@ -1477,6 +1652,13 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_command_tools_openrpc() {
+        let args = vec!["openethereum", "tools", "openrpc"];
+        let conf = parse(&args);
+        assert_eq!(conf.into_command().unwrap().cmd, Cmd::OpenRpc);
+    }
+
     #[test]
     fn test_command_signer_new_token() {
         let args = vec!["openethereum", "signer", "new-token"];
@@ -1524,6 +1706,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rpc_jwt_keys() {
+        let conf = parse(&["openethereum", "--jsonrpc-jwt-secret", "/tmp/jwt.hex"]);
+        assert_eq!(
+            conf.http_config().unwrap().jwt,
+            Some(JwtKey::Secret("/tmp/jwt.hex".into()))
+        );
+        assert_eq!(conf.ws_config().unwrap().jwt, None);
+
+        let conf = parse(&["openethereum", "--ws-jwt-public-key", "/tmp/jwt.pem"]);
+        assert_eq!(
+            conf.ws_config().unwrap().jwt,
+            Some(JwtKey::PublicKey("/tmp/jwt.pem".into()))
+        );
+
+        let conf = parse(&[
+            "openethereum",
+            "--ws-jwt-secret",
+            "/tmp/jwt.hex",
+            "--ws-jwt-public-key",
+            "/tmp/jwt.pem",
+        ]);
+        assert!(conf.ws_config().is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_config() {
+        assert_eq!(parse(&["openethereum"]).rate_limit_config(), Ok(None));
+
+        let conf = parse(&[
+            "openethereum",
+            "--jsonrpc-rate-limit",
+            "50",
+            "--jsonrpc-method-costs",
+            "eth_call=2,trace_filter=100",
+            "--jsonrpc-max-block-range",
+            "5000",
+        ]);
+        let conf = conf.rate_limit_config().unwrap().unwrap();
+        assert_eq!(conf.rate, 50);
+        assert_eq!(conf.burst, 500);
+        assert_eq!(conf.costs["eth_call"], 2);
+        assert_eq!(conf.costs["trace_filter"], 100);
+        assert_eq!(conf.costs["eth_getLogs"], 10);
+        assert_eq!(conf.max_block_range, Some(5000));
+
+        let conf = parse(&[
+            "openethereum",
+            "--jsonrpc-rate-limit",
+            "50",
+            "--jsonrpc-method-costs",
+            "eth_call",
+        ]);
+        assert!(conf.rate_limit_config().is_err());
+
+        let conf = parse(&["openethereum", "--jsonrpc-trusted-proxies", "2"]);
+        assert_eq!(conf.http_config().unwrap().trusted_proxies, 2);
+    }
+
+    #[test]
+    fn test_graphql_config() {
+        let conf = parse(&["openethereum"]).graphql_config().unwrap();
+        assert_eq!(conf, Default::default());
+
+        let conf = parse(&[
+            "openethereum",
+            "--graphql",
+            "--graphql-port",
+            "9000",
+            "--graphql-cors",
+            "all",
+        ]);
+        let conf = conf.graphql_config().unwrap();
+        assert!(conf.enabled);
+        assert_eq!(conf.port, 9000);
+        assert_eq!(conf.cors, None);
+
+        let conf = parse(&["openethereum", "--graphql-apis", "eth,foo"]);
+        assert!(conf.graphql_config().is_err());
+    }
+
     #[test]
     fn test_run_cmd() {
         let args = vec!["openethereum"];
@@ -1531,11 +1794,13 @@ mod tests {
         let mut expected = RunCmd {
             allow_missing_blocks: false,
             cache_config: Default::default(),
+            ethash_cache: Default::default(),
             dirs: Default::default(),
             spec: Default::default(),
             pruning: Default::default(),
             pruning_history: 64,
             pruning_memory: 32,
+            state_regeneration: Default::default(),
             daemon: None,
             logger_config: Default::default(),
             miner_options: Default::default(),
@@ -1543,6 +1808,7 @@ mod tests {
             poll_lifetime: 60,
             ws_conf: Default::default(),
             http_conf: Default::default(),
+            graphql_conf: Default::default(),
             ipc_conf: Default::default(),
             net_conf: default_network_config(),
             network_id: None,
@@ -1567,9 +1833,13 @@ mod tests {
             download_old_blocks: true,
             new_transactions_stats_period: 0,
             verifier_settings: Default::default(),
+            parallel_transactions: false,
             no_persistent_txqueue: false,
             max_round_blocks_to_import: 1,
             metrics_conf: MetricsConfiguration::default(),
+            telemetry_conf: None,
+            rate_limit: None,
+            rpc_slow_query_threshold: None,
         };
         expected.secretstore_conf.enabled = cfg!(feature = "secretstore");
         expected.secretstore_conf.http_enabled = cfg!(feature = "secretstore");
@@ -1899,6 +2169,8 @@ mod tests {
         assert_eq!(&conf0.ws_config().unwrap().interface, "0.0.0.0");
         assert_eq!(conf0.ws_config().unwrap().hosts, None);
         assert_eq!(conf0.ws_config().unwrap().origins, None);
+        assert_eq!(&conf0.graphql_config().unwrap().interface, "0.0.0.0");
+        assert_eq!(conf0.graphql_config().unwrap().hosts, None);
         assert_eq!(&conf0.secretstore_config().unwrap().interface, "0.0.0.0");
         assert_eq!(
             &conf0.secretstore_config().unwrap().http_interface,
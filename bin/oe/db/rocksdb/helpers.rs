@@ -14,9 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::kvdb_rocksdb::{CompactionProfile, DatabaseConfig};
+use super::{
+    kvdb_rocksdb::{CompactionProfile, DatabaseConfig},
+    migration::client_db_columns,
+};
 use ethcore::client::{ClientConfig, DatabaseCompactionProfile};
-use ethcore_db::NUM_COLUMNS;
 use std::path::Path;
 
 pub fn compaction_profile(
@@ -31,7 +33,9 @@ pub fn compaction_profile(
 }
 
 pub fn client_db_config(client_path: &Path, client_config: &ClientConfig) -> DatabaseConfig {
-    let mut client_db_config = DatabaseConfig::with_columns(NUM_COLUMNS);
+    // the version file is kept next to the database.
+    let version_path = client_path.parent().unwrap_or(client_path);
+    let mut client_db_config = DatabaseConfig::with_columns(client_db_columns(version_path));
 
     client_db_config.memory_budget = client_config.db_cache_size;
     client_db_config.compaction = compaction_profile(&client_config.db_compaction, &client_path);
@@ -44,10 +44,21 @@ pub const TO_V12: ChangeColumns = ChangeColumns {
     version: 12,
 };
 
+/// The migration from v16 to v17.
+/// Adds a column for checkpoint states. It is only executed once state regeneration
+/// is enabled, the column is kept if it is disabled later on.
+pub const TO_V17: ChangeColumns = ChangeColumns {
+    pre_columns: Some(7),
+    post_columns: Some(8),
+    version: 17,
+};
+
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
 const CURRENT_VERSION: u32 = 16;
+/// Version of databases with a column for checkpoint states.
+const CHECKPOINTS_VERSION: u32 = 17;
 /// Until this version please use upgrade tool.
 const USE_MIGRATION_TOOL: u32 = 15;
 /// A version of database at which blooms-db was introduced
@@ -118,15 +129,23 @@ fn current_version(path: &Path) -> Result<u32, Error> {
     }
 }
 
-/// Writes current database version to the file.
+/// Writes given database version to the file.
 /// Creates a new file if the version file does not exist yet.
-fn update_version(path: &Path) -> Result<(), Error> {
+fn update_version(path: &Path, version: u32) -> Result<(), Error> {
     fs::create_dir_all(path)?;
     let mut file = fs::File::create(version_file_path(path))?;
-    file.write_all(format!("{}", CURRENT_VERSION).as_bytes())?;
+    file.write_all(format!("{}", version).as_bytes())?;
     Ok(())
 }
 
+/// Number of columns of the client database whose version file is at given path.
+pub fn client_db_columns(path: &Path) -> Option<u32> {
+    match current_version(path) {
+        Ok(CHECKPOINTS_VERSION) => ethcore_db::NUM_COLUMNS,
+        _ => ethcore_db::NUM_COLUMNS_WITHOUT_CHECKPOINTS,
+    }
+}
+
 /// Consolidated database path
 fn consolidated_database_path(path: &Path) -> PathBuf {
     let mut state_path = path.to_owned();
@@ -153,6 +172,7 @@ pub fn default_migration_settings(compaction_profile: &CompactionProfile) -> Mig
 /// Migrations on the consolidated database.
 fn consolidated_database_migrations(
     compaction_profile: &CompactionProfile,
+    version: u32,
 ) -> Result<MigrationManager, Error> {
     let mut manager = MigrationManager::new(default_migration_settings(compaction_profile));
     manager
@@ -161,6 +181,11 @@ fn consolidated_database_migrations(
     manager
         .add_migration(TO_V12)
         .map_err(|_| Error::MigrationImpossible)?;
+    if version >= CHECKPOINTS_VERSION {
+        manager
+            .add_migration(TO_V17)
+            .map_err(|_| Error::MigrationImpossible)?;
+    }
     Ok(manager)
 }
 
@@ -206,8 +231,13 @@ fn exists(path: &Path) -> bool {
     fs::metadata(path).is_ok()
 }
 
-/// Migrates the database.
-pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> Result<(), Error> {
+/// Migrates the database. The column for checkpoint states is added if `state_checkpoints`
+/// is set.
+pub fn migrate(
+    path: &Path,
+    compaction_profile: &DatabaseCompactionProfile,
+    state_checkpoints: bool,
+) -> Result<(), Error> {
     let compaction_profile = helpers::compaction_profile(&compaction_profile, path);
 
     // read version file.
@@ -215,12 +245,18 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
 
     // migrate the databases.
     // main db directory may already exists, so let's check if we have blocks dir
-    if version > CURRENT_VERSION {
+    if version > CHECKPOINTS_VERSION {
         return Err(Error::FutureDBVersion);
     }
 
+    // checkpoint states are kept once the column was added.
+    let target_version = match state_checkpoints || version == CHECKPOINTS_VERSION {
+        true => CHECKPOINTS_VERSION,
+        false => CURRENT_VERSION,
+    };
+
     // We are in the latest version, yay!
-    if version == CURRENT_VERSION {
+    if version == target_version {
         return Ok(());
     }
 
@@ -231,15 +267,15 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
     let db_path = consolidated_database_path(path);
 
     // Further migrations
-    if version < CURRENT_VERSION && exists(&db_path) {
+    if version < target_version && exists(&db_path) {
         println!(
             "Migrating database from version {} to {}",
-            version, CURRENT_VERSION
+            version, target_version
         );
         migrate_database(
             version,
             &db_path,
-            consolidated_database_migrations(&compaction_profile)?,
+            consolidated_database_migrations(&compaction_profile, target_version)?,
         )?;
 
         if version < BLOOMS_DB_VERSION {
@@ -248,7 +284,7 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
                 max_open_files: 64,
                 memory_budget: None,
                 compaction: compaction_profile,
-                columns: ethcore_db::NUM_COLUMNS,
+                columns: ethcore_db::NUM_COLUMNS_WITHOUT_CHECKPOINTS,
             };
 
             migrate_blooms(&db_path, &db_config).map_err(Error::BloomsDB)?;
@@ -258,5 +294,5 @@ pub fn migrate(path: &Path, compaction_profile: &DatabaseCompactionProfile) -> R
     }
 
     // update version file.
-    update_version(path)
+    update_version(path, target_version)
 }
@@ -309,6 +309,7 @@ pub fn execute_upgrades(
     dirs: &DatabaseDirectories,
     pruning: Algorithm,
     compaction_profile: &DatabaseCompactionProfile,
+    state_checkpoints: bool,
 ) -> Result<(), String> {
     upgrade_data_paths(base_path, dirs, pruning);
 
@@ -323,7 +324,7 @@ pub fn execute_upgrades(
     }
 
     let client_path = dirs.db_path(pruning);
-    migrate(&client_path, compaction_profile).map_err(|e| format!("{}", e))
+    migrate(&client_path, compaction_profile, state_checkpoints).map_err(|e| format!("{}", e))
 }
 
 /// Prompts user asking for password.
@@ -69,6 +69,7 @@ extern crate parity_runtime;
 extern crate parity_version;
 extern crate prometheus;
 extern crate stats;
+extern crate telemetry;
 
 extern crate rpc_servers;
 
@@ -199,6 +200,9 @@ fn execute(command: Execute, logger: Arc<RotatingLogger>) -> Result<ExecutionAct
         Cmd::Hash(maybe_file) => {
             print_hash_of(maybe_file).map(|s| ExecutionAction::Instant(Some(s)))
         }
+        Cmd::OpenRpc => serde_json::to_string_pretty(&parity_rpc::openrpc::document())
+            .map(|s| ExecutionAction::Instant(Some(s)))
+            .map_err(|e| format!("Unable to serialize OpenRPC document: {}", e)),
         Cmd::Account(account_cmd) => {
             account::execute(account_cmd).map(|s| ExecutionAction::Instant(Some(s)))
         }
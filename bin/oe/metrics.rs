@@ -1,10 +1,19 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    sync::Arc,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
 
 use crate::{futures::Future, rpc, rpc_apis};
 
+use ethcore::client::{BlockChainClient, ChainInfo};
+use parity_rpc::is_major_importing;
 use parking_lot::Mutex;
 
-use hyper::{service::service_fn_ok, Body, Method, Request, Response, Server, StatusCode};
+use hyper::{
+    header::{HeaderValue, CONTENT_TYPE},
+    service::service_fn_ok,
+    Body, Method, Request, Response, Server, StatusCode,
+};
 
 use stats::{
     prometheus::{self, Encoder},
@@ -21,6 +30,26 @@ pub struct MetricsConfiguration {
     pub interface: String,
     /// The network port (default is 3000).
     pub port: u16,
+    /// Readiness thresholds of the health endpoints.
+    pub health: HealthConfiguration,
+}
+
+/// Thresholds below which `/ready` reports the node as not ready.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthConfiguration {
+    /// Minimum number of connected peers (default is 1).
+    pub min_peers: usize,
+    /// Maximum age of the best block in seconds (default is 300).
+    pub max_head_age: u64,
+}
+
+impl Default for HealthConfiguration {
+    fn default() -> Self {
+        HealthConfiguration {
+            min_peers: 1,
+            max_head_age: 300,
+        }
+    }
 }
 
 impl Default for MetricsConfiguration {
@@ -30,12 +59,78 @@ impl Default for MetricsConfiguration {
             prefix: "".into(),
             interface: "127.0.0.1".into(),
             port: 3000,
+            health: Default::default(),
         }
     }
 }
 
+/// Snapshot of the node state relevant to load balancers.
+#[derive(Debug, Clone, PartialEq)]
+struct Health {
+    syncing: bool,
+    peers: usize,
+    head_age: u64,
+}
+
+impl Health {
+    fn collect(deps: &rpc_apis::FullDependencies) -> Self {
+        let status = deps.sync.status();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Health {
+            syncing: is_major_importing(Some(status.state), deps.client.queue_info()),
+            peers: status.num_peers,
+            head_age: now.saturating_sub(deps.client.chain_info().best_block_timestamp),
+        }
+    }
+
+    /// Reasons for the node not to serve traffic, empty if it is ready.
+    fn unready_reasons(&self, conf: &HealthConfiguration) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if self.syncing {
+            reasons.push("major sync in progress".to_owned());
+        }
+        if self.peers < conf.min_peers {
+            reasons.push(format!(
+                "{} peers connected, {} required",
+                self.peers, conf.min_peers
+            ));
+        }
+        if self.head_age > conf.max_head_age {
+            reasons.push(format!(
+                "best block is {}s old, at most {}s allowed",
+                self.head_age, conf.max_head_age
+            ));
+        }
+        reasons
+    }
+
+    /// JSON response of the health endpoints. Only readiness checks fail with 503.
+    fn response(&self, conf: &HealthConfiguration, readiness: bool) -> Response<Body> {
+        let reasons = self.unready_reasons(conf);
+        let body = serde_json::json!({
+            "ready": reasons.is_empty(),
+            "syncing": self.syncing,
+            "peers": self.peers,
+            "headAge": self.head_age,
+            "reasons": reasons,
+        });
+
+        let mut res = Response::new(Body::from(body.to_string()));
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        if readiness && !reasons.is_empty() {
+            *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        }
+        res
+    }
+}
+
 struct State {
     rpc_apis: Arc<rpc_apis::FullDependencies>,
+    collectors: Vec<Box<dyn PrometheusMetrics + Send + Sync>>,
 }
 
 fn handle_request(
@@ -52,6 +147,9 @@ fn handle_request(
             let state = state.lock();
             state.rpc_apis.client.prometheus_metrics(&mut reg);
             state.rpc_apis.sync.prometheus_metrics(&mut reg);
+            for collector in &state.collectors {
+                collector.prometheus_metrics(&mut reg);
+            }
             let elapsed = start.elapsed();
             reg.register_gauge(
                 "metrics_time",
@@ -70,6 +168,12 @@ fn handle_request(
 
             Response::new(Body::from(text))
         }
+        (Method::GET, "/health") => {
+            Health::collect(&state.lock().rpc_apis).response(&conf.health, false)
+        }
+        (Method::GET, "/ready") => {
+            Health::collect(&state.lock().rpc_apis).response(&conf.health, true)
+        }
         (_, _) => {
             let mut res = Response::new(Body::from("not found"));
             *res.status_mut() = StatusCode::NOT_FOUND;
@@ -79,9 +183,13 @@ fn handle_request(
 }
 
 /// Start the prometheus metrics server accessible via GET <host>:<port>/metrics
+///
+/// Metrics of `collectors` are exported next to the client and sync ones.
+/// The server also answers liveness (`/health`) and readiness (`/ready`) probes.
 pub fn start_prometheus_metrics(
     conf: &MetricsConfiguration,
     deps: &rpc::Dependencies<rpc_apis::FullDependencies>,
+    collectors: Vec<Box<dyn PrometheusMetrics + Send + Sync>>,
 ) -> Result<(), String> {
     if !conf.enabled {
         return Ok(());
@@ -94,6 +202,7 @@ pub fn start_prometheus_metrics(
 
     let state = State {
         rpc_apis: deps.apis.clone(),
+        collectors,
     };
     let state = Arc::new(Mutex::new(state));
     let conf = Arc::new(conf.to_owned());
@@ -115,3 +224,37 @@ pub fn start_prometheus_metrics(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Health, HealthConfiguration};
+    use hyper::StatusCode;
+
+    #[test]
+    fn reports_readiness() {
+        let conf = HealthConfiguration {
+            min_peers: 2,
+            max_head_age: 60,
+        };
+        let healthy = Health {
+            syncing: false,
+            peers: 5,
+            head_age: 10,
+        };
+        assert!(healthy.unready_reasons(&conf).is_empty());
+        assert_eq!(healthy.response(&conf, true).status(), StatusCode::OK);
+
+        let lagging = Health {
+            syncing: true,
+            peers: 1,
+            head_age: 120,
+        };
+        assert_eq!(lagging.unready_reasons(&conf).len(), 3);
+        assert_eq!(
+            lagging.response(&conf, true).status(),
+            StatusCode::SERVICE_UNAVAILABLE
+        );
+        // liveness does not depend on sync state.
+        assert_eq!(lagging.response(&conf, false).status(), StatusCode::OK);
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{collections::HashSet, fmt, fs, num::NonZeroU32, str, time::Duration};
+use std::{collections::HashSet, fmt, num::NonZeroU32, path::Path, str, time::Duration};
 
 use crate::{
     miner::{
@@ -25,6 +25,7 @@ use crate::{
 };
 use ethcore::{
     client::Mode,
+    engines::RemoteSignerEndpoint,
     ethereum,
     spec::{Spec, SpecParams},
 };
@@ -138,12 +139,7 @@ impl SpecType {
             SpecType::Sokol => Ok(ethereum::new_sokol(params)),
             SpecType::Yolo3 => Ok(ethereum::new_yolo3(params)),
             SpecType::Dev => Ok(Spec::new_instant()),
-            SpecType::Custom(ref filename) => {
-                let file = fs::File::open(filename).map_err(|e| {
-                    format!("Could not load specification file at {}: {}", filename, e)
-                })?;
-                Spec::load(params, file)
-            }
+            SpecType::Custom(ref filename) => Spec::load_file(params, Path::new(filename)),
         }
     }
 
@@ -287,10 +283,19 @@ impl GasPricerConfig {
     }
 }
 
+/// External service holding the engine signer key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteEngineSigner {
+    pub endpoint: RemoteSignerEndpoint,
+    pub timeout: Duration,
+    pub retries: usize,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct MinerExtras {
     pub author: Address,
     pub engine_signer: Address,
+    pub engine_signer_remote: Option<RemoteEngineSigner>,
     pub extra_data: Vec<u8>,
     pub gas_range_target: (U256, U256),
     pub work_notify: Vec<String>,
@@ -302,6 +307,7 @@ impl Default for MinerExtras {
         MinerExtras {
             author: Default::default(),
             engine_signer: Default::default(),
+            engine_signer_remote: None,
             extra_data: version_data(),
             gas_range_target: (8_000_000.into(), 10_000_000.into()),
             work_notify: Default::default(),
@@ -14,20 +14,31 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use std::{collections::HashSet, io, path::PathBuf, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io, iter,
+    path::PathBuf,
+    sync::Arc,
+};
 
 use crate::{
     helpers::parity_ipc_path,
-    rpc_apis::{self, ApiSet},
+    rpc_apis::{self, Api, ApiSet},
 };
 use dir::{default_data_path, helpers::replace_home};
-use jsonrpc_core::MetaIoHandler;
+use jsonrpc_core::{
+    self as core,
+    futures::future::{self, Either},
+    MetaIoHandler,
+};
 use parity_rpc::{
     self as rpc,
     informant::{Middleware, RpcStats},
+    rate_limit::RateLimiter,
     DomainsValidation, Metadata,
 };
 use parity_runtime::Executor;
+use rustc_hex::FromHex;
 
 pub use parity_rpc::{HttpServer, IpcServer, RequestMiddleware};
 //pub use parity_rpc::ws::Server as WsServer;
@@ -35,6 +46,49 @@ pub use parity_rpc::ws::{ws, Server as WsServer};
 
 pub const DAPPS_DOMAIN: &'static str = "web3.site";
 
+/// Error code returned for calls not permitted by the client's access token.
+const PERMISSION_DENIED: i64 = -32090;
+/// Path of the HTTP health check and the method serving it.
+const HEALTH_API: (&'static str, &'static str) = ("/api/health", "parity_nodeStatus");
+/// Minimal length of HS256 shared secret in bytes.
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// Key verifying JSON Web Tokens of RPC clients.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JwtKey {
+    /// File with hex-encoded HS256 shared secret.
+    Secret(PathBuf),
+    /// File with PEM-encoded ES256 public key.
+    PublicKey(PathBuf),
+}
+
+impl JwtKey {
+    fn load(&self) -> Result<Arc<rpc::JwtAuth>, String> {
+        let read = |path: &PathBuf| {
+            fs::read(path).map_err(|e| format!("Unable to read JWT key {}: {}", path.display(), e))
+        };
+
+        let auth = match *self {
+            JwtKey::Secret(ref path) => {
+                let secret = String::from_utf8(read(path)?)
+                    .ok()
+                    .and_then(|hex| hex.trim().trim_start_matches("0x").from_hex().ok())
+                    .ok_or_else(|| format!("Invalid hex JWT secret in {}", path.display()))?;
+                if secret.len() < MIN_JWT_SECRET_LEN {
+                    return Err(format!(
+                        "JWT secret in {} is too short, expected at least {} bytes",
+                        path.display(),
+                        MIN_JWT_SECRET_LEN
+                    ));
+                }
+                rpc::JwtAuth::hs256(&secret)
+            }
+            JwtKey::PublicKey(ref path) => rpc::JwtAuth::es256(&read(path)?)?,
+        };
+        Ok(Arc::new(auth))
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct HttpConfiguration {
     pub enabled: bool,
@@ -47,6 +101,8 @@ pub struct HttpConfiguration {
     pub processing_threads: usize,
     pub max_payload: usize,
     pub keep_alive: bool,
+    pub jwt: Option<JwtKey>,
+    pub trusted_proxies: usize,
 }
 
 impl Default for HttpConfiguration {
@@ -62,6 +118,37 @@ impl Default for HttpConfiguration {
             processing_threads: 4,
             max_payload: 5,
             keep_alive: true,
+            jwt: None,
+            trusted_proxies: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphQLConfiguration {
+    pub enabled: bool,
+    pub interface: String,
+    pub port: u16,
+    pub apis: ApiSet,
+    pub cors: Option<Vec<String>>,
+    pub hosts: Option<Vec<String>>,
+    pub server_threads: usize,
+    pub processing_threads: usize,
+    pub max_payload: usize,
+}
+
+impl Default for GraphQLConfiguration {
+    fn default() -> Self {
+        GraphQLConfiguration {
+            enabled: false,
+            interface: "127.0.0.1".into(),
+            port: 8547,
+            apis: ApiSet::List(iter::once(Api::Eth).collect()),
+            cors: Some(vec![]),
+            hosts: Some(vec![]),
+            server_threads: 1,
+            processing_threads: 4,
+            max_payload: 5,
         }
     }
 }
@@ -100,6 +187,7 @@ pub struct WsConfiguration {
     pub signer_path: PathBuf,
     pub support_token_api: bool,
     pub max_payload: usize,
+    pub jwt: Option<JwtKey>,
 }
 
 impl Default for WsConfiguration {
@@ -120,6 +208,7 @@ impl Default for WsConfiguration {
             signer_path: replace_home(&data_dir, "$BASE/signer").into(),
             support_token_api: true,
             max_payload: 5,
+            jwt: None,
         }
     }
 }
@@ -150,6 +239,7 @@ pub struct Dependencies<D: rpc_apis::Dependencies> {
     pub apis: Arc<D>,
     pub executor: Executor,
     pub stats: Arc<RpcStats>,
+    pub rate_limiter: RateLimiter,
 }
 
 pub fn new_ws<D: rpc_apis::Dependencies>(
@@ -166,14 +256,20 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         .parse()
         .map_err(|_| format!("Invalid WebSockets listen host/port given: {}", url))?;
 
+    let jwt = match conf.jwt {
+        Some(ref key) => Some(key.load()?),
+        None => None,
+    };
     let full_handler = setup_apis(rpc_apis::ApiSet::All, deps);
     let handler = {
+        let (permissions, methods) = remote_apis(&conf.apis, jwt.is_some(), deps);
         let mut handler = MetaIoHandler::with_middleware((
             rpc::WsDispatcher::new(full_handler),
+            permissions,
+            deps.rate_limiter.clone(),
             Middleware::new(deps.stats.clone(), deps.apis.activity_notifier()),
         ));
-        let apis = conf.apis.list_apis();
-        deps.apis.extend_with_set(&mut handler, &apis);
+        handler.extend_with(methods);
 
         handler
     };
@@ -195,8 +291,8 @@ pub fn new_ws<D: rpc_apis::Dependencies>(
         allowed_origins,
         allowed_hosts,
         conf.max_connections,
-        rpc::WsExtractor::new(path.clone()),
-        rpc::WsExtractor::new(path.clone()),
+        rpc::WsExtractor::new(path.clone()).with_jwt(jwt.clone()),
+        rpc::WsExtractor::new(path.clone()).with_jwt(jwt),
         rpc::WsStats::new(deps.stats.clone()),
         conf.max_payload,
     );
@@ -234,30 +330,85 @@ pub fn new_http<D: rpc_apis::Dependencies>(
     let addr = url
         .parse()
         .map_err(|_| format!("Invalid {} listen host/port given: {}", id, url))?;
-    let handler = setup_apis(conf.apis, deps);
+    let cors_domains = into_domains(conf.cors);
+    let allowed_hosts = into_domains(with_domain(conf.hosts, domain, &Some(url.clone().into())));
+    let health_api = Some(HEALTH_API);
+
+    let start_result = match conf.jwt {
+        Some(ref key) => {
+            let auth = key.load()?;
+            rpc_servers::start_http_with_middleware(
+                &addr,
+                cors_domains,
+                allowed_hosts,
+                health_api,
+                setup_remote_apis(&conf.apis, true, deps),
+                rpc::JwtExtractor::new(auth.clone()).with_trusted_proxies(conf.trusted_proxies),
+                rpc::JwtExtractor::new(auth),
+                conf.server_threads,
+                conf.max_payload,
+                conf.keep_alive,
+            )
+        }
+        None => rpc_servers::start_http(
+            &addr,
+            cors_domains,
+            allowed_hosts,
+            health_api,
+            setup_remote_apis(&conf.apis, false, deps),
+            rpc::RpcExtractor::default().with_trusted_proxies(conf.trusted_proxies),
+            conf.server_threads,
+            conf.max_payload,
+            conf.keep_alive,
+        ),
+    };
+
+    match start_result {
+		Ok(server) => Ok(Some(server)),
+		Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => Err(
+			format!("{} address {} is already in use, make sure that another instance of an Ethereum client is not running or change the address using the --{}-port and --{}-interface options.", id, url, options, options)
+		),
+		Err(e) => Err(format!("{} error: {:?}", id, e)),
+	}
+}
 
+pub fn new_graphql<D: rpc_apis::Dependencies>(
+    conf: GraphQLConfiguration,
+    deps: &Dependencies<D>,
+) -> Result<Option<HttpServer>, String> {
+    if !conf.enabled {
+        return Ok(None);
+    }
+    if !conf.apis.list_apis().contains(&Api::Eth) {
+        return Err("GraphQL requires the eth API to be enabled, add it to --graphql-apis.".into());
+    }
+
+    let domain = DAPPS_DOMAIN;
+    let url = format!("{}:{}", conf.interface, conf.port);
+    let addr = url
+        .parse()
+        .map_err(|_| format!("Invalid GraphQL listen host/port given: {}", url))?;
     let cors_domains = into_domains(conf.cors);
     let allowed_hosts = into_domains(with_domain(conf.hosts, domain, &Some(url.clone().into())));
-    let health_api = Some(("/api/health", "parity_nodeStatus"));
 
-    let start_result = rpc_servers::start_http(
+    let start_result = rpc_servers::start_graphql(
         &addr,
         cors_domains,
         allowed_hosts,
-        health_api,
-        handler,
-        rpc::RpcExtractor,
+        deps.apis
+            .graphql()
+            .with_mutations(conf.apis.list_apis().contains(&Api::GraphQLMutation)),
         conf.server_threads,
+        conf.processing_threads,
         conf.max_payload,
-        conf.keep_alive,
     );
 
     match start_result {
 		Ok(server) => Ok(Some(server)),
 		Err(ref err) if err.kind() == io::ErrorKind::AddrInUse => Err(
-			format!("{} address {} is already in use, make sure that another instance of an Ethereum client is not running or change the address using the --{}-port and --{}-interface options.", id, url, options, options)
+			format!("GraphQL address {} is already in use, make sure that another instance of an Ethereum client is not running or change the address using the --graphql-port and --graphql-interface options.", url)
 		),
-		Err(e) => Err(format!("{} error: {:?}", id, e)),
+		Err(e) => Err(format!("GraphQL error: {:?}", e)),
 	}
 }
 
@@ -285,7 +436,7 @@ pub fn new_ipc<D: rpc_apis::Dependencies>(
         }
     }
 
-    match rpc_servers::start_ipc(&conf.socket_addr, handler, rpc::RpcExtractor) {
+    match rpc_servers::start_ipc(&conf.socket_addr, handler, rpc::RpcExtractor::default()) {
         Ok(server) => Ok(Some(server)),
         Err(io_error) => Err(format!("IPC error: {}", io_error)),
     }
@@ -343,9 +494,152 @@ where
     handler
 }
 
+/// Sets up a rate limited handler for clients connecting over the network.
+fn setup_remote_apis<D>(
+    apis: &ApiSet,
+    authenticated: bool,
+    deps: &Dependencies<D>,
+) -> MetaIoHandler<Metadata, (ApiPermissions, RateLimiter, Middleware<D::Notifier>)>
+where
+    D: rpc_apis::Dependencies,
+{
+    let (permissions, methods) = remote_apis(apis, authenticated, deps);
+    let mut handler = MetaIoHandler::with_middleware((
+        permissions,
+        deps.rate_limiter.clone(),
+        Middleware::new(deps.stats.clone(), deps.apis.activity_notifier()),
+    ));
+    handler.extend_with(methods);
+
+    handler
+}
+
+/// Returns permissions and the methods to expose to network clients.
+///
+/// With token authentication all APIs are exposed, restricted per request by the client's
+/// access token. Requests without a token can only access the configured APIs.
+fn remote_apis<D>(
+    apis: &ApiSet,
+    authenticated: bool,
+    deps: &Dependencies<D>,
+) -> (ApiPermissions, MetaIoHandler<Metadata>)
+where
+    D: rpc_apis::Dependencies,
+{
+    let mut methods = MetaIoHandler::default();
+    let permissions = match authenticated {
+        true => ApiPermissions {
+            methods: deps
+                .apis
+                .extend_with_set(&mut methods, &ApiSet::All.list_apis()),
+            public: apis.list_apis(),
+        },
+        false => {
+            deps.apis.extend_with_set(&mut methods, &apis.list_apis());
+            ApiPermissions::default()
+        }
+    };
+    (permissions, methods)
+}
+
+/// RPC middleware rejecting calls to APIs not granted by the client's access token.
+///
+/// Without any known methods (the default) all calls are allowed.
+#[derive(Default)]
+struct ApiPermissions {
+    /// APIs exposing each method.
+    methods: HashMap<String, HashSet<Api>>,
+    /// APIs available to clients without a token.
+    public: HashSet<Api>,
+}
+
+impl ApiPermissions {
+    fn is_allowed(&self, method: &str, params: &core::Params, meta: &Metadata) -> bool {
+        if self.methods.is_empty() {
+            return true;
+        }
+
+        let granted = match meta.claims {
+            Some(ref claims) => claims
+                .apis
+                .join(",")
+                .parse::<ApiSet>()
+                .map(|apis| apis.list_apis())
+                .unwrap_or_default(),
+            None => self.public.clone(),
+        };
+        // The health check method is served to everyone at the health API path anyway.
+        let allows = |method: &str| {
+            method == HEALTH_API.1
+                || self
+                    .methods
+                    .get(method)
+                    .map_or(true, |apis| !apis.is_disjoint(&granted))
+        };
+
+        // Generic pub-sub calls other methods on behalf of the client.
+        let subscribed = match (method, params) {
+            ("parity_subscribe", &core::Params::Array(ref params)) => {
+                params.get(0).and_then(|method| method.as_str())
+            }
+            _ => None,
+        };
+
+        allows(method) && subscribed.map_or(true, allows)
+    }
+}
+
+impl core::Middleware<Metadata> for ApiPermissions {
+    type Future = core::FutureResponse;
+    type CallFuture = core::middleware::NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: core::Call,
+        meta: Metadata,
+        process: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(core::Call, Metadata) -> X,
+        X: core::futures::Future<Item = Option<core::Output>, Error = ()> + Send + 'static,
+    {
+        let denied = match call {
+            core::Call::MethodCall(ref call)
+                if !self.is_allowed(&call.method, &call.params, &meta) =>
+            {
+                let error = core::Error {
+                    code: core::ErrorCode::ServerError(PERMISSION_DENIED),
+                    message: format!("Method {} is not allowed for this client", call.method),
+                    data: None,
+                };
+                Some(Some(core::Output::from(
+                    Err(error),
+                    call.id.clone(),
+                    call.jsonrpc,
+                )))
+            }
+            core::Call::Notification(ref notification)
+                if !self.is_allowed(&notification.method, &notification.params, &meta) =>
+            {
+                Some(None)
+            }
+            _ => None,
+        };
+
+        match denied {
+            Some(output) => Either::A(Box::new(future::ok(output))),
+            None => Either::B(process(call, meta)),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::address;
+    use super::{address, ApiPermissions};
+    use crate::rpc_apis::{Api, ApiSet};
+    use jsonrpc_core::{Params, Value};
+    use parity_rpc::{JwtClaims, Metadata};
+    use std::sync::Arc;
 
     #[test]
     fn should_return_proper_address() {
@@ -363,4 +657,60 @@ mod tests {
             Some("host".into())
         );
     }
+
+    #[test]
+    fn should_restrict_calls_to_granted_apis() {
+        let methods = vec![
+            ("eth_blockNumber", Api::Eth),
+            ("parity_nodeStatus", Api::Parity),
+            ("parity_setAuthor", Api::ParitySet),
+            ("parity_subscribe", Api::ParityPubSub),
+        ];
+        let permissions = ApiPermissions {
+            methods: methods
+                .into_iter()
+                .map(|(method, api)| (method.into(), vec![api].into_iter().collect()))
+                .collect(),
+            public: "eth,parity_pubsub".parse::<ApiSet>().unwrap().list_apis(),
+        };
+        let token = |apis: &[&str]| Metadata {
+            claims: Some(Arc::new(JwtClaims {
+                sub: None,
+                exp: None,
+                apis: apis.iter().map(|api| api.to_string()).collect(),
+            })),
+            ..Default::default()
+        };
+        let no_params = Params::None;
+        let subscribe = |method: &str| Params::Array(vec![method.into(), Value::Array(vec![])]);
+
+        // clients without a token get public APIs
+        let anonymous = Metadata::default();
+        assert!(permissions.is_allowed("eth_blockNumber", &no_params, &anonymous));
+        assert!(!permissions.is_allowed("parity_setAuthor", &no_params, &anonymous));
+        assert!(permissions.is_allowed("parity_nodeStatus", &no_params, &anonymous));
+        assert!(permissions.is_allowed(
+            "parity_subscribe",
+            &subscribe("eth_blockNumber"),
+            &anonymous
+        ));
+        assert!(!permissions.is_allowed(
+            "parity_subscribe",
+            &subscribe("parity_setAuthor"),
+            &anonymous
+        ));
+
+        // token holders get APIs from claims
+        let operator = token(&["parity_set"]);
+        assert!(!permissions.is_allowed("eth_blockNumber", &no_params, &operator));
+        assert!(permissions.is_allowed("parity_setAuthor", &no_params, &operator));
+        let all = token(&["all", "-parity_set"]);
+        assert!(permissions.is_allowed("eth_blockNumber", &no_params, &all));
+        assert!(!permissions.is_allowed("parity_setAuthor", &no_params, &all));
+        assert!(!permissions.is_allowed("eth_blockNumber", &no_params, &token(&["unknown"])));
+
+        // unknown methods are left to the handler
+        assert!(permissions.is_allowed("eth_unknown", &no_params, &anonymous));
+        assert!(ApiPermissions::default().is_allowed("parity_setAuthor", &no_params, &anonymous));
+    }
 }
@@ -16,7 +16,7 @@
 
 use std::{
     cmp::PartialEq,
-    collections::{BTreeMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     str::FromStr,
     sync::Arc,
 };
@@ -28,12 +28,17 @@ use crate::{
     miner::external::ExternalMiner,
     sync::{ManageNetwork, SyncProvider},
 };
-use ethcore::{client::Client, miner::Miner, snapshot::SnapshotService};
+use ethcore::{
+    client::Client,
+    miner::{Miner, MinerService},
+    snapshot::SnapshotService,
+};
 use ethcore_logger::RotatingLogger;
 use fetch::Client as FetchClient;
 use jsonrpc_core::{self as core, MetaIoHandler};
 use parity_rpc::{
     dispatch::FullDispatcher,
+    graphql::GraphQL,
     informant::{ActivityNotifier, ClientNotifier},
     Host, Metadata, NetworkSettings,
 };
@@ -71,6 +76,8 @@ pub enum Api {
     /// Geth-compatible (best-effort) debug API (Potentially UNSAFE)
     /// NOTE We don't aim to support all methods, only the ones that are useful.
     Debug,
+    /// GraphQL mutations (UNSAFE: Side Effects (submits transactions)). Served by the GraphQL endpoint only.
+    GraphQLMutation,
 }
 
 impl FromStr for Api {
@@ -82,6 +89,7 @@ impl FromStr for Api {
         match s {
             "debug" => Ok(Debug),
             "eth" => Ok(Eth),
+            "graphql_mutation" => Ok(GraphQLMutation),
             "net" => Ok(Net),
             "parity" => Ok(Parity),
             "parity_accounts" => Ok(ParityAccounts),
@@ -160,6 +168,7 @@ fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
     let mut modules = BTreeMap::new();
     for api in apis {
         let (name, version) = match *api {
+            Api::GraphQLMutation => continue,
             Api::Debug => ("debug", "1.0"),
             Api::Eth => ("eth", "1.0"),
             Api::EthPubSub => ("pubsub", "1.0"),
@@ -208,9 +217,17 @@ pub trait Dependencies {
     fn activity_notifier(&self) -> Self::Notifier;
 
     /// Extend the given I/O handler with endpoints for each API.
-    fn extend_with_set<S>(&self, handler: &mut MetaIoHandler<Metadata, S>, apis: &HashSet<Api>)
+    /// Returns the APIs exposing each added method.
+    fn extend_with_set<S>(
+        &self,
+        handler: &mut MetaIoHandler<Metadata, S>,
+        apis: &HashSet<Api>,
+    ) -> HashMap<String, HashSet<Api>>
     where
         S: core::Middleware<Metadata>;
+
+    /// Create the GraphQL request handler.
+    fn graphql(&self) -> GraphQL;
 }
 
 /// RPC dependencies for a full node.
@@ -239,10 +256,11 @@ pub struct FullDependencies {
 impl FullDependencies {
     fn extend_api<S>(
         &self,
-        handler: &mut MetaIoHandler<Metadata, S>,
+        io: &mut MetaIoHandler<Metadata, S>,
         apis: &HashSet<Api>,
         for_generic_pubsub: bool,
-    ) where
+    ) -> HashMap<String, HashSet<Api>>
+    where
         S: core::Middleware<Metadata>,
     {
         use parity_rpc::v1::*;
@@ -259,7 +277,11 @@ impl FullDependencies {
         let account_signer = Arc::new(dispatch::Signer::new(self.accounts.clone())) as _;
         let accounts = account_utils::accounts_list(self.accounts.clone());
 
+        let mut methods = HashMap::new();
         for api in apis {
+            // Methods are collected per API to learn which APIs expose them.
+            let mut api_handler = MetaIoHandler::<Metadata>::default();
+            let handler = &mut api_handler;
             match *api {
                 Api::Debug => {
                     handler.extend_with(DebugClient::new(self.client.clone()).to_delegate());
@@ -314,6 +336,17 @@ impl FullDependencies {
                                     h.notify_new_transactions(hashes);
                                 }
                             }));
+                        let h = client.handler();
+                        let miner = Arc::downgrade(&self.miner);
+                        self.miner
+                            .add_transactions_listener(Box::new(move |hashes| {
+                                if let Some(h) = h.upgrade() {
+                                    let miner = miner.clone();
+                                    h.notify_new_full_transactions(hashes, move |hash| {
+                                        miner.upgrade().and_then(|miner| miner.transaction(hash))
+                                    });
+                                }
+                            }));
 
                         if let Some(h) = client.handler().upgrade() {
                             self.client.add_notify(h);
@@ -418,8 +451,18 @@ impl FullDependencies {
                     #[cfg(feature = "accounts")]
                     handler.extend_with(SecretStoreClient::new(&self.accounts).to_delegate());
                 }
+                Api::GraphQLMutation => {}
+            }
+
+            for (method, _) in api_handler.iter() {
+                methods
+                    .entry(method.clone())
+                    .or_insert_with(HashSet::new)
+                    .insert(api.clone());
             }
+            io.extend_with(api_handler);
         }
+        methods
     }
 }
 
@@ -432,12 +475,24 @@ impl Dependencies for FullDependencies {
         }
     }
 
-    fn extend_with_set<S>(&self, handler: &mut MetaIoHandler<Metadata, S>, apis: &HashSet<Api>)
+    fn extend_with_set<S>(
+        &self,
+        handler: &mut MetaIoHandler<Metadata, S>,
+        apis: &HashSet<Api>,
+    ) -> HashMap<String, HashSet<Api>>
     where
         S: core::Middleware<Metadata>,
     {
         self.extend_api(handler, apis, false)
     }
+
+    fn graphql(&self) -> GraphQL {
+        GraphQL::new(
+            self.client.clone(),
+            self.miner.clone(),
+            self.gas_price_percentile,
+        )
+    }
 }
 
 impl ApiSet {
@@ -516,6 +571,7 @@ mod test {
         assert_eq!(Api::Traces, "traces".parse().unwrap());
         assert_eq!(Api::Rpc, "rpc".parse().unwrap());
         assert_eq!(Api::SecretStore, "secretstore".parse().unwrap());
+        assert_eq!(Api::GraphQLMutation, "graphql_mutation".parse().unwrap());
         assert!("rp".parse::<Api>().is_err());
     }
 
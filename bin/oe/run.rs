@@ -42,9 +42,14 @@ use crate::{
 use ansi_term::Colour;
 use dir::{DatabaseDirectories, Directories};
 use ethcore::{
-    client::{BlockChainClient, BlockInfo, Client, DatabaseCompactionProfile, Mode, VMType},
+    client::{
+        BlockChainClient, BlockInfo, Client, DatabaseCompactionProfile, Mode,
+        StateRegenerationConfig, VMType,
+    },
+    engines::{RemoteSigner, RemoteSignerConfig},
     miner::{self, stratum, Miner, MinerOptions, MinerService},
     snapshot::{self, SnapshotConfiguration},
+    spec::{EthashCacheConfig, SpecParams},
     verification::queue::VerifierSettings,
 };
 use ethcore_logger::{Config as LogConfig, RotatingLogger};
@@ -52,9 +57,14 @@ use ethcore_service::ClientService;
 use ethereum_types::{H256, U64};
 use journaldb::Algorithm;
 use node_filter::NodeFilter;
-use parity_rpc::{informant, is_major_importing, NetworkSettings};
+use parity_rpc::{
+    informant, is_major_importing,
+    rate_limit::{RateLimitConfig, RateLimiter},
+    NetworkSettings,
+};
 use parity_runtime::Runtime;
 use parity_version::version;
+use stats::PrometheusMetrics;
 
 // How often we attempt to take a snapshot: only snapshot on blocknumbers that are multiples of this.
 const SNAPSHOT_PERIOD: u64 = 20000;
@@ -68,11 +78,13 @@ const FETCH_FULL_NUM_DNS_THREADS: usize = 4;
 #[derive(Debug, PartialEq)]
 pub struct RunCmd {
     pub cache_config: CacheConfig,
+    pub ethash_cache: EthashCacheConfig,
     pub dirs: Directories,
     pub spec: SpecType,
     pub pruning: Pruning,
     pub pruning_history: u64,
     pub pruning_memory: usize,
+    pub state_regeneration: StateRegenerationConfig,
     /// Some if execution should be daemonized. Contains pid_file path.
     pub daemon: Option<String>,
     pub logger_config: LogConfig,
@@ -81,6 +93,7 @@ pub struct RunCmd {
     pub poll_lifetime: u32,
     pub ws_conf: rpc::WsConfiguration,
     pub http_conf: rpc::HttpConfiguration,
+    pub graphql_conf: rpc::GraphQLConfiguration,
     pub ipc_conf: rpc::IpcConfiguration,
     pub net_conf: sync::NetworkConfiguration,
     pub network_id: Option<u64>,
@@ -106,9 +119,13 @@ pub struct RunCmd {
     pub download_old_blocks: bool,
     pub new_transactions_stats_period: u64,
     pub verifier_settings: VerifierSettings,
+    pub parallel_transactions: bool,
     pub no_persistent_txqueue: bool,
     pub max_round_blocks_to_import: usize,
     pub metrics_conf: MetricsConfiguration,
+    pub telemetry_conf: Option<telemetry::Config>,
+    pub rate_limit: Option<RateLimitConfig>,
+    pub rpc_slow_query_threshold: Option<Duration>,
 }
 
 // node info fetcher for the local store.
@@ -141,7 +158,9 @@ impl crate::local_store::NodeInfo for FullNodeInfo {
 /// On error, returns what to print on stderr.
 pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient, String> {
     // load spec
-    let spec = cmd.spec.spec(&cmd.dirs.cache)?;
+    let spec = cmd
+        .spec
+        .spec(SpecParams::from_path(&cmd.dirs.cache).with_ethash_cache(cmd.ethash_cache))?;
 
     // load genesis hash
     let genesis_hash = spec.genesis_header().hash();
@@ -181,7 +200,13 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     let snapshot_path = db_dirs.snapshot_path();
 
     // execute upgrades
-    execute_upgrades(&cmd.dirs.base, &db_dirs, algorithm, &cmd.compaction)?;
+    execute_upgrades(
+        &cmd.dirs.base,
+        &db_dirs,
+        algorithm,
+        &cmd.compaction,
+        cmd.state_regeneration.enabled,
+    )?;
 
     // create dirs used by parity
     cmd.dirs.create_dirs(
@@ -298,7 +323,22 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
 
     let engine_signer = cmd.miner_extras.engine_signer;
     if engine_signer != Default::default() {
-        if let Some(author) = account_utils::miner_author(
+        if let Some(ref remote) = cmd.miner_extras.engine_signer_remote {
+            let signer = RemoteSigner::new(RemoteSignerConfig {
+                endpoint: remote.endpoint.clone(),
+                address: engine_signer,
+                timeout: remote.timeout,
+                retries: remote.retries,
+                protection_db: db_dirs.engine_signer_protection_path(),
+            })
+            .map_err(|e| format!("Failed to open engine signer protection database: {}", e))?;
+            info!(
+                "Signing consensus messages as {} through remote signer {:?}",
+                Colour::White.bold().paint(format!("{:#x}", engine_signer)),
+                remote.endpoint
+            );
+            miner.set_author(miner::Author::Sealer(Box::new(signer)));
+        } else if let Some(author) = account_utils::miner_author(
             &cmd.spec,
             &cmd.dirs,
             &account_provider,
@@ -330,6 +370,8 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     client_config.queue.verifier_settings.bad_hashes = verification_bad_blocks(&cmd.spec);
     client_config.transaction_verification_queue_size = ::std::cmp::max(2048, txpool_size / 4);
     client_config.snapshot = cmd.snapshot_conf.clone();
+    client_config.state_regeneration = cmd.state_regeneration.clone();
+    client_config.parallel_transactions = cmd.parallel_transactions;
 
     // set up bootnodes
     let mut net_conf = cmd.net_conf;
@@ -345,6 +387,12 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         .open(&client_path)
         .map_err(|e| format!("Failed to open database {:?}", e))?;
 
+    // start exporting spans before the client imports any block.
+    if let Some(ref telemetry_conf) = cmd.telemetry_conf {
+        telemetry::init(telemetry_conf.clone())
+            .map_err(|e| format!("Failed to start OTLP exporter: {}", e))?;
+    }
+
     // create client service.
     let service = ClientService::start(
         client_config,
@@ -433,10 +481,13 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     let external_miner = Arc::new(ExternalMiner::default());
 
     // start stratum
-    if let Some(ref stratum_config) = cmd.stratum {
-        stratum::Stratum::register(stratum_config, miner.clone(), Arc::downgrade(&client))
-            .map_err(|e| format!("Stratum start error: {:?}", e))?;
-    }
+    let stratum = match cmd.stratum {
+        Some(ref stratum_config) => Some(
+            stratum::Stratum::register(stratum_config, miner.clone(), Arc::downgrade(&client))
+                .map_err(|e| format!("Stratum start error: {:?}", e))?,
+        ),
+        None => None,
+    };
 
     // create sync object
     let (sync_provider, manage_network, chain_notify, priority_tasks, new_transaction_hashes) =
@@ -487,7 +538,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     }
 
     // set up dependencies for rpc servers
-    let rpc_stats = Arc::new(informant::RpcStats::default());
+    let rpc_stats = Arc::new(informant::RpcStats::new(cmd.rpc_slow_query_threshold));
     let secret_store = account_provider.clone();
     let signer_service = Arc::new(signer::new_service(&cmd.ws_conf, &cmd.logger_config));
 
@@ -513,10 +564,23 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         no_ancient_blocks: !cmd.download_old_blocks,
     });
 
+    let rate_limiter = match cmd.rate_limit {
+        Some(config) => {
+            let client = Arc::downgrade(&client);
+            RateLimiter::new(config, move || {
+                client
+                    .upgrade()
+                    .map_or(0, |client| client.chain_info().best_block_number)
+            })
+        }
+        None => RateLimiter::disabled(),
+    };
+
     let dependencies = rpc::Dependencies {
         apis: deps_for_rpc_apis.clone(),
         executor: runtime.executor(),
         stats: rpc_stats.clone(),
+        rate_limiter: rate_limiter.clone(),
     };
 
     // start rpc servers
@@ -524,7 +588,13 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
     let ipc_server = rpc::new_ipc(cmd.ipc_conf, &dependencies)?;
 
     // start the prometheus metrics server
-    start_prometheus_metrics(&cmd.metrics_conf, &dependencies)?;
+    let mut metrics_collectors: Vec<Box<dyn PrometheusMetrics + Send + Sync>> = Vec::new();
+    if let Some(stratum) = stratum {
+        metrics_collectors.push(Box::new(stratum));
+    }
+    metrics_collectors.push(Box::new(rate_limiter));
+    metrics_collectors.push(Box::new(rpc_stats.clone()));
+    start_prometheus_metrics(&cmd.metrics_conf, &dependencies, metrics_collectors)?;
 
     let http_server = rpc::new_http(
         "HTTP JSON-RPC",
@@ -532,6 +602,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
         cmd.http_conf.clone(),
         &dependencies,
     )?;
+    let graphql_server = rpc::new_graphql(cmd.graphql_conf.clone(), &dependencies)?;
 
     // secret store key server
     let secretstore_deps = secretstore::Dependencies {
@@ -607,6 +678,7 @@ pub fn execute(cmd: RunCmd, logger: Arc<RotatingLogger>) -> Result<RunningClient
                 watcher,
                 ws_server,
                 http_server,
+                graphql_server,
                 ipc_server,
                 secretstore_key_server,
                 runtime,
@@ -17,6 +17,7 @@
 //! Accounts Directory
 
 use ethkey::Password;
+use hd::Mnemonic;
 use std::{num::NonZeroU32, path::PathBuf};
 use Error;
 use SafeAccount;
@@ -93,6 +94,10 @@ pub trait VaultKeyDirectory: KeyDirectory {
     fn meta(&self) -> String;
     /// Set vault meta
     fn set_meta(&self, meta: &str) -> Result<(), Error>;
+    /// Get mnemonic of the HD wallet held by the vault, if any
+    fn mnemonic(&self) -> Result<Option<Mnemonic>, Error>;
+    /// Store mnemonic of an HD wallet in the vault, encrypted with vault key
+    fn set_mnemonic(&self, mnemonic: &Mnemonic) -> Result<(), Error>;
 }
 
 pub use self::{
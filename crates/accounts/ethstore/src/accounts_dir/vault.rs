@@ -20,6 +20,7 @@ use super::{
     KeyDirectory, SetKeyError, VaultKey, VaultKeyDirectory,
 };
 use crypto::Keccak256;
+use hd::Mnemonic;
 use json;
 use parking_lot::Mutex;
 use std::{
@@ -42,6 +43,7 @@ pub struct VaultKeyFileManager {
     name: String,
     key: VaultKey,
     meta: Mutex<String>,
+    mnemonic: Mutex<Option<Crypto>>,
 }
 
 impl VaultDiskDirectory {
@@ -59,14 +61,14 @@ impl VaultDiskDirectory {
         // create vault && vault file
         let vault_meta = "{}";
         fs::create_dir_all(&vault_dir_path)?;
-        if let Err(err) = create_vault_file(&vault_dir_path, &key, vault_meta) {
+        if let Err(err) = create_vault_file(&vault_dir_path, &key, vault_meta, None) {
             let _ = fs::remove_dir_all(&vault_dir_path); // can't do anything with this
             return Err(err);
         }
 
         Ok(DiskDirectory::new(
             vault_dir_path,
-            VaultKeyFileManager::new(name, key, vault_meta),
+            VaultKeyFileManager::new(name, key, vault_meta, None),
         ))
     }
 
@@ -82,11 +84,11 @@ impl VaultDiskDirectory {
         }
 
         // check that passed key matches vault file
-        let meta = read_vault_file(&vault_dir_path, Some(&key))?;
+        let (meta, mnemonic) = read_vault_file(&vault_dir_path, Some(&key))?;
 
         Ok(DiskDirectory::new(
             vault_dir_path,
-            VaultKeyFileManager::new(name, key, &meta),
+            VaultKeyFileManager::new(name, key, &meta, mnemonic),
         ))
     }
 
@@ -102,7 +104,7 @@ impl VaultDiskDirectory {
         }
 
         // check that passed key matches vault file
-        read_vault_file(&vault_dir_path, None)
+        read_vault_file(&vault_dir_path, None).map(|(meta, _)| meta)
     }
 
     fn create_temp_vault(&self, key: VaultKey) -> Result<VaultDiskDirectory, Error> {
@@ -172,10 +174,15 @@ impl VaultKeyDirectory for VaultDiskDirectory {
             .expect("self is instance of DiskDirectory; DiskDirectory always returns path; qed")
             .clone();
 
-        // preserve meta
+        // preserve meta and mnemonic, the latter re-encrypted with the new key
         temp_vault
             .set_meta(&self.meta())
             .map_err(SetKeyError::NonFatalOld)?;
+        if let Some(mnemonic) = self.mnemonic().map_err(SetKeyError::NonFatalOld)? {
+            temp_vault
+                .set_mnemonic(&mnemonic)
+                .map_err(SetKeyError::NonFatalOld)?;
+        }
 
         // jump to next fs level
         source_path.push("next");
@@ -219,18 +226,47 @@ impl VaultKeyDirectory for VaultDiskDirectory {
         let vault_path = self
             .path()
             .expect("self is instance of DiskDirectory; DiskDirectory always returns path; qed");
-        create_vault_file(vault_path, &key_manager.key, meta)?;
+        let mnemonic = key_manager.mnemonic.lock().clone();
+        create_vault_file(vault_path, &key_manager.key, meta, mnemonic.as_ref())?;
         *key_manager.meta.lock() = meta.to_owned();
         Ok(())
     }
+
+    fn mnemonic(&self) -> Result<Option<Mnemonic>, Error> {
+        let key_manager = self.key_manager();
+        let crypto = match *key_manager.mnemonic.lock() {
+            Some(ref crypto) => crypto.clone(),
+            None => return Ok(None),
+        };
+        let phrase = String::from_utf8(crypto.decrypt(&key_manager.key.password)?)
+            .map_err(|_| Error::InvalidMnemonic("not valid UTF-8".into()))?;
+        Mnemonic::from_phrase(&phrase).map(Some)
+    }
+
+    fn set_mnemonic(&self, mnemonic: &Mnemonic) -> Result<(), Error> {
+        let key_manager = self.key_manager();
+        let vault_path = self
+            .path()
+            .expect("self is instance of DiskDirectory; DiskDirectory always returns path; qed");
+        let crypto = Crypto::with_plain(
+            mnemonic.phrase().as_bytes(),
+            &key_manager.key.password,
+            key_manager.key.iterations,
+        )?;
+        let meta = self.meta();
+        create_vault_file(vault_path, &key_manager.key, &meta, Some(&crypto))?;
+        *key_manager.mnemonic.lock() = Some(crypto);
+        Ok(())
+    }
 }
 
 impl VaultKeyFileManager {
-    pub fn new(name: &str, key: VaultKey, meta: &str) -> Self {
+    pub fn new(name: &str, key: VaultKey, meta: &str, mnemonic: Option<Crypto>) -> Self {
         VaultKeyFileManager {
             name: name.into(),
             key: key,
             meta: Mutex::new(meta.to_owned()),
+            mnemonic: Mutex::new(mnemonic),
         }
     }
 }
@@ -291,7 +327,12 @@ fn check_vault_name(name: &str) -> bool {
 }
 
 /// Vault can be empty, but still must be pluggable => we store vault password in separate file
-fn create_vault_file<P>(vault_dir_path: P, key: &VaultKey, meta: &str) -> Result<(), Error>
+fn create_vault_file<P>(
+    vault_dir_path: P,
+    key: &VaultKey,
+    meta: &str,
+    mnemonic: Option<&Crypto>,
+) -> Result<(), Error>
 where
     P: AsRef<Path>,
 {
@@ -311,6 +352,7 @@ where
     let vault_file_contents = json::VaultFile {
         crypto: crypto.into(),
         meta: Some(meta.to_owned()),
+        mnemonic: mnemonic.cloned().map(Into::into),
     };
     vault_file_contents
         .write(&mut vault_file)
@@ -321,8 +363,11 @@ where
     Ok(())
 }
 
-/// When vault is opened => we must check that password matches && read metadata
-fn read_vault_file<P>(vault_dir_path: P, key: Option<&VaultKey>) -> Result<String, Error>
+/// When vault is opened => we must check that password matches && read metadata and mnemonic
+fn read_vault_file<P>(
+    vault_dir_path: P,
+    key: Option<&VaultKey>,
+) -> Result<(String, Option<Crypto>), Error>
 where
     P: AsRef<Path>,
 {
@@ -334,6 +379,7 @@ where
         json::VaultFile::load(vault_file).map_err(|e| Error::Custom(format!("{:?}", e)))?;
     let vault_file_meta = vault_file_contents.meta.unwrap_or("{}".to_owned());
     let vault_file_crypto: Crypto = vault_file_contents.crypto.into();
+    let vault_file_mnemonic = vault_file_contents.mnemonic.map(Into::into);
 
     if let Some(key) = key {
         let password_bytes = vault_file_crypto.decrypt(&key.password)?;
@@ -343,7 +389,7 @@ where
         }
     }
 
-    Ok(vault_file_meta)
+    Ok((vault_file_meta, vault_file_mnemonic))
 }
 
 #[cfg(test)]
@@ -411,7 +457,7 @@ mod test {
         fs::create_dir_all(&vault_dir).unwrap();
 
         // when
-        let result = create_vault_file(&vault_dir, &key, "{}");
+        let result = create_vault_file(&vault_dir, &key, "{}", None);
 
         // then
         assert!(result.is_ok());
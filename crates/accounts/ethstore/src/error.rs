@@ -50,6 +50,12 @@ pub enum Error {
     EthCryptoPublicKey(crypto::publickey::Error),
     /// Derivation error
     Derivation(DerivationError),
+    /// Invalid BIP-39 mnemonic
+    InvalidMnemonic(String),
+    /// Invalid BIP-32 derivation path
+    InvalidDerivationPath(String),
+    /// Vault does not hold a mnemonic
+    MnemonicNotFound,
     /// Custom error
     Custom(String),
 }
@@ -72,6 +78,9 @@ impl fmt::Display for Error {
             Error::EthCrypto(ref err) => err.to_string(),
             Error::EthCryptoPublicKey(ref err) => err.to_string(),
             Error::Derivation(ref err) => format!("Derivation error: {:?}", err),
+            Error::InvalidMnemonic(ref reason) => format!("Invalid mnemonic: {}", reason),
+            Error::InvalidDerivationPath(ref path) => format!("Invalid derivation path: {}", path),
+            Error::MnemonicNotFound => "Vault does not hold a mnemonic".into(),
             Error::Custom(ref s) => s.clone(),
         };
 
@@ -28,6 +28,7 @@ use crypto::publickey::{
     self, Address, ExtendedKeyPair, KeyPair, Message, Public, Secret, Signature,
 };
 use ethkey::Password;
+use hd::{self, HdPath, Mnemonic};
 use json::{self, OpaqueKeyFile, Uuid};
 use presale::PresaleWallet;
 use random::Random;
@@ -221,6 +222,19 @@ impl SimpleSecretStore for EthStore {
     fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error> {
         self.store.set_vault_meta(name, meta)
     }
+
+    fn set_vault_mnemonic(&self, name: &str, mnemonic: &Mnemonic) -> Result<(), Error> {
+        self.store.set_vault_mnemonic(name, mnemonic)
+    }
+
+    fn insert_hd_account(
+        &self,
+        name: &str,
+        path: &HdPath,
+        password: &Password,
+    ) -> Result<StoreAccountRef, Error> {
+        self.store.insert_hd_account(name, path, password)
+    }
 }
 
 impl SecretStore for EthStore {
@@ -890,6 +904,41 @@ impl SimpleSecretStore for EthMultiStore {
             .ok_or(Error::VaultNotFound)
             .and_then(|v| v.set_meta(meta))
     }
+
+    fn set_vault_mnemonic(&self, name: &str, mnemonic: &Mnemonic) -> Result<(), Error> {
+        let vaults = self.vaults.lock();
+        let vault = vaults.get(name).ok_or(Error::VaultNotFound)?;
+        match vault.mnemonic()? {
+            Some(ref existing) if existing == mnemonic => Ok(()),
+            Some(_) => Err(Error::InvalidMnemonic(
+                "vault already holds a different mnemonic".into(),
+            )),
+            None => vault.set_mnemonic(mnemonic),
+        }
+    }
+
+    fn insert_hd_account(
+        &self,
+        name: &str,
+        path: &HdPath,
+        password: &Password,
+    ) -> Result<StoreAccountRef, Error> {
+        let secret = {
+            // lock border
+            let vaults = self.vaults.lock();
+            let vault = vaults.get(name).ok_or(Error::VaultNotFound)?;
+            let mnemonic = vault.mnemonic()?.ok_or(Error::MnemonicNotFound)?;
+            hd::derive_secret(&mnemonic.to_seed(""), path)?
+        };
+
+        // deriving the same path twice yields the account that is already stored
+        let address = KeyPair::from_secret(secret.clone())?.address();
+        let account_ref = StoreAccountRef::vault(name, address);
+        if self.cache.read().contains_key(&account_ref) {
+            return Ok(account_ref);
+        }
+        self.insert_account(SecretVaultRef::Vault(name.to_owned()), secret, password)
+    }
 }
 
 #[cfg(test)]
@@ -900,10 +949,13 @@ mod tests {
     use super::{EthMultiStore, EthStore};
     use accounts_dir::{KeyDirectory, MemoryDirectory, RootDiskDirectory};
     use crypto::publickey::{Generator, KeyPair, Random};
-    use ethereum_types::H256;
+    use ethereum_types::{Address, H256};
+    use hd::{HdPath, Mnemonic};
     use secret_store::{
         Derivation, SecretStore, SecretVaultRef, SimpleSecretStore, StoreAccountRef,
     };
+    use std::str::FromStr;
+    use Error;
 
     fn keypair() -> KeyPair {
         Random.generate()
@@ -1478,6 +1530,62 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_derive_hd_accounts_from_vault_mnemonic() {
+        // given a vault holding a mnemonic
+        let mut dir = RootDiskDirectoryGuard::new();
+        let store = EthStore::open(dir.key_dir.take().unwrap()).unwrap();
+        let name = "vault";
+        let password = "password".into();
+        let mnemonic =
+            Mnemonic::from_phrase("test test test test test test test test test test test junk")
+                .unwrap();
+        store.create_vault(name, &password).unwrap();
+        assert_matches!(
+            store.insert_hd_account(name, &HdPath::ethereum(0), &password),
+            Err(Error::MnemonicNotFound)
+        );
+        store.set_vault_mnemonic(name, &mnemonic).unwrap();
+
+        // when deriving the first address twice
+        let account = store
+            .insert_hd_account(name, &HdPath::ethereum(0), &password)
+            .unwrap();
+        store
+            .insert_hd_account(name, &HdPath::ethereum(0), &password)
+            .unwrap();
+
+        // then it is stored once
+        assert_eq!(
+            account,
+            StoreAccountRef::vault(
+                name,
+                Address::from_str("f39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap()
+            )
+        );
+        assert_eq!(store.accounts().unwrap(), vec![account]);
+
+        // and the mnemonic survives a password change
+        let new_password = "new_password".into();
+        store.change_vault_password(name, &new_password).unwrap();
+        store.close_vault(name).unwrap();
+        store.open_vault(name, &new_password).unwrap();
+        store.set_vault_mnemonic(name, &mnemonic).unwrap();
+        let second = store
+            .insert_hd_account(name, &HdPath::ethereum(1), &new_password)
+            .unwrap();
+        assert_eq!(
+            second.address,
+            Address::from_str("70997970c51812dc3a010c7d01b50e0d17dc79c8").unwrap()
+        );
+
+        // and cannot be replaced by another one
+        assert_matches!(
+            store.set_vault_mnemonic(name, &Mnemonic::random(12).unwrap()),
+            Err(Error::InvalidMnemonic(_))
+        );
+    }
+
     #[test]
     fn should_save_meta_when_setting_before_password() {
         // given
@@ -0,0 +1,188 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+use crypto::{digest, pbkdf2};
+use rand::{rngs::OsRng, RngCore};
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+use Error;
+
+/// Number of PBKDF2 rounds used to stretch a mnemonic into a seed.
+const SEED_ITERATIONS: u32 = 2048;
+
+lazy_static! {
+    /// The BIP-39 English wordlist, sorted.
+    static ref WORDS: Vec<&'static str> = include_str!("english.txt").split_whitespace().collect();
+}
+
+/// BIP-39 mnemonic phrase, the human readable backup of an HD wallet.
+#[derive(Clone, PartialEq)]
+pub struct Mnemonic {
+    phrase: String,
+}
+
+impl Mnemonic {
+    /// Generates a new mnemonic of 12, 15, 18, 21 or 24 words.
+    pub fn random(words: usize) -> Result<Self, Error> {
+        let mut entropy = vec![0u8; entropy_len(words)?];
+        OsRng.fill_bytes(&mut entropy);
+        Self::from_entropy(&entropy)
+    }
+
+    /// Encodes 16 to 32 bytes of entropy (in steps of 4 bytes) as a mnemonic.
+    pub fn from_entropy(entropy: &[u8]) -> Result<Self, Error> {
+        if entropy.len() < 16 || entropy.len() > 32 || entropy.len() % 4 != 0 {
+            return Err(Error::InvalidMnemonic(format!(
+                "invalid entropy length {}",
+                entropy.len()
+            )));
+        }
+
+        // entropy is followed by the first `len / 4` bits of its hash
+        let checksum = digest::sha256(entropy)[0];
+        let bit = |i: usize| {
+            let byte = entropy.get(i / 8).cloned().unwrap_or(checksum);
+            (byte >> (7 - i % 8)) & 1 == 1
+        };
+        let words = (entropy.len() * 8 + entropy.len() / 4) / 11;
+        let phrase = (0..words)
+            .map(|word| (0..11).fold(0, |index, i| index << 1 | bit(word * 11 + i) as usize))
+            .map(|index| WORDS[index])
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Ok(Mnemonic { phrase })
+    }
+
+    /// Parses a mnemonic, checking its words and checksum.
+    pub fn from_phrase(phrase: &str) -> Result<Self, Error> {
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        let mut entropy = vec![0u8; entropy_len(words.len())?];
+        for (position, word) in words.iter().enumerate() {
+            let index = WORDS
+                .binary_search(word)
+                .map_err(|_| Error::InvalidMnemonic(format!("unknown word '{}'", word)))?;
+            for i in 0..11 {
+                let bit = position * 11 + i;
+                if bit < entropy.len() * 8 && (index >> (10 - i)) & 1 == 1 {
+                    entropy[bit / 8] |= 0x80 >> (bit % 8);
+                }
+            }
+        }
+
+        // re-encoding recomputes the checksum bits
+        let mnemonic = Self::from_entropy(&entropy)?;
+        if mnemonic.phrase != words.join(" ") {
+            return Err(Error::InvalidMnemonic("invalid checksum".into()));
+        }
+        Ok(mnemonic)
+    }
+
+    /// The words of the mnemonic, separated by single spaces.
+    pub fn phrase(&self) -> &str {
+        &self.phrase
+    }
+
+    /// Computes the 64-byte BIP-39 seed, protected by an optional `passphrase`.
+    pub fn to_seed(&self, passphrase: &str) -> [u8; 64] {
+        let salt = format!("mnemonic{}", passphrase).nfkd().collect::<String>();
+        let mut seed = [0u8; 64];
+        pbkdf2::sha512(
+            SEED_ITERATIONS,
+            pbkdf2::Salt(salt.as_bytes()),
+            pbkdf2::Secret(self.phrase.as_bytes()),
+            &mut seed,
+        );
+        seed
+    }
+}
+
+impl fmt::Debug for Mnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // never leak the phrase into logs
+        write!(f, "Mnemonic({} words)", self.phrase.split(' ').count())
+    }
+}
+
+fn entropy_len(words: usize) -> Result<usize, Error> {
+    match words {
+        12 | 15 | 18 | 21 | 24 => Ok(words / 3 * 4),
+        _ => Err(Error::InvalidMnemonic(format!(
+            "expected 12, 15, 18, 21 or 24 words, got {}",
+            words
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mnemonic;
+    use rustc_hex::ToHex;
+    use Error;
+
+    #[test]
+    fn should_encode_entropy() {
+        assert_eq!(
+            Mnemonic::from_entropy(&[0u8; 16]).unwrap().phrase(),
+            "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+        );
+        assert_eq!(
+            Mnemonic::from_entropy(&[0x7f; 16]).unwrap().phrase(),
+            "legal winner thank year wave sausage worth useful legal winner thank yellow"
+        );
+    }
+
+    #[test]
+    fn should_compute_seed() {
+        let mnemonic = Mnemonic::from_entropy(&[0u8; 16]).unwrap();
+        assert_eq!(
+            mnemonic.to_seed("TREZOR").to_hex(),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+    }
+
+    #[test]
+    fn should_parse_phrase() {
+        let phrase = "legal winner thank year wave sausage worth useful legal winner thank yellow";
+        assert_eq!(
+            Mnemonic::from_phrase(&format!("  {}\n", phrase)).unwrap(),
+            Mnemonic::from_entropy(&[0x7f; 16]).unwrap()
+        );
+
+        let random = Mnemonic::random(24).unwrap();
+        assert_eq!(Mnemonic::from_phrase(random.phrase()).unwrap(), random);
+    }
+
+    #[test]
+    fn should_reject_invalid_phrase() {
+        let bad_checksum =
+            "legal winner thank year wave sausage worth useful legal winner thank thank";
+        assert_matches!(
+            Mnemonic::from_phrase(bad_checksum),
+            Err(Error::InvalidMnemonic(_))
+        );
+        let unknown_word =
+            "legal winner thank year wave sausage worth useful legal winner thank parity";
+        assert_matches!(
+            Mnemonic::from_phrase(unknown_word),
+            Err(Error::InvalidMnemonic(_))
+        );
+        assert_matches!(
+            Mnemonic::from_phrase("legal winner thank"),
+            Err(Error::InvalidMnemonic(_))
+        );
+    }
+}
@@ -0,0 +1,110 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hierarchical deterministic wallets: BIP-39 mnemonics, BIP-32 derivation and BIP-44 paths.
+
+mod mnemonic;
+mod path;
+
+pub use self::{mnemonic::Mnemonic, path::HdPath};
+
+use crypto::publickey::{self, Address, ExtendedKeyPair, KeyPair, Secret};
+use Error;
+use IndexDerivation;
+
+/// Offset of hardened node indices in BIP-32.
+const HARDENED_OFFSET: u32 = 0x8000_0000;
+
+/// BIP-32 derivation step for a path node, hardened indices are offset by 2^31.
+fn node_derivation(node: &IndexDerivation) -> publickey::Derivation<u32> {
+    if node.soft {
+        publickey::Derivation::Soft(node.index)
+    } else {
+        publickey::Derivation::Hard(HARDENED_OFFSET | node.index)
+    }
+}
+
+/// Derives the secret at `path` from a BIP-39 seed, as defined by BIP-32.
+pub fn derive_secret(seed: &[u8], path: &HdPath) -> Result<Secret, Error> {
+    let mut extended = ExtendedKeyPair::with_seed(seed)?;
+    for node in path.nodes() {
+        extended = extended.derive(node_derivation(node))?;
+    }
+    Ok(extended.secret().as_raw().clone())
+}
+
+/// Derives the address of the account at `path` from a BIP-39 seed.
+pub fn derive_address(seed: &[u8], path: &HdPath) -> Result<Address, Error> {
+    let secret = derive_secret(seed, path)?;
+    Ok(KeyPair::from_secret(secret)?.address())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{derive_address, derive_secret, HdPath, Mnemonic};
+    use crypto::publickey::{Address, Secret};
+    use std::str::FromStr;
+
+    fn address(phrase: &str, path: &str) -> Address {
+        let seed = Mnemonic::from_phrase(phrase).unwrap().to_seed("");
+        derive_address(&seed, &path.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn should_derive_bip44_addresses() {
+        let phrase = "test test test test test test test test test test test junk";
+        assert_eq!(
+            address(phrase, "m/44'/60'/0'/0/0"),
+            Address::from_str("f39fd6e51aad88f6f4ce6ab8827279cfffb92266").unwrap()
+        );
+        assert_eq!(
+            address(phrase, "m/44'/60'/0'/0/1"),
+            Address::from_str("70997970c51812dc3a010c7d01b50e0d17dc79c8").unwrap()
+        );
+
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        assert_eq!(
+            address(phrase, "m/44'/60'/0'/0/0"),
+            Address::from_str("9858effd232b4033e47d90003d41ec34ecaeda94").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_derive_bip44_secret() {
+        let seed =
+            Mnemonic::from_phrase("test test test test test test test test test test test junk")
+                .unwrap()
+                .to_seed("");
+        let secret = derive_secret(&seed, &HdPath::ethereum(0)).unwrap();
+        assert_eq!(
+            secret,
+            Secret::from_str("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn should_match_bip32_test_vector() {
+        // BIP-32 test vector 1, chain m/0'/1/2'/2/1000000000.
+        let seed: Vec<u8> = (0u8..16).collect();
+        let path = "m/0'/1/2'/2/1000000000".parse().unwrap();
+        assert_eq!(
+            derive_secret(&seed, &path).unwrap(),
+            Secret::from_str("471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8")
+                .unwrap()
+        );
+    }
+}
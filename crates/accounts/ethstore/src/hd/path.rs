@@ -0,0 +1,124 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::{fmt, str::FromStr};
+use Derivation;
+use Error;
+use IndexDerivation;
+
+/// BIP-32 derivation path, e.g. `m/44'/60'/0'/0/0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdPath(Vec<IndexDerivation>);
+
+impl HdPath {
+    /// BIP-44 path of the `index`-th address of the first Ethereum account, `m/44'/60'/0'/0/index`.
+    pub fn ethereum(index: u32) -> Self {
+        let hard = |index| IndexDerivation { soft: false, index };
+        let soft = |index| IndexDerivation { soft: true, index };
+        HdPath(vec![hard(44), hard(60), hard(0), soft(0), soft(index)])
+    }
+
+    /// Nodes of the path, starting from the master key.
+    pub fn nodes(&self) -> &[IndexDerivation] {
+        &self.0
+    }
+}
+
+impl FromStr for HdPath {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidDerivationPath(path.to_owned());
+        let mut nodes = path.trim().split('/');
+        if nodes.next() != Some("m") {
+            return Err(invalid());
+        }
+
+        nodes
+            .map(|node| {
+                let (soft, index) = match node.trim_end_matches(|c: char| c == '\'' || c == 'h') {
+                    index if index.len() == node.len() => (true, index),
+                    index if index.len() + 1 == node.len() => (false, index),
+                    _ => return Err(invalid()),
+                };
+                match index.parse::<u32>() {
+                    Ok(index) if index < super::HARDENED_OFFSET => {
+                        Ok(IndexDerivation { soft, index })
+                    }
+                    _ => Err(invalid()),
+                }
+            })
+            .collect::<Result<_, _>>()
+            .map(HdPath)
+    }
+}
+
+impl fmt::Display for HdPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "m")?;
+        for node in &self.0 {
+            write!(f, "/{}{}", node.index, if node.soft { "" } else { "'" })?;
+        }
+        Ok(())
+    }
+}
+
+impl From<HdPath> for Derivation {
+    /// Hardened nodes are offset by 2^31, as in BIP-32. Plain hierarchical derivations
+    /// keep their indices as given.
+    fn from(path: HdPath) -> Self {
+        Derivation::Hierarchical(
+            path.0
+                .into_iter()
+                .map(|node| IndexDerivation {
+                    soft: node.soft,
+                    index: if node.soft {
+                        node.index
+                    } else {
+                        super::HARDENED_OFFSET | node.index
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HdPath;
+    use Error;
+
+    #[test]
+    fn should_parse_path() {
+        assert_eq!(
+            "m/44'/60'/0'/0/7".parse::<HdPath>().unwrap(),
+            HdPath::ethereum(7)
+        );
+        assert_eq!(
+            "m/44h/60h/0h/0/7".parse::<HdPath>().unwrap(),
+            HdPath::ethereum(7)
+        );
+        assert_eq!(HdPath::ethereum(7).to_string(), "m/44'/60'/0'/0/7");
+        assert_eq!("m".parse::<HdPath>().unwrap().nodes().len(), 0);
+    }
+
+    #[test]
+    fn should_reject_invalid_path() {
+        for path in &["", "44'/60'", "m/", "m/x", "m/1''", "m/2147483648"] {
+            assert_matches!(path.parse::<HdPath>(), Err(Error::InvalidDerivationPath(_)));
+        }
+    }
+}
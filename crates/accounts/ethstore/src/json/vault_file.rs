@@ -25,6 +25,9 @@ pub struct VaultFile {
     pub crypto: Crypto,
     /// Vault metadata string
     pub meta: Option<String>,
+    /// Mnemonic of the HD wallet held by the vault, encrypted with vault password
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mnemonic: Option<Crypto>,
 }
 
 impl VaultFile {
@@ -70,6 +73,7 @@ mod test {
                 mac: "16381463ea11c6eb2239a9f339c2e780516d29d234ce30ac5f166f9080b5a262".into(),
             },
             meta: Some("{}".into()),
+            mnemonic: None,
         };
 
         let serialized = serde_json::to_string(&file).unwrap();
@@ -95,6 +99,7 @@ mod test {
                 mac: "16381463ea11c6eb2239a9f339c2e780516d29d234ce30ac5f166f9080b5a262".into(),
             },
             meta: None,
+            mnemonic: None,
         };
 
         let serialized = serde_json::to_string(&file).unwrap();
@@ -28,6 +28,7 @@ extern crate serde_json;
 extern crate smallvec;
 extern crate tempdir;
 extern crate time;
+extern crate unicode_normalization;
 
 extern crate ethereum_types;
 extern crate ethkey as _ethkey;
@@ -47,6 +48,7 @@ extern crate matches;
 
 pub mod accounts_dir;
 pub mod ethkey;
+pub mod hd;
 
 mod account;
 mod json;
@@ -17,6 +17,7 @@
 use crypto::publickey::{Address, Message, Public, Secret, Signature};
 use ethereum_types::H256;
 use ethkey::Password;
+use hd::{HdPath, Mnemonic};
 use json::{OpaqueKeyFile, Uuid};
 use std::{
     cmp::Ordering,
@@ -158,6 +159,15 @@ pub trait SimpleSecretStore: Send + Sync {
     fn get_vault_meta(&self, name: &str) -> Result<String, Error>;
     /// Set vault metadata string.
     fn set_vault_meta(&self, name: &str, meta: &str) -> Result<(), Error>;
+    /// Store HD wallet mnemonic in opened vault.
+    fn set_vault_mnemonic(&self, name: &str, mnemonic: &Mnemonic) -> Result<(), Error>;
+    /// Derive account at given path from the mnemonic of opened vault and insert it to the vault with given password.
+    fn insert_hd_account(
+        &self,
+        name: &str,
+        path: &HdPath,
+        password: &Password,
+    ) -> Result<StoreAccountRef, Error>;
 }
 
 /// Secret Store API
@@ -251,6 +261,7 @@ impl Hash for StoreAccountRef {
 }
 
 /// Node in hierarchical derivation.
+#[derive(Debug, Clone, PartialEq)]
 pub struct IndexDerivation {
     /// Node is soft (allows proof of parent from parent node).
     pub soft: bool,
@@ -21,10 +21,11 @@ use memmap::MmapMut;
 use parking_lot::Mutex;
 use seed_compute::SeedHashCompute;
 
-use shared::{epoch, get_cache_size, to_hex, Node, ETHASH_CACHE_ROUNDS, NODE_BYTES};
+use shared::{get_cache_size, to_hex, Node, ETHASH_CACHE_ROUNDS, NODE_BYTES};
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     fs,
     io::{self, Read, Write},
     path::{Path, PathBuf},
@@ -66,6 +67,28 @@ fn new_buffer(path: &Path, num_nodes: usize, ident: &H256, optimize_for: Optimiz
         .unwrap_or_else(|| Either::Left(make_memory_cache(num_nodes, ident)))
 }
 
+/// Kind of an ethash file kept in the cache directory.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum CacheFileKind {
+    /// Light cache used for verification.
+    Light,
+    /// Full dataset (DAG) used for mining.
+    Dataset,
+}
+
+/// An ethash file found in the cache directory.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct CachedEpoch {
+    /// Epoch the file belongs to.
+    pub epoch: u64,
+    /// Kind of the file.
+    pub kind: CacheFileKind,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Location of the file.
+    pub path: PathBuf,
+}
+
 #[derive(Clone)]
 pub struct NodeCacheBuilder {
     // TODO: Remove this locking and just use an `Rc`?
@@ -76,10 +99,7 @@ pub struct NodeCacheBuilder {
 
 // TODO: Abstract the "optimize for" logic
 pub struct NodeCache {
-    builder: NodeCacheBuilder,
-    cache_dir: Cow<'static, Path>,
     cache_path: PathBuf,
-    epoch: u64,
     cache: Cache,
 }
 
@@ -104,8 +124,46 @@ impl NodeCacheBuilder {
         self.seedhash.lock().hash_block_number(block_number)
     }
 
-    fn epoch_to_ident(&self, epoch: u64) -> H256 {
-        self.seedhash.lock().hash_epoch(epoch)
+    /// Path of the full dataset file for the epoch of `block_number`.
+    pub fn dataset_path(&self, cache_dir: &Path, block_number: u64) -> PathBuf {
+        let ident = self.block_number_to_ident(block_number);
+        dataset_path(cache_dir, &ident)
+    }
+
+    /// Lists the light cache and dataset files of epochs up to `max_epoch` found in `cache_dir`.
+    /// Files which do not belong to any of these epochs are ignored.
+    pub fn cache_files(&self, cache_dir: &Path, max_epoch: u64) -> io::Result<Vec<CachedEpoch>> {
+        // a fresh instance, so that walking all the epochs does not reset the shared one
+        let seedhash = SeedHashCompute::default();
+        let epochs: HashMap<String, u64> = (0..=max_epoch)
+            .map(|epoch| (to_hex(&seedhash.hash_epoch(epoch)), epoch))
+            .collect();
+
+        let mut files = Vec::new();
+        for entry in fs::read_dir(cache_dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let name = match name.to_str() {
+                Some(name) => name,
+                None => continue,
+            };
+            let (kind, ident) = if name.starts_with(DATASET_PREFIX) {
+                (CacheFileKind::Dataset, &name[DATASET_PREFIX.len()..])
+            } else {
+                (CacheFileKind::Light, name)
+            };
+            if let Some(epoch) = epochs.get(ident) {
+                files.push(CachedEpoch {
+                    epoch: *epoch,
+                    kind,
+                    size: entry.metadata()?.len(),
+                    path: entry.path(),
+                });
+            }
+        }
+
+        files.sort_by_key(|file| (file.epoch, file.kind == CacheFileKind::Dataset));
+        Ok(files)
     }
 
     pub fn from_file<P: Into<Cow<'static, Path>>>(
@@ -123,9 +181,6 @@ impl NodeCacheBuilder {
 
         if byte_size(&cache) == expected_cache_size {
             Ok(NodeCache {
-                builder: self.clone(),
-                epoch: epoch(block_number),
-                cache_dir: cache_dir,
                 cache_path: path,
                 cache: cache,
             })
@@ -156,9 +211,6 @@ impl NodeCacheBuilder {
         let nodes = new_buffer(&path, num_nodes, &ident, self.optimize_for);
 
         NodeCache {
-            builder: self.clone(),
-            epoch: epoch(block_number),
-            cache_dir: cache_dir.into(),
             cache_path: path,
             cache: nodes,
         }
@@ -171,21 +223,34 @@ impl NodeCache {
     }
 
     pub fn flush(&mut self) -> io::Result<()> {
-        if let Some(last) = self
-            .epoch
-            .checked_sub(2)
-            .map(|ep| cache_path(self.cache_dir.as_ref(), &self.builder.epoch_to_ident(ep)))
-        {
-            fs::remove_file(last).unwrap_or_else(|error| match error.kind() {
-                io::ErrorKind::NotFound => (),
-                _ => warn!("Error removing stale DAG cache: {:?}", error),
-            });
-        }
-
         consume_cache(&mut self.cache, &self.cache_path)
     }
 }
 
+/// Removes the light cache and dataset files of epochs older than the `keep` most recent ones,
+/// counting back from `latest_epoch`. Files of later epochs are left untouched.
+pub fn prune_cache_dir(
+    builder: &NodeCacheBuilder,
+    cache_dir: &Path,
+    latest_epoch: u64,
+    keep: u64,
+) -> io::Result<()> {
+    let oldest_kept = (latest_epoch + 1).saturating_sub(keep);
+    if oldest_kept == 0 {
+        return Ok(());
+    }
+
+    for file in builder.cache_files(cache_dir, oldest_kept - 1)? {
+        debug!(target: "ethash", "Removing stale {:?} file of epoch {}", file.kind, file.epoch);
+        fs::remove_file(&file.path).unwrap_or_else(|error| match error.kind() {
+            io::ErrorKind::NotFound => (),
+            _ => warn!("Error removing stale DAG cache: {:?}", error),
+        });
+    }
+
+    Ok(())
+}
+
 fn make_memmapped_cache(path: &Path, num_nodes: usize, ident: &H256) -> io::Result<MmapMut> {
     use std::fs::OpenOptions;
 
@@ -220,6 +285,12 @@ fn cache_path<'a, P: Into<Cow<'a, Path>>>(path: P, ident: &H256) -> PathBuf {
     buf
 }
 
+const DATASET_PREFIX: &str = "full-";
+
+fn dataset_path(path: &Path, ident: &H256) -> PathBuf {
+    path.join(format!("{}{}", DATASET_PREFIX, to_hex(ident)))
+}
+
 fn consume_cache(cache: &mut Cache, path: &Path) -> io::Result<()> {
     use std::fs::OpenOptions;
 
@@ -24,7 +24,10 @@ use keccak::{keccak_256, keccak_512, H256};
 use progpow::{generate_cdag, keccak_f800_long, keccak_f800_short, progpow, CDag};
 use seed_compute::SeedHashCompute;
 use shared::*;
-use std::io;
+use std::{
+    fs,
+    io::{self, BufWriter, Write},
+};
 
 use std::{mem, path::Path};
 
@@ -119,6 +122,27 @@ impl Light {
         self.cache.flush()?;
         Ok(self.cache.cache_path())
     }
+
+    /// Generates the full dataset of this light cache's epoch into `path`.
+    /// The dataset is written to a temporary file first, so `path` never holds a partial dataset.
+    pub fn generate_dataset(&self, path: &Path) -> io::Result<()> {
+        let tmp_path = path.with_extension("tmp");
+        let num_nodes = get_data_size(self.block_number) / NODE_BYTES;
+        {
+            let mut file = BufWriter::new(fs::File::create(&tmp_path)?);
+            write_dataset(self.cache.as_ref(), num_nodes, &mut file)?;
+            file.flush()?;
+            file.get_ref().sync_all()?;
+        }
+        fs::rename(&tmp_path, path)
+    }
+}
+
+fn write_dataset<W: Write>(cache: &[Node], num_nodes: usize, out: &mut W) -> io::Result<()> {
+    for index in 0..num_nodes {
+        out.write_all(calculate_dag_item(index as u32, cache).as_bytes())?;
+    }
+    Ok(())
 }
 
 pub fn slow_hash_block_number(block_number: u64) -> H256 {
@@ -352,6 +376,24 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_write_dataset() {
+        let tempdir = TempDir::new("").unwrap();
+        let light = NodeCacheBuilder::new(None, u64::max_value()).light(tempdir.path(), 0);
+        let cache = light.cache.as_ref();
+
+        let mut dataset = Vec::new();
+        write_dataset(cache, 4, &mut dataset).unwrap();
+
+        assert_eq!(dataset.len(), 4 * NODE_BYTES);
+        for index in 0..4 {
+            assert_eq!(
+                &dataset[index * NODE_BYTES..(index + 1) * NODE_BYTES],
+                &calculate_dag_item(index as u32, cache).as_bytes()[..]
+            );
+        }
+    }
+
     #[test]
     fn test_get_data_size() {
         // https://github.com/ethereum/wiki/wiki/Ethash/ef6b93f9596746a088ea95d01ca2778be43ae68f#data-sizes
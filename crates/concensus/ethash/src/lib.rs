@@ -50,18 +50,21 @@ pub mod progpow;
 #[cfg(not(feature = "bench"))]
 mod progpow;
 
-pub use cache::{NodeCacheBuilder, OptimizeFor};
+use cache::prune_cache_dir;
+pub use cache::{CacheFileKind, CachedEpoch, NodeCacheBuilder, OptimizeFor};
 use compute::Light;
 pub use compute::{quick_get_difficulty, slow_hash_block_number, ProofOfWork};
 use ethereum_types::{BigEndianHash, U256, U512};
 use keccak::H256;
-use parking_lot::Mutex;
+use parking_lot::{Condvar, Mutex};
 pub use seed_compute::SeedHashCompute;
 pub use shared::ETHASH_EPOCH_LENGTH;
 use std::{
+    cmp,
     convert::TryFrom,
     mem,
     path::{Path, PathBuf},
+    thread,
 };
 
 use std::sync::Arc;
@@ -73,12 +76,79 @@ struct LightCache {
     prev: Option<Arc<Light>>,
 }
 
+/// Background generation and on-disk retention of ethash caches.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct CacheConfig {
+    /// Generate the light cache of the next epoch in background.
+    pub precompute: bool,
+    /// Also generate the full dataset (DAG) of the next epoch. Requires `precompute`.
+    pub precompute_dataset: bool,
+    /// Number of most recent epochs whose files are kept in the cache directory.
+    pub keep_epochs: u64,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            precompute: false,
+            precompute_dataset: false,
+            keep_epochs: 2,
+        }
+    }
+}
+
+/// State of the ethash caches.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CacheStatus {
+    /// Most recent epoch a light cache was requested for.
+    pub current_epoch: Option<u64>,
+    /// Epochs whose light caches are held in memory.
+    pub in_memory: Vec<u64>,
+    /// File being generated in background, if any.
+    pub generating: Option<(u64, CacheFileKind)>,
+    /// Light caches and datasets found in the cache directory.
+    pub on_disk: Vec<CachedEpoch>,
+}
+
+#[derive(Default)]
+struct BackgroundState {
+    latest_epoch: Option<u64>,
+    scheduled_epoch: Option<u64>,
+    generating: Option<(u64, CacheFileKind)>,
+    ready: Option<(u64, Arc<Light>)>,
+}
+
+#[derive(Default)]
+struct Background {
+    state: Mutex<BackgroundState>,
+    generated: Condvar,
+}
+
+impl Background {
+    /// Takes the precomputed light cache for `epoch`, waiting for it if it's being generated.
+    fn take(&self, epoch: u64) -> Option<Arc<Light>> {
+        let mut state = self.state.lock();
+        loop {
+            match state.ready {
+                Some((e, _)) if e == epoch => return state.ready.take().map(|(_, light)| light),
+                _ => (),
+            }
+            if state.generating != Some((epoch, CacheFileKind::Light)) {
+                return None;
+            }
+            self.generated.wait(&mut state);
+        }
+    }
+}
+
 /// Light/Full cache manager.
 pub struct EthashManager {
     nodecache_builder: NodeCacheBuilder,
     cache: Mutex<LightCache>,
     cache_dir: PathBuf,
     progpow_transition: u64,
+    config: CacheConfig,
+    background: Arc<Background>,
 }
 
 impl EthashManager {
@@ -87,6 +157,21 @@ impl EthashManager {
         cache_dir: &Path,
         optimize_for: T,
         progpow_transition: u64,
+    ) -> EthashManager {
+        Self::with_config(
+            cache_dir,
+            optimize_for,
+            progpow_transition,
+            CacheConfig::default(),
+        )
+    }
+
+    /// Create a new instance of ethash manager with the given cache configuration
+    pub fn with_config<T: Into<Option<OptimizeFor>>>(
+        cache_dir: &Path,
+        optimize_for: T,
+        progpow_transition: u64,
+        config: CacheConfig,
     ) -> EthashManager {
         EthashManager {
             cache_dir: cache_dir.to_path_buf(),
@@ -101,6 +186,39 @@ impl EthashManager {
                 prev_epoch: None,
                 prev: None,
             }),
+            config,
+            background: Arc::new(Background::default()),
+        }
+    }
+
+    /// Returns the state of the in-memory and on-disk caches.
+    pub fn cache_status(&self) -> CacheStatus {
+        let in_memory = {
+            let lights = self.cache.lock();
+            lights
+                .recent_epoch
+                .into_iter()
+                .chain(lights.prev_epoch)
+                .collect()
+        };
+        let (current_epoch, generating) = {
+            let state = self.background.state.lock();
+            (state.latest_epoch, state.generating)
+        };
+        let max_epoch = current_epoch.unwrap_or(0) + 1;
+        let on_disk = self
+            .nodecache_builder
+            .cache_files(&self.cache_dir, max_epoch)
+            .unwrap_or_else(|e| {
+                warn!("Error listing ethash cache directory: {}", e);
+                Vec::new()
+            });
+
+        CacheStatus {
+            current_epoch,
+            in_memory,
+            generating,
+            on_disk,
         }
     }
 
@@ -142,20 +260,14 @@ impl EthashManager {
 
             match light {
                 None => {
-                    let light = match self
-                        .nodecache_builder
-                        .light_from_file(&self.cache_dir, block_number)
-                    {
-                        Ok(light) => Arc::new(light),
-                        Err(e) => {
-                            debug!("Light cache file not found for {}:{}", block_number, e);
-                            let mut light =
-                                self.nodecache_builder.light(&self.cache_dir, block_number);
-                            if let Err(e) = light.to_file() {
-                                warn!("Light cache file write error: {}", e);
-                            }
-                            Arc::new(light)
-                        }
+                    let precomputed = if block_number == self.progpow_transition {
+                        None
+                    } else {
+                        self.background.take(epoch)
+                    };
+                    let light = match precomputed {
+                        Some(light) => light,
+                        None => self.load_or_generate(block_number),
                     };
                     lights.prev_epoch = mem::replace(&mut lights.recent_epoch, Some(epoch));
                     lights.prev = mem::replace(&mut lights.recent, Some(light.clone()));
@@ -164,8 +276,121 @@ impl EthashManager {
                 Some(light) => light,
             }
         };
+        self.on_epoch(epoch);
         light.compute(header_hash, nonce, block_number)
     }
+
+    fn load_or_generate(&self, block_number: u64) -> Arc<Light> {
+        match self
+            .nodecache_builder
+            .light_from_file(&self.cache_dir, block_number)
+        {
+            Ok(light) => Arc::new(light),
+            Err(e) => {
+                debug!("Light cache file not found for {}:{}", block_number, e);
+                let mut light = self.nodecache_builder.light(&self.cache_dir, block_number);
+                if let Err(e) = light.to_file() {
+                    warn!("Light cache file write error: {}", e);
+                }
+                let latest_epoch = self.background.state.lock().latest_epoch;
+                self.prune(latest_epoch.unwrap_or(0));
+                Arc::new(light)
+            }
+        }
+    }
+
+    fn prune(&self, latest_epoch: u64) {
+        if let Err(e) = prune_cache_dir(
+            &self.nodecache_builder,
+            &self.cache_dir,
+            latest_epoch,
+            self.config.keep_epochs,
+        ) {
+            warn!("Error pruning ethash cache directory: {}", e);
+        }
+    }
+
+    /// Records `epoch` as seen and schedules precomputation of the epoch following it.
+    fn on_epoch(&self, epoch: u64) {
+        let next = epoch + 1;
+        {
+            let mut state = self.background.state.lock();
+            state.latest_epoch = cmp::max(state.latest_epoch, Some(epoch));
+            if !self.config.precompute
+                || state.latest_epoch != Some(epoch)
+                || state.scheduled_epoch >= Some(next)
+                || state.generating.is_some()
+            {
+                return;
+            }
+            state.scheduled_epoch = Some(next);
+            state.generating = Some((next, CacheFileKind::Light));
+        }
+
+        let builder = self.nodecache_builder.clone();
+        let cache_dir = self.cache_dir.clone();
+        let background = self.background.clone();
+        let config = self.config;
+        let spawned = thread::Builder::new()
+            .name("ethash-precompute".into())
+            .spawn(move || precompute(builder, cache_dir, background, config, next));
+
+        if let Err(e) = spawned {
+            warn!("Failed to spawn ethash precompute thread: {}", e);
+            let mut state = self.background.state.lock();
+            state.generating = None;
+            self.background.generated.notify_all();
+        }
+    }
+}
+
+fn precompute(
+    builder: NodeCacheBuilder,
+    cache_dir: PathBuf,
+    background: Arc<Background>,
+    config: CacheConfig,
+    epoch: u64,
+) {
+    let block_number = epoch * ETHASH_EPOCH_LENGTH;
+    debug!(target: "ethash", "Precomputing light cache of epoch {}", epoch);
+    let light = match builder.light_from_file(&cache_dir, block_number) {
+        Ok(light) => light,
+        Err(_) => {
+            let mut light = builder.light(&cache_dir, block_number);
+            if let Err(e) = light.to_file() {
+                warn!("Light cache file write error: {}", e);
+            }
+            light
+        }
+    };
+    let light = Arc::new(light);
+
+    let latest_epoch = {
+        let mut state = background.state.lock();
+        state.ready = Some((epoch, light.clone()));
+        state.generating = if config.precompute_dataset {
+            Some((epoch, CacheFileKind::Dataset))
+        } else {
+            None
+        };
+        background.generated.notify_all();
+        state.latest_epoch.unwrap_or(0)
+    };
+
+    if config.precompute_dataset {
+        let path = builder.dataset_path(&cache_dir, block_number);
+        if !path.exists() {
+            debug!(target: "ethash", "Precomputing dataset of epoch {}", epoch);
+            if let Err(e) = light.generate_dataset(&path) {
+                warn!("Dataset file write error: {}", e);
+            }
+        }
+        background.state.lock().generating = None;
+    }
+
+    if let Err(e) = prune_cache_dir(&builder, &cache_dir, latest_epoch, config.keep_epochs) {
+        warn!("Error pruning ethash cache directory: {}", e);
+    }
 }
 
 /// Convert an Ethash boundary to its original difficulty. Basically just `f(x) = 2^256 / x`.
@@ -210,6 +435,59 @@ fn test_lru() {
     assert_eq!(ethash.cache.lock().prev_epoch.unwrap(), 0);
 }
 
+#[test]
+fn test_precomputed_light_is_used() {
+    use tempdir::TempDir;
+
+    let tempdir = TempDir::new("").unwrap();
+    let config = CacheConfig {
+        precompute: true,
+        ..Default::default()
+    };
+    let ethash = EthashManager::with_config(tempdir.path(), None, u64::max_value(), config);
+    let hash = [0u8; 32];
+    ethash.compute_light(1, &hash, 1);
+    assert_eq!(ethash.background.state.lock().scheduled_epoch, Some(1));
+    // waits for the background generation instead of building the cache again
+    ethash.compute_light(ETHASH_EPOCH_LENGTH, &hash, 1);
+    assert_eq!(ethash.cache.lock().recent_epoch, Some(1));
+    assert_eq!(ethash.background.state.lock().scheduled_epoch, Some(2));
+    assert_eq!(ethash.cache_status().current_epoch, Some(1));
+}
+
+#[test]
+fn test_prune_cache_dir() {
+    use std::fs;
+    use tempdir::TempDir;
+
+    let tempdir = TempDir::new("").unwrap();
+    let builder = NodeCacheBuilder::new(None, u64::max_value());
+    for epoch in 0..4 {
+        let block_number = epoch * ETHASH_EPOCH_LENGTH;
+        let dataset = builder.dataset_path(tempdir.path(), block_number);
+        fs::write(&dataset, b"dataset").unwrap();
+        let light = dataset.to_str().unwrap().replace("full-", "");
+        fs::write(light, b"light").unwrap();
+    }
+    fs::write(tempdir.path().join("unrelated"), b"").unwrap();
+
+    prune_cache_dir(&builder, tempdir.path(), 3, 2).unwrap();
+
+    let files = builder.cache_files(tempdir.path(), 10).unwrap();
+    let remaining: Vec<_> = files.iter().map(|f| (f.epoch, f.kind)).collect();
+    assert_eq!(
+        remaining,
+        vec![
+            (2, CacheFileKind::Light),
+            (2, CacheFileKind::Dataset),
+            (3, CacheFileKind::Light),
+            (3, CacheFileKind::Dataset),
+        ]
+    );
+    assert_eq!(files[0].size, 5);
+    assert!(tempdir.path().join("unrelated").exists());
+}
+
 #[test]
 fn test_difficulty_to_boundary() {
     use ethereum_types::{BigEndianHash, H256};
@@ -21,6 +21,7 @@ extern crate jsonrpc_core;
 extern crate jsonrpc_tcp_server;
 extern crate keccak_hash as hash;
 extern crate parking_lot;
+extern crate stats;
 
 #[macro_use]
 extern crate log;
@@ -32,29 +33,43 @@ extern crate tokio;
 #[cfg(test)]
 extern crate tokio_io;
 
+mod session;
 mod traits;
 
-pub use traits::{Error, JobDispatcher, PushWorkHandler, ServiceConfiguration};
+pub use session::{Vardiff, WorkerStats, NICEHASH_PROTOCOL};
+pub use traits::{Error, Job, JobDispatcher, PushWorkHandler, ServiceConfiguration, Share};
 
-use jsonrpc_core::{to_value, Compatibility, IoDelegate, MetaIoHandler, Metadata, Params, Value};
+use jsonrpc_core::{
+    to_value, Compatibility, ErrorCode, IoDelegate, MetaIoHandler, Metadata, Params, Value,
+};
 use jsonrpc_tcp_server::{
     Dispatcher, MetaExtractor, PushMessageError, RequestContext, Server as JsonRpcServer,
     ServerBuilder as JsonRpcServerBuilder,
 };
 use std::sync::Arc;
 
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use hash::keccak;
 use parking_lot::RwLock;
+use session::{nicehash_difficulty, Sessions};
+use stats::{PrometheusMetrics, PrometheusRegistry};
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
+    str::FromStr,
+    time::Duration,
 };
 
 type RpcResult = Result<jsonrpc_core::Value, jsonrpc_core::Error>;
 
 const NOTIFY_COUNTER_INITIAL: u32 = 16;
 
+/// Time an EthereumStratum/1.0.0 session may stay open without an authorized worker.
+const UNAUTHORIZED_SESSION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Number of workers reported individually in metrics; the rest are summed up as `other`.
+const MAX_WORKER_METRICS: usize = 64;
+
 /// Container which owns rpc server and stratum implementation
 pub struct Stratum {
     /// RPC server
@@ -76,6 +91,16 @@ impl Stratum {
         addr: &SocketAddr,
         dispatcher: Arc<dyn JobDispatcher>,
         secret: Option<H256>,
+    ) -> Result<Arc<Stratum>, Error> {
+        Self::start_with_vardiff(addr, dispatcher, secret, Vardiff::default())
+    }
+
+    /// Starts the server, adjusting share difficulty of EthereumStratum/1.0.0 workers per `vardiff`.
+    pub fn start_with_vardiff(
+        addr: &SocketAddr,
+        dispatcher: Arc<dyn JobDispatcher>,
+        secret: Option<H256>,
+        vardiff: Vardiff,
     ) -> Result<Arc<Stratum>, Error> {
         let implementation = Arc::new(StratumImpl {
             subscribers: RwLock::default(),
@@ -84,6 +109,8 @@ impl Stratum {
             workers: Arc::new(RwLock::default()),
             secret,
             notify_counter: RwLock::new(NOTIFY_COUNTER_INITIAL),
+            sessions: RwLock::new(Sessions::new(vardiff)),
+            stats: RwLock::default(),
         });
 
         let mut delegate = IoDelegate::<StratumImpl, SocketMetadata>::new(implementation.clone());
@@ -107,6 +134,76 @@ impl Stratum {
 
         Ok(stratum)
     }
+
+    /// Share statistics of every worker seen since the server started.
+    pub fn worker_stats(&self) -> HashMap<String, WorkerStats> {
+        let mut stats = self.implementation.stats.read().clone();
+        for session in self.implementation.sessions.read().sessions() {
+            if let Some(ref worker) = session.worker {
+                stats.entry(worker.clone()).or_default().difficulty = session.difficulty;
+            }
+        }
+        stats
+    }
+}
+
+impl PrometheusMetrics for Stratum {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        let (stats, other) = top_workers(self.worker_stats(), MAX_WORKER_METRICS);
+        let family = |f: fn(&WorkerStats) -> i64| {
+            stats
+                .iter()
+                .map(|(worker, stats)| (worker.as_str(), f(stats)))
+                .chain(other.as_ref().map(|other| ("other", f(other))))
+                .collect::<Vec<_>>()
+        };
+        let difficulty =
+            |s: &WorkerStats| s.difficulty.low_u64().min(i64::max_value() as u64) as i64;
+
+        r.register_gauge(
+            "stratum_workers",
+            "Authorized stratum workers",
+            self.implementation.workers.read().len() as i64,
+        );
+        r.register_gauge(
+            "stratum_sessions",
+            "Open EthereumStratum/1.0.0 sessions",
+            self.implementation.sessions.read().len() as i64,
+        );
+        r.register_counter_family(
+            "stratum_shares_accepted",
+            "Shares meeting the worker's difficulty",
+            "worker",
+            &family(|s| s.accepted as i64),
+        );
+        r.register_counter_family(
+            "stratum_shares_rejected",
+            "Shares not meeting the worker's difficulty",
+            "worker",
+            &family(|s| s.rejected as i64),
+        );
+        r.register_counter_family(
+            "stratum_shares_stale",
+            "Shares submitted for outdated jobs",
+            "worker",
+            &family(|s| s.stale as i64),
+        );
+        r.register_counter_family(
+            "stratum_blocks_found",
+            "Blocks sealed by the worker",
+            "worker",
+            &family(|s| s.blocks as i64),
+        );
+        r.register_gauge_family(
+            "stratum_worker_difficulty",
+            "Current share difficulty of the worker",
+            "worker",
+            &stats
+                .iter()
+                .map(|(worker, stats)| (worker.as_str(), difficulty(stats)))
+                .collect::<Vec<_>>(),
+        );
+    }
 }
 
 impl PushWorkHandler for Stratum {
@@ -136,17 +233,23 @@ struct StratumImpl {
     secret: Option<H256>,
     /// Dispatch notify counter
     notify_counter: RwLock<u32>,
+    /// EthereumStratum/1.0.0 sessions
+    sessions: RwLock<Sessions>,
+    /// Share statistics (worker_id - stats)
+    stats: RwLock<HashMap<String, WorkerStats>>,
 }
 
 impl StratumImpl {
     /// rpc method `mining.subscribe`
-    fn subscribe(&self, _params: Params, meta: SocketMetadata) -> RpcResult {
-        use std::str::FromStr;
-
+    fn subscribe(&self, params: Params, meta: SocketMetadata) -> RpcResult {
         self.subscribers.write().push(meta.addr().clone());
         self.job_queue.write().insert(meta.addr().clone());
         trace!(target: "stratum", "Subscription request from {:?}", meta.addr());
 
+        if is_nicehash_subscription(&params) {
+            return self.subscribe_nicehash(&meta);
+        }
+
         Ok(match self.dispatcher.initial() {
             Some(initial) => match jsonrpc_core::Value::from_str(&initial) {
                 Ok(val) => Ok(val),
@@ -160,6 +263,33 @@ impl StratumImpl {
         .expect("Empty slices are serializable; qed"))
     }
 
+    /// `mining.subscribe` for EthereumStratum/1.0.0: allocates an extranonce for the session
+    fn subscribe_nicehash(&self, meta: &SocketMetadata) -> RpcResult {
+        let addr = meta.addr();
+        if let Some(tcp_dispatcher) = meta.tcp_dispatcher.as_ref() {
+            self.close_stale_sessions(tcp_dispatcher);
+        }
+        let extranonce = match self.sessions.write().open(*addr) {
+            Some(session) => session.extranonce_hex(),
+            None => {
+                warn!(target: "stratum", "No extranonce left for {}", addr);
+                let mut error = jsonrpc_core::Error::new(ErrorCode::ServerError(-1));
+                error.message = "Too many sessions".into();
+                return Err(error);
+            }
+        };
+        trace!(target: "stratum", "EthereumStratum/1.0.0 session {} for {}", extranonce, addr);
+
+        Ok(Value::Array(vec![
+            Value::Array(vec![
+                "mining.notify".into(),
+                extranonce.clone().into(),
+                NICEHASH_PROTOCOL.into(),
+            ]),
+            extranonce.into(),
+        ]))
+    }
+
     /// rpc method `mining.authorize`
     fn authorize(&self, params: Params, meta: SocketMetadata) -> RpcResult {
         params
@@ -172,7 +302,19 @@ impl StratumImpl {
                     }
                 }
                 trace!(target: "stratum", "New worker #{} registered", worker_id);
-                self.workers.write().insert(meta.addr().clone(), worker_id);
+                self.workers
+                    .write()
+                    .insert(meta.addr().clone(), worker_id.clone());
+                let is_nicehash = match self.sessions.write().get_mut(meta.addr()) {
+                    Some(session) => {
+                        session.worker = Some(worker_id);
+                        true
+                    }
+                    None => false,
+                };
+                if let (true, Some(tcp_dispatcher)) = (is_nicehash, meta.tcp_dispatcher.as_ref()) {
+                    self.push_nicehash_setup(meta.addr(), tcp_dispatcher);
+                }
                 to_value(true)
             })
             .map(|v| v.expect("Only true/false is returned and it's always serializable; qed"))
@@ -180,6 +322,12 @@ impl StratumImpl {
 
     /// rpc method `mining.submit`
     fn submit(&self, params: Params, meta: SocketMetadata) -> RpcResult {
+        if self.sessions.read().get(meta.addr()).is_some() {
+            return Ok(to_value(self.submit_share(params, &meta))
+                .expect("Only true/false is returned and it's always serializable; qed"));
+        }
+
+        let worker = self.workers.read().get(meta.addr()).cloned();
         Ok(match params {
             Params::Array(vals) => {
                 // first two elements are service messages (worker_id & job_id)
@@ -193,6 +341,7 @@ impl StratumImpl {
                         .collect::<Vec<String>>(),
                 ) {
                     Ok(()) => {
+                        self.record_share(worker, Share::Block);
                         self.update_peers(
                             &meta
                                 .tcp_dispatcher
@@ -202,6 +351,7 @@ impl StratumImpl {
                     }
                     Err(submit_err) => {
                         warn!("Error while submitting share: {:?}", submit_err);
+                        self.record_share(worker, Share::Rejected);
                         to_value(false)
                     }
                 }
@@ -214,6 +364,127 @@ impl StratumImpl {
         .expect("Only true/false is returned and it's always serializable; qed"))
     }
 
+    /// `mining.submit` for EthereumStratum/1.0.0: `[worker_id, job_id, miner_nonce]`
+    fn submit_share(&self, params: Params, meta: &SocketMetadata) -> bool {
+        let vals = match params {
+            Params::Array(vals) => vals,
+            _ => {
+                trace!(target: "stratum", "Invalid submit share format {:?}", params);
+                return false;
+            }
+        };
+        let (job_id, miner_nonce) = match (vals.get(1), vals.get(2)) {
+            (Some(Value::String(job_id)), Some(Value::String(miner_nonce))) => {
+                (job_id, miner_nonce)
+            }
+            _ => {
+                trace!(target: "stratum", "Invalid submit share format {:?}", vals);
+                return false;
+            }
+        };
+
+        let (worker, nonce, difficulty) = match self.sessions.read().get(meta.addr()) {
+            Some(session) => (
+                session.worker.clone(),
+                session.full_nonce(miner_nonce),
+                session.difficulty,
+            ),
+            None => return false,
+        };
+        let worker = match worker {
+            Some(worker) => worker,
+            None => {
+                trace!(target: "stratum", "Share from unauthorized session {}", meta.addr());
+                return false;
+            }
+        };
+        let (header_hash, nonce) = match (H256::from_str(job_id.trim_start_matches("0x")), nonce) {
+            (Ok(header_hash), Some(nonce)) => (header_hash, nonce),
+            _ => {
+                trace!(target: "stratum", "Invalid share from {}: job {}, nonce {}", worker, job_id, miner_nonce);
+                self.record_share(Some(worker), Share::Rejected);
+                return false;
+            }
+        };
+
+        let share = match self.dispatcher.submit_share(header_hash, nonce, difficulty) {
+            Ok(share) => share,
+            Err(e) => {
+                warn!(target: "stratum", "Error while checking share: {:?}", e);
+                Share::Rejected
+            }
+        };
+        trace!(target: "stratum", "Share from {} for job {}: {:?}", worker, job_id, share);
+        self.record_share(Some(worker), share);
+
+        let tcp_dispatcher = meta
+            .tcp_dispatcher
+            .as_ref()
+            .expect("tcp_dispatcher is always initialized; qed");
+        let block_difficulty = self.dispatcher.nicehash_job().map(|job| job.difficulty);
+        let retarget = self
+            .sessions
+            .write()
+            .on_share(meta.addr(), share, block_difficulty);
+        if let Some(difficulty) = retarget {
+            self.push_difficulty(meta.addr(), &difficulty, tcp_dispatcher);
+        }
+        if share == Share::Block {
+            self.update_peers(tcp_dispatcher);
+        }
+
+        match share {
+            Share::Accepted | Share::Block => true,
+            Share::Stale | Share::Rejected => false,
+        }
+    }
+
+    /// Closes sessions of disconnected peers and of peers which never authorized, releasing their extranonces
+    fn close_stale_sessions(&self, tcp_dispatcher: &Dispatcher) {
+        let mut sessions = self.sessions.write();
+        let mut closed = sessions.expire_unauthorized(UNAUTHORIZED_SESSION_TIMEOUT);
+        closed.extend(sessions.close_where(|addr, _| tcp_dispatcher.is_connected(addr)));
+        if !closed.is_empty() {
+            trace!(target: "stratum", "Closed {} stale sessions", closed.len());
+            let mut job_queue = self.job_queue.write();
+            for addr in &closed {
+                job_queue.remove(addr);
+            }
+        }
+    }
+
+    fn record_share(&self, worker: Option<String>, share: Share) {
+        if let Some(worker) = worker {
+            self.stats.write().entry(worker).or_default().record(share);
+        }
+    }
+
+    /// Sends the share difficulty and the current job to a freshly authorized session
+    fn push_nicehash_setup(&self, addr: &SocketAddr, tcp_dispatcher: &Dispatcher) {
+        let difficulty = match self.sessions.read().get(addr) {
+            Some(session) => session.difficulty,
+            None => return,
+        };
+        self.push_difficulty(addr, &difficulty, tcp_dispatcher);
+        if let Some(job) = self.dispatcher.nicehash_job() {
+            self.push_message(addr, nicehash_notify(&job), tcp_dispatcher);
+        }
+    }
+
+    fn push_difficulty(&self, addr: &SocketAddr, difficulty: &U256, tcp_dispatcher: &Dispatcher) {
+        let msg = format!(
+            "{{\"id\":null,\"method\":\"mining.set_difficulty\",\"params\":[{}]}}",
+            nicehash_difficulty(difficulty)
+        );
+        self.push_message(addr, msg, tcp_dispatcher);
+    }
+
+    fn push_message(&self, addr: &SocketAddr, msg: String, tcp_dispatcher: &Dispatcher) {
+        if let Err(e) = tcp_dispatcher.push_message(addr, msg) {
+            trace!(target: "stratum", "Failed to push message to {}: {:?}", addr, e);
+        }
+    }
+
     /// Helper method
     fn update_peers(&self, tcp_dispatcher: &Dispatcher) {
         if let Some(job) = self.dispatcher.job() {
@@ -239,10 +510,27 @@ impl StratumImpl {
                 "{{ \"id\": {}, \"method\": \"mining.notify\", \"params\": {} }}",
                 next_request_id, payload
             );
+            let nicehash_job = self.dispatcher.nicehash_job();
+            let nicehash_msg = nicehash_job.as_ref().map(nicehash_notify);
+            let retargeted = self
+                .sessions
+                .write()
+                .on_idle(nicehash_job.map(|job| job.difficulty));
             trace!(target: "stratum", "pushing work for {} workers (payload: '{}')", workers.len(), &workers_msg);
             for (addr, _) in workers.iter() {
                 trace!(target: "stratum", "pusing work to {}", addr);
-                match tcp_dispatcher.push_message(addr, workers_msg.clone()) {
+                let msg = if self.sessions.read().get(addr).is_some() {
+                    if let Some(&(_, ref difficulty)) = retargeted.iter().find(|(a, _)| a == addr) {
+                        self.push_difficulty(addr, difficulty, tcp_dispatcher);
+                    }
+                    match nicehash_msg {
+                        Some(ref msg) => msg.clone(),
+                        None => continue,
+                    }
+                } else {
+                    workers_msg.clone()
+                };
+                match tcp_dispatcher.push_message(addr, msg) {
                     Err(PushMessageError::NoSuchPeer) => {
                         trace!(target: "stratum", "Worker no longer connected: {}", addr);
                         hup_peers.insert(addr.clone());
@@ -258,13 +546,63 @@ impl StratumImpl {
 
         if !hup_peers.is_empty() {
             let mut workers = self.workers.write();
+            let mut sessions = self.sessions.write();
             for hup_peer in hup_peers {
                 workers.remove(&hup_peer);
+                sessions.close(&hup_peer);
             }
         }
+        self.close_stale_sessions(tcp_dispatcher);
     }
 }
 
+/// Splits worker statistics into the `limit` workers with the most accepted shares and the sum of the rest.
+fn top_workers(
+    stats: HashMap<String, WorkerStats>,
+    limit: usize,
+) -> (Vec<(String, WorkerStats)>, Option<WorkerStats>) {
+    let mut stats: Vec<_> = stats.into_iter().collect();
+    stats.sort_by(|(a_worker, a), (b_worker, b)| {
+        b.accepted
+            .cmp(&a.accepted)
+            .then_with(|| a_worker.cmp(b_worker))
+    });
+    if stats.len() <= limit {
+        return (stats, None);
+    }
+    let other =
+        stats
+            .split_off(limit)
+            .into_iter()
+            .fold(WorkerStats::default(), |mut other, (_, stats)| {
+                other.accepted += stats.accepted;
+                other.rejected += stats.rejected;
+                other.stale += stats.stale;
+                other.blocks += stats.blocks;
+                other
+            });
+    (stats, Some(other))
+}
+
+fn is_nicehash_subscription(params: &Params) -> bool {
+    match *params {
+        Params::Array(ref vals) => match vals.get(1) {
+            Some(Value::String(ref protocol)) => protocol
+                .to_lowercase()
+                .starts_with(&NICEHASH_PROTOCOL.to_lowercase()),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+fn nicehash_notify(job: &Job) -> String {
+    format!(
+        "{{\"id\":null,\"method\":\"mining.notify\",\"params\":[\"{:x}\",\"{:x}\",\"{:x}\",true]}}",
+        job.header_hash, job.seed_hash, job.header_hash
+    )
+}
+
 #[derive(Clone)]
 pub struct SocketMetadata {
     addr: SocketAddr,
@@ -319,6 +657,7 @@ mod tests {
     };
 
     use jsonrpc_core::futures::{future, Future};
+    use parking_lot::Mutex;
     use tokio::{
         io,
         net::TcpStream,
@@ -508,6 +847,94 @@ mod tests {
 			response);
     }
 
+    #[derive(Default)]
+    struct ShareManager {
+        shares: Mutex<Vec<(H256, u64, U256)>>,
+    }
+
+    impl JobDispatcher for ShareManager {
+        fn submit(&self, _payload: Vec<String>) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn submit_share(
+            &self,
+            header_hash: H256,
+            nonce: u64,
+            difficulty: U256,
+        ) -> Result<Share, Error> {
+            self.shares.lock().push((header_hash, nonce, difficulty));
+            Ok(Share::Accepted)
+        }
+    }
+
+    #[test]
+    fn assigns_extranonce_to_nicehash_session() {
+        let addr = "127.0.0.1:19965".parse().unwrap();
+        let stratum = Stratum::start(&addr, Arc::new(VoidManager), None).unwrap();
+        let request = r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["ethminer", "EthereumStratum/1.0.0"], "id": 1}"#;
+
+        let response = String::from_utf8(dummy_request(&addr, request)).unwrap();
+
+        assert_eq!(
+            terminated_str(
+                r#"{"jsonrpc":"2.0","result":[["mining.notify","0000","EthereumStratum/1.0.0"],"0000"],"id":1}"#
+            ),
+            response
+        );
+        assert_eq!(1, stratum.implementation.sessions.read().len());
+    }
+
+    #[test]
+    fn accounts_nicehash_shares() {
+        let addr = "127.0.0.1:19960".parse().unwrap();
+        let manager = Arc::new(ShareManager::default());
+        let stratum = Stratum::start(&addr, manager.clone(), None).unwrap();
+        let header_hash = H256::from_low_u64_be(0xcafe);
+        let requests = [
+            r#"{"jsonrpc": "2.0", "method": "mining.subscribe", "params": ["ethminer", "EthereumStratum/1.0.0"], "id": 1}"#.to_owned(),
+            r#"{"jsonrpc": "2.0", "method": "mining.authorize", "params": ["miner1", ""], "id": 2}"#.to_owned(),
+            format!(
+                r#"{{"jsonrpc": "2.0", "method": "mining.submit", "params": ["miner1", "{:x}", "0000000000ff"], "id": 3}}"#,
+                header_hash
+            ),
+        ]
+        .join("\n");
+
+        let response = String::from_utf8(dummy_request(&addr, &requests)).unwrap();
+
+        assert!(response.contains(r#"{"jsonrpc":"2.0","result":true,"id":3}"#));
+        assert!(response.contains(r#""method":"mining.set_difficulty","params":[4]"#));
+        assert_eq!(
+            *manager.shares.lock(),
+            vec![(header_hash, 0xff, U256::from(4u64 << 32))]
+        );
+        let stats = stratum.worker_stats();
+        assert_eq!(stats["miner1"].accepted, 1);
+        assert_eq!(stats["miner1"].difficulty, U256::from(4u64 << 32));
+    }
+
+    #[test]
+    fn sums_up_workers_beyond_metrics_limit() {
+        let stats = (1..=4u64)
+            .map(|accepted| {
+                let stats = WorkerStats {
+                    accepted,
+                    rejected: 1,
+                    ..Default::default()
+                };
+                (format!("miner{}", accepted), stats)
+            })
+            .collect();
+
+        let (top, other) = top_workers(stats, 2);
+
+        let top: Vec<_> = top.iter().map(|(worker, _)| worker.as_str()).collect();
+        assert_eq!(top, vec!["miner4", "miner3"]);
+        let other = other.unwrap();
+        assert_eq!((other.accepted, other.rejected), (3, 2));
+    }
+
     #[test]
     fn jsonprc_server_is_send_and_sync() {
         fn is_send_and_sync<T: Send + Sync>() {}
@@ -0,0 +1,463 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EthereumStratum/1.0.0 session state: extranonce allocation, per-worker
+//! difficulty adjustment and share statistics.
+
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use ethereum_types::U256;
+use traits::Share;
+
+/// Protocol identifier sent by miners in `mining.subscribe`.
+pub const NICEHASH_PROTOCOL: &str = "EthereumStratum/1.0.0";
+
+/// Number of bytes of the nonce fixed by the extranonce.
+pub const EXTRANONCE_BYTES: usize = 2;
+
+/// Largest factor the difficulty is changed by in a single retarget.
+const MAX_RETARGET_FACTOR: u64 = 4;
+
+/// Per-worker share difficulty adjustment settings.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Vardiff {
+    /// Share difficulty assigned to new sessions.
+    pub initial: U256,
+    /// Lowest share difficulty a worker can be assigned.
+    pub min: U256,
+    /// Desired average time between two shares of a worker.
+    pub target_interval: Duration,
+    /// Number of accepted shares after which the difficulty is re-evaluated.
+    pub retarget_shares: u32,
+}
+
+impl Default for Vardiff {
+    fn default() -> Self {
+        Vardiff {
+            // 4 NiceHash difficulty units
+            initial: U256::from(4u64 << 32),
+            min: U256::from(1u64 << 32),
+            target_interval: Duration::from_secs(15),
+            retarget_shares: 8,
+        }
+    }
+}
+
+impl Vardiff {
+    /// Difficulty for a worker that submitted `shares` shares at `current` difficulty within `elapsed`.
+    ///
+    /// A worker that submitted no shares at all is treated as if it had submitted one.
+    fn retarget(&self, current: U256, shares: u32, elapsed: Duration) -> U256 {
+        let target_ms = self.target_interval.as_millis() as u64 * cmp::max(shares, 1) as u64;
+        let elapsed_ms = cmp::max(elapsed.as_millis() as u64, 1);
+
+        let lower = cmp::max(current / MAX_RETARGET_FACTOR, U256::one());
+        let upper = current.saturating_mul(MAX_RETARGET_FACTOR.into());
+        let adjusted = current.saturating_mul(target_ms.into()) / elapsed_ms;
+
+        cmp::max(cmp::min(cmp::max(adjusted, lower), upper), self.min)
+    }
+}
+
+/// Share statistics of a single worker.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct WorkerStats {
+    /// Shares meeting the worker's difficulty.
+    pub accepted: u64,
+    /// Shares not meeting the worker's difficulty.
+    pub rejected: u64,
+    /// Shares submitted for outdated jobs.
+    pub stale: u64,
+    /// Shares which sealed a block.
+    pub blocks: u64,
+    /// Current share difficulty.
+    pub difficulty: U256,
+}
+
+impl WorkerStats {
+    /// Records the outcome of a share.
+    pub fn record(&mut self, share: Share) {
+        match share {
+            Share::Accepted => self.accepted += 1,
+            Share::Block => {
+                self.accepted += 1;
+                self.blocks += 1;
+            }
+            Share::Stale => self.stale += 1,
+            Share::Rejected => self.rejected += 1,
+        }
+    }
+}
+
+/// A connection speaking EthereumStratum/1.0.0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    /// Nonce prefix assigned to the session.
+    pub extranonce: u16,
+    /// Worker authorized on the session, if any.
+    pub worker: Option<String>,
+    /// Current share difficulty.
+    pub difficulty: U256,
+    opened: Instant,
+    window_start: Instant,
+    window_shares: u32,
+}
+
+impl Session {
+    /// Hex-encoded extranonce, as sent to the miner.
+    pub fn extranonce_hex(&self) -> String {
+        format!("{:01$x}", self.extranonce, EXTRANONCE_BYTES * 2)
+    }
+
+    /// Combines the extranonce with the hex-encoded nonce part chosen by the miner.
+    pub fn full_nonce(&self, miner_part: &str) -> Option<u64> {
+        let miner_part = miner_part.trim_start_matches("0x");
+        if miner_part.len() != (8 - EXTRANONCE_BYTES) * 2 {
+            return None;
+        }
+        let miner_part = u64::from_str_radix(miner_part, 16).ok()?;
+        Some((self.extranonce as u64) << ((8 - EXTRANONCE_BYTES) * 8) | miner_part)
+    }
+}
+
+/// Open EthereumStratum/1.0.0 sessions.
+pub struct Sessions {
+    vardiff: Vardiff,
+    sessions: HashMap<SocketAddr, Session>,
+    extranonces: HashSet<u16>,
+    next_extranonce: u16,
+}
+
+impl Sessions {
+    /// Creates an empty session set.
+    pub fn new(vardiff: Vardiff) -> Self {
+        Sessions {
+            vardiff,
+            sessions: HashMap::new(),
+            extranonces: HashSet::new(),
+            next_extranonce: 0,
+        }
+    }
+
+    /// Opens a session for `addr`, returning `None` if all extranonces are in use.
+    pub fn open(&mut self, addr: SocketAddr) -> Option<&Session> {
+        if let Some(session) = self.sessions.remove(&addr) {
+            self.extranonces.remove(&session.extranonce);
+        }
+
+        let extranonce = (0..=u16::max_value())
+            .map(|offset| self.next_extranonce.wrapping_add(offset))
+            .find(|extranonce| !self.extranonces.contains(extranonce))?;
+        self.next_extranonce = extranonce.wrapping_add(1);
+        self.extranonces.insert(extranonce);
+
+        let session = Session {
+            extranonce,
+            worker: None,
+            difficulty: cmp::max(self.vardiff.initial, self.vardiff.min),
+            opened: Instant::now(),
+            window_start: Instant::now(),
+            window_shares: 0,
+        };
+        Some(self.sessions.entry(addr).or_insert(session))
+    }
+
+    /// Closes the session of `addr`, releasing its extranonce.
+    pub fn close(&mut self, addr: &SocketAddr) {
+        if let Some(session) = self.sessions.remove(addr) {
+            self.extranonces.remove(&session.extranonce);
+        }
+    }
+
+    /// Closes sessions rejected by `keep`, releasing their extranonces.
+    ///
+    /// Returns the addresses of the closed sessions.
+    pub fn close_where<F>(&mut self, mut keep: F) -> Vec<SocketAddr>
+    where
+        F: FnMut(&SocketAddr, &Session) -> bool,
+    {
+        let closed: Vec<_> = self
+            .sessions
+            .iter()
+            .filter(|(addr, session)| !keep(addr, session))
+            .map(|(addr, _)| *addr)
+            .collect();
+        for addr in &closed {
+            self.close(addr);
+        }
+        closed
+    }
+
+    /// Closes sessions on which no worker authorized within `timeout` of subscribing.
+    pub fn expire_unauthorized(&mut self, timeout: Duration) -> Vec<SocketAddr> {
+        self.close_where(|_, session| {
+            session.worker.is_some() || session.opened.elapsed() < timeout
+        })
+    }
+
+    /// Session of `addr`, if it speaks EthereumStratum/1.0.0.
+    pub fn get(&self, addr: &SocketAddr) -> Option<&Session> {
+        self.sessions.get(addr)
+    }
+
+    /// Mutable session of `addr`.
+    pub fn get_mut(&mut self, addr: &SocketAddr) -> Option<&mut Session> {
+        self.sessions.get_mut(addr)
+    }
+
+    /// Iterates over all open sessions.
+    pub fn sessions(&self) -> impl Iterator<Item = &Session> {
+        self.sessions.values()
+    }
+
+    /// Number of open sessions.
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Records a share submitted on the session of `addr` and re-evaluates its difficulty.
+    ///
+    /// Returns the new difficulty if it changed. The difficulty never exceeds `block_difficulty`.
+    pub fn on_share(
+        &mut self,
+        addr: &SocketAddr,
+        share: Share,
+        block_difficulty: Option<U256>,
+    ) -> Option<U256> {
+        let session = self.sessions.get_mut(addr)?;
+        match share {
+            Share::Accepted | Share::Block => session.window_shares += 1,
+            Share::Stale | Share::Rejected => return None,
+        }
+
+        if session.window_shares < self.vardiff.retarget_shares {
+            return None;
+        }
+        Self::retarget(&self.vardiff, session, block_difficulty)
+    }
+
+    /// Lowers the difficulty of sessions which haven't submitted enough shares recently.
+    ///
+    /// Returns the sessions whose difficulty changed.
+    pub fn on_idle(&mut self, block_difficulty: Option<U256>) -> Vec<(SocketAddr, U256)> {
+        let vardiff = &self.vardiff;
+        let window = vardiff.target_interval * vardiff.retarget_shares;
+        self.sessions
+            .iter_mut()
+            .filter(|(_, session)| session.window_start.elapsed() > window)
+            .filter_map(|(addr, session)| {
+                Self::retarget(vardiff, session, block_difficulty).map(|d| (*addr, d))
+            })
+            .collect()
+    }
+
+    fn retarget(
+        vardiff: &Vardiff,
+        session: &mut Session,
+        block_difficulty: Option<U256>,
+    ) -> Option<U256> {
+        let mut difficulty = vardiff.retarget(
+            session.difficulty,
+            session.window_shares,
+            session.window_start.elapsed(),
+        );
+        if let Some(block_difficulty) = block_difficulty {
+            difficulty = cmp::min(difficulty, cmp::max(block_difficulty, vardiff.min));
+        }
+
+        session.window_start = Instant::now();
+        session.window_shares = 0;
+
+        if difficulty == session.difficulty {
+            return None;
+        }
+        session.difficulty = difficulty;
+        Some(difficulty)
+    }
+}
+
+/// Share difficulty expressed in NiceHash units, where difficulty 1 is 2^32 hashes.
+pub fn nicehash_difficulty(difficulty: &U256) -> f64 {
+    let hashes = difficulty.0.iter().rev().fold(0f64, |acc, word| {
+        acc * 18446744073709551616f64 + *word as f64
+    });
+    hashes / 4294967296f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addr(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    #[test]
+    fn allocates_unique_extranonces() {
+        let mut sessions = Sessions::new(Vardiff::default());
+        let first = sessions.open(addr(1)).unwrap().extranonce;
+        let second = sessions.open(addr(2)).unwrap().extranonce;
+        assert_ne!(first, second);
+
+        sessions.close(&addr(1));
+        assert!(sessions.get(&addr(1)).is_none());
+        assert_eq!(sessions.len(), 1);
+
+        // reopening a session releases its previous extranonce
+        let third = sessions.open(addr(2)).unwrap().extranonce;
+        assert_ne!(second, third);
+        assert_eq!(sessions.len(), 1);
+    }
+
+    #[test]
+    fn expires_unauthorized_sessions() {
+        let mut sessions = Sessions::new(Vardiff::default());
+        let extranonce = sessions.open(addr(1)).unwrap().extranonce;
+        sessions.open(addr(2));
+        sessions.get_mut(&addr(2)).unwrap().worker = Some("miner".into());
+
+        assert!(sessions
+            .expire_unauthorized(Duration::from_secs(60))
+            .is_empty());
+        assert_eq!(
+            sessions.expire_unauthorized(Duration::from_secs(0)),
+            vec![addr(1)]
+        );
+        assert!(sessions.get(&addr(1)).is_none());
+        assert!(sessions.get(&addr(2)).is_some());
+
+        // the extranonce of the expired session can be handed out again
+        assert_eq!(sessions.close_where(|a, _| *a != addr(2)), vec![addr(2)]);
+        sessions.next_extranonce = extranonce;
+        assert_eq!(sessions.open(addr(3)).unwrap().extranonce, extranonce);
+    }
+
+    #[test]
+    fn runs_out_of_extranonces() {
+        let mut sessions = Sessions::new(Vardiff::default());
+        for port in 0..=u16::max_value() {
+            assert!(sessions.open(addr(port)).is_some());
+        }
+        assert!(sessions
+            .open(SocketAddr::from(([127, 0, 0, 2], 1)))
+            .is_none());
+    }
+
+    #[test]
+    fn combines_nonce() {
+        let mut sessions = Sessions::new(Vardiff::default());
+        sessions.open(addr(1));
+        let session = sessions.open(addr(2)).unwrap();
+        assert_eq!(session.extranonce_hex(), "0001");
+        assert_eq!(
+            session.full_nonce("0000000000ff"),
+            Some(0x0001_0000_0000_00ff)
+        );
+        assert_eq!(
+            session.full_nonce("0xffffffffffff"),
+            Some(0x0001_ffff_ffff_ffff)
+        );
+        assert_eq!(session.full_nonce("00ff"), None);
+        assert_eq!(session.full_nonce("zzzzzzzzzzzz"), None);
+    }
+
+    #[test]
+    fn retargets_towards_share_interval() {
+        let vardiff = Vardiff {
+            initial: 1000.into(),
+            min: 10.into(),
+            target_interval: Duration::from_secs(10),
+            retarget_shares: 4,
+        };
+
+        // shares twice as fast as desired
+        assert_eq!(
+            vardiff.retarget(1000.into(), 4, Duration::from_secs(20)),
+            2000.into()
+        );
+        // shares twice as slow as desired
+        assert_eq!(
+            vardiff.retarget(1000.into(), 4, Duration::from_secs(80)),
+            500.into()
+        );
+        // changes are bounded
+        assert_eq!(
+            vardiff.retarget(1000.into(), 4, Duration::from_millis(1)),
+            4000.into()
+        );
+        assert_eq!(
+            vardiff.retarget(1000.into(), 0, Duration::from_secs(3600)),
+            250.into()
+        );
+        assert_eq!(
+            vardiff.retarget(20.into(), 0, Duration::from_secs(3600)),
+            10.into()
+        );
+    }
+
+    #[test]
+    fn caps_difficulty_at_block_difficulty() {
+        let vardiff = Vardiff {
+            initial: 1000.into(),
+            min: 10.into(),
+            target_interval: Duration::from_secs(3600),
+            retarget_shares: 1,
+        };
+        let mut sessions = Sessions::new(vardiff);
+        sessions.open(addr(1));
+
+        assert_eq!(
+            sessions.on_share(&addr(1), Share::Accepted, Some(1500.into())),
+            Some(1500.into())
+        );
+        assert_eq!(sessions.on_share(&addr(1), Share::Rejected, None), None);
+        assert_eq!(sessions.get(&addr(1)).unwrap().difficulty, 1500.into());
+    }
+
+    #[test]
+    fn records_worker_stats() {
+        let mut stats = WorkerStats::default();
+        stats.record(Share::Accepted);
+        stats.record(Share::Block);
+        stats.record(Share::Stale);
+        stats.record(Share::Rejected);
+        assert_eq!(
+            stats,
+            WorkerStats {
+                accepted: 2,
+                rejected: 1,
+                stale: 1,
+                blocks: 1,
+                difficulty: 0.into(),
+            }
+        );
+    }
+
+    #[test]
+    fn converts_to_nicehash_difficulty() {
+        assert_eq!(nicehash_difficulty(&U256::from(1u64 << 32)), 1.0);
+        assert_eq!(nicehash_difficulty(&U256::from(3u64 << 31)), 1.5);
+        assert_eq!(
+            nicehash_difficulty(&(U256::one() << 96)),
+            4294967296.0 * 4294967296.0
+        );
+    }
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
 use jsonrpc_tcp_server::PushMessageError;
 use std;
 
@@ -39,6 +39,30 @@ impl From<PushMessageError> for Error {
     }
 }
 
+/// Work package handed out to EthereumStratum/1.0.0 sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    /// Hash of the header to seal; also used as the job id.
+    pub header_hash: H256,
+    /// Seed hash of the block's epoch.
+    pub seed_hash: H256,
+    /// Difficulty a share has to meet to seal the block.
+    pub difficulty: U256,
+}
+
+/// Outcome of checking a share submitted by a worker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Share {
+    /// Share meets the worker's difficulty.
+    Accepted,
+    /// Share meets the block difficulty and the block was sealed.
+    Block,
+    /// Share was submitted for a job which is no longer current.
+    Stale,
+    /// Share doesn't meet the worker's difficulty.
+    Rejected,
+}
+
 /// Interface that can provide pow/blockchain-specific responses for the clients
 pub trait JobDispatcher: Send + Sync {
     // json for initial client handshake
@@ -55,6 +79,21 @@ pub trait JobDispatcher: Send + Sync {
     }
     // miner job result
     fn submit(&self, payload: Vec<String>) -> Result<(), Error>;
+    // current work package for EthereumStratum/1.0.0 sessions
+    fn nicehash_job(&self) -> Option<Job> {
+        None
+    }
+    // check a share for `header_hash` sealed with `nonce` against the worker's `difficulty`
+    fn submit_share(
+        &self,
+        _header_hash: H256,
+        _nonce: u64,
+        _difficulty: U256,
+    ) -> Result<Share, Error> {
+        Err(Error::Dispatch(
+            "EthereumStratum/1.0.0 shares are not supported".into(),
+        ))
+    }
 }
 
 /// Interface that can handle requests to push job for workers
@@ -40,8 +40,13 @@ pub const COL_TRACE: Option<u32> = Some(4);
 pub const COL_ACCOUNT_BLOOM: Option<u32> = Some(5);
 /// Column for general information from the local node which can persist.
 pub const COL_NODE_INFO: Option<u32> = Some(6);
+/// Column for trie nodes of retained checkpoint states. Only present in databases
+/// which were migrated to retain them.
+pub const COL_STATE_CHECKPOINTS: Option<u32> = Some(7);
 /// Number of columns in DB
-pub const NUM_COLUMNS: Option<u32> = Some(7);
+pub const NUM_COLUMNS: Option<u32> = Some(8);
+/// Number of columns in DB which doesn't retain checkpoint states
+pub const NUM_COLUMNS_WITHOUT_CHECKPOINTS: Option<u32> = Some(7);
 
 /// Modes for updating caches.
 #[derive(Clone, Copy)]
@@ -43,6 +43,8 @@ use DB_PREFIX_LEN;
 /// that the states of any block the node has ever processed will be accessible.
 pub struct ArchiveDB {
     overlay: MemoryDB<KeccakHasher, DBValue>,
+    // bytes inserted into the overlay since it was last written out.
+    overlay_size: usize,
     backing: Arc<dyn KeyValueDB>,
     latest_era: Option<u64>,
     column: Option<u32>,
@@ -57,6 +59,7 @@ impl ArchiveDB {
             .map(|val| decode::<u64>(&val).expect("decoding db value failed"));
         ArchiveDB {
             overlay: ::new_memory_db(),
+            overlay_size: 0,
             backing,
             latest_era,
             column,
@@ -85,10 +88,12 @@ impl HashDB<KeccakHasher, DBValue> for ArchiveDB {
     }
 
     fn insert(&mut self, value: &[u8]) -> H256 {
+        self.overlay_size += value.len();
         self.overlay.insert(value)
     }
 
     fn emplace(&mut self, key: H256, value: DBValue) {
+        self.overlay_size += value.len();
         self.overlay.emplace(key, value);
     }
 
@@ -123,6 +128,7 @@ impl JournalDB for ArchiveDB {
     fn boxed_clone(&self) -> Box<dyn JournalDB> {
         Box::new(ArchiveDB {
             overlay: self.overlay.clone(),
+            overlay_size: self.overlay_size,
             backing: self.backing.clone(),
             latest_era: self.latest_era,
             column: self.column.clone(),
@@ -133,6 +139,12 @@ impl JournalDB for ArchiveDB {
         sizes.insert(String::from("db_archive_overlay"), self.overlay.len());
     }
 
+    // nothing is journalled, but uncommitted changes are kept in the overlay.
+    // Removals are not subtracted, so this is an upper bound.
+    fn journal_size(&self) -> usize {
+        self.overlay_size
+    }
+
     fn is_empty(&self) -> bool {
         self.latest_era.is_none()
     }
@@ -146,6 +158,7 @@ impl JournalDB for ArchiveDB {
         let mut inserts = 0usize;
         let mut deletes = 0usize;
 
+        self.overlay_size = 0;
         for i in self.overlay.drain() {
             let (key, (value, rc)) = i;
             if rc > 0 {
@@ -179,6 +192,7 @@ impl JournalDB for ArchiveDB {
         let mut inserts = 0usize;
         let mut deletes = 0usize;
 
+        self.overlay_size = 0;
         for i in self.overlay.drain() {
             let (key, (value, rc)) = i;
             if rc > 0 {
@@ -214,6 +228,12 @@ impl JournalDB for ArchiveDB {
     }
 
     fn consolidate(&mut self, with: MemoryDB<KeccakHasher, DBValue>) {
+        self.overlay_size += with
+            .keys()
+            .keys()
+            .filter_map(|key| with.raw(key))
+            .map(|(value, _)| value.len())
+            .sum::<usize>();
         self.overlay.consolidate(with);
     }
 
@@ -261,6 +281,19 @@ mod tests {
         assert!(jdb.contains(&x));
     }
 
+    #[test]
+    fn measures_uncommitted_changes() {
+        let mut jdb = ArchiveDB::new(Arc::new(ethcore_db::InMemoryWithMetrics::create(0)), None);
+        jdb.insert(b"foo");
+        let mut changes = ::new_memory_db();
+        changes.insert(b"barbaz");
+        jdb.consolidate(changes);
+        assert_eq!(jdb.journal_size(), 9);
+
+        jdb.commit_batch(0, &keccak(b"0"), None).unwrap();
+        assert_eq!(jdb.journal_size(), 0);
+    }
+
     #[test]
     fn long_history() {
         // history is 3
@@ -22,10 +22,12 @@ extern crate lazy_static;
 extern crate ethcore;
 extern crate ethcore_builtin;
 extern crate ethereum_types;
+extern crate ethjson;
 extern crate parity_bytes as bytes;
 extern crate rustc_hex;
+extern crate serde_json;
 
-use std::str::FromStr;
+use std::{convert::TryFrom, str::FromStr};
 
 use bytes::BytesRef;
 use criterion::{Bencher, Criterion};
@@ -36,6 +38,14 @@ use rustc_hex::FromHex;
 
 lazy_static! {
     static ref BYZANTIUM_MACHINE: EthereumMachine = new_byzantium_test_machine();
+    static ref P256_VERIFY: Builtin = builtin_from_spec(
+        r#"{ "name": "p256_verify", "pricing": { "linear": { "base": 3450, "word": 0 } } }"#
+    );
+}
+
+fn builtin_from_spec(spec: &str) -> Builtin {
+    let builtin: ethjson::spec::builtin::BuiltinCompat = serde_json::from_str(spec).unwrap();
+    Builtin::try_from(ethjson::spec::Builtin::from(builtin)).unwrap()
 }
 
 struct BuiltinBenchmark<'a> {
@@ -52,6 +62,11 @@ impl<'a> BuiltinBenchmark<'a> {
             .get(&H160::from_str(builtin_address).unwrap())
             .unwrap()
             .clone();
+
+        BuiltinBenchmark::with_builtin(builtin, input, expected)
+    }
+
+    fn with_builtin(builtin: &'a Builtin, input: &str, expected: &str) -> BuiltinBenchmark<'a> {
         let input = FromHex::from_hex(input).unwrap();
         let expected = FromHex::from_hex(expected).unwrap();
 
@@ -80,6 +95,17 @@ fn bench(id: &str, builtin_address: &'static str, input: &str, expected: &str, b
     b.bench_function(id, move |b| bench.run(b));
 }
 
+fn bench_builtin(
+    id: &str,
+    builtin: &'static Builtin,
+    input: &str,
+    expected: &str,
+    b: &mut Criterion,
+) {
+    let bench = BuiltinBenchmark::with_builtin(builtin, input, expected);
+    b.bench_function(id, move |b| bench.run(b));
+}
+
 criterion_group!(
     builtin,
     ecrecover,
@@ -138,7 +164,9 @@ criterion_group!(
     alt_bn128_pairing_two_point_match_4,
     alt_bn128_pairing_ten_point_match_1,
     alt_bn128_pairing_ten_point_match_2,
-    alt_bn128_pairing_ten_point_match_3
+    alt_bn128_pairing_ten_point_match_3,
+    p256_verify_valid,
+    p256_verify_invalid
 );
 criterion_main!(builtin);
 
@@ -711,3 +739,23 @@ fn alt_bn128_pairing_ten_point_match_3(b: &mut Criterion) {
 		b,
 	);
 }
+
+fn p256_verify_valid(b: &mut Criterion) {
+    bench_builtin(
+		"p256_verify_valid",
+		&P256_VERIFY,
+		"4b949c130904506119a31ad2ca94bc9a97f56be914676fe08a5de594ea4c96bd3f1c0fd850c64221a0ed758ced45c0c60b301c82a1f143c94d8dd6bb1ad2f617d3eee03a433de2bac8717482641498dac9fd67edca3b73e6e2f0a0fbb6b1d7939fad84aeae08bbef7f010014d82cef6a09de2b0cf871b5ce0c4f1d13a59a593407cb45769f1070e2c2470fe5b1bfe63133c0b0cdc64ea4bf3791a8ec2a07fd4f",
+		"0000000000000000000000000000000000000000000000000000000000000001",
+		b,
+	);
+}
+
+fn p256_verify_invalid(b: &mut Criterion) {
+    bench_builtin(
+		"p256_verify_invalid",
+		&P256_VERIFY,
+		"4b949c130904506119a31ad2ca94bc9a97f56be914676fe08a5de594ea4c96be3f1c0fd850c64221a0ed758ced45c0c60b301c82a1f143c94d8dd6bb1ad2f617d3eee03a433de2bac8717482641498dac9fd67edca3b73e6e2f0a0fbb6b1d7939fad84aeae08bbef7f010014d82cef6a09de2b0cf871b5ce0c4f1d13a59a593407cb45769f1070e2c2470fe5b1bfe63133c0b0cdc64ea4bf3791a8ec2a07fd4f",
+		"",
+		b,
+	);
+}
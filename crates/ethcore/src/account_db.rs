@@ -28,7 +28,7 @@ use ethereum_types::Address;
 // combines a key with an address hash to ensure uniqueness.
 // leaves the first 96 bits untouched in order to support partial key lookup.
 #[inline]
-fn combine_key<'a>(address_hash: &'a H256, key: &'a H256) -> H256 {
+pub(crate) fn combine_key<'a>(address_hash: &'a H256, key: &'a H256) -> H256 {
     let mut dst = key.clone();
     {
         let last_src: &[u8] = address_hash.as_bytes();
@@ -39,9 +39,10 @@ use ethereum_types::{Address, Bloom, H256, U256};
 use engines::EthEngine;
 use error::{BlockError, Error};
 use factory::Factories;
-use state::State;
+use machine::EthereumMachine;
+use state::{parallel::ParallelExecutor, ApplyResult, State};
 use state_db::StateDB;
-use trace::Tracing;
+use trace::{FlatTrace, Tracing, VMTrace};
 use triehash::ordered_trie_root;
 use unexpected::{Mismatch, OutOfBounds};
 use verification::PreverifiedBlock;
@@ -263,17 +264,52 @@ impl<'x> OpenBlock<'x> {
         t: SignedTransaction,
         h: Option<H256>,
     ) -> Result<&TypedReceipt, Error> {
+        self.push_transaction_with(t, h, |state, env_info, machine, t, tracing| {
+            state.apply(env_info, machine, t, tracing)
+        })
+    }
+
+    // Push a transaction into the block, using `apply` to execute it.
+    fn push_transaction_with<F>(
+        &mut self,
+        t: SignedTransaction,
+        h: Option<H256>,
+        apply: F,
+    ) -> Result<&TypedReceipt, Error>
+    where
+        F: FnOnce(
+            &mut State<StateDB>,
+            &EnvInfo,
+            &EthereumMachine,
+            &SignedTransaction,
+            bool,
+        ) -> ApplyResult<FlatTrace, VMTrace>,
+    {
         if self.block.transactions_set.contains(&t.hash()) {
             return Err(TransactionError::AlreadyImported.into());
         }
 
+        let mut span = telemetry::span("execute_transaction");
+        if span.is_recording() {
+            span.set_attribute("transaction.hash", format!("{:x}", t.hash()));
+        }
         let env_info = self.block.env_info();
-        let outcome = self.block.state.apply(
+        let tracing = self.block.traces.is_enabled();
+        let outcome = apply(
+            &mut self.block.state,
             &env_info,
             self.engine.machine(),
             &t,
-            self.block.traces.is_enabled(),
-        )?;
+            tracing,
+        )
+        .map_err(|e| {
+            span.set_error();
+            e
+        })?;
+        span.set_attribute(
+            "transaction.gas_used",
+            (outcome.receipt.gas_used - env_info.gas_used).low_u64(),
+        );
 
         self.block
             .transactions_set
@@ -327,6 +363,28 @@ impl<'x> OpenBlock<'x> {
         Ok(())
     }
 
+    /// Push transactions onto the block, executing them in parallel.
+    ///
+    /// Produces the same state, receipts and traces as `push_transactions`.
+    fn push_transactions_parallel(
+        &mut self,
+        transactions: Vec<SignedTransaction>,
+    ) -> Result<(), Error> {
+        let mut executor = ParallelExecutor::new(
+            &self.block.state,
+            &self.block.env_info(),
+            self.engine.machine(),
+            &transactions,
+            self.block.traces.is_enabled(),
+        );
+        for (index, t) in transactions.into_iter().enumerate() {
+            self.push_transaction_with(t, None, |state, env_info, machine, t, tracing| {
+                executor.apply(index, state, env_info, machine, t, tracing)
+            })?;
+        }
+        Ok(())
+    }
+
     /// Populate self from a header.
     fn populate_from(&mut self, header: &Header) {
         self.block.header.set_difficulty(*header.difficulty());
@@ -531,6 +589,7 @@ pub(crate) fn enact(
     uncles: Vec<Header>,
     engine: &dyn EthEngine,
     tracing: bool,
+    parallel_transactions: bool,
     db: StateDB,
     parent: &Header,
     last_hashes: Arc<LastHashes>,
@@ -576,8 +635,12 @@ pub(crate) fn enact(
     // t_nb 8.2 transfer all field from current header to OpenBlock header that we created
     b.populate_from(&header);
 
-    // t_nb 8.3 execute transactions one by one
-    b.push_transactions(transactions)?;
+    // t_nb 8.3 execute transactions one by one, or optimistically in parallel
+    if parallel_transactions {
+        b.push_transactions_parallel(transactions)?;
+    } else {
+        b.push_transactions(transactions)?;
+    }
 
     // t_nb 8.4 Push uncles to OpenBlock and check if we have more then max uncles
     for u in uncles {
@@ -593,6 +656,7 @@ pub fn enact_verified(
     block: PreverifiedBlock,
     engine: &dyn EthEngine,
     tracing: bool,
+    parallel_transactions: bool,
     db: StateDB,
     parent: &Header,
     last_hashes: Arc<LastHashes>,
@@ -606,6 +670,7 @@ pub fn enact_verified(
         block.uncles,
         engine,
         tracing,
+        parallel_transactions,
         db,
         parent,
         last_hashes,
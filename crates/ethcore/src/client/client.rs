@@ -19,9 +19,10 @@ use std::{
     collections::{BTreeMap, HashSet, VecDeque},
     convert::TryFrom,
     io::{BufRead, BufReader},
+    iter,
     str::{from_utf8, FromStr},
     sync::{
-        atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering as AtomicOrdering},
+        atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering as AtomicOrdering},
         Arc, Weak,
     },
     time::{Duration, Instant},
@@ -57,14 +58,16 @@ use types::{
     },
     BlockNumber,
 };
+use unexpected::Mismatch;
 use vm::{EnvInfo, LastHashes};
 
 use ansi_term::Colour;
-use block::{enact_verified, ClosedBlock, Drain, LockedBlock, OpenBlock, SealedBlock};
+use block::{enact, enact_verified, ClosedBlock, Drain, LockedBlock, OpenBlock, SealedBlock};
 use call_contract::RegistryInfo;
 use client::{
     ancient_import::AncientVerifier,
     bad_blocks,
+    regeneration::Checkpoints,
     traits::{ForceUpdateSealing, TransactionRequest},
     AccountData, BadBlocks, Balance, BlockChain as BlockChainTrait, BlockChainClient,
     BlockChainReset, BlockId, BlockInfo, BlockProducer, BroadcastProposalBlock, Call,
@@ -114,6 +117,13 @@ const ANCIENT_BLOCKS_QUEUE_SIZE: usize = 4096;
 const ANCIENT_BLOCKS_BATCH_SIZE: usize = 4;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
 const MIN_HISTORY_SIZE: u64 = 8;
+// Number of blocks copied out of the chain at once when regenerating state.
+const REPLAY_CHUNK_SIZE: u64 = 64;
+// Maximal number of states regenerated at once, each takes up to the configured memory.
+const MAX_CONCURRENT_REGENERATIONS: usize = 2;
+// Pruning waits for a checkpoint state to be copied until the journal takes this many
+// times the configured history memory. The checkpoint is abandoned then.
+const CHECKPOINT_PAUSE_HISTORY_MEM_FACTOR: usize = 4;
 
 /// Report on the status of a client.
 #[derive(Default, Clone, Debug, Eq, PartialEq)]
@@ -246,6 +256,15 @@ pub struct Client {
     /// Number of eras kept in a journal before they are pruned
     history: u64,
 
+    /// Index of checkpoint states, if historical state regeneration is enabled
+    checkpoints: Option<Arc<Checkpoints>>,
+
+    /// Most recently regenerated historical state
+    regenerated: Mutex<Option<(BlockNumber, H256, StateDB)>>,
+
+    /// Number of historical states being regenerated
+    regenerating: AtomicUsize,
+
     /// An action to be done if a mode/spec_name change happens
     on_user_defaults_change: Mutex<Option<Box<dyn FnMut(Option<Mode>) + 'static + Send>>>,
 
@@ -314,6 +333,8 @@ impl Importer {
                 return 0;
             }
             trace_time!("import_verified_blocks");
+            let mut import_span = telemetry::span("import_verified_blocks");
+            import_span.set_attribute("block.count", blocks.len());
             let start = Instant::now();
 
             for block in blocks {
@@ -333,9 +354,19 @@ impl Importer {
                     invalid_blocks.insert(hash);
                     continue;
                 }
+                let mut block_span = telemetry::span("import_block");
+                block_span.set_attribute("block.number", header.number());
+                block_span.set_attribute("block.transaction_count", block.transactions.len());
+                if block_span.is_recording() {
+                    block_span.set_attribute("block.hash", format!("{:x}", hash));
+                }
                 // t_nb 7.0 check and lock block
                 match self.check_and_lock_block(&bytes, block, client) {
                     Ok((closed_block, pending)) => {
+                        block_span.set_attribute(
+                            "block.gas_used",
+                            closed_block.header.gas_used().low_u64(),
+                        );
                         imported_blocks.push(hash);
                         let transactions_len = closed_block.transactions.len();
                         trace!(target:"block_import","Block #{}({}) check pass",header.number(),header.hash());
@@ -355,6 +386,7 @@ impl Importer {
                             .accrue_block(&header, transactions_len);
                     }
                     Err(err) => {
+                        block_span.set_error();
                         self.bad_blocks.report(
                             bytes,
                             format!("{:?}", err),
@@ -416,7 +448,10 @@ impl Importer {
         }
         trace!(target:"block_import","Flush block to db");
         let db = client.db.read();
-        db.key_value().flush().expect("DB flush failed.");
+        {
+            let _span = telemetry::span("db_flush");
+            db.key_value().flush().expect("DB flush failed.");
+        }
 
         self.block_queue.resignal_verification();
         trace!(target:"block_import","Resignal verifier");
@@ -535,10 +570,12 @@ impl Importer {
         }
 
         // t_nb 8.0 Block enacting. Execution of transactions.
+        let execute_span = telemetry::span("execute_block");
         let enact_result = enact_verified(
             block,
             engine,
             client.tracedb.read().tracing_enabled(),
+            client.config.parallel_transactions,
             db,
             &parent,
             last_hashes,
@@ -546,6 +583,7 @@ impl Importer {
             is_epoch_begin,
             &mut chain.ancestry_with_metadata_iter(*header.parent_hash()),
         );
+        drop(execute_span);
 
         let mut locked_block = match enact_result {
             Ok(b) => b,
@@ -644,6 +682,7 @@ impl Importer {
     where
         B: Drain,
     {
+        let _span = telemetry::span("commit_block");
         let hash = &header.hash();
         let number = header.number();
         let parent = header.parent_hash();
@@ -714,9 +753,13 @@ impl Importer {
         }
 
         // t_nb 9.6 push state to database Transaction. (It calls journal_under from JournalDB)
-        state
-            .journal_under(&mut batch, number, hash)
-            .expect("DB commit failed");
+        {
+            let mut span = telemetry::span("journal_under");
+            let records = state
+                .journal_under(&mut batch, number, hash)
+                .expect("DB commit failed");
+            span.set_attribute("journal.records", records);
+        }
 
         let finalized: Vec<_> = ancestry_actions
             .into_iter()
@@ -766,7 +809,10 @@ impl Importer {
         state.sync_cache(&route.enacted, &route.retracted, is_canon);
         // Final commit to the DB
         // t_nb 9.11 Write Transaction to database (cached)
-        client.db.read().key_value().write_buffered(batch);
+        {
+            let _span = telemetry::span("db_write");
+            client.db.read().key_value().write_buffered(batch);
+        }
         // t_nb 9.12 commit changed to become current greatest by applying pending insertion updates (Sync point)
         chain.commit();
 
@@ -777,6 +823,7 @@ impl Importer {
         client.update_last_hashes(&parent, hash);
 
         // t_nb 9.15 prune ancient states
+        let _prune_span = telemetry::span("prune_ancient");
         if let Err(e) = client.prune_ancient(state, &chain) {
             warn!("Failed to prune ancient state data: {}", e);
         }
@@ -994,6 +1041,24 @@ impl Client {
             );
         }
 
+        let checkpoints = if config.state_regeneration.enabled {
+            let checkpoints = Checkpoints::load(&**db.key_value());
+            if checkpoints.get(0).is_none() {
+                // genesis state is the first checkpoint.
+                let genesis_hash = spec.genesis_header().hash();
+                let checkpoint_db =
+                    checkpoints.state_db(db.key_value().clone(), config.state_cache_size);
+                let mut checkpoint_db = spec.ensure_db_good(checkpoint_db, &factories)?;
+                let mut batch = DBTransaction::new();
+                checkpoint_db.journal_under(&mut batch, 0, &genesis_hash)?;
+                checkpoint_db.journal_db().backing().write(batch)?;
+                checkpoints.record(&**db.key_value(), 0, genesis_hash)?;
+            }
+            Some(Arc::new(checkpoints))
+        } else {
+            None
+        };
+
         let engine = spec.engine.clone();
 
         let awake = match config.mode {
@@ -1033,6 +1098,9 @@ impl Client {
             last_hashes: RwLock::new(VecDeque::new()),
             factories,
             history,
+            checkpoints,
+            regenerated: Mutex::new(None),
+            regenerating: AtomicUsize::new(0),
             on_user_defaults_change: Mutex::new(None),
             registrar_address,
             exit_handler: Mutex::new(None),
@@ -1217,10 +1285,16 @@ impl Client {
                 return Arc::new(res);
             }
         }
+        let last_hashes = Self::chain_last_hashes(&self.chain.read(), parent_hash);
+        let mut cached_hashes = self.last_hashes.write();
+        *cached_hashes = VecDeque::from(last_hashes.clone());
+        Arc::new(last_hashes)
+    }
+
+    fn chain_last_hashes(chain: &BlockChain, parent_hash: &H256) -> LastHashes {
         let mut last_hashes = LastHashes::new();
         last_hashes.resize(256, H256::default());
         last_hashes[0] = parent_hash.clone();
-        let chain = self.chain.read();
         for i in 0..255 {
             match chain.block_details(&last_hashes[i]) {
                 Some(details) => {
@@ -1229,9 +1303,7 @@ impl Client {
                 None => break,
             }
         }
-        let mut cached_hashes = self.last_hashes.write();
-        *cached_hashes = VecDeque::from(last_hashes.clone());
-        Arc::new(last_hashes)
+        last_hashes
     }
 
     /// This is triggered by a message coming from a block queue when the block is ready for insertion
@@ -1291,6 +1363,25 @@ impl Client {
                     trace!(target: "client", "Pruning state for ancient era {}", earliest_era);
                     match chain.block_hash(earliest_era) {
                         Some(ancient_hash) => {
+                            if !self.checkpoint_ancient(
+                                &state_db,
+                                chain,
+                                earliest_era,
+                                ancient_hash,
+                            ) {
+                                let journal_size = state_db.journal_db().journal_size();
+                                if journal_size
+                                    < self.config.history_mem * CHECKPOINT_PAUSE_HISTORY_MEM_FACTOR
+                                {
+                                    trace!(target: "pruning", "Pruning is paused at era {} (checkpoint under way); latest era={}, journal_size={}",
+                                           earliest_era, latest_era, journal_size);
+                                    break;
+                                }
+                                warn!(target: "client", "State of block #{} is not checkpointed, copying it takes too long", earliest_era);
+                                if let Some(ref checkpoints) = self.checkpoints {
+                                    checkpoints.abandon(earliest_era);
+                                }
+                            }
                             let mut batch = DBTransaction::new();
                             state_db.mark_canonical(&mut batch, earliest_era, &ancient_hash)?;
                             self.db.read().key_value().write_buffered(batch);
@@ -1308,6 +1399,205 @@ impl Client {
         Ok(())
     }
 
+    // whether the state of an ancient era can be pruned. The state of an era due for a
+    // checkpoint is copied on a background thread first, pruning waits until it is done
+    // or abandoned.
+    fn checkpoint_ancient(
+        &self,
+        state_db: &StateDB,
+        chain: &BlockChain,
+        era: u64,
+        hash: H256,
+    ) -> bool {
+        let checkpoints = match self.checkpoints {
+            Some(ref checkpoints) => checkpoints,
+            None => return true,
+        };
+        let interval = cmp::max(self.config.state_regeneration.checkpoint_interval, 1);
+        if era % interval != 0 {
+            return true;
+        }
+        let state_root = match chain.block_header_data(&hash) {
+            Some(header) => header.state_root(),
+            None => return true,
+        };
+
+        let db = self.db.read().key_value().clone();
+        let source = state_db.journal_db().boxed_clone();
+        let accountdb = self.factories.accountdb.clone();
+        checkpoints
+            .clone()
+            .copy_in_background(era, move |checkpoints, abandoned| {
+                checkpoints.insert_unless_abandoned(
+                    &*db,
+                    source.as_hash_db(),
+                    &accountdb,
+                    era,
+                    hash,
+                    state_root,
+                    abandoned,
+                )
+            })
+    }
+
+    /// Rebuild the pruned state of a canonical block by re-executing blocks on top of
+    /// the nearest checkpoint. Returns `None` if regeneration is disabled, there is no
+    /// suitable checkpoint or re-execution fails.
+    fn regenerate_state(&self, number: BlockNumber, hash: H256) -> Option<StateDB> {
+        let checkpoints = self.checkpoints.as_ref()?;
+        let (checkpoint, checkpoint_hash) = checkpoints.nearest(number)?;
+
+        let cached = {
+            let chain = self.chain.read();
+            if chain.block_hash(number) != Some(hash) {
+                return None;
+            }
+            // continue from the last regenerated state if it is closer.
+            match *self.regenerated.lock() {
+                Some((cached, cached_hash, ref state_db))
+                    if cached > checkpoint
+                        && cached <= number
+                        && chain.block_hash(cached) == Some(cached_hash) =>
+                {
+                    Some((cached, cached_hash, state_db.boxed_clone()))
+                }
+                _ => None,
+            }
+        };
+        let (from, from_hash, state_db) = match cached {
+            Some(cached) => cached,
+            None => (
+                checkpoint,
+                checkpoint_hash,
+                checkpoints.state_db(
+                    self.db.read().key_value().clone(),
+                    self.config.state_cache_size,
+                ),
+            ),
+        };
+
+        let max_blocks = self.config.state_regeneration.max_blocks;
+        if number - from > max_blocks {
+            debug!(target: "client", "State of block #{} is {} blocks past the nearest checkpoint, limit is {}", number, number - from, max_blocks);
+            return None;
+        }
+
+        if self.regenerating.fetch_add(1, AtomicOrdering::SeqCst) >= MAX_CONCURRENT_REGENERATIONS {
+            self.regenerating.fetch_sub(1, AtomicOrdering::SeqCst);
+            debug!(target: "client", "State of block #{} is not regenerated, {} states are regenerated already", number, MAX_CONCURRENT_REGENERATIONS);
+            return None;
+        }
+        let replayed = self.replay_blocks(from_hash, number, state_db);
+        self.regenerating.fetch_sub(1, AtomicOrdering::SeqCst);
+
+        match replayed {
+            Ok(state_db) => {
+                trace!(target: "client", "Regenerated state of block #{} from block #{}", number, from);
+                // replaying keeps the overlay below the memory limit, so does the cache.
+                *self.regenerated.lock() = Some((number, hash, state_db.boxed_clone()));
+                Some(state_db)
+            }
+            Err(e) => {
+                warn!(target: "client", "Failed to regenerate state of block #{}: {}", number, e);
+                None
+            }
+        }
+    }
+
+    // re-execute canonical blocks after `from_hash` up to block `to` on top of its state.
+    // Blocks are copied out of the chain in chunks and executed without holding its lock.
+    fn replay_blocks(
+        &self,
+        from_hash: H256,
+        to: BlockNumber,
+        mut state_db: StateDB,
+    ) -> Result<StateDB, EthcoreError> {
+        let eip1559_transition = self.engine.params().eip1559_transition;
+        let max_memory = self.config.state_regeneration.max_memory;
+        let (mut parent, mut last_hashes) = {
+            let chain = self.chain.read();
+            let parent = chain
+                .block_header_data(&from_hash)
+                .ok_or_else(|| format!("Missing header of block {:x}", from_hash))?
+                .decode(eip1559_transition)?;
+            (parent, Self::chain_last_hashes(&chain, &from_hash))
+        };
+
+        while parent.number() < to {
+            let last = cmp::min(parent.number() + REPLAY_CHUNK_SIZE, to);
+            let blocks = {
+                let chain = self.chain.read();
+                (parent.number() + 1..=last)
+                    .map(|number| {
+                        let block = chain
+                            .block_hash(number)
+                            .and_then(|hash| chain.block(&hash))
+                            .ok_or_else(|| format!("Missing body of block #{}", number))?;
+                        let header = block.decode_header(eip1559_transition);
+                        let is_epoch_begin = chain
+                            .epoch_transition(number - 1, *header.parent_hash())
+                            .is_some();
+                        Ok((block, header, is_epoch_begin))
+                    })
+                    .collect::<Result<Vec<_>, EthcoreError>>()?
+            };
+
+            for (block, header, is_epoch_begin) in blocks {
+                // the chain may have been reorganised since the previous chunk was copied.
+                if *header.parent_hash() != parent.hash() {
+                    return Err(format!("Block #{} is no longer canonical", parent.number()).into());
+                }
+                let transactions = block
+                    .transactions()
+                    .into_iter()
+                    .map(SignedTransaction::new)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let executed = enact(
+                    header.clone(),
+                    transactions,
+                    block.uncles(eip1559_transition),
+                    &*self.engine,
+                    false,
+                    self.config.parallel_transactions,
+                    state_db,
+                    &parent,
+                    Arc::new(last_hashes.clone()),
+                    self.factories.clone(),
+                    is_epoch_begin,
+                    // engines only look at the ancestry of newly imported blocks.
+                    &mut iter::empty::<ExtendedHeader>(),
+                )?
+                .drain();
+
+                if executed.header.state_root() != header.state_root() {
+                    return Err(BlockError::InvalidStateRoot(Mismatch {
+                        expected: *header.state_root(),
+                        found: *executed.header.state_root(),
+                    })
+                    .into());
+                }
+                state_db = executed.state.drop().1;
+                last_hashes.insert(0, header.hash());
+                last_hashes.truncate(256);
+                parent = header;
+            }
+
+            let used = state_db.journal_db().journal_size();
+            if used > max_memory {
+                return Err(format!(
+                    "Changes up to block #{} take {} bytes, limit is {}",
+                    parent.number(),
+                    used,
+                    max_memory
+                )
+                .into());
+            }
+        }
+
+        Ok(state_db)
+    }
+
     // t_nb 9.14 update last hashes. They are build in step 7.5
     fn update_last_hashes(&self, parent: &H256, hash: &H256) {
         let mut hashes = self.last_hashes.write();
@@ -1335,6 +1625,16 @@ impl Client {
         self.chain.read().clone()
     }
 
+    /// Wait until checkpoint states copied in the background are written.
+    #[cfg(test)]
+    pub fn wait_for_checkpoints(&self) {
+        if let Some(ref checkpoints) = self.checkpoints {
+            while checkpoints.is_copying() {
+                ::std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+    }
+
     /// Replace io channel. Useful for testing.
     pub fn set_io_channel(&self, io_channel: IoChannel<ClientIoMessage>) {
         *self.io_channel.write() = io_channel;
@@ -1382,10 +1682,12 @@ impl Client {
         self.block_header(id).and_then(|header| {
             let db = self.state_db.read().boxed_clone();
 
-            // early exit for pruned blocks
-            if db.is_pruned() && self.pruning_info().earliest_state > block_number {
-                return None;
-            }
+            // pruned blocks can only be regenerated from a checkpoint.
+            let db = if db.is_pruned() && self.pruning_info().earliest_state > block_number {
+                self.regenerate_state(block_number, header.hash())?
+            } else {
+                db
+            };
 
             let root = header.state_root();
             State::from_existing(
@@ -1536,6 +1838,20 @@ impl Client {
                 p,
                 processing_threads,
             )?;
+
+            // snapshot states are retained as checkpoints as well.
+            if let Some(ref checkpoints) = self.checkpoints {
+                if let Some(header) = self.chain.read().block_header_data(&start_hash) {
+                    checkpoints.insert(
+                        &**self.db.read().key_value(),
+                        db.as_hash_db(),
+                        &self.factories.accountdb,
+                        snapshot_block_number,
+                        start_hash,
+                        header.state_root(),
+                    )?;
+                }
+            }
         }
         Ok(())
     }
@@ -1739,6 +2055,23 @@ impl snapshot::DatabaseRestore for Client {
             self.engine.params().eip1559_transition,
         ));
         *tracedb = TraceDB::new(self.config.tracing.clone(), db.clone(), chain.clone());
+
+        // the restored state is the earliest one available for regeneration.
+        if let Some(ref checkpoints) = self.checkpoints {
+            *self.regenerated.lock() = None;
+            checkpoints.reload(&**db.key_value());
+            let best_hash = chain.best_block_hash();
+            if let Some(header) = chain.block_header_data(&best_hash) {
+                checkpoints.insert(
+                    &**db.key_value(),
+                    state_db.as_hash_db(),
+                    &self.factories.accountdb,
+                    header.number(),
+                    best_hash,
+                    header.state_root(),
+                )?;
+            }
+        }
         Ok(())
     }
 }
@@ -2712,8 +3045,16 @@ impl BlockChainClient for Client {
             return None;
         }
 
-        self.block_number(block)
-            .and_then(|number| self.tracedb.read().block_traces(number))
+        let number = self.block_number(block)?;
+        match block {
+            // Retracted blocks keep their traces, look them up by hash.
+            BlockId::Hash(hash) => self.tracedb.read().block_traces_by_hash(number, hash),
+            _ => self.tracedb.read().block_traces(number),
+        }
+    }
+
+    fn tracing_enabled(&self) -> bool {
+        self.tracedb.read().tracing_enabled()
     }
 
     fn last_hashes(&self) -> LastHashes {
@@ -84,6 +84,30 @@ impl Display for Mode {
     }
 }
 
+/// Historical state regeneration configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct StateRegenerationConfig {
+    /// Whether pruned states are regenerated on demand.
+    pub enabled: bool,
+    /// Number of blocks between retained checkpoint states.
+    pub checkpoint_interval: u64,
+    /// Maximal number of blocks re-executed to regenerate a single state.
+    pub max_blocks: u64,
+    /// Maximal size in bytes of the in-memory changes of re-executed blocks.
+    pub max_memory: usize,
+}
+
+impl Default for StateRegenerationConfig {
+    fn default() -> Self {
+        StateRegenerationConfig {
+            enabled: false,
+            checkpoint_interval: 8192,
+            max_blocks: 8192,
+            max_memory: 512 * 1024 * 1024,
+        }
+    }
+}
+
 /// Client configuration. Includes configs for all sub-systems.
 #[derive(Debug, PartialEq, Clone)]
 pub struct ClientConfig {
@@ -127,6 +151,10 @@ pub struct ClientConfig {
     pub max_round_blocks_to_import: usize,
     /// Snapshot configuration
     pub snapshot: SnapshotConfiguration,
+    /// Historical state regeneration configuration
+    pub state_regeneration: StateRegenerationConfig,
+    /// Execute transactions of imported blocks optimistically in parallel.
+    pub parallel_transactions: bool,
 }
 
 impl Default for ClientConfig {
@@ -153,6 +181,8 @@ impl Default for ClientConfig {
             transaction_verification_queue_size: 8192,
             max_round_blocks_to_import: 1,
             snapshot: Default::default(),
+            state_regeneration: Default::default(),
+            parallel_transactions: false,
         }
     }
 }
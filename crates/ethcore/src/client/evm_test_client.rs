@@ -233,6 +233,27 @@ impl<'a> EvmTestClient<'a> {
         &self.state
     }
 
+    /// Credits `amount` to `address` within the block described by `env_info`,
+    /// as the engine does when paying block rewards.
+    pub fn reward(
+        &mut self,
+        env_info: &client::EnvInfo,
+        address: &H160,
+        amount: &U256,
+    ) -> Result<(), EvmTestError> {
+        let schedule = self.spec.engine.machine().schedule(env_info.number);
+        let cleanup_mode = if schedule.no_empty {
+            state::CleanupMode::NoEmpty
+        } else {
+            state::CleanupMode::ForceCreate
+        };
+        self.state
+            .add_balance(address, amount, cleanup_mode)
+            .map_err(EvmTestError::Trie)?;
+        self.state.commit()?;
+        Ok(())
+    }
+
     /// Execute the VM given ActionParams and tracer.
     /// Returns amount of gas left and the output.
     pub fn call<T: trace::Tracer, V: trace::VMTracer>(
@@ -281,10 +302,23 @@ impl<'a> EvmTestClient<'a> {
         transaction: transaction::SignedTransaction,
         tracer: T,
         vm_tracer: V,
+    ) -> std::result::Result<TransactSuccess<T::Output, V::Output>, TransactErr> {
+        self.transact_with_chain_id(env_info, transaction, None, tracer, vm_tracer)
+    }
+
+    /// Executes a SignedTransaction like `transact`, rejecting it unless it is
+    /// replay protected for `chain_id` or unprotected.
+    pub fn transact_with_chain_id<T: trace::Tracer, V: trace::VMTracer>(
+        &mut self,
+        env_info: &client::EnvInfo,
+        transaction: transaction::SignedTransaction,
+        chain_id: Option<u64>,
+        tracer: T,
+        vm_tracer: V,
     ) -> std::result::Result<TransactSuccess<T::Output, V::Output>, TransactErr> {
         let initial_gas = transaction.tx().gas;
         // Verify transaction
-        let is_ok = transaction.verify_basic(true, None);
+        let is_ok = transaction.verify_basic(true, chain_id);
         if let Err(error) = is_ok {
             return Err(TransactErr {
                 state_root: *self.state.root(),
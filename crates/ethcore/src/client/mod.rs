@@ -23,6 +23,7 @@ mod config;
 #[cfg(any(test, feature = "test-helpers"))]
 mod evm_test_client;
 mod io_message;
+mod regeneration;
 #[cfg(any(test, feature = "test-helpers"))]
 pub mod test_client;
 mod trace;
@@ -34,7 +35,10 @@ pub use self::test_client::{EachBlockWith, TestBlockChainClient};
 pub use self::{
     chain_notify::{ChainMessageType, ChainNotify, ChainRoute, ChainRouteType, NewBlocks},
     client::*,
-    config::{BlockChainConfig, ClientConfig, DatabaseCompactionProfile, Mode, VMType},
+    config::{
+        BlockChainConfig, ClientConfig, DatabaseCompactionProfile, Mode, StateRegenerationConfig,
+        VMType,
+    },
     io_message::ClientIoMessage,
     traits::{
         AccountData, BadBlocks, Balance, BlockChain, BlockChainClient, BlockChainReset, BlockInfo,
@@ -0,0 +1,578 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checkpoint states used to regenerate pruned historical state.
+//!
+//! Trie nodes of a checkpoint state are copied to `COL_STATE_CHECKPOINTS` before
+//! the journal prunes them. Nodes are content-addressed, so a checkpoint only
+//! stores nodes which changed since the previous one. States between
+//! checkpoints are rebuilt by re-executing blocks on top of the nearest
+//! checkpoint in an in-memory overlay.
+
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{
+        atomic::{AtomicBool, Ordering as AtomicOrdering},
+        Arc,
+    },
+    thread,
+};
+
+use account_db::{combine_key, Factory as AccountDBFactory};
+use db::{self, DBTransaction, DBValue, KeyValueDB};
+use error::{Error, ErrorKind};
+use ethereum_types::H256;
+use ethtrie::TrieError;
+use hash::{KECCAK_EMPTY, KECCAK_NULL_RLP};
+use hash_db::HashDB;
+use journaldb::{self, Algorithm};
+use keccak_hasher::KeccakHasher;
+use kvdb::{self, DBOp};
+use parking_lot::{Mutex, RwLock};
+use rlp::{self, Rlp};
+use state_db::StateDB;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+use types::basic_account::BasicAccount;
+
+/// Prefix of keys recording checkpointed blocks.
+const CHECKPOINT_KEY_PREFIX: &[u8] = b"ckpt";
+/// Number of copied nodes written in a single batch.
+const COPY_BATCH_SIZE: usize = 16 * 1024;
+
+fn checkpoint_key(number: u64) -> Vec<u8> {
+    let mut key = CHECKPOINT_KEY_PREFIX.to_vec();
+    key.extend_from_slice(&number.to_be_bytes());
+    key
+}
+
+/// Checkpoint column exposed as the state column, so that journal databases
+/// and state rebuilders can operate on checkpoint states.
+pub struct CheckpointDB {
+    db: Arc<dyn KeyValueDB>,
+}
+
+impl CheckpointDB {
+    /// Wraps the client database.
+    pub fn new(db: Arc<dyn KeyValueDB>) -> Self {
+        CheckpointDB { db }
+    }
+
+    fn column(col: Option<u32>) -> Option<u32> {
+        if col == db::COL_STATE {
+            db::COL_STATE_CHECKPOINTS
+        } else {
+            col
+        }
+    }
+}
+
+impl kvdb::KeyValueDB for CheckpointDB {
+    fn get(&self, col: Option<u32>, key: &[u8]) -> io::Result<Option<DBValue>> {
+        self.db.get(Self::column(col), key)
+    }
+
+    fn get_by_prefix(&self, col: Option<u32>, prefix: &[u8]) -> Option<Box<[u8]>> {
+        self.db.get_by_prefix(Self::column(col), prefix)
+    }
+
+    fn write_buffered(&self, transaction: DBTransaction) {
+        let mut mapped = DBTransaction::with_capacity(transaction.ops.len());
+        for op in transaction.ops {
+            match op {
+                DBOp::Insert { col, key, value } => mapped.put(Self::column(col), &key, &value),
+                DBOp::Delete { col, key } => mapped.delete(Self::column(col), &key),
+            }
+        }
+        self.db.write_buffered(mapped)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.db.flush()
+    }
+
+    fn iter<'a>(
+        &'a self,
+        col: Option<u32>,
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        kvdb::KeyValueDB::iter(&*self.db, Self::column(col))
+    }
+
+    fn iter_from_prefix<'a>(
+        &'a self,
+        col: Option<u32>,
+        prefix: &'a [u8],
+    ) -> Box<dyn Iterator<Item = (Box<[u8]>, Box<[u8]>)> + 'a> {
+        self.db.iter_from_prefix(Self::column(col), prefix)
+    }
+
+    fn restore(&self, _new_db: &str) -> io::Result<()> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Checkpoint database cannot be restored",
+        ))
+    }
+}
+
+impl PrometheusMetrics for CheckpointDB {
+    fn prometheus_metrics(&self, _: &mut PrometheusRegistry) {}
+}
+
+impl KeyValueDB for CheckpointDB {}
+
+/// Progress of the background copy of a checkpoint state.
+enum CopyStatus {
+    /// Copying is under way, unless the flag is set.
+    Running(u64, Arc<AtomicBool>),
+    Failed(u64),
+}
+
+/// Index of blocks whose state is retained in the checkpoint column.
+#[derive(Default)]
+pub struct Checkpoints {
+    blocks: RwLock<BTreeMap<u64, H256>>,
+    copying: Mutex<Option<CopyStatus>>,
+}
+
+impl Checkpoints {
+    /// Loads the index of checkpoints stored in the given database.
+    pub fn load(db: &dyn KeyValueDB) -> Self {
+        let blocks = db
+            .iter_from_prefix(db::COL_STATE_CHECKPOINTS, CHECKPOINT_KEY_PREFIX)
+            // trie node keys sharing the prefix are longer.
+            .filter(|(key, _)| key.len() == CHECKPOINT_KEY_PREFIX.len() + 8)
+            .filter_map(|(key, value)| {
+                let mut number = [0u8; 8];
+                number.copy_from_slice(&key[CHECKPOINT_KEY_PREFIX.len()..]);
+                rlp::decode(&value)
+                    .ok()
+                    .map(|hash| (u64::from_be_bytes(number), hash))
+            })
+            .collect();
+
+        Checkpoints {
+            blocks: RwLock::new(blocks),
+            copying: Mutex::new(None),
+        }
+    }
+
+    /// Replaces the index with checkpoints stored in the given database.
+    pub fn reload(&self, db: &dyn KeyValueDB) {
+        *self.blocks.write() = Self::load(db).blocks.into_inner();
+    }
+
+    /// Hash of the checkpointed block with given number.
+    pub fn get(&self, number: u64) -> Option<H256> {
+        self.blocks.read().get(&number).cloned()
+    }
+
+    /// Latest checkpoint at or before given block.
+    pub fn nearest(&self, number: u64) -> Option<(u64, H256)> {
+        self.blocks
+            .read()
+            .range(..=number)
+            .next_back()
+            .map(|(number, hash)| (*number, *hash))
+    }
+
+    /// Archive state database over the checkpoint column. Changes made by
+    /// re-executed blocks stay in its memory overlay unless journalled.
+    pub fn state_db(&self, db: Arc<dyn KeyValueDB>, cache_size: usize) -> StateDB {
+        let backing = Arc::new(CheckpointDB::new(db));
+        StateDB::new(
+            journaldb::new(backing, Algorithm::Archive, db::COL_STATE),
+            cache_size,
+        )
+    }
+
+    /// Records the block as checkpointed. Its state must already be written.
+    pub fn record(&self, db: &dyn KeyValueDB, number: u64, hash: H256) -> Result<(), Error> {
+        let mut batch = DBTransaction::new();
+        batch.put(
+            db::COL_STATE_CHECKPOINTS,
+            &checkpoint_key(number),
+            &rlp::encode(&hash),
+        );
+        db.write(batch)?;
+        self.blocks.write().insert(number, hash);
+        Ok(())
+    }
+
+    /// Whether copying the state of given block is finished, successfully or not.
+    /// Otherwise starts copying it with `copy` on a background thread, unless it is
+    /// already under way. `copy` should stop once the flag it is given is set. The
+    /// state must not be pruned before copying is finished or abandoned.
+    pub fn copy_in_background<F>(self: Arc<Self>, number: u64, copy: F) -> bool
+    where
+        F: FnOnce(&Checkpoints, &AtomicBool) -> Result<usize, Error> + Send + 'static,
+    {
+        if self.get(number).is_some() {
+            return true;
+        }
+        let mut copying = self.copying.lock();
+        match *copying {
+            Some(CopyStatus::Running(..)) => return false,
+            Some(CopyStatus::Failed(failed)) if failed == number => return true,
+            _ => {}
+        }
+
+        let checkpoints = self.clone();
+        let abandoned = Arc::new(AtomicBool::new(false));
+        let flag = abandoned.clone();
+        let spawned = thread::Builder::new()
+            .name("checkpoint".into())
+            .spawn(move || {
+                let status = match copy(&checkpoints, &flag) {
+                    Ok(copied) => {
+                        debug!(target: "client", "Checkpointed state of block #{} ({} new nodes)", number, copied);
+                        None
+                    }
+                    Err(e) => {
+                        warn!(target: "client", "Failed to checkpoint state of block #{}: {}", number, e);
+                        Some(CopyStatus::Failed(number))
+                    }
+                };
+                let mut copying = checkpoints.copying.lock();
+                // abandoned copies were marked as failed already.
+                if let Some(CopyStatus::Running(running, _)) = *copying {
+                    if running == number {
+                        *copying = status;
+                    }
+                }
+            });
+        match spawned {
+            Ok(_) => {
+                *copying = Some(CopyStatus::Running(number, abandoned));
+                false
+            }
+            Err(e) => {
+                warn!(target: "client", "Failed to checkpoint state of block #{}: {}", number, e);
+                *copying = Some(CopyStatus::Failed(number));
+                true
+            }
+        }
+    }
+
+    /// Stops copying the state of given block in the background. The block is not
+    /// checkpointed and its state may be pruned right away.
+    pub fn abandon(&self, number: u64) {
+        let mut copying = self.copying.lock();
+        match *copying {
+            Some(CopyStatus::Running(running, ref abandoned)) if running == number => {
+                abandoned.store(true, AtomicOrdering::SeqCst)
+            }
+            _ => return,
+        }
+        *copying = Some(CopyStatus::Failed(number));
+    }
+
+    /// Whether a checkpoint state is being copied in the background.
+    pub fn is_copying(&self) -> bool {
+        match *self.copying.lock() {
+            Some(CopyStatus::Running(..)) => true,
+            _ => false,
+        }
+    }
+
+    /// Copies the state with given root from `source` and records the block as checkpointed.
+    /// Returns the number of copied trie nodes and code entries.
+    pub fn insert(
+        &self,
+        db: &dyn KeyValueDB,
+        source: &dyn HashDB<KeccakHasher, DBValue>,
+        accountdb: &AccountDBFactory,
+        number: u64,
+        hash: H256,
+        state_root: H256,
+    ) -> Result<usize, Error> {
+        let abandoned = AtomicBool::new(false);
+        self.insert_unless_abandoned(db, source, accountdb, number, hash, state_root, &abandoned)
+    }
+
+    /// Like `insert`, but fails as soon as `abandoned` is set.
+    pub fn insert_unless_abandoned(
+        &self,
+        db: &dyn KeyValueDB,
+        source: &dyn HashDB<KeccakHasher, DBValue>,
+        accountdb: &AccountDBFactory,
+        number: u64,
+        hash: H256,
+        state_root: H256,
+        abandoned: &AtomicBool,
+    ) -> Result<usize, Error> {
+        let mut copier = Copier {
+            source,
+            db,
+            accountdb,
+            abandoned,
+            batch: DBTransaction::new(),
+            pending: 0,
+            copied: 0,
+        };
+        copier.copy_trie(&state_root, None)?;
+        copier.flush()?;
+        self.record(db, number, hash)?;
+        Ok(copier.copied)
+    }
+}
+
+/// Copies trie nodes missing from the checkpoint column.
+///
+/// Nodes are written after all their descendants, so a node present in the
+/// column implies that its whole subtrie is present as well, even if copying
+/// was interrupted.
+struct Copier<'a> {
+    source: &'a dyn HashDB<KeccakHasher, DBValue>,
+    db: &'a dyn KeyValueDB,
+    accountdb: &'a AccountDBFactory,
+    abandoned: &'a AtomicBool,
+    batch: DBTransaction,
+    pending: usize,
+    copied: usize,
+}
+
+impl<'a> Copier<'a> {
+    /// Database key of a storage trie node or code of given account.
+    fn key(&self, hash: &H256, address_hash: Option<&H256>) -> H256 {
+        match (address_hash, self.accountdb) {
+            (Some(address_hash), AccountDBFactory::Mangled) => combine_key(address_hash, hash),
+            _ => *hash,
+        }
+    }
+
+    fn copy_trie(&mut self, root: &H256, address_hash: Option<&H256>) -> Result<(), Error> {
+        if *root == KECCAK_NULL_RLP {
+            return Ok(());
+        }
+        self.copy_hashed(root, address_hash, &mut Vec::new())
+    }
+
+    fn copy_hashed(
+        &mut self,
+        hash: &H256,
+        address_hash: Option<&H256>,
+        path: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        if self.abandoned.load(AtomicOrdering::Relaxed) {
+            return Err("Copying was abandoned".into());
+        }
+        let key = self.key(hash, address_hash);
+        if self
+            .db
+            .get(db::COL_STATE_CHECKPOINTS, key.as_bytes())?
+            .is_some()
+        {
+            return Ok(());
+        }
+        let node = self
+            .source
+            .get(&key)
+            .ok_or_else(|| ErrorKind::Trie(TrieError::IncompleteDatabase(*hash)))?;
+        self.copy_children(&node, address_hash, path)?;
+        self.put(key, &node)
+    }
+
+    fn copy_children(
+        &mut self,
+        node: &[u8],
+        address_hash: Option<&H256>,
+        path: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let rlp = Rlp::new(node);
+        match rlp.item_count()? {
+            // leaf or extension, with hex-prefix encoded partial path.
+            2 => {
+                let partial = rlp.at(0)?.data()?;
+                let is_leaf = partial.first().map_or(false, |first| first & 0x20 != 0);
+                let len = path.len();
+                if partial.first().map_or(false, |first| first & 0x10 != 0) {
+                    path.push(partial[0] & 0x0f);
+                }
+                for byte in partial.iter().skip(1) {
+                    path.push(byte >> 4);
+                    path.push(byte & 0x0f);
+                }
+
+                if !is_leaf {
+                    self.copy_child(&rlp.at(1)?, address_hash, path)?;
+                } else if address_hash.is_none() {
+                    self.copy_account(rlp.at(1)?.data()?, path)?;
+                }
+                path.truncate(len);
+            }
+            // branch; values are never stored in branches of secure tries.
+            17 => {
+                for i in 0..16 {
+                    path.push(i as u8);
+                    self.copy_child(&rlp.at(i)?, address_hash, path)?;
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn copy_child(
+        &mut self,
+        child: &Rlp,
+        address_hash: Option<&H256>,
+        path: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        if child.is_empty() {
+            Ok(())
+        } else if child.is_list() {
+            // nodes shorter than a hash are inlined in their parent.
+            self.copy_children(child.as_raw(), address_hash, path)
+        } else {
+            self.copy_hashed(&child.as_val()?, address_hash, path)
+        }
+    }
+
+    fn copy_account(&mut self, value: &[u8], path: &[u8]) -> Result<(), Error> {
+        if path.len() != 64 {
+            return Err(
+                ErrorKind::Decoder(rlp::DecoderError::Custom("Invalid account key")).into(),
+            );
+        }
+        let mut address_hash = H256::zero();
+        for (byte, nibbles) in address_hash.as_bytes_mut().iter_mut().zip(path.chunks(2)) {
+            *byte = (nibbles[0] << 4) | nibbles[1];
+        }
+
+        let account: BasicAccount = rlp::decode(value)?;
+        self.copy_trie(&account.storage_root, Some(&address_hash))?;
+
+        if account.code_hash != KECCAK_EMPTY {
+            let key = self.key(&account.code_hash, Some(&address_hash));
+            if self
+                .db
+                .get(db::COL_STATE_CHECKPOINTS, key.as_bytes())?
+                .is_none()
+            {
+                let code = self.source.get(&key).ok_or_else(|| {
+                    ErrorKind::Trie(TrieError::IncompleteDatabase(account.code_hash))
+                })?;
+                self.put(key, &code)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn put(&mut self, key: H256, value: &[u8]) -> Result<(), Error> {
+        self.batch
+            .put(db::COL_STATE_CHECKPOINTS, key.as_bytes(), value);
+        self.pending += 1;
+        self.copied += 1;
+        if self.pending >= COPY_BATCH_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let batch = ::std::mem::replace(&mut self.batch, DBTransaction::new());
+        self.pending = 0;
+        self.db.write(batch)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use ethereum_types::{Address, U256};
+    use state::{CleanupMode, State};
+    use test_helpers::{get_temp_state_db, new_db};
+
+    #[test]
+    fn copies_checkpoint_state() {
+        let mut state = State::new(get_temp_state_db(), U256::zero(), Default::default());
+        let a = Address::from_low_u64_be(0xa);
+        let b = Address::from_low_u64_be(0xb);
+        state
+            .add_balance(&a, &U256::from(100), CleanupMode::NoEmpty)
+            .unwrap();
+        state.init_code(&b, vec![0x60, 0x00]).unwrap();
+        state
+            .set_storage(&b, H256::from_low_u64_be(1), H256::from_low_u64_be(2))
+            .unwrap();
+        state.commit().unwrap();
+        let (root, state_db) = state.drop();
+
+        let db = new_db();
+        let checkpoints = Checkpoints::default();
+        let copied = checkpoints
+            .insert(
+                &**db.key_value(),
+                state_db.as_hash_db(),
+                &Default::default(),
+                1,
+                H256::from_low_u64_be(1),
+                root,
+            )
+            .unwrap();
+        assert!(copied > 0);
+
+        // nothing changed, nothing to copy.
+        let copied = checkpoints
+            .insert(
+                &**db.key_value(),
+                state_db.as_hash_db(),
+                &Default::default(),
+                2,
+                H256::from_low_u64_be(2),
+                root,
+            )
+            .unwrap();
+        assert_eq!(copied, 0);
+
+        let checkpoint_db = checkpoints.state_db(db.key_value().clone(), 1024);
+        let state =
+            State::from_existing(checkpoint_db, root, U256::zero(), Default::default()).unwrap();
+        assert_eq!(state.balance(&a).unwrap(), U256::from(100));
+        assert_eq!(*state.code(&b).unwrap().unwrap(), vec![0x60, 0x00]);
+        assert_eq!(
+            state.storage_at(&b, &H256::from_low_u64_be(1)).unwrap(),
+            H256::from_low_u64_be(2)
+        );
+
+        let reloaded = Checkpoints::load(&**db.key_value());
+        assert_eq!(reloaded.nearest(5), Some((2, H256::from_low_u64_be(2))));
+        assert_eq!(reloaded.nearest(1), Some((1, H256::from_low_u64_be(1))));
+        assert_eq!(reloaded.nearest(0), None);
+    }
+
+    #[test]
+    fn abandons_background_copy() {
+        let checkpoints = Arc::new(Checkpoints::default());
+        let copied = checkpoints.clone().copy_in_background(1, |_, abandoned| {
+            while !abandoned.load(AtomicOrdering::SeqCst) {
+                thread::sleep(::std::time::Duration::from_millis(1));
+            }
+            Err("Copying was abandoned".into())
+        });
+        assert!(!copied);
+        assert!(!checkpoints.clone().copy_in_background(1, |_, _| Ok(0)));
+
+        checkpoints.abandon(1);
+        assert!(!checkpoints.is_copying());
+        // the block is skipped rather than copied again.
+        assert!(checkpoints.clone().copy_in_background(1, |_, _| Ok(0)));
+        assert_eq!(checkpoints.get(1), None);
+    }
+}
@@ -1018,6 +1018,10 @@ impl BlockChainClient for TestBlockChainClient {
         self.traces.read().clone()
     }
 
+    fn tracing_enabled(&self) -> bool {
+        self.traces.read().is_some()
+    }
+
     fn transactions_to_propagate(&self) -> Vec<Arc<VerifiedTransaction>> {
         self.miner
             .ready_transactions(self, 4096, miner::PendingOrdering::Priority)
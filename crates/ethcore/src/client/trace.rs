@@ -29,13 +29,14 @@ impl TraceDatabaseExtras for BlockChain {
     fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256> {
         (self as &dyn BlockProvider)
             .block_hash(block_number)
-            .and_then(|block_hash| {
-                let tx_address = TransactionAddress {
-                    block_hash: block_hash,
-                    index: tx_position,
-                };
-                self.transaction(&tx_address)
-            })
-            .map(|tx| tx.hash())
+            .and_then(|block_hash| self.block_transaction_hash(&block_hash, tx_position))
+    }
+
+    fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256> {
+        let tx_address = TransactionAddress {
+            block_hash: *block_hash,
+            index: tx_position,
+        };
+        self.transaction(&tx_address).map(|tx| tx.hash())
     }
 }
@@ -378,6 +378,9 @@ pub trait BlockChainClient:
     /// Returns traces created by transaction from block.
     fn block_traces(&self, trace: BlockId) -> Option<Vec<LocalizedTrace>>;
 
+    /// Returns true if traces of imported blocks are recorded.
+    fn tracing_enabled(&self) -> bool;
+
     /// Get last hashes starting from best block.
     fn last_hashes(&self) -> LastHashes;
 
@@ -1155,6 +1155,15 @@ impl AuthorityRound {
         self.empty_steps.lock().insert(empty_step);
     }
 
+    fn sign_seal(&self, step: u64, hash: H256) -> Result<Signature, Error> {
+        Ok(self
+            .signer
+            .read()
+            .as_ref()
+            .ok_or(publickey::Error::InvalidAddress)?
+            .sign_seal(step, hash)?)
+    }
+
     fn generate_empty_step(&self, parent_hash: &H256) {
         let step = self.step.inner.load();
         let empty_step_rlp = empty_step_rlp(step, parent_hash);
@@ -1767,10 +1776,10 @@ impl Engine<EthereumMachine> for AuthorityRound {
                 None
             };
 
-            if let Ok(signature) = self.sign(header_seal_hash(
-                header,
-                empty_steps_rlp.as_ref().map(|e| &**e),
-            )) {
+            if let Ok(signature) = self.sign_seal(
+                step,
+                header_seal_hash(header, empty_steps_rlp.as_ref().map(|e| &**e)),
+            ) {
                 trace!(target: "engine", "generate_seal: Issuing a block for step {}.", step);
 
                 // only issue the seal if we were the first to reach the compare_exchange.
@@ -241,6 +241,8 @@ impl Clique {
             None => Err(EngineError::RequiresSigner)?,
             Some(signer) => {
                 let digest = header.hash();
+                // Clique signers legitimately seal another block at the same height after a
+                // reorg, so the seals are not guarded by slashing protection.
                 match signer.sign(digest) {
                     Ok(sig) => Ok((sig, digest)),
                     Err(e) => Err(EngineError::Custom(e.into()))?,
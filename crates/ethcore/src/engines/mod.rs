@@ -24,6 +24,7 @@ mod null_engine;
 mod validator_set;
 
 pub mod block_reward;
+pub mod remote_signer;
 pub mod signer;
 
 pub use self::{
@@ -32,6 +33,7 @@ pub use self::{
     clique::Clique,
     instant_seal::{InstantSeal, InstantSealParams},
     null_engine::NullEngine,
+    remote_signer::{RemoteSigner, RemoteSignerConfig, RemoteSignerEndpoint},
     signer::EngineSigner,
 };
 
@@ -500,6 +502,23 @@ pub trait Engine<M: Machine>: Sync + Send {
         self.snapshot_components().is_some()
     }
 
+    /// State of the proof-of-work caches kept by this engine.
+    /// Returning `None` indicates that this engine doesn't use any.
+    fn pow_cache_status(&self) -> Option<::ethash::CacheStatus> {
+        None
+    }
+
+    /// Computes the proof-of-work of `nonce` for the given block using the engine's light caches.
+    /// Returning `None` indicates that this engine doesn't use proof-of-work.
+    fn compute_pow_light(
+        &self,
+        _block_number: u64,
+        _pow_hash: &H256,
+        _nonce: u64,
+    ) -> Option<::ethash::ProofOfWork> {
+        None
+    }
+
     /// Return a new open block header timestamp based on the parent timestamp.
     fn open_block_header_timestamp(&self, parent_timestamp: u64) -> u64 {
         use std::{cmp, time};
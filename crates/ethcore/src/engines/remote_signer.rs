@@ -0,0 +1,639 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An `EngineSigner` which keeps the validator key outside of the node process.
+//!
+//! Signing requests are sent to an external service exposing a Web3Signer-style API:
+//! `POST /api/v1/eth1/sign/<address>` with the body `{"data":"0x<digest>"}`. The service
+//! is expected to sign the 32-byte digest as-is (no message prefix) and to answer with the
+//! 65-byte `r || s || v` signature encoded as hex.
+//!
+//! Block seals are guarded by a slashing-protection database which refuses to sign two
+//! different blocks for the same consensus step, or a block for a step older than the
+//! last one signed. Only AuRa seals are guarded, keyed by their step.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::{Path, PathBuf},
+    str::FromStr,
+    thread,
+    time::Duration,
+};
+
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+
+use crypto::publickey::{public_to_address, recover, Error, Public, Signature};
+use ethereum_types::{Address, H256};
+use parking_lot::Mutex;
+use rustc_hex::{FromHex, ToHex};
+
+use super::signer::EngineSigner;
+
+/// Path of the signing endpoint, relative to the service root.
+const SIGN_PATH: &str = "/api/v1/eth1/sign";
+/// Number of most recent steps kept in the slashing-protection database.
+const PROTECTION_HISTORY: usize = 1024;
+/// Delay before the first retry; doubled after every failed attempt.
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Location of the external signing service.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteSignerEndpoint {
+    /// Plain HTTP service listening on `host:port`.
+    Http {
+        /// Host name or IP address.
+        host: String,
+        /// TCP port.
+        port: u16,
+    },
+    /// HTTP service listening on a Unix domain socket.
+    Unix(PathBuf),
+}
+
+impl FromStr for RemoteSignerEndpoint {
+    type Err = String;
+
+    /// Parses `http://host[:port]`, `http://[ipv6][:port]` or `unix:/path/to/socket`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        if let Some(path) = s.strip_prefix("unix:") {
+            if path.is_empty() {
+                return Err("Missing socket path in remote signer endpoint".into());
+            }
+            return Ok(RemoteSignerEndpoint::Unix(path.into()));
+        }
+
+        if s.starts_with("https://") {
+            return Err("TLS is not supported for the remote signer; \
+                 use a Unix socket or a local TLS-terminating proxy"
+                .into());
+        }
+
+        let authority = s
+            .strip_prefix("http://")
+            .ok_or_else(|| format!("Invalid remote signer endpoint: {}", s))?
+            .trim_end_matches('/');
+
+        let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+            let end = bracketed
+                .find(']')
+                .ok_or_else(|| format!("Invalid remote signer endpoint: {}", s))?;
+            (&bracketed[..end], &bracketed[end + 1..])
+        } else {
+            match authority.rfind(':') {
+                Some(idx) => (&authority[..idx], &authority[idx..]),
+                None => (authority, ""),
+            }
+        };
+        let port = match port {
+            "" => 80,
+            port => port
+                .strip_prefix(':')
+                .and_then(|port| port.parse().ok())
+                .ok_or_else(|| format!("Invalid port in remote signer endpoint: {}", s))?,
+        };
+
+        if host.is_empty() || host.contains('/') {
+            return Err(format!("Invalid remote signer endpoint: {}", s));
+        }
+
+        Ok(RemoteSignerEndpoint::Http {
+            host: host.into(),
+            port,
+        })
+    }
+}
+
+/// Remote signer configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteSignerConfig {
+    /// Signing service location.
+    pub endpoint: RemoteSignerEndpoint,
+    /// Address of the validator key held by the service.
+    pub address: Address,
+    /// Timeout for connecting to, writing to and reading from the service.
+    pub timeout: Duration,
+    /// Number of times a failed request is retried.
+    pub retries: usize,
+    /// Path of the slashing-protection database file.
+    pub protection_db: PathBuf,
+}
+
+/// Persistent record of block seals signed per consensus step.
+///
+/// Every entry is written to disk before the signature is requested, so that a crash
+/// between the request and the response can never lead to signing a conflicting block.
+pub struct SlashingProtection {
+    path: PathBuf,
+    signed: BTreeMap<u64, H256>,
+}
+
+impl SlashingProtection {
+    /// Opens the database at the given path, creating it if it doesn't exist.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut signed = BTreeMap::new();
+        if path.exists() {
+            for (idx, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let (step, hash) = parse_record(&line).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Corrupted slashing protection record at line {}", idx + 1),
+                    )
+                })?;
+                signed.insert(step, hash);
+            }
+        }
+
+        Ok(SlashingProtection {
+            path: path.into(),
+            signed,
+        })
+    }
+
+    /// Checks whether a seal with the given hash may be signed for `step`, and records it if so.
+    pub fn check_and_record(&mut self, step: u64, hash: H256) -> Result<(), String> {
+        match self.signed.get(&step) {
+            Some(signed) if *signed == hash => return Ok(()),
+            Some(signed) => {
+                return Err(format!(
+                    "Refusing to sign block {:?} for step {}: block {:?} was already signed for this step",
+                    hash, step, signed
+                ))
+            }
+            None => {}
+        }
+
+        if let Some(last) = self.signed.keys().next_back() {
+            if step < *last {
+                return Err(format!(
+                    "Refusing to sign block {:?} for step {}: already signed a block for the later step {}",
+                    hash, step, last
+                ));
+            }
+        }
+
+        self.append(step, hash)
+            .map_err(|e| format!("Unable to write slashing protection record: {}", e))?;
+        self.signed.insert(step, hash);
+
+        if self.signed.len() > 2 * PROTECTION_HISTORY {
+            self.prune()
+                .map_err(|e| format!("Unable to prune slashing protection database: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    fn append(&self, step: u64, hash: H256) -> io::Result<()> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(format_record(step, &hash).as_bytes())?;
+        file.sync_data()
+    }
+
+    fn prune(&mut self) -> io::Result<()> {
+        while self.signed.len() > PROTECTION_HISTORY {
+            let oldest = *self
+                .signed
+                .keys()
+                .next()
+                .expect("len > PROTECTION_HISTORY; qed");
+            self.signed.remove(&oldest);
+        }
+
+        let tmp = self.path.with_extension("tmp");
+        {
+            let mut file = File::create(&tmp)?;
+            for (step, hash) in &self.signed {
+                file.write_all(format_record(*step, hash).as_bytes())?;
+            }
+            file.sync_all()?;
+        }
+        fs::rename(tmp, &self.path)
+    }
+}
+
+fn format_record(step: u64, hash: &H256) -> String {
+    format!("{} {:x}\n", step, hash)
+}
+
+fn parse_record(line: &str) -> Option<(u64, H256)> {
+    let mut parts = line.split_whitespace();
+    let step = parts.next()?.parse().ok()?;
+    let hash = H256::from_str(parts.next()?).ok()?;
+    match parts.next() {
+        None => Some((step, hash)),
+        Some(_) => None,
+    }
+}
+
+/// An `EngineSigner` backed by an external signing service.
+pub struct RemoteSigner {
+    config: RemoteSignerConfig,
+    protection: Mutex<SlashingProtection>,
+}
+
+impl RemoteSigner {
+    /// Creates a new remote signer, opening its slashing-protection database.
+    pub fn new(config: RemoteSignerConfig) -> io::Result<Self> {
+        let protection = SlashingProtection::open(&config.protection_db)?;
+        Ok(RemoteSigner {
+            config,
+            protection: Mutex::new(protection),
+        })
+    }
+
+    /// Requests a signature, retrying failed attempts with exponential backoff.
+    fn request_signature(&self, hash: H256) -> Result<Signature, Error> {
+        let mut backoff = RETRY_BACKOFF;
+        let mut attempt = 0;
+        loop {
+            match self.try_request_signature(hash) {
+                Ok(signature) => return Ok(signature),
+                Err(e) if attempt < self.config.retries => {
+                    warn!(
+                        target: "engine",
+                        "Remote signer request failed (attempt {}/{}): {}",
+                        attempt + 1,
+                        self.config.retries + 1,
+                        e
+                    );
+                    thread::sleep(backoff);
+                    backoff *= 2;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    warn!(target: "engine", "Remote signer request failed: {}", e);
+                    return Err(Error::Custom(e));
+                }
+            }
+        }
+    }
+
+    fn try_request_signature(&self, hash: H256) -> Result<Signature, String> {
+        let path = format!("{}/0x{:x}", SIGN_PATH, self.config.address);
+        let body = format!("{{\"data\":\"0x{}\"}}", hash.as_bytes().to_hex::<String>());
+
+        let response = self
+            .post(&path, &body)
+            .map_err(|e| format!("Remote signer unreachable: {}", e))?;
+        let (status, body) = parse_response(&response)?;
+        if status != 200 {
+            return Err(format!(
+                "Remote signer responded with status {}: {}",
+                status,
+                String::from_utf8_lossy(body).trim()
+            ));
+        }
+
+        let signature = parse_signature(body)?;
+        let signer = recover(&signature, &hash)
+            .map(|public| public_to_address(&public))
+            .map_err(|e| format!("Remote signer returned an invalid signature: {}", e))?;
+        if signer != self.config.address {
+            return Err(format!(
+                "Remote signer returned a signature by {:?}, expected {:?}",
+                signer, self.config.address
+            ));
+        }
+
+        Ok(signature)
+    }
+
+    fn post(&self, path: &str, body: &str) -> io::Result<Vec<u8>> {
+        let timeout = Some(self.config.timeout);
+        match self.config.endpoint {
+            RemoteSignerEndpoint::Http { ref host, port } => {
+                let addr = (host.as_str(), port)
+                    .to_socket_addrs()?
+                    .next()
+                    .ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::NotFound, "host resolved to no address")
+                    })?;
+                let stream = TcpStream::connect_timeout(&addr, self.config.timeout)?;
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)?;
+                exchange(stream, &format_request(host, path, body))
+            }
+            #[cfg(unix)]
+            RemoteSignerEndpoint::Unix(ref socket) => {
+                let stream = UnixStream::connect(socket)?;
+                stream.set_read_timeout(timeout)?;
+                stream.set_write_timeout(timeout)?;
+                exchange(stream, &format_request("localhost", path, body))
+            }
+            #[cfg(not(unix))]
+            RemoteSignerEndpoint::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unix sockets are not supported on this platform",
+            )),
+        }
+    }
+}
+
+fn format_request(host: &str, path: &str, body: &str) -> Vec<u8> {
+    let host = if host.contains(':') {
+        format!("[{}]", host)
+    } else {
+        host.to_owned()
+    };
+    // HTTP/1.0 makes the service close the connection after the response
+    // and rules out chunked transfer encoding.
+    format!(
+        "POST {} HTTP/1.0\r\n\
+         Host: {}\r\n\
+         Content-Type: application/json\r\n\
+         Accept: text/plain, application/json\r\n\
+         Content-Length: {}\r\n\r\n{}",
+        path,
+        host,
+        body.len(),
+        body
+    )
+    .into_bytes()
+}
+
+fn exchange<S: Read + Write>(mut stream: S, request: &[u8]) -> io::Result<Vec<u8>> {
+    stream.write_all(request)?;
+    stream.flush()?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response)?;
+    Ok(response)
+}
+
+fn parse_response(response: &[u8]) -> Result<(u16, &[u8]), String> {
+    let split = response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .ok_or_else(|| "Malformed response from remote signer".to_owned())?;
+    let head = String::from_utf8_lossy(&response[..split]);
+    let status = head
+        .lines()
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| "Malformed status line from remote signer".to_owned())?;
+    Ok((status, &response[split + 4..]))
+}
+
+fn parse_signature(body: &[u8]) -> Result<Signature, String> {
+    let text = String::from_utf8_lossy(body);
+    let text = text.trim().trim_matches('"');
+    let bytes: Vec<u8> = text
+        .trim_start_matches("0x")
+        .from_hex()
+        .map_err(|e| format!("Remote signer returned malformed signature: {}", e))?;
+    if bytes.len() != 65 {
+        return Err(format!(
+            "Remote signer returned a signature of {} bytes, expected 65",
+            bytes.len()
+        ));
+    }
+
+    let v = match bytes[64] {
+        v @ 0..=1 => v,
+        v @ 27..=28 => v - 27,
+        v => return Err(format!("Remote signer returned invalid recovery id {}", v)),
+    };
+
+    Ok(Signature::from_rsv(
+        &H256::from_slice(&bytes[0..32]),
+        &H256::from_slice(&bytes[32..64]),
+        v,
+    ))
+}
+
+impl EngineSigner for RemoteSigner {
+    fn sign(&self, hash: H256) -> Result<Signature, Error> {
+        self.request_signature(hash)
+    }
+
+    fn sign_seal(&self, step: u64, hash: H256) -> Result<Signature, Error> {
+        // Hold the lock for the whole request so that concurrent seals are serialized.
+        let mut protection = self.protection.lock();
+        protection.check_and_record(step, hash).map_err(|e| {
+            warn!(target: "engine", "{}", e);
+            Error::Custom(e)
+        })?;
+        self.request_signature(hash)
+    }
+
+    fn address(&self) -> Address {
+        self.config.address
+    }
+
+    fn decrypt(&self, _auth_data: &[u8], _cipher: &[u8]) -> Result<Vec<u8>, Error> {
+        warn!(target: "engine", "Decryption is not supported by the remote signer");
+        Err(Error::InvalidMessage)
+    }
+
+    fn public(&self) -> Option<Public> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{sign, Generator, KeyPair, Random};
+    use hash::keccak;
+    use std::net::TcpListener;
+    use tempdir::TempDir;
+
+    fn serve_once(
+        listener: TcpListener,
+        keypair: KeyPair,
+        v_offset: u8,
+    ) -> thread::JoinHandle<String> {
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 512];
+            while !request.ends_with(b"\"}") {
+                let read = stream.read(&mut buf).unwrap();
+                assert!(read > 0);
+                request.extend_from_slice(&buf[..read]);
+            }
+            let request = String::from_utf8(request).unwrap();
+            let data = request.rsplit("\"data\":\"0x").next().unwrap();
+            let hash = H256::from_str(&data[..64]).unwrap();
+
+            let signature = sign(keypair.secret(), &hash).unwrap();
+            let mut bytes = signature.to_vec();
+            bytes[64] += v_offset;
+            let body = format!("0x{}", bytes.to_hex::<String>());
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+            .unwrap();
+            request
+        })
+    }
+
+    fn signer(port: u16, address: Address, dir: &TempDir) -> RemoteSigner {
+        RemoteSigner::new(RemoteSignerConfig {
+            endpoint: RemoteSignerEndpoint::Http {
+                host: "127.0.0.1".into(),
+                port,
+            },
+            address,
+            timeout: Duration::from_secs(5),
+            retries: 0,
+            protection_db: dir.path().join("protection"),
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(
+            "http://127.0.0.1:9000/".parse(),
+            Ok(RemoteSignerEndpoint::Http {
+                host: "127.0.0.1".into(),
+                port: 9000
+            })
+        );
+        assert_eq!(
+            "http://signer".parse(),
+            Ok(RemoteSignerEndpoint::Http {
+                host: "signer".into(),
+                port: 80
+            })
+        );
+        assert_eq!(
+            "unix:/run/signer.sock".parse(),
+            Ok(RemoteSignerEndpoint::Unix("/run/signer.sock".into()))
+        );
+        assert!("https://signer".parse::<RemoteSignerEndpoint>().is_err());
+        assert!("signer:9000".parse::<RemoteSignerEndpoint>().is_err());
+        assert!("http://signer:port"
+            .parse::<RemoteSignerEndpoint>()
+            .is_err());
+    }
+
+    #[test]
+    fn parses_ipv6_endpoints() {
+        assert_eq!(
+            "http://[::1]:9000".parse(),
+            Ok(RemoteSignerEndpoint::Http {
+                host: "::1".into(),
+                port: 9000
+            })
+        );
+        assert_eq!(
+            "http://[fe80::1]/".parse(),
+            Ok(RemoteSignerEndpoint::Http {
+                host: "fe80::1".into(),
+                port: 80
+            })
+        );
+        assert!("http://[::1".parse::<RemoteSignerEndpoint>().is_err());
+        assert!("http://[::1]9000".parse::<RemoteSignerEndpoint>().is_err());
+        assert!(String::from_utf8(format_request("::1", "/", ""))
+            .unwrap()
+            .contains("Host: [::1]\r\n"));
+    }
+
+    #[test]
+    fn signs_through_remote_service() {
+        let keypair = Random.generate();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = serve_once(listener, keypair.clone(), 27);
+
+        let dir = TempDir::new("").unwrap();
+        let signer = signer(port, keypair.address(), &dir);
+        let hash = keccak("block");
+        let signature = signer.sign(hash).unwrap();
+
+        assert_eq!(
+            public_to_address(&recover(&signature, &hash).unwrap()),
+            keypair.address()
+        );
+        let request = server.join().unwrap();
+        assert!(request.starts_with(&format!(
+            "POST {}/0x{:x} HTTP/1.0\r\n",
+            SIGN_PATH,
+            keypair.address()
+        )));
+    }
+
+    #[test]
+    fn rejects_signature_from_wrong_key() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = serve_once(listener, Random.generate(), 0);
+
+        let dir = TempDir::new("").unwrap();
+        let signer = signer(port, Random.generate().address(), &dir);
+        assert!(signer.sign(keccak("block")).is_err());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn protection_refuses_conflicting_seals() {
+        let dir = TempDir::new("").unwrap();
+        let path = dir.path().join("protection");
+        let mut protection = SlashingProtection::open(&path).unwrap();
+
+        assert!(protection.check_and_record(10, keccak("a")).is_ok());
+        assert!(protection.check_and_record(10, keccak("a")).is_ok());
+        assert!(protection.check_and_record(10, keccak("b")).is_err());
+        assert!(protection.check_and_record(12, keccak("c")).is_ok());
+        assert!(protection.check_and_record(11, keccak("d")).is_err());
+
+        // records survive a restart
+        let mut protection = SlashingProtection::open(&path).unwrap();
+        assert!(protection.check_and_record(10, keccak("b")).is_err());
+        assert!(protection.check_and_record(12, keccak("c")).is_ok());
+        assert!(protection.check_and_record(13, keccak("e")).is_ok());
+    }
+
+    #[test]
+    fn protection_prunes_old_steps() {
+        let dir = TempDir::new("").unwrap();
+        let path = dir.path().join("protection");
+        let mut protection = SlashingProtection::open(&path).unwrap();
+
+        let steps = 2 * PROTECTION_HISTORY as u64 + 1;
+        for step in 0..steps {
+            protection
+                .check_and_record(step, keccak(step.to_be_bytes()))
+                .unwrap();
+        }
+        assert_eq!(protection.signed.len(), PROTECTION_HISTORY);
+
+        let protection = SlashingProtection::open(&path).unwrap();
+        assert_eq!(protection.signed.len(), PROTECTION_HISTORY);
+        assert_eq!(protection.signed.keys().next_back(), Some(&(steps - 1)));
+    }
+}
@@ -25,6 +25,14 @@ pub trait EngineSigner: Send + Sync {
     /// Sign a consensus message hash.
     fn sign(&self, hash: H256) -> Result<Signature, publickey::Error>;
 
+    /// Sign the seal of a block proposed at the given consensus step.
+    ///
+    /// Signers keeping slashing-protection records may refuse to sign two different
+    /// blocks for the same step. By default this is the same as `sign`.
+    fn sign_seal(&self, _step: u64, hash: H256) -> Result<Signature, publickey::Error> {
+        self.sign(hash)
+    }
+
     /// Signing address
     fn address(&self) -> Address;
 
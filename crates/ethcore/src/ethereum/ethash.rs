@@ -38,7 +38,10 @@ use engines::{
     Engine,
 };
 use error::{BlockError, Error};
-use ethash::{self, quick_get_difficulty, slow_hash_block_number, EthashManager, OptimizeFor};
+use ethash::{
+    self, quick_get_difficulty, slow_hash_block_number, CacheConfig, CacheStatus, EthashManager,
+    OptimizeFor,
+};
 use machine::EthereumMachine;
 
 /// Number of blocks in an ethash snapshot.
@@ -204,13 +207,36 @@ impl Ethash {
         ethash_params: EthashParams,
         machine: EthereumMachine,
         optimize_for: T,
+    ) -> Arc<Self> {
+        Self::with_cache_config(
+            cache_dir,
+            ethash_params,
+            machine,
+            optimize_for,
+            CacheConfig::default(),
+        )
+    }
+
+    /// Create a new instance of Ethash engine, managing the light caches and datasets
+    /// according to `cache_config`
+    pub fn with_cache_config<T: Into<Option<OptimizeFor>>>(
+        cache_dir: &Path,
+        ethash_params: EthashParams,
+        machine: EthereumMachine,
+        optimize_for: T,
+        cache_config: CacheConfig,
     ) -> Arc<Self> {
         let progpow_transition = ethash_params.progpow_transition;
 
         Arc::new(Ethash {
             ethash_params,
             machine,
-            pow: EthashManager::new(cache_dir.as_ref(), optimize_for.into(), progpow_transition),
+            pow: EthashManager::with_config(
+                cache_dir.as_ref(),
+                optimize_for.into(),
+                progpow_transition,
+                cache_config,
+            ),
         })
     }
 }
@@ -458,6 +484,19 @@ impl Engine<EthereumMachine> for Arc<Ethash> {
     fn fork_choice(&self, new: &ExtendedHeader, current: &ExtendedHeader) -> engines::ForkChoice {
         engines::total_difficulty_fork_choice(new, current)
     }
+
+    fn pow_cache_status(&self) -> Option<CacheStatus> {
+        Some(self.pow.cache_status())
+    }
+
+    fn compute_pow_light(
+        &self,
+        block_number: u64,
+        pow_hash: &H256,
+        nonce: u64,
+    ) -> Option<ethash::ProofOfWork> {
+        Some(self.pow.compute_light(block_number, &pow_hash.0, nonce))
+    }
 }
 
 impl Ethash {
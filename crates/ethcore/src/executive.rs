@@ -1787,6 +1787,85 @@ mod tests {
         assert_eq!(substate.contracts_created.len(), 0);
     }
 
+    /// Minimal EOF container with a single STOP code section.
+    const EOF_CONTAINER: &str = "ef00010100040200010001030000000000000000";
+
+    fn create_with_eof_rules(
+        factory: Factory,
+        code: Vec<u8>,
+    ) -> (vm::Result<FinalizationResult>, Option<Arc<Bytes>>) {
+        let sender = Address::from_str("cd1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+        let address = contract_address(
+            CreateContractAddress::FromSenderAndNonce,
+            &sender,
+            &U256::zero(),
+            &[],
+        )
+        .0;
+        let mut params = ActionParams::default();
+        params.address = address.clone();
+        params.sender = sender.clone();
+        params.origin = sender.clone();
+        params.gas = U256::from(100_000);
+        params.code = Some(Arc::new(code));
+        let mut state = get_temp_state_with_factory(factory);
+        let info = EnvInfo::default();
+        let machine = make_london_machine(0);
+        let mut schedule = machine.schedule(info.number);
+        schedule.eip3540 = true;
+        schedule.eip3670 = true;
+        let mut substate = Substate::new();
+
+        let result = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            ex.create(params, &mut substate, &mut NoopTracer, &mut NoopVMTracer)
+        };
+
+        (result, state.code(&address).unwrap())
+    }
+
+    evm_test! {test_create_eof_contract_from_eof_initcode: test_create_eof_contract_from_eof_initcode_int}
+    fn test_create_eof_contract_from_eof_initcode(factory: Factory) {
+        // EOF initcode with the container to deploy in its data section:
+        //
+        // 60 14 - push 20
+        // 60 1f - push 31
+        // 60 00 - push 0
+        // 39 - copy data section to memory
+        // 60 14 - push 20
+        // 60 00 - push 0
+        // f3 - return
+        let code = format!(
+            "ef0001010004020001000c03001400000000036014601f60003960146000f3{}",
+            EOF_CONTAINER
+        )
+        .from_hex()
+        .unwrap();
+
+        let (result, code) = create_with_eof_rules(factory, code);
+
+        assert!(result.is_ok());
+        assert_eq!(code.unwrap().to_vec(), EOF_CONTAINER.from_hex().unwrap());
+    }
+
+    evm_test! {test_create_eof_contract_from_legacy_initcode: test_create_eof_contract_from_legacy_initcode_int}
+    fn test_create_eof_contract_from_legacy_initcode(factory: Factory) {
+        // 73 <container> - push 20 bytes
+        // 60 00 - push 0
+        // 52 - store container in memory
+        // 60 14 - push 20
+        // 60 0c - push 12
+        // f3 - return
+        let code = format!("73{}6000526014600cf3", EOF_CONTAINER)
+            .from_hex()
+            .unwrap();
+
+        let (result, code) = create_with_eof_rules(factory, code);
+
+        assert_eq!(result.err(), Some(vm::Error::InvalidCode));
+        assert_eq!(code, None);
+    }
+
     #[test]
     fn test_call_to_precompiled_tracing() {
         // code:
@@ -3181,4 +3260,67 @@ mod tests {
         // Since transaction errored due to wasm was deactivated, result is just empty
         assert_eq!(output[..], [0u8; 20][..]);
     }
+
+    #[test]
+    fn wasm_vm_trace() {
+        let contract_address =
+            Address::from_str("cd1722f3947def4cf144679da39c4c32bdc35681").unwrap();
+        let sender = Address::from_str("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+
+        let mut state = get_temp_state();
+        state
+            .add_balance(&sender, &U256::from(10000000000u64), CleanupMode::NoEmpty)
+            .unwrap();
+        state.commit().unwrap();
+
+        let mut params = ActionParams::default();
+        params.origin = sender.clone();
+        params.sender = sender.clone();
+        params.address = contract_address.clone();
+        params.gas = U256::from(20025);
+        params.code = Some(wasm_sample_code());
+
+        let mut info = EnvInfo::default();
+        info.number = 100;
+        let machine = ::ethereum::new_kovan_wasm_test_machine();
+        let schedule = machine.schedule(info.number);
+        let mut vm_tracer = ExecutiveVMTracer::toplevel();
+
+        let FinalizationResult { gas_left, .. } = {
+            let mut ex = Executive::new(&mut state, &info, &machine, &schedule);
+            ex.call(
+                params,
+                &mut Substate::new(),
+                &mut NoopTracer,
+                &mut vm_tracer,
+            )
+            .unwrap()
+        };
+        assert_eq!(gas_left, U256::from(18433));
+
+        // Host calls are traced with their id as program counter.
+        let (gas_func, ret_func, sender_func) = (30, 20, 190);
+        let trace = vm_tracer.drain().unwrap();
+        assert!(trace
+            .operations
+            .iter()
+            .all(|op| [gas_func, ret_func, sender_func].contains(&op.pc)));
+        assert!(trace.operations.iter().any(|op| op.pc == gas_func));
+
+        let sender_op = trace
+            .operations
+            .iter()
+            .find(|op| op.pc == sender_func)
+            .expect("contract queries the sender");
+        let mem_diff = sender_op
+            .executed
+            .as_ref()
+            .and_then(|ex| ex.mem_diff.as_ref())
+            .expect("sender is written to memory");
+        assert_eq!(mem_diff.data, sender.as_bytes().to_vec());
+
+        let ret_op = trace.operations.last().unwrap();
+        assert_eq!(ret_op.pc, ret_func);
+        assert_eq!(ret_op.executed.as_ref().unwrap().gas_used, gas_left);
+    }
 }
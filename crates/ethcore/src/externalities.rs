@@ -43,6 +43,7 @@ pub struct OriginInfo {
     origin: Address,
     gas_price: U256,
     value: U256,
+    eof: bool,
 }
 
 impl OriginInfo {
@@ -55,6 +56,10 @@ impl OriginInfo {
             value: match params.value {
                 ActionValue::Transfer(val) | ActionValue::Apparent(val) => val,
             },
+            eof: params
+                .code
+                .as_ref()
+                .map_or(false, |code| evm::eof::is_eof(code)),
         }
     }
 }
@@ -431,7 +436,16 @@ where
                         false => Ok(*gas),
                     };
                 }
-                if self.schedule.eip3541 && data.get(0) == Some(&0xefu8) {
+                let starts_with_ef = data.get(0) == Some(&0xefu8);
+                // EOF initcode may only deploy a valid container, legacy initcode may not deploy one at all.
+                let invalid_code =
+                    if self.schedule.eip3540 && (self.origin_info.eof || starts_with_ef) {
+                        !self.origin_info.eof
+                            || evm::eof::Container::parse(data, self.schedule).is_err()
+                    } else {
+                        self.schedule.eip3541 && starts_with_ef
+                    };
+                if invalid_code {
                     return match self.schedule.exceptional_failed_code_deposit {
                         true => Err(vm::Error::InvalidCode),
                         false => Ok(*gas),
@@ -485,6 +499,11 @@ where
 
         self.tracer
             .trace_suicide(address, balance, refund_address.clone());
+        // The refund itself is applied on finalization, once per destroyed account.
+        if self.schedule.suicide_refund_gas > 0 {
+            self.vm_tracer
+                .trace_suicide_refund(address, self.schedule.suicide_refund_gas as i128);
+        }
         self.substate.suicides.insert(address);
 
         Ok(())
@@ -508,10 +527,12 @@ where
 
     fn add_sstore_refund(&mut self, value: usize) {
         self.substate.sstore_clears_refund += value as i128;
+        self.vm_tracer.trace_refund(value as i128);
     }
 
     fn sub_sstore_refund(&mut self, value: usize) {
         self.substate.sstore_clears_refund -= value as i128;
+        self.vm_tracer.trace_refund(-(value as i128));
     }
 
     fn trace_next_instruction(&mut self, pc: usize, instruction: u8, current_gas: U256) -> bool {
@@ -576,6 +597,7 @@ mod tests {
             origin: Address::zero(),
             gas_price: U256::zero(),
             value: U256::zero(),
+            eof: false,
         }
     }
 
@@ -834,6 +856,44 @@ mod tests {
         assert_eq!(setup.sub_state.suicides.len(), 1);
     }
 
+    #[test]
+    fn should_trace_suicide_refund_once() {
+        use trace::{Eip3155Tracer, VMTracer};
+
+        let refund_account = &Address::default();
+
+        let mut setup = TestSetup::new();
+        let state = &mut setup.state;
+        let mut tracer = NoopTracer;
+        let mut vm_tracer = Eip3155Tracer::toplevel();
+        vm_tracer.prepare_subtrace(&[]);
+        let origin_info = get_test_origin();
+
+        {
+            let mut ext = Externalities::new(
+                state,
+                &setup.env_info,
+                &setup.machine,
+                &setup.schedule,
+                0,
+                0,
+                &origin_info,
+                &mut setup.sub_state,
+                OutputPolicy::InitContract,
+                &mut tracer,
+                &mut vm_tracer,
+                false,
+            );
+            ext.suicide(refund_account).unwrap();
+            ext.suicide(refund_account).unwrap();
+        }
+
+        vm_tracer.trace_next_instruction(0, 0x00, 0.into());
+        vm_tracer.done_subtrace();
+        let steps = vm_tracer.drain().unwrap();
+        assert_eq!(steps[0].refund, setup.schedule.suicide_refund_gas as u64);
+    }
+
     #[test]
     fn can_create() {
         use std::str::FromStr;
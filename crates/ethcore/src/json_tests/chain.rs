@@ -16,7 +16,7 @@
 
 use super::HookType;
 use client::{
-    Balance, BlockChainClient, BlockId, ChainInfo, Client, ClientConfig, EvmTestClient,
+    BadBlocks, Balance, BlockChainClient, BlockId, ChainInfo, Client, ClientConfig, EvmTestClient,
     ImportBlock, Nonce, StateOrBlock,
 };
 use ethereum_types::{H256, U256};
@@ -25,11 +25,54 @@ use io::IoChannel;
 use log::warn;
 use miner::Miner;
 use rustc_hex::ToHex;
-use spec::Genesis;
+use spec::{Genesis, Spec};
 use std::{path::Path, sync::Arc};
 use test_helpers;
+use types::{block_status::BlockStatus, BlockNumber};
 use verification::{queue::kind::blocks::Unverified, VerifierType};
 
+/// Outcome of importing a single block of a blockchain test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockResult {
+    /// Number of the block, if its RLP could be decoded.
+    pub number: Option<BlockNumber>,
+    /// Hash of the block, if its RLP could be decoded.
+    pub hash: Option<H256>,
+    /// Whether the test expects the block to be valid.
+    pub expected: bool,
+    /// Whether the block was imported into the chain.
+    pub imported: bool,
+    /// Reason the block was rejected with, if known.
+    pub error: Option<String>,
+}
+
+impl BlockResult {
+    /// Returns true if the block was imported as expected by the test.
+    pub fn is_success(&self) -> bool {
+        self.expected == self.imported
+    }
+}
+
+/// Outcome of a blockchain test.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChainResult {
+    /// Results of the test blocks, in import order.
+    pub blocks: Vec<BlockResult>,
+    /// Hash of the best block after the import.
+    pub best_block: H256,
+    /// Hash of the best block expected by the test.
+    pub expected_best_block: H256,
+    /// Whether the post-state matches the expected one.
+    pub post_state: bool,
+}
+
+impl ChainResult {
+    /// Returns true if the resulting chain matches the test expectations.
+    pub fn is_success(&self) -> bool {
+        self.best_block == self.expected_best_block && self.post_state
+    }
+}
+
 fn check_poststate(
     client: &Arc<Client>,
     test_name: &str,
@@ -162,77 +205,28 @@ pub fn json_chain_test<H: FnMut(&str, HookType)>(
                 }
             };
 
-            let spec = {
-                let mut spec = match EvmTestClient::spec_from_json(&blockchain.network) {
-                    Some(spec) => spec,
-                    None => {
-                        info!(
-                            "   SKIPPED {:?} {:?} - Unimplemented chainspec ",
-                            name, blockchain.network
-                        );
-                        continue;
-                    }
-                };
-
-                let genesis = Genesis::from(blockchain.genesis());
-                let state = From::from(blockchain.pre_state.clone());
-                spec.set_genesis_state(state)
-                    .expect("Failed to overwrite genesis state");
-                spec.overwrite_genesis_params(genesis);
-                spec
+            let spec = match EvmTestClient::spec_from_json(&blockchain.network) {
+                Some(spec) => spec,
+                None => {
+                    info!(
+                        "   SKIPPED {:?} {:?} - Unimplemented chainspec ",
+                        name, blockchain.network
+                    );
+                    continue;
+                }
             };
 
             start_stop_hook(&name, HookType::OnStart);
 
-            {
-                let db = test_helpers::new_db();
-                let mut config = ClientConfig::default();
-                if ethjson::blockchain::Engine::NoProof == blockchain.engine {
-                    config.verifier_type = VerifierType::CanonNoSeal;
-                    config.check_seal = false;
-                }
-                config.history = 8;
-                config.queue.verifier_settings.num_verifiers = 1;
-                let client = Client::new(
-                    config,
-                    &spec,
-                    db,
-                    Arc::new(Miner::new_for_tests(&spec, None)),
-                    IoChannel::disconnected(),
-                )
-                .expect("Failed to instantiate a new Client");
-
-                for b in blockchain.blocks_rlp() {
-                    let bytes_len = b.len();
-                    let block = Unverified::from_rlp(b, spec.params().eip1559_transition);
-                    match block {
-                        Ok(block) => {
-                            let num = block.header.number();
-                            debug!(target: "json-tests", "{} – Importing {} bytes. Block #{}", name, bytes_len, num);
-                            let res = client.import_block(block);
-                            if let Err(e) = res {
-                                warn!(target: "json-tests", "{} – Error importing block #{}: {:?}", name, num, e);
-                            }
-                            client.flush_queue();
-                            client.import_verified_blocks();
-                        }
-                        Err(decoder_err) => {
-                            warn!(target: "json-tests", "Error decoding test block: {:?} ({} bytes)", decoder_err, bytes_len);
-                        }
-                    }
+            // Both the sequential and the parallel executor must produce the expected chain.
+            let parallel_spec = EvmTestClient::spec_from_json(&blockchain.network)
+                .expect("the network spec was loaded above; qed");
+            for (parallel_transactions, spec) in vec![(false, spec), (true, parallel_spec)] {
+                let result = run_chain_test(&name, spec, &blockchain, parallel_transactions);
+                if !result.is_success() {
+                    warn!(target: "json-tests", "{} – Failed with parallel_transactions = {}", name, parallel_transactions);
                 }
-
-                let post_state_success = if let Some(post_state) = blockchain.post_state.clone() {
-                    check_poststate(&client, &name, post_state)
-                } else {
-                    true
-                };
-
-                fail_unless(
-                    client.chain_info().best_block_hash == blockchain.best_block.into()
-                        && post_state_success,
-                );
-                client.shutdown()
+                fail_unless(result.is_success());
             }
         }
 
@@ -247,3 +241,96 @@ pub fn json_chain_test<H: FnMut(&str, HookType)>(
 
     failed
 }
+
+/// Imports the blocks of a blockchain test into a new client and checks the resulting chain.
+///
+/// `spec` is the chain spec of the test network, its genesis is replaced by the test one.
+/// With `parallel_transactions` the blocks are imported through the parallel executor.
+pub fn run_chain_test(
+    name: &str,
+    mut spec: Spec,
+    blockchain: &ethjson::blockchain::BlockChain,
+    parallel_transactions: bool,
+) -> ChainResult {
+    let genesis = Genesis::from(blockchain.genesis());
+    let state = From::from(blockchain.pre_state.clone());
+    spec.set_genesis_state(state)
+        .expect("Failed to overwrite genesis state");
+    spec.overwrite_genesis_params(genesis);
+
+    let db = test_helpers::new_db();
+    let mut config = ClientConfig::default();
+    if ethjson::blockchain::Engine::NoProof == blockchain.engine {
+        config.verifier_type = VerifierType::CanonNoSeal;
+        config.check_seal = false;
+    }
+    config.history = 8;
+    config.parallel_transactions = parallel_transactions;
+    config.queue.verifier_settings.num_verifiers = 1;
+    let client = Client::new(
+        config,
+        &spec,
+        db,
+        Arc::new(Miner::new_for_tests(&spec, None)),
+        IoChannel::disconnected(),
+    )
+    .expect("Failed to instantiate a new Client");
+
+    let mut blocks = Vec::with_capacity(blockchain.blocks.len());
+    for block in &blockchain.blocks {
+        let b = block.rlp();
+        let bytes_len = b.len();
+        let mut result = BlockResult {
+            number: None,
+            hash: None,
+            expected: block.header.is_some(),
+            imported: false,
+            error: None,
+        };
+        match Unverified::from_rlp(b, spec.params().eip1559_transition) {
+            Ok(block) => {
+                let num = block.header.number();
+                let hash = block.header.hash();
+                result.number = Some(num);
+                result.hash = Some(hash);
+                debug!(target: "json-tests", "{} – Importing {} bytes. Block #{}", name, bytes_len, num);
+                let res = client.import_block(block);
+                if let Err(e) = res {
+                    warn!(target: "json-tests", "{} – Error importing block #{}: {:?}", name, num, e);
+                    result.error = Some(format!("{}", e));
+                }
+                client.flush_queue();
+                client.import_verified_blocks();
+
+                result.imported = client.block_status(BlockId::Hash(hash)) == BlockStatus::InChain;
+                if !result.imported && result.error.is_none() {
+                    result.error = client
+                        .bad_blocks()
+                        .into_iter()
+                        .find(|(bad, _)| bad.header.hash() == hash)
+                        .map(|(_, message)| message);
+                }
+            }
+            Err(decoder_err) => {
+                warn!(target: "json-tests", "Error decoding test block: {:?} ({} bytes)", decoder_err, bytes_len);
+                result.error = Some(format!("{}", decoder_err));
+            }
+        }
+        blocks.push(result);
+    }
+
+    let post_state = if let Some(ref post_state) = blockchain.post_state {
+        check_poststate(&client, name, post_state.clone())
+    } else {
+        true
+    };
+
+    let result = ChainResult {
+        blocks,
+        best_block: client.chain_info().best_block_hash,
+        expected_best_block: blockchain.best_block.clone().into(),
+        post_state,
+    };
+    client.shutdown();
+    result
+}
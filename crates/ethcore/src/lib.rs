@@ -57,6 +57,7 @@ extern crate rlp;
 extern crate rustc_hex;
 extern crate serde;
 extern crate stats;
+extern crate telemetry;
 extern crate time_utils;
 extern crate trie_db as trie;
 extern crate triehash_ethereum as triehash;
@@ -17,6 +17,7 @@
 //! Client-side stratum job dispatcher and mining notifier handler
 
 use std::{
+    collections::VecDeque,
     fmt,
     net::{AddrParseError, SocketAddr},
     sync::{Arc, Weak},
@@ -28,11 +29,18 @@ use ethash::{self, SeedHashCompute};
 use ethcore_miner::work_notify::NotifyWork;
 #[cfg(feature = "work-notify")]
 use ethcore_stratum::PushWorkHandler;
-use ethcore_stratum::{Error as StratumServiceError, JobDispatcher, Stratum as StratumService};
+pub use ethcore_stratum::Vardiff;
+use ethcore_stratum::{
+    Error as StratumServiceError, Job, JobDispatcher, Share, Stratum as StratumService,
+};
 use ethereum_types::{H256, H64, U256};
 use miner::{Miner, MinerService};
 use parking_lot::Mutex;
 use rlp::encode;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+
+/// Number of most recent work packages shares are checked against.
+const RECENT_JOBS: usize = 8;
 
 /// Configures stratum server options.
 #[derive(Debug, PartialEq, Clone)]
@@ -45,6 +53,8 @@ pub struct Options {
     pub port: u16,
     /// Secret for peers
     pub secret: Option<H256>,
+    /// Share difficulty adjustment for EthereumStratum/1.0.0 workers
+    pub vardiff: Vardiff,
 }
 
 fn clean_0x(s: &str) -> &str {
@@ -116,6 +126,8 @@ impl fmt::Display for PayloadError {
 /// Job dispatcher for stratum service
 pub struct StratumJobDispatcher {
     seed_compute: Mutex<SeedHashCompute>,
+    /// Recently handed out work (pow_hash, block number, difficulty), newest first
+    jobs: Mutex<VecDeque<(H256, u64, U256)>>,
     client: Weak<Client>,
     miner: Weak<Miner>,
 }
@@ -163,6 +175,77 @@ impl JobDispatcher for StratumJobDispatcher {
             }
         })
     }
+
+    fn nicehash_job(&self) -> Option<Job> {
+        self.with_core(|client, miner| {
+            miner
+                .work_package(&*client)
+                .map(|(pow_hash, number, _timestamp, difficulty)| {
+                    self.record_job(pow_hash, number, difficulty);
+                    Job {
+                        header_hash: pow_hash,
+                        seed_hash: self.seed_hash(number),
+                        difficulty,
+                    }
+                })
+        })
+    }
+
+    fn submit_share(
+        &self,
+        pow_hash: H256,
+        nonce: u64,
+        difficulty: U256,
+    ) -> Result<Share, StratumServiceError> {
+        let (number, block_difficulty) = {
+            let jobs = self.jobs.lock();
+            let latest = match jobs.front() {
+                Some(&(_, number, _)) => number,
+                None => return Ok(Share::Stale),
+            };
+            // work for an older block can't extend the chain anymore
+            match jobs.iter().find(|job| job.0 == pow_hash) {
+                Some(&(_, number, block_difficulty)) if number == latest => {
+                    (number, block_difficulty)
+                }
+                _ => return Ok(Share::Stale),
+            }
+        };
+
+        // shares are checked against the light caches of the client's engine
+        let pow = self
+            .with_core(|client, _| client.engine().compute_pow_light(number, &pow_hash, nonce))
+            .ok_or_else(|| {
+                StratumServiceError::Dispatch("Engine doesn't use proof-of-work".into())
+            })?;
+        let share_difficulty = ethash::boundary_to_difficulty(&H256(pow.value));
+        if share_difficulty < difficulty {
+            return Ok(Share::Rejected);
+        }
+        if share_difficulty < block_difficulty {
+            return Ok(Share::Accepted);
+        }
+
+        trace!(target: "stratum", "submit_share: share for {} meets block difficulty", pow_hash);
+        let seal = vec![
+            encode(&H256(pow.mix_hash)),
+            encode(&H64::from_low_u64_be(nonce)),
+        ];
+        let sealed = self.with_core_result(|client, miner| {
+            miner
+                .submit_seal(pow_hash, seal.clone())
+                .and_then(|block| client.import_sealed_block(block))
+                .map(|_| ())
+                .map_err(|e| StratumServiceError::Dispatch(e.to_string()))
+        });
+        match sealed {
+            Ok(()) => Ok(Share::Block),
+            Err(e) => {
+                warn!(target: "stratum", "submit_share: sealing failed: {:?}", e);
+                Ok(Share::Accepted)
+            }
+        }
+    }
 }
 
 impl StratumJobDispatcher {
@@ -170,6 +253,7 @@ impl StratumJobDispatcher {
     fn new(miner: Weak<Miner>, client: Weak<Client>) -> StratumJobDispatcher {
         StratumJobDispatcher {
             seed_compute: Mutex::new(SeedHashCompute::default()),
+            jobs: Mutex::new(VecDeque::with_capacity(RECENT_JOBS)),
             client: client,
             miner: miner,
         }
@@ -179,14 +263,27 @@ impl StratumJobDispatcher {
     fn payload(&self, pow_hash: H256, difficulty: U256, number: u64) -> String {
         // TODO: move this to engine
         let target = ethash::difficulty_to_boundary(&difficulty);
-        let seed_hash = &self.seed_compute.lock().hash_block_number(number);
-        let seed_hash = H256::from_slice(&seed_hash[..]);
+        let seed_hash = self.seed_hash(number);
         format!(
             r#"["0x", "0x{:x}","0x{:x}","0x{:x}","0x{:x}"]"#,
             pow_hash, seed_hash, target, number
         )
     }
 
+    fn seed_hash(&self, number: u64) -> H256 {
+        H256::from_slice(&self.seed_compute.lock().hash_block_number(number)[..])
+    }
+
+    /// Remembers handed out work so that shares for it can be checked
+    fn record_job(&self, pow_hash: H256, number: u64, difficulty: U256) {
+        let mut jobs = self.jobs.lock();
+        if jobs.iter().any(|job| job.0 == pow_hash) {
+            return;
+        }
+        jobs.push_front((pow_hash, number, difficulty));
+        jobs.truncate(RECENT_JOBS);
+    }
+
     fn with_core<F, R>(&self, f: F) -> Option<R>
     where
         F: Fn(Arc<Client>, Arc<Miner>) -> Option<R>,
@@ -208,6 +305,7 @@ impl StratumJobDispatcher {
 }
 
 /// Wrapper for dedicated stratum service
+#[derive(Clone)]
 pub struct Stratum {
     dispatcher: Arc<StratumJobDispatcher>,
     service: Arc<StratumService>,
@@ -244,6 +342,12 @@ impl NotifyWork for Stratum {
     }
 }
 
+impl PrometheusMetrics for Stratum {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        self.service.prometheus_metrics(r)
+    }
+}
+
 impl Stratum {
     /// New stratum job dispatcher, given the miner, client and dedicated stratum service
     pub fn start(
@@ -255,10 +359,11 @@ impl Stratum {
 
         let dispatcher = Arc::new(StratumJobDispatcher::new(miner, client));
 
-        let service = StratumService::start(
+        let service = StratumService::start_with_vardiff(
             &SocketAddr::new(options.listen_addr.parse::<IpAddr>()?, options.port),
             dispatcher.clone(),
             options.secret.clone(),
+            options.vardiff.clone(),
         )?;
 
         Ok(Stratum {
@@ -269,9 +374,13 @@ impl Stratum {
 
     /// Start STRATUM job dispatcher and register it in the miner
     #[cfg(feature = "work-notify")]
-    pub fn register(cfg: &Options, miner: Arc<Miner>, client: Weak<Client>) -> Result<(), Error> {
+    pub fn register(
+        cfg: &Options,
+        miner: Arc<Miner>,
+        client: Weak<Client>,
+    ) -> Result<Stratum, Error> {
         let stratum = Stratum::start(cfg, Arc::downgrade(&miner.clone()), client)?;
-        miner.add_work_listener(Box::new(stratum) as Box<dyn NotifyWork>);
-        Ok(())
+        miner.add_work_listener(Box::new(stratum.clone()) as Box<dyn NotifyWork>);
+        Ok(stratum)
     }
 }
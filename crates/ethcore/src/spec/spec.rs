@@ -19,6 +19,7 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
     convert::TryFrom,
+    fs,
     io::Read,
     path::Path,
     sync::Arc,
@@ -49,7 +50,7 @@ use spec::{seal::Generic as GenericSeal, Genesis};
 use state::{backend::Basic as BasicBackend, Backend, State, Substate};
 use trace::{NoopTracer, NoopVMTracer};
 
-pub use ethash::OptimizeFor;
+pub use ethash::{CacheConfig as EthashCacheConfig, OptimizeFor};
 
 const MAX_TRANSACTION_SIZE: usize = 300 * 1024;
 
@@ -147,6 +148,14 @@ pub struct CommonParams {
     pub eip3529_transition: BlockNumber,
     /// Number of first block where EIP-3541 rule begins.
     pub eip3541_transition: BlockNumber,
+    /// Number of first block where EIP-3540 EOF v1 containers begin.
+    pub eip3540_transition: BlockNumber,
+    /// Number of first block where EIP-3670 EOF code validation begins.
+    pub eip3670_transition: BlockNumber,
+    /// Number of first block where EIP-4200 EOF relative jumps begin.
+    pub eip4200_transition: BlockNumber,
+    /// Number of first block where EIP-4750 EOF functions begin.
+    pub eip4750_transition: BlockNumber,
     /// Number of first block where EIP-3607 rule begins.
     pub eip3607_transition: BlockNumber,
     /// Number of first block where dust cleanup rules (EIP-168 and EIP169) begin.
@@ -159,6 +168,8 @@ pub struct CommonParams {
     pub wasm_activation_transition: BlockNumber,
     /// Wasm deactivation blocknumber, if enabled.
     pub wasm_disable_transition: BlockNumber,
+    /// Wasm costs and limits.
+    pub wasm_costs: ::vm::WasmCosts,
     /// Number of first block where KIP-4 rules begin. Only has effect if Wasm is activated.
     pub kip4_transition: BlockNumber,
     /// Number of first block where KIP-6 rules begin. Only has effect if Wasm is activated.
@@ -242,6 +253,10 @@ impl CommonParams {
         schedule.eip2929 = block_number >= self.eip2929_transition;
         schedule.eip2930 = block_number >= self.eip2930_transition;
         schedule.eip3541 = block_number >= self.eip3541_transition;
+        schedule.eip3540 = block_number >= self.eip3540_transition;
+        schedule.eip3670 = block_number >= self.eip3670_transition;
+        schedule.eip4200 = block_number >= self.eip4200_transition;
+        schedule.eip4750 = block_number >= self.eip4750_transition;
         schedule.eip1559 = block_number >= self.eip1559_transition;
         schedule.eip3198 = block_number >= self.eip3198_transition;
         if schedule.eip1559 {
@@ -298,7 +313,7 @@ impl CommonParams {
         if block_number >= self.wasm_activation_transition
             && block_number < self.wasm_disable_transition
         {
-            let mut wasm = ::vm::WasmCosts::default();
+            let mut wasm = self.wasm_costs.clone();
             if block_number >= self.kip4_transition {
                 wasm.have_create2 = true;
             }
@@ -433,6 +448,18 @@ impl From<ethjson::spec::Params> for CommonParams {
             eip3541_transition: p
                 .eip3541_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
+            eip3540_transition: p
+                .eip3540_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip3670_transition: p
+                .eip3670_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip4200_transition: p
+                .eip4200_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
+            eip4750_transition: p
+                .eip4750_transition
+                .map_or_else(BlockNumber::max_value, Into::into),
             dust_protection_transition: p
                 .dust_protection_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
@@ -457,6 +484,7 @@ impl From<ethjson::spec::Params> for CommonParams {
             wasm_disable_transition: p
                 .wasm_disable_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
+            wasm_costs: p.wasm_costs.map_or_else(Default::default, Into::into),
             kip4_transition: p
                 .kip4_transition
                 .map_or_else(BlockNumber::max_value, Into::into),
@@ -498,6 +526,8 @@ pub struct SpecParams<'a> {
     /// memory. This may get more fine-grained in the future but for now is simply a binary
     /// option.
     pub optimization_setting: Option<OptimizeFor>,
+    /// Background generation and retention of ethash light caches and datasets.
+    pub ethash_cache: EthashCacheConfig,
 }
 
 impl<'a> SpecParams<'a> {
@@ -506,6 +536,7 @@ impl<'a> SpecParams<'a> {
         SpecParams {
             cache_dir: path,
             optimization_setting: None,
+            ethash_cache: EthashCacheConfig::default(),
         }
     }
 
@@ -514,8 +545,15 @@ impl<'a> SpecParams<'a> {
         SpecParams {
             cache_dir: path,
             optimization_setting: Some(optimization),
+            ethash_cache: EthashCacheConfig::default(),
         }
     }
+
+    /// Use the given ethash cache configuration
+    pub fn with_ethash_cache(mut self, ethash_cache: EthashCacheConfig) -> Self {
+        self.ethash_cache = ethash_cache;
+        self
+    }
 }
 
 impl<'a, T: AsRef<Path>> From<&'a T> for SpecParams<'a> {
@@ -636,6 +674,7 @@ fn load_from(spec_params: SpecParams, s: ethjson::spec::Spec) -> Result<Spec, Er
     let g = Genesis::from(s.genesis);
     let GenericSeal(seal_rlp) = g.seal.into();
     let params = CommonParams::from(s.params);
+    ::wasm::validate_costs(&params.wasm_costs)?;
 
     let (engine, hard_forks) = Spec::engine(spec_params, s.engine, params, builtins);
 
@@ -751,6 +790,10 @@ impl Spec {
             params.eip3198_transition,
             params.eip3529_transition,
             params.eip3541_transition,
+            params.eip3540_transition,
+            params.eip3670_transition,
+            params.eip4200_transition,
+            params.eip4750_transition,
             params.dust_protection_transition,
             params.wasm_activation_transition,
             params.wasm_disable_transition,
@@ -791,11 +834,12 @@ impl Spec {
                     }
                 }
 
-                Arc::new(::ethereum::Ethash::new(
+                Arc::new(::ethereum::Ethash::with_cache_config(
                     spec_params.cache_dir,
                     ethash.params.into(),
                     machine,
                     spec_params.optimization_setting,
+                    spec_params.ethash_cache,
                 ))
             }
             ethjson::spec::Engine::InstantSeal(Some(instant_seal)) => {
@@ -1053,6 +1097,23 @@ impl Spec {
             .and_then(|x| load_from(params.into(), x).map_err(fmt_err))
     }
 
+    /// Loads spec from a json file at `path`. Relative paths in the spec, like the KZG trusted
+    /// setup, are resolved against the directory of the file.
+    pub fn load_file<'a, T: Into<SpecParams<'a>>>(params: T, path: &Path) -> Result<Self, String> {
+        let file = fs::File::open(path).map_err(|e| {
+            format!(
+                "Could not load specification file at {}: {}",
+                path.display(),
+                e
+            )
+        })?;
+        let mut spec = ethjson::spec::Spec::load(file).map_err(fmt_err)?;
+        if let Some(dir) = path.parent() {
+            spec.resolve_paths(dir);
+        }
+        load_from(params.into(), spec).map_err(fmt_err)
+    }
+
     /// initialize genesis epoch data, using in-memory database for
     /// constructor.
     pub fn genesis_epoch_data(&self) -> Result<Vec<u8>, String> {
@@ -1259,6 +1320,44 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_wasm_costs() {
+        let spec = include_str!("../../res/chainspec/test/kovan_wasm_test.json");
+        let with_costs = |costs: &str| {
+            spec.replace(
+                "\"wasmDisableTransition\": 200",
+                &format!("\"wasmDisableTransition\": 200, \"wasmCosts\": {}", costs),
+            )
+        };
+        let tempdir = TempDir::new("").unwrap();
+
+        let spec = Spec::load(
+            &tempdir.path(),
+            with_costs(r#"{ "maxMemoryPages": 32, "instructions": { "flow": 2 } }"#).as_bytes(),
+        )
+        .unwrap();
+        let wasm_costs = &spec.params().wasm_costs;
+        assert_eq!(wasm_costs.max_memory_pages, 32);
+        assert_eq!(wasm_costs.instructions["flow"], 2);
+        assert_eq!(spec.engine.schedule(100).wasm().max_memory_pages, 32);
+
+        assert!(Spec::load(
+            &tempdir.path(),
+            with_costs(r#"{ "instructions": { "jump": 2 } }"#).as_bytes()
+        )
+        .is_err());
+        assert!(Spec::load(
+            &tempdir.path(),
+            with_costs(r#"{ "maxMemoryPages": "0x100000010" }"#).as_bytes()
+        )
+        .is_err());
+        assert!(Spec::load(
+            &tempdir.path(),
+            with_costs(r#"{ "maxStackHeight": "0x100000000" }"#).as_bytes()
+        )
+        .is_err());
+    }
+
     #[test]
     fn genesis_constructor() {
         let _ = ::env_logger::try_init();
@@ -0,0 +1,215 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Accounts and storage accessed while executing transactions.
+
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use std::collections::HashSet;
+
+/// A single modification of the state, in a form which can be replayed on another state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StateWrite {
+    /// Balance of the account was increased.
+    AddBalance(Address, U256),
+    /// Balance of the account was decreased.
+    SubBalance(Address, U256),
+    /// Nonce of the account was incremented.
+    IncNonce(Address),
+    /// Account was touched without being otherwise modified.
+    Touch(Address),
+    /// Storage value of the account was changed.
+    SetStorage(Address, H256, H256),
+    /// Contract was created with the given balance and nonce offset.
+    NewContract(Address, U256, U256),
+    /// Code of a new contract was initialised.
+    InitCode(Address, Bytes),
+    /// Code of the account was reset.
+    ResetCode(Address, Bytes),
+    /// Account was removed.
+    Kill(Address),
+}
+
+impl StateWrite {
+    /// Address of the modified account.
+    pub fn address(&self) -> &Address {
+        match *self {
+            StateWrite::AddBalance(ref a, _)
+            | StateWrite::SubBalance(ref a, _)
+            | StateWrite::IncNonce(ref a)
+            | StateWrite::Touch(ref a)
+            | StateWrite::SetStorage(ref a, _, _)
+            | StateWrite::NewContract(ref a, _, _)
+            | StateWrite::InitCode(ref a, _)
+            | StateWrite::ResetCode(ref a, _)
+            | StateWrite::Kill(ref a) => a,
+        }
+    }
+}
+
+/// Reads and writes recorded by a `State` while recording is enabled.
+///
+/// Writes made inside a checkpoint which is later reverted are dropped, reads are always kept
+/// since they influenced the execution anyway.
+#[derive(Debug, Default, Clone)]
+pub struct AccessSet {
+    accounts: HashSet<Address>,
+    storage: HashSet<(Address, H256)>,
+    storage_roots: HashSet<Address>,
+    writes: Vec<StateWrite>,
+    checkpoints: Vec<usize>,
+}
+
+impl AccessSet {
+    /// Writes in the order they were applied.
+    pub fn writes(&self) -> &[StateWrite] {
+        &self.writes
+    }
+
+    pub(super) fn note_account(&mut self, address: &Address) {
+        self.accounts.insert(*address);
+    }
+
+    pub(super) fn note_storage(&mut self, address: &Address, key: &H256) {
+        self.storage.insert((*address, *key));
+    }
+
+    pub(super) fn note_storage_root(&mut self, address: &Address) {
+        self.storage_roots.insert(*address);
+    }
+
+    pub(super) fn note_write(&mut self, write: StateWrite) {
+        self.writes.push(write);
+    }
+
+    pub(super) fn checkpoint(&mut self) {
+        self.checkpoints.push(self.writes.len());
+    }
+
+    pub(super) fn discard_checkpoint(&mut self) {
+        self.checkpoints.pop();
+    }
+
+    pub(super) fn revert_to_checkpoint(&mut self) {
+        if let Some(len) = self.checkpoints.pop() {
+            self.writes.truncate(len);
+        }
+    }
+}
+
+/// Accounts and storage keys modified by a sequence of transactions.
+#[derive(Debug, Default)]
+pub struct WriteSet {
+    accounts: HashSet<Address>,
+    storage: HashSet<(Address, H256)>,
+    storage_accounts: HashSet<Address>,
+    cleared: HashSet<Address>,
+}
+
+impl WriteSet {
+    /// Add the keys modified by `writes`.
+    pub fn extend(&mut self, writes: &[StateWrite]) {
+        for write in writes {
+            match *write {
+                StateWrite::SetStorage(ref a, ref key, _) => {
+                    self.storage.insert((*a, *key));
+                    self.storage_accounts.insert(*a);
+                }
+                // only existing accounts are touched, which leaves their values unchanged
+                StateWrite::Touch(_) => {}
+                StateWrite::NewContract(ref a, _, _) | StateWrite::Kill(ref a) => {
+                    self.accounts.insert(*a);
+                    self.cleared.insert(*a);
+                }
+                _ => {
+                    self.accounts.insert(*write.address());
+                }
+            }
+        }
+    }
+
+    /// Whether any value read by `access` could have been modified by the writes in this set.
+    pub fn conflicts_with(&self, access: &AccessSet) -> bool {
+        access.accounts.iter().any(|a| self.accounts.contains(a))
+            || access
+                .storage
+                .iter()
+                .any(|entry| self.storage.contains(entry) || self.cleared.contains(&entry.0))
+            || access
+                .storage_roots
+                .iter()
+                .any(|a| self.storage_accounts.contains(a) || self.cleared.contains(a))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverted_writes_are_dropped() {
+        let a = Address::from_low_u64_be(1);
+        let mut access = AccessSet::default();
+        access.note_write(StateWrite::IncNonce(a));
+        access.checkpoint();
+        access.note_write(StateWrite::AddBalance(a, 1.into()));
+        access.checkpoint();
+        access.note_write(StateWrite::Touch(a));
+        access.discard_checkpoint();
+        access.revert_to_checkpoint();
+        access.note_write(StateWrite::Kill(a));
+
+        assert_eq!(
+            access.writes(),
+            &[StateWrite::IncNonce(a), StateWrite::Kill(a)]
+        );
+    }
+
+    #[test]
+    fn detects_conflicts() {
+        let a = Address::from_low_u64_be(1);
+        let b = Address::from_low_u64_be(2);
+        let key = H256::from_low_u64_be(1);
+
+        let mut written = WriteSet::default();
+        written.extend(&[
+            StateWrite::AddBalance(a, 1.into()),
+            StateWrite::SetStorage(b, key, H256::from_low_u64_be(2)),
+        ]);
+
+        let mut access = AccessSet::default();
+        access.note_account(&b);
+        access.note_storage(&b, &H256::from_low_u64_be(2));
+        assert!(!written.conflicts_with(&access));
+
+        let mut access = AccessSet::default();
+        access.note_account(&a);
+        assert!(written.conflicts_with(&access));
+
+        let mut access = AccessSet::default();
+        access.note_storage(&b, &key);
+        assert!(written.conflicts_with(&access));
+
+        let mut access = AccessSet::default();
+        access.note_storage_root(&b);
+        assert!(written.conflicts_with(&access));
+
+        written.extend(&[StateWrite::Kill(a)]);
+        let mut access = AccessSet::default();
+        access.note_storage(&a, &key);
+        assert!(written.conflicts_with(&access));
+    }
+}
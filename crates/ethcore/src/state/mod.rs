@@ -54,12 +54,19 @@ use kvdb::DBValue;
 use ethtrie::{Result as TrieResult, TrieDB};
 use trie::{Recorder, Trie, TrieError};
 
+mod access;
 mod account;
 mod substate;
 
 pub mod backend;
+pub mod parallel;
 
-pub use self::{account::Account, backend::Backend, substate::Substate};
+pub use self::{
+    access::{AccessSet, StateWrite, WriteSet},
+    account::Account,
+    backend::Backend,
+    substate::Substate,
+};
 
 /// Used to return information about an `State::apply` operation.
 pub struct ApplyOutcome<T, V> {
@@ -261,6 +268,14 @@ pub fn prove_transaction_virtual<H: AsHashDB<KeccakHasher, DBValue> + Send + Syn
     }
 }
 
+// Whether the state is committed after every transaction to put intermediate roots into receipts.
+fn has_intermediate_commits(env_info: &EnvInfo, machine: &Machine) -> bool {
+    let params = machine.params();
+    env_info.number < params.eip658_transition
+        && (env_info.number < params.eip98_transition
+            || env_info.number < params.validate_receipts_transition)
+}
+
 /// Representation of the entire state of all accounts in the system.
 ///
 /// `State` can work together with `StateDB` to share account cache.
@@ -314,6 +329,8 @@ pub struct State<B> {
     checkpoints: RefCell<Vec<HashMap<Address, Option<AccountEntry>>>>,
     account_start_nonce: U256,
     factories: Factories,
+    // Accounts and storage accessed since recording was started.
+    access: RefCell<Option<AccessSet>>,
 }
 
 #[derive(Copy, Clone)]
@@ -384,6 +401,7 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            access: RefCell::new(None),
         }
     }
 
@@ -405,6 +423,7 @@ impl<B: Backend> State<B> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: account_start_nonce,
             factories: factories,
+            access: RefCell::new(None),
         };
 
         Ok(state)
@@ -417,6 +436,9 @@ impl<B: Backend> State<B> {
 
     /// Create a recoverable checkpoint of this state. Return the checkpoint index.
     pub fn checkpoint(&mut self) -> usize {
+        if let Some(ref mut access) = *self.access.get_mut() {
+            access.checkpoint();
+        }
         let checkpoints = self.checkpoints.get_mut();
         let index = checkpoints.len();
         checkpoints.push(HashMap::new());
@@ -425,6 +447,9 @@ impl<B: Backend> State<B> {
 
     /// Merge last checkpoint with previous.
     pub fn discard_checkpoint(&mut self) {
+        if let Some(ref mut access) = *self.access.get_mut() {
+            access.discard_checkpoint();
+        }
         // merge with previous checkpoint
         let last = self.checkpoints.get_mut().pop();
         if let Some(mut checkpoint) = last {
@@ -442,6 +467,9 @@ impl<B: Backend> State<B> {
 
     /// Revert to the last checkpoint and discard it.
     pub fn revert_to_checkpoint(&mut self) {
+        if let Some(ref mut access) = *self.access.get_mut() {
+            access.revert_to_checkpoint();
+        }
         if let Some(mut checkpoint) = self.checkpoints.get_mut().pop() {
             for (k, v) in checkpoint.drain() {
                 match v {
@@ -469,6 +497,57 @@ impl<B: Backend> State<B> {
         }
     }
 
+    /// Start recording accounts and storage read and written by this state.
+    /// Any previously recorded accesses are discarded.
+    pub fn start_access_recording(&mut self) {
+        *self.access.get_mut() = Some(AccessSet::default());
+    }
+
+    /// Stop recording and return the accesses recorded since `start_access_recording`.
+    pub fn take_access_set(&mut self) -> Option<AccessSet> {
+        self.access.get_mut().take()
+    }
+
+    /// Replay `writes` recorded by another state on top of this one.
+    pub fn apply_writes(&mut self, writes: &[StateWrite]) -> TrieResult<()> {
+        for write in writes {
+            match *write {
+                StateWrite::AddBalance(ref a, ref incr) => {
+                    self.require(a, false)?.add_balance(incr);
+                    self.note_write(|| write.clone());
+                }
+                StateWrite::SubBalance(ref a, ref decr) => {
+                    self.require(a, false)?.sub_balance(decr);
+                    self.note_write(|| write.clone());
+                }
+                StateWrite::IncNonce(ref a) => self.inc_nonce(a)?,
+                StateWrite::Touch(ref a) => self.touch(a)?,
+                StateWrite::SetStorage(ref a, ref key, ref value) => {
+                    self.set_storage(a, *key, *value)?
+                }
+                StateWrite::NewContract(ref a, ref balance, ref nonce_offset) => {
+                    self.new_contract(a, *balance, *nonce_offset)?
+                }
+                StateWrite::InitCode(ref a, ref code) => self.init_code(a, code.clone())?,
+                StateWrite::ResetCode(ref a, ref code) => self.reset_code(a, code.clone())?,
+                StateWrite::Kill(ref a) => self.kill_account(a),
+            }
+        }
+        Ok(())
+    }
+
+    fn note_read<F: FnOnce(&mut AccessSet)>(&self, f: F) {
+        if let Some(ref mut access) = *self.access.borrow_mut() {
+            f(access);
+        }
+    }
+
+    fn note_write<F: FnOnce() -> StateWrite>(&mut self, f: F) {
+        if let Some(ref mut access) = *self.access.get_mut() {
+            access.note_write(f());
+        }
+    }
+
     fn insert_cache(&self, address: &Address, account: AccountEntry) {
         // Dirty account which is not in the cache means this is a new account.
         // It goes directly into the checkpoint as there's nothing to rever to.
@@ -532,6 +611,7 @@ impl<B: Backend> State<B> {
                 rlp::DecoderError::Custom("Nonce overflow".into()),
             )));
         }
+        self.note_write(|| StateWrite::NewContract(*contract, balance, nonce_offset));
         self.insert_cache(
             contract,
             AccountEntry::new_dirty(Some(Account::new_contract(
@@ -545,6 +625,7 @@ impl<B: Backend> State<B> {
 
     /// Remove an existing account.
     pub fn kill_account(&mut self, account: &Address) {
+        self.note_write(|| StateWrite::Kill(*account));
         self.insert_cache(account, AccountEntry::new_dirty(None));
     }
 
@@ -587,6 +668,7 @@ impl<B: Backend> State<B> {
 
     /// Whether the base storage root of an account remains unchanged.
     pub fn is_base_storage_root_unchanged(&self, a: &Address) -> TrieResult<bool> {
+        self.note_read(|access| access.note_storage_root(a));
         Ok(self
             .ensure_cached(a, RequireCache::None, |a| {
                 a.as_ref()
@@ -597,6 +679,7 @@ impl<B: Backend> State<B> {
 
     /// Get the storage root of account `a`.
     pub fn storage_root(&self, a: &Address) -> TrieResult<Option<H256>> {
+        self.note_read(|access| access.note_storage_root(a));
         self.ensure_cached(a, RequireCache::None, |a| {
             a.as_ref().and_then(|account| account.storage_root())
         })
@@ -604,6 +687,7 @@ impl<B: Backend> State<B> {
 
     /// Get the original storage root since last commit of account `a`.
     pub fn original_storage_root(&self, a: &Address) -> TrieResult<H256> {
+        self.note_read(|access| access.note_storage_root(a));
         Ok(self
             .ensure_cached(a, RequireCache::None, |a| {
                 a.as_ref().map(|account| account.original_storage_root())
@@ -618,6 +702,8 @@ impl<B: Backend> State<B> {
         address: &Address,
         key: &H256,
     ) -> TrieResult<Option<H256>> {
+        self.note_read(|access| access.note_storage(address, key));
+
         #[must_use]
         enum ReturnKind {
             /// Use original storage at value at this address.
@@ -702,6 +788,8 @@ impl<B: Backend> State<B> {
         FCachedStorageAt: Fn(&Account, &H256) -> Option<H256>,
         FStorageAt: Fn(&Account, &dyn HashDB<KeccakHasher, DBValue>, &H256) -> TrieResult<H256>,
     {
+        self.note_read(|access| access.note_storage(address, key));
+
         // Storage key search and update works like this:
         // 1. If there's an entry for the account in the local cache check for the key and return it if found.
         // 2. If there's an entry for the account in the global cache check for the key or load it into that account.
@@ -823,6 +911,7 @@ impl<B: Backend> State<B> {
         let is_value_transfer = !incr.is_zero();
         if is_value_transfer || (cleanup_mode == CleanupMode::ForceCreate && !self.exists(a)?) {
             self.require(a, false)?.add_balance(incr);
+            self.note_write(|| StateWrite::AddBalance(*a, *incr));
         } else if let CleanupMode::TrackTouched(set) = cleanup_mode {
             if self.exists(a)? {
                 set.insert(*a);
@@ -842,6 +931,7 @@ impl<B: Backend> State<B> {
         trace!(target: "state", "sub_balance({}, {}): {}", a, decr, self.balance(a)?);
         if !decr.is_zero() || !self.exists(a)? {
             self.require(a, false)?.sub_balance(decr);
+            self.note_write(|| StateWrite::SubBalance(*a, *decr));
         }
         if let CleanupMode::TrackTouched(ref mut set) = *cleanup_mode {
             set.insert(*a);
@@ -864,14 +954,17 @@ impl<B: Backend> State<B> {
 
     /// Increment the nonce of account `a` by 1.
     pub fn inc_nonce(&mut self, a: &Address) -> TrieResult<()> {
-        self.require(a, false).map(|mut x| x.inc_nonce())
+        self.require(a, false)?.inc_nonce();
+        self.note_write(|| StateWrite::IncNonce(*a));
+        Ok(())
     }
 
     /// Mutate storage of account `a` so that it is `value` for `key`.
     pub fn set_storage(&mut self, a: &Address, key: H256, value: H256) -> TrieResult<()> {
         trace!(target: "state", "set_storage({}:{:x} to {:x})", a, key, value);
         if self.storage_at(a, &key)? != value {
-            self.require(a, false)?.set_storage(key, value)
+            self.require(a, false)?.set_storage(key, value);
+            self.note_write(|| StateWrite::SetStorage(*a, key, value));
         }
 
         Ok(())
@@ -880,6 +973,7 @@ impl<B: Backend> State<B> {
     /// Initialise the code of account `a` so that it is `code`.
     /// NOTE: Account should have been created with `new_contract`.
     pub fn init_code(&mut self, a: &Address, code: Bytes) -> TrieResult<()> {
+        self.note_write(|| StateWrite::InitCode(*a, code.clone()));
         self.require_or_from(
             a,
             true,
@@ -892,6 +986,7 @@ impl<B: Backend> State<B> {
 
     /// Reset the code of account `a` so that it is `code`.
     pub fn reset_code(&mut self, a: &Address, code: Bytes) -> TrieResult<()> {
+        self.note_write(|| StateWrite::ResetCode(*a, code.clone()));
         self.require_or_from(
             a,
             true,
@@ -936,14 +1031,9 @@ impl<B: Backend> State<B> {
     {
         let options = TransactOptions::new(tracer, vm_tracer);
         let e = self.execute(env_info, machine, t, options, false)?;
-        let params = machine.params();
+        let eip658 = env_info.number >= machine.params().eip658_transition;
 
-        let eip658 = env_info.number >= params.eip658_transition;
-        let no_intermediate_commits = eip658
-            || (env_info.number >= params.eip98_transition
-                && env_info.number >= params.validate_receipts_transition);
-
-        let outcome = if no_intermediate_commits {
+        let outcome = if !has_intermediate_commits(env_info, machine) {
             if eip658 {
                 TransactionOutcome::StatusCode(if e.exception.is_some() { 0 } else { 1 })
             } else {
@@ -996,6 +1086,7 @@ impl<B: Backend> State<B> {
 
     fn touch(&mut self, a: &Address) -> TrieResult<()> {
         self.require(a, false)?;
+        self.note_write(|| StateWrite::Touch(*a));
         Ok(())
     }
 
@@ -1065,6 +1156,11 @@ impl<B: Backend> State<B> {
         min_balance: &Option<U256>,
         kill_contracts: bool,
     ) -> TrieResult<()> {
+        self.note_read(|access| {
+            for address in touched {
+                access.note_account(address);
+            }
+        });
         let to_kill: HashSet<_> = {
             self.cache.borrow().iter().filter_map(|(address, ref entry)|
 			if touched.contains(address) && // Check all touched accounts
@@ -1321,6 +1417,8 @@ impl<B: Backend> State<B> {
     where
         F: Fn(Option<&Account>) -> U,
     {
+        self.note_read(|access| access.note_account(a));
+
         // check local cache first
         if let Some(ref mut maybe_acc) = self.cache.borrow_mut().get_mut(a) {
             if let Some(ref mut account) = maybe_acc.account {
@@ -1567,6 +1665,7 @@ impl Clone for State<StateDB> {
             checkpoints: RefCell::new(Vec::new()),
             account_start_nonce: self.account_start_nonce.clone(),
             factories: self.factories.clone(),
+            access: RefCell::new(None),
         }
     }
 }
@@ -0,0 +1,312 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Optimistic parallel execution of transactions.
+//!
+//! All transactions are first executed concurrently, each one on its own copy of the state
+//! as it was before the first of them. They are then committed in order: a transaction whose
+//! reads were not modified by any of the preceding ones has its recorded writes replayed on
+//! the state, any other one is executed again on top of the state of its predecessors.
+
+use super::{has_intermediate_commits, AccessSet, ApplyOutcome, ApplyResult, State, WriteSet};
+use ethereum_types::U256;
+use machine::EthereumMachine as Machine;
+use rayon::prelude::*;
+use state_db::StateDB;
+use trace::{FlatTrace, VMTrace};
+use types::transaction::SignedTransaction;
+use vm::EnvInfo;
+
+struct Speculation {
+    access: AccessSet,
+    outcome: ApplyOutcome<FlatTrace, VMTrace>,
+}
+
+/// Executes a batch of transactions in parallel, producing the same state, receipts and traces
+/// as applying them one by one.
+pub struct ParallelExecutor {
+    speculations: Vec<Option<Speculation>>,
+    // Gas used in the block when the transactions were speculatively executed.
+    base_gas_used: U256,
+    written: WriteSet,
+}
+
+impl ParallelExecutor {
+    /// Speculatively execute `transactions` on top of `state`.
+    ///
+    /// Nothing is executed ahead when receipts of the block contain intermediate state roots.
+    pub fn new(
+        state: &State<StateDB>,
+        env_info: &EnvInfo,
+        machine: &Machine,
+        transactions: &[SignedTransaction],
+        tracing: bool,
+    ) -> ParallelExecutor {
+        let speculations = if transactions.len() < 2 || has_intermediate_commits(env_info, machine)
+        {
+            transactions.iter().map(|_| None).collect()
+        } else {
+            let states: Vec<_> = transactions.iter().map(|_| state.clone()).collect();
+            states
+                .into_par_iter()
+                .zip(transactions.par_iter())
+                .map(|(mut state, t)| {
+                    state.start_access_recording();
+                    let outcome = state.apply(env_info, machine, t, tracing).ok()?;
+                    let access = state
+                        .take_access_set()
+                        .expect("access recording started above; qed");
+                    Some(Speculation { access, outcome })
+                })
+                .collect()
+        };
+
+        ParallelExecutor {
+            speculations,
+            base_gas_used: env_info.gas_used,
+            written: WriteSet::default(),
+        }
+    }
+
+    /// Apply the transaction at `index` of the batch to `state`.
+    ///
+    /// Transactions must be applied in order, with `env_info` accounting for the gas used by
+    /// the preceding ones.
+    pub fn apply(
+        &mut self,
+        index: usize,
+        state: &mut State<StateDB>,
+        env_info: &EnvInfo,
+        machine: &Machine,
+        t: &SignedTransaction,
+        tracing: bool,
+    ) -> ApplyResult<FlatTrace, VMTrace> {
+        let written = &self.written;
+        let speculation = self.speculations[index].take().filter(|s| {
+            env_info.gas_used + t.tx().gas <= env_info.gas_limit
+                && !written.conflicts_with(&s.access)
+        });
+
+        match speculation {
+            Some(Speculation {
+                access,
+                mut outcome,
+            }) => {
+                trace!(target: "state", "Committing speculative execution of {:?}", t.hash());
+                state.apply_writes(access.writes())?;
+                self.written.extend(access.writes());
+                outcome.receipt.gas_used =
+                    outcome.receipt.gas_used - self.base_gas_used + env_info.gas_used;
+                Ok(outcome)
+            }
+            None => {
+                trace!(target: "state", "Executing {:?} serially", t.hash());
+                state.start_access_recording();
+                let result = state.apply(env_info, machine, t, tracing);
+                let access = state
+                    .take_access_set()
+                    .expect("access recording started above; qed");
+                self.written.extend(access.writes());
+                result
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::publickey::{Generator, KeyPair, Random};
+    use ethereum_types::{Address, H256};
+    use rustc_hex::FromHex;
+    use state::CleanupMode;
+    use test_helpers::get_temp_state;
+    use types::transaction::{Action, Transaction, TypedTransaction};
+
+    fn transfer(sender: &KeyPair, nonce: u64, to: Address, value: u64) -> SignedTransaction {
+        TypedTransaction::Legacy(Transaction {
+            nonce: nonce.into(),
+            gas_price: 1.into(),
+            gas: 100_000.into(),
+            action: Action::Call(to),
+            value: value.into(),
+            data: vec![],
+        })
+        .sign(sender.secret(), None)
+    }
+
+    fn call(sender: &KeyPair, nonce: u64, to: Address, data: Vec<u8>) -> SignedTransaction {
+        TypedTransaction::Legacy(Transaction {
+            nonce: nonce.into(),
+            gas_price: 1.into(),
+            gas: 100_000.into(),
+            action: Action::Call(to),
+            value: 0.into(),
+            data,
+        })
+        .sign(sender.secret(), None)
+    }
+
+    fn assert_matches_sequential(
+        state: State<StateDB>,
+        env_info: EnvInfo,
+        transactions: Vec<SignedTransaction>,
+    ) {
+        let machine = ::ethereum::new_constantinople_test_machine();
+
+        let mut sequential = state.clone();
+        let mut sequential_info = env_info.clone();
+        let mut expected = Vec::new();
+        for t in &transactions {
+            let outcome = sequential
+                .apply(&sequential_info, &machine, t, true)
+                .unwrap();
+            sequential_info.gas_used = outcome.receipt.gas_used;
+            expected.push((outcome.receipt, outcome.trace));
+        }
+        sequential.commit().unwrap();
+
+        let mut parallel = state.clone();
+        let mut parallel_info = env_info.clone();
+        let mut executor =
+            ParallelExecutor::new(&parallel, &parallel_info, &machine, &transactions, true);
+        let mut outcomes = Vec::new();
+        for (index, t) in transactions.iter().enumerate() {
+            let outcome = executor
+                .apply(index, &mut parallel, &parallel_info, &machine, t, true)
+                .unwrap();
+            parallel_info.gas_used = outcome.receipt.gas_used;
+            outcomes.push((outcome.receipt, outcome.trace));
+        }
+        parallel.commit().unwrap();
+
+        assert_eq!(outcomes, expected);
+        assert_eq!(parallel.root(), sequential.root());
+    }
+
+    #[test]
+    fn should_match_sequential_transfers() {
+        let _ = env_logger::try_init();
+
+        let author = Address::from_low_u64_be(0xa);
+        let senders: Vec<_> = (0..4).map(|_| Random.generate()).collect();
+        let mut state = get_temp_state();
+        for sender in &senders {
+            state
+                .add_balance(
+                    &sender.address(),
+                    &1_000_000_000u64.into(),
+                    CleanupMode::NoEmpty,
+                )
+                .unwrap();
+        }
+        state.commit().unwrap();
+
+        let mut env_info = EnvInfo::default();
+        env_info.author = author;
+        env_info.gas_limit = 1_000_000.into();
+
+        let transactions = vec![
+            // independent transfers
+            transfer(&senders[0], 0, Address::from_low_u64_be(1), 10),
+            transfer(&senders[1], 0, Address::from_low_u64_be(2), 20),
+            // same sender as a preceding one
+            transfer(&senders[0], 1, Address::from_low_u64_be(3), 30),
+            // spends funds received in this block
+            transfer(&senders[2], 0, senders[3].address(), 100_000),
+            transfer(&senders[3], 0, author, 1_000_000_050),
+            // sends to the author who collects fees of every transaction
+            transfer(&senders[1], 1, author, 40),
+        ];
+
+        assert_matches_sequential(state, env_info, transactions);
+    }
+
+    #[test]
+    fn should_match_sequential_storage_access() {
+        let _ = env_logger::try_init();
+
+        // stores the second word of call data at the slot given by the first one
+        let code = FromHex::from_hex("60203560003555").unwrap();
+        let contract = Address::from_low_u64_be(0xc);
+        let senders: Vec<_> = (0..3).map(|_| Random.generate()).collect();
+        let mut state = get_temp_state();
+        state.init_code(&contract, code).unwrap();
+        for sender in &senders {
+            state
+                .add_balance(
+                    &sender.address(),
+                    &1_000_000_000u64.into(),
+                    CleanupMode::NoEmpty,
+                )
+                .unwrap();
+        }
+        state.commit().unwrap();
+
+        let mut env_info = EnvInfo::default();
+        env_info.gas_limit = 1_000_000.into();
+
+        let store = |slot: u64, value: u64| {
+            let mut data = H256::from_low_u64_be(slot).as_bytes().to_vec();
+            data.extend_from_slice(H256::from_low_u64_be(value).as_bytes());
+            data
+        };
+        let transactions = vec![
+            call(&senders[0], 0, contract, store(1, 1)),
+            call(&senders[1], 0, contract, store(2, 2)),
+            // overwrites a slot written by a preceding transaction
+            call(&senders[2], 0, contract, store(1, 3)),
+            transfer(&senders[0], 1, Address::from_low_u64_be(1), 10),
+        ];
+
+        assert_matches_sequential(state, env_info, transactions);
+    }
+
+    #[test]
+    fn should_reexecute_transactions_over_gas_limit() {
+        let _ = env_logger::try_init();
+
+        let senders: Vec<_> = (0..2).map(|_| Random.generate()).collect();
+        let mut state = get_temp_state();
+        for sender in &senders {
+            state
+                .add_balance(
+                    &sender.address(),
+                    &1_000_000_000u64.into(),
+                    CleanupMode::NoEmpty,
+                )
+                .unwrap();
+        }
+        state.commit().unwrap();
+
+        let machine = ::ethereum::new_constantinople_test_machine();
+        let mut env_info = EnvInfo::default();
+        env_info.gas_limit = 110_000.into();
+        let transactions = vec![
+            transfer(&senders[0], 0, Address::from_low_u64_be(1), 10),
+            transfer(&senders[1], 0, Address::from_low_u64_be(2), 20),
+        ];
+
+        let mut executor = ParallelExecutor::new(&state, &env_info, &machine, &transactions, false);
+        let outcome = executor
+            .apply(0, &mut state, &env_info, &machine, &transactions[0], false)
+            .unwrap();
+        env_info.gas_used = outcome.receipt.gas_used;
+        assert!(executor
+            .apply(1, &mut state, &env_info, &machine, &transactions[1], false)
+            .is_err());
+    }
+}
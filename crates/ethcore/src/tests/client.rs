@@ -23,7 +23,7 @@ use client::{
     traits::{
         BlockChainClient, BlockChainReset, BlockInfo, ChainInfo, ImportBlock, ImportExportBlocks,
     },
-    Client, ClientConfig, ImportSealedBlock, PrepareOpenBlock,
+    Client, ClientConfig, ImportSealedBlock, PrepareOpenBlock, StateRegenerationConfig,
 };
 use crypto::publickey::KeyPair;
 use ethereum;
@@ -39,7 +39,7 @@ use tempdir::TempDir;
 use test_helpers::{
     self, generate_dummy_client, generate_dummy_client_with_data, get_bad_state_dummy_block,
     get_good_dummy_block, get_good_dummy_block_seq, get_test_client_with_blocks,
-    push_blocks_to_client,
+    push_block_with_transactions, push_blocks_to_client,
 };
 use types::{
     data_format::DataFormat,
@@ -366,6 +366,61 @@ fn change_history_size() {
     assert_eq!(client.state().balance(&address).unwrap(), 100.into());
 }
 
+#[test]
+fn regenerates_pruned_state() {
+    let key = KeyPair::from_secret(keccak("test").into()).unwrap();
+    let client_with_regeneration = |enabled| {
+        let spec = Spec::new_test();
+        let mut config = ClientConfig::default();
+        config.history = 8;
+        config.history_mem = 0;
+        config.state_regeneration = StateRegenerationConfig {
+            enabled,
+            checkpoint_interval: 4,
+            max_blocks: 2,
+            max_memory: 1024 * 1024,
+        };
+        let client = Client::new(
+            config,
+            &spec,
+            test_helpers::new_db(),
+            Arc::new(Miner::new_for_tests(&spec, None)),
+            IoChannel::disconnected(),
+        )
+        .unwrap();
+
+        for nonce in 0..24 {
+            let tx = TypedTransaction::Legacy(Transaction {
+                nonce: nonce.into(),
+                gas_price: 0.into(),
+                gas: 21000.into(),
+                action: Action::Call(Address::default()),
+                value: 0.into(),
+                data: Vec::new(),
+            })
+            .sign(key.secret(), None);
+            push_block_with_transactions(&client, &[tx]);
+            client.wait_for_checkpoints();
+        }
+        assert!(client.pruning_info().earliest_state > 7);
+        client
+    };
+
+    let client = client_with_regeneration(true);
+    for number in &[2u64, 5, 10] {
+        let state = client.state_at(BlockId::Number(*number)).unwrap();
+        assert_eq!(state.nonce(&key.address()).unwrap(), (*number).into());
+    }
+    // more than `max_blocks` past the nearest checkpoint.
+    assert!(client.state_at(BlockId::Number(7)).is_none());
+    // but within reach of the last regenerated state.
+    assert!(client.state_at(BlockId::Number(6)).is_some());
+    assert!(client.state_at(BlockId::Number(7)).is_some());
+
+    let client = client_with_regeneration(false);
+    assert!(client.state_at(BlockId::Number(5)).is_none());
+}
+
 #[test]
 fn does_not_propagate_delayed_transactions() {
     let key = KeyPair::from_secret(keccak("test").into()).unwrap();
@@ -342,36 +342,44 @@ where
     }
 
     fn block_traces(&self, block_number: BlockNumber) -> Option<Vec<LocalizedTrace>> {
-        self.extras.block_hash(block_number).and_then(|block_hash| {
-            self.transactions_traces(&block_hash).map(|traces| {
-                traces
-                    .into_iter()
-                    .map(Into::<Vec<FlatTrace>>::into)
-                    .enumerate()
-                    .flat_map(|(tx_position, traces)| {
-                        let (trace_tx_number, trace_tx_hash) =
-                            match self.extras.transaction_hash(block_number, tx_position) {
-                                Some(hash) => (Some(tx_position), Some(hash.clone())),
-                                //None means trace without transaction (reward)
-                                None => (None, None),
-                            };
-
-                        traces
-                            .into_iter()
-                            .map(|trace| LocalizedTrace {
-                                action: trace.action,
-                                result: trace.result,
-                                subtraces: trace.subtraces,
-                                trace_address: trace.trace_address.into_iter().collect(),
-                                transaction_number: trace_tx_number,
-                                transaction_hash: trace_tx_hash,
-                                block_number: block_number,
-                                block_hash: block_hash,
-                            })
-                            .collect::<Vec<LocalizedTrace>>()
-                    })
-                    .collect::<Vec<LocalizedTrace>>()
-            })
+        self.extras
+            .block_hash(block_number)
+            .and_then(|block_hash| self.block_traces_by_hash(block_number, block_hash))
+    }
+
+    fn block_traces_by_hash(
+        &self,
+        block_number: BlockNumber,
+        block_hash: H256,
+    ) -> Option<Vec<LocalizedTrace>> {
+        self.transactions_traces(&block_hash).map(|traces| {
+            traces
+                .into_iter()
+                .map(Into::<Vec<FlatTrace>>::into)
+                .enumerate()
+                .flat_map(|(tx_position, traces)| {
+                    let (trace_tx_number, trace_tx_hash) =
+                        match self.extras.block_transaction_hash(&block_hash, tx_position) {
+                            Some(hash) => (Some(tx_position), Some(hash.clone())),
+                            //None means trace without transaction (reward)
+                            None => (None, None),
+                        };
+
+                    traces
+                        .into_iter()
+                        .map(|trace| LocalizedTrace {
+                            action: trace.action,
+                            result: trace.result,
+                            subtraces: trace.subtraces,
+                            trace_address: trace.trace_address.into_iter().collect(),
+                            transaction_number: trace_tx_number,
+                            transaction_hash: trace_tx_hash,
+                            block_number: block_number,
+                            block_hash: block_hash,
+                        })
+                        .collect::<Vec<LocalizedTrace>>()
+                })
+                .collect::<Vec<LocalizedTrace>>()
         })
     }
 
@@ -437,6 +445,10 @@ mod tests {
         ) -> Option<H256> {
             unimplemented!();
         }
+
+        fn block_transaction_hash(&self, _block_hash: &H256, _tx_position: usize) -> Option<H256> {
+            unimplemented!();
+        }
     }
 
     #[derive(Clone)]
@@ -464,6 +476,13 @@ mod tests {
                 .get(&block_number)
                 .and_then(|hashes| hashes.iter().cloned().nth(tx_position))
         }
+
+        fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256> {
+            self.block_hashes
+                .iter()
+                .find(|&(_, hash)| hash == block_hash)
+                .and_then(|(number, _)| self.transaction_hash(*number, tx_position))
+        }
     }
 
     #[test]
@@ -593,6 +612,11 @@ mod tests {
             tracedb.traces(&block_0).is_some(),
             "Traces should be available even if block is non-canon."
         );
+
+        let traces = tracedb.block_traces_by_hash(0, block_0.clone()).unwrap();
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].block_hash, block_0);
+        assert_eq!(traces[0].transaction_hash, Some(tx_0));
     }
 
     #[test]
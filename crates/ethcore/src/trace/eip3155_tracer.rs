@@ -0,0 +1,457 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-3155 VM tracer.
+
+use bytes::Bytes;
+use ethereum_types::{Address, U256};
+use evm::Instruction;
+use rustc_hex::ToHex;
+use serde::Serializer;
+use std::collections::HashSet;
+use trace::VMTracer;
+
+/// Single execution step, as specified by EIP-3155.
+///
+/// All values describe the state before the operation is executed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip3155Step {
+    /// Program counter.
+    pub pc: usize,
+    /// Opcode.
+    pub op: u8,
+    /// Gas left.
+    pub gas: U256,
+    /// Gas cost of the operation.
+    pub gas_cost: U256,
+    /// Memory, if requested.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "opt_bytes_to_hex"
+    )]
+    pub memory: Option<Bytes>,
+    /// Size of the memory in bytes.
+    pub mem_size: usize,
+    /// Stack, bottom first.
+    pub stack: Vec<U256>,
+    /// Call depth, starting at 1.
+    pub depth: usize,
+    /// Refund counter.
+    pub refund: u64,
+    /// Name of the opcode.
+    pub op_name: String,
+}
+
+fn opt_bytes_to_hex<S>(opt_bytes: &Option<Bytes>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(&format_args!(
+        "0x{}",
+        opt_bytes.as_ref().map_or("".to_string(), |b| b.to_hex())
+    ))
+}
+
+#[derive(Default)]
+struct Frame {
+    stack: Vec<U256>,
+    memory: Vec<u8>,
+    mem_size: usize,
+    mem_written: Option<(usize, usize)>,
+    instruction: u8,
+    /// Refunds of this frame and its successful subcalls.
+    refund: i128,
+    /// Refunds of the last finished subcall, kept until its result is known.
+    subcall_refund: i128,
+    /// Accounts destroyed by this frame and its successful subcalls.
+    suicides: HashSet<Address>,
+    /// Accounts destroyed by the last finished subcall, kept until its result is known.
+    subcall_suicides: HashSet<Address>,
+}
+
+/// Receiver of EIP-3155 steps.
+pub trait Eip3155Sink: Send {
+    /// Called with each step once its gas cost is known.
+    fn step(&mut self, step: Eip3155Step);
+}
+
+impl Eip3155Sink for Vec<Eip3155Step> {
+    fn step(&mut self, step: Eip3155Step) {
+        self.push(step);
+    }
+}
+
+/// VM tracer producing EIP-3155 execution steps.
+///
+/// Unlike `ExecutiveVMTracer` it emits a flat sequence of steps and tracks the stack,
+/// memory and refund counter, so it can feed cross-client trace comparisons.
+/// Steps are handed to the sink as soon as they are complete, so long executions
+/// are not buffered.
+pub struct Eip3155Tracer<S = Vec<Eip3155Step>> {
+    with_memory: bool,
+    frames: Vec<Frame>,
+    /// Step of the current instruction, waiting for its gas cost.
+    pending: Option<Eip3155Step>,
+    sink: S,
+}
+
+impl Eip3155Tracer {
+    /// Create a new top-level instance collecting the steps.
+    pub fn toplevel() -> Self {
+        Eip3155Tracer::new(Vec::new())
+    }
+}
+
+impl<S: Eip3155Sink> Eip3155Tracer<S> {
+    /// Create a new top-level instance passing the steps to `sink`.
+    pub fn new(sink: S) -> Self {
+        Eip3155Tracer {
+            with_memory: false,
+            frames: Vec::new(),
+            pending: None,
+            sink,
+        }
+    }
+
+    /// Includes the memory in the steps.
+    pub fn with_memory(mut self) -> Self {
+        self.with_memory = true;
+        self
+    }
+
+    fn flush(&mut self) {
+        if let Some(step) = self.pending.take() {
+            self.sink.step(step);
+        }
+    }
+
+    fn refund(&self) -> u64 {
+        let refund: i128 = self.frames.iter().map(|f| f.refund).sum();
+        if refund > 0 {
+            refund as u64
+        } else {
+            0
+        }
+    }
+}
+
+impl<S: Eip3155Sink> VMTracer for Eip3155Tracer<S> {
+    type Output = S;
+
+    fn trace_next_instruction(&mut self, pc: usize, instruction: u8, current_gas: U256) -> bool {
+        self.flush();
+        let refund = self.refund();
+        let with_memory = self.with_memory;
+        let depth = self.frames.len();
+        let frame = match self.frames.last_mut() {
+            Some(frame) => frame,
+            None => return true,
+        };
+        frame.instruction = instruction;
+        frame.mem_written = None;
+        self.pending = Some(Eip3155Step {
+            pc,
+            op: instruction,
+            gas: current_gas,
+            gas_cost: U256::zero(),
+            memory: if with_memory {
+                Some(frame.memory.clone())
+            } else {
+                None
+            },
+            mem_size: frame.mem_size,
+            stack: frame.stack.clone(),
+            depth,
+            refund,
+            op_name: Instruction::from_u8(instruction).map_or_else(
+                || format!("opcode {:#x} not defined", instruction),
+                |i| i.info().name.to_owned(),
+            ),
+        });
+        true
+    }
+
+    fn trace_prepare_execute(
+        &mut self,
+        _pc: usize,
+        _instruction: u8,
+        gas_cost: U256,
+        mem_written: Option<(usize, usize)>,
+        _store_written: Option<(U256, U256)>,
+    ) {
+        if let Some(step) = self.pending.as_mut() {
+            step.gas_cost = gas_cost;
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.mem_written = mem_written;
+        }
+    }
+
+    fn trace_executed(&mut self, _gas_used: U256, stack_push: &[U256], mem: &[u8]) {
+        let with_memory = self.with_memory;
+        let frame = match self.frames.last_mut() {
+            Some(frame) => frame,
+            None => return,
+        };
+        let info = Instruction::from_u8(frame.instruction).map(|i| i.info());
+        let len = frame.stack.len();
+        let args = info.map_or(0, |i| i.args);
+        frame.stack.truncate(len.saturating_sub(args));
+        frame.stack.extend_from_slice(stack_push);
+
+        let subcall_refund = ::std::mem::replace(&mut frame.subcall_refund, 0);
+        let subcall_suicides = ::std::mem::take(&mut frame.subcall_suicides);
+        if stack_push.first().map_or(false, |result| !result.is_zero()) {
+            frame.refund += subcall_refund;
+            frame.suicides.extend(subcall_suicides);
+        }
+
+        frame.mem_size = mem.len();
+        if with_memory {
+            frame.memory.resize(mem.len(), 0);
+            if let Some((offset, size)) = frame.mem_written.take() {
+                let end = ::std::cmp::min(offset + size, mem.len());
+                if offset < end {
+                    frame.memory[offset..end].copy_from_slice(&mem[offset..end]);
+                }
+            }
+        }
+    }
+
+    fn trace_refund(&mut self, refund: i128) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.refund += refund;
+        }
+    }
+
+    fn trace_suicide_refund(&mut self, address: Address, refund: i128) {
+        if self.frames.iter().any(|f| f.suicides.contains(&address)) {
+            return;
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.suicides.insert(address);
+            frame.refund += refund;
+        }
+    }
+
+    fn prepare_subtrace(&mut self, _code: &[u8]) {
+        self.flush();
+        self.frames.push(Frame::default());
+    }
+
+    fn done_subtrace(&mut self) {
+        self.flush();
+        let frame = self
+            .frames
+            .pop()
+            .expect("prepare/done_subtrace are not balanced");
+        if let Some(parent) = self.frames.last_mut() {
+            parent.subcall_refund = frame.refund;
+            parent.subcall_suicides = frame.suicides;
+        }
+    }
+
+    fn drain(mut self) -> Option<S> {
+        self.flush();
+        Some(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_trace_steps_with_stack_and_memory() {
+        let mut tracer = Eip3155Tracer::toplevel().with_memory();
+        tracer.prepare_subtrace(&[]);
+
+        // PUSH1 0x01
+        tracer.trace_next_instruction(0, 0x60, 100.into());
+        tracer.trace_prepare_execute(0, 0x60, 3.into(), None, None);
+        tracer.trace_executed(97.into(), &[1.into()], &[]);
+        // PUSH1 0x00
+        tracer.trace_next_instruction(2, 0x60, 97.into());
+        tracer.trace_prepare_execute(2, 0x60, 3.into(), None, None);
+        tracer.trace_executed(94.into(), &[0.into()], &[]);
+        // MSTORE
+        let mut mem = vec![0u8; 32];
+        mem[31] = 1;
+        tracer.trace_next_instruction(4, 0x52, 94.into());
+        tracer.trace_prepare_execute(4, 0x52, 6.into(), Some((0, 32)), None);
+        tracer.trace_executed(88.into(), &[], &mem);
+        // MSIZE
+        tracer.trace_next_instruction(5, 0x59, 88.into());
+        tracer.done_subtrace();
+
+        let steps = tracer.drain().unwrap();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[2].stack, vec![1.into(), 0.into()]);
+        assert_eq!(steps[2].gas_cost, 6.into());
+        assert_eq!(steps[2].op_name, "MSTORE");
+        assert_eq!(steps[3].stack, vec![]);
+        assert_eq!(steps[3].mem_size, 32);
+        assert_eq!(steps[3].memory, Some(mem));
+        assert_eq!(steps[3].depth, 1);
+    }
+
+    #[test]
+    fn should_emit_steps_once_complete() {
+        let mut tracer = Eip3155Tracer::toplevel();
+        tracer.prepare_subtrace(&[]);
+
+        tracer.trace_next_instruction(0, 0x60, 100.into());
+        tracer.trace_prepare_execute(0, 0x60, 3.into(), None, None);
+        tracer.trace_executed(97.into(), &[1.into()], &[]);
+        assert!(tracer.sink.is_empty());
+
+        // CALL is emitted before the steps of the subcall.
+        tracer.trace_next_instruction(2, 0xf1, 97.into());
+        tracer.trace_prepare_execute(2, 0xf1, 50.into(), None, None);
+        assert_eq!(tracer.sink.len(), 1);
+        tracer.prepare_subtrace(&[]);
+        assert_eq!(tracer.sink.len(), 2);
+        assert_eq!(tracer.sink[1].gas_cost, 50.into());
+        tracer.trace_next_instruction(0, 0x00, 40.into());
+        tracer.done_subtrace();
+        assert_eq!(tracer.sink.len(), 3);
+        assert_eq!(tracer.sink[2].depth, 2);
+        tracer.done_subtrace();
+
+        assert_eq!(tracer.drain().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn should_discard_refunds_of_failed_subcalls() {
+        let mut tracer = Eip3155Tracer::toplevel();
+        tracer.prepare_subtrace(&[]);
+
+        // SSTORE with a refund in the top-level frame.
+        tracer.trace_next_instruction(0, 0x55, 100.into());
+        tracer.trace_prepare_execute(0, 0x55, 5000.into(), None, Some((0.into(), 0.into())));
+        tracer.trace_refund(4800);
+        tracer.trace_executed(0.into(), &[], &[]);
+
+        // Reverted CALL refunding in the subcall.
+        tracer.trace_next_instruction(1, 0xf1, 100.into());
+        tracer.trace_prepare_execute(1, 0xf1, 50.into(), None, None);
+        tracer.prepare_subtrace(&[]);
+        tracer.trace_next_instruction(0, 0x55, 40.into());
+        tracer.trace_refund(4800);
+        tracer.done_subtrace();
+        tracer.trace_executed(60.into(), &[0.into()], &[]);
+
+        // Successful CALL refunding in the subcall.
+        tracer.trace_next_instruction(2, 0xf1, 60.into());
+        tracer.trace_prepare_execute(2, 0xf1, 50.into(), None, None);
+        tracer.prepare_subtrace(&[]);
+        tracer.trace_next_instruction(0, 0x55, 40.into());
+        tracer.trace_refund(4800);
+        tracer.done_subtrace();
+        tracer.trace_executed(20.into(), &[1.into()], &[]);
+
+        tracer.trace_next_instruction(3, 0x00, 20.into());
+        tracer.done_subtrace();
+
+        let steps = tracer.drain().unwrap();
+        let refunds: Vec<_> = steps.iter().map(|s| (s.depth, s.refund)).collect();
+        assert_eq!(
+            refunds,
+            vec![
+                (1, 0),
+                (1, 4800),
+                (2, 4800),
+                (1, 4800),
+                (2, 4800),
+                (1, 9600)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_trace_suicide_refunds_once_per_account() {
+        let destroyed = Address::from_low_u64_be(1);
+        let mut tracer = Eip3155Tracer::toplevel();
+        tracer.prepare_subtrace(&[]);
+
+        // Reverted CALL destroying the account.
+        tracer.trace_next_instruction(0, 0xf1, 100.into());
+        tracer.trace_prepare_execute(0, 0xf1, 50.into(), None, None);
+        tracer.prepare_subtrace(&[]);
+        tracer.trace_next_instruction(0, 0xff, 40.into());
+        tracer.trace_suicide_refund(destroyed, 24000);
+        tracer.done_subtrace();
+        tracer.trace_executed(60.into(), &[0.into()], &[]);
+
+        // Successful CALL destroying it twice.
+        tracer.trace_next_instruction(1, 0xf1, 60.into());
+        tracer.trace_prepare_execute(1, 0xf1, 50.into(), None, None);
+        tracer.prepare_subtrace(&[]);
+        tracer.trace_next_instruction(0, 0xff, 40.into());
+        tracer.trace_suicide_refund(destroyed, 24000);
+        tracer.done_subtrace();
+        tracer.trace_executed(20.into(), &[1.into()], &[]);
+        tracer.trace_next_instruction(2, 0xf1, 20.into());
+        tracer.trace_prepare_execute(2, 0xf1, 10.into(), None, None);
+        tracer.prepare_subtrace(&[]);
+        tracer.trace_next_instruction(0, 0xff, 5.into());
+        tracer.trace_suicide_refund(destroyed, 24000);
+        tracer.done_subtrace();
+        tracer.trace_executed(10.into(), &[1.into()], &[]);
+
+        // And destroying it in the top-level frame again.
+        tracer.trace_next_instruction(3, 0xff, 10.into());
+        tracer.trace_suicide_refund(destroyed, 24000);
+        tracer.done_subtrace();
+
+        let steps = tracer.drain().unwrap();
+        let refunds: Vec<_> = steps.iter().map(|s| (s.depth, s.refund)).collect();
+        assert_eq!(
+            refunds,
+            vec![
+                (1, 0),
+                (2, 0),
+                (1, 0),
+                (2, 0),
+                (1, 24000),
+                (2, 24000),
+                (1, 24000)
+            ]
+        );
+    }
+
+    #[test]
+    fn should_serialize_as_eip3155() {
+        let step = Eip3155Step {
+            pc: 0,
+            op: 0x60,
+            gas: 0xffff.into(),
+            gas_cost: 3.into(),
+            memory: None,
+            mem_size: 0,
+            stack: vec![1.into()],
+            depth: 1,
+            refund: 0,
+            op_name: "PUSH1".into(),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&step).unwrap(),
+            r#"{"pc":0,"op":96,"gas":"0xffff","gasCost":"0x3","memSize":0,"stack":["0x1"],"depth":1,"refund":0,"opName":"PUSH1"}"#
+        );
+    }
+}
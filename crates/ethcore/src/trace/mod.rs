@@ -18,6 +18,7 @@
 
 mod config;
 mod db;
+mod eip3155_tracer;
 mod executive_tracer;
 mod import;
 mod noop_tracer;
@@ -26,6 +27,7 @@ mod types;
 pub use self::{
     config::Config,
     db::TraceDB,
+    eip3155_tracer::{Eip3155Sink, Eip3155Step, Eip3155Tracer},
     executive_tracer::{ExecutiveTracer, ExecutiveVMTracer},
     import::ImportRequest,
     localized::LocalizedTrace,
@@ -107,6 +109,12 @@ pub trait VMTracer: Send {
     /// Trace the finalised execution of a single valid instruction.
     fn trace_executed(&mut self, _gas_used: U256, _stack_push: &[U256], _mem: &[u8]) {}
 
+    /// Trace a change of the refund counter by `_refund`.
+    fn trace_refund(&mut self, _refund: i128) {}
+
+    /// Trace the refund for destroying `_address`, which is due once per transaction.
+    fn trace_suicide_refund(&mut self, _address: Address, _refund: i128) {}
+
     /// Spawn subtracer which will be used to trace deeper levels of execution.
     fn prepare_subtrace(&mut self, _code: &[u8]) {}
 
@@ -125,6 +133,10 @@ pub trait DatabaseExtras {
 
     /// Returns hash of transaction at given position.
     fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256>;
+
+    /// Returns hash of transaction at given position in the block with given hash.
+    /// The block does not need to be canonical.
+    fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256>;
 }
 
 /// Db provides an interface to query tracesdb.
@@ -153,6 +165,13 @@ pub trait Database {
     /// Returns localized traces created in given block.
     fn block_traces(&self, block_number: BlockNumber) -> Option<Vec<LocalizedTrace>>;
 
+    /// Returns localized traces created in the block with given hash, canonical or not.
+    fn block_traces_by_hash(
+        &self,
+        block_number: BlockNumber,
+        block_hash: H256,
+    ) -> Option<Vec<LocalizedTrace>>;
+
     /// Filter traces matching given filter.
     fn filter(&self, filter: &Filter) -> Vec<LocalizedTrace>;
 }
@@ -383,7 +383,18 @@ impl<K: Kind> VerificationQueue<K> {
 
             let hash = item.hash();
             // t_nb 5.0 verify standalone block (this verification is done in VerificationQueue thread pool)
-            let is_ready = match K::verify(item, &*engine, verification.check_seal) {
+            let verified = {
+                let mut span = telemetry::span("verify_block");
+                if span.is_recording() {
+                    span.set_attribute("block.hash", format!("{:x}", hash));
+                }
+                let verified = K::verify(item, &*engine, verification.check_seal);
+                if verified.is_err() {
+                    span.set_error();
+                }
+                verified
+            };
+            let is_ready = match verified {
                 Ok(verified) => {
                     let mut verifying = verification.verifying.lock();
                     let mut idx = None;
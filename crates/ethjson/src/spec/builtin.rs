@@ -17,7 +17,7 @@
 //! Spec builtin deserialization.
 
 use crate::uint::Uint;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, path::Path};
 
 /// Linear pricing.
 #[derive(Debug, PartialEq, Deserialize, Clone)]
@@ -130,10 +130,14 @@ pub enum Pricing {
 pub struct BuiltinCompat {
     /// Builtin name.
     name: String,
-    /// Builtin pricing.
-    pricing: PricingCompat,
+    /// Builtin pricing, may be omitted for builtins with a registered pricer.
+    pricing: Option<PricingCompat>,
     /// Activation block.
     activate_at: Option<Uint>,
+    /// Deactivation block.
+    deactivate_at: Option<Uint>,
+    /// Path to the trusted setup file.
+    trusted_setup: Option<String>,
 }
 
 /// Spec builtin.
@@ -143,14 +147,29 @@ pub struct Builtin {
     pub name: String,
     /// Builtin pricing.
     pub pricing: BTreeMap<u64, PricingAt>,
+    /// Activation block of a builtin without pricing, which uses its registered pricer.
+    pub activate_at: Option<u64>,
+    /// Block at which the builtin is deactivated.
+    pub deactivate_at: Option<u64>,
+    /// Path to the KZG trusted setup file of `kzg_point_evaluation`.
+    pub trusted_setup: Option<String>,
+}
+
+impl BuiltinCompat {
+    /// Resolves a relative trusted setup path against `dir`.
+    pub(crate) fn resolve_paths(&mut self, dir: &Path) {
+        if let Some(ref mut trusted_setup) = self.trusted_setup {
+            *trusted_setup = dir.join(&*trusted_setup).to_string_lossy().into_owned();
+        }
+    }
 }
 
 impl From<BuiltinCompat> for Builtin {
     fn from(legacy: BuiltinCompat) -> Self {
-        let pricing = match legacy.pricing {
-            PricingCompat::Single(pricing) => {
+        let activate_at: u64 = legacy.activate_at.map_or(0, Into::into);
+        let (pricing, activate_at) = match legacy.pricing {
+            Some(PricingCompat::Single(pricing)) => {
                 let mut map = BTreeMap::new();
-                let activate_at: u64 = legacy.activate_at.map_or(0, Into::into);
                 map.insert(
                     activate_at,
                     PricingAt {
@@ -158,15 +177,20 @@ impl From<BuiltinCompat> for Builtin {
                         price: pricing,
                     },
                 );
-                map
-            }
-            PricingCompat::Multi(pricings) => {
-                pricings.into_iter().map(|(a, p)| (a.into(), p)).collect()
+                (map, None)
             }
+            Some(PricingCompat::Multi(pricings)) => (
+                pricings.into_iter().map(|(a, p)| (a.into(), p)).collect(),
+                None,
+            ),
+            None => (BTreeMap::new(), Some(activate_at)),
         };
         Self {
             name: legacy.name,
             pricing,
+            activate_at,
+            deactivate_at: legacy.deactivate_at.map(Into::into),
+            trusted_setup: legacy.trusted_setup,
         }
     }
 }
@@ -201,6 +225,7 @@ mod tests {
     };
     use macros::map;
     use serde_json;
+    use std::path::{Path, PathBuf};
 
     #[test]
     fn builtin_deserialization() {
@@ -317,6 +342,78 @@ mod tests {
             ]
         );
     }
+    #[test]
+    fn deactivate_at() {
+        let s = r#"{
+			"name": "short_lived",
+			"activate_at": 100,
+			"deactivate_at": "0x1000",
+			"pricing": { "linear": { "base": 10, "word": 1 } }
+		}"#;
+
+        let builtin: Builtin = serde_json::from_str::<BuiltinCompat>(s).unwrap().into();
+        assert_eq!(builtin.activate_at, None);
+        assert_eq!(builtin.deactivate_at, Some(0x1000));
+        assert_eq!(
+            builtin.pricing,
+            map![
+                100 => PricingAt {
+                    info: None,
+                    price: Pricing::Linear(Linear { base: 10, word: 1 })
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn without_pricing() {
+        let s = r#"{
+			"name": "registered",
+			"activate_at": 100
+		}"#;
+
+        let builtin: Builtin = serde_json::from_str::<BuiltinCompat>(s).unwrap().into();
+        assert_eq!(builtin.name, "registered");
+        assert!(builtin.pricing.is_empty());
+        assert_eq!(builtin.activate_at, Some(100));
+        assert_eq!(builtin.deactivate_at, None);
+    }
+
+    #[test]
+    fn trusted_setup() {
+        let s = r#"{
+			"name": "kzg_point_evaluation",
+			"trusted_setup": "res/trusted_setup.txt",
+			"pricing": { "linear": { "base": 50000, "word": 0 } }
+		}"#;
+
+        let builtin: Builtin = serde_json::from_str::<BuiltinCompat>(s).unwrap().into();
+        assert_eq!(builtin.name, "kzg_point_evaluation");
+        assert_eq!(
+            builtin.trusted_setup,
+            Some("res/trusted_setup.txt".to_owned())
+        );
+
+        let mut compat = serde_json::from_str::<BuiltinCompat>(s).unwrap();
+        compat.resolve_paths(Path::new("/etc/chains"));
+        let builtin: Builtin = compat.into();
+        assert_eq!(
+            builtin.trusted_setup.map(PathBuf::from),
+            Some(Path::new("/etc/chains").join("res/trusted_setup.txt"))
+        );
+
+        let mut compat = serde_json::from_str::<BuiltinCompat>(
+            &s.replace("res/trusted_setup.txt", "/srv/trusted_setup.txt"),
+        )
+        .unwrap();
+        compat.resolve_paths(Path::new("/etc/chains"));
+        let builtin: Builtin = compat.into();
+        assert_eq!(
+            builtin.trusted_setup,
+            Some("/srv/trusted_setup.txt".to_owned())
+        );
+    }
+
     #[test]
     fn deserialization_bls12_381_multiexp_operation() {
         let s = r#"{
@@ -32,6 +32,7 @@ pub mod spec;
 pub mod state;
 pub mod step_duration;
 pub mod validator_set;
+pub mod wasm;
 
 pub use self::{
     account::Account,
@@ -50,4 +51,5 @@ pub use self::{
     state::State,
     step_duration::StepDuration,
     validator_set::ValidatorSet,
+    wasm::WasmCosts,
 };
@@ -19,6 +19,7 @@
 use crate::{
     bytes::Bytes,
     hash::{Address, H256},
+    spec::WasmCosts,
     uint::{self, Uint},
 };
 
@@ -120,6 +121,14 @@ pub struct Params {
     /// See `CommonParams` docs.
     pub eip3541_transition: Option<Uint>,
     /// See `CommonParams` docs.
+    pub eip3540_transition: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip3670_transition: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip4200_transition: Option<Uint>,
+    /// See `CommonParams` docs.
+    pub eip4750_transition: Option<Uint>,
+    /// See `CommonParams` docs.
     pub eip3607_transition: Option<Uint>,
     /// See `CommonParams` docs.
     pub dust_protection_transition: Option<Uint>,
@@ -150,6 +159,8 @@ pub struct Params {
     pub wasm_activation_transition: Option<Uint>,
     /// Wasm deactivation block height, if activated.
     pub wasm_disable_transition: Option<Uint>,
+    /// Wasm costs and limits, if different from the defaults.
+    pub wasm_costs: Option<WasmCosts>,
     /// KIP4 activiation block height.
     pub kip4_transition: Option<Uint>,
     /// KIP6 activiation block height.
@@ -190,7 +201,8 @@ mod tests {
 			"gasLimitBoundDivisor": "0x20",
 			"maxCodeSize": "0x1000",
 			"wasmActivationTransition": "0x1010",
-            "wasmDisableTransition": "0x2010"
+            "wasmDisableTransition": "0x2010",
+			"wasmCosts": { "maxMemoryPages": 32 }
 		}"#;
 
         let deserialized: Params = serde_json::from_str(s).unwrap();
@@ -213,6 +225,10 @@ mod tests {
             deserialized.wasm_disable_transition,
             Some(Uint(U256::from(0x2010)))
         );
+        assert_eq!(
+            deserialized.wasm_costs.unwrap().max_memory_pages,
+            Some(Uint(U256::from(32)))
+        );
     }
 
     #[test]
@@ -18,7 +18,7 @@
 
 use crate::spec::{Engine, Genesis, Params, State};
 use serde_json::{self, Error};
-use std::io::Read;
+use std::{io::Read, path::Path};
 
 /// Fork spec definition
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
@@ -71,6 +71,12 @@ impl Spec {
     {
         serde_json::from_reader(reader)
     }
+
+    /// Resolves the relative paths of the spec, like the KZG trusted setup, against `dir`,
+    /// usually the directory of the spec file.
+    pub fn resolve_paths(&mut self, dir: &Path) {
+        self.accounts.resolve_paths(dir)
+    }
 }
 
 #[cfg(test)]
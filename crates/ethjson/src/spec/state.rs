@@ -21,7 +21,7 @@ use crate::{
     hash::Address,
     spec::{Account, Builtin},
 };
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, path::Path};
 
 /// Blockchain test state deserializer.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -37,6 +37,15 @@ impl State {
             .collect()
     }
 
+    /// Resolves the relative paths of builtins against `dir`.
+    pub(crate) fn resolve_paths(&mut self, dir: &Path) {
+        for account in self.0.values_mut() {
+            if let Some(ref mut builtin) = account.builtin {
+                builtin.resolve_paths(dir);
+            }
+        }
+    }
+
     /// Returns all constructors.
     pub fn constructors(&self) -> BTreeMap<Address, Bytes> {
         self.0
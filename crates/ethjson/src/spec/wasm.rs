@@ -0,0 +1,93 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Wasm costs and limits.
+
+use std::collections::BTreeMap;
+
+use serde::Deserialize;
+
+use crate::uint::Uint;
+
+/// Wasm costs and limits, overriding the defaults of the schedule.
+#[derive(Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct WasmCosts {
+    /// Default instruction cost.
+    pub regular: Option<Uint>,
+    /// Div instructions cost.
+    pub div: Option<Uint>,
+    /// Mul instructions cost.
+    pub mul: Option<Uint>,
+    /// Memory (load/store) instructions cost.
+    pub mem: Option<Uint>,
+    /// Cost of the instruction classes, by pwasm-utils class name (i.e. `flow` or `grow_mem`).
+    pub instructions: Option<BTreeMap<String, Uint>>,
+    /// Cost of static queries of U256 values.
+    pub static_u256: Option<Uint>,
+    /// Cost of static queries of addresses.
+    pub static_address: Option<Uint>,
+    /// Initial memory cost, per page (64kb).
+    pub initial_mem: Option<Uint>,
+    /// Memory growth cost, per page (64kb).
+    pub grow_mem: Option<Uint>,
+    /// Memory copy cost, per byte.
+    pub memcpy: Option<Uint>,
+    /// Maximum stack height.
+    pub max_stack_height: Option<Uint>,
+    /// Maximum memory, in pages (64kb).
+    pub max_memory_pages: Option<Uint>,
+    /// Instruction cost multiplier, relative to gas.
+    pub opcodes_mul: Option<Uint>,
+    /// Instruction cost divisor, relative to gas.
+    pub opcodes_div: Option<Uint>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmCosts;
+    use crate::uint::Uint;
+    use ethereum_types::U256;
+    use serde_json;
+
+    #[test]
+    fn wasm_costs_deserialization() {
+        let s = r#"{
+			"regular": 2,
+			"growMem": "0x4000",
+			"maxStackHeight": 32768,
+			"maxMemoryPages": 32,
+			"instructions": { "flow": 3, "grow_mem": 10 }
+		}"#;
+
+        let deserialized: WasmCosts = serde_json::from_str(s).unwrap();
+        assert_eq!(deserialized.regular, Some(Uint(U256::from(2))));
+        assert_eq!(deserialized.grow_mem, Some(Uint(U256::from(0x4000))));
+        assert_eq!(deserialized.max_stack_height, Some(Uint(U256::from(32768))));
+        assert_eq!(deserialized.max_memory_pages, Some(Uint(U256::from(32))));
+        assert_eq!(deserialized.div, None);
+        let instructions = deserialized.instructions.unwrap();
+        assert_eq!(instructions["flow"], Uint(U256::from(3)));
+        assert_eq!(instructions["grow_mem"], Uint(U256::from(10)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn wasm_costs_unknown_field() {
+        let _: WasmCosts = serde_json::from_str(r#"{ "regularCost": 2 }"#).unwrap();
+    }
+}
@@ -39,6 +39,17 @@ impl Into<u64> for Uint {
     }
 }
 
+/// Saturates at `u32::max_value()`, so that out of range values can be told apart.
+impl Into<u32> for Uint {
+    fn into(self) -> u32 {
+        if self.0 > U256::from(u32::max_value()) {
+            u32::max_value()
+        } else {
+            self.0.low_u32()
+        }
+    }
+}
+
 impl Into<usize> for Uint {
     fn into(self) -> usize {
         self.0.low_u64() as usize
@@ -172,4 +183,12 @@ mod test {
     fn uint_into() {
         assert_eq!(U256::from(10), Uint(U256::from(10)).into());
     }
+
+    #[test]
+    fn uint_into_u32_saturates() {
+        let value: u32 = Uint(U256::from(10)).into();
+        assert_eq!(value, 10);
+        let value: u32 = Uint(U256::from(u64::max_value())).into();
+        assert_eq!(value, u32::max_value());
+    }
 }
@@ -14,11 +14,26 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-//! OpenEthereum RPC Servers (WS, HTTP, IPC).
+//! OpenEthereum RPC Servers (WS, HTTP, IPC, GraphQL).
 
 #![warn(missing_docs)]
 
-use std::{io, net::SocketAddr};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+};
+
+use http::{
+    cors::AllowCors,
+    hyper::{self, header, Body, Method, StatusCode},
+};
+use jsonrpc_core::futures::{
+    future::{self, Either},
+    sync::oneshot,
+    Future, Stream,
+};
 
 pub use jsonrpc_core::{MetaIoHandler, Metadata, Middleware};
 
@@ -64,10 +79,11 @@ where
 
 /// Same as `start_http`, but takes an additional `middleware` parameter that is introduced as a
 /// hyper middleware.
-pub fn start_http_with_middleware<M, S, H, T, R>(
+pub fn start_http_with_middleware<M, S, H, T, R, A, B>(
     addr: &SocketAddr,
     cors_domains: http::DomainsValidation<http::AccessControlAllowOrigin>,
     allowed_hosts: http::DomainsValidation<http::Host>,
+    health_api: Option<(A, B)>,
     handler: H,
     extractor: T,
     middleware: R,
@@ -83,18 +99,208 @@ where
     H: Into<jsonrpc_core::MetaIoHandler<M, S>>,
     T: http::MetaExtractor<M>,
     R: http::RequestMiddleware,
+    A: Into<String>,
+    B: Into<String>,
 {
     Ok(http::ServerBuilder::with_meta_extractor(handler, extractor)
         .keep_alive(keep_alive)
         .threads(threads)
         .cors(cors_domains)
         .allowed_hosts(allowed_hosts)
+        .health_api(health_api)
         .cors_allow_headers(http::cors::AccessControlAllowHeaders::Any)
         .max_request_body_size(max_payload * 1024 * 1024)
         .request_middleware(middleware)
         .start_http(addr)?)
 }
 
+/// Executes GraphQL requests.
+pub trait GraphQLHandler: Send + Sync + 'static {
+    /// Executes JSON-encoded GraphQL request and returns the JSON-encoded response
+    /// together with a flag telling whether the request could be executed at all.
+    fn execute(&self, request: &[u8]) -> (bool, String);
+}
+
+/// Number of requests queued per GraphQL worker before new ones are turned away.
+const GRAPHQL_QUEUE_PER_WORKER: usize = 16;
+
+/// Start GraphQL server answering queries sent as HTTP POST requests.
+///
+/// Requests are executed by `workers` threads, so that slow queries don't block the server threads.
+pub fn start_graphql<H: GraphQLHandler>(
+    addr: &SocketAddr,
+    cors_domains: http::DomainsValidation<http::AccessControlAllowOrigin>,
+    allowed_hosts: http::DomainsValidation<http::Host>,
+    handler: H,
+    threads: usize,
+    workers: usize,
+    max_payload: usize,
+) -> io::Result<HttpServer> {
+    // All requests are answered by the middleware, the JSON-RPC handler is never reached.
+    Ok(http::ServerBuilder::new(jsonrpc_core::IoHandler::default())
+        .threads(threads)
+        .allowed_hosts(allowed_hosts)
+        .request_middleware(GraphQLMiddleware {
+            handler: Arc::new(handler),
+            workers: Arc::new(GraphQLWorkers::start(workers)?),
+            cors_domains: cors_domains.into(),
+            max_payload: max_payload * 1024 * 1024,
+        })
+        .start_http(addr)?)
+}
+
+type GraphQLJob = Box<dyn FnOnce() + Send>;
+
+/// Threads executing GraphQL requests. They stop once the server is dropped.
+struct GraphQLWorkers {
+    jobs: Mutex<mpsc::SyncSender<GraphQLJob>>,
+}
+
+impl GraphQLWorkers {
+    fn start(workers: usize) -> io::Result<Self> {
+        let workers = workers.max(1);
+        let (sender, receiver) =
+            mpsc::sync_channel::<GraphQLJob>(workers * GRAPHQL_QUEUE_PER_WORKER);
+        let receiver = Arc::new(Mutex::new(receiver));
+        for i in 0..workers {
+            let receiver = receiver.clone();
+            thread::Builder::new()
+                .name(format!("graphql-{}", i))
+                .spawn(move || loop {
+                    let job = match receiver.lock() {
+                        Ok(receiver) => receiver.recv(),
+                        Err(_) => return,
+                    };
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => return,
+                    }
+                })?;
+        }
+        Ok(GraphQLWorkers {
+            jobs: Mutex::new(sender),
+        })
+    }
+
+    /// Queues the job, returning `false` if all workers are busy.
+    fn spawn(&self, job: GraphQLJob) -> bool {
+        match self.jobs.lock() {
+            Ok(jobs) => jobs.try_send(job).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+struct GraphQLMiddleware<H> {
+    handler: Arc<H>,
+    workers: Arc<GraphQLWorkers>,
+    cors_domains: Option<Vec<http::AccessControlAllowOrigin>>,
+    max_payload: usize,
+}
+
+impl<H: GraphQLHandler> http::RequestMiddleware for GraphQLMiddleware<H> {
+    fn on_request(&self, request: hyper::Request<Body>) -> http::RequestMiddlewareAction {
+        let allow_origin = match http::cors_allow_origin(&request, &self.cors_domains) {
+            AllowCors::Invalid => {
+                return graphql_response(StatusCode::FORBIDDEN, None, "Origin not allowed").into()
+            }
+            AllowCors::Ok(origin) => Some(origin),
+            AllowCors::NotRequired => None,
+        };
+        let content_length = request
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<usize>().ok());
+
+        let method = request.method().clone();
+        match method {
+            Method::OPTIONS => graphql_response(StatusCode::OK, allow_origin, "").into(),
+            Method::POST if content_length.map_or(false, |len| len > self.max_payload) => {
+                graphql_response(StatusCode::PAYLOAD_TOO_LARGE, allow_origin, "").into()
+            }
+            Method::POST => {
+                let handler = self.handler.clone();
+                let workers = self.workers.clone();
+                let max_payload = self.max_payload;
+                let response = request.into_body().concat2().and_then(move |body| {
+                    if body.len() > max_payload {
+                        return Either::A(future::ok(graphql_response(
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            allow_origin,
+                            "",
+                        )));
+                    }
+                    let (sender, receiver) = oneshot::channel();
+                    let job = Box::new(move || {
+                        let _ = sender.send(handler.execute(&body));
+                    });
+                    if !workers.spawn(job) {
+                        return Either::A(future::ok(graphql_response(
+                            StatusCode::SERVICE_UNAVAILABLE,
+                            allow_origin,
+                            "",
+                        )));
+                    }
+                    Either::B(receiver.then(move |result| {
+                        Ok(match result {
+                            Ok((true, result)) => {
+                                graphql_response(StatusCode::OK, allow_origin, result)
+                            }
+                            Ok((false, result)) => {
+                                graphql_response(StatusCode::BAD_REQUEST, allow_origin, result)
+                            }
+                            Err(_) => graphql_response(
+                                StatusCode::INTERNAL_SERVER_ERROR,
+                                allow_origin,
+                                "",
+                            ),
+                        })
+                    }))
+                });
+                http::RequestMiddlewareAction::Respond {
+                    should_validate_hosts: true,
+                    response: Box::new(response),
+                }
+            }
+            _ => http::RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::new(future::ok(graphql_response(
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    allow_origin,
+                    "Send GraphQL queries with POST requests",
+                ))),
+            },
+        }
+    }
+}
+
+fn graphql_response<T: Into<Body>>(
+    status: StatusCode,
+    allow_origin: Option<header::HeaderValue>,
+    body: T,
+) -> hyper::Response<Body> {
+    let mut response = hyper::Response::new(body.into());
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_TYPE,
+        header::HeaderValue::from_static("application/json"),
+    );
+    if let Some(origin) = allow_origin {
+        headers.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            header::HeaderValue::from_static("POST, OPTIONS"),
+        );
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_HEADERS,
+            header::HeaderValue::from_static("Content-Type"),
+        );
+    }
+    response
+}
+
 /// Start IPC server listening on given path.
 pub fn start_ipc<M, S, H, T>(addr: &str, handler: H, extractor: T) -> io::Result<ipc::Server>
 where
@@ -0,0 +1,780 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates the OpenRPC document of methods declared in `src/v1/traits`,
+//! with schemas of parameter and result types declared in `src/v1/types`.
+
+#[macro_use]
+extern crate serde_json;
+extern crate syn;
+
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+use syn::{
+    punctuated::Punctuated, Attribute, Fields, FnArg, GenericArgument, Item, Lit, Meta, NestedMeta,
+    Pat, PathArguments, ReturnType, Token, TraitItem, Type,
+};
+
+const ERROR_MSG: &'static str = "Failed to generate OpenRPC document";
+const OPENRPC_VERSION: &'static str = "1.2.6";
+
+fn main() {
+    let src = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect(ERROR_MSG)).join("src/v1");
+    let traits = src.join("traits");
+    let types = src.join("types");
+    println!("cargo:rerun-if-changed={}", traits.display());
+    println!("cargo:rerun-if-changed={}", types.display());
+
+    let document = generate(&traits, &types);
+    let out_dir = env::var("OUT_DIR").expect(ERROR_MSG);
+    fs::write(
+        Path::new(&out_dir).join("openrpc.json"),
+        document.to_string(),
+    )
+    .expect(ERROR_MSG);
+}
+
+/// Returns OpenRPC document of traits and types in given directories.
+fn generate(traits: &Path, types: &Path) -> Value {
+    let mut schemas = Schemas::default();
+    for (module, file) in parse_dir(types) {
+        schemas.add_module(&module, file.items);
+    }
+
+    let mut methods = BTreeMap::new();
+    for (_, file) in parse_dir(traits) {
+        for item in file.items {
+            if let Item::Trait(item) = item {
+                if !item.attrs.iter().any(|attr| attr.path.is_ident("rpc")) {
+                    continue;
+                }
+                for item in item.items {
+                    if let TraitItem::Method(method) = item {
+                        if let Some((name, method)) = schemas.method(&method) {
+                            methods.entry(name).or_insert(method);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": "OpenEthereum JSON-RPC API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods.into_iter().map(|(_, m)| m).collect::<Vec<_>>(),
+        "components": { "schemas": schemas.into_components() },
+    })
+}
+
+/// Parses all Rust files in given directory, sorted by module name.
+fn parse_dir(dir: &Path) -> Vec<(String, syn::File)> {
+    let mut files = fs::read_dir(dir)
+        .expect(ERROR_MSG)
+        .map(|entry| entry.expect(ERROR_MSG).path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "rs"))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+        .into_iter()
+        .map(|path| {
+            let module = path
+                .file_stem()
+                .expect(ERROR_MSG)
+                .to_string_lossy()
+                .into_owned();
+            let source = fs::read_to_string(&path).expect(ERROR_MSG);
+            let file = syn::parse_file(&source)
+                .unwrap_or_else(|e| panic!("{}: {}: {}", ERROR_MSG, path.display(), e));
+            (module, file)
+        })
+        .collect()
+}
+
+/// Schemas of types declared in `v1::types`.
+#[derive(Default)]
+struct Schemas {
+    /// Declarations by type name, with the module declaring them first.
+    items: HashMap<String, (String, Item)>,
+    /// Declarations by module and type name, for names declared in several modules.
+    qualified: HashMap<(String, String), Item>,
+    /// Types with hand-written serialization.
+    manual: BTreeSet<String>,
+    /// Types referenced from the document.
+    used: BTreeSet<String>,
+}
+
+impl Schemas {
+    fn add_module(&mut self, module: &str, items: Vec<Item>) {
+        for item in items {
+            match item {
+                Item::Impl(ref item) => {
+                    let serde_trait = item.trait_.as_ref().map_or(false, |(_, path, _)| {
+                        path.segments.last().map_or(false, |s| {
+                            s.ident == "Serialize" || s.ident == "Deserialize"
+                        })
+                    });
+                    if let (true, Type::Path(ty)) = (serde_trait, &*item.self_ty) {
+                        if let Some(segment) = ty.path.segments.last() {
+                            self.manual.insert(segment.ident.to_string());
+                        }
+                    }
+                }
+                Item::Struct(_) | Item::Enum(_) | Item::Type(_) => {
+                    let name = item_name(&item);
+                    self.qualified
+                        .insert((module.into(), name.clone()), item.clone());
+                    self.items.entry(name).or_insert((module.into(), item));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Method description, if the function is an RPC method.
+    fn method(&mut self, method: &syn::TraitItemMethod) -> Option<(String, Value)> {
+        let attr = method
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("rpc") || attr.path.is_ident("pubsub"))?;
+        let args = attr
+            .parse_args_with(Punctuated::<NestedMeta, Token![,]>::parse_terminated)
+            .expect(ERROR_MSG);
+        let name = args.iter().find_map(|arg| match arg {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident("name") => lit_str(&nv.lit),
+            _ => None,
+        })?;
+        let subscribe = args.iter().any(|arg| match arg {
+            NestedMeta::Meta(meta) => meta.path().is_ident("subscribe"),
+            _ => false,
+        });
+        let docs = docs(&method.attrs);
+        if docs.iter().any(|line| line.starts_with("@ignore")) {
+            return None;
+        }
+
+        let mut params = Vec::new();
+        let mut names = BTreeSet::new();
+        for input in &method.sig.inputs {
+            let (pat, ty) = match input {
+                FnArg::Typed(arg) => (&*arg.pat, &*arg.ty),
+                FnArg::Receiver(_) => continue,
+            };
+            if is_context(ty) {
+                continue;
+            }
+            let (ty, required) = match option_inner(ty) {
+                Some(inner) => (inner, false),
+                None => (ty, true),
+            };
+            let mut name = match pat {
+                Pat::Ident(pat) if !pat.ident.to_string().starts_with('_') => {
+                    camel_case(&pat.ident.to_string())
+                }
+                _ => param_name(ty),
+            };
+            if !names.insert(name.clone()) {
+                name = format!("{}{}", name, params.len() + 1);
+                names.insert(name.clone());
+            }
+            params.push(json!({
+                "name": name,
+                "required": required,
+                "schema": self.schema(ty, &[]),
+            }));
+        }
+
+        let result = match method.sig.output {
+            _ if subscribe => json!({ "type": "string", "description": "Subscription id" }),
+            ReturnType::Default => json!({ "type": "null" }),
+            ReturnType::Type(_, ref ty) => self.schema(ty, &[]),
+        };
+
+        let mut description = json!({
+            "name": name,
+            "params": params,
+            "result": { "name": "result", "schema": result },
+        });
+        let summary = docs
+            .iter()
+            .take_while(|line| !line.starts_with('@'))
+            .cloned()
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !summary.is_empty() {
+            description["summary"] = summary.into();
+        }
+        if docs.iter().any(|line| line.starts_with("@deprecated")) {
+            description["deprecated"] = true.into();
+        }
+        Some((name, description))
+    }
+
+    /// Schema of a type, `generics` are type parameters of the enclosing declaration.
+    fn schema(&mut self, ty: &Type, generics: &[String]) -> Value {
+        let path = match ty {
+            Type::Path(ty) => &ty.path,
+            Type::Reference(ty) => return self.schema(&ty.elem, generics),
+            Type::Paren(ty) => return self.schema(&ty.elem, generics),
+            Type::Tuple(ty) if ty.elems.is_empty() => return json!({ "type": "null" }),
+            Type::Tuple(ty) => {
+                let items = ty
+                    .elems
+                    .iter()
+                    .map(|ty| self.schema(ty, generics))
+                    .collect::<Vec<_>>();
+                return json!({ "type": "array", "items": items });
+            }
+            _ => return json!({}),
+        };
+        let segment = path.segments.last().expect(ERROR_MSG);
+        let name = segment.ident.to_string();
+        let args = match segment.arguments {
+            PathArguments::AngleBracketed(ref args) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    GenericArgument::Type(ty) => Some(ty.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let module = match path.segments.len() {
+            1 => None,
+            len => Some(path.segments[len - 2].ident.to_string()),
+        };
+
+        match (name.as_str(), args.len()) {
+            ("Result", _) | ("BoxFuture", _) | ("Box", 1) | ("Arc", 1) | ("Trailing", 1) => {
+                self.schema(&args[0], generics)
+            }
+            ("Option", 1) => json!({
+                "oneOf": [self.schema(&args[0], generics), { "type": "null" }],
+            }),
+            ("Vec", 1) | ("VecDeque", 1) | ("HashSet", 1) | ("BTreeSet", 1) => json!({
+                "type": "array",
+                "items": self.schema(&args[0], generics),
+            }),
+            ("HashMap", 2) | ("BTreeMap", 2) => json!({
+                "type": "object",
+                "additionalProperties": self.schema(&args[1], generics),
+            }),
+            ("Either", 2) => json!({
+                "oneOf": [self.schema(&args[0], generics), self.schema(&args[1], generics)],
+            }),
+            // Rich values are flattened with additional fields.
+            ("Rich", 1) => json!({
+                "allOf": [self.schema(&args[0], generics), { "type": "object" }],
+            }),
+            ("bool", _) => json!({ "type": "boolean" }),
+            ("String", _) | ("str", _) => json!({ "type": "string" }),
+            ("u8", _)
+            | ("u16", _)
+            | ("u32", _)
+            | ("u64", _)
+            | ("usize", _)
+            | ("i32", _)
+            | ("i64", _) => json!({ "type": "integer" }),
+            ("f32", _) | ("f64", _) => json!({ "type": "number" }),
+            ("Value", _) => json!({}),
+            ("H32", _) => hex_schema(Some(4)),
+            ("H64", _) => hex_schema(Some(8)),
+            ("H128", _) => hex_schema(Some(16)),
+            ("H160", _) | ("Address", _) => hex_schema(Some(20)),
+            ("H256", _) => hex_schema(Some(32)),
+            ("H512", _) => hex_schema(Some(64)),
+            ("H520", _) => hex_schema(Some(65)),
+            ("H2048", _) | ("Bloom", _) => hex_schema(Some(256)),
+            ("Bytes", _) => hex_schema(None),
+            ("U64", _) | ("U128", _) | ("U256", _) | ("Index", _) => quantity_schema(),
+            ("SubscriptionId", _) => json!({ "type": "string" }),
+            _ if generics.contains(&name) => json!({}),
+            _ => match self.declaration(module.as_ref().map(String::as_str), &name) {
+                Some((key, _)) => {
+                    let reference = json!({ "$ref": format!("#/components/schemas/{}", key) });
+                    self.used.insert(key);
+                    reference
+                }
+                None => json!({ "title": name }),
+            },
+        }
+    }
+
+    /// Schema key and declaration of a type.
+    fn declaration(&self, module: Option<&str>, name: &str) -> Option<(String, Item)> {
+        if let Some(module) = module {
+            if let Some(item) = self.qualified.get(&(module.into(), name.into())) {
+                return match self.items.get(name) {
+                    Some((first, _)) if first == module => Some((name.into(), item.clone())),
+                    _ => Some((format!("{}{}", pascal_case(module), name), item.clone())),
+                };
+            }
+        }
+        self.items
+            .get(name)
+            .map(|(_, item)| (name.into(), item.clone()))
+    }
+
+    /// Schemas of all referenced declarations.
+    fn into_components(mut self) -> Value {
+        let mut done = BTreeMap::new();
+        while let Some(key) = self
+            .used
+            .iter()
+            .find(|key| !done.contains_key(*key))
+            .cloned()
+        {
+            let item = self
+                .items
+                .get(&key)
+                .map(|(_, item)| item.clone())
+                .or_else(|| {
+                    self.qualified
+                        .iter()
+                        .find(|((module, name), _)| {
+                            format!("{}{}", pascal_case(module), name) == key
+                        })
+                        .map(|(_, item)| item.clone())
+                })
+                .expect(ERROR_MSG);
+            let schema = self.item_schema(&item);
+            done.insert(key, schema);
+        }
+        Value::Object(done.into_iter().collect())
+    }
+
+    fn item_schema(&mut self, item: &Item) -> Value {
+        let name = item_name(item);
+        let (attrs, generics) = match item {
+            Item::Struct(item) => (&item.attrs, &item.generics),
+            Item::Enum(item) => (&item.attrs, &item.generics),
+            Item::Type(item) => (&item.attrs, &item.generics),
+            _ => unreachable!("only types are declared; qed"),
+        };
+        let generics = generics
+            .type_params()
+            .map(|param| param.ident.to_string())
+            .collect::<Vec<_>>();
+
+        let mut schema = json!({ "title": name });
+        let docs = docs(attrs);
+        if !docs.is_empty() {
+            schema["description"] = docs.join(" ").into();
+        }
+
+        if let Some(manual) = self.manual_schema(&name) {
+            extend(&mut schema, manual);
+            return schema;
+        }
+        if self.manual.contains(&name) {
+            return schema;
+        }
+
+        let rename_all = serde_attr(attrs, "rename_all");
+        match item {
+            Item::Type(item) => {
+                return self.schema(&item.ty, &generics);
+            }
+            Item::Struct(item) => match item.fields {
+                Fields::Named(_) => {
+                    let (object, flattened) =
+                        self.object(&item.fields, rename_all.as_ref(), &generics);
+                    if flattened.is_empty() {
+                        extend(&mut schema, object);
+                    } else {
+                        let mut all_of = vec![object];
+                        all_of.extend(flattened);
+                        schema["allOf"] = all_of.into();
+                    }
+                }
+                Fields::Unnamed(ref unnamed) if unnamed.unnamed.len() == 1 => {
+                    return self.schema(&unnamed.unnamed[0].ty, &generics);
+                }
+                Fields::Unnamed(ref unnamed) => {
+                    let items = unnamed
+                        .unnamed
+                        .iter()
+                        .map(|field| self.schema(&field.ty, &generics))
+                        .collect::<Vec<_>>();
+                    extend(&mut schema, json!({ "type": "array", "items": items }));
+                }
+                Fields::Unit => schema["type"] = "null".into(),
+            },
+            Item::Enum(item) => {
+                let untagged = has_serde_flag(attrs, "untagged");
+                let unit = item
+                    .variants
+                    .iter()
+                    .all(|variant| matches!(variant.fields, Fields::Unit));
+                let variant_name = |variant: &syn::Variant| {
+                    serde_attr(&variant.attrs, "rename").unwrap_or_else(|| {
+                        rename(&variant.ident.to_string(), rename_all.as_ref(), true)
+                    })
+                };
+
+                if unit && !untagged {
+                    let names = item.variants.iter().map(variant_name).collect::<Vec<_>>();
+                    extend(&mut schema, json!({ "type": "string", "enum": names }));
+                } else {
+                    let mut variants = Vec::new();
+                    for variant in &item.variants {
+                        let variant_schema = match variant.fields {
+                            Fields::Unit if untagged => json!({ "type": "null" }),
+                            Fields::Unit => json!({ "enum": [variant_name(variant)] }),
+                            Fields::Unnamed(ref unnamed) if unnamed.unnamed.len() == 1 => {
+                                self.schema(&unnamed.unnamed[0].ty, &generics)
+                            }
+                            Fields::Unnamed(ref unnamed) => {
+                                let items = unnamed
+                                    .unnamed
+                                    .iter()
+                                    .map(|field| self.schema(&field.ty, &generics))
+                                    .collect::<Vec<_>>();
+                                json!({ "type": "array", "items": items })
+                            }
+                            Fields::Named(_) => {
+                                let variant_rename_all = serde_attr(&variant.attrs, "rename_all");
+                                let (object, _) = self.object(
+                                    &variant.fields,
+                                    variant_rename_all.as_ref(),
+                                    &generics,
+                                );
+                                object
+                            }
+                        };
+                        variants.push(match untagged || matches!(variant.fields, Fields::Unit) {
+                            true => variant_schema,
+                            false => {
+                                let name = variant_name(variant);
+                                json!({
+                                    "type": "object",
+                                    "properties": { name.clone(): variant_schema },
+                                    "required": [name],
+                                })
+                            }
+                        });
+                    }
+                    schema["oneOf"] = variants.into();
+                }
+            }
+            _ => unreachable!("only types are declared; qed"),
+        }
+        schema
+    }
+
+    /// Object schema fields of named struct fields and schemas of flattened fields.
+    fn object(
+        &mut self,
+        fields: &Fields,
+        rename_all: Option<&String>,
+        generics: &[String],
+    ) -> (Value, Vec<Value>) {
+        let mut properties = serde_json::Map::new();
+        let mut required = Vec::new();
+        let mut flattened = Vec::new();
+        for field in fields {
+            let ident = match field.ident {
+                Some(ref ident) => ident.to_string(),
+                None => continue,
+            };
+            if has_serde_flag(&field.attrs, "skip")
+                || has_serde_flag(&field.attrs, "skip_serializing")
+            {
+                continue;
+            }
+            if has_serde_flag(&field.attrs, "flatten") {
+                flattened.push(self.schema(&field.ty, generics));
+                continue;
+            }
+            let name = serde_attr(&field.attrs, "rename")
+                .unwrap_or_else(|| rename(&ident, rename_all, false));
+            let optional = option_inner(&field.ty).is_some()
+                || has_serde_flag(&field.attrs, "default")
+                || serde_attr(&field.attrs, "skip_serializing_if").is_some();
+            if !optional {
+                required.push(name.clone());
+            }
+            let mut schema = self.schema(&field.ty, generics);
+            let docs = docs(&field.attrs);
+            // references can't have sibling fields
+            if !docs.is_empty() && schema.get("$ref").is_none() {
+                schema["description"] = docs.join(" ").into();
+            }
+            properties.insert(name, schema);
+        }
+
+        let mut object = json!({ "type": "object", "properties": properties });
+        if !required.is_empty() {
+            object["required"] = required.into();
+        }
+        (object, flattened)
+    }
+
+    /// Schemas of hand-serialized types which are common in parameters and results.
+    fn manual_schema(&mut self, name: &str) -> Option<Value> {
+        let mut reference = |name: &str| {
+            self.used.insert(name.into());
+            json!({ "$ref": format!("#/components/schemas/{}", name) })
+        };
+
+        match name {
+            "BlockNumber" => Some(json!({
+                "oneOf": [
+                    { "type": "string", "enum": ["latest", "earliest", "pending"] },
+                    quantity_schema(),
+                    {
+                        "type": "object",
+                        "properties": {
+                            "blockNumber": quantity_schema(),
+                            "blockHash": hex_schema(Some(32)),
+                            "requireCanonical": { "type": "boolean" },
+                        },
+                    },
+                ],
+            })),
+            "BlockTransactions" => Some(json!({
+                "oneOf": [
+                    { "type": "array", "items": hex_schema(Some(32)) },
+                    { "type": "array", "items": reference("Transaction") },
+                ],
+            })),
+            "FilterChanges" => Some(json!({
+                "oneOf": [
+                    { "type": "array", "items": reference("Log") },
+                    { "type": "array", "items": hex_schema(Some(32)) },
+                ],
+            })),
+            "SyncStatus" => Some(json!({
+                "oneOf": [
+                    { "type": "boolean", "enum": [false] },
+                    reference("SyncInfo"),
+                ],
+            })),
+            "VariadicValue" => Some(json!({
+                "oneOf": [{ "type": "null" }, {}, { "type": "array", "items": {} }],
+            })),
+            "Work" => Some(json!({ "type": "array", "items": hex_schema(None) })),
+            _ => None,
+        }
+    }
+}
+
+/// Adds fields of the `fields` object to the `schema` object.
+fn extend(schema: &mut Value, fields: Value) {
+    if let (Some(schema), Value::Object(fields)) = (schema.as_object_mut(), fields) {
+        schema.extend(fields);
+    }
+}
+
+fn item_name(item: &Item) -> String {
+    match item {
+        Item::Struct(item) => item.ident.to_string(),
+        Item::Enum(item) => item.ident.to_string(),
+        Item::Type(item) => item.ident.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn hex_schema(bytes: Option<usize>) -> Value {
+    let pattern = match bytes {
+        Some(bytes) => format!("^0x[0-9a-fA-F]{{{}}}$", bytes * 2),
+        None => "^0x([0-9a-fA-F]{2})*$".into(),
+    };
+    json!({ "type": "string", "pattern": pattern })
+}
+
+fn quantity_schema() -> Value {
+    json!({ "type": "string", "pattern": "^0x([1-9a-fA-F][0-9a-fA-F]*|0)$" })
+}
+
+/// Whether the parameter is filled in by the server rather than sent by the client.
+fn is_context(ty: &Type) -> bool {
+    let ty = option_inner(ty).unwrap_or(ty);
+    match ty {
+        Type::Path(ty) => {
+            let first = ty.path.segments.first().map(|s| s.ident.to_string());
+            let last = ty.path.segments.last().map(|s| s.ident.to_string());
+            first.as_ref().map(String::as_str) == Some("Self")
+                || last.as_ref().map(String::as_str) == Some("Subscriber")
+        }
+        _ => false,
+    }
+}
+
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let segment = match ty {
+        Type::Path(ty) => ty.path.segments.last()?,
+        _ => return None,
+    };
+    if segment.ident != "Option" {
+        return None;
+    }
+    match segment.arguments {
+        PathArguments::AngleBracketed(ref args) => match args.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Parameter name derived from its type, e.g. `blockNumber` for `BlockNumber`.
+fn param_name(ty: &Type) -> String {
+    let segment = match ty {
+        Type::Path(ty) => ty.path.segments.last(),
+        Type::Reference(ty) => return param_name(&ty.elem),
+        _ => None,
+    };
+    let segment = match segment {
+        Some(segment) => segment,
+        None => return "param".into(),
+    };
+    let name = segment.ident.to_string();
+    let inner = match segment.arguments {
+        PathArguments::AngleBracketed(ref args) => match args.args.first() {
+            Some(GenericArgument::Type(ty)) => Some(param_name(ty)),
+            _ => None,
+        },
+        _ => None,
+    };
+    match (name.as_str(), inner) {
+        ("Vec", Some(inner)) => format!("{}s", inner),
+        (_, Some(ref inner)) if name == "Box" || name == "Trailing" => inner.clone(),
+        ("H160", _) | ("Address", _) => "address".into(),
+        ("H256", _) => "hash".into(),
+        ("U256", _) | ("U64", _) | ("u64", _) | ("usize", _) => "quantity".into(),
+        ("Bytes", _) => "data".into(),
+        ("String", _) => "string".into(),
+        ("bool", _) => "flag".into(),
+        _ => {
+            let mut chars = name.chars();
+            match chars.next() {
+                Some(first) => first.to_lowercase().chain(chars).collect(),
+                None => "param".into(),
+            }
+        }
+    }
+}
+
+fn docs(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => lit_str(&nv.lit),
+            _ => None,
+        })
+        .map(|line| line.trim().to_owned())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn lit_str(lit: &Lit) -> Option<String> {
+    match lit {
+        Lit::Str(s) => Some(s.value()),
+        _ => None,
+    }
+}
+
+/// Arguments of `#[serde(...)]` attributes.
+fn serde_args(attrs: &[Attribute]) -> Vec<Meta> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flat_map(|args| args.into_iter())
+        .filter_map(|arg| match arg {
+            NestedMeta::Meta(meta) => Some(meta),
+            NestedMeta::Lit(_) => None,
+        })
+        .collect()
+}
+
+fn serde_attr(attrs: &[Attribute], name: &str) -> Option<String> {
+    serde_args(attrs).iter().find_map(|meta| match meta {
+        Meta::NameValue(nv) if nv.path.is_ident(name) => lit_str(&nv.lit),
+        _ => None,
+    })
+}
+
+fn has_serde_flag(attrs: &[Attribute], name: &str) -> bool {
+    serde_args(attrs)
+        .iter()
+        .any(|meta| matches!(meta, Meta::Path(path) if path.is_ident(name)))
+}
+
+/// Applies serde `rename_all` rule to a field (snake_case) or variant (PascalCase) name.
+fn rename(name: &str, rule: Option<&String>, variant: bool) -> String {
+    let words = match variant {
+        true => split_pascal(name),
+        false => name.split('_').map(str::to_lowercase).collect(),
+    };
+    match rule.map(String::as_str) {
+        Some("camelCase") => camel_case(&words.join("_")),
+        Some("snake_case") => words.join("_"),
+        Some("kebab-case") => words.join("-"),
+        Some("lowercase") => words.concat(),
+        Some("UPPERCASE") => words.concat().to_uppercase(),
+        Some("PascalCase") => pascal_case(&words.join("_")),
+        _ => name.into(),
+    }
+}
+
+fn split_pascal(name: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+    for c in name.chars() {
+        if c.is_uppercase() || words.is_empty() {
+            words.push(c.to_lowercase().collect());
+        } else {
+            words.last_mut().expect("words is not empty; qed").push(c);
+        }
+    }
+    words
+}
+
+fn camel_case(name: &str) -> String {
+    let pascal = pascal_case(name);
+    let mut chars = pascal.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -41,7 +41,11 @@
 extern crate futures;
 
 extern crate ansi_term;
+extern crate base64;
 extern crate itertools;
+extern crate jsonwebtoken;
+#[macro_use]
+extern crate juniper;
 extern crate order_stat;
 extern crate parking_lot;
 extern crate rand;
@@ -76,6 +80,7 @@ extern crate parity_runtime;
 extern crate parity_version as version;
 extern crate rlp;
 extern crate stats;
+extern crate telemetry;
 extern crate vm;
 
 #[cfg(any(test, feature = "ethcore-accounts"))]
@@ -117,6 +122,7 @@ extern crate rpc_common;
 pub extern crate jsonrpc_ws_server as ws;
 
 mod authcodes;
+mod jwt;
 pub mod v1;
 
 // #[cfg(test)]
@@ -132,11 +138,12 @@ pub use ipc::{
 pub use jsonrpc_pubsub::Session as PubSubSession;
 
 pub use authcodes::{AuthCodes, TimeProvider};
+pub use jwt::{JwtAuth, JwtClaims};
 pub use v1::{
     block_import::{is_major_importing, is_major_importing_or_waiting},
     dispatch,
-    extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
-    informant, signer, Metadata, NetworkSettings, Origin,
+    extractors::{JwtExtractor, RpcExtractor, WsDispatcher, WsExtractor, WsStats},
+    graphql, informant, openrpc, rate_limit, signer, Metadata, NetworkSettings, Origin,
 };
 
 /// RPC HTTP Server instance
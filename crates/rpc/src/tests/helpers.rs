@@ -24,6 +24,23 @@ use parity_runtime::{Runtime, TaskExecutor};
 
 use authcodes::AuthCodes;
 
+/// Shared secret of HS256 access tokens used in tests.
+pub const JWT_SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+/// Creates HS256 access token granting given APIs.
+#[cfg(test)]
+pub fn jwt_token(secret: &[u8], apis: &[&str]) -> String {
+    use jsonwebtoken::{encode, Header};
+    use jwt::JwtClaims;
+
+    let claims = JwtClaims {
+        sub: Some("operator".into()),
+        exp: None,
+        apis: apis.iter().map(|api| api.to_string()).collect(),
+    };
+    encode(&Header::default(), &claims, secret).unwrap()
+}
+
 /// Server with event loop
 pub struct Server<T> {
     /// Server
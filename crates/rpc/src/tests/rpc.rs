@@ -14,10 +14,16 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::sync::Arc;
+
 use http::{self, hyper};
 use rpc_servers::{HttpServer, MetaIoHandler};
 
-use tests::{helpers::Server, http_client};
+use jwt::JwtAuth;
+use tests::{
+    helpers::{Server, JWT_SECRET},
+    http_client,
+};
 use v1::{extractors, Metadata};
 
 fn serve(handler: Option<MetaIoHandler<Metadata>>) -> Server<HttpServer> {
@@ -29,8 +35,9 @@ fn serve(handler: Option<MetaIoHandler<Metadata>>) -> Server<HttpServer> {
             &address,
             http::DomainsValidation::Disabled,
             http::DomainsValidation::Disabled,
+            None::<(String, String)>,
             handler,
-            extractors::RpcExtractor,
+            extractors::RpcExtractor::default(),
             |request: hyper::Request<hyper::Body>| http::RequestMiddlewareAction::Proceed {
                 should_continue_on_invalid_cors: false,
                 request,
@@ -43,6 +50,27 @@ fn serve(handler: Option<MetaIoHandler<Metadata>>) -> Server<HttpServer> {
     })
 }
 
+fn serve_jwt(handler: MetaIoHandler<Metadata>) -> Server<HttpServer> {
+    let address = "127.0.0.1:0".parse().unwrap();
+    let auth = Arc::new(JwtAuth::hs256(JWT_SECRET));
+
+    Server::new(|_remote| {
+        rpc_servers::start_http_with_middleware(
+            &address,
+            http::DomainsValidation::Disabled,
+            http::DomainsValidation::Disabled,
+            None::<(String, String)>,
+            handler,
+            extractors::JwtExtractor::new(auth.clone()),
+            extractors::JwtExtractor::new(auth),
+            1,
+            5,
+            false,
+        )
+        .unwrap()
+    })
+}
+
 /// Test a single request to running server
 fn request(server: Server<HttpServer>, request: &str) -> http_client::Response {
     http_client::request(server.server.address(), request)
@@ -52,6 +80,7 @@ fn request(server: Server<HttpServer>, request: &str) -> http_client::Response {
 mod tests {
     use super::{request, Server};
     use jsonrpc_core::{MetaIoHandler, Value};
+    use tests::helpers::{jwt_token, JWT_SECRET};
     use v1::Metadata;
 
     fn serve() -> (Server<::HttpServer>, ::std::net::SocketAddr) {
@@ -162,4 +191,93 @@ mod tests {
             res.headers
         );
     }
+
+    fn serve_jwt() -> (Server<::HttpServer>, ::std::net::SocketAddr) {
+        let mut io = MetaIoHandler::default();
+        io.add_method_with_meta("hello", |_, meta: Metadata| {
+            Ok(match meta.claims {
+                Some(claims) => {
+                    Value::Array(claims.apis.iter().cloned().map(Value::String).collect())
+                }
+                None => Value::Null,
+            })
+        });
+        let server = super::serve_jwt(io);
+        let address = server.server.address().to_owned();
+
+        (server, address)
+    }
+
+    fn request_with_token(
+        server: Server<::HttpServer>,
+        address: ::std::net::SocketAddr,
+        token: Option<String>,
+    ) -> ::tests::http_client::Response {
+        let req = r#"{"method":"hello","params":[],"jsonrpc":"2.0","id":1}"#;
+        let authorization = token
+            .map(|token| format!("Authorization: Bearer {}\r\n", token))
+            .unwrap_or_default();
+        request(
+            server,
+            &format!(
+                "\
+				POST / HTTP/1.1\r\n\
+				Host: {}\r\n\
+				Content-Type: application/json\r\n\
+				Content-Length: {}\r\n\
+				Connection: close\r\n\
+				{}\
+				\r\n\
+				{}
+			",
+                address,
+                req.len(),
+                authorization,
+                req
+            ),
+        )
+    }
+
+    #[test]
+    fn should_extract_jwt_claims() {
+        // given
+        let (server, address) = serve_jwt();
+        let token = jwt_token(JWT_SECRET, &["safe", "parity_set"]);
+
+        // when
+        let res = request_with_token(server, address, Some(token));
+
+        // then
+        res.assert_status("HTTP/1.1 200 OK");
+        assert_eq!(
+            res.body,
+            "{\"jsonrpc\":\"2.0\",\"result\":[\"safe\",\"parity_set\"],\"id\":1}\n"
+        );
+    }
+
+    #[test]
+    fn should_allow_requests_without_jwt() {
+        // given
+        let (server, address) = serve_jwt();
+
+        // when
+        let res = request_with_token(server, address, None);
+
+        // then
+        res.assert_status("HTTP/1.1 200 OK");
+        assert_eq!(res.body, "{\"jsonrpc\":\"2.0\",\"result\":null,\"id\":1}\n");
+    }
+
+    #[test]
+    fn should_reject_invalid_jwt() {
+        // given
+        let (server, address) = serve_jwt();
+        let token = jwt_token(b"another secret", &["all"]);
+
+        // when
+        let res = request_with_token(server, address, Some(token));
+
+        // then
+        res.assert_status("HTTP/1.1 401 Unauthorized");
+    }
 }
@@ -21,8 +21,9 @@ use std::sync::Arc;
 use jsonrpc_core::MetaIoHandler;
 use ws;
 
+use jwt::JwtAuth;
 use tests::{
-    helpers::{GuardedAuthCodes, Server},
+    helpers::{GuardedAuthCodes, Server, JWT_SECRET},
     http_client,
 };
 use v1::{extractors, informant};
@@ -33,6 +34,7 @@ pub fn serve() -> (Server<ws::Server>, usize, GuardedAuthCodes) {
     let io = MetaIoHandler::default();
     let authcodes = GuardedAuthCodes::default();
     let stats = Arc::new(informant::RpcStats::default());
+    let jwt = Some(Arc::new(JwtAuth::hs256(JWT_SECRET)));
 
     let res = Server::new(|_| {
         rpc_servers::start_ws(
@@ -41,8 +43,8 @@ pub fn serve() -> (Server<ws::Server>, usize, GuardedAuthCodes) {
             ws::DomainsValidation::Disabled,
             ws::DomainsValidation::Disabled,
             5,
-            extractors::WsExtractor::new(Some(&authcodes.path)),
-            extractors::WsExtractor::new(Some(&authcodes.path)),
+            extractors::WsExtractor::new(Some(&authcodes.path)).with_jwt(jwt.clone()),
+            extractors::WsExtractor::new(Some(&authcodes.path)).with_jwt(jwt),
             extractors::WsStats::new(stats),
             5 * 1024 * 1024,
         )
@@ -63,6 +65,7 @@ mod testing {
     use super::{http_client, request, serve};
     use hash::keccak;
     use std::time;
+    use tests::helpers::{jwt_token, JWT_SECRET};
 
     #[test]
     fn should_not_redirect_to_parity_host() {
@@ -184,4 +187,64 @@ mod testing {
         assert_eq!(response1.status, "HTTP/1.1 403 Forbidden".to_owned());
         http_client::assert_security_headers_present(&response1.headers, None);
     }
+
+    #[test]
+    fn should_allow_if_jwt_is_valid() {
+        // given
+        let (server, port, _) = serve();
+
+        // when
+        let response = request(
+            server,
+            &format!(
+                "\
+				GET / HTTP/1.1\r\n\
+				Host: 127.0.0.1:{}\r\n\
+				Connection: Close\r\n\
+				Sec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\
+				Sec-WebSocket-Protocol: {}\r\n\
+				Sec-WebSocket-Version: 13\r\n\
+				\r\n\
+				{{}}
+			",
+                port,
+                jwt_token(JWT_SECRET, &["eth"]),
+            ),
+        );
+
+        // then
+        assert_eq!(
+            response.status,
+            "HTTP/1.1 101 Switching Protocols".to_owned()
+        );
+    }
+
+    #[test]
+    fn should_block_if_jwt_is_invalid() {
+        // given
+        let (server, port, _) = serve();
+
+        // when
+        let response = request(
+            server,
+            &format!(
+                "\
+				GET / HTTP/1.1\r\n\
+				Host: 127.0.0.1:{}\r\n\
+				Connection: Close\r\n\
+				Sec-WebSocket-Key: x3JJHMbDL1EzLkh9GBhXDw==\r\n\
+				Sec-WebSocket-Protocol: {}\r\n\
+				Sec-WebSocket-Version: 13\r\n\
+				\r\n\
+				{{}}
+			",
+                port,
+                jwt_token(b"another secret", &["eth"]),
+            ),
+        );
+
+        // then
+        assert_eq!(response.status, "HTTP/1.1 403 Forbidden".to_owned());
+        http_client::assert_security_headers_present(&response.headers, None);
+    }
 }
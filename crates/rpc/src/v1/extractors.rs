@@ -26,14 +26,26 @@ use ethereum_types::H256;
 use http::hyper;
 use ipc;
 use jsonrpc_core as core;
-use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::future::{self, Either};
 use jsonrpc_pubsub::Session;
+use jwt::{self, JwtAuth, JwtClaims};
 use ws;
 
 use v1::{informant::RpcStats, Metadata, Origin};
 
 /// Common HTTP & IPC metadata extractor.
-pub struct RpcExtractor;
+#[derive(Default)]
+pub struct RpcExtractor {
+    trusted_proxies: usize,
+}
+
+impl RpcExtractor {
+    /// Identify HTTP clients by the `X-Forwarded-For` header of `proxies` reverse proxies.
+    pub fn with_trusted_proxies(mut self, proxies: usize) -> Self {
+        self.trusted_proxies = proxies;
+        self
+    }
+}
 
 impl http::MetaExtractor<Metadata> for RpcExtractor {
     fn read_metadata(&self, req: &hyper::Request<hyper::Body>) -> Metadata {
@@ -43,6 +55,19 @@ impl http::MetaExtractor<Metadata> for RpcExtractor {
 
         let origin = as_string(req.headers().get("origin"));
         let user_agent = as_string(req.headers().get("user-agent"));
+        // Each proxy appends the address it received the request from, so the client
+        // can only forge the addresses preceding the one added by the outermost proxy.
+        let client_ip = match self.trusted_proxies {
+            0 => None,
+            proxies => as_string(req.headers().get("x-forwarded-for")).and_then(|ips| {
+                ips.rsplit(',')
+                    .nth(proxies - 1)
+                    .and_then(|ip| ip.trim().parse().ok())
+            }),
+        };
+
+        let request_size = as_string(req.headers().get(hyper::header::CONTENT_LENGTH))
+            .and_then(|len| len.parse().ok());
 
         Metadata {
             origin: Origin::Rpc(format!(
@@ -51,6 +76,9 @@ impl http::MetaExtractor<Metadata> for RpcExtractor {
                 user_agent.unwrap_or_else(|| "unknown agent".to_string())
             )),
             session: None,
+            claims: None,
+            client_ip,
+            request_size,
         }
     }
 }
@@ -60,6 +88,75 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
         Metadata {
             origin: Origin::Ipc(H256::from_low_u64_be(req.session_id)),
             session: Some(Arc::new(Session::new(req.sender.clone()))),
+            claims: None,
+            client_ip: None,
+            request_size: None,
+        }
+    }
+}
+
+/// HTTP metadata extractor and request middleware authenticating clients with JSON Web Tokens.
+///
+/// Requests without `Authorization` header proceed without claims,
+/// requests with an invalid token are rejected with `401 Unauthorized`.
+pub struct JwtExtractor {
+    auth: Arc<JwtAuth>,
+    extractor: RpcExtractor,
+}
+
+impl JwtExtractor {
+    /// Creates new `JwtExtractor` verifying tokens with given `JwtAuth`.
+    pub fn new(auth: Arc<JwtAuth>) -> Self {
+        JwtExtractor {
+            auth,
+            extractor: RpcExtractor::default(),
+        }
+    }
+
+    /// Identify clients without a token by the `X-Forwarded-For` header of `proxies` reverse proxies.
+    pub fn with_trusted_proxies(mut self, proxies: usize) -> Self {
+        self.extractor = self.extractor.with_trusted_proxies(proxies);
+        self
+    }
+
+    fn claims(&self, req: &hyper::Request<hyper::Body>) -> Option<Result<JwtClaims, String>> {
+        req.headers()
+            .get(hyper::header::AUTHORIZATION)
+            .map(|header| {
+                header
+                    .to_str()
+                    .map_err(|e| e.to_string())
+                    .and_then(|value| self.auth.verify_bearer(value))
+            })
+    }
+}
+
+impl http::MetaExtractor<Metadata> for JwtExtractor {
+    fn read_metadata(&self, req: &hyper::Request<hyper::Body>) -> Metadata {
+        let mut metadata = self.extractor.read_metadata(req);
+        metadata.claims = self.claims(req).and_then(Result::ok).map(Arc::new);
+        metadata
+    }
+}
+
+impl http::RequestMiddleware for JwtExtractor {
+    fn on_request(&self, request: hyper::Request<hyper::Body>) -> http::RequestMiddlewareAction {
+        if let Some(Err(err)) = self.claims(&request) {
+            warn!(target: "rpc", "Blocked HTTP request using invalid token: {}", err);
+            let response = hyper::Response::builder()
+                .status(hyper::StatusCode::UNAUTHORIZED)
+                .header(hyper::header::WWW_AUTHENTICATE, "Bearer")
+                .body(hyper::Body::from("Invalid access token.\n"))
+                .expect("Status and headers are valid; qed");
+            return http::RequestMiddlewareAction::Respond {
+                should_validate_hosts: true,
+                response: Box::new(future::ok(response)),
+            };
+        }
+
+        http::RequestMiddlewareAction::Proceed {
+            should_continue_on_invalid_cors: false,
+            request,
         }
     }
 }
@@ -67,6 +164,7 @@ impl ipc::MetaExtractor<Metadata> for RpcExtractor {
 /// WebSockets server metadata extractor and request middleware.
 pub struct WsExtractor {
     authcodes_path: Option<PathBuf>,
+    jwt: Option<Arc<JwtAuth>>,
 }
 
 impl WsExtractor {
@@ -74,6 +172,20 @@ impl WsExtractor {
     pub fn new(path: Option<&Path>) -> Self {
         WsExtractor {
             authcodes_path: path.map(ToOwned::to_owned),
+            jwt: None,
+        }
+    }
+
+    /// Accept JSON Web Tokens passed as the WebSocket protocol.
+    pub fn with_jwt(mut self, jwt: Option<Arc<JwtAuth>>) -> Self {
+        self.jwt = jwt;
+        self
+    }
+
+    fn jwt_claims(&self, protocol: &str) -> Option<Result<JwtClaims, String>> {
+        match self.jwt {
+            Some(ref auth) if jwt::is_jwt(protocol) => Some(auth.verify(protocol)),
+            _ => None,
         }
     }
 }
@@ -99,8 +211,20 @@ impl ws::MetaExtractor<Metadata> for WsExtractor {
                 session: H256::from_low_u64_be(id),
             },
         };
+        let claims = req
+            .protocols
+            .get(0)
+            .and_then(|p| self.jwt_claims(p))
+            .and_then(Result::ok)
+            .map(Arc::new);
         let session = Some(Arc::new(Session::new(req.sender())));
-        Metadata { origin, session }
+        Metadata {
+            origin,
+            session,
+            claims,
+            client_ip: None,
+            request_size: None,
+        }
     }
 }
 
@@ -128,20 +252,22 @@ impl ws::RequestMiddleware for WsExtractor {
 
         // If protocol is provided it needs to be valid.
         let protocols = req.protocols().ok().unwrap_or_else(Vec::new);
-        if let Some(ref path) = self.authcodes_path {
-            if protocols.len() == 1 {
-                let authorization = auth_token_hash(&path, protocols[0], false);
-                if authorization.is_none() {
-                    warn!(
-                        "Blocked connection from {} using invalid token.",
-                        req.header("origin")
-                            .and_then(|e| ::std::str::from_utf8(e).ok())
-                            .unwrap_or("Unknown Origin")
-                    );
-                    let mut response = Response::new(403, "Forbidden", vec![]);
-                    add_security_headers(&mut response);
-                    return Some(response).into();
-                }
+        if protocols.len() == 1 {
+            let authorized = match (self.jwt_claims(protocols[0]), &self.authcodes_path) {
+                (Some(claims), _) => claims.is_ok(),
+                (None, Some(path)) => auth_token_hash(&path, protocols[0], false).is_some(),
+                (None, None) => true,
+            };
+            if !authorized {
+                warn!(
+                    "Blocked connection from {} using invalid token.",
+                    req.header("origin")
+                        .and_then(|e| ::std::str::from_utf8(e).ok())
+                        .unwrap_or("Unknown Origin")
+                );
+                let mut response = Response::new(403, "Forbidden", vec![]);
+                add_security_headers(&mut response);
+                return Some(response).into();
             }
         }
 
@@ -265,12 +391,13 @@ mod tests {
         hyper::{Body, Request},
         MetaExtractor,
     };
+    use std::net::IpAddr;
     use Origin;
 
     #[test]
     fn should_extract_rpc_origin() {
         // given
-        let extractor = RpcExtractor;
+        let extractor = RpcExtractor::default();
         let req1 = Request::get("127.0.0.1").body(Body::empty()).unwrap();
         let req2 = Request::get("127.0.0.1")
             .header("user-agent", "http://openethereum.github.io")
@@ -301,4 +428,34 @@ mod tests {
             Origin::Rpc("http://openethereum.github.io / http://openethereum.github.io".into())
         );
     }
+
+    #[test]
+    fn should_extract_forwarded_client_ip() {
+        // given
+        let request = |forwarded_for: &str| {
+            Request::get("127.0.0.1")
+                .header("x-forwarded-for", forwarded_for)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let client_ip = |proxies: usize, forwarded_for: &str| {
+            RpcExtractor::default()
+                .with_trusted_proxies(proxies)
+                .read_metadata(&request(forwarded_for))
+                .client_ip
+        };
+        let ip = |ip: &str| Some(ip.parse::<IpAddr>().unwrap());
+
+        // then
+        assert_eq!(client_ip(0, "203.0.113.7"), None);
+        assert_eq!(client_ip(1, "203.0.113.7"), ip("203.0.113.7"));
+        assert_eq!(client_ip(1, "2001:db8::1"), ip("2001:db8::1"));
+        assert_eq!(client_ip(1, "198.51.100.1, 203.0.113.7"), ip("203.0.113.7"));
+        assert_eq!(
+            client_ip(2, "198.51.100.1, 203.0.113.7, 10.0.0.1"),
+            ip("203.0.113.7")
+        );
+        assert_eq!(client_ip(2, "203.0.113.7"), None);
+        assert_eq!(client_ip(1, "not an address"), None);
+    }
 }
@@ -0,0 +1,237 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Chain access of GraphQL resolvers.
+
+use std::sync::Arc;
+
+use ethcore::{
+    client::{
+        BlockChainClient, BlockId, Call, EngineInfo, StateClient, StateInfo, TransactionId, UncleId,
+    },
+    miner::{self, MinerService},
+};
+use ethereum_types::{H256, U256};
+use jsonrpc_core::Error;
+use types::{
+    blockchain_info::BlockChainInfo,
+    encoded,
+    filter::Filter,
+    header::Header,
+    log_entry::LocalizedLogEntry,
+    receipt::LocalizedReceipt,
+    transaction::{LocalizedTransaction, SignedTransaction, TypedTransaction},
+};
+
+use v1::helpers::{
+    dispatch::{default_gas_price, FullDispatcher},
+    errors,
+};
+
+/// State a query is executed against.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum At {
+    /// State after given block.
+    Block(BlockId),
+    /// State of the pending block, or the latest one if nothing is being sealed.
+    Pending,
+}
+
+/// Object-safe view of the client and miner used by the schema.
+pub trait Backend: Send + Sync {
+    /// Best block information.
+    fn chain_info(&self) -> BlockChainInfo;
+    /// Decoded header of given block.
+    fn header(&self, id: BlockId) -> Option<Header>;
+    /// Block with transactions.
+    fn block(&self, id: BlockId) -> Option<encoded::Block>;
+    /// Total difficulty of given block.
+    fn total_difficulty(&self, id: BlockId) -> Option<U256>;
+    /// Decoded header of an uncle.
+    fn uncle(&self, id: UncleId) -> Option<Header>;
+    /// Transaction included in the chain.
+    fn transaction(&self, id: TransactionId) -> Option<LocalizedTransaction>;
+    /// Receipt of transaction included in the chain.
+    fn receipt(&self, id: TransactionId) -> Option<LocalizedReceipt>;
+    /// Logs matching given filter.
+    fn logs(&self, filter: Filter) -> Result<Vec<LocalizedLogEntry>, Error>;
+    /// Account state at given block.
+    fn state(&self, at: At) -> Result<Box<dyn StateInfo>, Error>;
+    /// Executes a call without committing the state.
+    fn call(&self, transaction: &SignedTransaction, at: At)
+        -> Result<(Vec<u8>, U256, bool), Error>;
+    /// Estimates gas needed by the transaction.
+    fn estimate_gas(&self, transaction: &SignedTransaction, at: At) -> Result<U256, Error>;
+    /// Transactions of the pending block.
+    fn pending_transactions(&self) -> Vec<SignedTransaction>;
+    /// Imports raw signed transaction into the queue.
+    fn send_raw_transaction(&self, raw: &[u8]) -> Result<H256, Error>;
+    /// Suggested gas price.
+    fn gas_price(&self) -> U256;
+    /// Chain ID used for transaction signing.
+    fn chain_id(&self) -> Option<u64>;
+}
+
+/// Backend implemented directly on the client and miner.
+pub struct ChainBackend<C, M> {
+    client: Arc<C>,
+    miner: Arc<M>,
+    gas_price_percentile: usize,
+}
+
+impl<C, M> ChainBackend<C, M> {
+    /// Creates new backend.
+    pub fn new(client: Arc<C>, miner: Arc<M>, gas_price_percentile: usize) -> Self {
+        ChainBackend {
+            client,
+            miner,
+            gas_price_percentile,
+        }
+    }
+}
+
+impl<C, M, T: StateInfo + 'static> ChainBackend<C, M>
+where
+    C: miner::BlockChainClient
+        + BlockChainClient
+        + StateClient<State = T>
+        + Call<State = T>
+        + EngineInfo,
+    M: MinerService<State = T>,
+{
+    fn eip1559_transition(&self) -> u64 {
+        self.client.engine().params().eip1559_transition
+    }
+
+    fn state_and_header(&self, at: At) -> Result<(T, Header), Error> {
+        match at {
+            At::Pending => {
+                let best_block = self.client.chain_info().best_block_number;
+                match (
+                    self.miner.pending_state(best_block),
+                    self.miner.pending_block_header(best_block),
+                ) {
+                    (Some(state), Some(header)) => Ok((state, header)),
+                    _ => Ok(self.client.latest_state_and_header()),
+                }
+            }
+            At::Block(id) => {
+                let state = self.client.state_at(id).ok_or_else(errors::state_pruned)?;
+                let header = self.header(id).ok_or_else(errors::state_pruned)?;
+                Ok((state, header))
+            }
+        }
+    }
+}
+
+impl<C, M, T: StateInfo + 'static> Backend for ChainBackend<C, M>
+where
+    C: miner::BlockChainClient
+        + BlockChainClient
+        + StateClient<State = T>
+        + Call<State = T>
+        + EngineInfo
+        + 'static,
+    M: MinerService<State = T> + 'static,
+{
+    fn chain_info(&self) -> BlockChainInfo {
+        self.client.chain_info()
+    }
+
+    fn header(&self, id: BlockId) -> Option<Header> {
+        self.client
+            .block_header(id)
+            .and_then(|header| header.decode(self.eip1559_transition()).ok())
+    }
+
+    fn block(&self, id: BlockId) -> Option<encoded::Block> {
+        self.client.block(id)
+    }
+
+    fn total_difficulty(&self, id: BlockId) -> Option<U256> {
+        self.client.block_total_difficulty(id)
+    }
+
+    fn uncle(&self, id: UncleId) -> Option<Header> {
+        self.client
+            .uncle(id)
+            .and_then(|header| header.decode(self.eip1559_transition()).ok())
+    }
+
+    fn transaction(&self, id: TransactionId) -> Option<LocalizedTransaction> {
+        self.client.block_transaction(id)
+    }
+
+    fn receipt(&self, id: TransactionId) -> Option<LocalizedReceipt> {
+        self.client.transaction_receipt(id)
+    }
+
+    fn logs(&self, filter: Filter) -> Result<Vec<LocalizedLogEntry>, Error> {
+        self.client
+            .logs(filter)
+            .map_err(errors::filter_block_not_found)
+    }
+
+    fn state(&self, at: At) -> Result<Box<dyn StateInfo>, Error> {
+        self.state_and_header(at)
+            .map(|(state, _)| Box::new(state) as Box<dyn StateInfo>)
+    }
+
+    fn call(
+        &self,
+        transaction: &SignedTransaction,
+        at: At,
+    ) -> Result<(Vec<u8>, U256, bool), Error> {
+        let (mut state, header) = self.state_and_header(at)?;
+        let executed = self
+            .client
+            .call(transaction, Default::default(), &mut state, &header)
+            .map_err(errors::call)?;
+        Ok((
+            executed.output,
+            executed.gas_used,
+            executed.exception.is_none(),
+        ))
+    }
+
+    fn estimate_gas(&self, transaction: &SignedTransaction, at: At) -> Result<U256, Error> {
+        let (state, header) = self.state_and_header(at)?;
+        self.client
+            .estimate_gas(transaction, &state, &header)
+            .map_err(errors::call)
+    }
+
+    fn pending_transactions(&self) -> Vec<SignedTransaction> {
+        let best_block = self.client.chain_info().best_block_number;
+        self.miner
+            .pending_transactions(best_block)
+            .unwrap_or_default()
+    }
+
+    fn send_raw_transaction(&self, raw: &[u8]) -> Result<H256, Error> {
+        let transaction = TypedTransaction::decode(raw).map_err(errors::rlp)?;
+        let signed = SignedTransaction::new(transaction).map_err(errors::transaction)?;
+        FullDispatcher::dispatch_transaction(&*self.client, &*self.miner, signed.into(), false)
+    }
+
+    fn gas_price(&self) -> U256 {
+        default_gas_price(&*self.client, &*self.miner, self.gas_price_percentile)
+    }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.client.signing_chain_id()
+    }
+}
@@ -0,0 +1,326 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Depth and complexity limits of GraphQL queries.
+//!
+//! Juniper doesn't bound the work a single document can request, so documents are
+//! measured before they are executed. Fragment spreads are expanded in place.
+
+use std::collections::HashMap;
+
+/// Limits of a single GraphQL document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Maximal nesting of selection sets.
+    pub max_depth: usize,
+    /// Maximal number of selected fields.
+    pub max_complexity: u64,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 12,
+            max_complexity: 500,
+        }
+    }
+}
+
+impl Limits {
+    /// Checks that the document stays within the limits.
+    ///
+    /// Malformed documents pass, they are rejected by the parser afterwards.
+    pub fn check(&self, document: &str) -> Result<(), String> {
+        let (operations, fragments) = definitions(&tokenize(document));
+        let mut measured = HashMap::new();
+        let mut depth = 0;
+        let mut complexity = 0u64;
+        for operation in &operations {
+            let (d, c) = measure(operation, &fragments, &mut measured, &mut Vec::new())?;
+            depth = depth.max(d);
+            complexity = complexity.saturating_add(c);
+        }
+
+        if depth > self.max_depth {
+            return Err(format!(
+                "Query is nested too deeply, at most {} levels are allowed",
+                self.max_depth
+            ));
+        }
+        if complexity > self.max_complexity {
+            return Err(format!(
+                "Query selects too many fields, at most {} are allowed",
+                self.max_complexity
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Token<'a> {
+    Name(&'a str),
+    Punctuator(char),
+    Spread,
+    Value,
+}
+
+/// Selection set of an operation or a fragment.
+#[derive(Debug, Default)]
+struct Selection<'a> {
+    depth: usize,
+    fields: u64,
+    /// Fragment spreads and the depth they appear at.
+    spreads: Vec<(usize, &'a str)>,
+}
+
+fn tokenize<'a>(document: &'a str) -> Vec<Token<'a>> {
+    let bytes = document.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            b'#' => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            b'"' if document[i..].starts_with("\"\"\"") => {
+                i = document[i + 3..]
+                    .find("\"\"\"")
+                    .map_or(bytes.len(), |end| i + 3 + end + 3);
+                tokens.push(Token::Value);
+                continue;
+            }
+            b'"' => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' && bytes[i] != b'\n' {
+                    i += if bytes[i] == b'\\' { 2 } else { 1 };
+                }
+                i += 1;
+                tokens.push(Token::Value);
+                continue;
+            }
+            b'.' if document[i..].starts_with("...") => {
+                i += 3;
+                tokens.push(Token::Spread);
+                continue;
+            }
+            b'_' | b'a'..=b'z' | b'A'..=b'Z' => {
+                while i < bytes.len() && (bytes[i] == b'_' || bytes[i].is_ascii_alphanumeric()) {
+                    i += 1;
+                }
+                tokens.push(Token::Name(&document[start..i]));
+                continue;
+            }
+            b'-' | b'0'..=b'9' => {
+                i += 1;
+                while i < bytes.len()
+                    && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.' || bytes[i] == b'-')
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Value);
+                continue;
+            }
+            c if c.is_ascii_whitespace() || c == b',' => {}
+            c => tokens.push(Token::Punctuator(c as char)),
+        }
+        i += 1;
+    }
+    tokens
+}
+
+/// Splits the document into operations and named fragments.
+fn definitions<'a>(tokens: &[Token<'a>]) -> (Vec<Selection<'a>>, HashMap<&'a str, Selection<'a>>) {
+    let mut operations = Vec::new();
+    let mut fragments = HashMap::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let fragment = match (tokens[i], tokens.get(i + 1)) {
+            (Token::Name("fragment"), Some(&Token::Name(name))) => Some(name),
+            _ => None,
+        };
+        // skip names, variables and directives up to the selection set
+        let mut parens = 0usize;
+        while i < tokens.len() && (parens > 0 || tokens[i] != Token::Punctuator('{')) {
+            match tokens[i] {
+                Token::Punctuator('(') => parens += 1,
+                Token::Punctuator(')') => parens = parens.saturating_sub(1),
+                _ => {}
+            }
+            i += 1;
+        }
+        if i == tokens.len() {
+            break;
+        }
+
+        let (selection, end) = selection(tokens, i);
+        i = end;
+        match fragment {
+            Some(name) => {
+                fragments.insert(name, selection);
+            }
+            None => operations.push(selection),
+        }
+    }
+    (operations, fragments)
+}
+
+/// Measures the selection set opened at `start`, returning it with the index past its end.
+fn selection<'a>(tokens: &[Token<'a>], start: usize) -> (Selection<'a>, usize) {
+    let mut selection = Selection::default();
+    // whether each open brace is a selection set of a field, rather than an inline fragment
+    let mut braces = Vec::new();
+    let mut inline_fragment = false;
+    let mut parens = 0usize;
+    let mut i = start;
+    while i < tokens.len() {
+        let token = tokens[i];
+        i += 1;
+        if parens > 0 {
+            match token {
+                Token::Punctuator('(') => parens += 1,
+                Token::Punctuator(')') => parens -= 1,
+                _ => {}
+            }
+            continue;
+        }
+        match token {
+            Token::Punctuator('(') => parens += 1,
+            Token::Punctuator('{') => {
+                braces.push(!inline_fragment);
+                inline_fragment = false;
+                let depth = braces.iter().filter(|field| **field).count();
+                selection.depth = selection.depth.max(depth);
+            }
+            Token::Punctuator('}') => {
+                braces.pop();
+                if braces.is_empty() {
+                    break;
+                }
+            }
+            // directive name
+            Token::Punctuator('@') => i += 1,
+            Token::Spread => match tokens.get(i) {
+                Some(&Token::Name("on")) => {
+                    inline_fragment = true;
+                    i += 2;
+                }
+                Some(&Token::Name(name)) => {
+                    let depth = braces.iter().filter(|field| **field).count();
+                    selection.spreads.push((depth, name));
+                    i += 1;
+                }
+                _ => inline_fragment = true,
+            },
+            // alias
+            Token::Name(_) if tokens.get(i) == Some(&Token::Punctuator(':')) => i += 1,
+            Token::Name(_) => selection.fields += 1,
+            _ => {}
+        }
+    }
+    (selection, i)
+}
+
+/// Depth and number of fields of the selection, with fragment spreads expanded.
+fn measure<'a>(
+    selection: &Selection<'a>,
+    fragments: &HashMap<&'a str, Selection<'a>>,
+    measured: &mut HashMap<&'a str, (usize, u64)>,
+    visiting: &mut Vec<&'a str>,
+) -> Result<(usize, u64), String> {
+    let mut depth = selection.depth;
+    let mut fields = selection.fields;
+    for &(at, name) in &selection.spreads {
+        let (fragment_depth, fragment_fields) = match measured.get(name) {
+            Some(&result) => result,
+            None => {
+                let fragment = match fragments.get(name) {
+                    Some(fragment) => fragment,
+                    // unknown fragments are reported by the validation
+                    None => continue,
+                };
+                if visiting.contains(&name) {
+                    return Err(format!("Fragment {} spreads itself", name));
+                }
+                visiting.push(name);
+                let result = measure(fragment, fragments, measured, visiting)?;
+                visiting.pop();
+                measured.insert(name, result);
+                result
+            }
+        };
+        // the fragment's own selection set is the one it is spread into
+        depth = depth.max(at + fragment_depth.saturating_sub(1));
+        fields = fields.saturating_add(fragment_fields);
+    }
+    Ok((depth, fields))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Limits;
+
+    fn limits(max_depth: usize, max_complexity: u64) -> Limits {
+        Limits {
+            max_depth,
+            max_complexity,
+        }
+    }
+
+    #[test]
+    fn should_measure_depth() {
+        let query = r#"query Q($n: Long = 1) {
+            block(number: $n) { number parent { hash } }
+            logs(filter: { addresses: [], topics: [] }) { index }
+        }"#;
+        assert!(limits(3, 10).check(query).is_ok());
+        assert!(limits(2, 10).check(query).is_err());
+        // inline fragments don't add to the depth
+        assert!(limits(2, 10)
+            .check("{ block { ... on Block { number } } }")
+            .is_ok());
+    }
+
+    #[test]
+    fn should_count_fields() {
+        let query = "{ a: block { number } b: block { number } # comment { x y z }\n gasPrice }";
+        assert!(limits(5, 5).check(query).is_ok());
+        assert!(limits(5, 4).check(query).is_err());
+        assert!(limits(5, 1)
+            .check(r#"{ gasPrice(x: "{ a b c }") }"#)
+            .is_ok());
+    }
+
+    #[test]
+    fn should_expand_fragments() {
+        let query = r#"
+            { block { ...A } }
+            fragment A on Block { parent { ...B ...B } }
+            fragment B on Block { parent { number hash } }
+        "#;
+        // block, parent, 2 * (parent, number, hash)
+        assert!(limits(4, 8).check(query).is_ok());
+        assert!(limits(3, 8).check(query).is_err());
+        assert!(limits(4, 7).check(query).is_err());
+
+        let query = "{ block { ...A } } fragment A on Block { parent { ...A } }";
+        assert!(limits(100, 100).check(query).is_err());
+    }
+}
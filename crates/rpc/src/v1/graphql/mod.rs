@@ -0,0 +1,125 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! GraphQL endpoint.
+//!
+//! Compliant with EIP-1767, using the same schema as Geth.
+
+mod backend;
+mod complexity;
+mod schema;
+
+use std::sync::Arc;
+
+use ethcore::{
+    client::{BlockChainClient, Call, EngineInfo, StateClient, StateInfo},
+    miner::{self, MinerService},
+};
+use juniper::http::GraphQLRequest;
+use rpc_servers::GraphQLHandler;
+
+use self::schema::{Context, Schema};
+pub use self::{
+    backend::{At, Backend, ChainBackend},
+    complexity::Limits,
+};
+
+/// GraphQL request handler.
+pub struct GraphQL {
+    schema: Schema,
+    backend: Arc<dyn Backend>,
+    mutations: bool,
+    limits: Limits,
+}
+
+/// Query document of a request.
+#[derive(Deserialize)]
+struct Document {
+    query: String,
+}
+
+impl GraphQL {
+    /// Creates new handler resolving queries against given client and miner.
+    pub fn new<C, M, T>(client: Arc<C>, miner: Arc<M>, gas_price_percentile: usize) -> Self
+    where
+        T: StateInfo + 'static,
+        C: miner::BlockChainClient
+            + BlockChainClient
+            + StateClient<State = T>
+            + Call<State = T>
+            + EngineInfo
+            + 'static,
+        M: MinerService<State = T> + 'static,
+    {
+        Self::with_backend(Arc::new(ChainBackend::new(
+            client,
+            miner,
+            gas_price_percentile,
+        )))
+    }
+
+    /// Creates new handler resolving queries against given backend.
+    pub fn with_backend(backend: Arc<dyn Backend>) -> Self {
+        GraphQL {
+            schema: schema::schema(),
+            backend,
+            mutations: false,
+            limits: Limits::default(),
+        }
+    }
+
+    /// Allows requests to execute mutations, which submit transactions.
+    pub fn with_mutations(mut self, mutations: bool) -> Self {
+        self.mutations = mutations;
+        self
+    }
+
+    /// Rejects queries exceeding given limits.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Executes a JSON-encoded request and returns the JSON-encoded response.
+    pub fn handle(&self, request: &[u8]) -> (bool, String) {
+        let error = |message: String| {
+            let error = ::serde_json::json!({ "errors": [{ "message": message }] });
+            (false, error.to_string())
+        };
+        let (request, document) = match (
+            ::serde_json::from_slice::<GraphQLRequest>(request),
+            ::serde_json::from_slice::<Document>(request),
+        ) {
+            (Ok(request), Ok(document)) => (request, document),
+            (Err(err), _) | (_, Err(err)) => return error(format!("Invalid request: {}", err)),
+        };
+        if let Err(err) = self.limits.check(&document.query) {
+            return error(err);
+        }
+
+        let context = Context::new(self.backend.clone(), self.mutations);
+        let response = request.execute(&self.schema, &context);
+        let body =
+            ::serde_json::to_string(&response).expect("GraphQL response is serializable; qed");
+        (response.is_ok(), body)
+    }
+}
+
+impl GraphQLHandler for GraphQL {
+    fn execute(&self, request: &[u8]) -> (bool, String) {
+        self.handle(request)
+    }
+}
@@ -0,0 +1,926 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EIP-1767 schema.
+
+use std::{iter, sync::Arc};
+
+use ethcore::client::{BlockId, StateInfo, TransactionId, UncleId};
+use ethereum_types::{H160, H256, U256};
+use juniper::{
+    self, parser::ScalarToken, FieldResult, ParseScalarResult, ParseScalarValue, RootNode, Value,
+};
+use rustc_hex::{FromHex, ToHex};
+use types::{
+    filter::Filter,
+    header::Header,
+    log_entry::LocalizedLogEntry,
+    receipt::{LocalizedReceipt, TransactionOutcome},
+    transaction::{Action, LocalizedTransaction, SignedTransaction, UnverifiedTransaction},
+};
+
+use super::backend::{At, Backend};
+use v1::helpers::{fake_sign, CallRequest};
+
+/// Maximal number of blocks returned by a `blocks` query.
+const MAX_BLOCKS: u64 = 100;
+/// Maximal number of blocks searched by a `logs` query.
+const MAX_LOGS_BLOCK_RANGE: u64 = 1_000;
+
+/// Schema served by the GraphQL endpoint.
+pub type Schema = RootNode<'static, Query, Mutation>;
+
+/// Creates the schema.
+pub fn schema() -> Schema {
+    Schema::new(Query, Mutation)
+}
+
+/// State shared by resolvers of a single request.
+pub struct Context {
+    backend: Arc<dyn Backend>,
+    mutations: bool,
+}
+
+impl Context {
+    /// Creates resolvers context, `mutations` tells whether mutations may be executed.
+    pub fn new(backend: Arc<dyn Backend>, mutations: bool) -> Self {
+        Context { backend, mutations }
+    }
+}
+
+impl juniper::Context for Context {}
+
+fn from_hex(value: &str) -> Option<Vec<u8>> {
+    if !value.starts_with("0x") {
+        return None;
+    }
+    value[2..].from_hex().ok()
+}
+
+fn from_number(value: &str) -> Option<U256> {
+    if value.starts_with("0x") {
+        value[2..].parse().ok()
+    } else {
+        U256::from_dec_str(value).ok()
+    }
+}
+
+/// 32 byte binary string.
+pub struct Bytes32(H256);
+
+graphql_scalar!(Bytes32 {
+    description: "Bytes32 is a 32 byte binary string, represented as 0x-prefixed hexadecimal."
+
+    resolve(&self) -> Value {
+        Value::scalar(format!("0x{:x}", self.0))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Bytes32> {
+        v.as_scalar_value::<String>()
+            .and_then(|value| from_hex(value))
+            .filter(|bytes| bytes.len() == 32)
+            .map(|bytes| Bytes32(H256::from_slice(&bytes)))
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a> {
+        <String as ParseScalarValue>::from_str(value)
+    }
+});
+
+/// 20 byte account address.
+pub struct Address(H160);
+
+graphql_scalar!(Address {
+    description: "Address is a 20 byte Ethereum address, represented as 0x-prefixed hexadecimal."
+
+    resolve(&self) -> Value {
+        Value::scalar(format!("0x{:x}", self.0))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Address> {
+        v.as_scalar_value::<String>()
+            .and_then(|value| from_hex(value))
+            .filter(|bytes| bytes.len() == 20)
+            .map(|bytes| Address(H160::from_slice(&bytes)))
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a> {
+        <String as ParseScalarValue>::from_str(value)
+    }
+});
+
+/// Arbitrary length binary string.
+pub struct Bytes(Vec<u8>);
+
+graphql_scalar!(Bytes {
+    description: "Bytes is an arbitrary length binary string, represented as 0x-prefixed hexadecimal. An empty byte string is represented as '0x'."
+
+    resolve(&self) -> Value {
+        Value::scalar(format!("0x{}", self.0.to_hex()))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Bytes> {
+        v.as_scalar_value::<String>()
+            .and_then(|value| from_hex(value))
+            .map(Bytes)
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a> {
+        <String as ParseScalarValue>::from_str(value)
+    }
+});
+
+/// 256 bit unsigned integer.
+pub struct BigInt(U256);
+
+graphql_scalar!(BigInt {
+    description: "BigInt is a large integer. Input is accepted as either a JSON number or as a string. Strings may be either decimal or 0x-prefixed hexadecimal. Output values are all 0x-prefixed hexadecimal."
+
+    resolve(&self) -> Value {
+        Value::scalar(format!("0x{:x}", self.0))
+    }
+
+    from_input_value(v: &InputValue) -> Option<BigInt> {
+        v.as_scalar_value::<i32>()
+            .and_then(|value| if *value >= 0 { Some(U256::from(*value)) } else { None })
+            .or_else(|| v.as_scalar_value::<String>().and_then(|value| from_number(value)))
+            .map(BigInt)
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a> {
+        match value {
+            ScalarToken::Int(_) => <i32 as ParseScalarValue>::from_str(value),
+            _ => <String as ParseScalarValue>::from_str(value),
+        }
+    }
+});
+
+/// 64 bit unsigned integer.
+pub struct Long(u64);
+
+graphql_scalar!(Long {
+    description: "Long is a 64 bit unsigned integer. Input is accepted as either a JSON number or as a string. Strings may be either decimal or 0x-prefixed hexadecimal. Output values are all 0x-prefixed hexadecimal."
+
+    resolve(&self) -> Value {
+        Value::scalar(format!("0x{:x}", self.0))
+    }
+
+    from_input_value(v: &InputValue) -> Option<Long> {
+        v.as_scalar_value::<i32>()
+            .and_then(|value| if *value >= 0 { Some(*value as u64) } else { None })
+            .or_else(|| {
+                v.as_scalar_value::<String>()
+                    .and_then(|value| from_number(value))
+                    .filter(|value| *value <= U256::from(u64::max_value()))
+                    .map(|value| value.low_u64())
+            })
+            .map(Long)
+    }
+
+    from_str<'a>(value: ScalarToken<'a>) -> ParseScalarResult<'a> {
+        match value {
+            ScalarToken::Int(_) => <i32 as ParseScalarValue>::from_str(value),
+            _ => <String as ParseScalarValue>::from_str(value),
+        }
+    }
+});
+
+fn at_block(block: Option<Long>) -> At {
+    At::Block(block.map_or(BlockId::Latest, |block| BlockId::Number(block.0)))
+}
+
+/// Transaction executed without committing its result.
+#[derive(juniper::GraphQLInputObject)]
+pub struct CallData {
+    /// Address the call is sent from, zero address if not given.
+    from: Option<Address>,
+    /// Address the call is sent to, a contract creation if not given.
+    to: Option<Address>,
+    /// Gas provided for the call.
+    gas: Option<Long>,
+    /// Price of gas, in wei.
+    gas_price: Option<BigInt>,
+    /// Value sent with the call, in wei.
+    value: Option<BigInt>,
+    /// Data sent with the call.
+    data: Option<Bytes>,
+}
+
+impl CallData {
+    fn sign(self) -> FieldResult<SignedTransaction> {
+        let request = CallRequest {
+            from: self.from.map(|from| from.0),
+            to: self.to.map(|to| to.0),
+            gas: self.gas.map(|gas| gas.0.into()),
+            gas_price: self.gas_price.map(|price| price.0),
+            value: self.value.map(|value| value.0),
+            data: self.data.map(|data| data.0),
+            ..Default::default()
+        };
+        Ok(fake_sign::sign_call(request)?)
+    }
+
+    fn call(self, context: &Context, at: At) -> FieldResult<CallResult> {
+        let (data, gas_used, success) = context.backend.call(&self.sign()?, at)?;
+        Ok(CallResult {
+            data: Bytes(data),
+            gas_used: Long(gas_used.low_u64()),
+            status: Long(success as u64),
+        })
+    }
+
+    fn estimate_gas(self, context: &Context, at: At) -> FieldResult<Long> {
+        let gas = context.backend.estimate_gas(&self.sign()?, at)?;
+        Ok(Long(gas.low_u64()))
+    }
+}
+
+/// Result of a call.
+#[derive(juniper::GraphQLObject)]
+pub struct CallResult {
+    /// Data returned by the call.
+    data: Bytes,
+    /// Gas used by the call.
+    gas_used: Long,
+    /// 1 if the call succeeded, 0 otherwise.
+    status: Long,
+}
+
+/// Log filter applied to blocks in a range.
+#[derive(juniper::GraphQLInputObject)]
+pub struct FilterCriteria {
+    /// First block of the range, latest if not given.
+    from_block: Option<Long>,
+    /// Last block of the range, latest if not given.
+    to_block: Option<Long>,
+    /// Addresses the logs must be emitted by. Any address matches if not given.
+    addresses: Option<Vec<Address>>,
+    /// Alternatives for each topic position. An empty list matches any topic.
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+/// Log filter applied to a single block.
+#[derive(juniper::GraphQLInputObject)]
+pub struct BlockFilterCriteria {
+    /// Addresses the logs must be emitted by. Any address matches if not given.
+    addresses: Option<Vec<Address>>,
+    /// Alternatives for each topic position. An empty list matches any topic.
+    topics: Option<Vec<Vec<Bytes32>>>,
+}
+
+fn filter(
+    from_block: BlockId,
+    to_block: BlockId,
+    addresses: Option<Vec<Address>>,
+    topics: Option<Vec<Vec<Bytes32>>>,
+) -> Filter {
+    let topics = topics
+        .unwrap_or_default()
+        .into_iter()
+        .map(|topics| match topics.is_empty() {
+            true => None,
+            false => Some(topics.into_iter().map(|topic| topic.0).collect()),
+        })
+        .chain(iter::repeat(None))
+        .take(4)
+        .collect();
+
+    Filter {
+        from_block,
+        to_block,
+        address: addresses.map(|addresses| addresses.into_iter().map(|a| a.0).collect()),
+        topics,
+        limit: None,
+    }
+}
+
+/// Ethereum account at a particular block.
+pub struct Account {
+    address: H160,
+    at: At,
+}
+
+#[juniper::object(Context = Context)]
+impl Account {
+    /// Address of the account.
+    fn address(&self) -> Address {
+        Address(self.address)
+    }
+
+    /// Balance of the account, in wei.
+    fn balance(&self, context: &Context) -> FieldResult<BigInt> {
+        Ok(BigInt(
+            context.backend.state(self.at)?.balance(&self.address)?,
+        ))
+    }
+
+    /// Number of transactions sent from the account.
+    fn transaction_count(&self, context: &Context) -> FieldResult<Long> {
+        let nonce = context.backend.state(self.at)?.nonce(&self.address)?;
+        Ok(Long(nonce.low_u64()))
+    }
+
+    /// Code of the contract, empty for externally owned accounts.
+    fn code(&self, context: &Context) -> FieldResult<Bytes> {
+        let code = context.backend.state(self.at)?.code(&self.address)?;
+        Ok(Bytes(code.map_or_else(Vec::new, |code| (*code).clone())))
+    }
+
+    /// Value of the storage slot.
+    fn storage(&self, context: &Context, slot: Bytes32) -> FieldResult<Bytes32> {
+        let value = context
+            .backend
+            .state(self.at)?
+            .storage_at(&self.address, &slot.0)?;
+        Ok(Bytes32(value))
+    }
+}
+
+/// Log emitted by a transaction.
+pub struct Log {
+    entry: LocalizedLogEntry,
+}
+
+#[juniper::object(Context = Context)]
+impl Log {
+    /// Index of the log within the block.
+    fn index(&self) -> i32 {
+        self.entry.log_index as i32
+    }
+
+    /// Account which emitted the log, at the latest or given block.
+    fn account(&self, block: Option<Long>) -> Account {
+        Account {
+            address: self.entry.address,
+            at: at_block(block),
+        }
+    }
+
+    /// Topics of the log.
+    fn topics(&self) -> Vec<Bytes32> {
+        self.entry.topics.iter().cloned().map(Bytes32).collect()
+    }
+
+    /// Data of the log.
+    fn data(&self) -> Bytes {
+        Bytes(self.entry.data.clone())
+    }
+
+    /// Transaction which emitted the log.
+    fn transaction(&self, context: &Context) -> FieldResult<Transaction> {
+        context
+            .backend
+            .transaction(TransactionId::Hash(self.entry.transaction_hash))
+            .map(Transaction::included)
+            .ok_or_else(|| "Transaction not found".into())
+    }
+}
+
+/// Location of a transaction included in the chain.
+struct Location {
+    block_hash: H256,
+    index: usize,
+}
+
+/// Ethereum transaction.
+pub struct Transaction {
+    transaction: UnverifiedTransaction,
+    sender: Option<H160>,
+    location: Option<Location>,
+}
+
+impl Transaction {
+    fn included(transaction: LocalizedTransaction) -> Self {
+        Transaction {
+            sender: transaction.cached_sender,
+            location: Some(Location {
+                block_hash: transaction.block_hash,
+                index: transaction.transaction_index,
+            }),
+            transaction: transaction.signed,
+        }
+    }
+
+    fn pending(transaction: SignedTransaction) -> Self {
+        let (transaction, sender, _) = transaction.deconstruct();
+        Transaction {
+            transaction,
+            sender: Some(sender),
+            location: None,
+        }
+    }
+}
+
+#[juniper::object(Context = Context)]
+impl Transaction {
+    /// Hash of the transaction.
+    fn hash(&self) -> Bytes32 {
+        Bytes32(self.transaction.hash())
+    }
+
+    /// Nonce of the sender account at the time of the transaction.
+    fn nonce(&self) -> Long {
+        Long(self.transaction.tx().nonce.low_u64())
+    }
+
+    /// Index of the transaction within its block, null if pending.
+    fn index(&self) -> Option<i32> {
+        self.location.as_ref().map(|location| location.index as i32)
+    }
+
+    /// Sender account, at the latest or given block.
+    fn from(&self, block: Option<Long>) -> FieldResult<Account> {
+        let address = match self.sender {
+            Some(sender) => sender,
+            None => SignedTransaction::new(self.transaction.clone())?.sender(),
+        };
+        Ok(Account {
+            address,
+            at: at_block(block),
+        })
+    }
+
+    /// Receiving account, at the latest or given block. Null for contract creations.
+    fn to(&self, block: Option<Long>) -> Option<Account> {
+        match self.transaction.tx().action {
+            Action::Call(address) => Some(Account {
+                address,
+                at: at_block(block),
+            }),
+            Action::Create => None,
+        }
+    }
+
+    /// Value transferred, in wei.
+    fn value(&self) -> BigInt {
+        BigInt(self.transaction.tx().value)
+    }
+
+    /// Price of gas, in wei. Maximal fee per gas for EIP-1559 transactions.
+    fn gas_price(&self) -> BigInt {
+        BigInt(self.transaction.tx().gas_price)
+    }
+
+    /// Gas provided by the sender.
+    fn gas(&self) -> Long {
+        Long(self.transaction.tx().gas.low_u64())
+    }
+
+    /// Data supplied to the target of the transaction.
+    fn input_data(&self) -> Bytes {
+        Bytes(self.transaction.tx().data.clone())
+    }
+
+    /// Block including the transaction, null if pending.
+    fn block(&self, context: &Context) -> Option<Block> {
+        let location = self.location.as_ref()?;
+        context
+            .backend
+            .header(BlockId::Hash(location.block_hash))
+            .map(Block::new)
+    }
+
+    /// Status of the execution: 1 on success, 0 on failure. Null if pending or before Byzantium.
+    fn status(&self, context: &Context) -> Option<Long> {
+        match self.receipt(context)?.outcome {
+            TransactionOutcome::StatusCode(code) => Some(Long(code.into())),
+            _ => None,
+        }
+    }
+
+    /// Gas used by the transaction, null if pending.
+    fn gas_used(&self, context: &Context) -> Option<Long> {
+        self.receipt(context)
+            .map(|receipt| Long(receipt.gas_used.low_u64()))
+    }
+
+    /// Gas used by the block up to and including the transaction, null if pending.
+    fn cumulative_gas_used(&self, context: &Context) -> Option<Long> {
+        self.receipt(context)
+            .map(|receipt| Long(receipt.cumulative_gas_used.low_u64()))
+    }
+
+    /// Contract created by the transaction, at the latest or given block.
+    fn created_contract(&self, context: &Context, block: Option<Long>) -> Option<Account> {
+        self.receipt(context)?
+            .contract_address
+            .map(|address| Account {
+                address,
+                at: at_block(block),
+            })
+    }
+
+    /// Logs emitted by the transaction, null if pending.
+    fn logs(&self, context: &Context) -> Option<Vec<Log>> {
+        self.receipt(context).map(|receipt| {
+            receipt
+                .logs
+                .into_iter()
+                .map(|entry| Log { entry })
+                .collect()
+        })
+    }
+
+    /// R component of the signature.
+    fn r(&self) -> BigInt {
+        BigInt(self.transaction.signature.r.into())
+    }
+
+    /// S component of the signature.
+    fn s(&self) -> BigInt {
+        BigInt(self.transaction.signature.s.into())
+    }
+
+    /// V component of the signature.
+    fn v(&self) -> BigInt {
+        BigInt(self.transaction.v().into())
+    }
+}
+
+impl Transaction {
+    fn receipt(&self, context: &Context) -> Option<LocalizedReceipt> {
+        self.location.as_ref()?;
+        context
+            .backend
+            .receipt(TransactionId::Hash(self.transaction.hash()))
+    }
+}
+
+/// Ethereum block.
+pub struct Block {
+    header: Header,
+}
+
+impl Block {
+    fn new(header: Header) -> Self {
+        Block { header }
+    }
+
+    fn id(&self) -> BlockId {
+        BlockId::Hash(self.header.hash())
+    }
+
+    fn body_transactions(&self, context: &Context) -> Option<Vec<UnverifiedTransaction>> {
+        context
+            .backend
+            .block(self.id())
+            .map(|block| block.transactions())
+    }
+
+    fn ommer_hashes(&self, context: &Context) -> Option<Vec<H256>> {
+        context
+            .backend
+            .block(self.id())
+            .map(|block| block.uncle_hashes())
+    }
+}
+
+#[juniper::object(Context = Context)]
+impl Block {
+    /// Number of the block.
+    fn number(&self) -> Long {
+        Long(self.header.number())
+    }
+
+    /// Hash of the block.
+    fn hash(&self) -> Bytes32 {
+        Bytes32(self.header.hash())
+    }
+
+    /// Parent block, null for the genesis block.
+    fn parent(&self, context: &Context) -> Option<Block> {
+        if self.header.number() == 0 {
+            return None;
+        }
+        context
+            .backend
+            .header(BlockId::Hash(*self.header.parent_hash()))
+            .map(Block::new)
+    }
+
+    /// Root of the transactions trie.
+    fn transactions_root(&self) -> Bytes32 {
+        Bytes32(*self.header.transactions_root())
+    }
+
+    /// Number of transactions in the block, null if the block body is unknown.
+    fn transaction_count(&self, context: &Context) -> Option<i32> {
+        context
+            .backend
+            .block(self.id())
+            .map(|block| block.transactions_count() as i32)
+    }
+
+    /// Root of the state trie after the block.
+    fn state_root(&self) -> Bytes32 {
+        Bytes32(*self.header.state_root())
+    }
+
+    /// Root of the receipts trie.
+    fn receipts_root(&self) -> Bytes32 {
+        Bytes32(*self.header.receipts_root())
+    }
+
+    /// Author of the block, at this or given block.
+    fn miner(&self, block: Option<Long>) -> Account {
+        Account {
+            address: *self.header.author(),
+            at: match block {
+                Some(_) => at_block(block),
+                None => At::Block(self.id()),
+            },
+        }
+    }
+
+    /// Extra data of the block.
+    fn extra_data(&self) -> Bytes {
+        Bytes(self.header.extra_data().clone())
+    }
+
+    /// Gas limit of the block.
+    fn gas_limit(&self) -> Long {
+        Long(self.header.gas_limit().low_u64())
+    }
+
+    /// Gas used by transactions of the block.
+    fn gas_used(&self) -> Long {
+        Long(self.header.gas_used().low_u64())
+    }
+
+    /// Base fee per gas, null before EIP-1559.
+    fn base_fee_per_gas(&self) -> Option<BigInt> {
+        self.header.base_fee().map(BigInt)
+    }
+
+    /// Unix timestamp of the block.
+    fn timestamp(&self) -> Long {
+        Long(self.header.timestamp())
+    }
+
+    /// Bloom filter of logs of the block.
+    fn logs_bloom(&self) -> Bytes {
+        Bytes(self.header.log_bloom().as_bytes().to_vec())
+    }
+
+    /// Difficulty of the block.
+    fn difficulty(&self) -> BigInt {
+        BigInt(*self.header.difficulty())
+    }
+
+    /// Sum of difficulties of the chain up to and including the block.
+    fn total_difficulty(&self, context: &Context) -> FieldResult<BigInt> {
+        context
+            .backend
+            .total_difficulty(self.id())
+            .map(BigInt)
+            .ok_or_else(|| "Total difficulty not known".into())
+    }
+
+    /// Number of ommers of the block, null if the block body is unknown.
+    fn ommer_count(&self, context: &Context) -> Option<i32> {
+        self.ommer_hashes(context).map(|hashes| hashes.len() as i32)
+    }
+
+    /// Ommers of the block, null if the block body is unknown.
+    fn ommers(&self, context: &Context) -> Option<Vec<Block>> {
+        let count = self.ommer_hashes(context)?.len();
+        (0..count)
+            .map(|index| {
+                context
+                    .backend
+                    .uncle(UncleId {
+                        block: self.id(),
+                        position: index,
+                    })
+                    .map(Block::new)
+            })
+            .collect()
+    }
+
+    /// Ommer at given index.
+    fn ommer_at(&self, context: &Context, index: i32) -> Option<Block> {
+        if index < 0 {
+            return None;
+        }
+        context
+            .backend
+            .uncle(UncleId {
+                block: self.id(),
+                position: index as usize,
+            })
+            .map(Block::new)
+    }
+
+    /// Hash of the ommers list.
+    fn ommer_hash(&self) -> Bytes32 {
+        Bytes32(*self.header.uncles_hash())
+    }
+
+    /// Transactions of the block, null if the block body is unknown.
+    fn transactions(&self, context: &Context) -> Option<Vec<Transaction>> {
+        let block_hash = self.header.hash();
+        let transactions = self.body_transactions(context)?;
+        Some(
+            transactions
+                .into_iter()
+                .enumerate()
+                .map(|(index, transaction)| Transaction {
+                    transaction,
+                    sender: None,
+                    location: Some(Location { block_hash, index }),
+                })
+                .collect(),
+        )
+    }
+
+    /// Transaction at given index.
+    fn transaction_at(&self, context: &Context, index: i32) -> Option<Transaction> {
+        if index < 0 {
+            return None;
+        }
+        context
+            .backend
+            .transaction(TransactionId::Location(self.id(), index as usize))
+            .map(Transaction::included)
+    }
+
+    /// Logs of the block matching the filter.
+    fn logs(&self, context: &Context, filter: BlockFilterCriteria) -> FieldResult<Vec<Log>> {
+        let logs = context.backend.logs(self::filter(
+            self.id(),
+            self.id(),
+            filter.addresses,
+            filter.topics,
+        ))?;
+        Ok(logs.into_iter().map(|entry| Log { entry }).collect())
+    }
+
+    /// Account at this block.
+    fn account(&self, address: Address) -> Account {
+        Account {
+            address: address.0,
+            at: At::Block(self.id()),
+        }
+    }
+
+    /// Executes a call on top of the state after this block.
+    fn call(&self, context: &Context, data: CallData) -> FieldResult<CallResult> {
+        data.call(context, At::Block(self.id()))
+    }
+
+    /// Estimates gas needed by the transaction on top of the state after this block.
+    fn estimate_gas(&self, context: &Context, data: CallData) -> FieldResult<Long> {
+        data.estimate_gas(context, At::Block(self.id()))
+    }
+}
+
+/// Block being sealed.
+pub struct Pending;
+
+#[juniper::object(Context = Context)]
+impl Pending {
+    /// Number of pending transactions.
+    fn transaction_count(&self, context: &Context) -> i32 {
+        context.backend.pending_transactions().len() as i32
+    }
+
+    /// Pending transactions.
+    fn transactions(&self, context: &Context) -> Vec<Transaction> {
+        context
+            .backend
+            .pending_transactions()
+            .into_iter()
+            .map(Transaction::pending)
+            .collect()
+    }
+
+    /// Account in the pending state.
+    fn account(&self, address: Address) -> Account {
+        Account {
+            address: address.0,
+            at: At::Pending,
+        }
+    }
+
+    /// Executes a call on top of the pending state.
+    fn call(&self, context: &Context, data: CallData) -> FieldResult<CallResult> {
+        data.call(context, At::Pending)
+    }
+
+    /// Estimates gas needed by the transaction on top of the pending state.
+    fn estimate_gas(&self, context: &Context, data: CallData) -> FieldResult<Long> {
+        data.estimate_gas(context, At::Pending)
+    }
+}
+
+/// Query root.
+pub struct Query;
+
+#[juniper::object(Context = Context)]
+impl Query {
+    /// Block by number or hash, the latest one if neither is given.
+    fn block(
+        &self,
+        context: &Context,
+        number: Option<Long>,
+        hash: Option<Bytes32>,
+    ) -> FieldResult<Option<Block>> {
+        let id = match (number, hash) {
+            (Some(_), Some(_)) => return Err("Only one of number or hash can be given".into()),
+            (Some(number), None) => BlockId::Number(number.0),
+            (None, Some(hash)) => BlockId::Hash(hash.0),
+            (None, None) => BlockId::Latest,
+        };
+        Ok(context.backend.header(id).map(Block::new))
+    }
+
+    /// Blocks in the range, up to the latest one if `to` is not given.
+    fn blocks(&self, context: &Context, from: Long, to: Option<Long>) -> FieldResult<Vec<Block>> {
+        let best_block = context.backend.chain_info().best_block_number;
+        let to = to.map_or(best_block, |to| to.0.min(best_block));
+        if from.0 > to {
+            return Ok(Vec::new());
+        }
+        if to - from.0 >= MAX_BLOCKS {
+            return Err(format!("At most {} blocks can be queried at once", MAX_BLOCKS).into());
+        }
+        Ok((from.0..=to)
+            .filter_map(|number| context.backend.header(BlockId::Number(number)))
+            .map(Block::new)
+            .collect())
+    }
+
+    /// Block being sealed.
+    fn pending(&self) -> Pending {
+        Pending
+    }
+
+    /// Transaction included in the chain.
+    fn transaction(&self, context: &Context, hash: Bytes32) -> Option<Transaction> {
+        context
+            .backend
+            .transaction(TransactionId::Hash(hash.0))
+            .map(Transaction::included)
+    }
+
+    /// Logs matching the filter.
+    fn logs(&self, context: &Context, filter: FilterCriteria) -> FieldResult<Vec<Log>> {
+        let best_block = context.backend.chain_info().best_block_number;
+        let number = |block: &Option<Long>| block.as_ref().map_or(best_block, |b| b.0);
+        let (from, to) = (number(&filter.from_block), number(&filter.to_block));
+        if to.saturating_sub(from) >= MAX_LOGS_BLOCK_RANGE {
+            return Err(format!(
+                "Logs can be queried from at most {} blocks at once",
+                MAX_LOGS_BLOCK_RANGE
+            )
+            .into());
+        }
+        let block_id =
+            |block: Option<Long>| block.map_or(BlockId::Latest, |b| BlockId::Number(b.0));
+        let logs = context.backend.logs(self::filter(
+            block_id(filter.from_block),
+            block_id(filter.to_block),
+            filter.addresses,
+            filter.topics,
+        ))?;
+        Ok(logs.into_iter().map(|entry| Log { entry }).collect())
+    }
+
+    /// Suggested gas price, in wei.
+    fn gas_price(&self, context: &Context) -> BigInt {
+        BigInt(context.backend.gas_price())
+    }
+
+    /// Chain ID used for transaction signing.
+    #[graphql(name = "chainID")]
+    fn chain_id(&self, context: &Context) -> Option<BigInt> {
+        context.backend.chain_id().map(|id| BigInt(id.into()))
+    }
+}
+
+/// Mutation root.
+pub struct Mutation;
+
+#[juniper::object(Context = Context)]
+impl Mutation {
+    /// Imports signed RLP-encoded transaction and returns its hash.
+    fn send_raw_transaction(&self, context: &Context, data: Bytes) -> FieldResult<Bytes32> {
+        if !context.mutations {
+            return Err("Mutations are disabled, enable the graphql_mutation API".into());
+        }
+        Ok(Bytes32(context.backend.send_raw_transaction(&data.0)?))
+    }
+}
@@ -181,4 +181,8 @@ impl<C: miner::BlockChainClient + BlockChainClient, M: MinerService> Dispatcher
     fn dispatch_transaction(&self, signed_transaction: PendingTransaction) -> Result<H256> {
         Self::dispatch_transaction(&*self.client, &*self.miner, signed_transaction, true)
     }
+
+    fn chain_id(&self) -> Option<u64> {
+        self.client.signing_chain_id()
+    }
 }
@@ -142,6 +142,9 @@ pub trait Dispatcher: Send + Sync + Clone {
 
     /// "Dispatch" a local transaction.
     fn dispatch_transaction(&self, signed_transaction: PendingTransaction) -> Result<H256>;
+
+    /// Chain id used for replay protection of signed payloads, if any.
+    fn chain_id(&self) -> Option<u64>;
 }
 
 /// Payload to sign
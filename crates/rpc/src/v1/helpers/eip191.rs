@@ -15,11 +15,11 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! EIP-191 compliant decoding + hashing
-use eip_712::{hash_structured_data, EIP712};
-use ethereum_types::H256;
+use eip_712::{hash_structured_data, hash_structured_data_with_version, TypedDataVersion, EIP712};
+use ethereum_types::{H256, U256};
 use hash::keccak;
 use jsonrpc_core::Error;
-use serde_json::{from_value, Value};
+use serde_json::{from_str, from_value, Value};
 use std::fmt::Display;
 use v1::{
     helpers::{dispatch::eth_data_hash, errors},
@@ -53,6 +53,34 @@ pub fn hash_message(version: EIP191Version, message: Value) -> Result<H256, Erro
     Ok(data)
 }
 
+/// deserializes and hashes typed data sent to `eth_signTypedData_v3/v4`,
+/// wallets send it either as a JSON object or as a string containing one.
+/// A chain id given in the domain has to match the one of the node.
+pub fn hash_typed_data(
+    version: TypedDataVersion,
+    message: Value,
+    chain_id: Option<u64>,
+) -> Result<H256, Error> {
+    let typed_data = match message {
+        Value::String(ref json) => from_str::<EIP712>(json),
+        message => from_value::<EIP712>(message),
+    }
+    .map_err(map_serde_err("TypedData"))?;
+
+    if let Some(domain_chain_id) = typed_data.chain_id() {
+        if chain_id.map(U256::from) != Some(domain_chain_id) {
+            return Err(errors::invalid_call_data(format!(
+                "Domain chainId {} does not match the chain id of the node ({})",
+                domain_chain_id,
+                chain_id.map_or_else(|| "none".into(), |id| id.to_string()),
+            )));
+        }
+    }
+
+    hash_structured_data_with_version(typed_data, version)
+        .map_err(|err| errors::invalid_call_data(err.kind()))
+}
+
 fn map_serde_err<T: Display>(struct_name: &'static str) -> impl Fn(T) -> Error {
     move |error: T| {
         errors::invalid_call_data(format!("Error deserializing '{}': {}", struct_name, error))
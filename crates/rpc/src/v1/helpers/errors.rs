@@ -36,6 +36,7 @@ mod codes {
     pub const NO_NEW_WORK: i64 = -32003;
     pub const NO_WORK_REQUIRED: i64 = -32004;
     pub const CANNOT_SUBMIT_WORK: i64 = -32005;
+    pub const LIMIT_EXCEEDED: i64 = -32006;
     pub const UNKNOWN_ERROR: i64 = -32009;
     pub const TRANSACTION_ERROR: i64 = -32010;
     pub const EXECUTION_ERROR: i64 = -32015;
@@ -100,6 +101,28 @@ pub fn request_rejected_limit() -> Error {
     }
 }
 
+pub fn rate_limited(retry_after: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::LIMIT_EXCEEDED),
+        message: "Rate limit exceeded.".into(),
+        data: Some(Value::String(format!(
+            "Retry after {} seconds.",
+            retry_after
+        ))),
+    }
+}
+
+pub fn block_range_too_large(range: u64, max: u64) -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::LIMIT_EXCEEDED),
+        message: format!(
+            "Queried block range of {} blocks exceeds the limit of {} blocks.",
+            range, max
+        ),
+        data: None,
+    }
+}
+
 pub fn account<T: fmt::Debug>(error: &str, details: T) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::ACCOUNT_ERROR),
@@ -309,6 +332,15 @@ pub fn ws_disabled() -> Error {
     }
 }
 
+pub fn tracing_disabled() -> Error {
+    Error {
+        code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
+        message: "Tracing is disabled. Restart with --tracing on to enable this subscription."
+            .into(),
+        data: None,
+    }
+}
+
 pub fn network_disabled() -> Error {
     Error {
         code: ErrorCode::ServerError(codes::UNSUPPORTED_REQUEST),
@@ -23,6 +23,7 @@ use std::{
 
 use jsonrpc_core::{
     futures::{self, Future, IntoFuture},
+    types::Value,
     Error, Result,
 };
 use jsonrpc_pubsub::{
@@ -34,13 +35,14 @@ use v1::{
     helpers::{errors, limit_logs, Subscribers},
     metadata::Metadata,
     traits::EthPubSub,
-    types::{pubsub, Header, Log, RichHeader},
+    types::{pubsub, Header, Log, RichHeader, Transaction},
 };
 
 use ethcore::client::{
     BlockChainClient, BlockId, ChainNotify, ChainRouteType, EngineInfo, NewBlocks,
 };
-use ethereum_types::H256;
+use ethereum_types::{H256, U256};
+use miner::pool::VerifiedTransaction;
 use parity_runtime::Executor;
 use parking_lot::RwLock;
 
@@ -54,14 +56,24 @@ pub struct EthPubSubClient<C> {
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    full_transactions_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TransactionFilter)>>>,
+    traces_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TraceFilter)>>>,
 }
 
-impl<C> EthPubSubClient<C> {
+impl<C> EthPubSubClient<C>
+where
+    C: BlockChainClient + EngineInfo,
+{
     /// Creates new `EthPubSubClient`.
     pub fn new(client: Arc<C>, executor: Executor) -> Self {
         let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
         let transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let full_transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let traces_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let pending_base_fee = client
+            .engine()
+            .calculate_base_fee(&client.best_block_header());
 
         EthPubSubClient {
             handler: Arc::new(ChainNotificationHandler {
@@ -70,10 +82,15 @@ impl<C> EthPubSubClient<C> {
                 heads_subscribers: heads_subscribers.clone(),
                 logs_subscribers: logs_subscribers.clone(),
                 transactions_subscribers: transactions_subscribers.clone(),
+                full_transactions_subscribers: full_transactions_subscribers.clone(),
+                traces_subscribers: traces_subscribers.clone(),
+                pending_base_fee: RwLock::new(pending_base_fee),
             }),
             heads_subscribers,
             logs_subscribers,
             transactions_subscribers,
+            full_transactions_subscribers,
+            traces_subscribers,
         }
     }
 
@@ -84,6 +101,8 @@ impl<C> EthPubSubClient<C> {
         *client.heads_subscribers.write() = Subscribers::default();
         *client.logs_subscribers.write() = Subscribers::default();
         *client.transactions_subscribers.write() = Subscribers::default();
+        *client.full_transactions_subscribers.write() = Subscribers::default();
+        *client.traces_subscribers.write() = Subscribers::default();
         client
     }
 
@@ -100,6 +119,11 @@ pub struct ChainNotificationHandler<C> {
     heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
     logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
     transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    full_transactions_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TransactionFilter)>>>,
+    traces_subscribers: Arc<RwLock<Subscribers<(Client, pubsub::TraceFilter)>>>,
+    /// Base fee of the block pending transactions are expected to be included in,
+    /// updated once per new best block.
+    pending_base_fee: RwLock<Option<U256>>,
 }
 
 impl<C> ChainNotificationHandler<C>
@@ -182,10 +206,99 @@ where
     }
 }
 
+impl<C> ChainNotificationHandler<C>
+where
+    C: BlockChainClient + EngineInfo,
+{
+    /// Notify all subscribers about new transactions matching their filters.
+    ///
+    /// Transactions are looked up with `transaction` and converted on the executor,
+    /// since the transaction queue notifies its listeners while it is locked.
+    pub fn notify_new_full_transactions<F>(&self, hashes: &[H256], transaction: F)
+    where
+        F: Fn(&H256) -> Option<Arc<VerifiedTransaction>> + Send + 'static,
+    {
+        if self.full_transactions_subscribers.read().is_empty() {
+            return;
+        }
+
+        let hashes = hashes.to_vec();
+        let base_fee = *self.pending_base_fee.read();
+        let subscribers = self.full_transactions_subscribers.clone();
+        let executor = self.executor.clone();
+        self.executor.spawn_fn(move || {
+            let subscribers = subscribers.read();
+            for tx in hashes.iter().filter_map(transaction) {
+                let mut result = None;
+                for &(ref subscriber, ref filter) in subscribers.values() {
+                    if !filter.matches(tx.signed(), base_fee) {
+                        continue;
+                    }
+                    let result = result.get_or_insert_with(|| {
+                        pubsub::Result::Transaction(Box::new(Transaction::from_signed(
+                            tx.signed().clone(),
+                        )))
+                    });
+                    Self::notify(&executor, subscriber, result.clone());
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+impl<C> ChainNotificationHandler<C>
+where
+    C: BlockChainClient + EngineInfo,
+{
+    fn notify_traces(&self, route: &[(H256, ChainRouteType)]) {
+        let subscribers = self.traces_subscribers.read();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        // We notify traces enacting and retracting as the order in route.
+        for &(hash, ref typ) in route {
+            let traces = match self.client.block_traces(BlockId::Hash(hash)) {
+                Some(traces) => traces,
+                None => continue,
+            };
+            let removed = match *typ {
+                ChainRouteType::Enacted => false,
+                ChainRouteType::Retracted => true,
+            };
+
+            for &(ref subscriber, ref filter) in subscribers.values() {
+                for trace in traces.iter().filter(|trace| filter.matches(trace)) {
+                    Self::notify(
+                        &self.executor,
+                        subscriber,
+                        pubsub::Result::Trace(Box::new(pubsub::TraceNotification {
+                            trace: trace.clone().into(),
+                            removed,
+                        })),
+                    );
+                }
+            }
+        }
+    }
+}
+
 impl<C: BlockChainClient + EngineInfo> ChainNotify for ChainNotificationHandler<C> {
     // t_nb 11.3 RPC. Notify subscriber header/logs about new block
     fn new_blocks(&self, new_blocks: NewBlocks) {
-        if self.heads_subscribers.read().is_empty() && self.logs_subscribers.read().is_empty() {
+        if !new_blocks.route.enacted().is_empty() {
+            // Pending transactions are expected to be included in the next block.
+            *self.pending_base_fee.write() = self
+                .client
+                .engine()
+                .calculate_base_fee(&self.client.best_block_header());
+        }
+
+        if self.heads_subscribers.read().is_empty()
+            && self.logs_subscribers.read().is_empty()
+            && self.traces_subscribers.read().is_empty()
+        {
             return;
         }
         const EXTRA_INFO_PROOF: &str = "Object exists in in blockchain (fetched earlier), extra_info is always available if object exists; qed";
@@ -233,10 +346,13 @@ impl<C: BlockChainClient + EngineInfo> ChainNotify for ChainNotificationHandler<
                 })
                 .collect()),
         });
+
+        // Traces
+        self.notify_traces(new_blocks.route.route());
     }
 }
 
-impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
+impl<C: BlockChainClient + EngineInfo + 'static> EthPubSub for EthPubSubClient<C> {
     type Metadata = Metadata;
 
     fn subscribe(
@@ -244,17 +360,22 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
         _meta: Metadata,
         subscriber: Subscriber<pubsub::Result>,
         kind: pubsub::Kind,
-        params: Option<pubsub::Params>,
+        params: Option<Value>,
     ) {
+        let params = match pubsub::Params::parse(&kind, params) {
+            Ok(params) => params,
+            Err(err) => {
+                let _ = subscriber.reject(err);
+                return;
+            }
+        };
+
         let error = match (kind, params) {
-            (pubsub::Kind::NewHeads, None) => {
+            (pubsub::Kind::NewHeads, pubsub::Params::None) => {
                 self.heads_subscribers.write().push(subscriber);
                 return;
             }
-            (pubsub::Kind::NewHeads, _) => {
-                errors::invalid_params("newHeads", "Expected no parameters.")
-            }
-            (pubsub::Kind::Logs, Some(pubsub::Params::Logs(filter))) => match filter.try_into() {
+            (pubsub::Kind::Logs, pubsub::Params::Logs(filter)) => match filter.try_into() {
                 Ok(filter) => {
                     self.logs_subscribers.write().push(subscriber, filter);
                     return;
@@ -262,12 +383,34 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
                 Err(err) => err,
             },
             (pubsub::Kind::Logs, _) => errors::invalid_params("logs", "Expected a filter object."),
-            (pubsub::Kind::NewPendingTransactions, None) => {
+            (pubsub::Kind::NewPendingTransactions, pubsub::Params::None) => {
                 self.transactions_subscribers.write().push(subscriber);
                 return;
             }
-            (pubsub::Kind::NewPendingTransactions, _) => {
-                errors::invalid_params("newPendingTransactions", "Expected no parameters.")
+            (pubsub::Kind::PendingTransactions, pubsub::Params::None) => {
+                self.full_transactions_subscribers
+                    .write()
+                    .push(subscriber, Default::default());
+                return;
+            }
+            (pubsub::Kind::PendingTransactions, pubsub::Params::Transactions(filter)) => {
+                self.full_transactions_subscribers
+                    .write()
+                    .push(subscriber, filter);
+                return;
+            }
+            (pubsub::Kind::Traces, _) if !self.handler.client.tracing_enabled() => {
+                errors::tracing_disabled()
+            }
+            (pubsub::Kind::Traces, pubsub::Params::None) => {
+                self.traces_subscribers
+                    .write()
+                    .push(subscriber, Default::default());
+                return;
+            }
+            (pubsub::Kind::Traces, pubsub::Params::Traces(filter)) => {
+                self.traces_subscribers.write().push(subscriber, filter);
+                return;
             }
             _ => errors::unimplemented(None),
         };
@@ -279,7 +422,13 @@ impl<C: Send + Sync + 'static> EthPubSub for EthPubSubClient<C> {
         let res = self.heads_subscribers.write().remove(&id).is_some();
         let res2 = self.logs_subscribers.write().remove(&id).is_some();
         let res3 = self.transactions_subscribers.write().remove(&id).is_some();
+        let res4 = self
+            .full_transactions_subscribers
+            .write()
+            .remove(&id)
+            .is_some();
+        let res5 = self.traces_subscribers.write().remove(&id).is_some();
 
-        Ok(res || res2 || res3)
+        Ok(res || res2 || res3 || res4 || res5)
     }
 }
@@ -42,9 +42,9 @@ use v1::{
     },
     traits::Parity,
     types::{
-        block_number_to_id, BlockNumber, Bytes, CallRequest, ChainStatus, Header, Histogram,
-        LocalTransactionStatus, Peers, Receipt, RecoveredAccount, RichHeader, RpcSettings,
-        Transaction, TransactionStats,
+        block_number_to_id, BlockNumber, Bytes, CallRequest, ChainStatus, EthashCacheStatus,
+        Header, Histogram, LocalTransactionStatus, Peers, Receipt, RecoveredAccount, RichHeader,
+        RpcSettings, Transaction, TransactionStats,
     },
 };
 use version::version_data;
@@ -361,6 +361,14 @@ where
         })
     }
 
+    fn ethash_cache_status(&self) -> Result<EthashCacheStatus> {
+        self.client
+            .engine()
+            .pow_cache_status()
+            .map(Into::into)
+            .ok_or_else(|| errors::unsupported("Chain does not use ethash", None))
+    }
+
     fn block_header(&self, number: Option<BlockNumber>) -> BoxFuture<RichHeader> {
         const EXTRA_INFO_PROOF: &str = "Object exists in blockchain (fetched earlier), extra_info is always available if object exists; qed";
         let number = number.unwrap_or_default();
@@ -15,9 +15,9 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! RPC generic methods implementation.
-use jsonrpc_core::Result;
+use jsonrpc_core::{Result, Value};
 use std::collections::BTreeMap;
-use v1::traits::Rpc;
+use v1::{openrpc, traits::Rpc};
 
 /// RPC generic methods implementation.
 pub struct RpcClient {
@@ -63,4 +63,8 @@ impl Rpc for RpcClient {
 
         Ok(modules)
     }
+
+    fn discover(&self) -> Result<Value> {
+        Ok(openrpc::document())
+    }
 }
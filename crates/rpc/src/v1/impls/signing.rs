@@ -20,10 +20,12 @@ use parking_lot::Mutex;
 use std::sync::Arc;
 use transient_hashmap::TransientHashMap;
 
+use eip_712::TypedDataVersion;
 use ethereum_types::{H160, H256, H520, U256};
 
 use jsonrpc_core::{
     futures::{future, future::Either, Async, Future, Poll},
+    types::Value,
     BoxFuture, Error, Result,
 };
 
@@ -31,7 +33,7 @@ use v1::{
     helpers::{
         deprecated::{self, DeprecationNotice},
         dispatch::{self, Dispatcher},
-        errors,
+        eip191, errors,
         external_signer::{
             ConfirmationReceiver as RpcConfirmationReceiver,
             ConfirmationResult as RpcConfirmationResult, SignerService, SigningQueue,
@@ -155,6 +157,29 @@ impl<D: Dispatcher + 'static> SigningQueueClient<D> {
             }),
         )
     }
+
+    fn sign_typed_data(
+        &self,
+        meta: Metadata,
+        address: H160,
+        data: Value,
+        version: TypedDataVersion,
+    ) -> BoxFuture<H520> {
+        let hash = try_bf!(eip191::hash_typed_data(
+            version,
+            data,
+            self.dispatcher.chain_id()
+        ));
+        let res = self.dispatch(
+            RpcConfirmationPayload::EIP191SignMessage((address, hash).into()),
+            meta.origin,
+        );
+
+        Box::new(res.flatten().and_then(move |response| match response {
+            RpcConfirmationResponse::Signature(sig) => Ok(sig),
+            e => Err(errors::internal("Unexpected result.", e)),
+        }))
+    }
 }
 
 impl<D: Dispatcher + 'static> ParitySigning for SigningQueueClient<D> {
@@ -304,4 +329,12 @@ impl<D: Dispatcher + 'static> EthSigning for SigningQueueClient<D> {
             e => Err(errors::internal("Unexpected result.", e)),
         }))
     }
+
+    fn sign_typed_data_v4(&self, meta: Metadata, address: H160, data: Value) -> BoxFuture<H520> {
+        self.sign_typed_data(meta, address, data, TypedDataVersion::V4)
+    }
+
+    fn sign_typed_data_v3(&self, meta: Metadata, address: H160, data: Value) -> BoxFuture<H520> {
+        self.sign_typed_data(meta, address, data, TypedDataVersion::V3)
+    }
 }
@@ -18,16 +18,18 @@
 
 use std::sync::Arc;
 
+use eip_712::TypedDataVersion;
 use ethereum_types::{Address, H160, H256, H520, U256};
 use jsonrpc_core::{
     futures::{future, Future},
+    types::Value,
     BoxFuture, Result,
 };
 use v1::{
     helpers::{
         deprecated::{self, DeprecationNotice},
         dispatch::{self, Dispatcher},
-        errors,
+        eip191, errors,
     },
     metadata::Metadata,
     traits::{EthSigning, ParitySigning},
@@ -71,6 +73,30 @@ impl<D: Dispatcher + 'static> SigningUnsafeClient<D> {
                 .map(dispatch::WithToken::into_value),
         )
     }
+
+    fn sign_typed_data(
+        &self,
+        address: H160,
+        data: Value,
+        version: TypedDataVersion,
+    ) -> BoxFuture<H520> {
+        let hash = try_bf!(eip191::hash_typed_data(
+            version,
+            data,
+            self.dispatcher.chain_id()
+        ));
+        Box::new(
+            self.handle(
+                RpcConfirmationPayload::EIP191SignMessage((address, hash).into()),
+                address,
+            )
+            .then(|res| match res {
+                Ok(RpcConfirmationResponse::Signature(signature)) => Ok(signature),
+                Err(e) => Err(e),
+                e => Err(errors::internal("Unexpected result", e)),
+            }),
+        )
+    }
 }
 
 impl<D: Dispatcher + 'static> EthSigning for SigningUnsafeClient<D> {
@@ -128,6 +154,14 @@ impl<D: Dispatcher + 'static> EthSigning for SigningUnsafeClient<D> {
             }),
         )
     }
+
+    fn sign_typed_data_v4(&self, _: Metadata, address: H160, data: Value) -> BoxFuture<H520> {
+        self.sign_typed_data(address, data, TypedDataVersion::V4)
+    }
+
+    fn sign_typed_data_v3(&self, _: Metadata, address: H160, data: Value) -> BoxFuture<H520> {
+        self.sign_typed_data(address, data, TypedDataVersion::V3)
+    }
 }
 
 impl<D: Dispatcher + 'static> ParitySigning for SigningUnsafeClient<D> {
@@ -21,20 +21,37 @@ use jsonrpc_core::futures::future::Either;
 use order_stat;
 use parity_runtime;
 use parking_lot::RwLock;
+use serde_json;
+use stats::{PrometheusHistogram, PrometheusMetrics, PrometheusRegistry};
 use std::{
+    collections::HashMap,
     fmt,
     sync::{
-        atomic::{self, AtomicUsize},
+        atomic::{self, AtomicU64, AtomicUsize},
         Arc,
     },
     time,
 };
+use v1::Metadata;
 
 pub use self::parity_runtime::Executor;
 
 const RATE_SECONDS: usize = 10;
 const STATS_SAMPLES: usize = 60;
 
+/// Upper bounds of method latency buckets, in seconds.
+const LATENCY_BUCKETS: &[f64] = &[
+    0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+/// Upper bounds of payload size buckets, in bytes.
+const SIZE_BUCKETS: &[f64] = &[
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0,
+];
+/// Maximal number of methods with separate statistics, others are counted together.
+const MAX_METHODS: usize = 256;
+/// Maximal number of characters of parameters written to the slow query log.
+const SLOW_QUERY_PARAMS_LEN: usize = 256;
+
 struct RateCalculator {
     era: time::Instant,
     samples: [u16; RATE_SECONDS],
@@ -139,15 +156,43 @@ impl<T: Default + Copy + Ord> StatsCalculator<T> {
     }
 }
 
+/// Statistics of calls to a single method.
+#[derive(Debug)]
+struct MethodStats {
+    latency: PrometheusHistogram,
+    request_size: PrometheusHistogram,
+    errors: AtomicU64,
+}
+
+impl Default for MethodStats {
+    fn default() -> Self {
+        MethodStats {
+            latency: PrometheusHistogram::new(LATENCY_BUCKETS),
+            request_size: PrometheusHistogram::new(SIZE_BUCKETS),
+            errors: AtomicU64::new(0),
+        }
+    }
+}
+
 /// RPC Statistics
 #[derive(Default, Debug)]
 pub struct RpcStats {
     requests: RwLock<RateCalculator>,
     roundtrips: RwLock<StatsCalculator<u128>>,
     active_sessions: AtomicUsize,
+    methods: RwLock<HashMap<String, Arc<MethodStats>>>,
+    slow_query_threshold: Option<time::Duration>,
 }
 
 impl RpcStats {
+    /// Create statistics logging calls which take longer than `slow_query_threshold`.
+    pub fn new(slow_query_threshold: Option<time::Duration>) -> Self {
+        RpcStats {
+            slow_query_threshold,
+            ..Default::default()
+        }
+    }
+
     /// Count session opened
     pub fn open_session(&self) {
         self.active_sessions.fetch_add(1, atomic::Ordering::SeqCst);
@@ -183,6 +228,95 @@ impl RpcStats {
     pub fn approximated_roundtrip(&self) -> u128 {
         self.roundtrips.read().approximated_median()
     }
+
+    /// Add finished method call. Size of the request, if known, is in bytes.
+    pub fn add_call(
+        &self,
+        method: &str,
+        duration: time::Duration,
+        request_size: Option<usize>,
+        failed: bool,
+    ) {
+        let stats = self.method_stats(method);
+        stats.latency.observe(duration.as_secs_f64());
+        if let Some(size) = request_size {
+            stats.request_size.observe(size as f64);
+        }
+        if failed {
+            stats.errors.fetch_add(1, atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Returns statistics of given method, only taking the write lock for a new method.
+    fn method_stats(&self, method: &str) -> Arc<MethodStats> {
+        if let Some(stats) = self.methods.read().get(method) {
+            return stats.clone();
+        }
+
+        let mut methods = self.methods.write();
+        let method = match methods.contains_key(method) || methods.len() < MAX_METHODS {
+            true => method,
+            false => "other",
+        };
+        methods
+            .entry(method.to_owned())
+            .or_insert_with(Default::default)
+            .clone()
+    }
+
+    /// Returns number of calls and failed calls of given method
+    pub fn method_calls(&self, method: &str) -> (u64, u64) {
+        self.methods.read().get(method).map_or((0, 0), |stats| {
+            (
+                stats.latency.count(),
+                stats.errors.load(atomic::Ordering::Relaxed),
+            )
+        })
+    }
+}
+
+impl PrometheusMetrics for RpcStats {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        r.register_gauge(
+            "rpc_sessions",
+            "Number of open RPC sessions",
+            self.sessions() as i64,
+        );
+
+        let methods = self.methods.read();
+        let histograms = |f: fn(&MethodStats) -> &PrometheusHistogram| {
+            methods
+                .iter()
+                .map(|(method, stats)| (method.as_str(), f(stats)))
+                .collect::<Vec<_>>()
+        };
+        r.register_histogram_family(
+            "rpc_method_latency_seconds",
+            "Time spent executing RPC calls",
+            "method",
+            &histograms(|stats| &stats.latency),
+        );
+        r.register_histogram_family(
+            "rpc_method_request_bytes",
+            "Size of HTTP requests of single RPC calls",
+            "method",
+            &histograms(|stats| &stats.request_size),
+        );
+        r.register_counter_family(
+            "rpc_method_errors",
+            "Number of RPC calls which returned an error",
+            "method",
+            &methods
+                .iter()
+                .map(|(method, stats)| {
+                    (
+                        method.as_str(),
+                        stats.errors.load(atomic::Ordering::Relaxed) as i64,
+                    )
+                })
+                .collect::<Vec<_>>(),
+        );
+    }
 }
 
 /// Notifies about RPC activity.
@@ -204,18 +338,18 @@ impl<T: ActivityNotifier> Middleware<T> {
     }
 }
 
-impl<M: core::Metadata, T: ActivityNotifier> core::Middleware<M> for Middleware<T> {
+impl<T: ActivityNotifier> core::Middleware<Metadata> for Middleware<T> {
     type Future = core::FutureResponse;
-    type CallFuture = core::middleware::NoopCallFuture;
+    type CallFuture = core::FutureOutput;
 
     fn on_request<F, X>(
         &self,
         request: core::Request,
-        meta: M,
+        meta: Metadata,
         process: F,
     ) -> Either<Self::Future, X>
     where
-        F: FnOnce(core::Request, M) -> X,
+        F: FnOnce(core::Request, Metadata) -> X,
         X: core::futures::Future<Item = Option<core::Response>, Error = ()> + Send + 'static,
     {
         let start = time::Instant::now();
@@ -228,6 +362,11 @@ impl<M: core::Metadata, T: ActivityNotifier> core::Middleware<M> for Middleware<
             _ => None,
         };
         let stats = self.stats.clone();
+        // The request size reported by the transport only belongs to a single call.
+        let mut meta = meta;
+        if let core::Request::Batch(_) = request {
+            meta.request_size = None;
+        }
 
         let future = process(request, meta).map(move |res| {
             let time = start.elapsed().as_micros();
@@ -240,6 +379,71 @@ impl<M: core::Metadata, T: ActivityNotifier> core::Middleware<M> for Middleware<
 
         Either::A(Box::new(future))
     }
+
+    fn on_call<F, X>(
+        &self,
+        call: core::Call,
+        meta: Metadata,
+        process: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(core::Call, Metadata) -> X,
+        X: core::futures::Future<Item = Option<core::Output>, Error = ()> + Send + 'static,
+    {
+        let start = time::Instant::now();
+
+        let (method, params) = match call {
+            core::Call::MethodCall(ref call) => (call.method.clone(), &call.params),
+            core::Call::Notification(ref notification) => {
+                (notification.method.clone(), &notification.params)
+            }
+            core::Call::Invalid { .. } => return Either::B(process(call, meta)),
+        };
+        // Parameters are only serialized for the slow query log.
+        let (params, client) = match self.stats.slow_query_threshold {
+            Some(_) => (Some(params.clone()), meta.client_id()),
+            None => (None, String::new()),
+        };
+        let request_size = meta.request_size;
+        let stats = self.stats.clone();
+        let mut span = telemetry::detached_span(method.clone(), telemetry::SpanKind::Server);
+        span.set_attribute("rpc.system", "jsonrpc");
+        if span.is_recording() {
+            span.set_attribute("rpc.method", method.clone());
+        }
+
+        let future = process(call, meta).map(move |output| {
+            let elapsed = start.elapsed();
+            let (method, failed) = match output {
+                Some(core::Output::Failure(ref failure))
+                    if failure.error.code == core::ErrorCode::MethodNotFound =>
+                {
+                    ("unknown", true)
+                }
+                Some(core::Output::Failure(_)) => (method.as_str(), true),
+                _ => (method.as_str(), false),
+            };
+            stats.add_call(method, elapsed, request_size, failed);
+            if failed {
+                span.set_error();
+            }
+            drop(span);
+
+            if let Some(params) = params {
+                if stats.slow_query_threshold.map_or(false, |threshold| elapsed >= threshold) {
+                    let params = serde_json::to_string(&params).unwrap_or_default();
+                    let params = match params.char_indices().nth(SLOW_QUERY_PARAMS_LEN) {
+                        Some((end, _)) => format!("{}...", &params[..end]),
+                        None => params,
+                    };
+                    info!(target: "rpc_slow", "{} {} took {}ms, client: {}", method, params, elapsed.as_millis(), client);
+                }
+            }
+            output
+        });
+
+        Either::A(Box::new(future))
+    }
 }
 
 /// Client Notifier
@@ -257,7 +461,18 @@ impl ActivityNotifier for ClientNotifier {
 #[cfg(test)]
 mod tests {
 
-    use super::{RateCalculator, RpcStats, StatsCalculator};
+    use super::{
+        ActivityNotifier, Middleware, RateCalculator, RpcStats, StatsCalculator, MAX_METHODS,
+    };
+    use jsonrpc_core::{MetaIoHandler, Value};
+    use std::{sync::Arc, time::Duration};
+    use v1::Metadata;
+
+    struct NoopNotifier;
+
+    impl ActivityNotifier for NoopNotifier {
+        fn active(&self) {}
+    }
 
     #[test]
     fn should_calculate_rate() {
@@ -314,6 +529,55 @@ mod tests {
         assert_eq!(stats.approximated_roundtrip(), 125);
     }
 
+    #[test]
+    fn should_count_method_calls() {
+        // given
+        let stats = RpcStats::default();
+
+        // when
+        stats.add_call("eth_call", Duration::from_millis(3), Some(100), false);
+        stats.add_call("eth_call", Duration::from_millis(300), None, true);
+        for method in 0..MAX_METHODS {
+            stats.add_call(&method.to_string(), Duration::from_millis(1), None, false);
+        }
+
+        // then
+        assert_eq!(stats.method_calls("eth_call"), (2, 1));
+        assert_eq!(stats.methods.read()["eth_call"].request_size.count(), 1);
+        assert_eq!(stats.method_calls("eth_getLogs"), (0, 0));
+        assert_eq!(stats.method_calls("other"), (1, 0));
+    }
+
+    #[test]
+    fn should_record_call_latency() {
+        // given
+        let stats = Arc::new(RpcStats::new(Some(Duration::from_millis(0))));
+        let mut io = MetaIoHandler::with_middleware(Middleware::new(stats.clone(), NoopNotifier));
+        io.add_method("hello", |_| Ok(Value::String("world".into())));
+
+        // when
+        let request = r#"[
+            {"jsonrpc":"2.0","method":"hello","params":[],"id":1},
+            {"jsonrpc":"2.0","method":"missing","params":[],"id":2}
+        ]"#;
+        let meta = Metadata {
+            request_size: Some(request.len()),
+            ..Default::default()
+        };
+        io.handle_request_sync(request, meta.clone());
+        io.handle_request_sync(
+            r#"{"jsonrpc":"2.0","method":"hello","params":[],"id":1}"#,
+            meta,
+        );
+
+        // then
+        assert_eq!(stats.method_calls("hello"), (2, 0));
+        assert_eq!(stats.method_calls("missing"), (0, 0));
+        assert_eq!(stats.method_calls("unknown"), (1, 1));
+        // only the single call is attributed the request size
+        assert_eq!(stats.methods.read()["hello"].request_size.count(), 1);
+    }
+
     #[test]
     fn should_be_sync_and_send() {
         let stats = RpcStats::default();
@@ -15,11 +15,12 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! OpenEthereum RPC requests Metadata.
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
 use jsonrpc_core;
 use jsonrpc_pubsub::{PubSubMetadata, Session};
 
+use jwt::JwtClaims;
 use v1::types::Origin;
 
 /// RPC methods metadata.
@@ -29,6 +30,33 @@ pub struct Metadata {
     pub origin: Origin,
     /// Request PubSub Session
     pub session: Option<Arc<Session>>,
+    /// Claims of the verified access token the request was made with
+    pub claims: Option<Arc<JwtClaims>>,
+    /// Client address reported by a trusted reverse proxy
+    pub client_ip: Option<IpAddr>,
+    /// Size of the request in bytes, as reported by the transport
+    pub request_size: Option<usize>,
+}
+
+impl Metadata {
+    /// Identifies the client making the request: the access token subject, the address
+    /// reported by a trusted reverse proxy or the connection the request arrived on, in that order.
+    /// HTTP transport doesn't expose the peer address, so remaining HTTP clients are anonymous.
+    pub fn client_id(&self) -> String {
+        if let Some(sub) = self.claims.as_ref().and_then(|claims| claims.sub.as_ref()) {
+            return format!("token:{}", sub);
+        }
+        if let Some(ip) = self.client_ip {
+            return ip.to_string();
+        }
+        match self.origin {
+            Origin::Ws { ref session } | Origin::Signer { ref session } => {
+                format!("ws:{:x}", session)
+            }
+            Origin::Ipc(ref session) => format!("ipc:{:x}", session),
+            Origin::Rpc(_) | Origin::Unknown => "anonymous".into(),
+        }
+    }
 }
 
 impl jsonrpc_core::Metadata for Metadata {}
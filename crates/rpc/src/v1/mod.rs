@@ -37,12 +37,15 @@ mod tests;
 mod types;
 
 pub mod extractors;
+pub mod graphql;
 pub mod informant;
 pub mod metadata;
+pub mod openrpc;
+pub mod rate_limit;
 pub mod traits;
 
 pub use self::{
-    extractors::{RpcExtractor, WsDispatcher, WsExtractor, WsStats},
+    extractors::{JwtExtractor, RpcExtractor, WsDispatcher, WsExtractor, WsStats},
     helpers::{block_import, dispatch, NetworkSettings},
     impls::*,
     metadata::Metadata,
@@ -0,0 +1,58 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! OpenRPC document of the API.
+//!
+//! Generated at build time from the RPC traits and types, see `build.rs`.
+
+use serde_json::Value;
+use version::raw_package_info;
+
+const DOCUMENT: &str = include_str!(concat!(env!("OUT_DIR"), "/openrpc.json"));
+
+/// Returns the OpenRPC document describing all RPC methods.
+pub fn document() -> Value {
+    let mut document: Value =
+        ::serde_json::from_str(DOCUMENT).expect("OpenRPC document is generated as valid JSON; qed");
+    let (_, version, _) = raw_package_info();
+    document["info"]["version"] = Value::String(version.into());
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::document;
+
+    #[test]
+    fn should_describe_methods_and_schemas() {
+        let document = document();
+
+        assert_eq!(document["openrpc"], "1.2.6");
+        let methods = document["methods"].as_array().unwrap();
+        let call = methods
+            .iter()
+            .find(|method| method["name"] == "eth_call")
+            .unwrap();
+        assert_eq!(call["params"][0]["name"], "callRequest");
+        assert_eq!(
+            call["params"][0]["schema"]["$ref"],
+            "#/components/schemas/CallRequest"
+        );
+        assert_eq!(call["params"][1]["required"], false);
+        assert!(document["components"]["schemas"]["CallRequest"]["properties"]["from"].is_object());
+        assert!(!methods.iter().any(|method| method["name"] == "rpc_modules"));
+    }
+}
@@ -0,0 +1,578 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-client RPC rate limiting.
+//!
+//! Every call is charged a cost in request units, which is taken from the client's
+//! token bucket. The bucket refills at a constant rate up to its burst capacity.
+
+use std::{
+    cmp,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use ethereum_types::H256;
+use jsonrpc_core as core;
+use jsonrpc_core::futures::future::{self, Either};
+use parking_lot::Mutex;
+use serde_json;
+use stats::{PrometheusMetrics, PrometheusRegistry};
+
+use v1::{helpers::errors, types::BlockNumber, Metadata};
+
+/// Number of blocks covered by a single cost unit of ranged queries.
+const BLOCKS_PER_COST: u64 = 1_000;
+/// Number of tracked clients after which idle ones are forgotten.
+const MAX_CLIENTS: usize = 4_096;
+/// Number of tracked clients left after forgetting the least recently seen ones.
+const PRUNED_CLIENTS: usize = MAX_CLIENTS * 3 / 4;
+/// Number of clients reported individually in metrics; the rest are summed up as `other`.
+const MAX_CLIENT_METRICS: usize = 32;
+
+/// Rate limiting configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    /// Request units added to each client's bucket per second.
+    pub rate: u64,
+    /// Maximal number of request units a client can accumulate.
+    pub burst: u64,
+    /// Cost of methods not listed in `costs`.
+    pub default_cost: u64,
+    /// Cost of particular methods. For `eth_getLogs` and `trace_filter`
+    /// it is charged for every 1000 blocks of the queried range.
+    pub costs: HashMap<String, u64>,
+    /// Maximal block range of `eth_getLogs` and `trace_filter` queries.
+    pub max_block_range: Option<u64>,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        let costs = [
+            ("eth_call", 5),
+            ("eth_estimateGas", 5),
+            ("eth_getLogs", 10),
+            ("eth_getFilterLogs", 10),
+            ("trace_call", 10),
+            ("trace_callMany", 20),
+            ("trace_rawTransaction", 10),
+            ("trace_block", 20),
+            ("trace_filter", 20),
+            ("trace_replayTransaction", 20),
+            ("trace_replayBlockTransactions", 50),
+        ];
+
+        RateLimitConfig {
+            rate: 100,
+            burst: 1_000,
+            default_cost: 1,
+            costs: costs
+                .iter()
+                .map(|&(method, cost)| (method.to_owned(), cost))
+                .collect(),
+            max_block_range: None,
+        }
+    }
+}
+
+/// Calls made by a client.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Usage {
+    calls: u64,
+    cost: u64,
+    rejected: u64,
+}
+
+/// Request units bucket and usage of a single client.
+struct Client {
+    tokens: f64,
+    refilled: Instant,
+    seen: Instant,
+    usage: Usage,
+}
+
+impl Client {
+    fn new(burst: u64, now: Instant) -> Self {
+        Client {
+            tokens: burst as f64,
+            refilled: now,
+            seen: now,
+            usage: Usage::default(),
+        }
+    }
+
+    fn refill(&mut self, config: &RateLimitConfig, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.refilled);
+        self.tokens =
+            (self.tokens + elapsed.as_secs_f64() * config.rate as f64).min(config.burst as f64);
+        self.refilled = now;
+    }
+}
+
+struct Limits {
+    config: RateLimitConfig,
+    best_block: Box<dyn Fn() -> u64 + Send + Sync>,
+    clients: Mutex<HashMap<String, Client>>,
+    /// Usage of all clients, including forgotten ones
+    calls: AtomicU64,
+    cost: AtomicU64,
+    rejected: AtomicU64,
+}
+
+impl Limits {
+    /// Charges the client for the call, returning an error if it exceeds its quota.
+    fn charge(
+        &self,
+        client: &str,
+        method: &str,
+        params: &core::Params,
+        now: Instant,
+    ) -> core::Result<()> {
+        let cost = self.cost(method, params)?;
+
+        let mut clients = self.clients.lock();
+        if clients.len() >= MAX_CLIENTS && !clients.contains_key(client) {
+            self.prune(&mut clients, now);
+        }
+        let entry = clients
+            .entry(client.to_owned())
+            .or_insert_with(|| Client::new(self.config.burst, now));
+        entry.refill(&self.config, now);
+        entry.seen = now;
+        entry.usage.calls += 1;
+        self.calls.fetch_add(1, Ordering::Relaxed);
+
+        if entry.tokens < cost as f64 {
+            entry.usage.rejected += 1;
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+            let missing = cost as f64 - entry.tokens;
+            let retry_after = (missing / cmp::max(self.config.rate, 1) as f64).ceil() as u64;
+            return Err(errors::rate_limited(retry_after));
+        }
+
+        entry.tokens -= cost as f64;
+        entry.usage.cost += cost;
+        self.cost.fetch_add(cost, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Usage of the `limit` clients which spent the most request units and the usage
+    /// of all remaining clients, including forgotten ones.
+    fn usage(&self, limit: usize) -> (Vec<(String, Usage)>, Usage) {
+        let mut usage = {
+            let clients = self.clients.lock();
+            clients
+                .iter()
+                .map(|(id, client)| (id.clone(), client.usage))
+                .collect::<Vec<_>>()
+        };
+        usage.sort_by(|(a_id, a), (b_id, b)| b.cost.cmp(&a.cost).then_with(|| a_id.cmp(b_id)));
+        usage.truncate(limit);
+
+        let mut other = Usage {
+            calls: self.calls.load(Ordering::Relaxed),
+            cost: self.cost.load(Ordering::Relaxed),
+            rejected: self.rejected.load(Ordering::Relaxed),
+        };
+        for (_, client) in &usage {
+            other.calls = other.calls.saturating_sub(client.calls);
+            other.cost = other.cost.saturating_sub(client.cost);
+            other.rejected = other.rejected.saturating_sub(client.rejected);
+        }
+        (usage, other)
+    }
+
+    /// Forgets clients which have been idle long enough to refill their buckets.
+    /// If there are still too many, the least recently seen ones are forgotten too.
+    fn prune(&self, clients: &mut HashMap<String, Client>, now: Instant) {
+        let config = &self.config;
+        clients.retain(|_, client| {
+            client.refill(config, now);
+            client.tokens < config.burst as f64
+        });
+
+        if clients.len() >= MAX_CLIENTS {
+            let mut seen = clients
+                .iter()
+                .map(|(id, client)| (client.seen, id.clone()))
+                .collect::<Vec<_>>();
+            seen.sort();
+            let excess = clients.len() - PRUNED_CLIENTS;
+            for (_, id) in seen.into_iter().take(excess) {
+                clients.remove(&id);
+            }
+        }
+    }
+
+    fn cost(&self, method: &str, params: &core::Params) -> core::Result<u64> {
+        let cost = self
+            .config
+            .costs
+            .get(method)
+            .cloned()
+            .unwrap_or(self.config.default_cost);
+
+        let blocks = match method {
+            "eth_getLogs" | "trace_filter" => self.block_range(params),
+            _ => None,
+        };
+        let cost = match blocks {
+            Some(blocks) => {
+                if let Some(max) = self.config.max_block_range {
+                    if blocks > max {
+                        return Err(errors::block_range_too_large(blocks, max));
+                    }
+                }
+                let units = cmp::max((blocks + BLOCKS_PER_COST - 1) / BLOCKS_PER_COST, 1);
+                cost.saturating_mul(units)
+            }
+            None => cost,
+        };
+
+        // The most expensive calls are allowed with a full bucket.
+        Ok(cmp::min(cost, self.config.burst))
+    }
+
+    /// Number of blocks queried by `eth_getLogs` or `trace_filter` filter.
+    fn block_range(&self, params: &core::Params) -> Option<u64> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Range {
+            from_block: Option<BlockNumber>,
+            to_block: Option<BlockNumber>,
+            block_hash: Option<H256>,
+        }
+
+        let range: Range = match *params {
+            core::Params::Array(ref params) => {
+                serde_json::from_value(params.get(0)?.clone()).ok()?
+            }
+            _ => return None,
+        };
+        if range.block_hash.is_some() {
+            return Some(1);
+        }
+
+        let best = (self.best_block)();
+        let number = |block: Option<BlockNumber>| match block {
+            Some(BlockNumber::Num(n)) => cmp::min(n, best),
+            Some(BlockNumber::Earliest) => 0,
+            _ => best,
+        };
+        let (from, to) = (number(range.from_block), number(range.to_block));
+        Some(to.saturating_sub(from) + 1)
+    }
+}
+
+/// RPC middleware enforcing per-client request quotas.
+///
+/// Clients are identified by the subject of their access token, the address reported
+/// by a trusted reverse proxy or their WebSocket/IPC connection. Remaining HTTP clients share a quota.
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    limits: Option<Arc<Limits>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter resolving `latest` block of ranged queries with `best_block`.
+    pub fn new<F>(config: RateLimitConfig, best_block: F) -> Self
+    where
+        F: Fn() -> u64 + Send + Sync + 'static,
+    {
+        RateLimiter {
+            limits: Some(Arc::new(Limits {
+                config,
+                best_block: Box::new(best_block),
+                clients: Mutex::new(HashMap::new()),
+                calls: AtomicU64::new(0),
+                cost: AtomicU64::new(0),
+                rejected: AtomicU64::new(0),
+            })),
+        }
+    }
+
+    /// Creates a rate limiter allowing all calls.
+    pub fn disabled() -> Self {
+        RateLimiter::default()
+    }
+}
+
+impl core::Middleware<Metadata> for RateLimiter {
+    type Future = core::FutureResponse;
+    type CallFuture = core::middleware::NoopCallFuture;
+
+    fn on_call<F, X>(
+        &self,
+        call: core::Call,
+        meta: Metadata,
+        process: F,
+    ) -> Either<Self::CallFuture, X>
+    where
+        F: FnOnce(core::Call, Metadata) -> X,
+        X: core::futures::Future<Item = Option<core::Output>, Error = ()> + Send + 'static,
+    {
+        let limits = match self.limits {
+            Some(ref limits) => limits,
+            None => return Either::B(process(call, meta)),
+        };
+
+        let client = meta.client_id();
+        let now = Instant::now();
+        let rejected = match call {
+            core::Call::MethodCall(ref call) => limits
+                .charge(&client, &call.method, &call.params, now)
+                .err()
+                .map(|error| {
+                    Some(core::Output::from(
+                        Err(error),
+                        call.id.clone(),
+                        call.jsonrpc,
+                    ))
+                }),
+            core::Call::Notification(ref notification) => limits
+                .charge(&client, &notification.method, &notification.params, now)
+                .err()
+                .map(|_| None),
+            core::Call::Invalid { .. } => None,
+        };
+
+        match rejected {
+            Some(output) => Either::A(Box::new(future::ok(output))),
+            None => Either::B(process(call, meta)),
+        }
+    }
+}
+
+impl PrometheusMetrics for RateLimiter {
+    fn prometheus_metrics(&self, r: &mut PrometheusRegistry) {
+        let limits = match self.limits {
+            Some(ref limits) => limits,
+            None => return,
+        };
+
+        let (usage, other) = limits.usage(MAX_CLIENT_METRICS);
+        let family = |f: fn(&Usage) -> u64| {
+            usage
+                .iter()
+                .map(|(id, usage)| (id.as_str(), f(usage) as i64))
+                .chain(Some(("other", f(&other) as i64)))
+                .collect::<Vec<_>>()
+        };
+        r.register_gauge(
+            "rpc_clients",
+            "Number of RPC clients tracked by the rate limiter",
+            limits.clients.lock().len() as i64,
+        );
+        r.register_counter_family(
+            "rpc_client_calls",
+            "RPC calls made by the client",
+            "client",
+            &family(|u| u.calls),
+        );
+        r.register_counter_family(
+            "rpc_client_cost",
+            "Request units spent by the client",
+            "client",
+            &family(|u| u.cost),
+        );
+        r.register_counter_family(
+            "rpc_client_rejected",
+            "RPC calls of the client rejected by the rate limiter",
+            "client",
+            &family(|u| u.rejected),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimitConfig, RateLimiter, Usage, MAX_CLIENTS, PRUNED_CLIENTS};
+    use jsonrpc_core::{MetaIoHandler, Params, Value};
+    use std::{
+        sync::atomic::Ordering,
+        time::{Duration, Instant},
+    };
+    use v1::Metadata;
+
+    fn limiter(max_block_range: Option<u64>) -> RateLimiter {
+        RateLimiter::new(
+            RateLimitConfig {
+                rate: 10,
+                burst: 100,
+                max_block_range,
+                ..Default::default()
+            },
+            || 100_000,
+        )
+    }
+
+    fn filter(from: &str, to: &str) -> Params {
+        Params::Array(vec![
+            ::serde_json::json!({ "fromBlock": from, "toBlock": to }),
+        ])
+    }
+
+    #[test]
+    fn should_charge_ranged_queries_per_blocks() {
+        // given
+        let limits = limiter(None).limits.unwrap();
+
+        // then
+        assert_eq!(limits.cost("eth_blockNumber", &Params::None), Ok(1));
+        assert_eq!(limits.cost("eth_getLogs", &filter("0x1", "0x1")), Ok(10));
+        assert_eq!(limits.cost("eth_getLogs", &filter("0x1", "0xbb8")), Ok(30));
+        assert_eq!(limits.cost("trace_filter", &filter("0x1", "0x3e8")), Ok(20));
+        assert_eq!(
+            limits.cost(
+                "eth_getLogs",
+                &Params::Array(vec![
+                    ::serde_json::json!({ "blockHash": format!("0x{:064x}", 1) })
+                ])
+            ),
+            Ok(10)
+        );
+        // capped at burst
+        assert_eq!(
+            limits.cost("eth_getLogs", &filter("earliest", "latest")),
+            Ok(100)
+        );
+    }
+
+    #[test]
+    fn should_reject_too_large_block_ranges() {
+        // given
+        let limits = limiter(Some(1_000)).limits.unwrap();
+
+        // then
+        assert!(limits.cost("eth_getLogs", &filter("0x1", "0x3e8")).is_ok());
+        let error = limits
+            .cost("eth_getLogs", &filter("earliest", "latest"))
+            .unwrap_err();
+        assert_eq!(error.code.code(), -32006);
+    }
+
+    #[test]
+    fn should_refill_buckets() {
+        // given
+        let limits = limiter(None).limits.unwrap();
+        let now = Instant::now();
+        let call = |now| {
+            limits.charge(
+                "client",
+                "trace_replayBlockTransactions",
+                &Params::None,
+                now,
+            )
+        };
+
+        // when
+        assert!(call(now).is_ok());
+        assert!(call(now).is_ok());
+        let error = call(now).unwrap_err();
+
+        // then
+        assert_eq!(error.code.code(), -32006);
+        assert!(limits
+            .charge("another", "eth_blockNumber", &Params::None, now)
+            .is_ok());
+        assert!(call(now + Duration::from_secs(4)).is_err());
+        assert!(call(now + Duration::from_secs(5)).is_ok());
+        assert_eq!(limits.calls.load(Ordering::Relaxed), 6);
+        assert_eq!(limits.rejected.load(Ordering::Relaxed), 2);
+        assert_eq!(limits.cost.load(Ordering::Relaxed), 151);
+        let (usage, other) = limits.usage(1);
+        assert_eq!(
+            usage,
+            vec![(
+                "client".to_owned(),
+                Usage {
+                    calls: 5,
+                    cost: 150,
+                    rejected: 2
+                }
+            )]
+        );
+        assert_eq!(
+            other,
+            Usage {
+                calls: 1,
+                cost: 1,
+                rejected: 0
+            }
+        );
+    }
+
+    #[test]
+    fn should_forget_least_recently_seen_clients() {
+        // given
+        let limits = limiter(None).limits.unwrap();
+        let now = Instant::now();
+        let call = |client: &str, now| {
+            limits
+                .charge(client, "trace_replayBlockTransactions", &Params::None, now)
+                .unwrap();
+        };
+        for i in 0..MAX_CLIENTS {
+            call(
+                &format!("client{}", i),
+                now + Duration::from_millis(i as u64),
+            );
+        }
+
+        // when
+        call("another", now + Duration::from_millis(MAX_CLIENTS as u64));
+
+        // then
+        let clients = limits.clients.lock();
+        assert_eq!(clients.len(), PRUNED_CLIENTS + 1);
+        assert!(!clients.contains_key("client0"));
+        assert!(clients.contains_key(&format!("client{}", MAX_CLIENTS - 1)));
+        assert!(clients.contains_key("another"));
+    }
+
+    #[test]
+    fn should_return_rate_limit_error() {
+        // given
+        let limiter = RateLimiter::new(
+            RateLimitConfig {
+                rate: 1,
+                burst: 10,
+                ..Default::default()
+            },
+            || 0,
+        );
+        let mut io = MetaIoHandler::with_middleware(limiter);
+        io.add_method("eth_blockNumber", |_| Ok(Value::String("0x1".into())));
+        let request = r#"{"jsonrpc": "2.0", "method": "eth_blockNumber", "params": [], "id": 1}"#;
+        let meta = Metadata::default();
+        for _ in 0..10 {
+            io.handle_request_sync(request, meta.clone()).unwrap();
+        }
+
+        // when
+        let response = io.handle_request_sync(request, meta).unwrap();
+
+        // then
+        assert_eq!(
+            response,
+            r#"{"jsonrpc":"2.0","error":{"code":-32006,"message":"Rate limit exceeded.","data":"Retry after 1 seconds."},"id":1}"#
+        );
+    }
+}
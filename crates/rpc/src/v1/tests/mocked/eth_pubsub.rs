@@ -26,11 +26,21 @@ use std::time::Duration;
 
 use v1::{EthPubSub, EthPubSubClient, Metadata};
 
-use ethcore::client::{
-    ChainNotify, ChainRoute, ChainRouteType, EachBlockWith, NewBlocks, TestBlockChainClient,
+use ethcore::{
+    client::{
+        ChainNotify, ChainRoute, ChainRouteType, EachBlockWith, NewBlocks, TestBlockChainClient,
+    },
+    trace::{
+        trace::{Action as TraceAction, Call, Res},
+        LocalizedTrace,
+    },
 };
 use ethereum_types::{Address, H256};
+use miner::pool::VerifiedTransaction;
 use parity_runtime::Runtime;
+use serde_json;
+use types::transaction::{Action, Transaction, TypedTransaction};
+use vm::CallType;
 
 const DURATION_ZERO: Duration = Duration::from_millis(0);
 
@@ -268,6 +278,187 @@ fn should_subscribe_to_pending_transactions() {
     assert_eq!(res, None);
 }
 
+#[test]
+fn should_subscribe_to_filtered_pending_transactions() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Fail if the filter is invalid
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["pendingTransactions", {"value": "0x1"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params: Invalid Pub-Sub parameters: unknown field `value`, expected one of `from`, `to`, `methodSelector`, `minGasPrice`."},"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["pendingTransactions", {"from": "0x0000000000000000000000000000000000000005", "minGasPrice": "0x2"}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Send new transactions
+    let new_tx = |gas_price: u64, sender: u64| {
+        let signed = TypedTransaction::Legacy(Transaction {
+            action: Action::Call(Address::from_low_u64_be(1)),
+            gas_price: gas_price.into(),
+            ..Default::default()
+        })
+        .fake_sign(Address::from_low_u64_be(sender));
+        Arc::new(VerifiedTransaction::from_pending_block_transaction(signed))
+    };
+    let expected = new_tx(2, 5);
+    let pool = vec![new_tx(2, 6), new_tx(1, 5), expected.clone()];
+    let hashes = pool.iter().map(|tx| *tx.hash()).collect::<Vec<_>>();
+    handler.notify_new_full_transactions(&hashes, move |hash| {
+        pool.iter().find(|tx| tx.hash() == hash).cloned()
+    });
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let res: serde_json::Value = serde_json::from_str(&res.unwrap()).unwrap();
+    assert_eq!(
+        res["params"]["result"]["hash"],
+        format!("{:?}", expected.hash())
+    );
+    assert_eq!(
+        res["params"]["result"]["from"],
+        "0x0000000000000000000000000000000000000005"
+    );
+
+    // And unsubscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x43ca64edf03768e1"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata),
+        Some(response.to_owned())
+    );
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    assert_eq!(res, None);
+}
+
+#[test]
+fn should_subscribe_to_traces() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+    *client.traces.write() = Some(vec![LocalizedTrace {
+        action: TraceAction::Call(Call {
+            from: Address::from_low_u64_be(0xf),
+            to: Address::from_low_u64_be(0x10),
+            value: 0x1.into(),
+            gas: 0x100.into(),
+            input: vec![1, 2, 3],
+            call_type: CallType::Call,
+        }),
+        result: Res::None,
+        subtraces: 0,
+        trace_address: vec![0],
+        transaction_number: Some(0),
+        transaction_hash: Some(H256::from_low_u64_be(5)),
+        block_number: 10,
+        block_hash: H256::from_low_u64_be(10),
+    }]);
+
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let handler = pubsub.handler().upgrade().unwrap();
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe to a non-matching filter
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["traces", {"toAddress": ["0x0000000000000000000000000000000000000011"]}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x43ca64edf03768e1","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Subscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["traces", {"fromAddress": ["0x000000000000000000000000000000000000000f"], "callType": ["call"]}], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":"0x9c4a369cc0eb3d0b","id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+
+    // Check notifications
+    handler.new_blocks(NewBlocks::new(
+        vec![],
+        vec![],
+        ChainRoute::new(vec![
+            (H256::from_low_u64_be(9), ChainRouteType::Retracted),
+            (H256::from_low_u64_be(10), ChainRouteType::Enacted),
+        ]),
+        vec![],
+        vec![],
+        DURATION_ZERO,
+        false,
+    ));
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":{"action":{"callType":"call","from":"0x000000000000000000000000000000000000000f","gas":"0x100","input":"0x010203","to":"0x0000000000000000000000000000000000000010","value":"0x1"},"blockHash":"0x000000000000000000000000000000000000000000000000000000000000000a","blockNumber":10,"removed":true,"result":null,"subtraces":0,"traceAddress":[0],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000005","transactionPosition":0,"type":"call"},"subscription":"0x9c4a369cc0eb3d0b"}}"#;
+    assert_eq!(res, Some(response.into()));
+
+    let (res, receiver) = receiver.into_future().wait().unwrap();
+    let response = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"result":{"action":{"callType":"call","from":"0x000000000000000000000000000000000000000f","gas":"0x100","input":"0x010203","to":"0x0000000000000000000000000000000000000010","value":"0x1"},"blockHash":"0x000000000000000000000000000000000000000000000000000000000000000a","blockNumber":10,"removed":false,"result":null,"subtraces":0,"traceAddress":[0],"transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000005","transactionPosition":0,"type":"call"},"subscription":"0x9c4a369cc0eb3d0b"}}"#;
+    assert_eq!(res, Some(response.into()));
+
+    // And unsubscribe
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x9c4a369cc0eb3d0b"], "id": 1}"#;
+    let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata),
+        Some(response.to_owned())
+    );
+
+    let (res, _receiver) = receiver.into_future().wait().unwrap();
+    assert_eq!(res, None);
+}
+
+#[test]
+fn should_reject_traces_if_tracing_is_disabled() {
+    // given
+    let el = Runtime::with_thread_count(1);
+    let client = TestBlockChainClient::new();
+    let pubsub = EthPubSubClient::new_test(Arc::new(client), el.executor());
+    let pubsub = pubsub.to_delegate();
+
+    let mut io = MetaIoHandler::default();
+    io.extend_with(pubsub);
+
+    let mut metadata = Metadata::default();
+    let (sender, _receiver) = futures::sync::mpsc::channel(8);
+    metadata.session = Some(Arc::new(Session::new(sender)));
+
+    // Subscribe
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Tracing is disabled. Restart with --tracing on to enable this subscription."},"id":1}"#;
+    let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["traces"], "id": 1}"#;
+    assert_eq!(
+        io.handle_request_sync(request, metadata.clone()),
+        Some(response.to_owned())
+    );
+}
+
 #[test]
 fn should_return_unimplemented() {
     // given
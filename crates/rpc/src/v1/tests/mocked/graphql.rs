@@ -0,0 +1,226 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use ethcore::client::{BlockChainClient, EachBlockWith, Executed, TestBlockChainClient};
+use ethereum_types::U256;
+use serde_json::{self, Value};
+use types::ids::BlockId;
+
+use v1::{
+    graphql::{GraphQL, Limits},
+    tests::helpers::TestMinerService,
+};
+
+struct GraphQLTester {
+    client: Arc<TestBlockChainClient>,
+    graphql: GraphQL,
+}
+
+impl Default for GraphQLTester {
+    fn default() -> Self {
+        let client = Arc::new(TestBlockChainClient::new());
+        let miner = Arc::new(TestMinerService::default());
+        let graphql = GraphQL::new(client.clone(), miner, 50);
+        GraphQLTester { client, graphql }
+    }
+}
+
+impl GraphQLTester {
+    fn query(&self, query: &str) -> (bool, Value) {
+        let request = serde_json::json!({ "query": query }).to_string();
+        let (ok, response) = self.graphql.handle(request.as_bytes());
+        (ok, serde_json::from_str(&response).unwrap())
+    }
+}
+
+#[test]
+fn graphql_block_by_number() {
+    let tester = GraphQLTester::default();
+    tester.client.add_blocks(2, EachBlockWith::Nothing);
+    let hash = tester.client.block_hash(BlockId::Number(1)).unwrap();
+    let parent = tester.client.block_hash(BlockId::Number(0)).unwrap();
+
+    let (ok, response) = tester.query("{ block(number: 1) { number hash parent { hash } } }");
+
+    assert!(ok);
+    assert_eq!(
+        response,
+        serde_json::json!({
+            "data": { "block": {
+                "number": "0x1",
+                "hash": format!("0x{:x}", hash),
+                "parent": { "hash": format!("0x{:x}", parent) },
+            }}
+        })
+    );
+}
+
+#[test]
+fn graphql_latest_block_and_range() {
+    let tester = GraphQLTester::default();
+    tester.client.add_blocks(3, EachBlockWith::Nothing);
+
+    let (ok, response) = tester.query(r#"{ block { number } blocks(from: "0x1") { number } }"#);
+
+    assert!(ok);
+    assert_eq!(
+        response,
+        serde_json::json!({
+            "data": {
+                "block": { "number": "0x3" },
+                "blocks": [{ "number": "0x1" }, { "number": "0x2" }, { "number": "0x3" }],
+            }
+        })
+    );
+}
+
+#[test]
+fn graphql_unknown_block_and_transaction() {
+    let tester = GraphQLTester::default();
+
+    let (ok, response) = tester.query(
+        r#"{
+            block(number: 10) { number }
+            transaction(hash: "0x0000000000000000000000000000000000000000000000000000000000000001") { hash }
+        }"#,
+    );
+
+    assert!(ok);
+    assert_eq!(
+        response,
+        serde_json::json!({ "data": { "block": null, "transaction": null } })
+    );
+}
+
+#[test]
+fn graphql_gas_price_and_chain_id() {
+    let tester = GraphQLTester::default();
+
+    let (ok, response) = tester.query("{ gasPrice chainID }");
+
+    assert!(ok);
+    assert_eq!(
+        response,
+        serde_json::json!({ "data": { "gasPrice": "0x4a817c800", "chainID": null } })
+    );
+}
+
+#[test]
+fn graphql_call_and_estimate_gas() {
+    let tester = GraphQLTester::default();
+    tester.client.set_execution_result(Ok(Executed {
+        exception: None,
+        gas: U256::zero(),
+        gas_used: U256::from(0xff30),
+        refunded: U256::from(0x5),
+        cumulative_gas_used: U256::zero(),
+        logs: vec![],
+        contracts_created: vec![],
+        output: vec![0x12, 0x34, 0xff],
+        trace: vec![],
+        vm_trace: None,
+        state_diff: None,
+    }));
+
+    let (ok, response) = tester.query(
+        r#"{
+            block {
+                call(data: { to: "0xd46e8dd67c5d32be8058bb8eb970870f07244567", data: "0x" }) {
+                    data gasUsed status
+                }
+            }
+            pending {
+                estimateGas(data: { to: "0xd46e8dd67c5d32be8058bb8eb970870f07244567", value: 10 })
+            }
+        }"#,
+    );
+
+    assert!(ok);
+    assert_eq!(
+        response,
+        serde_json::json!({
+            "data": {
+                "block": { "call": { "data": "0x1234ff", "gasUsed": "0xff30", "status": "0x1" } },
+                "pending": { "estimateGas": "0x5208" },
+            }
+        })
+    );
+}
+
+#[test]
+fn graphql_rejects_invalid_requests() {
+    let tester = GraphQLTester::default();
+
+    let (ok, response) = tester.graphql.handle(b"{ block");
+    assert!(!ok);
+    assert!(serde_json::from_str::<Value>(&response).unwrap()["errors"].is_array());
+
+    let (ok, response) = tester.query("{ block(number: \"0xzz\") { number } }");
+    assert!(!ok);
+    assert!(response["errors"].is_array());
+}
+
+#[test]
+fn graphql_mutations_are_disabled_by_default() {
+    let tester = GraphQLTester::default();
+
+    let (ok, response) = tester.query(r#"mutation { sendRawTransaction(data: "0xf8") }"#);
+
+    assert!(ok);
+    assert_eq!(response["data"], Value::Null);
+    assert_eq!(
+        response["errors"][0]["message"],
+        "Mutations are disabled, enable the graphql_mutation API"
+    );
+}
+
+#[test]
+fn graphql_limits_block_ranges() {
+    let tester = GraphQLTester::default();
+    tester.client.add_blocks(150, EachBlockWith::Nothing);
+
+    let (ok, response) = tester.query(r#"{ blocks(from: "0x0") { number } }"#);
+    assert!(ok);
+    assert_eq!(
+        response["errors"][0]["message"],
+        "At most 100 blocks can be queried at once"
+    );
+
+    let (ok, response) = tester.query(r#"{ blocks(from: "0x0", to: "0x63") { number } }"#);
+    assert!(ok);
+    assert_eq!(response["data"]["blocks"].as_array().unwrap().len(), 100);
+}
+
+#[test]
+fn graphql_rejects_deep_queries() {
+    let mut tester = GraphQLTester::default();
+    tester.graphql = tester.graphql.with_limits(Limits {
+        max_depth: 2,
+        max_complexity: 100,
+    });
+
+    let (ok, _) = tester.query("{ block { number } }");
+    assert!(ok);
+
+    let (ok, response) = tester.query("{ block { parent { number } } }");
+    assert!(!ok);
+    assert_eq!(
+        response["errors"][0]["message"],
+        "Query is nested too deeply, at most 2 levels are allowed"
+    );
+}
@@ -20,6 +20,7 @@
 mod debug;
 mod eth;
 mod eth_pubsub;
+mod graphql;
 mod manage_network;
 mod net;
 mod parity;
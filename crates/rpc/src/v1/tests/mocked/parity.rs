@@ -505,6 +505,19 @@ fn rpc_parity_node_kind() {
     assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_parity_ethash_cache_status_without_ethash() {
+    let deps = Dependencies::new();
+    let io = deps.default_client();
+
+    let request =
+        r#"{"jsonrpc": "2.0", "method": "parity_ethashCacheStatus", "params":[], "id": 1}"#;
+    let response =
+        r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"Chain does not use ethash"},"id":1}"#;
+
+    assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_parity_call() {
     let deps = Dependencies::new();
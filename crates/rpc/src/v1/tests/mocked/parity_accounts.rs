@@ -602,6 +602,62 @@ fn derive_key_index() {
     assert_eq!(res, Some(response.into()));
 }
 
+#[test]
+fn derive_key_index_from_path() {
+    let tester = setup();
+    tester
+        .accounts
+        .insert_account(
+            "0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a"
+                .parse()
+                .unwrap(),
+            &"password1".into(),
+        )
+        .expect("account should be inserted ok");
+
+    // same nodes as in `derive_key_index`
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_deriveAddressIndex", "params": ["0xc171033d5cbff7175f29dfd3a63dda3d6f8f385e", "password1", "m/0/1", false ], "id": 3}"#;
+    let response =
+        r#"{"jsonrpc":"2.0","result":"0xcc548e0bb2efe792a920ae0fbf583b13919f274f","id":3}"#;
+    let res = tester.io.handle_request_sync(&request);
+    assert_eq!(res, Some(response.into()));
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_deriveAddressIndex", "params": ["0xc171033d5cbff7175f29dfd3a63dda3d6f8f385e", "password1", "0/1", false ], "id": 3}"#;
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32023,"message":"Could not parse derivation request: {:?}","data":"InvalidPath(\"0/1\")"},"id":3}"#;
+    let res = tester.io.handle_request_sync(&request);
+    assert_eq!(res, Some(response.into()));
+}
+
+#[test]
+fn derive_key_index_hardened_path() {
+    let tester = setup();
+    tester
+        .accounts
+        .insert_account(
+            "0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a0a"
+                .parse()
+                .unwrap(),
+            &"password1".into(),
+        )
+        .expect("account should be inserted ok");
+
+    // hardened nodes of a BIP-32 path are derived at index + 2^31
+    let response =
+        r#"{"jsonrpc":"2.0","result":"0x9d693e863f6182f41ef193fdc2267b874201aab9","id":3}"#;
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_deriveAddressIndex", "params": ["0xc171033d5cbff7175f29dfd3a63dda3d6f8f385e", "password1", "m/44'/60'/0'/0/0", false ], "id": 3}"#;
+    let res = tester.io.handle_request_sync(&request);
+    assert_eq!(res, Some(response.into()));
+
+    // while explicit hard nodes keep deriving at the index given
+    let response =
+        r#"{"jsonrpc":"2.0","result":"0x21b763139204ab32bbf827ffaaf3f8f6bf2d1a7e","id":3}"#;
+
+    let request = r#"{"jsonrpc": "2.0", "method": "parity_deriveAddressIndex", "params": ["0xc171033d5cbff7175f29dfd3a63dda3d6f8f385e", "password1", [{ "type": "hard", "index": 44 }, { "type": "hard", "index": 60 }, { "type": "hard", "index": 0 }, { "type": "soft", "index": 0 }, { "type": "soft", "index": 0 }], false ], "id": 3}"#;
+    let res = tester.io.handle_request_sync(&request);
+    assert_eq!(res, Some(response.into()));
+}
+
 #[test]
 fn should_export_account() {
     // given
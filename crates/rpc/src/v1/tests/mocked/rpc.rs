@@ -14,9 +14,11 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use jsonrpc_core::IoHandler;
+use jsonrpc_core::{IoHandler, Value};
+use serde_json;
 use std::collections::BTreeMap;
 use v1::{Rpc, RpcClient};
+use version::raw_package_info;
 
 fn rpc_client() -> RpcClient {
     let mut modules = BTreeMap::new();
@@ -50,3 +52,20 @@ fn rpc_modules() {
 
     assert_eq!(io.handle_request_sync(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_discover() {
+    let rpc = rpc_client().to_delegate();
+    let mut io = IoHandler::new();
+    io.extend_with(rpc);
+
+    let request = r#"{"jsonrpc": "2.0", "method": "rpc_discover", "params": [], "id": 1}"#;
+    let response: Value = serde_json::from_str(&io.handle_request_sync(request).unwrap()).unwrap();
+
+    assert_eq!(response["result"]["openrpc"], "1.2.6");
+    assert_eq!(response["result"]["info"]["version"], raw_package_info().1);
+    let methods = response["result"]["methods"].as_array().unwrap();
+    assert!(methods
+        .iter()
+        .any(|method| method["name"] == "rpc_discover"));
+}
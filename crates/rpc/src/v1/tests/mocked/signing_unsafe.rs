@@ -18,7 +18,7 @@ use std::{str::FromStr, sync::Arc};
 
 use accounts::AccountProvider;
 use ethcore::client::TestBlockChainClient;
-use ethereum_types::{Address, U256};
+use ethereum_types::{Address, H520, U256};
 use parity_runtime::Runtime;
 use parking_lot::Mutex;
 use types::transaction::{Action, Transaction, TypedTransaction};
@@ -291,3 +291,99 @@ fn rpc_eth_send_transaction_error() {
         Some(response.into())
     );
 }
+
+const TYPED_DATA: &'static str = r#"{
+	"primaryType": "Group",
+	"domain": {
+		"name": "Ether Mail",
+		"version": "1",
+		"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+	},
+	"message": {
+		"name": "Farmers",
+		"members": [
+			{ "name": "Bob", "wallets": ["0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"] },
+			{ "name": "Cow", "wallets": [] }
+		]
+	},
+	"types": {
+		"EIP712Domain": [
+			{ "name": "name", "type": "string" },
+			{ "name": "version", "type": "string" },
+			{ "name": "verifyingContract", "type": "address" }
+		],
+		"Group": [
+			{ "name": "name", "type": "string" },
+			{ "name": "members", "type": "Person[]" }
+		],
+		"Person": [
+			{ "name": "name", "type": "string" },
+			{ "name": "wallets", "type": "address[]" }
+		]
+	}
+}"#;
+
+#[test]
+fn rpc_eth_sign_typed_data_v4() {
+    let tester = EthTester::default();
+    let address = tester.accounts_provider.new_account(&"".into()).unwrap();
+    tester
+        .accounts_provider
+        .unlock_account_permanently(address, "".into())
+        .unwrap();
+
+    let typed_data = serde_json::from_str(TYPED_DATA).unwrap();
+    let hash = eip_712::hash_structured_data(typed_data).unwrap();
+    let signature = tester.accounts_provider.sign(address, None, hash).unwrap();
+
+    // typed data is accepted both as an object and as a JSON string
+    let as_string = serde_json::to_string(TYPED_DATA).unwrap();
+    for typed_data in &[TYPED_DATA, as_string.as_str()] {
+        let request = format!(
+            r#"{{"jsonrpc":"2.0","method":"eth_signTypedData_v4","params":["0x{:x}",{}],"id":1}}"#,
+            address, typed_data
+        );
+        let response = format!(
+            r#"{{"jsonrpc":"2.0","result":"0x{:x}","id":1}}"#,
+            H520(signature.into_electrum())
+        );
+        assert_eq!(tester.io.handle_request_sync(&request), Some(response));
+    }
+
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_signTypedData_v3","params":["0x{:x}",{}],"id":1}}"#,
+        address, TYPED_DATA
+    );
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32058,"message":"Arrays are not supported by typed data v3, found array field 'members'"},"id":1}"#;
+    assert_eq!(
+        tester.io.handle_request_sync(&request),
+        Some(response.into())
+    );
+}
+
+#[test]
+fn rpc_eth_sign_typed_data_chain_id_mismatch() {
+    let tester = EthTester::default();
+    let address = tester.accounts_provider.new_account(&"".into()).unwrap();
+    tester
+        .accounts_provider
+        .unlock_account_permanently(address, "".into())
+        .unwrap();
+
+    let typed_data = TYPED_DATA
+        .replace(r#""version": "1","#, r#""version": "1", "chainId": "0x1","#)
+        .replace(
+            r#"{ "name": "version", "type": "string" },"#,
+            r#"{ "name": "version", "type": "string" },
+			{ "name": "chainId", "type": "uint256" },"#,
+        );
+    let request = format!(
+        r#"{{"jsonrpc":"2.0","method":"eth_signTypedData_v4","params":["0x{:x}",{}],"id":1}}"#,
+        address, typed_data
+    );
+    let response = r#"{"jsonrpc":"2.0","error":{"code":-32058,"message":"Domain chainId 1 does not match the chain id of the node (none)"},"id":1}"#;
+    assert_eq!(
+        tester.io.handle_request_sync(&request),
+        Some(response.into())
+    );
+}
@@ -16,7 +16,7 @@
 
 //! Eth PUB-SUB rpc interface.
 
-use jsonrpc_core::Result;
+use jsonrpc_core::{types::Value, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::{typed, SubscriptionId};
 
@@ -35,7 +35,7 @@ pub trait EthPubSub {
         _: Self::Metadata,
         _: typed::Subscriber<pubsub::Result>,
         _: pubsub::Kind,
-        _: Option<pubsub::Params>,
+        _: Option<Value>,
     );
 
     /// Unsubscribe from existing Eth subscription.
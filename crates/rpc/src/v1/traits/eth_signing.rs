@@ -16,7 +16,7 @@
 
 //! Eth rpc interface.
 
-use jsonrpc_core::BoxFuture;
+use jsonrpc_core::{types::Value, BoxFuture};
 use jsonrpc_derive::rpc;
 
 use ethereum_types::{H160, H256, H520};
@@ -47,4 +47,13 @@ pub trait EthSigning {
         _: Self::Metadata,
         _: TransactionRequest,
     ) -> BoxFuture<RichRawTransaction>;
+
+    /// Signs EIP-712 typed data, supporting arrays and recursive struct types.
+    /// The typed data may be given either as an object or as a JSON string.
+    #[rpc(meta, name = "eth_signTypedData_v4")]
+    fn sign_typed_data_v4(&self, _: Self::Metadata, _: H160, _: Value) -> BoxFuture<H520>;
+
+    /// Signs EIP-712 typed data following the older v3 encoding, which rejects arrays.
+    #[rpc(meta, name = "eth_signTypedData_v3")]
+    fn sign_typed_data_v3(&self, _: Self::Metadata, _: H160, _: Value) -> BoxFuture<H520>;
 }
@@ -24,8 +24,9 @@ use jsonrpc_derive::rpc;
 
 use ethcore::miner::TransactionFilter;
 use v1::types::{
-    BlockNumber, Bytes, CallRequest, ChainStatus, Histogram, LocalTransactionStatus, Peers,
-    Receipt, RecoveredAccount, RichHeader, RpcSettings, Transaction, TransactionStats,
+    BlockNumber, Bytes, CallRequest, ChainStatus, EthashCacheStatus, Histogram,
+    LocalTransactionStatus, Peers, Receipt, RecoveredAccount, RichHeader, RpcSettings, Transaction,
+    TransactionStats,
 };
 
 /// Parity-specific rpc interface.
@@ -191,6 +192,11 @@ pub trait Parity {
     #[rpc(name = "parity_nodeKind")]
     fn node_kind(&self) -> Result<::v1::types::NodeKind>;
 
+    /// Get the state of the ethash light caches and datasets.
+    /// Returns an error if the chain doesn't use ethash.
+    #[rpc(name = "parity_ethashCacheStatus")]
+    fn ethash_cache_status(&self) -> Result<EthashCacheStatus>;
+
     /// Get block header.
     /// Same as `eth_getBlockByNumber` but without uncles and transactions.
     #[rpc(name = "parity_getBlockHeaderByNumber")]
@@ -128,7 +128,7 @@ pub trait ParityAccounts {
     fn derive_key_hash(&self, _: H160, _: Password, _: DeriveHash, _: bool) -> Result<H160>;
 
     /// Derive new address from given account address using
-    /// hierarchical derivation (sequence of 32-bit integer indices).
+    /// hierarchical derivation (sequence of 32-bit integer indices or a BIP-32 path string).
     /// Resulting address can be either saved as a new account (with the same password).
     #[rpc(name = "parity_deriveAddressIndex")]
     fn derive_key_index(
@@ -18,7 +18,7 @@
 
 use std::collections::BTreeMap;
 
-use jsonrpc_core::Result;
+use jsonrpc_core::{Result, Value};
 use jsonrpc_derive::rpc;
 
 /// RPC Interface.
@@ -33,4 +33,8 @@ pub trait Rpc {
     /// @ignore
     #[rpc(name = "rpc_modules")]
     fn rpc_modules(&self) -> Result<BTreeMap<String, String>>;
+
+    /// Returns the OpenRPC document describing available methods.
+    #[rpc(name = "rpc_discover")]
+    fn discover(&self) -> Result<Value>;
 }
@@ -49,7 +49,14 @@ pub struct DeriveHierarchicalItem {
 }
 
 /// Hierarchical (index sequence) request
-pub type DeriveHierarchical = Vec<DeriveHierarchicalItem>;
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum DeriveHierarchical {
+    /// Sequence of nodes
+    Items(Vec<DeriveHierarchicalItem>),
+    /// BIP-32 path, e.g. `m/44'/60'/0'/0/0`
+    Path(String),
+}
 
 /// Generic derivate request
 pub enum Derive {
@@ -76,6 +83,7 @@ impl From<DeriveHash> for Derive {
 #[derive(Debug)]
 pub enum ConvertError {
     IndexOverlfow(u64),
+    InvalidPath(String),
 }
 
 impl Derive {
@@ -83,25 +91,31 @@ impl Derive {
     #[cfg(any(test, feature = "accounts"))]
     pub fn to_derivation(self) -> Result<ethstore::Derivation, ConvertError> {
         Ok(match self {
-            Derive::Hierarchical(drv) => ethstore::Derivation::Hierarchical({
-                let mut members = Vec::<ethstore::IndexDerivation>::new();
-                for h in drv {
-                    if h.index > ::std::u32::MAX as u64 {
-                        return Err(ConvertError::IndexOverlfow(h.index));
+            Derive::Hierarchical(DeriveHierarchical::Path(path)) => path
+                .parse::<ethstore::hd::HdPath>()
+                .map_err(|_| ConvertError::InvalidPath(path.clone()))?
+                .into(),
+            Derive::Hierarchical(DeriveHierarchical::Items(drv)) => {
+                ethstore::Derivation::Hierarchical({
+                    let mut members = Vec::<ethstore::IndexDerivation>::new();
+                    for h in drv {
+                        if h.index > ::std::u32::MAX as u64 {
+                            return Err(ConvertError::IndexOverlfow(h.index));
+                        }
+                        members.push(match h.d_type {
+                            DerivationType::Soft => ethstore::IndexDerivation {
+                                soft: true,
+                                index: h.index as u32,
+                            },
+                            DerivationType::Hard => ethstore::IndexDerivation {
+                                soft: false,
+                                index: h.index as u32,
+                            },
+                        });
                     }
-                    members.push(match h.d_type {
-                        DerivationType::Soft => ethstore::IndexDerivation {
-                            soft: true,
-                            index: h.index as u32,
-                        },
-                        DerivationType::Hard => ethstore::IndexDerivation {
-                            soft: false,
-                            index: h.index as u32,
-                        },
-                    });
-                }
-                members
-            }),
+                    members
+                })
+            }
             Derive::Hash(drv) => match drv.d_type {
                 DerivationType::Soft => ethstore::Derivation::SoftHash(drv.hash.into()),
                 DerivationType::Hard => ethstore::Derivation::HardHash(drv.hash.into()),
@@ -0,0 +1,116 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Ethash cache status.
+
+use ethash;
+use ethereum_types::U64;
+
+/// State of the ethash light caches and datasets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthashCacheStatus {
+    /// Most recent epoch a light cache was requested for.
+    pub current_epoch: Option<U64>,
+    /// Epochs whose light caches are held in memory.
+    pub in_memory: Vec<U64>,
+    /// File being generated in background.
+    pub generating: Option<EthashCacheFile>,
+    /// Files kept in the cache directory.
+    pub on_disk: Vec<EthashCacheFile>,
+}
+
+/// An ethash light cache or dataset file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthashCacheFile {
+    /// Epoch of the file.
+    pub epoch: U64,
+    /// Kind of the file.
+    pub kind: EthashCacheFileKind,
+    /// Size of the file in bytes, if it is complete.
+    pub size: Option<U64>,
+}
+
+/// Kind of an ethash cache file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EthashCacheFileKind {
+    /// Light cache used for verification.
+    Light,
+    /// Full dataset used for mining.
+    Dataset,
+}
+
+impl From<ethash::CacheFileKind> for EthashCacheFileKind {
+    fn from(kind: ethash::CacheFileKind) -> Self {
+        match kind {
+            ethash::CacheFileKind::Light => EthashCacheFileKind::Light,
+            ethash::CacheFileKind::Dataset => EthashCacheFileKind::Dataset,
+        }
+    }
+}
+
+impl From<ethash::CacheStatus> for EthashCacheStatus {
+    fn from(status: ethash::CacheStatus) -> Self {
+        EthashCacheStatus {
+            current_epoch: status.current_epoch.map(Into::into),
+            in_memory: status.in_memory.into_iter().map(Into::into).collect(),
+            generating: status.generating.map(|(epoch, kind)| EthashCacheFile {
+                epoch: epoch.into(),
+                kind: kind.into(),
+                size: None,
+            }),
+            on_disk: status
+                .on_disk
+                .into_iter()
+                .map(|file| EthashCacheFile {
+                    epoch: file.epoch.into(),
+                    kind: file.kind.into(),
+                    size: Some(file.size.into()),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EthashCacheStatus;
+    use ethash::{CacheFileKind, CacheStatus, CachedEpoch};
+    use serde_json;
+
+    #[test]
+    fn test_serialize_ethash_cache_status() {
+        let status: EthashCacheStatus = CacheStatus {
+            current_epoch: Some(1),
+            in_memory: vec![1, 0],
+            generating: Some((2, CacheFileKind::Dataset)),
+            on_disk: vec![CachedEpoch {
+                epoch: 1,
+                kind: CacheFileKind::Light,
+                size: 16907456,
+                path: "cache".into(),
+            }],
+        }
+        .into();
+
+        assert_eq!(
+            serde_json::to_string(&status).unwrap(),
+            r#"{"currentEpoch":"0x1","inMemory":["0x1","0x0"],"generating":{"epoch":"0x2","kind":"dataset","size":null},"onDisk":[{"epoch":"0x1","kind":"light","size":"0x101fcc0"}]}"#
+        );
+    }
+}
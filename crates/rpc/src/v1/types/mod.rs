@@ -30,6 +30,7 @@ pub use self::{
     },
     derivation::{Derive, DeriveHash, DeriveHierarchical},
     eip191::{EIP191Version, PresignedTransaction},
+    ethash::{EthashCacheFile, EthashCacheFileKind, EthashCacheStatus},
     fee_history::EthFeeHistory,
     filter::{Filter, FilterChanges},
     histogram::Histogram,
@@ -63,6 +64,7 @@ mod call_request;
 mod confirmations;
 mod derivation;
 mod eip191;
+mod ethash;
 mod fee_history;
 mod filter;
 mod histogram;
@@ -16,10 +16,23 @@
 
 //! Pub-Sub types.
 
-use ethereum_types::H256;
-use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
-use serde_json::{from_value, Value};
-use v1::types::{Filter, Log, RichHeader};
+use ethcore::trace::{
+    trace::{Action as EthAction, Res as EthRes},
+    LocalizedTrace as EthLocalizedTrace,
+};
+use ethereum_types::{H160, H256, H32, U256};
+use jsonrpc_core::Error as RpcError;
+use serde::{de::DeserializeOwned, Deserialize, Serialize, Serializer};
+use serde_json::{self, from_value, Value};
+use types::transaction::{Action, SignedTransaction};
+use v1::{
+    helpers::errors::{invalid_params, unimplemented},
+    types::{
+        filter::{FilterAddress, VariadicValue},
+        trace::CallType,
+        Filter, LocalizedTrace, Log, RichHeader, Transaction,
+    },
+};
 
 /// Subscription result.
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -30,6 +43,10 @@ pub enum Result {
     Log(Box<Log>),
     /// Transaction hash
     TransactionHash(H256),
+    /// Full transaction
+    Transaction(Box<Transaction>),
+    /// Trace
+    Trace(Box<TraceNotification>),
 }
 
 impl Serialize for Result {
@@ -41,6 +58,8 @@ impl Serialize for Result {
             Result::Header(ref header) => header.serialize(serializer),
             Result::Log(ref log) => log.serialize(serializer),
             Result::TransactionHash(ref hash) => hash.serialize(serializer),
+            Result::Transaction(ref transaction) => transaction.serialize(serializer),
+            Result::Trace(ref trace) => trace.serialize(serializer),
         }
     }
 }
@@ -56,8 +75,12 @@ pub enum Kind {
     Logs,
     /// New Pending Transactions subscription.
     NewPendingTransactions,
+    /// New Pending Transactions subscription with full transaction objects.
+    PendingTransactions,
     /// Node syncing status subscription.
     Syncing,
+    /// Traces of imported blocks subscription.
+    Traces,
 }
 
 /// Subscription kind.
@@ -67,6 +90,10 @@ pub enum Params {
     None,
     /// Log parameters.
     Logs(Filter),
+    /// Pending transaction parameters.
+    Transactions(TransactionFilter),
+    /// Trace parameters.
+    Traces(TraceFilter),
 }
 
 impl Default for Params {
@@ -75,27 +102,154 @@ impl Default for Params {
     }
 }
 
-impl<'a> Deserialize<'a> for Params {
-    fn deserialize<D>(deserializer: D) -> ::std::result::Result<Params, D::Error>
-    where
-        D: Deserializer<'a>,
-    {
-        let v: Value = Deserialize::deserialize(deserializer)?;
+impl Params {
+    /// Parses parameters of a subscription of given kind.
+    pub fn parse(kind: &Kind, params: Option<Value>) -> ::std::result::Result<Params, RpcError> {
+        let params = match params {
+            Some(Value::Null) | None => return Ok(Params::None),
+            Some(params) => params,
+        };
+        let invalid = |e: serde_json::Error| {
+            RpcError::invalid_params(format!("Invalid Pub-Sub parameters: {}", e))
+        };
 
-        if v.is_null() {
-            return Ok(Params::None);
+        match *kind {
+            Kind::NewHeads => Err(invalid_params("newHeads", "Expected no parameters.")),
+            Kind::Logs => from_value(params).map(Params::Logs).map_err(invalid),
+            Kind::NewPendingTransactions => Err(invalid_params(
+                "newPendingTransactions",
+                "Expected no parameters.",
+            )),
+            Kind::PendingTransactions => from_value(params)
+                .map(Params::Transactions)
+                .map_err(invalid),
+            Kind::Syncing => Err(unimplemented(None)),
+            Kind::Traces => from_value(params).map(Params::Traces).map_err(invalid),
         }
+    }
+}
+
+/// Pending transactions subscription filter.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionFilter {
+    /// Sender
+    pub from: Option<FilterAddress>,
+    /// Recipient, contract creations never match
+    pub to: Option<FilterAddress>,
+    /// First four bytes of the call data
+    pub method_selector: Option<VariadicValue<H32>>,
+    /// Minimal effective gas price
+    pub min_gas_price: Option<U256>,
+}
+
+impl TransactionFilter {
+    /// Returns true if the transaction matches the filter.
+    ///
+    /// `base_fee` is the base fee of the block the transaction is expected to be included in
+    /// and is used to compute the effective gas price.
+    pub fn matches(&self, tx: &SignedTransaction, base_fee: Option<U256>) -> bool {
+        let to = match tx.tx().action {
+            Action::Call(ref to) => Some(to),
+            Action::Create => None,
+        };
+        let selector = match tx.tx().data.get(..4) {
+            Some(selector) if to.is_some() => Some(H32::from_slice(selector)),
+            _ => None,
+        };
+
+        matches_variadic(&self.from, Some(&tx.sender()))
+            && matches_variadic(&self.to, to)
+            && matches_variadic(&self.method_selector, selector.as_ref())
+            && self
+                .min_gas_price
+                .map_or(true, |min| tx.effective_gas_price(base_fee) >= min)
+    }
+}
+
+/// Traces subscription filter.
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceFilter {
+    /// From address
+    pub from_address: Option<Vec<H160>>,
+    /// To address
+    pub to_address: Option<Vec<H160>>,
+    /// Call types, only calls match if present
+    pub call_type: Option<Vec<CallType>>,
+}
+
+impl TraceFilter {
+    /// Returns true if the trace matches the filter.
+    ///
+    /// Addresses are matched like in `trace_filter`: creations are matched by the created
+    /// contract address and rewards by the author.
+    pub fn matches(&self, trace: &EthLocalizedTrace) -> bool {
+        let (from, to, call_type) = match trace.action {
+            EthAction::Call(ref call) => (Some(call.from), Some(call.to), Some(&call.call_type)),
+            EthAction::Create(ref create) => {
+                let to = match trace.result {
+                    EthRes::Create(ref result) => Some(result.address),
+                    _ => None,
+                };
+                (Some(create.from), to, None)
+            }
+            EthAction::Suicide(ref suicide) => {
+                (Some(suicide.address), Some(suicide.refund_address), None)
+            }
+            EthAction::Reward(ref reward) => (None, Some(reward.author), None),
+        };
 
-        from_value(v.clone())
-            .map(Params::Logs)
-            .map_err(|e| D::Error::custom(format!("Invalid Pub-Sub parameters: {}", e)))
+        matches_addresses(&self.from_address, from)
+            && matches_addresses(&self.to_address, to)
+            && self.call_type.as_ref().map_or(true, |types| {
+                call_type.map_or(false, |t| types.contains(&t.clone().into()))
+            })
+    }
+}
+
+/// Trace notification.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TraceNotification {
+    /// Trace
+    #[serde(flatten)]
+    pub trace: LocalizedTrace,
+    /// Set if the trace was removed from the chain due to a reorganization.
+    pub removed: bool,
+}
+
+fn matches_addresses(filter: &Option<Vec<H160>>, address: Option<H160>) -> bool {
+    match *filter {
+        Some(ref addresses) if !addresses.is_empty() => {
+            address.map_or(false, |a| addresses.contains(&a))
+        }
+        _ => true,
+    }
+}
+
+fn matches_variadic<T>(filter: &Option<VariadicValue<T>>, value: Option<&T>) -> bool
+where
+    T: DeserializeOwned + PartialEq,
+{
+    match *filter {
+        None | Some(VariadicValue::Null) => true,
+        Some(VariadicValue::Single(ref expected)) => value == Some(expected),
+        Some(VariadicValue::Multiple(ref expected)) => {
+            value.map_or(false, |v| expected.contains(v))
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Kind, Params, Result};
+    use super::{Kind, Params, Result, TransactionFilter};
+    use ethereum_types::{H160, U256};
     use serde_json;
+    use types::transaction::{
+        AccessListTx, Action, EIP1559TransactionTx, Transaction, TypedTransaction,
+    };
     use v1::types::{filter::VariadicValue, Filter, Header, RichHeader};
 
     #[test]
@@ -112,20 +266,29 @@ mod tests {
             serde_json::from_str::<Kind>(r#""newPendingTransactions""#).unwrap(),
             Kind::NewPendingTransactions
         );
+        assert_eq!(
+            serde_json::from_str::<Kind>(r#""pendingTransactions""#).unwrap(),
+            Kind::PendingTransactions
+        );
         assert_eq!(
             serde_json::from_str::<Kind>(r#""syncing""#).unwrap(),
             Kind::Syncing
         );
     }
 
+    fn parse(kind: Kind, params: &str) -> ::std::result::Result<Params, RpcError> {
+        Params::parse(&kind, Some(serde_json::from_str(params).unwrap()))
+    }
+
     #[test]
     fn should_deserialize_logs() {
-        let none = serde_json::from_str::<Params>(r#"null"#).unwrap();
+        let none = parse(Kind::Logs, r#"null"#).unwrap();
         assert_eq!(none, Params::None);
 
-        let logs1 = serde_json::from_str::<Params>(r#"{}"#).unwrap();
-        let logs2 = serde_json::from_str::<Params>(r#"{"limit":10}"#).unwrap();
-        let logs3 = serde_json::from_str::<Params>(
+        let logs1 = parse(Kind::Logs, r#"{}"#).unwrap();
+        let logs2 = parse(Kind::Logs, r#"{"limit":10}"#).unwrap();
+        let logs3 = parse(
+            Kind::Logs,
             r#"{"topics":["0x000000000000000000000000a94f5374fce5edbc8e2a8697c15331677e6ebf0b"]}"#,
         )
         .unwrap();
@@ -168,6 +331,83 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_deserialize_transactions() {
+        let params = parse(
+            Kind::PendingTransactions,
+            r#"{"from":"0x0000000000000000000000000000000000000001","methodSelector":["0xa9059cbb","0x095ea7b3"],"minGasPrice":"0x3b9aca00"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            params,
+            Params::Transactions(TransactionFilter {
+                from: Some(VariadicValue::Single(H160::from_low_u64_be(1))),
+                to: None,
+                method_selector: Some(VariadicValue::Multiple(vec![
+                    "a9059cbb".parse().unwrap(),
+                    "095ea7b3".parse().unwrap(),
+                ])),
+                min_gas_price: Some(1_000_000_000.into()),
+            })
+        );
+    }
+
+    #[test]
+    fn should_report_errors_of_the_subscription_kind() {
+        let logs = parse(Kind::Logs, r#"{"value":"0x1"}"#).unwrap_err();
+        assert!(logs.message.contains("expected one of `fromBlock`"));
+
+        let transactions = parse(Kind::PendingTransactions, r#"{"value":"0x1"}"#).unwrap_err();
+        assert!(transactions.message.contains("expected one of `from`"));
+
+        assert!(parse(Kind::NewHeads, r#"{}"#).is_err());
+        assert_eq!(parse(Kind::NewHeads, r#"null"#).unwrap(), Params::None);
+    }
+
+    #[test]
+    fn should_match_transactions() {
+        let sender = H160::from_low_u64_be(1);
+        let to = H160::from_low_u64_be(2);
+        let call = TypedTransaction::EIP1559Transaction(EIP1559TransactionTx {
+            transaction: AccessListTx::new(
+                Transaction {
+                    action: Action::Call(to),
+                    data: vec![0xa9, 0x05, 0x9c, 0xbb, 0x00],
+                    gas_price: 20.into(),
+                    ..Default::default()
+                },
+                vec![],
+            ),
+            max_priority_fee_per_gas: 2.into(),
+        })
+        .fake_sign(sender);
+        let create = TypedTransaction::Legacy(Transaction {
+            action: Action::Create,
+            data: vec![0xa9, 0x05, 0x9c, 0xbb, 0x00],
+            gas_price: 10.into(),
+            ..Default::default()
+        })
+        .fake_sign(sender);
+
+        let filter = TransactionFilter {
+            from: Some(VariadicValue::Single(sender)),
+            to: Some(VariadicValue::Single(to)),
+            method_selector: Some(VariadicValue::Single("a9059cbb".parse().unwrap())),
+            min_gas_price: Some(10.into()),
+        };
+        assert!(filter.matches(&call, Some(U256::from(8))));
+        assert!(!filter.matches(&call, Some(U256::from(7))));
+        assert!(!filter.matches(&create, None));
+
+        let filter = TransactionFilter {
+            method_selector: Some(VariadicValue::Single("a9059cbb".parse().unwrap())),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&create, None));
+        assert!(TransactionFilter::default().matches(&create, None));
+    }
+
     #[test]
     fn should_serialize_header() {
         let header = Result::Header(Box::new(RichHeader {
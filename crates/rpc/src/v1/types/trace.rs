@@ -22,7 +22,7 @@ use ethcore::{
     trace::{trace, FlatTrace, LocalizedTrace as EthLocalizedTrace, TraceError},
 };
 use ethereum_types::{H160, H256, U256};
-use serde::{ser::SerializeStruct, Serialize, Serializer};
+use serde::{ser::SerializeStruct, Deserialize, Serialize, Serializer};
 use types::{account_diff, state_diff};
 use vm;
 
@@ -235,7 +235,7 @@ impl From<state_diff::StateDiff> for StateDiff {
 }
 
 /// Create response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct Create {
     /// Sender
     from: H160,
@@ -259,7 +259,7 @@ impl From<trace::Create> for Create {
 }
 
 /// Call type.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum CallType {
     /// None
@@ -287,7 +287,7 @@ impl From<vm::CallType> for CallType {
 }
 
 /// Call response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Call {
     /// Sender
@@ -318,7 +318,7 @@ impl From<trace::Call> for Call {
 }
 
 /// Reward type.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub enum RewardType {
     /// Block
@@ -343,7 +343,7 @@ impl From<trace::RewardType> for RewardType {
 }
 
 /// Reward action
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Reward {
     /// Author's address.
@@ -365,7 +365,7 @@ impl From<trace::Reward> for Reward {
 }
 
 /// Suicide
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Suicide {
     /// Address.
@@ -387,7 +387,7 @@ impl From<trace::Suicide> for Suicide {
 }
 
 /// Action
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Action {
     /// Call
     Call(Call),
@@ -411,7 +411,7 @@ impl From<trace::Action> for Action {
 }
 
 /// Call Result
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CallResult {
     /// Gas used
@@ -430,7 +430,7 @@ impl From<trace::CallResult> for CallResult {
 }
 
 /// Craete Result
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateResult {
     /// Gas used
@@ -452,7 +452,7 @@ impl From<trace::CreateResult> for CreateResult {
 }
 
 /// Response
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Res {
     /// Call
     Call(CallResult),
@@ -479,7 +479,7 @@ impl From<trace::Res> for Res {
 }
 
 /// Trace
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct LocalizedTrace {
     /// Action
     action: Action,
@@ -15,9 +15,11 @@
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
 //! EIP712 structs
+use crate::encode::parse_integer;
 use ethereum_types::{Address, H256, U256};
 use lazy_static::lazy_static;
 use regex::Regex;
+use serde::de::{self, Deserialize, Deserializer};
 use serde_json::Value;
 use std::collections::HashMap;
 use validator::{Validate, ValidationErrors};
@@ -34,13 +36,32 @@ lazy_static! {
 #[serde(deny_unknown_fields)]
 #[derive(Deserialize, Serialize, Validate, Debug, Clone)]
 pub(crate) struct EIP712Domain {
-    pub(crate) name: String,
-    pub(crate) version: String,
-    pub(crate) chain_id: U256,
-    pub(crate) verifying_contract: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) version: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_chain_id")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) chain_id: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) verifying_contract: Option<Address>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) salt: Option<H256>,
 }
+
+/// wallets send the chain id either as a number, a decimal string or a hex string
+fn deserialize_chain_id<'de, D>(deserializer: D) -> Result<Option<U256>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => parse_integer(&value, false)
+            .map(Some)
+            .ok_or_else(|| de::Error::custom(format!("invalid chainId {}", value))),
+    }
+}
+
 /// EIP-712 struct
 #[serde(rename_all = "camelCase")]
 #[serde(deny_unknown_fields)]
@@ -52,6 +73,13 @@ pub struct EIP712 {
     pub(crate) domain: EIP712Domain,
 }
 
+impl EIP712 {
+    /// chain id the typed data is bound to, if its domain specifies one
+    pub fn chain_id(&self) -> Option<U256> {
+        self.domain.chain_id
+    }
+}
+
 impl Validate for EIP712 {
     fn validate(&self) -> Result<(), ValidationErrors> {
         for field_types in self.types.values() {
@@ -30,6 +30,24 @@ use serde_json::{to_value, Value};
 use std::{collections::HashSet, str::FromStr};
 use validator::Validate;
 
+/// Typed data encoding rules, following the `eth_signTypedData_v*` methods implemented by MetaMask
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypedDataVersion {
+    /// `eth_signTypedData_v3`: arrays are not supported, fields missing from the message are skipped
+    V3,
+    /// `eth_signTypedData_v4`: arrays are supported, missing structs are encoded as zero
+    V4,
+}
+
+/// fields allowed in the `EIP712Domain` struct along with their types
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+
 fn check_hex(string: &str) -> Result<()> {
     if string.len() >= 2 && &string[..2] == "0x" {
         return Ok(());
@@ -66,7 +84,7 @@ fn build_dependencies<'a>(
                     &field.type_
                 };
                 // seen this type before? or not a custom type skip
-                if !deps.contains(field_type) || message_types.contains_key(field_type) {
+                if !deps.contains(field_type) && message_types.contains_key(field_type) {
                     types.insert(field_type);
                 }
             }
@@ -107,13 +125,112 @@ fn type_hash(message_type: &str, typed_data: &MessageTypes) -> Result<H256> {
     Ok(keccak(encode_type(message_type, typed_data)?))
 }
 
+/// parses an integer given either as a JSON number, a decimal string or a 0x-prefixed hex string,
+/// negative values are returned in two's complement and only accepted if `signed` is set
+pub(crate) fn parse_integer(value: &Value, signed: bool) -> Option<U256> {
+    let (negative, abs) = match value {
+        Value::Number(number) => match number.as_u64() {
+            Some(number) => (false, U256::from(number)),
+            None => (true, U256::from(number.as_i64()?.wrapping_neg() as u64)),
+        },
+        Value::String(string) => {
+            let (negative, string) = match string.strip_prefix('-') {
+                Some(string) => (true, string),
+                None => (false, &string[..]),
+            };
+            let abs = match string.strip_prefix("0x") {
+                Some(hex) if !hex.is_empty() && hex.len() <= 64 => U256::from_str(hex).ok()?,
+                Some(_) => return None,
+                None => U256::from_dec_str(string).ok()?,
+            };
+            (negative, abs)
+        }
+        _ => return None,
+    };
+
+    match (negative, signed) {
+        (false, _) => Some(abs),
+        (true, true) => Some((!abs).overflowing_add(U256::one()).0),
+        (true, false) => None,
+    }
+}
+
+fn check_domain(typed_data: &EIP712) -> Result<()> {
+    let domain_type = typed_data
+        .types
+        .get("EIP712Domain")
+        .ok_or_else(|| ErrorKind::InvalidDomain("the EIP712Domain type is missing".into()))?;
+
+    for field in domain_type {
+        match DOMAIN_FIELDS.iter().find(|(name, _)| *name == field.name) {
+            Some((_, type_)) if *type_ == field.type_ => (),
+            Some((name, type_)) => {
+                return Err(ErrorKind::InvalidDomain(format!(
+                    "the field '{}' should be of type '{}'",
+                    name, type_
+                )))?
+            }
+            None => {
+                return Err(ErrorKind::InvalidDomain(format!(
+                    "unknown field '{}'",
+                    field.name
+                )))?
+            }
+        }
+    }
+
+    let domain =
+        to_value(&typed_data.domain).expect("EIP712Domain serialization is infallible; qed");
+    if let Some(name) = domain
+        .as_object()
+        .expect("EIP712Domain is serialized as a map; qed")
+        .keys()
+        .find(|name| domain_type.iter().all(|field| field.name != **name))
+    {
+        return Err(ErrorKind::InvalidDomain(format!(
+            "the field '{}' is not declared in the EIP712Domain type",
+            name
+        )))?;
+    }
+
+    Ok(())
+}
+
+/// encodes a struct member or an array item,
+/// v4 encodes absent structs as zero instead of failing
+fn encode_field(
+    field_type: &Type,
+    message_types: &MessageTypes,
+    value: &Value,
+    field_name: Option<&str>,
+    version: Option<TypedDataVersion>,
+) -> Result<Vec<u8>> {
+    match field_type {
+        Type::Custom(ref ident)
+            if version == Some(TypedDataVersion::V4)
+                && value.is_null()
+                && message_types.contains_key(&*ident) =>
+        {
+            Ok(H256::zero().as_ref().to_vec())
+        }
+        _ => encode_data(field_type, message_types, value, field_name, version),
+    }
+}
+
 fn encode_data(
     message_type: &Type,
     message_types: &MessageTypes,
     value: &Value,
     field_name: Option<&str>,
+    version: Option<TypedDataVersion>,
 ) -> Result<Vec<u8>> {
     let encoded = match message_type {
+        Type::Array { .. } if version == Some(TypedDataVersion::V3) => {
+            return Err(ErrorKind::UnsupportedArray(
+                field_name.unwrap_or("").to_owned(),
+            ))?;
+        }
+
         Type::Array { inner, length } => {
             let mut items = vec![];
             let values = value.as_array().ok_or(serde_error("array", field_name))?;
@@ -130,7 +247,7 @@ fn encode_data(
             }
 
             for item in values {
-                let mut encoded = encode_data(&*inner, &message_types, item, field_name)?;
+                let mut encoded = encode_field(&*inner, &message_types, item, field_name, version)?;
                 items.append(&mut encoded);
             }
 
@@ -146,8 +263,12 @@ fn encode_data(
                 .expect("Already checked in match guard; qed")
             {
                 let value = &value[&field.name];
+                if version == Some(TypedDataVersion::V3) && value.is_null() {
+                    continue;
+                }
                 let type_ = parse_type(&*field.type_)?;
-                let mut encoded = encode_data(&type_, &message_types, &value, Some(&*field.name))?;
+                let mut encoded =
+                    encode_field(&type_, &message_types, &value, Some(&*field.name), version)?;
                 tokens.append(&mut encoded);
             }
 
@@ -199,7 +320,7 @@ fn encode_data(
             encode(&[EthAbiToken::Address(address)])
         }
 
-        Type::Uint | Type::Int => {
+        Type::Uint | Type::Int if version.is_none() => {
             let string = value.as_str().ok_or(serde_error("int/uint", field_name))?;
 
             check_hex(&string)?;
@@ -215,6 +336,18 @@ fn encode_data(
             encode(&[token])
         }
 
+        Type::Uint | Type::Int => {
+            let signed = *message_type == Type::Int;
+            let uint = parse_integer(value, signed).ok_or(serde_error("int/uint", field_name))?;
+
+            let token = if signed {
+                EthAbiToken::Int(uint)
+            } else {
+                EthAbiToken::Uint(uint)
+            };
+            encode(&[token])
+        }
+
         _ => {
             return Err(ErrorKind::UnknownType(
                 format!("{}", field_name.unwrap_or("")),
@@ -229,25 +362,47 @@ fn encode_data(
 
 /// encodes and hashes the given EIP712 struct
 pub fn hash_structured_data(typed_data: EIP712) -> Result<H256> {
+    hash(typed_data, None)
+}
+
+/// encodes and hashes the given EIP712 struct following the given version of `eth_signTypedData`
+pub fn hash_structured_data_with_version(
+    typed_data: EIP712,
+    version: TypedDataVersion,
+) -> Result<H256> {
+    hash(typed_data, Some(version))
+}
+
+/// `version` selects the rules of `eth_signTypedData_v*`,
+/// the original `eth_signTypedData` ones are used without it
+fn hash(typed_data: EIP712, version: Option<TypedDataVersion>) -> Result<H256> {
     // validate input
     typed_data.validate()?;
+    if version == Some(TypedDataVersion::V4) {
+        check_domain(&typed_data)?;
+    }
     // EIP-191 compliant
     let prefix = (b"\x19\x01").to_vec();
     let domain = to_value(&typed_data.domain).unwrap();
-    let (domain_hash, data_hash) = (
-        encode_data(
-            &Type::Custom("EIP712Domain".into()),
-            &typed_data.types,
-            &domain,
-            None,
-        )?,
+    let domain_hash = encode_data(
+        &Type::Custom("EIP712Domain".into()),
+        &typed_data.types,
+        &domain,
+        None,
+        version,
+    )?;
+    // signing the domain alone leaves out the message hash
+    let data_hash = if version.is_some() && typed_data.primary_type == "EIP712Domain" {
+        vec![]
+    } else {
         encode_data(
             &Type::Custom(typed_data.primary_type),
             &typed_data.types,
             &typed_data.message,
             None,
-        )?,
-    );
+            version,
+        )?
+    };
     let concat = [&prefix[..], &domain_hash[..], &data_hash[..]].concat();
     Ok(keccak(concat))
 }
@@ -633,4 +788,191 @@ mod tests {
             "cd8b34cd09c541cfc0a2fcd147e47809b98b335649c2aa700db0b0c4501a02a0",
         );
     }
+
+    const FAMILY_TREE: &'static str = r#"{
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" }
+			],
+			"Person": [
+				{ "name": "name", "type": "string" },
+				{ "name": "mother", "type": "Person" },
+				{ "name": "father", "type": "Person" }
+			]
+		},
+		"domain": {
+			"name": "Family Tree",
+			"version": "1",
+			"chainId": 1,
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+		},
+		"primaryType": "Person",
+		"message": {
+			"name": "Jon",
+			"mother": {
+				"name": "Lyanna",
+				"father": { "name": "Rickard" }
+			},
+			"father": {
+				"name": "Rhaegar",
+				"father": { "name": "Aeris II" }
+			}
+		}
+	}"#;
+
+    #[test]
+    fn test_recursive_types() {
+        let typed_data = from_str::<EIP712>(FAMILY_TREE).expect("alas error!");
+        let v4 = hash_structured_data_with_version(typed_data.clone(), TypedDataVersion::V4)
+            .expect("alas error!");
+        let v3 = hash_structured_data_with_version(typed_data, TypedDataVersion::V3)
+            .expect("alas error!");
+
+        assert_eq!(
+            &format!("{:x}", v4)[..],
+            "807773b9faa9879d4971b43856c4d60c2da15c6f8c062bd9d33afefb756de19c",
+        );
+        assert_eq!(
+            &format!("{:x}", v3)[..],
+            "0f11d777f9a8098d88e3869334a8f1404fd942062c5037045bae4e3b457007bd",
+        );
+    }
+
+    #[test]
+    fn test_v3_rejects_arrays() {
+        const TEST: &'static str = r#"{
+		"primaryType": "Group",
+		"domain": {
+			"name": "Ether Mail",
+			"version": "1",
+			"chainId": "0x1",
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC"
+		},
+		"message": {
+			"name": "Farmers",
+			"members": ["0xbBbBBBBbbBBBbbbBbbBbbbbBBbBbbbbBbBbbBBbB"]
+		},
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" }
+			],
+			"Group": [
+				{ "name": "name", "type": "string" },
+				{ "name": "members", "type": "address[]" }
+			]
+		}
+	}"#;
+
+        let typed_data = from_str::<EIP712>(TEST).expect("alas error!");
+        assert_eq!(
+            hash_structured_data_with_version(typed_data, TypedDataVersion::V3)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::UnsupportedArray("members".into())
+        )
+    }
+
+    const TRANSFER: &'static str = r#"{
+		"types": {
+			"EIP712Domain": [
+				{ "name": "name", "type": "string" },
+				{ "name": "version", "type": "string" },
+				{ "name": "chainId", "type": "uint256" },
+				{ "name": "verifyingContract", "type": "address" },
+				{ "name": "salt", "type": "bytes32" }
+			],
+			"Transfer": [
+				{ "name": "amount", "type": "uint256" },
+				{ "name": "delta", "type": "int256" },
+				{ "name": "memo", "type": "bytes" }
+			]
+		},
+		"domain": {
+			"name": "Ether Mail",
+			"version": "1",
+			"chainId": 1,
+			"verifyingContract": "0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC",
+			"salt": "0xf2d857f4a3edcb9b78b4d503bfe733db1e3f6cdc2b7971ee739626c97e86a558"
+		},
+		"primaryType": "Transfer",
+		"message": {
+			"amount": "1000000000000000000",
+			"delta": -42,
+			"memo": "0xdeadbeef"
+		}
+	}"#;
+
+    #[test]
+    fn test_domain_salt_and_integer_formats() {
+        let typed_data = from_str::<EIP712>(TRANSFER).expect("alas error!");
+        assert_eq!(typed_data.chain_id(), Some(U256::from(1)));
+        let hash = hash_structured_data_with_version(typed_data, TypedDataVersion::V4)
+            .expect("alas error!");
+        assert_eq!(
+            &format!("{:x}", hash)[..],
+            "f67ad225424996e3aea94fc423ca65bd92d39396f61676b03440e91824092a59",
+        );
+
+        // the same values given as hex strings
+        let hex = TRANSFER
+            .replace(r#""1000000000000000000""#, r#""0xde0b6b3a7640000""#)
+            .replace("-42", r#""-0x2a""#);
+        let typed_data = from_str::<EIP712>(&hex).expect("alas error!");
+        let hash = hash_structured_data_with_version(typed_data, TypedDataVersion::V4)
+            .expect("alas error!");
+        assert_eq!(
+            &format!("{:x}", hash)[..],
+            "f67ad225424996e3aea94fc423ca65bd92d39396f61676b03440e91824092a59",
+        );
+    }
+
+    #[test]
+    fn test_parse_integer() {
+        use serde_json::json;
+
+        assert_eq!(parse_integer(&json!(42), false), Some(U256::from(42)));
+        assert_eq!(parse_integer(&json!("42"), false), Some(U256::from(42)));
+        assert_eq!(parse_integer(&json!("0x2a"), false), Some(U256::from(42)));
+        assert_eq!(parse_integer(&json!(-1), true), Some(U256::max_value()));
+        assert_eq!(parse_integer(&json!(-1), false), None);
+        assert_eq!(parse_integer(&json!("forty-two"), false), None);
+    }
+
+    #[test]
+    fn test_domain_validation() {
+        let undeclared = TRANSFER.replace(
+            r#",
+				{ "name": "salt", "type": "bytes32" }"#,
+            "",
+        );
+        let typed_data = from_str::<EIP712>(&undeclared).expect("alas error!");
+        assert_eq!(
+            hash_structured_data_with_version(typed_data.clone(), TypedDataVersion::V4)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidDomain(
+                "the field 'salt' is not declared in the EIP712Domain type".into()
+            )
+        );
+        // only v4 validates the domain
+        assert!(hash_structured_data_with_version(typed_data, TypedDataVersion::V3).is_ok());
+
+        let wrong_type = TRANSFER.replace(
+            r#"{ "name": "chainId", "type": "uint256" }"#,
+            r#"{ "name": "chainId", "type": "string" }"#,
+        );
+        let typed_data = from_str::<EIP712>(&wrong_type).expect("alas error!");
+        assert_eq!(
+            hash_structured_data_with_version(typed_data, TypedDataVersion::V4)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::InvalidDomain("the field 'chainId' should be of type 'uint256'".into())
+        );
+    }
 }
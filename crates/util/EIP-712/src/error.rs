@@ -65,6 +65,15 @@ pub enum ErrorKind {
     /// Typed array length doesn't fit into a u64
     #[fail(display = "Attempted to declare fixed size with length {}", _0)]
     InvalidArraySize(String),
+    /// the EIP712Domain type or value doesn't follow the EIP-712 definition
+    #[fail(display = "Invalid EIP712Domain: {}", _0)]
+    InvalidDomain(String),
+    /// arrays were used with a version of typed data that doesn't support them
+    #[fail(
+        display = "Arrays are not supported by typed data v3, found array field '{}'",
+        _0
+    )]
+    UnsupportedArray(String),
 }
 
 pub(crate) fn serde_error(expected: &str, field: Option<&str>) -> ErrorKind {
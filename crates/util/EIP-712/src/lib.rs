@@ -170,7 +170,9 @@ mod parser;
 
 /// EIP712 struct
 pub use crate::eip712::EIP712;
-/// the EIP-712 encoding function
-pub use crate::encode::hash_structured_data;
+/// the EIP-712 encoding functions
+pub use crate::encode::{
+    hash_structured_data, hash_structured_data_with_version, TypedDataVersion,
+};
 /// encoding Error types
 pub use crate::error::{Error, ErrorKind};
@@ -264,6 +264,11 @@ impl DatabaseDirectories {
     pub fn network_path(&self) -> PathBuf {
         self.spec_root_path().join("network")
     }
+
+    /// Get the path for the engine signer slashing-protection database.
+    pub fn engine_signer_protection_path(&self) -> PathBuf {
+        self.spec_root_path().join("engine_signer_protection")
+    }
 }
 
 fn default_path(t: AppDataType) -> Option<PathBuf> {
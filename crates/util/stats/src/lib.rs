@@ -17,8 +17,13 @@
 //! Statistical functions and helpers.
 
 use std::{
+    collections::HashMap,
     iter::FromIterator,
     ops::{Add, Deref, Div, Sub},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Instant,
 };
 
@@ -67,6 +72,82 @@ impl PrometheusRegistry {
             .expect("prometheus identifiers must be are unique");
     }
 
+    /// Adds a new prometheus counter family with one counter per value of `label`
+    pub fn register_counter_family(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &[(&str, i64)],
+    ) {
+        let name = format!("{}{}", self.prefix, name);
+        let c =
+            prometheus::IntCounterVec::new(prometheus::Opts::new(name.as_str(), help), &[label])
+                .expect("name, help and label must be non-empty");
+        for (label_value, value) in values {
+            c.with_label_values(&[label_value]).inc_by(*value);
+        }
+        self.registry
+            .register(Box::new(c))
+            .expect("prometheus identifiers must be unique");
+    }
+
+    /// Adds a new prometheus gauge family with one gauge per value of `label`
+    pub fn register_gauge_family(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &[(&str, i64)],
+    ) {
+        let name = format!("{}{}", self.prefix, name);
+        let g = prometheus::IntGaugeVec::new(prometheus::Opts::new(name.as_str(), help), &[label])
+            .expect("name, help and label must be non-empty");
+        for (label_value, value) in values {
+            g.with_label_values(&[label_value]).set(*value);
+        }
+        self.registry
+            .register(Box::new(g))
+            .expect("prometheus identifiers must be unique");
+    }
+
+    /// Adds a new prometheus histogram family with one histogram per value of `label`
+    pub fn register_histogram_family(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &[(&str, &PrometheusHistogram)],
+    ) {
+        let name = format!("{}{}", self.prefix, name);
+        let desc = prometheus::core::Desc::new(
+            name.clone(),
+            help.into(),
+            vec![label.into()],
+            HashMap::new(),
+        )
+        .expect("name, help and label must be valid");
+
+        let mut family = prometheus::proto::MetricFamily::default();
+        family.set_name(name);
+        family.set_help(help.into());
+        family.set_field_type(prometheus::proto::MetricType::HISTOGRAM);
+        for (label_value, histogram) in values {
+            let mut label_pair = prometheus::proto::LabelPair::default();
+            label_pair.set_name(label.into());
+            label_pair.set_value(label_value.to_string());
+
+            let mut metric = prometheus::proto::Metric::default();
+            metric.mut_label().push(label_pair);
+            metric.set_histogram(histogram.to_proto());
+            family.mut_metric().push(metric);
+        }
+
+        self.registry
+            .register(Box::new(HistogramFamily { desc, family }))
+            .expect("prometheus identifiers must be unique");
+    }
+
     /// Adds a new prometheus counter with the time spent in running the specified function
     pub fn register_optime<F: Fn() -> T, T>(&mut self, name: &str, f: &F) -> T {
         let start = Instant::now();
@@ -86,6 +167,101 @@ pub trait PrometheusMetrics {
     fn prometheus_metrics(&self, registry: &mut PrometheusRegistry);
 }
 
+impl<T: PrometheusMetrics + ?Sized> PrometheusMetrics for Arc<T> {
+    fn prometheus_metrics(&self, registry: &mut PrometheusRegistry) {
+        (**self).prometheus_metrics(registry)
+    }
+}
+
+/// Observations counted in buckets with fixed upper bounds, exported as a prometheus histogram.
+///
+/// Observations are lock-free, so a histogram can be shared between threads.
+#[derive(Debug)]
+pub struct PrometheusHistogram {
+    bounds: Vec<f64>,
+    counts: Vec<AtomicU64>,
+    /// Bits of the `f64` sum of observations.
+    sum: AtomicU64,
+}
+
+impl PrometheusHistogram {
+    /// Create an empty histogram with given (sorted) bucket upper bounds.
+    /// Observations above the last bound are only included in the total count.
+    pub fn new(bounds: &[f64]) -> Self {
+        PrometheusHistogram {
+            bounds: bounds.to_vec(),
+            counts: (0..=bounds.len()).map(|_| AtomicU64::new(0)).collect(),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    /// Count an observation.
+    pub fn observe(&self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket].fetch_add(1, Ordering::Relaxed);
+
+        let mut sum = self.sum.load(Ordering::Relaxed);
+        loop {
+            let new = (f64::from_bits(sum) + value).to_bits();
+            match self
+                .sum
+                .compare_exchange_weak(sum, new, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => break,
+                Err(current) => sum = current,
+            }
+        }
+    }
+
+    /// Number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts
+            .iter()
+            .map(|count| count.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// Sum of observations.
+    pub fn sum(&self) -> f64 {
+        f64::from_bits(self.sum.load(Ordering::Relaxed))
+    }
+
+    fn to_proto(&self) -> prometheus::proto::Histogram {
+        let mut histogram = prometheus::proto::Histogram::default();
+        histogram.set_sample_count(self.count());
+        histogram.set_sample_sum(self.sum());
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count.load(Ordering::Relaxed);
+            let mut bucket = prometheus::proto::Bucket::default();
+            bucket.set_upper_bound(*bound);
+            bucket.set_cumulative_count(cumulative);
+            histogram.mut_bucket().push(bucket);
+        }
+        histogram
+    }
+}
+
+/// Snapshot of a histogram family registered with the prometheus registry.
+struct HistogramFamily {
+    desc: prometheus::core::Desc,
+    family: prometheus::proto::MetricFamily,
+}
+
+impl prometheus::core::Collector for HistogramFamily {
+    fn desc(&self) -> Vec<&prometheus::core::Desc> {
+        vec![&self.desc]
+    }
+
+    fn collect(&self) -> Vec<prometheus::proto::MetricFamily> {
+        vec![self.family.clone()]
+    }
+}
+
 /// Sorted corpus of data.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Corpus<T>(Vec<T>);
@@ -218,6 +394,39 @@ where
 mod tests {
     use super::*;
 
+    #[test]
+    fn check_prometheus_histogram() {
+        use prometheus::Encoder;
+
+        let histogram = PrometheusHistogram::new(&[1.0, 5.0]);
+        histogram.observe(0.5);
+        histogram.observe(1.0);
+        histogram.observe(3.0);
+        histogram.observe(10.0);
+        assert_eq!(histogram.count(), 4);
+        assert_eq!(histogram.sum(), 14.5);
+
+        let mut registry = PrometheusRegistry::new("oe_".into());
+        registry.register_histogram_family(
+            "latency",
+            "Latency",
+            "method",
+            &[("eth_call", &histogram)],
+        );
+        let mut buffer = vec![];
+        prometheus::TextEncoder::new()
+            .encode(&registry.registry().gather(), &mut buffer)
+            .unwrap();
+        let text = String::from_utf8(buffer).unwrap();
+
+        assert!(text.contains("# TYPE oe_latency histogram"));
+        assert!(text.contains("oe_latency_bucket{method=\"eth_call\",le=\"1\"} 2"));
+        assert!(text.contains("oe_latency_bucket{method=\"eth_call\",le=\"5\"} 3"));
+        assert!(text.contains("oe_latency_bucket{method=\"eth_call\",le=\"+Inf\"} 4"));
+        assert!(text.contains("oe_latency_sum{method=\"eth_call\"} 14.5"));
+        assert!(text.contains("oe_latency_count{method=\"eth_call\"} 4"));
+    }
+
     #[test]
     fn check_corpus() {
         let corpus = Corpus::from(vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
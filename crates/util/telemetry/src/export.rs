@@ -0,0 +1,257 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! OTLP/HTTP exporter of finished spans.
+
+use std::{
+    io,
+    sync::mpsc::{self, Receiver, RecvTimeoutError, SyncSender},
+    thread,
+    time::{Duration, Instant},
+};
+
+use futures::{Future, Stream};
+use hyper::{client::HttpConnector, header, Body, Client, Method, Request, Uri};
+use serde_json::{json, Value as Json};
+use tokio::{runtime::current_thread::Runtime, timer::Timeout};
+
+use super::{Config, SpanData, SpanKind, Value};
+
+/// Maximal number of finished span batches waiting for export.
+const QUEUE_SIZE: usize = 1024;
+/// Maximal number of spans sent in a single request.
+const BATCH_SIZE: usize = 512;
+/// Maximal time a finished span waits for export.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(2);
+/// Timeout of collector requests.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// Default path of the traces endpoint of a collector.
+const TRACES_PATH: &str = "/v1/traces";
+
+/// Starts the exporter thread, returning the queue of spans to export.
+pub(crate) fn spawn(config: Config) -> io::Result<SyncSender<Vec<SpanData>>> {
+    let uri = traces_uri(&config.endpoint)?;
+    let (sender, receiver) = mpsc::sync_channel(QUEUE_SIZE);
+    info!(target: "telemetry", "Exporting traces to {}", uri);
+    thread::Builder::new()
+        .name("otlp-exporter".into())
+        .spawn(move || match Exporter::new(uri) {
+            Ok(mut exporter) => exporter.run(&config.service_name, receiver),
+            Err(err) => warn!(target: "telemetry", "Failed to start trace exporter: {}", err),
+        })?;
+    Ok(sender)
+}
+
+/// Collector URI, with the default traces path unless the endpoint specifies one.
+fn traces_uri(endpoint: &str) -> io::Result<Uri> {
+    let invalid = |msg: &str| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Invalid OTLP endpoint {}: {}", endpoint, msg),
+        )
+    };
+    let uri: Uri = endpoint.parse().map_err(|_| invalid("malformed URI"))?;
+    if uri.scheme_str() != Some("http") {
+        return Err(invalid("only http:// endpoints are supported"));
+    }
+    if uri.host().unwrap_or_default().is_empty() {
+        return Err(invalid("missing host"));
+    }
+    match uri.path() {
+        "" | "/" => format!("{}{}", endpoint.trim_end_matches('/'), TRACES_PATH)
+            .parse()
+            .map_err(|_| invalid("malformed URI")),
+        _ => Ok(uri),
+    }
+}
+
+/// Batches finished spans and posts them to the collector.
+struct Exporter {
+    runtime: Runtime,
+    client: Client<HttpConnector>,
+    uri: Uri,
+}
+
+impl Exporter {
+    fn new(uri: Uri) -> io::Result<Exporter> {
+        Ok(Exporter {
+            runtime: Runtime::new()?,
+            client: Client::new(),
+            uri,
+        })
+    }
+
+    fn run(&mut self, service_name: &str, receiver: Receiver<Vec<SpanData>>) {
+        let mut batch = Vec::with_capacity(BATCH_SIZE);
+        let mut last_flush = Instant::now();
+        loop {
+            let timeout = FLUSH_INTERVAL
+                .checked_sub(last_flush.elapsed())
+                .unwrap_or_default();
+            let disconnected = match receiver.recv_timeout(timeout) {
+                Ok(spans) => {
+                    batch.extend(spans);
+                    if batch.len() < BATCH_SIZE && last_flush.elapsed() < FLUSH_INTERVAL {
+                        continue;
+                    }
+                    false
+                }
+                Err(RecvTimeoutError::Timeout) => false,
+                Err(RecvTimeoutError::Disconnected) => true,
+            };
+
+            for spans in batch.chunks(BATCH_SIZE) {
+                let body = encode(service_name, spans).to_string();
+                if let Err(err) = self.post(body) {
+                    warn!(target: "telemetry", "Failed to export {} spans to {}: {}", spans.len(), self.uri, err);
+                }
+            }
+            batch.clear();
+            last_flush = Instant::now();
+            if disconnected {
+                return;
+            }
+        }
+    }
+
+    fn post(&mut self, body: String) -> Result<(), String> {
+        let request = Request::builder()
+            .method(Method::POST)
+            .uri(self.uri.clone())
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))
+            .map_err(|err| err.to_string())?;
+        let response = self.client.request(request).and_then(|response| {
+            let status = response.status();
+            // read the whole response, so that the connection can be reused.
+            response.into_body().concat2().map(move |_| status)
+        });
+
+        let status = self
+            .runtime
+            .block_on(Timeout::new(response, REQUEST_TIMEOUT))
+            .map_err(|err| err.to_string())?;
+        if status.is_success() {
+            Ok(())
+        } else {
+            Err(format!("collector responded with {}", status))
+        }
+    }
+}
+
+/// Encodes spans as an OTLP `ExportTraceServiceRequest`.
+fn encode(service_name: &str, spans: &[SpanData]) -> Json {
+    let spans: Vec<Json> = spans
+        .iter()
+        .map(|span| {
+            json!({
+                "traceId": format!("{:032x}", span.context.trace_id),
+                "spanId": format!("{:016x}", span.context.span_id),
+                "parentSpanId": span.parent_id.map(|id| format!("{:016x}", id)).unwrap_or_default(),
+                "name": span.name,
+                "kind": match span.kind {
+                    SpanKind::Internal => 1,
+                    SpanKind::Server => 2,
+                },
+                "startTimeUnixNano": span.start_time.to_string(),
+                "endTimeUnixNano": span.end_time.to_string(),
+                "attributes": span
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| attribute(key, value))
+                    .collect::<Vec<_>>(),
+                "status": { "code": if span.error { 2 } else { 0 } },
+            })
+        })
+        .collect();
+
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [attribute("service.name", &Value::Str(service_name.into()))],
+            },
+            "scopeSpans": [{
+                "scope": { "name": service_name },
+                "spans": spans,
+            }],
+        }],
+    })
+}
+
+fn attribute(key: &str, value: &Value) -> Json {
+    let value = match *value {
+        // 64-bit integers are encoded as strings in OTLP JSON.
+        Value::Int(value) => json!({ "intValue": value.to_string() }),
+        Value::Str(ref value) => json!({ "stringValue": value }),
+        Value::Bool(value) => json!({ "boolValue": value }),
+    };
+    json!({ "key": key, "value": value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SpanContext;
+
+    #[test]
+    fn parses_endpoints() {
+        assert_eq!(
+            traces_uri("http://collector:4000").unwrap(),
+            "http://collector:4000/v1/traces"
+        );
+        assert_eq!(
+            traces_uri("http://collector:4000/").unwrap(),
+            "http://collector:4000/v1/traces"
+        );
+        assert_eq!(
+            traces_uri("http://127.0.0.1/otlp/v1/traces").unwrap(),
+            "http://127.0.0.1/otlp/v1/traces"
+        );
+        assert!(traces_uri("https://collector:4318").is_err());
+        assert!(traces_uri("collector:4318").is_err());
+    }
+
+    #[test]
+    fn encodes_spans() {
+        let span = SpanData {
+            context: SpanContext {
+                trace_id: 1,
+                span_id: 2,
+            },
+            parent_id: Some(3),
+            name: "import_block".into(),
+            kind: SpanKind::Internal,
+            start_time: 10,
+            end_time: 20,
+            attributes: vec![("block.number", Value::Int(5))],
+            error: true,
+        };
+        let encoded = encode("openethereum", &[span]);
+        let resource = &encoded["resourceSpans"][0];
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "openethereum"
+        );
+        let span = &resource["scopeSpans"][0]["spans"][0];
+        assert_eq!(span["traceId"], "00000000000000000000000000000001");
+        assert_eq!(span["spanId"], "0000000000000002");
+        assert_eq!(span["parentSpanId"], "0000000000000003");
+        assert_eq!(span["startTimeUnixNano"], "10");
+        assert_eq!(span["attributes"][0]["key"], "block.number");
+        assert_eq!(span["attributes"][0]["value"]["intValue"], "5");
+        assert_eq!(span["status"]["code"], 2);
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Span instrumentation exported to an OpenTelemetry collector.
+//!
+//! Spans are only recorded once `init` has started an exporter; until then
+//! creating them is a no-op. Finished spans are collected per thread until the
+//! outermost span of the thread finishes, then batched by a background thread
+//! and sent to the collector over OTLP/HTTP with JSON encoding.
+
+#[macro_use]
+extern crate log;
+
+mod export;
+
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    io, mem,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::SyncSender,
+    },
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+
+/// OTLP exporter configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// Collector endpoint, e.g. `http://127.0.0.1:4318`. Spans are posted to
+    /// `/v1/traces` unless the endpoint specifies another path.
+    pub endpoint: String,
+    /// Value of the `service.name` resource attribute.
+    pub service_name: String,
+}
+
+/// Maximal number of finished spans a thread holds before sending them.
+const LOCAL_BATCH_SIZE: usize = 64;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+lazy_static! {
+    static ref EXPORTER: Mutex<Option<SyncSender<Vec<SpanData>>>> = Mutex::new(None);
+}
+
+thread_local! {
+    static LOCAL: RefCell<LocalSpans> = RefCell::new(LocalSpans::default());
+}
+
+/// Spans of a thread: the stack of current spans and finished ones waiting to
+/// be sent to the exporter.
+#[derive(Default)]
+struct LocalSpans {
+    current: Vec<SpanContext>,
+    finished: Vec<SpanData>,
+    sender: Option<SyncSender<Vec<SpanData>>>,
+}
+
+impl LocalSpans {
+    fn flush(&mut self) {
+        if self.finished.is_empty() {
+            return;
+        }
+        // the exporter queue is looked up once per thread.
+        if self.sender.is_none() {
+            self.sender = EXPORTER.lock().clone();
+        }
+        let spans = mem::take(&mut self.finished);
+        if let Some(ref sender) = self.sender {
+            if sender.try_send(spans).is_err() {
+                trace!(target: "telemetry", "Span queue is full, dropping spans");
+            }
+        }
+    }
+}
+
+impl Drop for LocalSpans {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Starts exporting spans to the configured collector.
+pub fn init(config: Config) -> io::Result<()> {
+    let sender = export::spawn(config)?;
+    *EXPORTER.lock() = Some(sender);
+    ENABLED.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Whether spans are recorded.
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Starts a span within the current one, which becomes the current span of
+/// this thread until dropped.
+pub fn span<N: Into<Cow<'static, str>>>(name: N) -> Span {
+    Span::start(name.into(), SpanKind::Internal, true)
+}
+
+/// Starts a span within the current one without making it current. Used for
+/// work which may complete on another thread.
+pub fn detached_span<N: Into<Cow<'static, str>>>(name: N, kind: SpanKind) -> Span {
+    Span::start(name.into(), kind, false)
+}
+
+/// Kind of a span, as defined by OpenTelemetry.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpanKind {
+    /// Internal operation.
+    Internal,
+    /// Handling of a remote request.
+    Server,
+}
+
+/// Value of a span attribute.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Integer value.
+    Int(i64),
+    /// String value.
+    Str(String),
+    /// Boolean value.
+    Bool(bool),
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<u64> for Value {
+    fn from(value: u64) -> Self {
+        Value::Int(value.min(i64::MAX as u64) as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::Int(value.into())
+    }
+}
+
+impl From<usize> for Value {
+    fn from(value: usize) -> Self {
+        Value::from(value as u64)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl<'a> From<&'a str> for Value {
+    fn from(value: &'a str) -> Self {
+        Value::Str(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Str(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SpanContext {
+    trace_id: u128,
+    span_id: u64,
+}
+
+#[derive(Debug)]
+struct SpanData {
+    context: SpanContext,
+    parent_id: Option<u64>,
+    name: Cow<'static, str>,
+    kind: SpanKind,
+    start_time: u64,
+    end_time: u64,
+    attributes: Vec<(&'static str, Value)>,
+    error: bool,
+}
+
+/// A timed operation, recorded when dropped.
+pub struct Span {
+    data: Option<SpanData>,
+    started: Instant,
+    entered: bool,
+}
+
+impl Span {
+    fn start(name: Cow<'static, str>, kind: SpanKind, enter: bool) -> Span {
+        let data = if is_enabled() {
+            let parent = LOCAL.with(|local| local.borrow().current.last().cloned());
+            let context = SpanContext {
+                trace_id: parent.map_or_else(|| rand::random::<u128>().max(1), |p| p.trace_id),
+                span_id: rand::random::<u64>().max(1),
+            };
+            if enter {
+                LOCAL.with(|local| local.borrow_mut().current.push(context));
+            }
+            Some(SpanData {
+                context,
+                parent_id: parent.map(|p| p.span_id),
+                name,
+                kind,
+                start_time: unix_nanos(),
+                end_time: 0,
+                attributes: Vec::new(),
+                error: false,
+            })
+        } else {
+            None
+        };
+
+        Span {
+            entered: enter && data.is_some(),
+            data,
+            started: Instant::now(),
+        }
+    }
+
+    /// Whether the span is recorded. Allows skipping costly attribute values.
+    pub fn is_recording(&self) -> bool {
+        self.data.is_some()
+    }
+
+    /// Sets an attribute of the span.
+    pub fn set_attribute<V: Into<Value>>(&mut self, key: &'static str, value: V) {
+        if let Some(ref mut data) = self.data {
+            data.attributes.push((key, value.into()));
+        }
+    }
+
+    /// Marks the operation as failed.
+    pub fn set_error(&mut self) {
+        if let Some(ref mut data) = self.data {
+            data.error = true;
+        }
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let mut data = match self.data.take() {
+            Some(data) => data,
+            None => return,
+        };
+        data.end_time = data.start_time + self.started.elapsed().as_nanos() as u64;
+        let entered = self.entered;
+
+        // spans dropped while the thread exits are lost.
+        let _ = LOCAL.try_with(|local| {
+            let mut local = local.borrow_mut();
+            if entered {
+                if let Some(pos) = local.current.iter().rposition(|c| *c == data.context) {
+                    local.current.remove(pos);
+                }
+            }
+            local.finished.push(data);
+            if local.current.is_empty() || local.finished.len() >= LOCAL_BATCH_SIZE {
+                local.flush();
+            }
+        });
+    }
+}
+
+fn unix_nanos() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn nests_spans_of_a_thread() {
+        let (sender, receiver) = mpsc::sync_channel(16);
+        *EXPORTER.lock() = Some(sender);
+        ENABLED.store(true, Ordering::SeqCst);
+
+        {
+            let mut parent = span("import_block");
+            parent.set_attribute("block.number", 5u64);
+            {
+                let _child = span("execute_block");
+            }
+            let _detached = detached_span("rpc_call", SpanKind::Server);
+        }
+        let _sibling = span("flush");
+        drop(_sibling);
+
+        // spans are sent once the outermost span finishes.
+        let mut spans = receiver.recv().unwrap().into_iter();
+        let child = spans.next().unwrap();
+        let detached = spans.next().unwrap();
+        let parent = spans.next().unwrap();
+        assert!(spans.next().is_none());
+        let sibling = receiver.recv().unwrap().remove(0);
+
+        assert_eq!(child.name, "execute_block");
+        assert_eq!(parent.name, "import_block");
+        assert_eq!(child.parent_id, Some(parent.context.span_id));
+        assert_eq!(child.context.trace_id, parent.context.trace_id);
+        assert_eq!(detached.parent_id, Some(parent.context.span_id));
+        assert_eq!(parent.parent_id, None);
+        assert_eq!(parent.attributes, vec![("block.number", Value::Int(5))]);
+        assert!(parent.end_time >= child.end_time);
+        assert_eq!(sibling.parent_id, None);
+        assert_ne!(sibling.context.trace_id, parent.context.trace_id);
+    }
+}
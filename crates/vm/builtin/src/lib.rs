@@ -22,12 +22,15 @@ use std::{
     cmp::{max, min},
     collections::BTreeMap,
     convert::{TryFrom, TryInto},
+    fmt,
     io::{self, Cursor, Read},
     mem::size_of,
     str::FromStr,
+    sync::Arc,
 };
 
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use c_kzg::{Bytes32, Bytes48, KzgProof, KzgSettings};
 use eip_152::compress;
 use eth_pairings::public_interface::eip2537::{
     EIP2537Executor, SCALAR_BYTE_LENGTH, SERIALIZED_G1_POINT_BYTE_LENGTH,
@@ -36,6 +39,7 @@ use eth_pairings::public_interface::eip2537::{
 use ethereum_types::{H256, U256};
 use ethjson;
 use keccak_hash::keccak;
+use lazy_static::lazy_static;
 use log::{trace, warn};
 use num::{BigUint, One, Zero};
 use parity_bytes::BytesRef;
@@ -43,6 +47,7 @@ use parity_crypto::{
     digest,
     publickey::{recover_allowing_all_zero_message, Signature, ZeroesAllowedMessage},
 };
+use parking_lot::{Mutex, RwLock};
 
 /// Native implementation of a built-in contract.
 pub trait Implementation: Send + Sync {
@@ -51,7 +56,7 @@ pub trait Implementation: Send + Sync {
 }
 
 /// A gas pricing scheme for built-in contracts.
-trait Pricer: Send + Sync {
+pub trait Pricer: Send + Sync {
     /// The gas cost of running this built-in for the given input data at block number `at`
     fn cost(&self, input: &[u8]) -> U256;
 }
@@ -88,6 +93,17 @@ enum Pricing {
     Bls12ConstOperations(Bls12ConstOperations),
     Bls12MultiexpG1(Bls12MultiexpPricerG1),
     Bls12MultiexpG2(Bls12MultiexpPricerG2),
+    Registered(RegisteredPricer),
+}
+
+/// Pricer of a registered built-in.
+#[derive(Clone)]
+struct RegisteredPricer(Arc<dyn Pricer>);
+
+impl fmt::Debug for RegisteredPricer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "RegisteredPricer")
+    }
 }
 
 impl Pricer for Pricing {
@@ -103,15 +119,16 @@ impl Pricer for Pricing {
             Pricing::Bls12ConstOperations(inner) => inner.cost(input),
             Pricing::Bls12MultiexpG1(inner) => inner.cost(input),
             Pricing::Bls12MultiexpG2(inner) => inner.cost(input),
+            Pricing::Registered(inner) => inner.0.cost(input),
         }
     }
 }
 
 /// A linear pricing model. This computes a price using a base cost and a cost per-word.
 #[derive(Debug)]
-struct Linear {
-    base: u64,
-    word: u64,
+pub struct Linear {
+    pub base: u64,
+    pub word: u64,
 }
 
 /// A special pricing model for modular exponentiation.
@@ -536,6 +553,7 @@ pub type Bls12MultiexpPricerG2 = Bls12MultiexpPricer<G2Marker>;
 pub struct Builtin {
     pricer: BTreeMap<u64, Pricing>,
     native: EthereumBuiltin,
+    deactivate_at: Option<u64>,
 }
 
 impl Builtin {
@@ -562,10 +580,13 @@ impl Builtin {
         self.native.execute(input, output)
     }
 
-    /// Whether the builtin is activated at the given block number.
+    /// Whether the builtin is activated, and not yet deactivated, at the given block number.
     #[inline]
     pub fn is_active(&self, at: u64) -> bool {
         self.pricer.range(0..=at).last().is_some()
+            && self
+                .deactivate_at
+                .map_or(true, |deactivate_at| at < deactivate_at)
     }
 }
 
@@ -573,14 +594,33 @@ impl TryFrom<ethjson::spec::builtin::Builtin> for Builtin {
     type Error = String;
 
     fn try_from(b: ethjson::spec::builtin::Builtin) -> Result<Self, Self::Error> {
-        let native = EthereumBuiltin::from_str(&b.name)?;
+        let native = match b.trusted_setup {
+            Some(ref path) if b.name == "kzg_point_evaluation" => {
+                EthereumBuiltin::KzgPointEvaluation(KzgPointEvaluation::from_file(path)?)
+            }
+            Some(_) => return Err(format!("trusted setup given for builtin: {}", b.name)),
+            None => EthereumBuiltin::from_str(&b.name)?,
+        };
         let mut pricer = BTreeMap::new();
 
         for (activate_at, p) in b.pricing {
             pricer.insert(activate_at, p.price.into());
         }
 
-        Ok(Self { pricer, native })
+        if let Some(activate_at) = b.activate_at {
+            match native {
+                EthereumBuiltin::Registered(ref registered) => {
+                    pricer.insert(activate_at, Pricing::Registered(registered.pricer.clone()));
+                }
+                _ => return Err(format!("missing pricing for builtin: {}", b.name)),
+            }
+        }
+
+        Ok(Self {
+            pricer,
+            native,
+            deactivate_at: b.deactivate_at,
+        })
     }
 }
 
@@ -685,32 +725,107 @@ enum EthereumBuiltin {
     Bls12MapFpToG1(Bls12MapFpToG1),
     /// bls12_381 fp2 to g2 mapping
     Bls12MapFp2ToG2(Bls12MapFp2ToG2),
+    /// secp256r1 signature verification (RIP-7212)
+    P256Verify(P256Verify),
+    /// KZG point evaluation (EIP-4844)
+    KzgPointEvaluation(KzgPointEvaluation),
+    /// built-in added with `register`
+    Registered(Registered),
+}
+
+/// Built-in added with `register`.
+#[derive(Clone)]
+struct Registered {
+    implementation: Arc<dyn Implementation>,
+    pricer: RegisteredPricer,
+}
+
+lazy_static! {
+    static ref REGISTRY: RwLock<BTreeMap<String, Registered>> = RwLock::new(feature_builtins());
+}
+
+/// Built-ins enabled with the Cargo feature of the same name.
+fn feature_builtins() -> BTreeMap<String, Registered> {
+    #[allow(unused_mut)]
+    let mut builtins = BTreeMap::new();
+    #[cfg(feature = "sha3_512")]
+    builtins.insert(
+        "sha3_512".to_owned(),
+        Registered {
+            implementation: Arc::new(Sha3_512),
+            pricer: RegisteredPricer(Arc::new(Linear { base: 60, word: 12 })),
+        },
+    );
+    builtins
+}
+
+/// Registers a native built-in contract, so that chain specs can refer to it by `name`.
+///
+/// The `pricer` is used when the chain spec of the built-in has no `pricing`, from its
+/// `activate_at` block on. Built-ins must be registered before the chain spec is loaded.
+pub fn register<I, P>(name: &str, implementation: I, pricer: P) -> Result<(), String>
+where
+    I: Implementation + 'static,
+    P: Pricer + 'static,
+{
+    let mut registry = REGISTRY.write();
+    if registry.contains_key(name) || EthereumBuiltin::native_from_str(name).is_some() {
+        return Err(format!("builtin already exists: {}", name));
+    }
+    registry.insert(
+        name.to_owned(),
+        Registered {
+            implementation: Arc::new(implementation),
+            pricer: RegisteredPricer(Arc::new(pricer)),
+        },
+    );
+    Ok(())
+}
+
+impl EthereumBuiltin {
+    /// Built-in implemented by this crate, registered ones are not looked up.
+    fn native_from_str(name: &str) -> Option<EthereumBuiltin> {
+        let builtin = match name {
+            "identity" => EthereumBuiltin::Identity(Identity),
+            "ecrecover" => EthereumBuiltin::EcRecover(EcRecover),
+            "sha256" => EthereumBuiltin::Sha256(Sha256),
+            "ripemd160" => EthereumBuiltin::Ripemd160(Ripemd160),
+            "modexp" => EthereumBuiltin::Modexp(Modexp),
+            "alt_bn128_add" => EthereumBuiltin::Bn128Add(Bn128Add),
+            "alt_bn128_mul" => EthereumBuiltin::Bn128Mul(Bn128Mul),
+            "alt_bn128_pairing" => EthereumBuiltin::Bn128Pairing(Bn128Pairing),
+            "blake2_f" => EthereumBuiltin::Blake2F(Blake2F),
+            "bls12_381_g1_add" => EthereumBuiltin::Bls12G1Add(Bls12G1Add),
+            "bls12_381_g1_mul" => EthereumBuiltin::Bls12G1Mul(Bls12G1Mul),
+            "bls12_381_g1_multiexp" => EthereumBuiltin::Bls12G1MultiExp(Bls12G1MultiExp),
+            "bls12_381_g2_add" => EthereumBuiltin::Bls12G2Add(Bls12G2Add),
+            "bls12_381_g2_mul" => EthereumBuiltin::Bls12G2Mul(Bls12G2Mul),
+            "bls12_381_g2_multiexp" => EthereumBuiltin::Bls12G2MultiExp(Bls12G2MultiExp),
+            "bls12_381_pairing" => EthereumBuiltin::Bls12Pairing(Bls12Pairing),
+            "bls12_381_fp_to_g1" => EthereumBuiltin::Bls12MapFpToG1(Bls12MapFpToG1),
+            "bls12_381_fp2_to_g2" => EthereumBuiltin::Bls12MapFp2ToG2(Bls12MapFp2ToG2),
+            "p256_verify" => EthereumBuiltin::P256Verify(P256Verify),
+            "kzg_point_evaluation" => {
+                EthereumBuiltin::KzgPointEvaluation(KzgPointEvaluation::ethereum())
+            }
+            _ => return None,
+        };
+        Some(builtin)
+    }
 }
 
 impl FromStr for EthereumBuiltin {
     type Err = String;
 
     fn from_str(name: &str) -> Result<EthereumBuiltin, Self::Err> {
-        match name {
-            "identity" => Ok(EthereumBuiltin::Identity(Identity)),
-            "ecrecover" => Ok(EthereumBuiltin::EcRecover(EcRecover)),
-            "sha256" => Ok(EthereumBuiltin::Sha256(Sha256)),
-            "ripemd160" => Ok(EthereumBuiltin::Ripemd160(Ripemd160)),
-            "modexp" => Ok(EthereumBuiltin::Modexp(Modexp)),
-            "alt_bn128_add" => Ok(EthereumBuiltin::Bn128Add(Bn128Add)),
-            "alt_bn128_mul" => Ok(EthereumBuiltin::Bn128Mul(Bn128Mul)),
-            "alt_bn128_pairing" => Ok(EthereumBuiltin::Bn128Pairing(Bn128Pairing)),
-            "blake2_f" => Ok(EthereumBuiltin::Blake2F(Blake2F)),
-            "bls12_381_g1_add" => Ok(EthereumBuiltin::Bls12G1Add(Bls12G1Add)),
-            "bls12_381_g1_mul" => Ok(EthereumBuiltin::Bls12G1Mul(Bls12G1Mul)),
-            "bls12_381_g1_multiexp" => Ok(EthereumBuiltin::Bls12G1MultiExp(Bls12G1MultiExp)),
-            "bls12_381_g2_add" => Ok(EthereumBuiltin::Bls12G2Add(Bls12G2Add)),
-            "bls12_381_g2_mul" => Ok(EthereumBuiltin::Bls12G2Mul(Bls12G2Mul)),
-            "bls12_381_g2_multiexp" => Ok(EthereumBuiltin::Bls12G2MultiExp(Bls12G2MultiExp)),
-            "bls12_381_pairing" => Ok(EthereumBuiltin::Bls12Pairing(Bls12Pairing)),
-            "bls12_381_fp_to_g1" => Ok(EthereumBuiltin::Bls12MapFpToG1(Bls12MapFpToG1)),
-            "bls12_381_fp2_to_g2" => Ok(EthereumBuiltin::Bls12MapFp2ToG2(Bls12MapFp2ToG2)),
-            _ => return Err(format!("invalid builtin name: {}", name)),
+        match EthereumBuiltin::native_from_str(name) {
+            Some(builtin) => Ok(builtin),
+            None => REGISTRY
+                .read()
+                .get(name)
+                .cloned()
+                .map(EthereumBuiltin::Registered)
+                .ok_or_else(|| format!("invalid builtin name: {}", name)),
         }
     }
 }
@@ -736,6 +851,9 @@ impl Implementation for EthereumBuiltin {
             EthereumBuiltin::Bls12Pairing(inner) => inner.execute(input, output),
             EthereumBuiltin::Bls12MapFpToG1(inner) => inner.execute(input, output),
             EthereumBuiltin::Bls12MapFp2ToG2(inner) => inner.execute(input, output),
+            EthereumBuiltin::P256Verify(inner) => inner.execute(input, output),
+            EthereumBuiltin::KzgPointEvaluation(inner) => inner.execute(input, output),
+            EthereumBuiltin::Registered(inner) => inner.implementation.execute(input, output),
         }
     }
 }
@@ -803,6 +921,55 @@ pub struct Bls12MapFpToG1;
 /// The Bls12MapFp2ToG2 builtin.
 pub struct Bls12MapFp2ToG2;
 
+#[derive(Debug)]
+/// The P256Verify builtin.
+pub struct P256Verify;
+
+/// The KzgPointEvaluation builtin.
+pub struct KzgPointEvaluation {
+    settings: &'static KzgSettings,
+}
+
+lazy_static! {
+    /// Trusted setups loaded from files, by path. They are kept for the lifetime of the process,
+    /// so that loading the same chain spec again does not parse the setup again.
+    static ref TRUSTED_SETUPS: Mutex<BTreeMap<String, &'static KzgSettings>> =
+        Mutex::new(BTreeMap::new());
+}
+
+impl KzgPointEvaluation {
+    /// Uses the trusted setup of the Ethereum KZG ceremony.
+    pub fn ethereum() -> Self {
+        KzgPointEvaluation {
+            settings: c_kzg::ethereum_kzg_settings(),
+        }
+    }
+
+    /// Loads the trusted setup from a file in the format of the consensus specs.
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let mut setups = TRUSTED_SETUPS.lock();
+        if let Some(settings) = setups.get(path) {
+            return Ok(KzgPointEvaluation { settings });
+        }
+        let settings = KzgSettings::load_trusted_setup_file(path.as_ref())
+            .map_err(|e| format!("invalid trusted setup {}: {:?}", path, e))?;
+        let settings: &'static KzgSettings = Box::leak(Box::new(settings));
+        setups.insert(path.to_owned(), settings);
+        Ok(KzgPointEvaluation { settings })
+    }
+}
+
+impl fmt::Debug for KzgPointEvaluation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "KzgPointEvaluation")
+    }
+}
+
+#[cfg(feature = "sha3_512")]
+#[derive(Debug)]
+/// The SHA3-512 builtin, enabled with the `sha3_512` feature.
+pub struct Sha3_512;
+
 impl Implementation for Identity {
     fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
         output.write(0, input);
@@ -856,6 +1023,20 @@ impl Implementation for Sha256 {
     }
 }
 
+#[cfg(feature = "sha3_512")]
+impl Implementation for Sha3_512 {
+    fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
+        use tiny_keccak::{Hasher, Sha3};
+
+        let mut d = [0u8; 64];
+        let mut sha3 = Sha3::v512();
+        sha3.update(input);
+        sha3.finalize(&mut d);
+        output.write(0, &d);
+        Ok(())
+    }
+}
+
 impl Implementation for Blake2F {
     /// Format of `input`:
     /// [4 bytes for rounds][64 bytes for h][128 bytes for m][8 bytes for t_0][8 bytes for t_1][1 byte for f]
@@ -1200,6 +1381,105 @@ impl Implementation for Bls12MapFp2ToG2 {
     }
 }
 
+impl Implementation for P256Verify {
+    /// Format of `input`:
+    /// [32 bytes for message hash][32 bytes for r][32 bytes for s][32 bytes for x][32 bytes for y]
+    ///
+    /// Writes 1 as a 32 bytes word if the signature is valid, nothing otherwise.
+    fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
+        use p256::{
+            ecdsa::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey},
+            EncodedPoint, FieldBytes,
+        };
+
+        const P256_VERIFY_ARG_LEN: usize = 160;
+
+        if input.len() != P256_VERIFY_ARG_LEN {
+            trace!(target: "builtin", "input length for P256Verify must be exactly {} bytes, was {}", P256_VERIFY_ARG_LEN, input.len());
+            return Ok(());
+        }
+
+        let signature = match Signature::from_scalars(
+            *FieldBytes::from_slice(&input[32..64]),
+            *FieldBytes::from_slice(&input[64..96]),
+        ) {
+            Ok(signature) => signature,
+            Err(_) => return Ok(()),
+        };
+        let point = EncodedPoint::from_affine_coordinates(
+            FieldBytes::from_slice(&input[96..128]),
+            FieldBytes::from_slice(&input[128..160]),
+            false,
+        );
+        let key = match VerifyingKey::from_encoded_point(&point) {
+            Ok(key) => key,
+            Err(_) => return Ok(()),
+        };
+
+        if key.verify_prehash(&input[0..32], &signature).is_ok() {
+            let mut valid = [0u8; 32];
+            valid[31] = 1;
+            output.write(0, &valid);
+        }
+
+        Ok(())
+    }
+}
+
+impl Implementation for KzgPointEvaluation {
+    /// Format of `input`:
+    /// [32 bytes for versioned hash][32 bytes for z][32 bytes for y][48 bytes for commitment][48 bytes for proof]
+    ///
+    /// Writes the number of field elements per blob and the BLS modulus, as 32 bytes words.
+    fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
+        const KZG_POINT_EVALUATION_ARG_LEN: usize = 192;
+        const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+        const FIELD_ELEMENTS_PER_BLOB: u64 = 4096;
+        const BLS_MODULUS: [u8; 32] = [
+            0x73, 0xed, 0xa7, 0x53, 0x29, 0x9d, 0x7d, 0x48, 0x33, 0x39, 0xd8, 0x08, 0x09, 0xa1,
+            0xd8, 0x05, 0x53, 0xbd, 0xa4, 0x02, 0xff, 0xfe, 0x5b, 0xfe, 0xff, 0xff, 0xff, 0xff,
+            0x00, 0x00, 0x00, 0x01,
+        ];
+
+        if input.len() != KZG_POINT_EVALUATION_ARG_LEN {
+            trace!(target: "builtin", "input length for KZG point evaluation must be exactly {} bytes, was {}", KZG_POINT_EVALUATION_ARG_LEN, input.len());
+            return Err("input length for KZG point evaluation must be exactly 192 bytes");
+        }
+
+        let commitment = &input[96..144];
+        let mut versioned_hash = [0u8; 32];
+        versioned_hash.copy_from_slice(&*digest::sha256(commitment));
+        versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
+        if input[0..32] != versioned_hash[..] {
+            return Err("versioned hash does not match the commitment");
+        }
+
+        let bytes32 = |b: &[u8]| Bytes32::from_bytes(b).map_err(|_| "invalid field element");
+        let bytes48 = |b: &[u8]| Bytes48::from_bytes(b).map_err(|_| "invalid point");
+        let valid = KzgProof::verify_kzg_proof(
+            &bytes48(commitment)?,
+            &bytes32(&input[32..64])?,
+            &bytes32(&input[64..96])?,
+            &bytes48(&input[144..192])?,
+            self.settings,
+        )
+        .map_err(|e| {
+            trace!(target: "builtin", "KZG point evaluation error: {:?}", e);
+            "KZG point evaluation error"
+        })?;
+
+        if !valid {
+            return Err("invalid KZG proof");
+        }
+
+        let mut result = [0u8; 64];
+        result[24..32].copy_from_slice(&FIELD_ELEMENTS_PER_BLOB.to_be_bytes());
+        result[32..64].copy_from_slice(&BLS_MODULUS);
+        output.write(0, &result);
+        Ok(())
+    }
+}
+
 fn read_fr(reader: &mut io::Chain<&[u8], io::Repeat>) -> Result<bn::Fr, &'static str> {
     let mut buf = [0u8; 32];
 
@@ -1369,9 +1649,9 @@ impl Bn128Pairing {
 #[cfg(test)]
 mod tests {
     use super::{
-        modexp as me, BTreeMap, Bls12ConstOperations, Bls12PairingPrice, Bls12PairingPricer,
-        Builtin, EthereumBuiltin, FromStr, Implementation, Linear, Modexp2565Pricer, ModexpPricer,
-        Pricer, Pricing,
+        modexp as me, register, BTreeMap, Bls12ConstOperations, Bls12PairingPrice,
+        Bls12PairingPricer, Builtin, EthereumBuiltin, FromStr, Implementation, Linear,
+        Modexp2565Pricer, ModexpPricer, Pricer, Pricing,
     };
     use ethereum_types::U256;
     use ethjson::spec::builtin::{
@@ -1391,6 +1671,7 @@ mod tests {
         let f = Builtin {
             pricer: map![0 => Pricing::Blake2F(123)],
             native: EthereumBuiltin::from_str("blake2_f").unwrap(),
+            deactivate_at: None,
         };
         // 5 rounds
         let input = hex!("0000000548c9bdf267e6096a3ba7ca8485ae67bb2bf894fe72f36e3cf1361d5f3af54fa5d182e6ad7f520e511f6c3e2b8c68059b6bbd41fbabd9831f79217e1319cde05b61626300000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000300000000000000000000000000000001");
@@ -1406,6 +1687,7 @@ mod tests {
         let f = Builtin {
             pricer: map![0 => Pricing::Blake2F(123)],
             native: EthereumBuiltin::from_str("blake2_f").expect("known builtin"),
+            deactivate_at: None,
         };
         // invalid input (too short)
         let input = hex!("00");
@@ -1726,6 +2008,7 @@ mod tests {
         let f = Builtin {
             pricer: map![0 => Pricing::Modexp(ModexpPricer { divisor: 20 })],
             native: EthereumBuiltin::from_str("modexp").unwrap(),
+            deactivate_at: None,
         };
 
         // test for potential base len overflow
@@ -1904,6 +2187,7 @@ mod tests {
         let f = Builtin {
             pricer: map![0 => Pricing::Linear(Linear { base: 0, word: 0 })],
             native: EthereumBuiltin::from_str("alt_bn128_add").unwrap(),
+            deactivate_at: None,
         };
 
         // zero-points additions
@@ -1967,6 +2251,7 @@ mod tests {
         let f = Builtin {
             pricer: map![0 => Pricing::Linear(Linear { base: 0, word: 0 })],
             native: EthereumBuiltin::from_str("alt_bn128_mul").unwrap(),
+            deactivate_at: None,
         };
 
         // zero-point multiplication
@@ -2010,6 +2295,7 @@ mod tests {
         Builtin {
             pricer: map![0 => Pricing::Linear(Linear { base: 0, word: 0 })],
             native: EthereumBuiltin::from_str("alt_bn128_pairing").unwrap(),
+            deactivate_at: None,
         }
     }
 
@@ -2095,6 +2381,7 @@ mod tests {
         let b = Builtin {
             pricer: map![100_000 => pricer],
             native: EthereumBuiltin::from_str("identity").unwrap(),
+            deactivate_at: None,
         };
 
         assert!(!b.is_active(99_999));
@@ -2102,12 +2389,105 @@ mod tests {
         assert!(b.is_active(100_001));
     }
 
+    #[test]
+    fn is_active_until_deactivation() {
+        let b = Builtin::try_from(JsonBuiltin {
+            name: "identity".to_owned(),
+            pricing: map![
+                100 => PricingAt {
+                    info: None,
+                    price: JsonPricing::Linear(JsonLinearPricing { base: 10, word: 20 })
+                }
+            ],
+            activate_at: None,
+            deactivate_at: Some(200),
+            trusted_setup: None,
+        })
+        .expect("known builtin");
+
+        assert!(!b.is_active(99));
+        assert!(b.is_active(100));
+        assert!(b.is_active(199));
+        assert!(!b.is_active(200));
+    }
+
+    struct Reverse;
+
+    impl Implementation for Reverse {
+        fn execute(&self, input: &[u8], output: &mut BytesRef) -> Result<(), &'static str> {
+            let reversed: Vec<u8> = input.iter().rev().cloned().collect();
+            output.write(0, &reversed);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn from_registered() {
+        register("test_reverse", Reverse, Linear { base: 5, word: 1 }).expect("name is not taken");
+
+        let b = Builtin::try_from(JsonBuiltin {
+            name: "test_reverse".to_owned(),
+            pricing: BTreeMap::new(),
+            activate_at: Some(10),
+            deactivate_at: None,
+            trusted_setup: None,
+        })
+        .expect("registered builtin");
+
+        assert!(!b.is_active(9));
+        assert!(b.is_active(10));
+        assert_eq!(b.cost(&[0; 33], 10), U256::from(7));
+
+        let i = [0u8, 1, 2, 3];
+        let mut o = [255u8; 4];
+        b.execute(&i[..], &mut BytesRef::Fixed(&mut o[..]))
+            .expect("Builtin should not fail");
+        assert_eq!(o, [3u8, 2, 1, 0]);
+
+        // Spec pricing overrides the registered pricer.
+        let b = Builtin::try_from(JsonBuiltin {
+            name: "test_reverse".to_owned(),
+            pricing: map![
+                0 => PricingAt {
+                    info: None,
+                    price: JsonPricing::Linear(JsonLinearPricing { base: 10, word: 20 })
+                }
+            ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
+        })
+        .expect("registered builtin");
+        assert_eq!(b.cost(&[0; 1], 0), U256::from(30));
+    }
+
+    #[test]
+    fn register_rejects_existing_names() {
+        assert!(register("identity", Reverse, Linear { base: 5, word: 1 }).is_err());
+        register("test_duplicate", Reverse, Linear { base: 5, word: 1 })
+            .expect("name is not taken");
+        assert!(register("test_duplicate", Reverse, Linear { base: 5, word: 1 }).is_err());
+    }
+
+    #[test]
+    fn standard_builtin_requires_pricing() {
+        assert!(Builtin::try_from(JsonBuiltin {
+            name: "identity".to_owned(),
+            pricing: BTreeMap::new(),
+            activate_at: Some(0),
+            deactivate_at: None,
+            trusted_setup: None,
+        })
+        .is_err());
+    }
+
     #[test]
     fn from_named_linear() {
         let pricer = Pricing::Linear(Linear { base: 10, word: 20 });
         let b = Builtin {
             pricer: map![0 => pricer],
             native: EthereumBuiltin::from_str("identity").unwrap(),
+            deactivate_at: None,
         };
 
         assert_eq!(b.cost(&[0; 0], 0), U256::from(10));
@@ -2132,6 +2512,9 @@ mod tests {
                     price: JsonPricing::Linear(JsonLinearPricing { base: 10, word: 20 })
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .expect("known builtin");
 
@@ -2167,6 +2550,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2202,6 +2588,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2233,6 +2622,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2271,6 +2663,9 @@ mod tests {
                     })
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2317,6 +2712,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2329,6 +2727,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g1_add").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2354,6 +2753,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g1_mul").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2378,6 +2778,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g1_multiexp").unwrap(),
+            deactivate_at: None,
         };
         let input = hex!("
 			0000000000000000000000000000000012196c5a43d69224d8713389285f26b98f86ee910ab3dd668e413738282003cc5b7357af9a7af54bb713d62255e80f56
@@ -2446,6 +2847,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g2_add").unwrap(),
+            deactivate_at: None,
         };
         let input = hex!("
 			00000000000000000000000000000000161c595d151a765c7dee03c9210414cdffab84b9078b4b98f9df09be5ec299b8f6322c692214f00ede97958f235c352b
@@ -2476,6 +2878,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g2_mul").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2504,6 +2907,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => Pricing::Bls12ConstOperations(Bls12ConstOperations{price: 1})],
             native: EthereumBuiltin::from_str("bls12_381_g2_multiexp").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2607,6 +3011,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => 	Pricing::Bls12Pairing(Bls12PairingPricer{price: Bls12PairingPrice{base: 1, pair: 1}})],
             native: EthereumBuiltin::from_str("bls12_381_pairing").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2641,6 +3046,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => 	Pricing::Bls12Pairing(Bls12PairingPricer{price: Bls12PairingPrice{base: 1, pair: 1}})],
             native: EthereumBuiltin::from_str("bls12_381_fp_to_g1").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2663,6 +3069,7 @@ mod tests {
         let f = Builtin {
             pricer: btreemap![0 => 	Pricing::Bls12Pairing(Bls12PairingPricer{price: Bls12PairingPrice{base: 1, pair: 1}})],
             native: EthereumBuiltin::from_str("bls12_381_fp2_to_g2").unwrap(),
+            deactivate_at: None,
         };
 
         let input = hex!("
@@ -2683,6 +3090,331 @@ mod tests {
         assert_eq!(&output[..], &expected[..]);
     }
 
+    #[test]
+    fn p256_verify() {
+        let f = Builtin::try_from(JsonBuiltin {
+            name: "p256_verify".to_owned(),
+            pricing: map![
+                0 => PricingAt {
+                    info: None,
+                    price: JsonPricing::Linear(JsonLinearPricing { base: 3450, word: 0 })
+                }
+            ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
+        })
+        .expect("known builtin");
+
+        let n = "ffffffff00000000ffffffffffffffffbce6faada7179e84f3b9cac2fc632551";
+        let hash = "4b949c130904506119a31ad2ca94bc9a97f56be914676fe08a5de594ea4c96bd";
+        let r = "3f1c0fd850c64221a0ed758ced45c0c60b301c82a1f143c94d8dd6bb1ad2f617";
+        let s = "d3eee03a433de2bac8717482641498dac9fd67edca3b73e6e2f0a0fbb6b1d793";
+        let x = "9fad84aeae08bbef7f010014d82cef6a09de2b0cf871b5ce0c4f1d13a59a5934";
+        let y = "07cb45769f1070e2c2470fe5b1bfe63133c0b0cdc64ea4bf3791a8ec2a07fd4f";
+        let zero = "0000000000000000000000000000000000000000000000000000000000000000";
+
+        let cases = vec![
+            (
+                "valid signature",
+                format!("{}{}{}{}{}", hash, r, s, x, y),
+                true,
+            ),
+            (
+                "valid signature of another key",
+                concat!(
+                    "e56b08e9a8b81a879cc29a691edc10e1c81d1eaea64a59e2afa3f72bd17b292e",
+                    "7f15ffeab7fff914162db61313655438bcf8a68872b8a1be32e2b751c6cee002",
+                    "1b7870858b115b2e46bac95c1243de499c9cc0d895c5f0329991968bec6bf122",
+                    "044e92f4e3b47eb4d594fe194bfef0f01e0f38a91e28a6b4ca860deb5af539b8",
+                    "c77b8fe09aaf5f974602a599fc358a679cba339ea5556c3bcd76a0c7df772b01",
+                )
+                .to_owned(),
+                true,
+            ),
+            (
+                "malleated s (n - s) is accepted",
+                format!(
+                    "{}{}{}{}{}",
+                    hash,
+                    r,
+                    "2c111fc4bcc21d46378e8b7d9beb6724f2e992bfdcdc2a9e10c929c745b14dbe",
+                    x,
+                    y
+                ),
+                true,
+            ),
+            (
+                "modified hash",
+                format!(
+                    "{}{}{}{}{}",
+                    "4b949c130904506119a31ad2ca94bc9a97f56be914676fe08a5de594ea4c96be", r, s, x, y
+                ),
+                false,
+            ),
+            (
+                "r and s swapped",
+                format!("{}{}{}{}{}", hash, s, r, x, y),
+                false,
+            ),
+            ("r = 0", format!("{}{}{}{}{}", hash, zero, s, x, y), false),
+            ("s = 0", format!("{}{}{}{}{}", hash, r, zero, x, y), false),
+            ("r = n", format!("{}{}{}{}{}", hash, n, s, x, y), false),
+            ("s = n", format!("{}{}{}{}{}", hash, r, n, x, y), false),
+            (
+                "point not on curve",
+                format!(
+                    "{}{}{}{}{}",
+                    hash,
+                    r,
+                    s,
+                    x,
+                    "07cb45769f1070e2c2470fe5b1bfe63133c0b0cdc64ea4bf3791a8ec2a07fd50"
+                ),
+                false,
+            ),
+            (
+                "point at infinity",
+                format!("{}{}{}{}{}", hash, r, s, zero, zero),
+                false,
+            ),
+            (
+                "input too short",
+                format!("{}{}{}{}{}", hash, r, s, x, &y[..62]),
+                false,
+            ),
+            (
+                "input too long",
+                format!("{}{}{}{}{}00", hash, r, s, x, y),
+                false,
+            ),
+            ("empty input", String::new(), false),
+        ];
+
+        for (name, input, valid) in cases {
+            let input: Vec<u8> = input.from_hex().unwrap();
+            assert_eq!(f.cost(&input, 0), U256::from(3450), "{}", name);
+
+            let mut output = Vec::new();
+            f.execute(&input, &mut BytesRef::Flexible(&mut output))
+                .expect("Builtin should not fail");
+            let expected = if valid {
+                hex!("0000000000000000000000000000000000000000000000000000000000000001").to_vec()
+            } else {
+                Vec::new()
+            };
+            assert_eq!(output, expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn p256_verify_wycheproof() {
+        // Wycheproof ECDSA secp256r1 with SHA-256 vectors, in the go-ethereum precompile test format.
+        // Signatures that are not valid DER cannot be expressed as input and are left out.
+        let vectors: Vec<serde_json::Value> =
+            serde_json::from_str(include_str!("../res/p256_verify_wycheproof.json"))
+                .expect("valid test vectors");
+        let f = EthereumBuiltin::from_str("p256_verify").expect("known builtin");
+
+        for vector in vectors {
+            let input: Vec<u8> = vector["Input"].as_str().unwrap().from_hex().unwrap();
+            let expected: Vec<u8> = vector["Expected"].as_str().unwrap().from_hex().unwrap();
+            let mut output = Vec::new();
+            f.execute(&input, &mut BytesRef::Flexible(&mut output))
+                .expect("Builtin should not fail");
+            assert_eq!(output, expected, "{}", vector["Name"]);
+        }
+    }
+
+    #[test]
+    fn kzg_point_evaluation() {
+        let f = Builtin::try_from(JsonBuiltin {
+            name: "kzg_point_evaluation".to_owned(),
+            pricing: map![
+                0 => PricingAt {
+                    info: None,
+                    price: JsonPricing::Linear(JsonLinearPricing { base: 50000, word: 0 })
+                }
+            ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
+        })
+        .expect("known builtin");
+
+        let versioned_hash = "01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b";
+        let z = "564c0a11a0f704f4fc3e8acfe0f8245f0ad1347b378fbf96e206da11a5d36306";
+        let y = "24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a1";
+        let commitment = "8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7";
+        let proof = "873033e038326e87ed3e1276fd140253fa08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16a";
+        let input = |versioned_hash: &str, z: &str, y: &str, proof: &str| -> Vec<u8> {
+            format!("{}{}{}{}{}", versioned_hash, z, y, commitment, proof)
+                .from_hex()
+                .unwrap()
+        };
+
+        let valid = input(versioned_hash, z, y, proof);
+        assert_eq!(f.cost(&valid, 0), U256::from(50000));
+        let mut output = [0u8; 64];
+        f.execute(&valid, &mut BytesRef::Fixed(&mut output[..]))
+            .expect("Builtin should not fail");
+        assert_eq!(
+            &output[..],
+            &hex!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001")[..]
+        );
+
+        let bls_modulus = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+        let cases = vec![
+            (
+                "versioned hash of another commitment",
+                input(
+                    "01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549c",
+                    z,
+                    y,
+                    proof,
+                ),
+            ),
+            (
+                "unknown versioned hash version",
+                input(
+                    "02e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b",
+                    z,
+                    y,
+                    proof,
+                ),
+            ),
+            (
+                "wrong evaluation",
+                input(
+                    versioned_hash,
+                    z,
+                    "24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a2",
+                    proof,
+                ),
+            ),
+            ("z not in the field", input(versioned_hash, bls_modulus, y, proof)),
+            ("y not in the field", input(versioned_hash, z, bls_modulus, proof)),
+            (
+                "proof not on the curve",
+                input(
+                    versioned_hash,
+                    z,
+                    y,
+                    "873033e038326e87ed3e1276fd140253fa08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16b",
+                ),
+            ),
+            ("input too short", valid[..191].to_vec()),
+            ("empty input", Vec::new()),
+        ];
+
+        for (name, input) in cases {
+            let mut output = [0u8; 64];
+            assert!(
+                f.execute(&input, &mut BytesRef::Fixed(&mut output[..]))
+                    .is_err(),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn kzg_point_evaluation_trusted_setup() {
+        let builtin = |name: &str, trusted_setup: &str| {
+            Builtin::try_from(JsonBuiltin {
+                name: name.to_owned(),
+                pricing: map![
+                    0 => PricingAt {
+                        info: None,
+                        price: JsonPricing::Linear(JsonLinearPricing { base: 50000, word: 0 })
+                    }
+                ],
+                activate_at: None,
+                deactivate_at: None,
+                trusted_setup: Some(trusted_setup.to_owned()),
+            })
+        };
+
+        assert!(builtin("kzg_point_evaluation", "/nonexistent/trusted_setup.txt").is_err());
+        assert!(builtin("identity", "/nonexistent/trusted_setup.txt").is_err());
+
+        // The setup of the Ethereum KZG ceremony, in the format of the consensus specs.
+        let trusted_setup = concat!(env!("CARGO_MANIFEST_DIR"), "/res/trusted_setup.txt");
+        let f = builtin("kzg_point_evaluation", trusted_setup).expect("valid trusted setup");
+        let input = concat!(
+            "01e798154708fe7789429634053cbf9f99b619f9f084048927333fce637f549b",
+            "564c0a11a0f704f4fc3e8acfe0f8245f0ad1347b378fbf96e206da11a5d36306",
+            "24d25032e67a7e6a4910df5834b8fe70e6bcfeeac0352434196bdf4b2485d5a1",
+            "8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7",
+            "873033e038326e87ed3e1276fd140253fa08e9fc25fb2d9a98527fc22a2c9612fbeafdad446cbc7bcdbdcd780af2c16a",
+        )
+        .from_hex()
+        .unwrap();
+        let mut output = [0u8; 64];
+        f.execute(&input, &mut BytesRef::Fixed(&mut output[..]))
+            .expect("Builtin should not fail");
+        assert_eq!(
+            &output[..],
+            &hex!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001")[..]
+        );
+    }
+
+    #[test]
+    fn kzg_point_evaluation_consensus_spec_vector() {
+        // Unmodified input of the consensus-spec test case from
+        // https://github.com/ethereum/c-kzg-4844/blob/main/tests/verify_kzg_proof/kzg-mainnet/verify_kzg_proof_case_correct_proof_31ebd010e6098750/data.yaml
+        let commitment = hex!("8f59a8d2a1a625a17f3fea0fe5eb8c896db3764f3185481bc22f91b4aaffcca25f26936857bc3a7c2539ea8ec3a952b7");
+        let z = hex!("73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000000");
+        let y = hex!("1522a4a7f34e1ea350ae07c29c96c7e79655aa926122e95fe69fcbd932ca49e9");
+        let proof = hex!("a62ad71d14c5719385c0686f1871430475bf3a00f0aa3f7b8dd99a9abc2160744faf0070725e00b60ad9a026a15b1a8c");
+
+        let mut versioned_hash = digest::sha256(&commitment[..]).to_vec();
+        versioned_hash[0] = 0x01;
+        let input = [
+            &versioned_hash[..],
+            &z[..],
+            &y[..],
+            &commitment[..],
+            &proof[..],
+        ]
+        .concat();
+        let f = EthereumBuiltin::from_str("kzg_point_evaluation").expect("known builtin");
+
+        let mut output = Vec::new();
+        // The expected output of the case is `true`, i.e. the proof is valid.
+        f.execute(&input, &mut BytesRef::Flexible(&mut output))
+            .expect("Builtin should not fail");
+        assert_eq!(
+            output,
+            hex!("000000000000000000000000000000000000000000000000000000000000100073eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001").to_vec()
+        );
+    }
+
+    #[test]
+    fn kzg_point_evaluation_vectors() {
+        // Generated with c-kzg after the cases of the consensus-spec `verify_kzg_proof` tests
+        // (correct proofs for the spec blobs and points, incorrect proofs and evaluations, invalid
+        // points and field elements), as precompile input. An empty `Expected` means the execution
+        // fails.
+        let vectors: Vec<serde_json::Value> =
+            serde_json::from_str(include_str!("../res/kzg_point_evaluation.json"))
+                .expect("valid test vectors");
+        let f = EthereumBuiltin::from_str("kzg_point_evaluation").expect("known builtin");
+
+        for vector in vectors {
+            let input: Vec<u8> = vector["Input"].as_str().unwrap().from_hex().unwrap();
+            let expected: Vec<u8> = vector["Expected"].as_str().unwrap().from_hex().unwrap();
+            let mut output = Vec::new();
+            let result = f.execute(&input, &mut BytesRef::Flexible(&mut output));
+            if expected.is_empty() {
+                assert!(result.is_err(), "{}", vector["Name"]);
+            } else {
+                assert!(result.is_ok(), "{}", vector["Name"]);
+                assert_eq!(output, expected, "{}", vector["Name"]);
+            }
+        }
+    }
+
     #[test]
     fn bls12_381_g1_multiexp_init_from_spec() {
         use ethjson::spec::builtin::{Bls12G1Multiexp, Pricing};
@@ -2697,6 +3429,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -2722,6 +3457,9 @@ mod tests {
                     }),
                 }
             ],
+            activate_at: None,
+            deactivate_at: None,
+            trusted_setup: None,
         })
         .unwrap();
 
@@ -68,6 +68,8 @@ criterion_group!(
     mem_gas_calculation_increasing_u256,
     blockhash_mulmod_small,
     blockhash_mulmod_large,
+    basic_block_loop,
+    basic_block_straight_line,
 );
 criterion_main!(basic);
 
@@ -244,6 +246,43 @@ fn run_code(b: &mut Bencher, code: Bytes) {
     });
 }
 
+/// Static gas instructions: `PUSH1 0x01 PUSH1 0x02 ADD PUSH1 0x03 MUL POP`.
+const STATIC_GAS_BODY: &str = "600160020160030250";
+
+/// Loop 1000 times over a basic block of 32 static gas bodies, whose gas is precharged on
+/// entry. The code hash is set, so that its basic blocks are analyzed only once.
+fn basic_block_loop(b: &mut Criterion) {
+    b.bench_function("basic block of static gas instructions, 1000 times", |b| {
+        let code = format!("6103e85b60019003{}80600357", STATIC_GAS_BODY.repeat(32))
+            .from_hex()
+            .unwrap();
+        let code_hash = hash::keccak(&code);
+        let factory = Factory::default();
+        let mut ext = FakeExt::new();
+        b.iter(|| {
+            let mut params = ActionParams::default();
+            params.address = Address::from_str("0f572e5295c57f15886f9b263e2f6d2d6c7b5ec6").unwrap();
+            params.gas = U256::MAX;
+            params.code = Some(Arc::new(black_box(code.clone())));
+            params.code_hash = Some(code_hash);
+            let vm = factory.create(params, ext.schedule(), 0);
+            result(vm.exec(&mut ext).ok().unwrap())
+        });
+    });
+}
+
+/// Run 24KB of straight-line static gas code once, including its basic block analysis.
+fn basic_block_straight_line(b: &mut Criterion) {
+    b.bench_function("basic block analysis of 24KB straight-line code", |b| {
+        run_code(
+            b,
+            format!("{}00", STATIC_GAS_BODY.repeat(2730))
+                .from_hex()
+                .unwrap(),
+        );
+    });
+}
+
 /// Compute mulmod(U256::MAX, U256::MAX, 1) 500 times.
 fn mulmod1_500(b: &mut Criterion) {
     b.bench_function("mulmod modulo 1, 500 times", |b| {
@@ -0,0 +1,656 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EVM object format (EOF) v1 containers.
+//!
+//! See https://eips.ethereum.org/EIPS/eip-3540 for the container layout,
+//! https://eips.ethereum.org/EIPS/eip-3670 for code validation,
+//! https://eips.ethereum.org/EIPS/eip-4200 for relative jumps and
+//! https://eips.ethereum.org/EIPS/eip-4750 for functions.
+
+use bit_set::BitSet;
+use instructions::{self, Instruction};
+use std::fmt;
+use vm::Schedule;
+
+/// Prefix of every EOF container.
+pub const MAGIC: [u8; 2] = [0xef, 0x00];
+/// The only supported container version.
+pub const VERSION: u8 = 0x01;
+/// Maximum number of code sections in a container.
+pub const MAX_CODE_SECTIONS: usize = 1024;
+/// Maximum depth of the function return stack.
+pub const MAX_RETURN_STACK_SIZE: usize = 1024;
+/// Maximum value of a code section `max_stack_height`.
+pub const MAX_STACK_HEIGHT: usize = 1023;
+/// Maximum number of function inputs or outputs.
+pub const MAX_FUNCTION_IO: u8 = 0x7f;
+
+const KIND_TYPE: u8 = 0x01;
+const KIND_CODE: u8 = 0x02;
+const KIND_DATA: u8 = 0x03;
+const TERMINATOR: u8 = 0x00;
+const TYPE_ENTRY_SIZE: usize = 4;
+/// Designated invalid instruction, a valid terminating instruction in EOF code.
+const INVALID: u8 = 0xfe;
+
+/// Returns true if the code is formatted as an EOF container, valid or not.
+pub fn is_eof(code: &[u8]) -> bool {
+    code.starts_with(&MAGIC)
+}
+
+/// Returns true if the instruction may not appear in EOF code under given schedule.
+pub fn is_deprecated(instruction: Instruction, schedule: &Schedule) -> bool {
+    match instruction {
+        instructions::CALLCODE | instructions::SUICIDE => schedule.eip3670,
+        instructions::JUMP | instructions::JUMPI | instructions::PC => schedule.eip4750,
+        instructions::BEGINSUB | instructions::RETURNSUB | instructions::JUMPSUB => true,
+        _ => false,
+    }
+}
+
+/// Container validation error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Container is shorter than its header declares.
+    Truncated,
+    /// Container does not start with the EOF magic.
+    InvalidMagic,
+    /// Container version is not supported.
+    UnsupportedVersion(u8),
+    /// Section header is missing or out of order.
+    InvalidSectionHeader,
+    /// Number or size of code sections is not allowed.
+    InvalidCodeSections,
+    /// Type section does not describe the code sections.
+    InvalidTypeSection,
+    /// Container has bytes after the data section.
+    TrailingBytes,
+    /// Code section contains an undefined or disallowed instruction.
+    UndefinedInstruction {
+        /// Code section index.
+        section: usize,
+        /// Offset of the instruction within the section.
+        position: usize,
+        /// The offending opcode.
+        opcode: u8,
+    },
+    /// Instruction immediate runs past the end of its code section.
+    TruncatedImmediate {
+        /// Code section index.
+        section: usize,
+        /// Offset of the instruction within the section.
+        position: usize,
+    },
+    /// Code section does not end with a terminating instruction.
+    MissingTerminatingInstruction {
+        /// Code section index.
+        section: usize,
+    },
+    /// Relative jump lands outside its code section or within immediate data.
+    InvalidJumpDestination {
+        /// Code section index.
+        section: usize,
+        /// Offset of the jump instruction within the section.
+        position: usize,
+    },
+    /// `CALLF` refers to a code section which does not exist.
+    InvalidFunctionIndex {
+        /// Code section index.
+        section: usize,
+        /// Offset of the instruction within the section.
+        position: usize,
+        /// Called code section index.
+        index: usize,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use self::Error::*;
+        match *self {
+            Truncated => write!(f, "Container truncated"),
+            InvalidMagic => write!(f, "Invalid container magic"),
+            UnsupportedVersion(version) => write!(f, "Unsupported container version {}", version),
+            InvalidSectionHeader => write!(f, "Invalid section header"),
+            InvalidCodeSections => write!(f, "Invalid number or size of code sections"),
+            InvalidTypeSection => write!(f, "Invalid type section"),
+            TrailingBytes => write!(f, "Trailing bytes after data section"),
+            UndefinedInstruction {
+                section,
+                position,
+                opcode,
+            } => write!(
+                f,
+                "Undefined instruction {:#x} at {} in code section {}",
+                opcode, position, section
+            ),
+            TruncatedImmediate { section, position } => write!(
+                f,
+                "Truncated immediate at {} in code section {}",
+                position, section
+            ),
+            MissingTerminatingInstruction { section } => write!(
+                f,
+                "Code section {} does not end with a terminating instruction",
+                section
+            ),
+            InvalidJumpDestination { section, position } => write!(
+                f,
+                "Invalid relative jump destination at {} in code section {}",
+                position, section
+            ),
+            InvalidFunctionIndex {
+                section,
+                position,
+                index,
+            } => write!(
+                f,
+                "Call to unknown code section {} at {} in code section {}",
+                index, position, section
+            ),
+        }
+    }
+}
+
+/// Code section of a container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodeSection {
+    /// Offset of the section within the container.
+    pub offset: usize,
+    /// Size of the section.
+    pub size: usize,
+    /// Number of stack items the function takes.
+    pub inputs: u8,
+    /// Number of stack items the function returns.
+    pub outputs: u8,
+    /// Maximum stack height the function reaches.
+    pub max_stack_height: u16,
+}
+
+/// Parsed and validated EOF container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Container {
+    /// Code sections, the first one is the entry point.
+    pub code_sections: Vec<CodeSection>,
+    /// Offset of the data section within the container.
+    pub data_offset: usize,
+    /// Size of the data section.
+    pub data_size: usize,
+}
+
+impl Container {
+    /// Parses the container and validates it against the rules enabled in given schedule.
+    pub fn parse(code: &[u8], schedule: &Schedule) -> Result<Container, Error> {
+        if !is_eof(code) {
+            return Err(Error::InvalidMagic);
+        }
+
+        let mut header = HeaderReader {
+            code,
+            position: MAGIC.len(),
+        };
+        let version = header.read_u8()?;
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        header.expect_kind(KIND_TYPE)?;
+        let types_size = header.read_u16()? as usize;
+        header.expect_kind(KIND_CODE)?;
+        let sections = header.read_u16()? as usize;
+        if sections == 0 || sections > MAX_CODE_SECTIONS || (sections > 1 && !schedule.eip4750) {
+            return Err(Error::InvalidCodeSections);
+        }
+        if types_size != sections * TYPE_ENTRY_SIZE {
+            return Err(Error::InvalidTypeSection);
+        }
+        let mut sizes = Vec::with_capacity(sections);
+        for _ in 0..sections {
+            let size = header.read_u16()? as usize;
+            if size == 0 {
+                return Err(Error::InvalidCodeSections);
+            }
+            sizes.push(size);
+        }
+        header.expect_kind(KIND_DATA)?;
+        let data_size = header.read_u16()? as usize;
+        header.expect_kind(TERMINATOR)?;
+
+        let types_offset = header.position;
+        let code_offset = types_offset + types_size;
+        let data_offset = code_offset + sizes.iter().sum::<usize>();
+        let end = data_offset + data_size;
+        if code.len() < end {
+            return Err(Error::Truncated);
+        }
+        if code.len() > end {
+            return Err(Error::TrailingBytes);
+        }
+
+        let mut code_sections = Vec::with_capacity(sections);
+        let mut offset = code_offset;
+        for (index, size) in sizes.into_iter().enumerate() {
+            let entry = types_offset + index * TYPE_ENTRY_SIZE;
+            let section = CodeSection {
+                offset,
+                size,
+                inputs: code[entry],
+                outputs: code[entry + 1],
+                max_stack_height: read_u16(code, entry + 2),
+            };
+            if (index == 0 && (section.inputs != 0 || section.outputs != 0))
+                || section.inputs > MAX_FUNCTION_IO
+                || section.outputs > MAX_FUNCTION_IO
+                || section.max_stack_height as usize > MAX_STACK_HEIGHT
+            {
+                return Err(Error::InvalidTypeSection);
+            }
+            offset += size;
+            code_sections.push(section);
+        }
+
+        if schedule.eip3670 {
+            for (index, section) in code_sections.iter().enumerate() {
+                let section_code = &code[section.offset..section.offset + section.size];
+                validate_code(section_code, index, sections, schedule)?;
+            }
+        }
+
+        Ok(Container {
+            code_sections,
+            data_offset,
+            data_size,
+        })
+    }
+}
+
+struct HeaderReader<'a> {
+    code: &'a [u8],
+    position: usize,
+}
+
+impl<'a> HeaderReader<'a> {
+    fn read_u8(&mut self) -> Result<u8, Error> {
+        let value = *self.code.get(self.position).ok_or(Error::Truncated)?;
+        self.position += 1;
+        Ok(value)
+    }
+
+    fn read_u16(&mut self) -> Result<u16, Error> {
+        Ok((self.read_u8()? as u16) << 8 | self.read_u8()? as u16)
+    }
+
+    fn expect_kind(&mut self, kind: u8) -> Result<(), Error> {
+        match self.read_u8()? {
+            k if k == kind => Ok(()),
+            _ => Err(Error::InvalidSectionHeader),
+        }
+    }
+}
+
+fn read_u16(code: &[u8], position: usize) -> u16 {
+    (code[position] as u16) << 8 | code[position + 1] as u16
+}
+
+/// Checks instructions, immediates and relative jump destinations of a single code section.
+fn validate_code(
+    code: &[u8],
+    section: usize,
+    sections: usize,
+    schedule: &Schedule,
+) -> Result<(), Error> {
+    let mut immediates = BitSet::with_capacity(code.len());
+    let mut jumps = Vec::new();
+    let mut position = 0;
+    let mut terminated = false;
+
+    while position < code.len() {
+        let opcode = code[position];
+        let instruction = match Instruction::from_u8(opcode) {
+            Some(instruction)
+                if instruction.is_enabled(schedule) && !is_deprecated(instruction, schedule) =>
+            {
+                instruction
+            }
+            None if opcode == INVALID => {
+                terminated = true;
+                position += 1;
+                continue;
+            }
+            _ => {
+                return Err(Error::UndefinedInstruction {
+                    section,
+                    position,
+                    opcode,
+                })
+            }
+        };
+
+        let immediate_size = match instruction {
+            instructions::RJUMP | instructions::RJUMPI | instructions::CALLF => 2,
+            instructions::RJUMPV => match code.get(position + 1) {
+                Some(max_index) => 1 + 2 * (*max_index as usize + 1),
+                None => return Err(Error::TruncatedImmediate { section, position }),
+            },
+            _ => instruction.push_bytes().unwrap_or(0),
+        };
+        let next = position + 1 + immediate_size;
+        if next > code.len() {
+            return Err(Error::TruncatedImmediate { section, position });
+        }
+
+        match instruction {
+            instructions::RJUMP | instructions::RJUMPI => {
+                let offset = read_u16(code, position + 1) as i16;
+                jumps.push((position, next as isize + offset as isize));
+            }
+            instructions::RJUMPV => {
+                for case in 0..=(code[position + 1] as usize) {
+                    let offset = read_u16(code, position + 2 + 2 * case) as i16;
+                    jumps.push((position, next as isize + offset as isize));
+                }
+            }
+            instructions::CALLF => {
+                let index = read_u16(code, position + 1) as usize;
+                if index >= sections {
+                    return Err(Error::InvalidFunctionIndex {
+                        section,
+                        position,
+                        index,
+                    });
+                }
+            }
+            _ => {}
+        }
+
+        for immediate in position + 1..next {
+            immediates.insert(immediate);
+        }
+        terminated = matches!(
+            instruction,
+            instructions::STOP
+                | instructions::RETURN
+                | instructions::REVERT
+                | instructions::RETF
+                | instructions::RJUMP
+        );
+        position = next;
+    }
+
+    if !terminated {
+        return Err(Error::MissingTerminatingInstruction { section });
+    }
+
+    for (position, destination) in jumps {
+        if destination < 0
+            || destination as usize >= code.len()
+            || immediates.contains(destination as usize)
+        {
+            return Err(Error::InvalidJumpDestination { section, position });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn schedule() -> Schedule {
+        let mut schedule = Schedule::new_london();
+        schedule.eip3540 = true;
+        schedule.eip3670 = true;
+        schedule.eip4200 = true;
+        schedule.eip4750 = true;
+        schedule
+    }
+
+    /// Builds a container from `(inputs, outputs, max_stack_height, code)` sections.
+    fn container(sections: &[(u8, u8, u16, &[u8])], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0xef, 0x00, VERSION, KIND_TYPE];
+        let types_size = sections.len() * TYPE_ENTRY_SIZE;
+        bytes.extend_from_slice(&[(types_size >> 8) as u8, types_size as u8, KIND_CODE]);
+        bytes.extend_from_slice(&[(sections.len() >> 8) as u8, sections.len() as u8]);
+        for &(_, _, _, code) in sections {
+            bytes.extend_from_slice(&[(code.len() >> 8) as u8, code.len() as u8]);
+        }
+        bytes.extend_from_slice(&[KIND_DATA, (data.len() >> 8) as u8, data.len() as u8]);
+        bytes.push(TERMINATOR);
+        for &(inputs, outputs, max_stack_height, _) in sections {
+            bytes.extend_from_slice(&[inputs, outputs]);
+            bytes.extend_from_slice(&[(max_stack_height >> 8) as u8, max_stack_height as u8]);
+        }
+        for &(_, _, _, code) in sections {
+            bytes.extend_from_slice(code);
+        }
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn should_parse_minimal_container() {
+        let code = hex!("ef000101000402000100010300000000000000fe");
+        assert_eq!(code.to_vec(), container(&[(0, 0, 0, &[0xfe])], &[]));
+
+        let parsed = Container::parse(&code, &schedule()).unwrap();
+
+        assert_eq!(
+            parsed.code_sections,
+            vec![CodeSection {
+                offset: 19,
+                size: 1,
+                inputs: 0,
+                outputs: 0,
+                max_stack_height: 0,
+            }]
+        );
+        assert_eq!(parsed.data_offset, 20);
+        assert_eq!(parsed.data_size, 0);
+    }
+
+    #[test]
+    fn should_reject_malformed_headers() {
+        let valid = container(&[(0, 0, 0, &[0x00])], &[0xaa, 0xbb]);
+        let schedule = schedule();
+        assert!(Container::parse(&valid, &schedule).is_ok());
+
+        assert_eq!(
+            Container::parse(&hex!("6000"), &schedule),
+            Err(Error::InvalidMagic)
+        );
+        assert_eq!(
+            Container::parse(&hex!("ef0002"), &schedule),
+            Err(Error::UnsupportedVersion(2))
+        );
+        assert_eq!(
+            Container::parse(&valid[..10], &schedule),
+            Err(Error::Truncated)
+        );
+        assert_eq!(
+            Container::parse(&valid[..valid.len() - 1], &schedule),
+            Err(Error::Truncated)
+        );
+
+        let mut trailing = valid.clone();
+        trailing.push(0);
+        assert_eq!(
+            Container::parse(&trailing, &schedule),
+            Err(Error::TrailingBytes)
+        );
+
+        let mut wrong_kind = valid.clone();
+        wrong_kind[3] = KIND_CODE;
+        assert_eq!(
+            Container::parse(&wrong_kind, &schedule),
+            Err(Error::InvalidSectionHeader)
+        );
+
+        let mut wrong_types = valid.clone();
+        wrong_types[5] = 8;
+        assert_eq!(
+            Container::parse(&wrong_types, &schedule),
+            Err(Error::InvalidTypeSection)
+        );
+    }
+
+    #[test]
+    fn should_validate_type_section() {
+        let schedule = schedule();
+        assert_eq!(
+            Container::parse(&container(&[(1, 0, 1, &[0x00])], &[]), &schedule),
+            Err(Error::InvalidTypeSection)
+        );
+        assert_eq!(
+            Container::parse(
+                &container(&[(0, 0, 0, &[0x00]), (0x80, 0, 0x80, &[0xe4])], &[]),
+                &schedule
+            ),
+            Err(Error::InvalidTypeSection)
+        );
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 1024, &[0x00])], &[]), &schedule),
+            Err(Error::InvalidTypeSection)
+        );
+    }
+
+    #[test]
+    fn should_require_eip4750_for_multiple_sections() {
+        let code = container(&[(0, 0, 0, &hex!("e3000100")), (0, 0, 0, &[0xe4])], &[]);
+        let mut schedule = schedule();
+        assert!(Container::parse(&code, &schedule).is_ok());
+
+        schedule.eip4750 = false;
+        assert_eq!(
+            Container::parse(&code, &schedule),
+            Err(Error::InvalidCodeSections)
+        );
+    }
+
+    #[test]
+    fn should_validate_instructions() {
+        let schedule = schedule();
+        // undefined instruction
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &[0x0c, 0x00])], &[]), &schedule),
+            Err(Error::UndefinedInstruction {
+                section: 0,
+                position: 0,
+                opcode: 0x0c,
+            })
+        );
+        // deprecated instruction
+        assert_eq!(
+            Container::parse(
+                &container(&[(0, 0, 0, &hex!("6000565b00"))], &[]),
+                &schedule
+            ),
+            Err(Error::UndefinedInstruction {
+                section: 0,
+                position: 2,
+                opcode: 0x56,
+            })
+        );
+        // truncated push
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &[0x61, 0x00])], &[]), &schedule),
+            Err(Error::TruncatedImmediate {
+                section: 0,
+                position: 0,
+            })
+        );
+        // missing terminating instruction
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &[0x5b])], &[]), &schedule),
+            Err(Error::MissingTerminatingInstruction { section: 0 })
+        );
+        // push data ending with STOP opcode does not terminate
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &[0x60, 0x00])], &[]), &schedule),
+            Err(Error::MissingTerminatingInstruction { section: 0 })
+        );
+    }
+
+    #[test]
+    fn should_skip_code_validation_without_eip3670() {
+        let code = container(&[(0, 0, 0, &[0x0c])], &[]);
+        let mut schedule = schedule();
+        schedule.eip3670 = false;
+
+        assert!(Container::parse(&code, &schedule).is_ok());
+    }
+
+    #[test]
+    fn should_validate_relative_jumps() {
+        let schedule = schedule();
+        // RJUMPI +1 over STOP, STOP
+        let valid = hex!("6001e100010000");
+        assert!(Container::parse(&container(&[(0, 0, 1, &valid)], &[]), &schedule).is_ok());
+
+        // RJUMP -3 to itself
+        assert!(
+            Container::parse(&container(&[(0, 0, 0, &hex!("e0fffd"))], &[]), &schedule).is_ok()
+        );
+
+        // RJUMP into its own immediate
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &hex!("e0fffe"))], &[]), &schedule),
+            Err(Error::InvalidJumpDestination {
+                section: 0,
+                position: 0,
+            })
+        );
+
+        // RJUMPV with two cases, the second one past the end of the section
+        assert_eq!(
+            Container::parse(
+                &container(&[(0, 0, 1, &hex!("6000e2010000001000"))], &[]),
+                &schedule
+            ),
+            Err(Error::InvalidJumpDestination {
+                section: 0,
+                position: 2,
+            })
+        );
+
+        // truncated jump table
+        assert_eq!(
+            Container::parse(
+                &container(&[(0, 0, 1, &hex!("6000e2010000"))], &[]),
+                &schedule
+            ),
+            Err(Error::TruncatedImmediate {
+                section: 0,
+                position: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn should_validate_function_calls() {
+        let schedule = schedule();
+        assert_eq!(
+            Container::parse(&container(&[(0, 0, 0, &hex!("e3000100"))], &[]), &schedule),
+            Err(Error::InvalidFunctionIndex {
+                section: 0,
+                position: 0,
+                index: 1,
+            })
+        );
+    }
+}
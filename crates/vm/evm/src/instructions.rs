@@ -18,6 +18,8 @@
 
 pub use self::Instruction::*;
 
+use vm::Schedule;
+
 macro_rules! enum_with_from_u8 {
 	(
 		$( #[$enum_attr:meta] )*
@@ -330,6 +332,17 @@ enum_with_from_u8! {
         #[doc = "Jumps to a defined BEGINSUB subroutine."]
         JUMPSUB = 0x5e,
 
+        #[doc = "Relative jump by a static offset, EOF only."]
+        RJUMP = 0xe0,
+        #[doc = "Conditional relative jump by a static offset, EOF only."]
+        RJUMPI = 0xe1,
+        #[doc = "Relative jump through a static jump table, EOF only."]
+        RJUMPV = 0xe2,
+        #[doc = "Calls a function code section, EOF only."]
+        CALLF = 0xe3,
+        #[doc = "Returns from a function code section, EOF only."]
+        RETF = 0xe4,
+
         #[doc = "create a new account with associated code"]
         CREATE = 0xf0,
         #[doc = "message-call into an account"]
@@ -397,6 +410,31 @@ impl Instruction {
         }
     }
 
+    /// Returns true if the instruction may only appear in EOF code.
+    pub fn is_eof_only(&self) -> bool {
+        *self >= RJUMP && *self <= RETF
+    }
+
+    /// Returns true if the instruction is activated by given schedule.
+    pub fn is_enabled(&self, schedule: &Schedule) -> bool {
+        match *self {
+            DELEGATECALL => schedule.have_delegate_call,
+            CREATE2 => schedule.have_create2,
+            STATICCALL => schedule.have_static_call,
+            RETURNDATACOPY | RETURNDATASIZE => schedule.have_return_data,
+            REVERT => schedule.have_revert,
+            SHL | SHR | SAR => schedule.have_bitwise_shifting,
+            EXTCODEHASH => schedule.have_extcodehash,
+            CHAINID => schedule.have_chain_id,
+            SELFBALANCE => schedule.have_selfbalance,
+            BASEFEE => schedule.eip3198,
+            BEGINSUB | JUMPSUB | RETURNSUB => schedule.have_subs,
+            RJUMP | RJUMPI | RJUMPV => schedule.eip4200,
+            CALLF | RETF => schedule.eip4750,
+            _ => true,
+        }
+    }
+
     /// Returns the instruction info.
     pub fn info(&self) -> &'static InstructionInfo {
         INSTRUCTIONS[*self as usize].as_ref().expect("A instruction is defined in Instruction enum, but it is not found in InstructionInfo struct; this indicates a logic failure in the code.")
@@ -604,6 +642,11 @@ lazy_static! {
         arr[BEGINSUB as usize] = Some(InstructionInfo::new("BEGINSUB", 0, 0, GasPriceTier::Base));
         arr[JUMPSUB as usize] = Some(InstructionInfo::new("JUMPSUB", 1, 0, GasPriceTier::High));
         arr[RETURNSUB as usize] = Some(InstructionInfo::new("RETURNSUB", 0, 0, GasPriceTier::Low));
+        arr[RJUMP as usize] = Some(InstructionInfo::new("RJUMP", 0, 0, GasPriceTier::Base));
+        arr[RJUMPI as usize] = Some(InstructionInfo::new("RJUMPI", 1, 0, GasPriceTier::Special));
+        arr[RJUMPV as usize] = Some(InstructionInfo::new("RJUMPV", 1, 0, GasPriceTier::Special));
+        arr[CALLF as usize] = Some(InstructionInfo::new("CALLF", 0, 0, GasPriceTier::Low));
+        arr[RETF as usize] = Some(InstructionInfo::new("RETF", 0, 0, GasPriceTier::VeryLow));
         arr[CREATE as usize] = Some(InstructionInfo::new("CREATE", 3, 1, GasPriceTier::Special));
         arr[CALL as usize] = Some(InstructionInfo::new("CALL", 7, 1, GasPriceTier::Special));
         arr[CALLCODE as usize] = Some(InstructionInfo::new("CALLCODE", 7, 1, GasPriceTier::Special));
@@ -652,6 +695,27 @@ mod tests {
         assert_eq!(SWAP10.swap_position(), Some(10));
     }
 
+    #[test]
+    fn test_is_eof_only() {
+        assert!(RJUMP.is_eof_only());
+        assert!(RETF.is_eof_only());
+        assert!(!JUMP.is_eof_only());
+        assert!(!CREATE.is_eof_only());
+    }
+
+    #[test]
+    fn test_is_enabled() {
+        let mut schedule = Schedule::new_london();
+        assert!(BASEFEE.is_enabled(&schedule));
+        assert!(!RJUMPV.is_enabled(&schedule));
+        assert!(!CALLF.is_enabled(&schedule));
+
+        schedule.eip4200 = true;
+        assert!(RJUMPV.is_enabled(&schedule));
+        assert!(!CALLF.is_enabled(&schedule));
+        assert!(!REVERT.is_enabled(&Schedule::new_homestead()));
+    }
+
     #[test]
     fn test_get_log_topics() {
         assert_eq!(LOG0.log_topics(), Some(0));
@@ -0,0 +1,267 @@
+// Copyright 2015-2020 Parity Technologies (UK) Ltd.
+// This file is part of OpenEthereum.
+
+// OpenEthereum is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// OpenEthereum is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Basic block analysis of legacy code.
+//!
+//! A basic block is a run of instructions with static gas cost which is always
+//! executed from its first instruction. Its gas is charged and its stack bounds
+//! are checked once on entry instead of for every instruction.
+
+use super::gasometer;
+use bit_set::BitSet;
+use instructions::{self, Instruction};
+use std::{cmp, mem};
+use vm::Schedule;
+
+/// Static requirements of a basic block.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Number of instructions in the block.
+    pub instructions: usize,
+    /// Sum of the static gas costs of the instructions.
+    pub gas: usize,
+    /// Number of stack items the block needs on entry.
+    pub stack_required: usize,
+    /// Maximal stack growth within the block relative to the entry height.
+    pub stack_max_growth: usize,
+}
+
+/// Basic blocks of a contract code.
+#[derive(Debug, Default)]
+pub struct BasicBlocks {
+    starts: BitSet,
+    blocks: Vec<(usize, BasicBlock)>,
+}
+
+impl BasicBlocks {
+    /// Splits the code into basic blocks.
+    ///
+    /// Static instruction costs are the same in every schedule, so the result
+    /// can be shared between them. Instructions not enabled by a schedule are
+    /// still rejected when they are executed.
+    pub fn analyze(code: &[u8], schedule: &Schedule) -> Self {
+        let mut analysis = BasicBlocks::default();
+        let mut current: Option<(usize, BlockBuilder)> = None;
+        let mut position = 0;
+
+        while position < code.len() {
+            let instruction = Instruction::from_u8(code[position]);
+            let gas = instruction.and_then(|instruction| {
+                gasometer::static_gas(schedule, instruction, instruction.info())
+            });
+            let instruction = match (instruction, gas) {
+                (Some(instruction), Some(gas)) => {
+                    // Jump destinations are always the start of a block.
+                    if instruction == instructions::JUMPDEST {
+                        analysis.finish(current.take());
+                    }
+                    let (_, block) =
+                        current.get_or_insert_with(|| (position, BlockBuilder::default()));
+                    block.push(instruction, gas);
+                    Some(instruction)
+                }
+                // Instructions with dynamic cost are charged when executed.
+                (instruction, _) => {
+                    analysis.finish(current.take());
+                    instruction
+                }
+            };
+
+            if let Some(instruction) = instruction {
+                if ends_block(instruction) {
+                    analysis.finish(current.take());
+                }
+                if let Some(push_bytes) = instruction.push_bytes() {
+                    position += push_bytes;
+                }
+            }
+            position += 1;
+        }
+        analysis.finish(current);
+
+        analysis.starts.shrink_to_fit();
+        analysis.blocks.shrink_to_fit();
+        analysis
+    }
+
+    /// Returns the basic block starting at given position.
+    pub fn get(&self, position: usize) -> Option<&BasicBlock> {
+        if !self.starts.contains(position) {
+            return None;
+        }
+        self.blocks
+            .binary_search_by_key(&position, |&(start, _)| start)
+            .ok()
+            .map(|index| &self.blocks[index].1)
+    }
+
+    /// Approximate heap size of the analysis.
+    pub fn heap_size(&self) -> usize {
+        self.starts.capacity() / 8 + self.blocks.capacity() * mem::size_of::<(usize, BasicBlock)>()
+    }
+
+    fn finish(&mut self, block: Option<(usize, BlockBuilder)>) {
+        if let Some((start, builder)) = block {
+            self.starts.insert(start);
+            self.blocks.push((start, builder.block));
+        }
+    }
+}
+
+/// Sum of the static gas costs of `count` instructions of a basic block, starting
+/// with the instruction at `position`.
+pub fn static_gas(code: &[u8], mut position: usize, count: usize, schedule: &Schedule) -> usize {
+    let mut gas = 0;
+    for _ in 0..count {
+        let instruction = Instruction::from_u8(code[position])
+            .expect("basic blocks consist of valid instructions; qed");
+        gas += gasometer::static_gas(schedule, instruction, instruction.info())
+            .expect("basic blocks consist of instructions with static gas; qed");
+        position += 1 + instruction.push_bytes().unwrap_or(0);
+    }
+    gas
+}
+
+/// Returns true if the instruction may continue anywhere but the next instruction,
+/// or if it observes the gas left.
+fn ends_block(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        instructions::JUMP
+            | instructions::JUMPI
+            | instructions::JUMPSUB
+            | instructions::RETURNSUB
+            | instructions::BEGINSUB
+            | instructions::STOP
+            | instructions::GAS
+    )
+}
+
+#[derive(Default)]
+struct BlockBuilder {
+    block: BasicBlock,
+    height: isize,
+}
+
+impl BlockBuilder {
+    fn push(&mut self, instruction: Instruction, gas: usize) {
+        let info = instruction.info();
+        self.height -= info.args as isize;
+        self.block.stack_required =
+            cmp::max(self.block.stack_required as isize, -self.height) as usize;
+        self.height += info.ret as isize;
+        self.block.stack_max_growth =
+            cmp::max(self.block.stack_max_growth as isize, self.height) as usize;
+        self.block.instructions += 1;
+        self.block.gas += gas;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn should_split_code_into_basic_blocks() {
+        // given
+
+        // 0000 60 01   PUSH1 01
+        // 0002 60 06   PUSH1 06
+        // 0004 57      JUMPI
+        // 0005 00      STOP
+        // 0006 5B      JUMPDEST
+        // 0007 50      POP
+        // 0008 60 00   PUSH1 00
+        // 000A 54      SLOAD
+        // 000B 01      ADD
+        let code = hex!("6001600657005b5060005401");
+        let schedule = Schedule::new_london();
+
+        // when
+        let blocks = BasicBlocks::analyze(&code, &schedule);
+
+        // then
+        assert_eq!(
+            blocks.get(0),
+            Some(&BasicBlock {
+                instructions: 3,
+                gas: 3 + 3 + 10,
+                stack_required: 0,
+                stack_max_growth: 2,
+            })
+        );
+        assert_eq!(
+            blocks.get(5),
+            Some(&BasicBlock {
+                instructions: 1,
+                gas: 0,
+                stack_required: 0,
+                stack_max_growth: 0,
+            })
+        );
+        assert_eq!(
+            blocks.get(6),
+            Some(&BasicBlock {
+                instructions: 3,
+                gas: 1 + 2 + 3,
+                stack_required: 1,
+                stack_max_growth: 0,
+            })
+        );
+        assert_eq!(blocks.get(10), None);
+        assert_eq!(
+            blocks.get(11),
+            Some(&BasicBlock {
+                instructions: 1,
+                gas: 3,
+                stack_required: 2,
+                stack_max_growth: 0,
+            })
+        );
+        assert_eq!(blocks.get(1), None);
+    }
+
+    #[test]
+    fn should_sum_static_gas_of_remaining_instructions() {
+        // PUSH1 01, PUSH1 02, ADD, POP
+        let code = hex!("600160020150");
+        let schedule = Schedule::new_london();
+
+        assert_eq!(static_gas(&code, 0, 4, &schedule), 3 + 3 + 3 + 2);
+        assert_eq!(static_gas(&code, 2, 2, &schedule), 3 + 3);
+        assert_eq!(static_gas(&code, 5, 1, &schedule), 2);
+        assert_eq!(static_gas(&code, 5, 0, &schedule), 0);
+    }
+
+    #[test]
+    fn should_not_start_blocks_in_push_data() {
+        // given
+
+        // 0000 61 5B5B  PUSH2 5B5B
+        // 0003 5B       JUMPDEST
+        let code = hex!("615b5b5b");
+
+        // when
+        let blocks = BasicBlocks::analyze(&code, &Schedule::new_london());
+
+        // then
+        assert_eq!(blocks.get(0).map(|block| block.instructions), Some(1));
+        assert_eq!(blocks.get(1), None);
+        assert_eq!(blocks.get(2), None);
+        assert_eq!(blocks.get(3).map(|block| block.instructions), Some(1));
+    }
+}
@@ -20,7 +20,7 @@ use std::cmp;
 
 use super::stack::VecStack;
 use evm;
-use instructions::{self, Instruction, InstructionInfo};
+use instructions::{self, GasPriceTier, Instruction, InstructionInfo};
 use interpreter::stack::Stack;
 use vm::{self, Schedule};
 
@@ -135,6 +135,7 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
 
         let cost = match instruction {
             instructions::JUMPDEST => Request::Gas(Gas::from(1)),
+            instructions::RJUMPI | instructions::RJUMPV => Request::Gas(Gas::from(4)),
             instructions::SSTORE => {
                 if schedule.eip1706 && self.current_gas <= Gas::from(schedule.call_stipend) {
                     return Err(vm::Error::OutOfGas);
@@ -411,6 +412,31 @@ impl<Gas: evm::CostType> Gasometer<Gas> {
     }
 }
 
+/// Gas cost of an instruction if it does not depend on the machine state, `None` otherwise.
+///
+/// Matches the cost charged by `Gasometer::requirements` for these instructions.
+pub fn static_gas(
+    schedule: &Schedule,
+    instruction: Instruction,
+    info: &InstructionInfo,
+) -> Option<usize> {
+    match instruction {
+        instructions::JUMPDEST => Some(1),
+        instructions::RJUMPI | instructions::RJUMPV => Some(4),
+        instructions::MSTORE
+        | instructions::MLOAD
+        | instructions::MSTORE8
+        | instructions::RETURN
+        | instructions::REVERT
+        | instructions::CALLDATACOPY
+        | instructions::CODECOPY
+        | instructions::RETURNDATACOPY
+        | instructions::BLOCKHASH => None,
+        _ if info.tier == GasPriceTier::Special => None,
+        _ => Some(schedule.tier_step_gas[info.tier.idx()]),
+    }
+}
+
 #[inline]
 fn mem_needed_const<Gas: evm::CostType>(mem: &U256, add: usize) -> vm::Result<Gas> {
     Gas::from_u256(overflowing!(mem.overflowing_add(U256::from(add))))
@@ -564,3 +590,59 @@ fn test_calculate_mem_cost() {
     assert_eq!(new_mem_gas, 3);
     assert_eq!(mem_size, 32);
 }
+
+#[test]
+fn test_static_gas_matches_requirements() {
+    use vm::{tests::FakeExt, Ext};
+
+    // given
+    let schedules = vec![
+        Schedule::new_frontier(),
+        Schedule::new_homestead(),
+        Schedule::new_post_eip150(24576, true, true, true),
+        Schedule::new_byzantium(),
+        Schedule::new_constantinople(),
+        Schedule::new_istanbul(),
+        Schedule::new_berlin(),
+        Schedule::new_london(),
+    ];
+    let london = Schedule::new_london();
+    let mut stack = VecStack::with_capacity(1024, U256::zero());
+    for _ in 0..17 {
+        stack.push(U256::zero());
+    }
+
+    for schedule in schedules {
+        let mut ext = FakeExt::new();
+        ext.schedule = schedule;
+
+        for opcode in 0..=255u8 {
+            let instruction = match Instruction::from_u8(opcode) {
+                Some(instruction) => instruction,
+                None => continue,
+            };
+            let info = instruction.info();
+            let gas = match static_gas(ext.schedule(), instruction, info) {
+                Some(gas) => gas,
+                None => continue,
+            };
+            let mut gasometer = Gasometer::<usize>::new(1_000_000);
+
+            // when
+            let requirements = gasometer
+                .requirements(&ext, instruction, info, &stack, &Address::zero(), 0)
+                .unwrap();
+
+            // then
+            assert_eq!(requirements.gas_cost, gas, "{}", info.name);
+            assert_eq!(requirements.memory_required_size, 0, "{}", info.name);
+            // Basic blocks are shared between schedules.
+            assert_eq!(
+                static_gas(&london, instruction, info),
+                Some(gas),
+                "{}",
+                info.name
+            );
+        }
+    }
+}
@@ -16,6 +16,7 @@
 
 //! Rust VM implementation
 
+mod basic_blocks;
 #[macro_use]
 mod informant;
 mod gasometer;
@@ -34,11 +35,13 @@ use vm::{
     GasLeft, MessageCallResult, ParamsType, ReturnData, Schedule, TrapError, TrapKind,
 };
 
+use eof;
 use evm::CostType;
 use instructions::{self, Instruction, InstructionInfo};
 
 pub use self::shared_cache::SharedCache;
 use self::{
+    basic_blocks::BasicBlocks,
     gasometer::Gasometer,
     memory::Memory,
     stack::{Stack, VecStack},
@@ -96,6 +99,11 @@ impl CodeReader {
         U256::from(&self.code[pos..max])
     }
 
+    /// Read a signed 16-bit relative jump offset. Move PC
+    fn read_relative_offset(&mut self) -> isize {
+        self.read(2).low_u64() as u16 as i16 as isize
+    }
+
     fn len(&self) -> usize {
         self.code.len()
     }
@@ -107,6 +115,8 @@ enum InstructionResult<Gas> {
     JumpToPosition(U256),
     JumpToSubroutine(U256),
     ReturnFromSubroutine(usize),
+    JumpRelative(isize),
+    CallFunction(usize),
     StopExecutionNeedsReturn {
         /// Gas left.
         gas: Gas,
@@ -193,6 +203,10 @@ pub struct Interpreter<Cost: CostType> {
     gasometer: Option<Gasometer<Cost>>,
     stack: VecStack<U256>,
     return_stack: Vec<usize>,
+    eof: Option<Arc<eof::Container>>,
+    basic_blocks: Option<Arc<BasicBlocks>>,
+    /// Instructions left in the current basic block, already charged for on entry.
+    block_remaining: usize,
     resume_output_range: Option<(U256, U256)>,
     resume_result: Option<InstructionResult<Cost>>,
     last_stack_ret_len: usize,
@@ -294,8 +308,23 @@ impl<Cost: CostType> Interpreter<Cost> {
         schedule: &Schedule,
         depth: usize,
     ) -> Interpreter<Cost> {
-        let reader = CodeReader::new(params.code.take().expect("VM always called with code; qed"));
+        let mut reader =
+            CodeReader::new(params.code.take().expect("VM always called with code; qed"));
         let params = InterpreterParams::from(params);
+        // Invalid containers are executed as legacy code and fail on the 0xEF byte.
+        let eof = if schedule.eip3540 && eof::is_eof(&reader.code) {
+            cache.eof_container(&params.code_hash, &reader.code, schedule)
+        } else {
+            None
+        };
+        if let Some(ref eof) = eof {
+            reader.position = eof.code_sections[0].offset;
+        }
+        // EOF code is executed instruction by instruction.
+        let basic_blocks = match eof {
+            Some(_) => None,
+            None => Some(cache.basic_blocks(&params.code_hash, &reader.code, schedule)),
+        };
         let informant = informant::EvmInformant::new(depth);
         let valid_jump_destinations = None;
         let valid_subroutine_destinations = None;
@@ -315,6 +344,9 @@ impl<Cost: CostType> Interpreter<Cost> {
             gasometer,
             stack,
             return_stack,
+            eof,
+            basic_blocks,
+            block_remaining: 0,
             done: false,
             // Overridden in `step_inner` based on
             // the result of `ext.trace_next_instruction`.
@@ -366,6 +398,12 @@ impl<Cost: CostType> Interpreter<Cost> {
                 let instruction = Instruction::from_u8(opcode);
                 self.reader.position += 1;
 
+                // Tracing is decided per instruction and traced instructions are checked
+                // and charged one by one, so leave a precharged block before tracing.
+                if self.do_trace && self.block_remaining > 0 {
+                    self.leave_basic_block(ext.schedule());
+                }
+
                 // TODO: make compile-time removable if too much of a performance hit.
                 self.do_trace = self.do_trace
                     && ext.trace_next_instruction(
@@ -389,54 +427,68 @@ impl<Cost: CostType> Interpreter<Cost> {
 
                 let info = instruction.info();
                 self.last_stack_ret_len = info.ret;
-                if let Err(e) = self.verify_instruction(ext, instruction, info) {
-                    return InterpreterResult::Done(Err(e));
-                };
 
-                // Calculate gas cost
-                let requirements = match self
-                    .gasometer
-                    .as_mut()
-                    .expect(GASOMETER_PROOF)
-                    .requirements(
-                        ext,
-                        instruction,
-                        info,
-                        &self.stack,
-                        &self.params.address,
-                        self.mem.size(),
-                    ) {
-                    Ok(t) => t,
-                    Err(e) => return InterpreterResult::Done(Err(e)),
-                };
-                if self.do_trace {
-                    ext.trace_prepare_execute(
-                        self.reader.position - 1,
-                        opcode,
-                        requirements.gas_cost.as_u256(),
-                        Self::mem_written(instruction, &self.stack),
-                        Self::store_written(instruction, &self.stack),
-                    );
+                if self.block_remaining == 0 && !self.do_trace {
+                    self.enter_basic_block(ext.schedule());
                 }
-                if let Err(e) = self
-                    .gasometer
-                    .as_mut()
-                    .expect(GASOMETER_PROOF)
-                    .verify_gas(&requirements.gas_cost)
-                {
+                let provided = if self.block_remaining > 0 {
+                    // Gas and stack requirements were checked on entering the block.
+                    self.block_remaining -= 1;
+                    if let Err(e) = self.verify_instruction_enabled(ext.schedule(), instruction) {
+                        return InterpreterResult::Done(Err(e));
+                    }
+                    None
+                } else {
+                    if let Err(e) = self.verify_instruction(ext, instruction, info) {
+                        return InterpreterResult::Done(Err(e));
+                    };
+
+                    // Calculate gas cost
+                    let requirements = match self
+                        .gasometer
+                        .as_mut()
+                        .expect(GASOMETER_PROOF)
+                        .requirements(
+                            ext,
+                            instruction,
+                            info,
+                            &self.stack,
+                            &self.params.address,
+                            self.mem.size(),
+                        ) {
+                        Ok(t) => t,
+                        Err(e) => return InterpreterResult::Done(Err(e)),
+                    };
                     if self.do_trace {
-                        ext.trace_failed();
+                        ext.trace_prepare_execute(
+                            self.reader.position - 1,
+                            opcode,
+                            requirements.gas_cost.as_u256(),
+                            Self::mem_written(instruction, &self.stack),
+                            Self::store_written(instruction, &self.stack),
+                        );
                     }
-                    return InterpreterResult::Done(Err(e));
-                }
-                self.mem.expand(requirements.memory_required_size);
-                self.gasometer
-                    .as_mut()
-                    .expect(GASOMETER_PROOF)
-                    .current_mem_gas = requirements.memory_total_gas;
-                self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas =
-                    self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas
-                        - requirements.gas_cost;
+                    if let Err(e) = self
+                        .gasometer
+                        .as_mut()
+                        .expect(GASOMETER_PROOF)
+                        .verify_gas(&requirements.gas_cost)
+                    {
+                        if self.do_trace {
+                            ext.trace_failed();
+                        }
+                        return InterpreterResult::Done(Err(e));
+                    }
+                    self.mem.expand(requirements.memory_required_size);
+                    self.gasometer
+                        .as_mut()
+                        .expect(GASOMETER_PROOF)
+                        .current_mem_gas = requirements.memory_total_gas;
+                    self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas =
+                        self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas
+                            - requirements.gas_cost;
+                    requirements.provide_gas
+                };
 
                 evm_debug!({
                     self.informant.before_instruction(
@@ -450,12 +502,7 @@ impl<Cost: CostType> Interpreter<Cost> {
 
                 // Execute instruction
                 let current_gas = self.gasometer.as_mut().expect(GASOMETER_PROOF).current_gas;
-                let result = match self.exec_instruction(
-                    current_gas,
-                    ext,
-                    instruction,
-                    requirements.provide_gas,
-                ) {
+                let result = match self.exec_instruction(current_gas, ext, instruction, provided) {
                     Err(x) => {
                         if self.do_trace {
                             ext.trace_failed();
@@ -532,6 +579,19 @@ impl<Cost: CostType> Interpreter<Cost> {
             InstructionResult::ReturnFromSubroutine(pos) => {
                 self.reader.position = pos;
             }
+            InstructionResult::JumpRelative(offset) => {
+                let destination = self.reader.position as isize + offset;
+                if destination < 0 || destination as usize >= self.reader.len() {
+                    return InterpreterResult::Done(Err(vm::Error::BadJumpDestination {
+                        destination: destination as usize,
+                    }));
+                }
+                self.reader.position = destination as usize;
+            }
+            InstructionResult::CallFunction(position) => {
+                self.return_stack.push(self.reader.position);
+                self.reader.position = position;
+            }
             InstructionResult::StopExecutionNeedsReturn {
                 gas,
                 init_off,
@@ -570,34 +630,69 @@ impl<Cost: CostType> Interpreter<Cost> {
         InterpreterResult::Continue
     }
 
-    fn verify_instruction(
+    /// Charges gas of the basic block starting at the current instruction and enters it,
+    /// provided its static gas and stack requirements are met. Otherwise the instructions
+    /// are checked one by one, so that failures are reported exactly as before.
+    fn enter_basic_block(&mut self, schedule: &Schedule) {
+        let block = match self
+            .basic_blocks
+            .as_ref()
+            .and_then(|blocks| blocks.get(self.reader.position - 1))
+        {
+            Some(block) => block,
+            None => return,
+        };
+        let gasometer = self.gasometer.as_mut().expect(GASOMETER_PROOF);
+        let gas = Cost::from(block.gas);
+        if gasometer.current_gas < gas
+            || !self.stack.has(block.stack_required)
+            || self.stack.size() + block.stack_max_growth > schedule.stack_limit
+        {
+            return;
+        }
+        gasometer.current_gas = gasometer.current_gas - gas;
+        self.block_remaining = block.instructions;
+    }
+
+    /// Leaves the current basic block, returning the gas charged for its instructions
+    /// which were not executed yet.
+    fn leave_basic_block(&mut self, schedule: &Schedule) {
+        let gas = basic_blocks::static_gas(
+            &self.reader.code,
+            self.reader.position - 1,
+            self.block_remaining,
+            schedule,
+        );
+        let gasometer = self.gasometer.as_mut().expect(GASOMETER_PROOF);
+        gasometer.current_gas = gasometer.current_gas + Cost::from(gas);
+        self.block_remaining = 0;
+    }
+
+    fn verify_instruction_enabled(
         &self,
-        ext: &dyn vm::Ext,
+        schedule: &Schedule,
         instruction: Instruction,
-        info: &InstructionInfo,
     ) -> vm::Result<()> {
-        let schedule = ext.schedule();
-
-        use instructions::*;
-        if (instruction == DELEGATECALL && !schedule.have_delegate_call)
-            || (instruction == CREATE2 && !schedule.have_create2)
-            || (instruction == STATICCALL && !schedule.have_static_call)
-            || ((instruction == RETURNDATACOPY || instruction == RETURNDATASIZE)
-                && !schedule.have_return_data)
-            || (instruction == REVERT && !schedule.have_revert)
-            || ((instruction == SHL || instruction == SHR || instruction == SAR)
-                && !schedule.have_bitwise_shifting)
-            || (instruction == EXTCODEHASH && !schedule.have_extcodehash)
-            || (instruction == CHAINID && !schedule.have_chain_id)
-            || (instruction == SELFBALANCE && !schedule.have_selfbalance)
-            || (instruction == BASEFEE && !schedule.eip3198)
-            || ((instruction == BEGINSUB || instruction == JUMPSUB || instruction == RETURNSUB)
-                && !schedule.have_subs)
-        {
+        let allowed = match self.eof {
+            Some(_) => !eof::is_deprecated(instruction, schedule),
+            None => !instruction.is_eof_only(),
+        };
+        if !allowed || !instruction.is_enabled(schedule) {
             return Err(vm::Error::BadInstruction {
                 instruction: instruction as u8,
             });
         }
+        Ok(())
+    }
+
+    fn verify_instruction(
+        &self,
+        ext: &dyn vm::Ext,
+        instruction: Instruction,
+        info: &InstructionInfo,
+    ) -> vm::Result<()> {
+        let schedule = ext.schedule();
+        self.verify_instruction_enabled(schedule, instruction)?;
 
         if !self.stack.has(info.args) {
             Err(vm::Error::StackUnderflow {
@@ -688,6 +783,72 @@ impl<Cost: CostType> Interpreter<Cost> {
                     });
                 }
             }
+            instructions::RJUMP => {
+                let offset = self.reader.read_relative_offset();
+                return Ok(InstructionResult::JumpRelative(offset));
+            }
+            instructions::RJUMPI => {
+                let offset = self.reader.read_relative_offset();
+                let condition = self.stack.pop_back();
+                if !condition.is_zero() {
+                    return Ok(InstructionResult::JumpRelative(offset));
+                }
+            }
+            instructions::RJUMPV => {
+                let case = self.stack.pop_back();
+                let max_index = self.reader.read(1).low_u64() as usize;
+                let table = self.reader.position;
+                let end = table + 2 * (max_index + 1);
+                if case <= U256::from(max_index) {
+                    self.reader.position = table + 2 * case.low_u64() as usize;
+                    let offset = self.reader.read_relative_offset();
+                    self.reader.position = end;
+                    return Ok(InstructionResult::JumpRelative(offset));
+                }
+                self.reader.position = end;
+            }
+            instructions::CALLF => {
+                let index = self.reader.read(2).low_u64() as usize;
+                let section = self
+                    .eof
+                    .as_ref()
+                    .and_then(|eof| eof.code_sections.get(index))
+                    .ok_or(vm::Error::BadJumpDestination { destination: index })?;
+                if self.return_stack.len() >= eof::MAX_RETURN_STACK_SIZE {
+                    return Err(vm::Error::OutOfSubStack {
+                        wanted: 1,
+                        limit: eof::MAX_RETURN_STACK_SIZE,
+                    });
+                }
+                let inputs = section.inputs as usize;
+                if !self.stack.has(inputs) {
+                    return Err(vm::Error::StackUnderflow {
+                        instruction: "CALLF",
+                        wanted: inputs,
+                        on_stack: self.stack.size(),
+                    });
+                }
+                let height = self.stack.size() - inputs + section.max_stack_height as usize;
+                let limit = ext.schedule().stack_limit;
+                if height > limit {
+                    return Err(vm::Error::OutOfStack {
+                        instruction: "CALLF",
+                        wanted: height - self.stack.size(),
+                        limit,
+                    });
+                }
+                return Ok(InstructionResult::CallFunction(section.offset));
+            }
+            instructions::RETF => {
+                if let Some(pos) = self.return_stack.pop() {
+                    return Ok(InstructionResult::ReturnFromSubroutine(pos));
+                } else {
+                    return Err(vm::Error::SubStackUnderflow {
+                        wanted: 1,
+                        on_stack: 0,
+                    });
+                }
+            }
             instructions::CREATE | instructions::CREATE2 => {
                 let endowment = self.stack.pop_back();
                 let init_off = self.stack.pop_back();
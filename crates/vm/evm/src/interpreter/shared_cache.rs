@@ -14,16 +14,23 @@
 // You should have received a copy of the GNU General Public License
 // along with OpenEthereum.  If not, see <http://www.gnu.org/licenses/>.
 
-use super::super::instructions::{self, Instruction};
+use super::{
+    super::instructions::{self, Instruction},
+    basic_blocks::BasicBlocks,
+};
 use bit_set::BitSet;
+use eof::{self, Container};
 use ethereum_types::H256;
 use hash::KECCAK_EMPTY;
 use memory_cache::MemoryLruCache;
 use parity_util_mem::{MallocSizeOf, MallocSizeOfOps};
 use parking_lot::Mutex;
-use std::sync::Arc;
+use std::{mem, sync::Arc};
+use vm::Schedule;
 
 const DEFAULT_CACHE_SIZE: usize = 4 * 1024 * 1024;
+/// Basic blocks get `1 / BASIC_BLOCKS_CACHE_DIVISOR` of the cache size, jump destinations the rest.
+const BASIC_BLOCKS_CACHE_DIVISOR: usize = 2;
 
 #[derive(Clone)]
 struct Bits(Arc<BitSet>);
@@ -41,17 +48,44 @@ struct CacheItem {
     sub_entrypoint: Bits,
 }
 
+#[derive(Clone)]
+struct EofItem {
+    /// Validation rules the container was checked against.
+    rules: (bool, bool, bool),
+    container: Arc<Container>,
+}
+
+impl MallocSizeOf for EofItem {
+    fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+        self.container.code_sections.capacity() * mem::size_of::<eof::CodeSection>()
+    }
+}
+
+#[derive(Clone)]
+struct Blocks(Arc<BasicBlocks>);
+
+impl MallocSizeOf for Blocks {
+    fn size_of(&self, _ops: &mut MallocSizeOfOps) -> usize {
+        self.0.heap_size()
+    }
+}
+
 /// Global cache for EVM interpreter
 pub struct SharedCache {
     jump_destinations: Mutex<MemoryLruCache<H256, CacheItem>>,
+    eof_containers: Mutex<MemoryLruCache<H256, EofItem>>,
+    basic_blocks: Mutex<MemoryLruCache<H256, Blocks>>,
 }
 
 impl SharedCache {
     /// Create a jump destinations cache with a maximum size in bytes
     /// to cache.
     pub fn new(max_size: usize) -> Self {
+        let basic_blocks_size = max_size / BASIC_BLOCKS_CACHE_DIVISOR;
         SharedCache {
-            jump_destinations: Mutex::new(MemoryLruCache::new(max_size)),
+            jump_destinations: Mutex::new(MemoryLruCache::new(max_size - basic_blocks_size)),
+            eof_containers: Mutex::new(MemoryLruCache::new(max_size)),
+            basic_blocks: Mutex::new(MemoryLruCache::new(basic_blocks_size)),
         }
     }
 
@@ -81,6 +115,69 @@ impl SharedCache {
         (d.jump_destination.0, d.sub_entrypoint.0)
     }
 
+    /// Get basic blocks of a legacy contract.
+    pub fn basic_blocks(
+        &self,
+        code_hash: &Option<H256>,
+        code: &[u8],
+        schedule: &Schedule,
+    ) -> Arc<BasicBlocks> {
+        if let Some(ref code_hash) = code_hash {
+            if code_hash == &KECCAK_EMPTY {
+                return Arc::new(BasicBlocks::analyze(code, schedule));
+            }
+
+            if let Some(blocks) = self.basic_blocks.lock().get_mut(code_hash) {
+                return blocks.0.clone();
+            }
+        }
+
+        let blocks = Arc::new(BasicBlocks::analyze(code, schedule));
+
+        if let Some(ref code_hash) = code_hash {
+            self.basic_blocks
+                .lock()
+                .insert(*code_hash, Blocks(blocks.clone()));
+        }
+
+        blocks
+    }
+
+    /// Get the validated EOF container of a contract, `None` if the container is invalid.
+    ///
+    /// Only successful validations are cached.
+    pub fn eof_container(
+        &self,
+        code_hash: &Option<H256>,
+        code: &[u8],
+        schedule: &Schedule,
+    ) -> Option<Arc<Container>> {
+        let rules = (schedule.eip3670, schedule.eip4200, schedule.eip4750);
+        let code_hash = code_hash.filter(|hash| hash != &KECCAK_EMPTY);
+
+        if let Some(ref code_hash) = code_hash {
+            if let Some(item) = self.eof_containers.lock().get_mut(code_hash) {
+                if item.rules == rules {
+                    return Some(item.container.clone());
+                }
+            }
+        }
+
+        let container = Arc::new(Container::parse(code, schedule).ok()?);
+
+        if let Some(code_hash) = code_hash {
+            self.eof_containers.lock().insert(
+                code_hash,
+                EofItem {
+                    rules,
+                    container: container.clone(),
+                },
+            );
+        }
+
+        Some(container)
+    }
+
     fn find_jump_and_sub_destinations(code: &[u8]) -> CacheItem {
         let mut jump_dests = BitSet::with_capacity(code.len());
         let mut sub_entrypoints = BitSet::with_capacity(code.len());
@@ -209,4 +306,25 @@ mod test {
         assert!(cache_item.jump_destination.0.iter().eq(vec![0].into_iter()));
         assert!(cache_item.sub_entrypoint.0.iter().eq(vec![2].into_iter()));
     }
+
+    #[test]
+    fn test_eof_container_revalidated_with_new_rules() {
+        // given
+
+        // EOF container with a single code section holding undefined instruction 0x0C
+        let code = hex!("ef0001010004020001000103000000000000000c");
+        let code_hash = Some(H256::repeat_byte(1));
+        let cache = SharedCache::default();
+        let mut schedule = Schedule::new_london();
+        schedule.eip3540 = true;
+
+        // when
+        let without_validation = cache.eof_container(&code_hash, &code, &schedule);
+        schedule.eip3670 = true;
+        let with_validation = cache.eof_container(&code_hash, &code, &schedule);
+
+        // then
+        assert_eq!(without_validation.unwrap().code_sections.len(), 1);
+        assert!(with_validation.is_none());
+    }
 }
@@ -38,6 +38,7 @@ extern crate hex_literal;
 #[cfg(test)]
 extern crate rustc_hex;
 
+pub mod eof;
 pub mod evm;
 pub mod interpreter;
 
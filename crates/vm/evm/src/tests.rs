@@ -514,6 +514,52 @@ fn test_gas_limit(factory: super::Factory) {
     );
 }
 
+evm_test! {test_gas_in_basic_block: test_gas_in_basic_block_int}
+fn test_gas_in_basic_block(factory: super::Factory) {
+    // PUSH1 1, PUSH1 2, ADD, GAS, PUSH1 0, SSTORE
+    let code = "60016002015a600055".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+
+    let gas_left = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap()
+    };
+
+    assert_eq!(gas_left, U256::from(79_986));
+    assert_store(
+        &ext,
+        0,
+        "000000000000000000000000000000000000000000000000000000000001868d",
+    );
+}
+
+evm_test! {test_basic_block_errors_in_execution_order: test_basic_block_errors_in_execution_order_int}
+fn test_basic_block_errors_in_execution_order(factory: super::Factory) {
+    // ADD on empty stack, PUSH1 1, PUSH1 2, ADD, GAS
+    let code = "0160016002015a".from_hex().unwrap();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(5);
+    params.code = Some(Arc::new(code));
+    let mut ext = FakeExt::new();
+
+    let current = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+
+    let expected = Result::Err(vm::Error::StackUnderflow {
+        instruction: "ADD",
+        wanted: 2,
+        on_stack: 0,
+    });
+    assert_eq!(current, expected);
+}
+
 evm_test! {test_mul: test_mul_int}
 fn test_mul(factory: super::Factory) {
     let code = "65012365124623626543219002600055".from_hex().unwrap();
@@ -1232,6 +1278,78 @@ fn test_subs_walk_into_subroutine(factory: super::Factory) {
     assert_eq!(current, expected);
 }
 
+fn eof_ext() -> FakeExt {
+    let mut ext = FakeExt::new_london(Address::zero(), Address::zero(), &[]);
+    ext.schedule.eip3540 = true;
+    ext.schedule.eip3670 = true;
+    ext.schedule.eip4200 = true;
+    ext.schedule.eip4750 = true;
+    ext
+}
+
+evm_test! {test_eof_relative_jumps_and_functions: test_eof_relative_jumps_and_functions_int}
+fn test_eof_relative_jumps_and_functions(factory: super::Factory) {
+    // section 0: PUSH1 1, RJUMPI +1, INVALID, PUSH1 0x2a, CALLF 1, PUSH1 0, SSTORE, STOP
+    // section 1 (1 input, 1 output): PUSH1 1, ADD, RETF
+    let code = hex!(
+        "ef0001010008020002000f00040300000000000002010100026001e10001fe602ae3000160005500600101e4"
+    )
+    .to_vec();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100_000);
+    params.code = Some(Arc::new(code));
+    let mut ext = eof_ext();
+
+    {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap()).unwrap();
+    }
+
+    assert_store(
+        &ext,
+        0,
+        "000000000000000000000000000000000000000000000000000000000000002b",
+    );
+}
+
+evm_test! {test_eof_instructions_in_legacy_code: test_eof_instructions_in_legacy_code_int}
+fn test_eof_instructions_in_legacy_code(factory: super::Factory) {
+    let code = hex!("e0000000").to_vec();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100);
+    params.code = Some(Arc::new(code));
+    let mut ext = eof_ext();
+
+    let current = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+
+    let expected = Result::Err(vm::Error::BadInstruction { instruction: 0xe0 });
+    assert_eq!(current, expected);
+}
+
+evm_test! {test_eof_invalid_container: test_eof_invalid_container_int}
+fn test_eof_invalid_container(factory: super::Factory) {
+    // RJUMP into its own immediate
+    let code = hex!("ef000101000402000100030300000000000000e0fffe").to_vec();
+
+    let mut params = ActionParams::default();
+    params.gas = U256::from(100);
+    params.code = Some(Arc::new(code));
+    let mut ext = eof_ext();
+
+    let current = {
+        let vm = factory.create(params, ext.schedule(), ext.depth());
+        test_finalize(vm.exec(&mut ext).ok().unwrap())
+    };
+
+    let expected = Result::Err(vm::Error::BadInstruction { instruction: 0xef });
+    assert_eq!(current, expected);
+}
+
 evm_test! {test_calls: test_calls_int}
 fn test_calls(factory: super::Factory) {
     let code = "600054602d57600160005560006000600060006050610998610100f160006000600060006050610998610100f25b".from_hex().unwrap();
@@ -16,6 +16,9 @@
 
 //! Cost schedule and other parameterisations for the EVM.
 
+use ethjson;
+use std::collections::BTreeMap;
+
 // Gas per non accessed address when sload
 pub const EIP2929_COLD_SLOAD_COST: usize = 2100;
 // Gas per non accessed address accessing account from other opcodes defined in EIP2929
@@ -173,10 +176,18 @@ pub struct Schedule {
     pub max_refund_quotient: usize,
     // Enable EIP-3541 rule
     pub eip3541: bool,
+    /// Enable EIP-3540 EOF v1 containers
+    pub eip3540: bool,
+    /// Enable EIP-3670 code validation of EOF containers
+    pub eip3670: bool,
+    /// Enable EIP-4200 static relative jumps in EOF code
+    pub eip4200: bool,
+    /// Enable EIP-4750 functions in EOF code
+    pub eip4750: bool,
 }
 
 /// Wasm cost table
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WasmCosts {
     /// Default opcode cost
     pub regular: u32,
@@ -186,6 +197,9 @@ pub struct WasmCosts {
     pub mul: u32,
     /// Memory (load/store) operations multiplier.
     pub mem: u32,
+    /// Cost of instruction classes, by pwasm-utils class name (i.e. `flow` or `grow_mem`).
+    /// Takes precedence over `regular`, `div`, `mul` and `mem`.
+    pub instructions: BTreeMap<String, u32>,
     /// General static query of U256 value from env-info
     pub static_u256: u32,
     /// General static query of Address value from env-info
@@ -198,6 +212,8 @@ pub struct WasmCosts {
     pub memcpy: u32,
     /// Max stack height (native WebAssembly stack limiter)
     pub max_stack_height: u32,
+    /// Max memory (in 64kb pages) a contract can request.
+    pub max_memory_pages: u32,
     /// Cost of wasm opcode is calculated as TABLE_ENTRY_COST * `opcodes_mul` / `opcodes_div`
     pub opcodes_mul: u32,
     /// Cost of wasm opcode is calculated as TABLE_ENTRY_COST * `opcodes_mul` / `opcodes_div`
@@ -215,12 +231,14 @@ impl Default for WasmCosts {
             div: 16,
             mul: 4,
             mem: 2,
+            instructions: BTreeMap::new(),
             static_u256: 64,
             static_address: 40,
             initial_mem: 4096,
             grow_mem: 8192,
             memcpy: 1,
             max_stack_height: 64 * 1024,
+            max_memory_pages: 16,
             opcodes_mul: 3,
             opcodes_div: 8,
             have_create2: false,
@@ -229,6 +247,43 @@ impl Default for WasmCosts {
     }
 }
 
+impl From<ethjson::spec::WasmCosts> for WasmCosts {
+    fn from(costs: ethjson::spec::WasmCosts) -> Self {
+        let defaults = WasmCosts::default();
+        WasmCosts {
+            regular: costs.regular.map_or(defaults.regular, Into::into),
+            div: costs.div.map_or(defaults.div, Into::into),
+            mul: costs.mul.map_or(defaults.mul, Into::into),
+            mem: costs.mem.map_or(defaults.mem, Into::into),
+            instructions: costs
+                .instructions
+                .map_or(defaults.instructions, |instructions| {
+                    instructions
+                        .into_iter()
+                        .map(|(class, cost)| (class, cost.into()))
+                        .collect()
+                }),
+            static_u256: costs.static_u256.map_or(defaults.static_u256, Into::into),
+            static_address: costs
+                .static_address
+                .map_or(defaults.static_address, Into::into),
+            initial_mem: costs.initial_mem.map_or(defaults.initial_mem, Into::into),
+            grow_mem: costs.grow_mem.map_or(defaults.grow_mem, Into::into),
+            memcpy: costs.memcpy.map_or(defaults.memcpy, Into::into),
+            max_stack_height: costs
+                .max_stack_height
+                .map_or(defaults.max_stack_height, Into::into),
+            max_memory_pages: costs
+                .max_memory_pages
+                .map_or(defaults.max_memory_pages, Into::into),
+            opcodes_mul: costs.opcodes_mul.map_or(defaults.opcodes_mul, Into::into),
+            opcodes_div: costs.opcodes_div.map_or(defaults.opcodes_div, Into::into),
+            have_create2: defaults.have_create2,
+            have_gasleft: defaults.have_gasleft,
+        }
+    }
+}
+
 /// Dust accounts cleanup mode.
 #[derive(Debug, PartialEq, Eq)]
 pub enum CleanDustMode {
@@ -326,6 +381,10 @@ impl Schedule {
             eip3198: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3540: false,
+            eip3670: false,
+            eip4200: false,
+            eip4750: false,
         }
     }
 
@@ -469,6 +528,10 @@ impl Schedule {
             eip3198: false,
             max_refund_quotient: MAX_REFUND_QUOTIENT,
             eip3541: false,
+            eip3540: false,
+            eip3670: false,
+            eip4200: false,
+            eip4750: false,
         }
     }
 
@@ -69,6 +69,28 @@ impl From<Error> for vm::Error {
     }
 }
 
+/// Maximum number of pages (64kb) of a wasm linear memory.
+const MAX_MEMORY_PAGES: u32 = 65536;
+
+/// Checks that wasm costs from a chain spec can be used to run contracts.
+///
+/// Limits above `u32::max_value()` are saturated by the chain spec conversion, and rejected here.
+pub fn validate_costs(wasm_costs: &vm::WasmCosts) -> Result<(), String> {
+    if wasm_costs.max_memory_pages > MAX_MEMORY_PAGES {
+        return Err(format!(
+            "Wasm maxMemoryPages {} exceeds the {} pages of a wasm memory",
+            wasm_costs.max_memory_pages, MAX_MEMORY_PAGES
+        ));
+    }
+    if wasm_costs.max_stack_height == u32::max_value() {
+        return Err(format!(
+            "Wasm maxStackHeight must be below {}",
+            u32::max_value()
+        ));
+    }
+    parser::gas_rules(wasm_costs).map(|_| ())
+}
+
 /// Wasm interpreter instance
 pub struct WasmInterpreter {
     params: ActionParams,
@@ -99,7 +121,10 @@ impl WasmInterpreter {
         let loaded_module =
             wasmi::Module::from_parity_wasm_module(module).map_err(Error::Interpreter)?;
 
-        let instantiation_resolver = env::ImportResolver::with_limit(16, ext.schedule().wasm());
+        let instantiation_resolver = env::ImportResolver::with_limit(
+            ext.schedule().wasm().max_memory_pages,
+            ext.schedule().wasm(),
+        );
 
         let module_instance = wasmi::ModuleInstance::new(
             &loaded_module,
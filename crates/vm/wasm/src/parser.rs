@@ -20,11 +20,13 @@ use parity_wasm::{
     elements::{self, Deserialize},
     peek_size,
 };
+use std::str::FromStr;
 use vm;
 use wasm_utils::{self, rules};
 
-fn gas_rules(wasm_costs: &vm::WasmCosts) -> rules::Set {
-    rules::Set::new(wasm_costs.regular, {
+/// Gas metering rules of the schedule.
+pub fn gas_rules(wasm_costs: &vm::WasmCosts) -> Result<rules::Set, String> {
+    let rules = rules::Set::new(wasm_costs.regular, {
         let mut vals = ::std::collections::BTreeMap::new();
         vals.insert(
             rules::InstructionType::Load,
@@ -42,10 +44,17 @@ fn gas_rules(wasm_costs: &vm::WasmCosts) -> rules::Set {
             rules::InstructionType::Mul,
             rules::Metering::Fixed(wasm_costs.mul as u32),
         );
+        for (class, cost) in &wasm_costs.instructions {
+            let class = rules::InstructionType::from_str(class)
+                .map_err(|_| format!("Unknown wasm instruction class: {}", class))?;
+            vals.insert(class, rules::Metering::Fixed(*cost));
+        }
         vals
     })
     .with_grow_cost(wasm_costs.grow_mem)
-    .with_forbidden_floats()
+    .with_forbidden_floats();
+
+    Ok(rules)
 }
 
 /// Splits payload to code and data according to params.params_type, also
@@ -84,9 +93,9 @@ pub fn payload<'a>(
         )));
     }
 
-    let contract_module =
-        wasm_utils::inject_gas_counter(deserialized_module, &gas_rules(wasm_costs))
-            .map_err(|_| vm::Error::Wasm(format!("Wasm contract error: bytecode invalid")))?;
+    let gas_rules = gas_rules(wasm_costs).map_err(vm::Error::Wasm)?;
+    let contract_module = wasm_utils::inject_gas_counter(deserialized_module, &gas_rules)
+        .map_err(|_| vm::Error::Wasm(format!("Wasm contract error: bytecode invalid")))?;
 
     let contract_module =
         wasm_utils::stack_height::inject_limiter(contract_module, wasm_costs.max_stack_height)
@@ -38,8 +38,28 @@ pub struct Runtime<'a> {
     memory: MemoryRef,
     args: Vec<u8>,
     result: Vec<u8>,
+    /// Memory written by the current host call, for the VM tracer.
+    mem_written: Option<(usize, usize)>,
+    /// Storage written by the current host call, for the VM tracer.
+    store_written: Option<(U256, U256)>,
+    /// Host call being traced, if any.
+    traced_host_call: Option<TracedHostCall>,
 }
 
+/// Host call being traced.
+///
+/// Wasm code is traced at the granularity of host calls (including the `gas` calls injected
+/// at the start of each metered block): each call is one operation of the VM trace, with the
+/// host function id as program counter.
+struct TracedHostCall {
+    index: usize,
+    gas_counter: u64,
+    prepared: bool,
+}
+
+/// Instruction reported to the VM tracer for host calls.
+const HOST_CALL_INSTRUCTION: u8 = 0;
+
 /// User trap in native code
 #[derive(Debug, Clone, PartialEq)]
 pub enum Error {
@@ -163,7 +183,111 @@ impl<'a> Runtime<'a> {
             context: context,
             args: args,
             result: Vec::new(),
+            mem_written: None,
+            store_written: None,
+            traced_host_call: None,
+        }
+    }
+
+    /// Writes to the sandboxed memory, recording the write for the VM tracer.
+    fn set_memory(&mut self, ptr: u32, data: &[u8]) -> Result<()> {
+        self.memory.set(ptr, data)?;
+        self.mem_written = Some((ptr as usize, data.len()));
+        Ok(())
+    }
+
+    /// Converts wasm gas to the gas of the caller.
+    fn to_evm_gas(&self, wasm_gas: u64) -> U256 {
+        let wasm_costs = self.ext.schedule().wasm();
+        U256::from(wasm_gas) * U256::from(wasm_costs.opcodes_mul)
+            / U256::from(wasm_costs.opcodes_div)
+    }
+
+    /// Starts tracing a host call, if the VM tracer wants it.
+    pub fn trace_host_call_start(&mut self, index: usize) {
+        let gas_left = self.to_evm_gas(self.gas_limit.saturating_sub(self.gas_counter));
+        if !self
+            .ext
+            .trace_next_instruction(index, HOST_CALL_INSTRUCTION, gas_left)
+        {
+            return;
+        }
+
+        self.mem_written = None;
+        self.store_written = None;
+        self.traced_host_call = Some(TracedHostCall {
+            index,
+            gas_counter: self.gas_counter,
+            prepared: false,
+        });
+    }
+
+    /// Prepares the trace of a host call about to execute another contract, so that the traces
+    /// of the executed contract are nested under it.
+    ///
+    /// The cost is the gas charged so far plus `gas_provided` to the executed contract, if it
+    /// was not charged yet.
+    fn trace_host_call_prepare(&mut self, gas_provided: U256) {
+        let (index, gas_counter) = match self.traced_host_call {
+            Some(ref mut traced) if !traced.prepared => {
+                traced.prepared = true;
+                (traced.index, traced.gas_counter)
+            }
+            _ => return,
+        };
+        let gas_cost = self.to_evm_gas(self.gas_counter.saturating_sub(gas_counter)) + gas_provided;
+        self.ext
+            .trace_prepare_execute(index, HOST_CALL_INSTRUCTION, gas_cost, None, None);
+    }
+
+    /// Finishes tracing the current host call, if any, with its result.
+    ///
+    /// Calls that did not execute another contract are prepared here, once their cost is known.
+    pub fn trace_host_call_end(
+        &mut self,
+        result: &::std::result::Result<Option<RuntimeValue>, Trap>,
+    ) {
+        let traced = match self.traced_host_call.take() {
+            Some(traced) => traced,
+            None => return,
+        };
+        let mem_written = self.mem_written.take();
+        let store_written = self.store_written.take();
+        if !traced.prepared {
+            let gas_cost = self.to_evm_gas(self.gas_counter.saturating_sub(traced.gas_counter));
+            self.ext.trace_prepare_execute(
+                traced.index,
+                HOST_CALL_INSTRUCTION,
+                gas_cost,
+                mem_written,
+                store_written,
+            );
         }
+
+        let stack_push = match *result {
+            Ok(value) => value.map(|value| match value {
+                RuntimeValue::I32(v) => U256::from(v as u32),
+                RuntimeValue::I64(v) => U256::from(v as u64),
+                _ => U256::zero(),
+            }),
+            // Returning and suiciding are implemented as traps but succeed.
+            Err(ref trap) => match *trap.kind() {
+                TrapKind::Host(ref e) => match e.downcast_ref::<Error>() {
+                    Some(&Error::Return) | Some(&Error::Suicide) => None,
+                    _ => return self.ext.trace_failed(),
+                },
+                _ => return self.ext.trace_failed(),
+            },
+        };
+        let mem = match mem_written {
+            Some((offset, size)) if !traced.prepared => {
+                self.memory.get(0, offset + size).unwrap_or_default()
+            }
+            _ => Vec::new(),
+        };
+        let stack_push: Vec<U256> = stack_push.into_iter().collect();
+        let gas_left = self.to_evm_gas(self.gas_limit.saturating_sub(self.gas_counter));
+        self.ext.trace_executed(gas_left, &stack_push, &mem);
     }
 
     /// Loads 256-bit hash from the specifed sandboxed memory pointer
@@ -300,6 +424,7 @@ impl<'a> Runtime<'a> {
         self.ext
             .set_storage(key, val)
             .map_err(|_| Error::StorageUpdateError)?;
+        self.store_written = Some((key.into_uint(), val.into_uint()));
 
         if former_val != H256::zero() && val == H256::zero() {
             let sstore_clears_schedule = self.schedule().sstore_refund_gas;
@@ -365,7 +490,8 @@ impl<'a> Runtime<'a> {
         let args_len = self.args.len() as u64;
         self.charge(|s| args_len * s.wasm().memcpy as u64)?;
 
-        self.memory.set(ptr, &self.args[..])?;
+        self.memory.set(ptr, &self.args)?;
+        self.mem_written = Some((ptr as usize, self.args.len()));
         Ok(())
     }
 
@@ -464,6 +590,7 @@ impl<'a> Runtime<'a> {
         };
 
         self.charge(|_| adjusted_gas)?;
+        self.trace_host_call_prepare(U256::zero());
 
         let call_result = self
             .ext
@@ -496,7 +623,7 @@ impl<'a> Runtime<'a> {
                     - gas_left.low_u64() * self.ext.schedule().wasm().opcodes_div as u64
                         / self.ext.schedule().wasm().opcodes_mul as u64;
 
-                self.memory.set(result_ptr, &result)?;
+                self.set_memory(result_ptr, &result)?;
                 Ok(0i32.into())
             }
             vm::MessageCallResult::Reverted(gas_left, data) => {
@@ -508,7 +635,7 @@ impl<'a> Runtime<'a> {
                     - gas_left.low_u64() * self.ext.schedule().wasm().opcodes_div as u64
                         / self.ext.schedule().wasm().opcodes_mul as u64;
 
-                self.memory.set(result_ptr, &result)?;
+                self.set_memory(result_ptr, &result)?;
                 Ok((-1i32).into())
             }
             vm::MessageCallResult::Failed => Ok((-1i32).into()),
@@ -532,14 +659,14 @@ impl<'a> Runtime<'a> {
 
     fn return_address_ptr(&mut self, ptr: u32, val: Address) -> Result<()> {
         self.charge(|schedule| schedule.wasm().static_address as u64)?;
-        self.memory.set(ptr, val.as_bytes())?;
+        self.set_memory(ptr, val.as_bytes())?;
         Ok(())
     }
 
     fn return_u256_ptr(&mut self, ptr: u32, val: U256) -> Result<()> {
         let value: H256 = BigEndianHash::from_uint(&val);
         self.charge(|schedule| schedule.wasm().static_u256 as u64)?;
-        self.memory.set(ptr, value.as_bytes())?;
+        self.set_memory(ptr, value.as_bytes())?;
         Ok(())
     }
 
@@ -565,6 +692,7 @@ impl<'a> Runtime<'a> {
         let gas_left: U256 = U256::from(self.gas_left()?)
             * U256::from(self.ext.schedule().wasm().opcodes_mul)
             / U256::from(self.ext.schedule().wasm().opcodes_div);
+        self.trace_host_call_prepare(gas_left);
 
         match self
             .ext
@@ -573,7 +701,7 @@ impl<'a> Runtime<'a> {
             .expect("Trap is false; trap error will not happen; qed")
         {
             vm::ContractCreateResult::Created(address, gas_left) => {
-                self.memory.set(result_ptr, address.as_bytes())?;
+                self.set_memory(result_ptr, address.as_bytes())?;
                 self.gas_counter = self.gas_limit -
 					// this cannot overflow, since initial gas is in [0..u64::max) range,
 					// and gas_left cannot be bigger
@@ -704,7 +832,7 @@ impl<'a> Runtime<'a> {
     pub fn blockhash(&mut self, args: RuntimeArgs) -> Result<()> {
         self.adjusted_charge(|schedule| schedule.blockhash_gas as u64)?;
         let hash = self.ext.blockhash(&U256::from(args.nth_checked::<u64>(0)?));
-        self.memory.set(args.nth_checked(1)?, hash.as_bytes())?;
+        self.set_memory(args.nth_checked(1)?, hash.as_bytes())?;
 
         Ok(())
     }
@@ -827,7 +955,8 @@ mod ext_impl {
             index: usize,
             args: RuntimeArgs,
         ) -> Result<Option<RuntimeValue>, Trap> {
-            match index {
+            self.trace_host_call_start(index);
+            let result = match index {
                 STORAGE_WRITE_FUNC => void!(self.storage_write(args)),
                 STORAGE_READ_FUNC => void!(self.storage_read(args)),
                 RET_FUNC => void!(self.ret(args)),
@@ -855,7 +984,9 @@ mod ext_impl {
                 CREATE2_FUNC => some!(self.create2(args)),
                 GASLEFT_FUNC => some!(self.gasleft()),
                 _ => panic!("env module doesn't provide function at index {}", index),
-            }
+            };
+            self.trace_host_call_end(&result);
+            result
         }
     }
 }